@@ -180,19 +180,37 @@ local function zetrss_picker(opts)
         local zetrss = require("zetrss")
         local config = zetrss.get_config()
 
+        -- A viewer is open already: show the article there
+        if zetrss.jump_to_article(article.id) then
+          actions.close(bufnr)
+          return
+        end
+
         -- Build command with environment variable using env command
         local binary = config.zetrss_bin or "zetrss"
-        -- Generate unique session ID for temp file coordination
-        local session_id = vim.fn.system("uuidgen"):gsub("\n", "")
-        if session_id == "" then
-          -- Fallback if uuidgen is not available
-          session_id = tostring(os.time()) .. "-" .. tostring(math.random(1000000))
+        -- A private directory per viewer session (inside Neovim's own temp dir)
+        -- holds the files the viewer leaves for its last action and its socket,
+        -- so concurrent viewers don't read each other's; removed on exit
+        local session_dir = vim.fn.tempname()
+        vim.fn.mkdir(session_dir, "p", 448) -- 0700
+        local function read_session_file(kind)
+          local file = io.open(session_dir .. "/" .. kind .. ".txt", "r")
+          if not file then
+            return nil
+          end
+          local contents = file:read("*a")
+          file:close()
+          return contents
         end
-        local cmd = string.format("env ZETRSS_DATA_DIR=%s ZETRSS_SESSION_ID=%s %s view --id %s",
+        -- The viewer answers requests (e.g. jump to an article) on this socket
+        local socket = session_dir .. "/viewer.sock"
+        local cmd = string.format("env ZETRSS_DATA_DIR=%s ZETRSS_SESSION_DIR=%s ZETRSS_SOCKET=%s %s view --id %s --on-finish %s",
           vim.fn.shellescape(config.zetrss_path),
-          vim.fn.shellescape(session_id),
+          vim.fn.shellescape(session_dir),
+          vim.fn.shellescape(socket),
           binary,
-          vim.fn.shellescape(article.id))
+          vim.fn.shellescape(article.id),
+          vim.fn.shellescape(config.on_finish or "list"))
 
         -- Save current window before closing telescope
         local original_win = vim.api.nvim_get_current_win()
@@ -261,9 +279,11 @@ local function zetrss_picker(opts)
           end
 
           -- Start terminal
+          zetrss.set_viewer_socket(socket)
           local ok_term, job_id = pcall(vim.fn.termopen, cmd, {
             on_exit = function(_, exit_code)
               vim.schedule(function()
+                zetrss.set_viewer_socket(nil)
                 -- Clean up autocommands
                 pcall(vim.api.nvim_del_augroup_by_name, augroup_name)
 
@@ -294,27 +314,14 @@ local function zetrss_picker(opts)
                   end, 50)
                 elseif exit_code == 1 then
                   -- Open browser
-                  -- Use system temp dir (must match Rust's std::env::temp_dir())
-                  local temp_dir = os.getenv("TMPDIR") or "/tmp/"
-                  local temp_file_path = temp_dir .. "zetrss_open_url_" .. session_id .. ".txt"
-                  local url_file = io.open(temp_file_path, "r")
-                  if url_file then
-                    local url = url_file:read("*a")
-                    url_file:close()
-                    -- Clean up temp file after reading
-                    os.remove(temp_file_path)
+                  local url = read_session_file("open_url")
+                  if url then
                     vim.fn.system("open " .. vim.fn.shellescape(url))
                   end
                 elseif exit_code == 2 then
                   -- Open note in floating window
-                  local temp_dir = os.getenv("TMPDIR") or "/tmp/"
-                  local temp_file_path = temp_dir .. "zetrss_note_path_" .. session_id .. ".txt"
-                  local note_file = io.open(temp_file_path, "r")
-                  if note_file then
-                    local note_path = note_file:read("*a")
-                    note_file:close()
-                    os.remove(temp_file_path)
-
+                  local note_path = read_session_file("note_path")
+                  if note_path then
                     -- Create floating window for note
                     local float_buf = vim.api.nvim_create_buf(false, false)
                     local float_width = math.min(120, math.floor(vim.o.columns * 0.9))
@@ -388,14 +395,8 @@ local function zetrss_picker(opts)
                   end
                 elseif exit_code == 3 then
                   -- Open in vim buffer (read-only) in floating window
-                  local temp_dir = os.getenv("TMPDIR") or "/tmp/"
-                  local temp_file_path = temp_dir .. "zetrss_vim_path_" .. session_id .. ".txt"
-                  local vim_file = io.open(temp_file_path, "r")
-                  if vim_file then
-                    local article_path = vim_file:read("*a")
-                    vim_file:close()
-                    os.remove(temp_file_path)
-
+                  local article_path = read_session_file("vim_path")
+                  if article_path then
                     -- Create floating window for article
                     local float_buf = vim.api.nvim_create_buf(false, false)
                     local float_width = math.min(120, math.floor(vim.o.columns * 0.9))
@@ -469,12 +470,22 @@ local function zetrss_picker(opts)
                       end,
                     })
                   end
+                elseif exit_code == 5 then
+                  -- Finished the queue with on_finish = "summary"
+                  local summary = read_session_file("summary")
+                  if summary then
+                    vim.notify("ZetRss: " .. summary, vim.log.levels.INFO)
+                  end
                 end
+                -- exit_code 4: finished with on_finish = "exit", nothing to reopen
+                vim.fn.delete(session_dir, "rf")
               end)
             end
           })
 
           if not ok_term then
+            vim.fn.delete(session_dir, "rf")
+            zetrss.set_viewer_socket(nil)
             vim.notify("ZetRss: Failed to start terminal: " .. tostring(job_id), vim.log.levels.ERROR)
             if vim.api.nvim_win_is_valid(win) then
               vim.api.nvim_win_close(win, true)
@@ -491,6 +502,15 @@ local function zetrss_picker(opts)
       actions.select_default:replace(open_in_viewer)
 
 
+      -- Read the article in a normal buffer instead of the TUI viewer
+      map("i", "<C-b>", function()
+        local selection = action_state.get_selected_entry()
+        if selection then
+          actions.close(prompt_bufnr)
+          require("zetrss").open_in_buffer(selection.value.id)
+        end
+      end)
+
       -- Keep C-n since you said it works
       map("i", "<C-n>", function()
         local selection = action_state.get_selected_entry()
@@ -499,39 +519,14 @@ local function zetrss_picker(opts)
 
           local article = selection.value
           local zetrss = require("zetrss")
-          local config = zetrss.get_config()
-
-          -- Generate filename
-          local date = vim.fn.strftime("%Y%m%d%H%M")
-          local safe_title = article.title:gsub("[^%w]+", "-"):lower():sub(1, 50)
-          local filename = config.zet_path .. "/" .. date .. "-" .. safe_title .. ".md"
-
-          -- Create note content
-          local content = {
-            "# " .. article.title,
-            "",
-            "Source: " .. article.link,
-            "Feed: " .. article.feed,
-            "Date: " .. (article.published or ""),
-            "",
-            "## Summary",
-            "",
-          }
-
-          -- Add first paragraph of content as summary
-          if article.content then
-            local first_para = article.content:match("^(.-)\n\n") or article.content:sub(1, 500)
-            table.insert(content, first_para)
-          end
-
-          table.insert(content, "")
-          table.insert(content, "## Notes")
-          table.insert(content, "")
-          table.insert(content, "")
 
-          -- Write file
-          vim.fn.writefile(content, filename)
-          vim.cmd("edit " .. filename)
+          -- The binary names and fills the note per the [notes] config
+          local filename, err = zetrss.create_note(article.id)
+          if not filename then
+            vim.notify("ZetRss: " .. err, vim.log.levels.ERROR)
+            return
+          end
+          vim.cmd("edit " .. vim.fn.fnameescape(filename))
           vim.notify("Created note: " .. filename)
 
           -- Mark as read
@@ -42,6 +42,9 @@ local function parse_frontmatter(content)
   end
 
   article.content = table.concat(body_lines, "\n")
+  if article.content:match("^%s*zetrss:encrypted:") then
+    article.content = "(Encrypted: open the article in the viewer to read it)"
+  end
   return article
 end
 
@@ -56,8 +59,8 @@ function M.get_articles(limit, options)
 
   local articles = {}
 
-  -- Get all .md files
-  local handle = io.popen("ls " .. vim.fn.shellescape(articles_dir) .. "/*.md 2>/dev/null")
+  -- Get all .md files (one subdirectory per feed)
+  local handle = io.popen("find " .. vim.fn.shellescape(articles_dir) .. " -maxdepth 2 -name '*.md' 2>/dev/null")
   local result = handle:read("*a")
   handle:close()
 
@@ -124,58 +127,16 @@ function M.search_articles(query)
   return results
 end
 
--- Mark article as read
+-- Mark article as read (through `zetrss rpc`, which keeps the index in step)
 function M.mark_as_read(article_id)
-  local articles = M.get_articles()
-
-  for _, article in ipairs(articles) do
-    if article.id == article_id and article.filepath then
-      M.update_article_field(article.filepath, "read", "true")
-      return true
-    end
-  end
-
-  return false
+  local result = require("zetrss.rpc").request_sync("mark-read", { id = article_id })
+  return result ~= nil
 end
 
 -- Toggle star on article
 function M.toggle_star(article_id)
-  local articles = M.get_articles()
-
-  for _, article in ipairs(articles) do
-    if article.id == article_id and article.filepath then
-      local new_value = article.starred and "false" or "true"
-      M.update_article_field(article.filepath, "starred", new_value)
-      return true
-    end
-  end
-
-  return false
-end
-
--- Update a field in the article's frontmatter
-function M.update_article_field(filepath, field, value)
-  local lines = vim.fn.readfile(filepath)
-  local in_frontmatter = false
-  local updated = false
-
-  for i, line in ipairs(lines) do
-    if line:match("^---") then
-      if not in_frontmatter then
-        in_frontmatter = true
-      else
-        in_frontmatter = false
-      end
-    elseif in_frontmatter and line:match("^" .. field .. ":") then
-      lines[i] = field .. ": " .. value
-      updated = true
-      break
-    end
-  end
-
-  if updated then
-    vim.fn.writefile(lines, filepath)
-  end
+  local result = require("zetrss.rpc").request_sync("star", { id = article_id })
+  return result ~= nil
 end
 
 -- Get stats
@@ -218,6 +179,47 @@ function M.get_feeds()
   return feeds or {}
 end
 
+-- One page of articles from `zetrss query`, for pickers and dashboards.
+-- filters: feed, read, starred, tags (list), since ("3d"), text (list),
+-- limit, offset, sort; returns { articles, offset, limit, has_more } or nil, err
+function M.query(filters)
+  filters = filters or {}
+  local config = require("zetrss").get_config()
+  local cmd = { config.zetrss_bin or "zetrss", "query" }
+  local function add(flag, value)
+    if value ~= nil then
+      table.insert(cmd, flag)
+      table.insert(cmd, tostring(value))
+    end
+  end
+  add("--feed", filters.feed)
+  add("--read", filters.read)
+  add("--starred", filters.starred)
+  add("--since", filters.since)
+  add("--limit", filters.limit)
+  add("--offset", filters.offset)
+  add("--sort", filters.sort)
+  for _, tag in ipairs(filters.tags or {}) do
+    add("--tag", tag)
+  end
+  for _, text in ipairs(filters.text or {}) do
+    add("--text", text)
+  end
+
+  local escaped = vim.tbl_map(vim.fn.shellescape, cmd)
+  local result = vim.fn.system(string.format("env ZETRSS_DATA_DIR=%s %s 2>/dev/null",
+    vim.fn.shellescape(config.zetrss_path),
+    table.concat(escaped, " ")))
+  if vim.v.shell_error ~= 0 then
+    return nil, "zetrss query failed"
+  end
+  local ok, page = pcall(vim.json.decode, result)
+  if not ok then
+    return nil, "Invalid zetrss query output"
+  end
+  return page
+end
+
 -- Get articles filtered by feed URL
 function M.get_articles_by_feed(feed_url, options)
   options = options or {}
@@ -0,0 +1,96 @@
+-- nvim-cmp source offering links to cached articles while writing notes:
+-- completing an article's title inserts a link in the buffer's markup.
+-- Register it with require("zetrss.cmp").register() and add
+-- { name = "zetrss" } to the sources (blink.cmp users: through blink.compat).
+local M = {}
+
+-- Seconds before the article list is read again from `zetrss complete`
+local REFRESH_AFTER = 60
+
+local source = {}
+source.__index = source
+
+function M.new()
+  return setmetatable({ items = nil, loaded_at = 0, loading = false }, source)
+end
+
+function M.register()
+  local ok, cmp = pcall(require, "cmp")
+  if not ok then
+    vim.notify("ZetRss: nvim-cmp is not installed", vim.log.levels.WARN)
+    return
+  end
+  cmp.register_source("zetrss", M.new())
+end
+
+-- The link to insert for an article in a buffer of `filetype`
+local function link(filetype, title, url)
+  if filetype == "org" then
+    return string.format("[[%s][%s]]", url, title)
+  elseif filetype == "markdown" or filetype == "vimwiki" or filetype == "telekasten" then
+    return string.format("[%s](%s)", title:gsub("[%[%]]", ""), url)
+  end
+  return string.format("%s <%s>", title, url)
+end
+
+function source:get_debug_name()
+  return "zetrss"
+end
+
+function source:is_available()
+  return require("zetrss").get_config().zetrss_bin ~= nil
+end
+
+-- Runs `zetrss complete --json` in the background; callback(articles)
+function source:load(callback)
+  local config = require("zetrss").get_config()
+  self.loading = true
+  local output = {}
+  vim.fn.jobstart({ config.zetrss_bin, "--json", "complete" }, {
+    env = { ZETRSS_DATA_DIR = config.zetrss_path },
+    stdout_buffered = true,
+    on_stdout = function(_, data)
+      output = data
+    end,
+    on_exit = function(_, code)
+      self.loading = false
+      local ok, articles = pcall(vim.json.decode, table.concat(output, ""))
+      if code == 0 and ok and type(articles) == "table" then
+        self.items = articles
+        self.loaded_at = os.time()
+      end
+      callback(self.items or {})
+    end,
+  })
+end
+
+function source:complete(params, callback)
+  local filetype = vim.bo[params.context.bufnr].filetype
+  local function respond(articles)
+    local items = {}
+    for _, article in ipairs(articles) do
+      table.insert(items, {
+        label = article.title,
+        filterText = article.title,
+        insertText = link(filetype, article.title, article.url),
+        documentation = article.url,
+        kind = 18, -- cmp's Reference kind
+      })
+    end
+    callback({ items = items, isIncomplete = false })
+  end
+
+  if self.items and os.time() - self.loaded_at < REFRESH_AFTER then
+    respond(self.items)
+  elseif self.loading then
+    respond(self.items or {})
+  else
+    self:load(function(articles)
+      vim.schedule(function()
+        respond(articles)
+      end)
+    end)
+  end
+end
+
+return M
@@ -0,0 +1,59 @@
+-- :checkhealth zetrss, rendered from `zetrss healthcheck --json`
+local M = {}
+
+local health = vim.health or require("health")
+local start = health.start or health.report_start
+local ok = health.ok or health.report_ok
+local warn = health.warn or health.report_warn
+local error = health.error or health.report_error
+local info = health.info or health.report_info
+
+function M.check()
+  start("zetrss")
+
+  local config = require("zetrss").get_config()
+  if not config.zetrss_bin then
+    error("zetrss binary not found", { "Build it with 'make build' in the plugin directory" })
+    return
+  end
+  ok("Binary: " .. config.zetrss_bin)
+
+  local cmd = string.format("env ZETRSS_DATA_DIR=%s %s --json healthcheck 2>/dev/null",
+    vim.fn.shellescape(config.zetrss_path),
+    vim.fn.shellescape(config.zetrss_bin))
+  local output = vim.fn.system(cmd)
+  local decoded, report = pcall(vim.json.decode, output)
+  if vim.v.shell_error ~= 0 or not decoded or type(report) ~= "table" then
+    error("`zetrss healthcheck` failed", { "Run `zetrss doctor` in a shell for details" })
+    return
+  end
+
+  info("Version: " .. report.version)
+  info("Data dir: " .. report.data_dir)
+  info("Feeds: " .. report.feeds)
+  if report.index.ok then
+    ok(string.format("Index: %d articles, %d unread", report.index.articles, report.index.unread))
+  else
+    error("Index: " .. tostring(report.index.error), { "Run `zetrss reindex`" })
+  end
+  if report.last_fetch ~= vim.NIL and report.last_fetch then
+    info("Last fetch: " .. report.last_fetch)
+  else
+    warn("No feed has been fetched yet", { "Run :ZetRss fetch" })
+  end
+
+  start("zetrss checks")
+  for _, check in ipairs(report.checks) do
+    local message = check.name .. ": " .. check.detail
+    local advice = (check.fix ~= vim.NIL and check.fix) and { check.fix } or nil
+    if check.status == "ok" then
+      ok(message)
+    elseif check.status == "warn" then
+      warn(message, advice)
+    else
+      error(message, advice)
+    end
+  end
+end
+
+return M
@@ -4,6 +4,7 @@ local config = {
   zetrss_path = vim.fn.stdpath("data") .. "/zetrss",
   zet_path = nil,
   zetrss_bin = nil,
+  on_finish = "list",
 }
 
 function M.setup(opts)
@@ -11,12 +12,13 @@ function M.setup(opts)
   local ok, err = pcall(function()
     opts = opts or {}
 
-    -- Get username and set default zet path
-    local username = vim.fn.system("whoami"):gsub("\n", "")
     local defaults = {
       zetrss_path = vim.fn.stdpath("data") .. "/zetrss",
-      zet_path = vim.fn.expand("~/git/" .. username .. "/zet"),
+      -- nil leaves the zet to the binary's config file (zet_path, zet_roots)
+      zet_path = nil,
       zetrss_bin = nil,
+      -- After the last article: "list" (reopen picker), "exit", "summary" or "fetch"
+      on_finish = "list",
     }
 
     config = vim.tbl_deep_extend("force", defaults, opts)
@@ -62,6 +64,14 @@ function M.setup(opts)
       M.clear_cache()
     elseif subcommand == "mark-all-read" then
       M.mark_all_read()
+    elseif subcommand == "status" then
+      M.daemon_status()
+    elseif subcommand == "broken" then
+      M.broken_feeds()
+    elseif subcommand == "sync" then
+      M.sync()
+    elseif subcommand == "pocket" then
+      M.pocket()
     else
       vim.notify("Unknown subcommand: " .. subcommand .. "\n\nAvailable subcommands:\n" ..
         "  browse (default) - Browse unread articles\n" ..
@@ -74,6 +84,10 @@ function M.setup(opts)
         "  fetch            - Fetch RSS articles\n" ..
         "  update           - Rescan and fetch new articles\n" ..
         "  mark-all-read    - Mark all unread articles as read\n" ..
+        "  status           - Show counts from a running `zetrss daemon`\n" ..
+        "  broken           - Quickfix list of failing feeds' #feed lines\n" ..
+        "  sync             - Sync with the [sync] feed reader account\n" ..
+        "  pocket           - Send starred articles to Pocket\n" ..
         "  clear-cache      - Clear all cached data",
         vim.log.levels.ERROR)
     end
@@ -93,6 +107,10 @@ function M.setup(opts)
         "fetch",
         "update",
         "mark-all-read",
+        "status",
+        "broken",
+        "sync",
+        "pocket",
         "clear-cache"
       })
     end,
@@ -119,39 +137,58 @@ function M.scan()
   end
 
   -- Use env command to set environment variable
-  local cmd = string.format("env ZETRSS_DATA_DIR=%s %s scan --path %s",
+  local cmd = string.format("env ZETRSS_DATA_DIR=%s %s --json scan",
     vim.fn.shellescape(config.zetrss_path),
-    config.zetrss_bin,
-    vim.fn.shellescape(config.zet_path))
+    config.zetrss_bin)
+  if config.zet_path then
+    cmd = cmd .. " --path " .. vim.fn.shellescape(vim.fn.expand(config.zet_path))
+  end
 
   vim.notify("Scanning for RSS feeds...", vim.log.levels.INFO)
 
+  local output = {}
   vim.fn.jobstart(cmd, {
+    stdout_buffered = true,
+    on_stdout = function(_, data)
+      output = data
+    end,
     on_exit = function(_, code)
       if code == 0 then
-        vim.notify("RSS feed scan completed!", vim.log.levels.INFO)
+        local ok, result = pcall(vim.fn.json_decode, table.concat(output, ""))
+        local count = ok and result and result.count or 0
+        vim.notify(string.format("RSS feed scan completed! Found %d feeds.", count), vim.log.levels.INFO)
       else
         vim.notify("RSS feed scan failed!", vim.log.levels.ERROR)
       end
     end,
-    on_stdout = function(_, data)
-      for _, line in ipairs(data) do
-        if line ~= "" then
-          print(line)
-        end
-      end
-    end,
   })
 end
 
+-- Asks a running daemon to fetch right away, else fetches with `zetrss fetch`
 function M.fetch(update)
+  local socket = not update and require("zetrss.ipc").daemon_socket()
+  if not socket then
+    M.spawn_fetch(update)
+    return
+  end
+  require("zetrss.ipc").request(socket, "refresh", nil, function(_, err)
+    if err then
+      M.spawn_fetch(update)
+    else
+      vim.notify("Asked the running daemon to fetch feeds.", vim.log.levels.INFO)
+    end
+  end)
+end
+
+function M.spawn_fetch(update)
   if not config.zetrss_bin then
     vim.notify("zetrss binary not found! Please build it with 'make build' in plugin directory", vim.log.levels.ERROR)
     return
   end
 
   -- Use env command to set environment variable
-  local cmd = string.format("env ZETRSS_DATA_DIR=%s %s fetch",
+  -- Progress events arrive on stderr as JSON lines, among any log lines
+  local cmd = string.format("env ZETRSS_DATA_DIR=%s %s --json fetch --events 2",
     vim.fn.shellescape(config.zetrss_path),
     config.zetrss_bin)
   if update then
@@ -160,24 +197,61 @@ function M.fetch(update)
 
   vim.notify("Fetching RSS feeds...", vim.log.levels.INFO)
 
+  local output = {}
+  local progress = { feeds = 0, done = 0, added = 0, failed = 0 }
+  local partial = ""
   vim.fn.jobstart(cmd, {
+    stdout_buffered = true,
+    on_stdout = function(_, data)
+      output = data
+    end,
+    on_stderr = function(_, data)
+      -- data holds the text split at newlines; its last item is unfinished
+      data[1] = partial .. data[1]
+      partial = table.remove(data)
+      for _, line in ipairs(data) do
+        local ok, event = pcall(vim.json.decode, line)
+        if ok and type(event) == "table" then
+          M.show_fetch_progress(progress, event)
+        end
+      end
+    end,
     on_exit = function(_, code)
-      if code == 0 then
-        vim.notify("RSS feeds fetched successfully!", vim.log.levels.INFO)
-      else
+      vim.api.nvim_echo({ { "" } }, false, {})
+      local ok, result = pcall(vim.fn.json_decode, table.concat(output, ""))
+      if code ~= 0 or not ok or type(result) ~= "table" then
         vim.notify("Failed to fetch RSS feeds!", vim.log.levels.ERROR)
+        return
       end
-    end,
-    on_stdout = function(_, data)
-      for _, line in ipairs(data) do
-        if line ~= "" and (line:match("✓") or line:match("✗")) then
-          print(line)
+
+      for _, feed in ipairs(result.feeds or {}) do
+        if feed.error ~= vim.NIL and feed.error then
+          print("✗ " .. feed.url .. ": " .. feed.error)
         end
       end
+      vim.notify(string.format("RSS feeds fetched: %d succeeded, %d failed.",
+        result.succeeded or 0, result.failed or 0), vim.log.levels.INFO)
     end,
   })
 end
 
+-- Shows a fetch progress event in the command line
+function M.show_fetch_progress(progress, event)
+  if event.event == "begin" then
+    progress.feeds = event.feeds
+  elseif event.event == "finished" then
+    progress.done = progress.done + 1
+    progress.added = progress.added + (event.added or 0)
+    if event.error ~= vim.NIL and event.error then
+      progress.failed = progress.failed + 1
+    end
+  else
+    return
+  end
+  vim.api.nvim_echo({ { string.format("ZetRss: fetched %d/%d feeds, %d new articles, %d failed",
+    progress.done, progress.feeds, progress.added, progress.failed) } }, false, {})
+end
+
 function M.mark_all_read()
   -- Count unread articles first
   local articles = require("zetrss.articles")
@@ -222,11 +296,174 @@ function M.mark_all_read()
   end)
 end
 
+-- Reads the status file written by `zetrss daemon` without running the binary
+function M.get_daemon_status()
+  local path = config.zetrss_path .. "/state/daemon.json"
+  if vim.fn.filereadable(path) == 0 then
+    return nil
+  end
+  local ok, status = pcall(vim.fn.json_decode, table.concat(vim.fn.readfile(path), ""))
+  if not ok then
+    return nil
+  end
+  return status
+end
+
+function M.daemon_status()
+  local status = M.get_daemon_status()
+  if not status then
+    vim.notify("No daemon status found. Start one with `zetrss daemon`.", vim.log.levels.WARN)
+    return
+  end
+
+  -- A running daemon answers with fresh counts; the file is from its last cycle
+  local socket = require("zetrss.ipc").daemon_socket()
+  if socket then
+    require("zetrss.ipc").request(socket, "unread", nil, function(result)
+      if result then
+        status.unread = result.unread
+      end
+      M.show_daemon_status(status)
+    end)
+  else
+    M.show_daemon_status(status)
+  end
+end
+
+function M.show_daemon_status(status)
+
+  local failed = 0
+  for _, feed in ipairs(status.feeds or {}) do
+    if feed.last_error ~= vim.NIL and feed.last_error then
+      failed = failed + 1
+    end
+  end
+  vim.notify(string.format("Daemon %s: %d unread, %d feeds (%d failing)",
+    status.running and "running" or "stopped",
+    status.unread or 0, #(status.feeds or {}), failed), vim.log.levels.INFO)
+end
+
+-- Creates the article's zet note through `zetrss rpc`, which applies the
+-- [notes] dir, filename and template settings; returns its path or nil, error
+function M.create_note(article_id)
+  -- Summaries from a local LLM can take a while
+  local result, err = require("zetrss.rpc").request_sync("create-note", { id = article_id }, 180000)
+  if not result then
+    return nil, "Failed to create note: " .. err
+  end
+  return result.path
+end
+
+-- Opens the article's link in the browser through `zetrss rpc`
+function M.open_url(article_id)
+  local _, err = require("zetrss.rpc").request_sync("open-url", { id = article_id })
+  if err then
+    vim.notify("ZetRss: " .. err, vim.log.levels.ERROR)
+  end
+end
+
+-- Loads the #feed lines of feeds whose last fetch failed into the quickfix list
+function M.broken_feeds()
+  if not config.zetrss_bin then
+    vim.notify("zetrss binary not found! Please build it with 'make build' in plugin directory", vim.log.levels.ERROR)
+    return
+  end
+
+  local cmd = string.format("env ZETRSS_DATA_DIR=%s %s broken --quickfix",
+    vim.fn.shellescape(config.zetrss_path),
+    config.zetrss_bin)
+  local lines = vim.fn.systemlist(cmd)
+  if vim.v.shell_error ~= 0 then
+    vim.notify("Failed to list broken feeds: " .. table.concat(lines, "\n"), vim.log.levels.ERROR)
+    return
+  end
+  if #lines == 0 then
+    vim.notify("No failing feeds.", vim.log.levels.INFO)
+    return
+  end
+
+  vim.fn.setqflist({}, " ", {
+    title = "ZetRss broken feeds",
+    lines = lines,
+    efm = "%f:%l:%c: %m,%m",
+  })
+  vim.cmd("copen")
+end
+
+-- Runs `zetrss sync` in the background and reports what it did
+function M.sync()
+  if not config.zetrss_bin then
+    vim.notify("zetrss binary not found! Please build it with 'make build' in plugin directory", vim.log.levels.ERROR)
+    return
+  end
+
+  vim.notify("Syncing...", vim.log.levels.INFO)
+  local output = {}
+  local errors = {}
+  vim.fn.jobstart({ config.zetrss_bin, "--json", "sync" }, {
+    env = { ZETRSS_DATA_DIR = config.zetrss_path },
+    stdout_buffered = true,
+    stderr_buffered = true,
+    on_stdout = function(_, data)
+      output = data
+    end,
+    on_stderr = function(_, data)
+      errors = data
+    end,
+    on_exit = function(_, code)
+      local ok, report = pcall(vim.json.decode, table.concat(output, ""))
+      if code ~= 0 or not ok or type(report) ~= "table" then
+        -- --json reports the error as {"error": ...} on stderr
+        local decoded, failure = pcall(vim.json.decode, table.concat(errors, ""))
+        local message = decoded and type(failure) == "table" and failure.error or table.concat(errors, "\n")
+        vim.notify("Sync failed: " .. message, vim.log.levels.ERROR)
+        return
+      end
+      vim.notify(string.format("Synced: %d articles added, %d updated, %d changes sent",
+        report.added, report.updated, report.pushed), vim.log.levels.INFO)
+    end,
+  })
+end
+
+-- Runs `zetrss pocket` in the background and reports what it sent
+function M.pocket()
+  if not config.zetrss_bin then
+    vim.notify("zetrss binary not found! Please build it with 'make build' in plugin directory", vim.log.levels.ERROR)
+    return
+  end
+
+  local output = {}
+  local errors = {}
+  vim.fn.jobstart({ config.zetrss_bin, "--json", "pocket" }, {
+    env = { ZETRSS_DATA_DIR = config.zetrss_path },
+    stdout_buffered = true,
+    stderr_buffered = true,
+    on_stdout = function(_, data)
+      output = data
+    end,
+    on_stderr = function(_, data)
+      errors = data
+    end,
+    on_exit = function(_, code)
+      local ok, report = pcall(vim.json.decode, table.concat(output, ""))
+      if code ~= 0 or not ok or type(report) ~= "table" then
+        local decoded, failure = pcall(vim.json.decode, table.concat(errors, ""))
+        local message = decoded and type(failure) == "table" and failure.error or table.concat(errors, "\n")
+        vim.notify("Pocket export failed: " .. message, vim.log.levels.ERROR)
+        return
+      end
+      local level = report.failed > 0 and vim.log.levels.WARN or vim.log.levels.INFO
+      vim.notify(string.format("Sent %d starred articles to Pocket (%d rejected)",
+        report.sent, report.failed), level)
+    end,
+  })
+end
+
 function M.clear_cache()
   -- Get article count for informative message
   local articles_dir = config.zetrss_path .. "/articles"
   local count = 0
-  local handle = io.popen("ls " .. vim.fn.shellescape(articles_dir) .. "/*.md 2>/dev/null | wc -l")
+  local handle = io.popen("find " .. vim.fn.shellescape(articles_dir) .. " -maxdepth 2 -name '*.md' 2>/dev/null | wc -l")
   if handle then
     count = tonumber(handle:read("*a")) or 0
     handle:close()
@@ -264,6 +501,62 @@ function M.clear_cache()
   end)
 end
 
+-- Shows an article as Markdown in a normal buffer (`zetrss preview`), with
+-- the colorscheme and folding of markdown files, and marks it read
+function M.open_in_buffer(article_id)
+  if not config.zetrss_bin then
+    vim.notify("zetrss binary not found! Please build it with 'make build' in plugin directory", vim.log.levels.ERROR)
+    return
+  end
+  local width = math.min(100, vim.api.nvim_win_get_width(0))
+  local cmd = string.format("env ZETRSS_DATA_DIR=%s %s preview --format markdown --width %d --id %s 2>/dev/null",
+    vim.fn.shellescape(config.zetrss_path),
+    config.zetrss_bin,
+    width,
+    vim.fn.shellescape(article_id))
+  local lines = vim.fn.systemlist(cmd)
+  if vim.v.shell_error ~= 0 then
+    vim.notify("ZetRss: article not found: " .. article_id, vim.log.levels.ERROR)
+    return
+  end
+
+  local name = "zetrss://" .. article_id
+  local buf = vim.fn.bufnr(name)
+  if buf == -1 then
+    buf = vim.api.nvim_create_buf(true, true)
+    vim.api.nvim_buf_set_name(buf, name)
+  end
+  vim.bo[buf].modifiable = true
+  vim.api.nvim_buf_set_lines(buf, 0, -1, false, lines)
+  vim.bo[buf].modifiable = false
+  vim.bo[buf].bufhidden = "hide"
+  vim.bo[buf].filetype = "markdown"
+  vim.api.nvim_set_current_buf(buf)
+
+  require("zetrss.articles").mark_as_read(article_id)
+end
+
+-- Socket of the viewer open in a terminal buffer, if any
+local viewer_socket = nil
+
+function M.set_viewer_socket(path)
+  viewer_socket = path
+end
+
+-- Shows the article in the open viewer instead of starting another one;
+-- returns false when no viewer is open
+function M.jump_to_article(article_id)
+  if not viewer_socket then
+    return false
+  end
+  require("zetrss.ipc").request(viewer_socket, "jump", { id = article_id }, function(_, err)
+    if err then
+      vim.notify("ZetRss: " .. err, vim.log.levels.ERROR)
+    end
+  end)
+  return true
+end
+
 function M.get_config()
   return config
 end
@@ -0,0 +1,66 @@
+-- Requests to running zetrss processes (the daemon, an open viewer) over
+-- their Unix sockets, in the same JSON-RPC as `zetrss rpc`
+local M = {}
+
+local uv = vim.uv or vim.loop
+
+-- Sends one request to the socket at `path`; callback(result, err) runs on
+-- the main loop once the answer arrives
+function M.request(path, method, params, callback)
+  callback = vim.schedule_wrap(callback or function() end)
+  local pipe = uv.new_pipe(false)
+  local buffer = ""
+  local finished = false
+  local function finish(result, err)
+    if finished then
+      return
+    end
+    finished = true
+    if not pipe:is_closing() then
+      pipe:close()
+    end
+    callback(result, err)
+  end
+
+  pipe:connect(path, function(connect_err)
+    if connect_err then
+      finish(nil, "Nothing listens on " .. path)
+      return
+    end
+    pipe:read_start(function(read_err, data)
+      if read_err or not data then
+        finish(nil, read_err or "Connection closed")
+        return
+      end
+      buffer = buffer .. data
+      local line = buffer:match("^(.-)\n")
+      if line then
+        local ok, message = pcall(vim.json.decode, line)
+        if not ok then
+          finish(nil, "Invalid answer: " .. line)
+        elseif message.error then
+          finish(nil, message.error.message)
+        else
+          finish(message.result, nil)
+        end
+      end
+    end)
+    pipe:write(vim.json.encode({
+      jsonrpc = "2.0",
+      id = 1,
+      method = method,
+      params = params or vim.empty_dict(),
+    }) .. "\n")
+  end)
+end
+
+-- The running daemon's socket, from its status file
+function M.daemon_socket()
+  local status = require("zetrss").get_daemon_status()
+  if status and status.running and type(status.socket) == "string" then
+    return status.socket
+  end
+  return nil
+end
+
+return M
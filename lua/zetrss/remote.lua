@@ -0,0 +1,48 @@
+-- `zetrss nvim`: the binary attached to this Neovim over msgpack-RPC. Once
+-- attached it defines the global `ZetRss` table, one function per method of
+-- `zetrss rpc` (dashes become underscores), answering with Lua tables:
+--
+--   ZetRss.list({ query = "read:false", limit = 10 })
+--   ZetRss.mark_read({ id = "..." })
+local M = {}
+
+local job = nil
+
+-- Starts the process unless it's attached already and waits for it to
+-- register; returns the `ZetRss` table or nil, err
+function M.start(timeout_ms)
+  if job and _G.ZetRss then
+    return _G.ZetRss
+  end
+  local config = require("zetrss").get_config()
+  if not config.zetrss_bin then
+    return nil, "zetrss binary not found! Please build it with 'make build' in plugin directory"
+  end
+  if vim.v.servername == "" then
+    vim.fn.serverstart()
+  end
+
+  job = vim.fn.jobstart({ config.zetrss_bin, "nvim" }, {
+    env = { ZETRSS_DATA_DIR = config.zetrss_path, NVIM = vim.v.servername },
+    on_exit = function()
+      job = nil
+      _G.ZetRss = nil
+    end,
+  })
+  if job <= 0 then
+    job = nil
+    return nil, "Failed to start zetrss nvim"
+  end
+  if not vim.wait(timeout_ms or 5000, function() return _G.ZetRss ~= nil end, 10) then
+    return nil, "zetrss nvim didn't attach"
+  end
+  return _G.ZetRss
+end
+
+function M.stop()
+  if job then
+    vim.fn.jobstop(job)
+  end
+end
+
+return M
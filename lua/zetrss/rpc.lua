@@ -0,0 +1,115 @@
+-- Client for `zetrss rpc`: one long-running process answering newline-delimited
+-- JSON-RPC requests, instead of spawning the binary for every action
+local M = {}
+
+local job = nil
+local next_id = 0
+-- Callbacks of requests waiting for their answer, by request id
+local pending = {}
+-- Start of a line whose end hasn't arrived yet
+local partial = ""
+
+local function handle_line(line)
+  if line == "" then
+    return
+  end
+  local ok, message = pcall(vim.json.decode, line)
+  if not ok or type(message) ~= "table" then
+    return
+  end
+  local callback = pending[message.id]
+  if not callback then
+    return
+  end
+  pending[message.id] = nil
+  if message.error then
+    callback(nil, message.error.message or "zetrss rpc error")
+  else
+    callback(message.result, nil)
+  end
+end
+
+local function start()
+  if job then
+    return job
+  end
+  local config = require("zetrss").get_config()
+  if not config.zetrss_bin then
+    return nil, "zetrss binary not found! Please build it with 'make build' in plugin directory"
+  end
+
+  local id = vim.fn.jobstart({ config.zetrss_bin, "rpc" }, {
+    env = { ZETRSS_DATA_DIR = config.zetrss_path },
+    on_stdout = function(_, data)
+      -- data holds the text split at newlines; its last item is unfinished
+      data[1] = partial .. data[1]
+      partial = table.remove(data)
+      for _, line in ipairs(data) do
+        handle_line(line)
+      end
+    end,
+    on_exit = function()
+      job = nil
+      partial = ""
+      local waiting = pending
+      pending = {}
+      for _, callback in pairs(waiting) do
+        callback(nil, "zetrss rpc exited")
+      end
+    end,
+  })
+  if id <= 0 then
+    return nil, "Failed to start zetrss rpc"
+  end
+  job = id
+  return job
+end
+
+-- Sends a request; callback(result, err) runs when the answer arrives
+function M.request(method, params, callback)
+  local channel, err = start()
+  if not channel then
+    callback(nil, err)
+    return
+  end
+  next_id = next_id + 1
+  pending[next_id] = callback
+  vim.fn.chansend(channel, vim.json.encode({
+    jsonrpc = "2.0",
+    id = next_id,
+    method = method,
+    params = params or vim.empty_dict(),
+  }) .. "\n")
+end
+
+-- Sends a request and waits for its answer; returns result or nil, err
+function M.request_sync(method, params, timeout_ms)
+  local done, result, err = false, nil, nil
+  M.request(method, params, function(res, e)
+    done, result, err = true, res, e
+  end)
+  if not vim.wait(timeout_ms or 10000, function() return done end, 10) then
+    return nil, "zetrss rpc: no answer to " .. method
+  end
+  return result, err
+end
+
+-- Sends a request whose answer doesn't matter
+function M.notify(method, params)
+  local channel = start()
+  if channel then
+    vim.fn.chansend(channel, vim.json.encode({
+      jsonrpc = "2.0",
+      method = method,
+      params = params or vim.empty_dict(),
+    }) .. "\n")
+  end
+end
+
+function M.stop()
+  if job then
+    vim.fn.jobstop(job)
+  end
+end
+
+return M
@@ -0,0 +1,90 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+#[path = "archive_tests.rs"]
+mod tests;
+
+/// Extension of archive bundles: gzip-compressed JSON lines
+pub const BUNDLE_EXT: &str = "jsonl.gz";
+
+/// An article file moved into an archive bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedFile {
+    pub id: String,
+    /// File name the article had in `articles/`
+    pub name: String,
+    pub markdown: String,
+    /// Contents of the raw entry JSON kept next to the article, if any
+    pub raw: Option<String>,
+    /// Contents of the article's highlights file, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlights: Option<String>,
+}
+
+/// Bundle an article published at `published` belongs in: `YYYY-MM`, or `undated`
+pub fn bundle_name(published: Option<DateTime<Utc>>) -> String {
+    published
+        .map(|d| d.format("%Y-%m").to_string())
+        .unwrap_or_else(|| "undated".to_string())
+}
+
+/// Path of the named bundle in `archive_dir`
+pub fn bundle_path(archive_dir: &Path, name: &str) -> PathBuf {
+    archive_dir.join(format!("{}.{}", name, BUNDLE_EXT))
+}
+
+/// Name of a bundle file, or None if the path isn't one
+pub fn bundle_name_of(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    file_name
+        .strip_suffix(BUNDLE_EXT)?
+        .strip_suffix('.')
+        .map(str::to_string)
+}
+
+/// Reads every article in a bundle; a missing bundle is empty
+pub fn read_bundle(path: &Path) -> Result<Vec<ArchivedFile>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file =
+        File::open(path).with_context(|| format!("Failed to open archive {}", path.display()))?;
+    let mut files = Vec::new();
+    for line in BufReader::new(GzDecoder::new(file)).lines() {
+        let line = line.with_context(|| format!("Failed to read archive {}", path.display()))?;
+        if !line.trim().is_empty() {
+            files.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(files)
+}
+
+/// Replaces a bundle's contents, removing the bundle when `files` is empty
+/// Written to a temporary file first so a crash can't truncate the bundle
+pub fn write_bundle(path: &Path, files: &[ArchivedFile]) -> Result<()> {
+    if files.is_empty() {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+
+    let tmp = path.with_extension("tmp");
+    let mut encoder = GzEncoder::new(File::create(&tmp)?, Compression::default());
+    for file in files {
+        serde_json::to_writer(&mut encoder, file)?;
+        encoder.write_all(b"\n")?;
+    }
+    encoder.finish()?.sync_all()?;
+    fs::rename(&tmp, path)
+        .with_context(|| format!("Failed to write archive {}", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use super::*;
+use chrono::TimeZone;
+use tempfile::TempDir;
+
+fn file(id: &str) -> ArchivedFile {
+    ArchivedFile {
+        id: id.to_string(),
+        name: format!("20240105-120000-{}.md", id),
+        markdown: format!("---\nid: {}\n---\nBody", id),
+        raw: None,
+        highlights: None,
+    }
+}
+
+#[test]
+fn test_bundle_names() {
+    let date = Utc.with_ymd_and_hms(2024, 1, 5, 12, 0, 0).unwrap();
+    assert_eq!(bundle_name(Some(date)), "2024-01");
+    assert_eq!(bundle_name(None), "undated");
+
+    let path = bundle_path(Path::new("/data/archive"), "2024-01");
+    assert_eq!(path, Path::new("/data/archive/2024-01.jsonl.gz"));
+    assert_eq!(bundle_name_of(&path).as_deref(), Some("2024-01"));
+    assert_eq!(bundle_name_of(Path::new("/data/archive/2024-01.tmp")), None);
+}
+
+#[test]
+fn test_bundle_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let path = bundle_path(dir.path(), "2024-01");
+    assert!(read_bundle(&path).unwrap().is_empty());
+
+    let files = vec![file("a"), file("b")];
+    write_bundle(&path, &files).unwrap();
+    assert_eq!(read_bundle(&path).unwrap(), files);
+
+    // Emptying a bundle deletes it
+    write_bundle(&path, &[]).unwrap();
+    assert!(!path.exists());
+}
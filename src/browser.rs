@@ -0,0 +1,707 @@
+//! Three-pane terminal reader shown by `zetrss view` without an id
+//!
+//! Feeds with their unread counts on the left, the selected feed's articles
+//! (newest first, filtered with the `list` query syntax via `/`) in the middle
+//! and a preview of the selected article on the right. Tab and h/l move the
+//! focus between panes. Enter opens the article in the viewer; `q` there comes
+//! back here, while the viewer's action keys end the session with the same
+//! exit codes as `view --id`.
+//!
+//! `o` cycles the sort order and `U`, `S` and `t` filter by unread, starred
+//! and tag. The sort, filters, query and selected feed are saved to
+//! `state/browser.json` on exit and restored on the next start.
+
+use crate::cache::{ArticleStore, ReadBatch, TextCache};
+use crate::engine::ArticleView;
+use crate::filter::{ArticleQuery, SortOrder};
+use crate::models::FeedItem;
+use crate::query::parse_query;
+use crate::theme::{self, Theme};
+use crate::viewer::{self, Tui, ViewerMode, ViewerOutcome};
+use anyhow::{Context, Result};
+use crossterm::event::{self, Event, KeyCode};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::Instant;
+
+#[cfg(test)]
+#[path = "browser_tests.rs"]
+mod tests;
+
+/// Where the list view is kept between sessions
+const STATE_FILE: &str = "browser.json";
+
+/// Runs the reader until it is quit or an action key is picked in an opened
+/// article
+pub fn run_browser(cache: &TextCache) -> Result<ViewerOutcome> {
+    let started = Instant::now();
+    let mut app = BrowserApp::load(cache, ListView::load(cache)?)?;
+
+    let mut terminal = viewer::enter_tui()?;
+    let res = browse(&mut terminal, cache, &mut app);
+    viewer::leave_tui(&mut terminal)?;
+    app.saved_view().save(cache)?;
+    let exit_code = match res? {
+        Some(end) => viewer::action_exit_code(&end.mode, &end.article)?,
+        None => 0,
+    };
+
+    Ok(ViewerOutcome {
+        exit_code,
+        finished: false,
+        articles_read: app.articles_read,
+        elapsed: started.elapsed(),
+    })
+}
+
+/// The key loop; returns how the last opened article ended if that ended
+/// the session
+fn browse(
+    terminal: &mut Tui,
+    cache: &TextCache,
+    app: &mut BrowserApp,
+) -> Result<Option<viewer::QueueEnd>> {
+    loop {
+        terminal.draw(|f| ui(f, &mut *app))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        let page = terminal.size()?.height.saturating_sub(6) as isize;
+        app.status = None;
+
+        if let Some(input) = app.input.as_mut() {
+            match key.code {
+                KeyCode::Enter => {
+                    let (prompt, text) = app.input.take().unwrap_or_default();
+                    match prompt {
+                        Prompt::Query => app.apply_query(cache, text)?,
+                        Prompt::Tag => {
+                            let tag = text.trim().trim_start_matches('#');
+                            app.view.tag = (!tag.is_empty()).then(|| tag.to_string());
+                            app.selected = 0;
+                            app.reload_articles(cache)?;
+                        }
+                    }
+                }
+                KeyCode::Esc => app.input = None,
+                KeyCode::Backspace => {
+                    input.1.pop();
+                }
+                KeyCode::Char(c) => input.1.push(c),
+                _ => {}
+            }
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+            KeyCode::Char('/') => app.input = Some((Prompt::Query, app.view.query.clone())),
+            KeyCode::Char('t') => {
+                let tag = app.view.tag.clone().unwrap_or_default();
+                app.input = Some((Prompt::Tag, tag));
+            }
+            KeyCode::Char('o') => {
+                app.view.sort = next_sort(app.view.sort);
+                app.reload_articles(cache)?;
+            }
+            KeyCode::Char('U') => {
+                app.view.unread_only = !app.view.unread_only;
+                app.reload_articles(cache)?;
+            }
+            KeyCode::Char('S') => {
+                app.view.starred_only = !app.view.starred_only;
+                app.reload_articles(cache)?;
+            }
+            KeyCode::Tab => app.focus = app.focus.next(),
+            KeyCode::BackTab => app.focus = app.focus.prev(),
+            KeyCode::Char('l') | KeyCode::Right => app.focus = app.focus.right(),
+            KeyCode::Char('h') | KeyCode::Left => app.focus = app.focus.left(),
+            KeyCode::Char('j') | KeyCode::Down => app.move_by(cache, 1)?,
+            KeyCode::Char('k') | KeyCode::Up => app.move_by(cache, -1)?,
+            KeyCode::PageDown | KeyCode::Char(' ') => app.move_by(cache, page)?,
+            KeyCode::PageUp => app.move_by(cache, -page)?,
+            KeyCode::Char('g') | KeyCode::Home => app.move_by(cache, isize::MIN)?,
+            KeyCode::Char('G') | KeyCode::End => app.move_by(cache, isize::MAX)?,
+            KeyCode::Char('s') => {
+                if let Some(article) = app.articles.get_mut(app.selected) {
+                    if cache.toggle_star(&article.id).is_ok() {
+                        article.starred = !article.starred;
+                        if article.starred {
+                            match crate::pocket::on_star(cache.base_dir(), article) {
+                                Ok(true) => app.status = Some("Sent to Pocket".to_string()),
+                                Ok(false) => {}
+                                Err(e) => app.status = Some(format!("{:#}", e)),
+                            }
+                        }
+                    }
+                }
+            }
+            KeyCode::Char('A') => app.mark_all_read(cache)?,
+            KeyCode::Char('u') => app.undo(cache)?,
+            KeyCode::Enter if app.focus == Pane::Feeds => app.focus = Pane::Items,
+            KeyCode::Enter => {
+                if app.articles.is_empty() {
+                    continue;
+                }
+                let ids: Vec<String> = app.articles.iter().map(|a| a.id.clone()).collect();
+                let end =
+                    viewer::read_queue(terminal, cache, &ids, app.selected, false, false, None)?;
+                app.articles_read += end.articles_read;
+                if end.mode != ViewerMode::Reading {
+                    return Ok(Some(end));
+                }
+                // Come back to the article read last
+                if let Some(index) = app.articles.iter().position(|a| a.id == end.article.id) {
+                    app.selected = index;
+                }
+                app.reload(cache)?;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// What the footer prompt edits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Prompt {
+    /// A `list` query, after `/`
+    #[default]
+    Query,
+    /// The tag filter, after `t`
+    Tag,
+}
+
+/// Sort order and filters of the article list
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct ListView {
+    sort: SortOrder,
+    unread_only: bool,
+    starred_only: bool,
+    tag: Option<String>,
+    /// Query the articles are filtered by, in `list` syntax
+    query: String,
+    /// Feed selected in the feeds pane; None for all feeds
+    feed: Option<String>,
+}
+
+impl ListView {
+    /// The view saved by the last session, or the default one
+    fn load(cache: &TextCache) -> Result<Self> {
+        let path = cache.state_path(STATE_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        // A view that no longer parses isn't worth refusing to start over
+        Ok(serde_json::from_str(&content).unwrap_or_default())
+    }
+
+    fn save(&self, cache: &TextCache) -> Result<()> {
+        let path = cache.state_path(STATE_FILE);
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Sort and active filters for the list title, e.g. "newest · unread · #rust"
+    fn describe(&self) -> String {
+        let mut parts = vec![format!("{:?}", self.sort).to_lowercase()];
+        if self.unread_only {
+            parts.push("unread".to_string());
+        }
+        if self.starred_only {
+            parts.push("starred".to_string());
+        }
+        if let Some(tag) = &self.tag {
+            parts.push(format!("#{}", tag));
+        }
+        if !self.query.is_empty() {
+            parts.push(self.query.clone());
+        }
+        parts.join(" · ")
+    }
+}
+
+/// The sort order `o` switches to
+fn next_sort(sort: SortOrder) -> SortOrder {
+    match sort {
+        SortOrder::Newest => SortOrder::Oldest,
+        SortOrder::Oldest => SortOrder::Feed,
+        SortOrder::Feed => SortOrder::Unread,
+        SortOrder::Unread => SortOrder::Title,
+        SortOrder::Title => SortOrder::Newest,
+    }
+}
+
+/// Which pane keys act on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Feeds,
+    Items,
+    Preview,
+}
+
+impl Pane {
+    fn next(self) -> Self {
+        match self {
+            Pane::Feeds => Pane::Items,
+            Pane::Items => Pane::Preview,
+            Pane::Preview => Pane::Feeds,
+        }
+    }
+
+    fn prev(self) -> Self {
+        self.next().next()
+    }
+
+    fn right(self) -> Self {
+        match self {
+            Pane::Feeds => Pane::Items,
+            _ => Pane::Preview,
+        }
+    }
+
+    fn left(self) -> Self {
+        match self {
+            Pane::Preview => Pane::Items,
+            _ => Pane::Feeds,
+        }
+    }
+}
+
+/// A row of the feeds pane; the first one (no URL) lists every feed
+struct FeedEntry {
+    url: Option<String>,
+    label: String,
+    unread: usize,
+}
+
+/// An article rendered for the preview pane at a given width
+struct Preview {
+    id: String,
+    width: u16,
+    lines: Vec<String>,
+}
+
+struct BrowserApp {
+    feeds: Vec<FeedEntry>,
+    feed_selected: usize,
+    articles: Vec<FeedItem>,
+    selected: usize,
+    view: ListView,
+    /// Query or tag being edited in the footer
+    input: Option<(Prompt, String)>,
+    /// Why the last query was rejected
+    error: Option<String>,
+    /// Footer message shown until the next key
+    status: Option<String>,
+    /// Last mark-all-read, reverted with `u`
+    undo: Option<ReadBatch>,
+    focus: Pane,
+    preview: Option<Preview>,
+    preview_scroll: u16,
+    articles_read: usize,
+    /// Scroll positions of the rendered lists, kept between frames
+    feed_state: ListState,
+    list_state: ListState,
+}
+
+impl BrowserApp {
+    fn load(cache: &TextCache, view: ListView) -> Result<Self> {
+        let mut app = Self {
+            feeds: Vec::new(),
+            feed_selected: 0,
+            articles: Vec::new(),
+            selected: 0,
+            view,
+            input: None,
+            error: None,
+            status: None,
+            undo: None,
+            focus: Pane::Items,
+            preview: None,
+            preview_scroll: 0,
+            articles_read: 0,
+            feed_state: ListState::default(),
+            list_state: ListState::default(),
+        };
+        app.reload(cache)?;
+        Ok(app)
+    }
+
+    /// Re-reads feeds and articles, keeping both selections on the same feed
+    /// and article if they are still listed
+    fn reload(&mut self, cache: &TextCache) -> Result<()> {
+        let feed_url = if self.feeds.is_empty() {
+            // First load: the feed the saved view was on
+            self.view.feed.clone()
+        } else {
+            self.selected_feed_url()
+        };
+        self.feeds = feed_entries(cache)?;
+        self.feed_selected = feed_url
+            .and_then(|url| {
+                self.feeds
+                    .iter()
+                    .position(|f| f.url.as_deref() == Some(url.as_str()))
+            })
+            .unwrap_or(0);
+        self.reload_articles(cache)
+    }
+
+    fn reload_articles(&mut self, cache: &TextCache) -> Result<()> {
+        let selected_id = self.selected_id();
+        // A saved query that stopped parsing is dropped rather than kept failing
+        let mut filter = parse_query(&self.view.query).unwrap_or_else(|e| {
+            self.error = Some(format!("{:#}", e));
+            self.view.query.clear();
+            Default::default()
+        });
+        if let Some(url) = self.selected_feed_url() {
+            filter.feed = Some(url);
+        }
+        if self.view.unread_only {
+            filter.read = Some(false);
+        }
+        if self.view.starred_only {
+            filter.starred = Some(true);
+        }
+        filter.tags.extend(self.view.tag.clone());
+        self.articles = cache.get_articles(&ArticleQuery {
+            filter,
+            sort: self.view.sort,
+            ..Default::default()
+        })?;
+        self.selected = selected_id
+            .and_then(|id| self.articles.iter().position(|a| a.id == id))
+            .unwrap_or(self.selected)
+            .min(self.articles.len().saturating_sub(1));
+        Ok(())
+    }
+
+    /// Filters the articles by a new query; an invalid one is reported and
+    /// the list stays as it was
+    fn apply_query(&mut self, cache: &TextCache, query: String) -> Result<()> {
+        if let Err(e) = parse_query(&query) {
+            self.error = Some(format!("{:#}", e));
+            return Ok(());
+        }
+        self.error = None;
+        self.view.query = query;
+        self.selected = 0;
+        self.reload_articles(cache)
+    }
+
+    /// Moves the selection of the focused pane, or scrolls the preview
+    fn move_by(&mut self, cache: &TextCache, delta: isize) -> Result<()> {
+        match self.focus {
+            Pane::Feeds => {
+                let selected = step(self.feed_selected, delta, self.feeds.len());
+                if selected != self.feed_selected {
+                    self.feed_selected = selected;
+                    self.selected = 0;
+                    self.reload_articles(cache)?;
+                }
+            }
+            Pane::Items => self.selected = step(self.selected, delta, self.articles.len()),
+            Pane::Preview => {
+                let lines = self.preview.as_ref().map_or(0, |p| p.lines.len());
+                self.preview_scroll = step(self.preview_scroll as usize, delta, lines) as u16;
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks every listed article read, keeping the batch for `undo`
+    fn mark_all_read(&mut self, cache: &TextCache) -> Result<()> {
+        let ids: Vec<String> = self.articles.iter().map(|a| a.id.clone()).collect();
+        let batch = cache.mark_read_batch(&ids)?;
+        if batch.ids.is_empty() {
+            self.status = Some("Nothing unread in this list".to_string());
+            return Ok(());
+        }
+        self.status = Some(format!("Marked {} read (u to undo)", batch.ids.len()));
+        self.undo = Some(batch);
+        self.reload(cache)
+    }
+
+    /// Reverts the last mark-all-read
+    fn undo(&mut self, cache: &TextCache) -> Result<()> {
+        let Some(batch) = self.undo.take() else {
+            self.status = Some("Nothing to undo".to_string());
+            return Ok(());
+        };
+        let count = cache.undo_read_batch(&batch)?;
+        self.status = Some(format!("Marked {} unread again", count));
+        self.reload(cache)
+    }
+
+    /// The list view with the selected feed, as saved for the next session
+    fn saved_view(&self) -> ListView {
+        ListView {
+            feed: self.selected_feed_url(),
+            ..self.view.clone()
+        }
+    }
+
+    fn selected_feed_url(&self) -> Option<String> {
+        self.feeds.get(self.feed_selected)?.url.clone()
+    }
+
+    fn selected_id(&self) -> Option<String> {
+        self.articles.get(self.selected).map(|a| a.id.clone())
+    }
+}
+
+/// `index` moved by `delta` and clamped to a list of `len` items
+fn step(index: usize, delta: isize, len: usize) -> usize {
+    index
+        .saturating_add_signed(delta)
+        .min(len.saturating_sub(1))
+}
+
+/// "All feeds" followed by each cached or subscribed feed with its unread count
+fn feed_entries(cache: &TextCache) -> Result<Vec<FeedEntry>> {
+    let feed_list = cache.get_feed_list()?;
+    let mut counts: std::collections::BTreeMap<String, usize> =
+        feed_list.iter().map(|feed| (feed.url.clone(), 0)).collect();
+    for feed in cache.feed_counts()? {
+        counts.insert(feed.feed_url, feed.unread);
+    }
+
+    let mut entries = vec![FeedEntry {
+        url: None,
+        label: "All feeds".to_string(),
+        unread: counts.values().sum(),
+    }];
+    entries.extend(counts.into_iter().map(|(url, unread)| {
+        FeedEntry {
+            // A title given in the zet, else the feed's own
+            label: feed_list
+                .iter()
+                .find(|feed| feed.url == url)
+                .and_then(|feed| feed.options.title.clone())
+                .or_else(|| cache.feed_title(&url))
+                .unwrap_or_else(|| short_url(&url).to_string()),
+            url: Some(url),
+            unread,
+        }
+    }));
+    Ok(entries)
+}
+
+fn short_url(url: &str) -> &str {
+    url.trim_start_matches("https://")
+        .trim_start_matches("http://")
+}
+
+fn ui(f: &mut Frame, app: &mut BrowserApp) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(5),    // Panes
+            Constraint::Length(3), // Footer or filter input
+        ])
+        .split(f.size());
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(20),
+            Constraint::Percentage(35),
+            Constraint::Percentage(45),
+        ])
+        .split(rows[0]);
+
+    let theme = theme::get();
+    render_feeds(f, panes[0], app, theme);
+    render_list(f, panes[1], app, theme);
+    render_preview(f, panes[2], app, theme);
+    render_footer(f, rows[1], app, theme);
+}
+
+/// Border of a pane, highlighted when it has the focus
+fn pane_block(title: String, focused: bool, theme: &Theme) -> Block<'static> {
+    Block::default()
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(if focused {
+            theme.focused_border
+        } else {
+            theme.border
+        })
+        .title(title)
+        .title_alignment(Alignment::Center)
+}
+
+fn render_feeds(f: &mut Frame, area: Rect, app: &mut BrowserApp, theme: &Theme) {
+    let items: Vec<ListItem> = app
+        .feeds
+        .iter()
+        .map(|feed| {
+            let count = if feed.unread > 0 {
+                Span::styled(format!("{:>4} ", feed.unread), theme.accent)
+            } else {
+                Span::raw("     ")
+            };
+            ListItem::new(Line::from(vec![count, Span::raw(feed.label.clone())]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(pane_block(
+            " Feeds ".to_string(),
+            app.focus == Pane::Feeds,
+            theme,
+        ))
+        .highlight_style(theme.selection);
+    app.feed_state.select(Some(app.feed_selected));
+    f.render_stateful_widget(list, area, &mut app.feed_state);
+}
+
+fn render_list(f: &mut Frame, area: Rect, app: &mut BrowserApp, theme: &Theme) {
+    let items: Vec<ListItem> = app
+        .articles
+        .iter()
+        .map(|article| ListItem::new(list_line(article, theme)))
+        .collect();
+
+    let title = format!(
+        " Articles ({}) — {} ",
+        app.articles.len(),
+        app.view.describe()
+    );
+    let list = List::new(items)
+        .block(pane_block(title, app.focus == Pane::Items, theme))
+        .highlight_style(theme.selection);
+    app.list_state.select(Some(app.selected));
+    f.render_stateful_widget(list, area, &mut app.list_state);
+}
+
+/// One row: unread and star markers, date and title
+fn list_line(article: &FeedItem, theme: &Theme) -> Line<'static> {
+    let date = article
+        .published
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| " ".repeat(10));
+    let title_style = if article.read {
+        Style::default()
+    } else {
+        Style::default().add_modifier(Modifier::BOLD)
+    };
+    Line::from(vec![
+        Span::styled(if article.read { "  " } else { "● " }, theme.accent),
+        Span::styled(if article.starred { "★ " } else { "  " }, theme.star),
+        Span::styled(format!("{}  ", date), theme.muted),
+        Span::styled(article.title.clone(), title_style),
+    ])
+}
+
+fn render_preview(f: &mut Frame, area: Rect, app: &mut BrowserApp, theme: &Theme) {
+    let block = pane_block(" Preview ".to_string(), app.focus == Pane::Preview, theme);
+    let Some(article) = app.articles.get(app.selected) else {
+        f.render_widget(Paragraph::new("No articles").block(block), area);
+        return;
+    };
+
+    // Re-render only when the article or the pane width changed
+    let width = area.width.saturating_sub(2);
+    let stale = app
+        .preview
+        .as_ref()
+        .is_none_or(|p| p.id != article.id || p.width != width);
+    if stale {
+        app.preview = Some(Preview {
+            id: article.id.clone(),
+            width,
+            lines: ArticleView::from_article(article.clone(), width as usize).lines,
+        });
+        app.preview_scroll = 0;
+    }
+
+    let mut lines = vec![
+        Line::from(Span::styled(article.title.clone(), theme.title)),
+        Line::from(Span::styled(
+            short_url(&article.feed_url).to_string(),
+            theme.feed,
+        )),
+        Line::default(),
+    ];
+    if let Some(preview) = &app.preview {
+        lines.extend(
+            preview
+                .lines
+                .iter()
+                .skip(app.preview_scroll as usize)
+                .map(|line| Line::raw(line.clone())),
+        );
+    }
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn render_footer(f: &mut Frame, area: Rect, app: &BrowserApp, theme: &Theme) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(theme.border);
+
+    if let Some((prompt, input)) = &app.input {
+        let (marker, title) = match prompt {
+            Prompt::Query => ("/", " Filter (feed:~x tag:y read:false \"phrase\") "),
+            Prompt::Tag => ("#", " Tag (empty for any) "),
+        };
+        let prompt = Paragraph::new(Line::from(vec![
+            Span::styled(marker, theme.accent),
+            Span::raw(input.clone()),
+        ]))
+        .block(block.title(title));
+        f.render_widget(prompt, area);
+        return;
+    }
+    if let Some(status) = &app.status {
+        let message = Paragraph::new(Span::styled(status.clone(), theme.accent))
+            .block(block)
+            .alignment(Alignment::Center);
+        f.render_widget(message, area);
+        return;
+    }
+    if let Some(error) = &app.error {
+        let message = Paragraph::new(Span::styled(error.clone(), theme.error)).block(block);
+        f.render_widget(message, area);
+        return;
+    }
+
+    let key = |k: &'static str| Span::styled(k, theme.key);
+    let footer = Paragraph::new(Line::from(vec![
+        key(" Enter "),
+        Span::raw(" Open  "),
+        key(" Tab/h/l "),
+        Span::raw(" Pane  "),
+        key(" / "),
+        Span::raw(" Filter  "),
+        key(" s "),
+        Span::raw(" Star  "),
+        key(" o "),
+        Span::raw(" Sort  "),
+        key(" U/S/t "),
+        Span::raw(" Unread/starred/tag  "),
+        key(" A "),
+        Span::raw(" All read  "),
+        key(" u "),
+        Span::raw(" Undo  "),
+        key(" j/k "),
+        Span::raw(" Move  "),
+        key(" q "),
+        Span::raw(" Quit  "),
+    ]))
+    .block(block)
+    .alignment(Alignment::Center);
+    f.render_widget(footer, area);
+}
@@ -0,0 +1,197 @@
+use super::*;
+use crate::models::Feed;
+use chrono::{Duration, Utc};
+use tempfile::TempDir;
+
+fn cache_with_articles(n: i64) -> (TextCache, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    store_articles(&cache, "https://example.com/feed", "article", n);
+    (cache, temp_dir)
+}
+
+fn store_articles(cache: &TextCache, url: &str, prefix: &str, n: i64) {
+    let items = (1..=n)
+        .map(|i| FeedItem {
+            id: format!("{}-{}", prefix, i),
+            feed_url: url.to_string(),
+            title: format!("Article {}", i),
+            link: format!("https://example.com/{}", i),
+            published: Some(Utc::now() - Duration::hours(i)),
+            content: Some(format!("Body {}", i)),
+            ..Default::default()
+        })
+        .collect();
+    cache
+        .store_feed(&Feed {
+            url: url.to_string(),
+            title: format!("{} feed", prefix),
+            description: None,
+            last_fetched: Some(Utc::now()),
+            bytes: 0,
+            items,
+        })
+        .unwrap();
+}
+
+fn query(query: &str) -> ListView {
+    ListView {
+        query: query.to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_selection_stays_in_bounds() {
+    let (cache, _temp_dir) = cache_with_articles(3);
+    let mut app = BrowserApp::load(&cache, ListView::default()).unwrap();
+    assert_eq!(app.selected_id().as_deref(), Some("article-1"));
+
+    app.move_by(&cache, -1).unwrap();
+    assert_eq!(app.selected, 0);
+    app.move_by(&cache, isize::MAX).unwrap();
+    assert_eq!(app.selected, 2);
+    app.move_by(&cache, 1).unwrap();
+    assert_eq!(app.selected, 2);
+}
+
+#[test]
+fn test_reload_keeps_selected_article() {
+    let (cache, _temp_dir) = cache_with_articles(3);
+    let mut app = BrowserApp::load(&cache, query("read:false")).unwrap();
+    app.move_by(&cache, 1).unwrap();
+    assert_eq!(app.selected_id().as_deref(), Some("article-2"));
+
+    cache.mark_as_read("article-1").unwrap();
+    app.reload(&cache).unwrap();
+    assert_eq!(app.articles.len(), 2);
+    assert_eq!(app.selected_id().as_deref(), Some("article-2"));
+
+    // The selected article dropped out: stay at the same row
+    cache.mark_as_read("article-2").unwrap();
+    app.reload(&cache).unwrap();
+    assert_eq!(app.selected_id().as_deref(), Some("article-3"));
+}
+
+#[test]
+fn test_invalid_query_keeps_list() {
+    let (cache, _temp_dir) = cache_with_articles(3);
+    let mut app = BrowserApp::load(&cache, ListView::default()).unwrap();
+
+    app.apply_query(&cache, "colour:blue".to_string()).unwrap();
+    assert!(app.error.as_deref().unwrap().contains("colour"));
+    assert_eq!(app.articles.len(), 3);
+
+    app.apply_query(&cache, "title:\"Article 2\"".to_string())
+        .unwrap();
+    assert!(app.error.is_none());
+    assert_eq!(app.articles.len(), 1);
+    assert_eq!(app.selected_id().as_deref(), Some("article-2"));
+}
+
+#[test]
+fn test_feeds_pane_filters_articles() {
+    let (cache, _temp_dir) = cache_with_articles(3);
+    store_articles(&cache, "https://other.example/rss", "other", 2);
+    cache.mark_as_read("other-1").unwrap();
+    let mut app = BrowserApp::load(&cache, ListView::default()).unwrap();
+
+    let feeds: Vec<(&str, usize)> = app
+        .feeds
+        .iter()
+        .map(|f| (f.label.as_str(), f.unread))
+        .collect();
+    assert_eq!(
+        feeds,
+        vec![("All feeds", 4), ("article feed", 3), ("other feed", 1)]
+    );
+    assert_eq!(app.articles.len(), 5);
+
+    app.focus = app.focus.left();
+    assert_eq!(app.focus, Pane::Feeds);
+    app.move_by(&cache, isize::MAX).unwrap();
+    assert_eq!(
+        app.selected_feed_url().as_deref(),
+        Some("https://other.example/rss")
+    );
+    assert_eq!(app.articles.len(), 2);
+    assert!(app.articles.iter().all(|a| a.id.starts_with("other")));
+
+    // The feed stays selected across reloads
+    app.reload(&cache).unwrap();
+    assert_eq!(app.feed_selected, 2);
+    assert_eq!(app.articles.len(), 2);
+}
+
+#[test]
+fn test_pane_focus_cycles() {
+    assert_eq!(Pane::Feeds.next(), Pane::Items);
+    assert_eq!(Pane::Preview.next(), Pane::Feeds);
+    assert_eq!(Pane::Feeds.prev(), Pane::Preview);
+    assert_eq!(Pane::Feeds.left(), Pane::Feeds);
+    assert_eq!(Pane::Items.right(), Pane::Preview);
+    assert_eq!(Pane::Preview.right(), Pane::Preview);
+}
+
+#[test]
+fn test_mark_all_read_and_undo() {
+    let (cache, _temp_dir) = cache_with_articles(3);
+    store_articles(&cache, "https://other.example/rss", "other", 2);
+    let mut app = BrowserApp::load(&cache, query("feed:~example.com")).unwrap();
+    assert_eq!(app.articles.len(), 3);
+
+    app.mark_all_read(&cache).unwrap();
+    assert!(app.articles.iter().all(|a| a.read));
+    // Articles outside the list are untouched
+    assert_eq!(app.feeds[0].unread, 2);
+
+    app.undo(&cache).unwrap();
+    assert!(app.articles.iter().all(|a| !a.read));
+    assert_eq!(app.feeds[0].unread, 5);
+    assert!(app.undo.is_none());
+}
+
+#[test]
+fn test_sort_and_filters() {
+    let (cache, _temp_dir) = cache_with_articles(3);
+    cache.mark_as_read("article-1").unwrap();
+    cache.toggle_star("article-3").unwrap();
+    let mut app = BrowserApp::load(&cache, ListView::default()).unwrap();
+    let ids =
+        |app: &BrowserApp| -> Vec<String> { app.articles.iter().map(|a| a.id.clone()).collect() };
+
+    app.view.sort = SortOrder::Unread;
+    app.reload_articles(&cache).unwrap();
+    assert_eq!(ids(&app), vec!["article-2", "article-3", "article-1"]);
+
+    app.view.starred_only = true;
+    app.reload_articles(&cache).unwrap();
+    assert_eq!(ids(&app), vec!["article-3"]);
+    assert_eq!(app.view.describe(), "unread · starred");
+
+    app.view.starred_only = false;
+    app.view.unread_only = true;
+    app.view.tag = Some("rust".to_string());
+    app.reload_articles(&cache).unwrap();
+    assert!(app.articles.is_empty());
+}
+
+#[test]
+fn test_view_is_restored() {
+    let (cache, _temp_dir) = cache_with_articles(3);
+    store_articles(&cache, "https://other.example/rss", "other", 2);
+    let mut app = BrowserApp::load(&cache, ListView::default()).unwrap();
+    app.view.sort = next_sort(app.view.sort);
+    app.view.unread_only = true;
+    app.focus = Pane::Feeds;
+    app.move_by(&cache, isize::MAX).unwrap();
+    app.saved_view().save(&cache).unwrap();
+
+    let view = ListView::load(&cache).unwrap();
+    assert_eq!(view.sort, SortOrder::Oldest);
+    assert_eq!(view.feed.as_deref(), Some("https://other.example/rss"));
+    let app = BrowserApp::load(&cache, view).unwrap();
+    assert_eq!(app.feed_selected, 2);
+    assert_eq!(app.articles.len(), 2);
+    assert_eq!(app.articles[0].id, "other-2");
+}
@@ -1,39 +1,63 @@
-use crate::models::{Feed, FeedItem};
+use crate::archive;
+use crate::crypt;
+use crate::filter::{ArticleFilter, ArticleQuery, SortOrder};
+use crate::history::{self, FetchRecord};
+use crate::index::{self, ArticleIndex, FeedCounts};
+use crate::migrate;
+use crate::models::{Feed, FeedItem, Highlight, RawEntry};
+use crate::quarantine::{self, QuarantineEntry};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
+use lru::LruCache;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::fs;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
 
 #[cfg(test)]
 #[path = "cache_tests.rs"]
 mod tests;
 /// Text-based cache for RSS articles and feeds
-/// Stores articles as markdown files with YAML frontmatter
+/// Stores articles as markdown files with YAML frontmatter, plus a SQLite
+/// index of their metadata (`index.sqlite`) for fast lookups and listing
 pub struct TextCache {
     base_dir: PathBuf,
     articles_dir: PathBuf,
+    archive_dir: PathBuf,
+    index: ArticleIndex,
+    /// Recently parsed articles, checked against the file's mtime and size
+    parsed: Mutex<LruCache<PathBuf, (FileStamp, FeedItem)>>,
+    /// Key of an encrypted cache, set on first use
+    cipher: OnceLock<crypt::Cipher>,
 }
 
+/// Number of parsed articles `TextCache` keeps in memory
+const PARSED_CACHE_SIZE: usize = 1024;
+
+/// Extension of the per-feed fetch history logs in `feeds/`
+const HISTORY_EXT: &str = "log.jsonl";
+
+/// How long a malformed article file must stay unchanged before it is
+/// quarantined, so files another process is still writing are left alone
+const QUARANTINE_GRACE: Duration = Duration::from_secs(30);
+
+/// Timestamp prefix of article file names
+const FILE_DATE_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Viewer scroll offsets of partly read articles, by article id
+const POSITIONS_FILE: &str = "positions.json";
+
+/// Modification time (ns) and length of a file, to spot changes behind our back
+type FileStamp = (i64, u64);
+
 impl TextCache {
     /// Creates a new TextCache instance
     /// Initializes the data directory structure if it doesn't exist
     pub fn new() -> Result<Self> {
-        // Check if running from Neovim and use its data directory
-        let base_dir = if let Ok(nvim_data) = std::env::var("ZETRSS_DATA_DIR") {
-            // If Neovim sets this env var, use it
-            PathBuf::from(nvim_data)
-        } else {
-            // Otherwise use XDG data directory standard
-            if let Ok(xdg_data) = std::env::var("XDG_DATA_HOME") {
-                PathBuf::from(xdg_data).join("zetrss")
-            } else {
-                // Fallback to ~/.local/share/zetrss (XDG default)
-                let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-                PathBuf::from(home).join(".local/share/zetrss")
-            }
-        };
-
-        Self::with_base_dir(base_dir)
+        Self::with_base_dir(default_data_dir())
     }
 
     /// Creates a new TextCache with an explicit base directory
@@ -41,33 +65,346 @@ impl TextCache {
         fs::create_dir_all(&base_dir)?;
         let articles_dir = base_dir.join("articles");
         fs::create_dir_all(&articles_dir)?;
+        let archive_dir = base_dir.join("archive");
+        fs::create_dir_all(&archive_dir)?;
         fs::create_dir_all(base_dir.join("feeds"))?;
         fs::create_dir_all(base_dir.join("state"))?;
+        let index = ArticleIndex::open(&base_dir.join("index.sqlite"))?;
 
-        Ok(Self {
+        let cache = Self {
             base_dir,
             articles_dir,
-        })
+            archive_dir,
+            index,
+            parsed: Mutex::new(LruCache::new(
+                NonZeroUsize::new(PARSED_CACHE_SIZE).expect("cache size is non-zero"),
+            )),
+            cipher: OnceLock::new(),
+        };
+        migrate::run(&cache)?;
+        // Pick up files written or edited outside this process
+        cache.sync_index()?;
+        Ok(cache)
+    }
+
+    /// Directory holding one feed's articles: `articles/<sanitized-feed>/`
+    pub(crate) fn feed_dir(&self, feed_url: &str) -> PathBuf {
+        self.articles_dir.join(feed_dir_name(feed_url))
+    }
+
+    /// Re-indexes article files changed on disk since the index last saw them
+    /// Returns the number of files (re)indexed; changed archive bundles are
+    /// re-indexed too
+    pub fn sync_index(&self) -> Result<usize> {
+        let malformed = RefCell::new(Vec::new());
+        let updated = self.index.sync(&self.articles_dir, |path| {
+            self.parse_valid_article(path).inspect_err(|e| {
+                malformed
+                    .borrow_mut()
+                    .push((path.to_path_buf(), format!("{:#}", e)))
+            })
+        })?;
+        for (path, reason) in malformed.into_inner() {
+            self.quarantine_if_settled(&path, &reason)?;
+        }
+
+        let indexed = self.index.archive_mtimes()?;
+        let mut seen = std::collections::HashSet::new();
+        for entry in fs::read_dir(&self.archive_dir)?.filter_map(|e| e.ok()) {
+            let Some(name) = archive::bundle_name_of(&entry.path()) else {
+                continue;
+            };
+            if indexed.get(&name) != Some(&index::file_mtime(&entry.path())?) {
+                self.reindex_archive(&name)?;
+            }
+            seen.insert(name);
+        }
+        for name in indexed.keys().filter(|name| !seen.contains(*name)) {
+            self.index.remove_archive(name)?;
+        }
+        Ok(updated)
+    }
+
+    /// Rebuilds the index from every article file and archive bundle
+    /// For when it has drifted in ways modification times can't reveal
+    /// Returns the number of article files indexed
+    pub fn rebuild_index(&self) -> Result<usize> {
+        self.index.clear()?;
+        self.parsed().clear();
+        let indexed = self.sync_index()?;
+        self.index.compact()?;
+        Ok(indexed)
+    }
+
+    /// Replaces the index rows of an archive bundle with its current contents
+    fn reindex_archive(&self, name: &str) -> Result<()> {
+        let path = archive::bundle_path(&self.archive_dir, name);
+        if !path.exists() {
+            return self.index.remove_archive(name);
+        }
+        let items: Vec<_> = archive::read_bundle(&path)?
+            .iter()
+            .filter_map(|file| {
+                let hot_path = self.articles_dir.join(&file.name);
+                let item = Self::parse_article(&file.markdown, &hot_path).ok()?;
+                Some((hot_path.to_string_lossy().to_string(), item))
+            })
+            .collect();
+        self.index
+            .index_archive(name, index::file_mtime(&path)?, &items)
+    }
+
+    /// Moves a malformed article file to `quarantine/`, unless it changed in
+    /// the last `QUARANTINE_GRACE`: it may be half-written by another process
+    fn quarantine_if_settled(&self, path: &Path, reason: &str) -> Result<()> {
+        let Ok(modified) = fs::metadata(path).and_then(|m| m.modified()) else {
+            return Ok(());
+        };
+        if modified.elapsed().unwrap_or_default() < QUARANTINE_GRACE {
+            return Ok(());
+        }
+        let entry = quarantine::quarantine(&self.base_dir, &self.articles_dir, path, reason)?;
+        self.parsed().pop(path);
+        tracing::warn!(
+            "Quarantined malformed article {} ({}) as {}",
+            entry.original,
+            reason,
+            quarantine::dir(&self.base_dir).join(&entry.file).display()
+        );
+        Ok(())
+    }
+
+    /// Article files moved to `quarantine/` because they didn't parse
+    pub fn quarantined(&self) -> Result<Vec<QuarantineEntry>> {
+        quarantine::entries(&self.base_dir)
+    }
+
+    /// Updates the index row for an article file after writing it
+    fn reindex_file(&self, path: &Path) -> Result<()> {
+        // A rewrite within the filesystem's timestamp granularity could keep
+        // the stamp, so never trust the cached copy of a file we just wrote
+        self.parsed().pop(path);
+        let item = self.parse_article_file(path)?;
+        self.index.upsert(&item, index::file_mtime(path)?)
+    }
+
+    /// Looks up an article's file in the index
+    /// Re-syncs once on a miss or a vanished file, in case another process changed the cache
+    fn indexed_path(&self, item_id: &str) -> Result<Option<PathBuf>> {
+        if let Some(path) = self.index.path_for_id(item_id)? {
+            if path.exists() {
+                return Ok(Some(path));
+            }
+        }
+        self.sync_index()?;
+        self.index.path_for_id(item_id)
+    }
+
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    pub(crate) fn articles_dir(&self) -> &Path {
+        &self.articles_dir
+    }
+
+    /// Path of a file in the state directory (e.g. `feeds.json`)
+    pub fn state_path(&self, name: &str) -> PathBuf {
+        self.base_dir.join("state").join(name)
+    }
+
+    /// Line the viewer was scrolled to when the article was last left mid-read
+    pub fn reading_position(&self, item_id: &str) -> Result<Option<u16>> {
+        Ok(self.reading_positions()?.get(item_id).copied())
+    }
+
+    /// Remembers where reading stopped; None forgets it (article finished or
+    /// left at the top)
+    pub fn set_reading_position(&self, item_id: &str, line: Option<u16>) -> Result<()> {
+        let mut positions = self.reading_positions()?;
+        let changed = match line {
+            Some(line) => positions.insert(item_id.to_string(), line) != Some(line),
+            None => positions.remove(item_id).is_some(),
+        };
+        if !changed {
+            return Ok(());
+        }
+        let path = self.state_path(POSITIONS_FILE);
+        fs::write(&path, serde_json::to_string_pretty(&positions)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn reading_positions(&self) -> Result<BTreeMap<String, u16>> {
+        let path = self.state_path(POSITIONS_FILE);
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    /// True if article bodies are stored encrypted (see `crypt`)
+    pub fn is_encrypted(&self) -> bool {
+        crypt::is_enabled(&self.base_dir)
+    }
+
+    /// Unlocks an encrypted cache with `passphrase` instead of looking one up
+    pub fn unlock(&self, passphrase: &str) -> Result<()> {
+        let cipher = crypt::open(&self.base_dir, passphrase)?;
+        let _ = self.cipher.set(cipher);
+        Ok(())
+    }
+
+    /// The cache's cipher, unlocking it with `crypt::passphrase` on first use
+    fn cipher(&self) -> Result<&crypt::Cipher> {
+        if self.cipher.get().is_none() {
+            self.unlock(&crypt::passphrase(&self.base_dir)?)?;
+        }
+        Ok(self.cipher.get().expect("set by unlock"))
+    }
+
+    /// The text following an article's frontmatter, sealed if the cache is encrypted
+    fn seal_body(&self, body: &str) -> Result<String> {
+        if !self.is_encrypted() {
+            return Ok(body.to_string());
+        }
+        Ok(format!("\n{}\n", self.cipher()?.encrypt(body)))
+    }
+
+    /// Opens a sealed article body. Parsing leaves bodies sealed, so indexing
+    /// and listing metadata never need the key; this runs before articles are
+    /// handed out.
+    fn decrypt_article(&self, mut article: FeedItem) -> Result<FeedItem> {
+        if let Some(ref body) = article.content {
+            if crypt::is_encrypted(body) {
+                let body = self.cipher()?.decrypt(body)?;
+                article.description = Some(body.clone());
+                article.content = Some(body);
+            }
+        }
+        Ok(article)
+    }
+
+    /// Turns encryption on and seals every article body, raw entry and
+    /// archive bundle, then compacts the index so no plaintext lingers in it.
+    /// Returns the number of articles encrypted.
+    pub fn encrypt_all(&self, passphrase: &str) -> Result<usize> {
+        let cipher = crypt::enable(&self.base_dir, passphrase)?;
+        let _ = self.cipher.set(cipher);
+        let count = self.convert_all(true)?;
+        self.index.compact()?;
+        Ok(count)
+    }
+
+    /// Opens every sealed article body, raw entry and archive bundle, then
+    /// turns encryption off. Returns the number of articles decrypted.
+    pub fn decrypt_all(&self) -> Result<usize> {
+        // Check the passphrase before touching any file
+        self.cipher()?;
+        let count = self.convert_all(false)?;
+        crypt::disable(&self.base_dir)?;
+        Ok(count)
+    }
+
+    fn convert_all(&self, seal: bool) -> Result<usize> {
+        let cipher = self.cipher()?;
+        let convert = |text: &str| -> Result<String> {
+            match (seal, crypt::is_encrypted(text)) {
+                (true, false) => Ok(cipher.encrypt(text)),
+                (false, true) => cipher.decrypt(text),
+                _ => Ok(text.to_string()),
+            }
+        };
+        let convert_markdown = |markdown: &str| -> Result<String> {
+            // Files without frontmatter are left alone; `doctor` reports them
+            let Some(split) = frontmatter_end(markdown) else {
+                return Ok(markdown.to_string());
+            };
+            let (head, body) = markdown.split_at(split);
+            Ok(match (seal, crypt::is_encrypted(body)) {
+                (true, false) => format!("{}\n{}\n", head, cipher.encrypt(body)),
+                (false, true) => format!("{}{}", head, cipher.decrypt(body)?),
+                _ => markdown.to_string(),
+            })
+        };
+
+        let mut count = 0;
+        for path in index::article_files(&self.articles_dir) {
+            let markdown = fs::read_to_string(&path)?;
+            let converted = convert_markdown(&markdown)?;
+            if converted != markdown {
+                fs::write(&path, converted)?;
+                self.reindex_file(&path)?;
+                count += 1;
+            }
+            for sidecar in [raw_entry_path(&path), highlights_path(&path)] {
+                if sidecar.exists() {
+                    let text = fs::read_to_string(&sidecar)?;
+                    fs::write(&sidecar, convert(&text)?)?;
+                }
+            }
+        }
+
+        for entry in fs::read_dir(&self.archive_dir)?.filter_map(|e| e.ok()) {
+            let Some(name) = archive::bundle_name_of(&entry.path()) else {
+                continue;
+            };
+            let mut files = archive::read_bundle(&entry.path())?;
+            for file in &mut files {
+                let markdown = convert_markdown(&file.markdown)?;
+                if markdown != file.markdown {
+                    file.markdown = markdown;
+                    count += 1;
+                }
+                if let Some(ref raw) = file.raw {
+                    file.raw = Some(convert(raw)?);
+                }
+                if let Some(ref highlights) = file.highlights {
+                    file.highlights = Some(convert(highlights)?);
+                }
+            }
+            archive::write_bundle(&entry.path(), &files)?;
+            self.reindex_archive(&name)?;
+        }
+        Ok(count)
     }
 
     /// Stores a feed's articles to disk
     /// Each article is saved as a separate markdown file
-    pub fn store_feed(&self, feed: &Feed) -> Result<()> {
+    /// Returns the number of articles that weren't cached before
+    pub fn store_feed(&self, feed: &Feed) -> Result<usize> {
         // Check for duplicate IDs in the feed
         let mut seen_ids = std::collections::HashSet::new();
+        let mut added = 0;
 
         for item in &feed.items {
+            // Feed-provided IDs are untrusted: normalize them the same way lookups do
+            let id = normalize_id(&item.id);
+            if let Err(e) = validate_id(&id) {
+                tracing::warn!("Skipping article from {}: {}", feed.url, e);
+                continue;
+            }
+
+            // Archived articles count as stored; refetching must not resurrect them
+            if self.index.archive_for_id(&id)?.is_some() {
+                continue;
+            }
+
             // Check if ID already exists in cache
-            if let Ok(Some(_)) = self.get_article_by_id(&item.id) {
-                // Article with this ID already exists, skip it
+            if let Some(existing) = self.index.path_for_id(&id)? {
+                // Article with this ID already exists; only fill in a missing raw entry
+                if !raw_entry_path(&existing).exists() {
+                    self.write_raw_entry(&existing, item)?;
+                }
                 continue;
             }
 
             // Check if we've seen this ID in the current feed batch
-            let mut unique_id = item.id.clone();
+            let mut unique_id = id.clone();
             let mut counter = 1;
             while seen_ids.contains(&unique_id) {
-                unique_id = format!("{}-{}", item.id, counter);
+                unique_id = format!("{}-{}", id, counter);
                 counter += 1;
             }
             seen_ids.insert(unique_id.clone());
@@ -76,12 +413,10 @@ impl TextCache {
             let mut item_to_store = item.clone();
             item_to_store.id = unique_id;
             self.store_article(&item_to_store)?;
+            added += 1;
         }
 
-        let feed_meta_path = self
-            .base_dir
-            .join("feeds")
-            .join(format!("{}.json", sanitize_filename(&feed.url)));
+        let feed_meta_path = self.feed_meta_path(&feed.url, "json");
 
         let meta = serde_json::json!({
             "url": feed.url,
@@ -92,19 +427,41 @@ impl TextCache {
 
         fs::write(feed_meta_path, serde_json::to_string_pretty(&meta)?)?;
 
-        Ok(())
+        Ok(added)
     }
 
-    fn store_article(&self, item: &FeedItem) -> Result<()> {
-        let filename = format!(
-            "{}-{}.md",
-            item.published
-                .unwrap_or_else(Utc::now)
-                .format("%Y%m%d-%H%M%S"),
-            sanitize_filename(&item.id)
-        );
+    /// Title the feed had when it was last fetched
+    pub fn feed_title(&self, feed_url: &str) -> Option<String> {
+        let content = fs::read_to_string(self.feed_meta_path(feed_url, "json")).ok()?;
+        let meta: serde_json::Value = serde_json::from_str(&content).ok()?;
+        meta["title"]
+            .as_str()
+            .filter(|title| !title.is_empty())
+            .map(str::to_string)
+    }
+
+    /// Path of a per-feed file in `feeds/` with the given extension
+    fn feed_meta_path(&self, feed_url: &str, ext: &str) -> PathBuf {
+        self.base_dir
+            .join("feeds")
+            .join(format!("{}.{}", sanitize_filename(feed_url), ext))
+    }
+
+    /// Appends a fetch attempt to the feed's history log
+    pub fn record_fetch(&self, feed_url: &str, record: &FetchRecord) -> Result<()> {
+        history::append(&self.feed_meta_path(feed_url, HISTORY_EXT), record)
+    }
+
+    /// The feed's recorded fetch attempts, oldest first
+    pub fn fetch_history(&self, feed_url: &str) -> Result<Vec<FetchRecord>> {
+        history::read(&self.feed_meta_path(feed_url, HISTORY_EXT))
+    }
 
-        let filepath = self.base_dir.join("articles").join(filename);
+    fn store_article(&self, item: &FeedItem) -> Result<()> {
+        let feed_dir = self.feed_dir(&item.feed_url);
+        fs::create_dir_all(&feed_dir)?;
+        let filepath = feed_dir.join(article_file_name(item));
+        self.ensure_in_articles_dir(&filepath)?;
 
         if filepath.exists() {
             return Ok(());
@@ -118,18 +475,11 @@ title: {}
 link: {}
 author: {}
 date: {}
+fetched: {}
 read: false
 starred: false
----
-
-# {}
-
-{}
-
-{}
-
-[Read original]({})
-"#,
+tags: {}
+---{}"#,
             item.id,
             item.feed_url,
             item.title.replace('\n', " "),
@@ -138,64 +488,399 @@ starred: false
             item.published
                 .map(|d| d.to_rfc3339())
                 .unwrap_or_else(|| Utc::now().to_rfc3339()),
-            item.title,
-            item.description.as_deref().unwrap_or(""),
-            item.content.as_deref().unwrap_or(""),
-            item.link
+            Utc::now().to_rfc3339(),
+            format_tags(&item.tags),
+            self.seal_body(&format!(
+                "\n{}",
+                render_body(
+                    &item.title,
+                    item.description.as_deref(),
+                    item.content.as_deref(),
+                    &item.link
+                )
+            ))?
         );
 
-        fs::write(filepath, content)?;
-        Ok(())
+        fs::write(&filepath, content)?;
+        self.write_raw_entry(&filepath, item)?;
+        self.reindex_file(&filepath)
     }
 
-    /// Retrieves articles from disk, sorted by modification time
-    /// Returns up to `limit` articles if specified
-    pub fn get_articles(&self, limit: Option<usize>) -> Result<Vec<FeedItem>> {
-        let mut articles = Vec::new();
-        let articles_dir = self.base_dir.join("articles");
+    /// Saves the parsed feed entry next to the article file, if the item has one
+    fn write_raw_entry(&self, article_path: &Path, item: &FeedItem) -> Result<()> {
+        let Some(ref raw) = item.raw else {
+            return Ok(());
+        };
+        let path = raw_entry_path(article_path);
+        self.ensure_in_articles_dir(&path)?;
+        let json = serde_json::to_string_pretty(raw)?;
+        let json = if self.is_encrypted() {
+            self.cipher()?.encrypt(&json)
+        } else {
+            json
+        };
+        fs::write(&path, json)
+            .with_context(|| format!("Failed to write raw entry {}", path.display()))
+    }
 
-        let mut entries: Vec<_> = fs::read_dir(&articles_dir)?
-            .filter_map(|e| e.ok())
-            .filter(|e| e.path().extension().is_some_and(|ext| ext == "md"))
-            .collect();
+    /// The feed entry an article was created from, as originally parsed
+    /// None for articles stored before raw entries were kept
+    pub fn get_raw_entry(&self, item_id: &str) -> Result<Option<RawEntry>> {
+        let path = raw_entry_path(&self.find_article_path(item_id)?);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read raw entry {}", path.display()))?;
+        let json = if crypt::is_encrypted(&json) {
+            self.cipher()?.decrypt(&json)?
+        } else {
+            json
+        };
+        Ok(Some(serde_json::from_str(&json)?))
+    }
 
-        entries.sort_by_key(|e| {
-            e.metadata()
-                .and_then(|m| m.modified())
-                .unwrap_or_else(|_| std::time::SystemTime::now())
-        });
-        entries.reverse();
+    /// Passages highlighted in the article, oldest first
+    pub fn get_highlights(&self, item_id: &str) -> Result<Vec<Highlight>> {
+        let path = highlights_path(&self.find_article_path(item_id)?);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read highlights {}", path.display()))?;
+        let json = if crypt::is_encrypted(&json) {
+            self.cipher()?.decrypt(&json)?
+        } else {
+            json
+        };
+        Ok(serde_json::from_str(&json)?)
+    }
 
-        let limit = limit.unwrap_or(entries.len());
+    /// Records a highlighted passage of the article
+    /// Returns the article's number of highlights
+    pub fn add_highlight(&self, item_id: &str, text: &str) -> Result<usize> {
+        let mut highlights = self.get_highlights(item_id)?;
+        highlights.push(Highlight {
+            text: text.to_string(),
+            created: Utc::now(),
+        });
+        let path = highlights_path(&self.find_article_path(item_id)?);
+        self.ensure_in_articles_dir(&path)?;
+        let json = serde_json::to_string_pretty(&highlights)?;
+        let json = if self.is_encrypted() {
+            self.cipher()?.encrypt(&json)
+        } else {
+            json
+        };
+        fs::write(&path, json)
+            .with_context(|| format!("Failed to write highlights {}", path.display()))?;
+        Ok(highlights.len())
+    }
 
-        for entry in entries.into_iter().take(limit) {
-            if let Ok(item) = self.parse_article_file(&entry.path()) {
-                articles.push(item);
+    /// One page of the articles matching the query, in its sort order
+    /// The index filters and orders candidates, so only the page itself is
+    /// read from disk (plus candidates that fail free-text terms)
+    pub fn get_articles(&self, query: &ArticleQuery) -> Result<Vec<FeedItem>> {
+        let filter = &query.filter;
+        // The index has the final say unless text terms or a sub-second
+        // `since` must be checked against the file
+        let index_decides = filter.text_terms.is_empty() && filter.since.is_none();
+        let mut skip = query.offset;
+        let mut articles = Vec::new();
+        for entry in self.index.query(filter, query.sort)? {
+            if query.limit.is_some_and(|limit| articles.len() >= limit) {
+                break;
+            }
+            if skip > 0 && index_decides {
+                skip -= 1;
+                continue;
+            }
+            let Some(ref path) = entry.filepath else {
+                continue;
+            };
+            if let Ok(article) = self.parse_article_file(Path::new(path)) {
+                let article = self.decrypt_article(article)?;
+                if !filter.matches(&article) {
+                    continue;
+                }
+                if skip > 0 {
+                    skip -= 1;
+                } else {
+                    articles.push(article);
+                }
             }
         }
+        Ok(articles)
+    }
 
+    /// Articles matching the filter, best full-text match first
+    /// Free-text and title terms go through the search index (stemmed, one phrase
+    /// per term); without any, this is the same as `get_articles`
+    pub fn search(&self, filter: &ArticleFilter, limit: Option<usize>) -> Result<Vec<FeedItem>> {
+        let Some(fts) = index::fts_query(filter) else {
+            return self.get_articles(&ArticleQuery {
+                filter: filter.clone(),
+                limit,
+                ..Default::default()
+            });
+        };
+        let rest = ArticleFilter {
+            text_terms: Vec::new(),
+            title_terms: Vec::new(),
+            ..filter.clone()
+        };
+
+        // Archived hits are read from their bundle, each bundle at most once
+        let mut bundles = std::collections::HashMap::new();
+        let mut articles = Vec::new();
+        for hit in self.index.search(&fts, None)? {
+            if limit.is_some_and(|limit| articles.len() >= limit) {
+                break;
+            }
+            let article = match hit.archive {
+                None => self.parse_article_file(&hit.path).ok(),
+                Some(name) => {
+                    if !bundles.contains_key(&name) {
+                        let files =
+                            archive::read_bundle(&archive::bundle_path(&self.archive_dir, &name))?;
+                        bundles.insert(name.clone(), files);
+                    }
+                    bundles[&name]
+                        .iter()
+                        .find(|file| self.articles_dir.join(&file.name) == hit.path)
+                        .and_then(|file| Self::archived_article(file, &hit.path).ok())
+                }
+            };
+            if let Some(article) = article {
+                let article = self.decrypt_article(article)?;
+                if rest.matches(&article) {
+                    articles.push(article);
+                }
+            }
+        }
         Ok(articles)
     }
 
-    /// Get a single article by ID (linear scan)
+    /// Finds articles containing the query as a phrase, best match first
+    /// Matches title and body, or only the title when `title_only` is set
+    pub fn search_articles(&self, query: &str, title_only: bool) -> Result<Vec<FeedItem>> {
+        let terms = vec![query.to_string()];
+        let filter = if title_only {
+            ArticleFilter {
+                title_terms: terms,
+                ..Default::default()
+            }
+        } else {
+            ArticleFilter {
+                text_terms: terms,
+                ..Default::default()
+            }
+        };
+        self.search(&filter, None)
+    }
+
+    /// Lists article files that can't be parsed, with the reason
+    /// Other commands skip these silently
+    pub fn find_corrupt_articles(&self) -> Result<Vec<(PathBuf, String)>> {
+        let mut corrupt = Vec::new();
+        for path in index::article_files(&self.articles_dir) {
+            if let Err(e) = self.parse_valid_article(&path) {
+                corrupt.push((path, format!("{:#}", e)));
+            }
+        }
+        corrupt.sort();
+        Ok(corrupt)
+    }
+
+    /// Get a single article by ID (index lookup)
     pub fn get_article_by_id(&self, article_id: &str) -> Result<Option<FeedItem>> {
-        // We need to read all articles and match by the ID in the frontmatter, not filename
-        // because filenames can have collisions (e.g., newsletter vs 2020/newsletter)
-        let articles = self.get_articles(None)?;
+        validate_id(article_id)?;
 
-        for article in articles {
-            if article.id == article_id {
-                return Ok(Some(article));
+        // Filenames can collide (e.g., newsletter vs 2020/newsletter), so the
+        // index maps the frontmatter ID to the file that actually holds it
+        let Some(path) = self.indexed_path(article_id)? else {
+            return self.get_archived_article(article_id);
+        };
+        match self.parse_article_file(&path) {
+            Ok(article) if article.id == article_id => self.decrypt_article(article).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Reads an archived article from its bundle
+    fn get_archived_article(&self, article_id: &str) -> Result<Option<FeedItem>> {
+        let Some(name) = self.index.archive_for_id(article_id)? else {
+            return Ok(None);
+        };
+        let files = archive::read_bundle(&archive::bundle_path(&self.archive_dir, &name))?;
+        match files.iter().find(|file| file.id == article_id) {
+            Some(file) => {
+                let article = Self::archived_article(file, &self.articles_dir.join(&file.name))?;
+                self.decrypt_article(article).map(Some)
             }
+            None => Ok(None),
         }
+    }
 
-        Ok(None)
+    /// Parses an archived article; it has no file of its own, so no file path
+    fn archived_article(file: &archive::ArchivedFile, hot_path: &Path) -> Result<FeedItem> {
+        let mut article = Self::parse_article(&file.markdown, hot_path)?;
+        article.filepath = None;
+        Ok(article)
+    }
+
+    /// Moves articles published before `cutoff` into per-month archive bundles
+    /// Archived articles leave `articles/` but stay searchable and can be read
+    /// by ID; undated articles are never archived. Nothing moves on a dry run.
+    pub fn archive(&self, cutoff: DateTime<Utc>, dry_run: bool) -> Result<Vec<FeedItem>> {
+        let old = ArticleFilter {
+            until: Some(cutoff),
+            ..Default::default()
+        };
+        let articles = self.index.query(&old, SortOrder::Newest)?;
+        if dry_run {
+            return Ok(articles);
+        }
+
+        let mut by_bundle: std::collections::BTreeMap<String, Vec<&FeedItem>> = Default::default();
+        for article in &articles {
+            by_bundle
+                .entry(archive::bundle_name(article.published))
+                .or_default()
+                .push(article);
+        }
+
+        for (name, items) in by_bundle {
+            let bundle = archive::bundle_path(&self.archive_dir, &name);
+            let mut files = archive::read_bundle(&bundle)?;
+            let mut moved = Vec::new();
+            for item in items {
+                let Some(ref filepath) = item.filepath else {
+                    continue;
+                };
+                let path = Path::new(filepath);
+                self.ensure_in_articles_dir(path)?;
+                files.push(archive::ArchivedFile {
+                    id: item.id.clone(),
+                    name: path
+                        .strip_prefix(&self.articles_dir)
+                        .unwrap_or(path)
+                        .to_string_lossy()
+                        .to_string(),
+                    markdown: fs::read_to_string(path).with_context(|| {
+                        format!("Failed to read article file: {}", path.display())
+                    })?,
+                    raw: fs::read_to_string(raw_entry_path(path)).ok(),
+                    highlights: fs::read_to_string(highlights_path(path)).ok(),
+                });
+                moved.push((item.id.as_str(), path));
+            }
+            // The bundle is complete on disk before any article file goes away
+            archive::write_bundle(&bundle, &files)?;
+            for (id, path) in moved {
+                self.delete_article_file(id, path)?;
+            }
+            self.reindex_archive(&name)?;
+        }
+        Ok(articles)
     }
 
-    fn parse_article_file(&self, path: &Path) -> Result<FeedItem> {
+    /// Moves archived articles back into `articles/`
+    /// Returns the number of articles restored
+    pub fn unarchive(&self, ids: &[String]) -> Result<usize> {
+        let mut by_bundle: std::collections::BTreeMap<String, Vec<&str>> = Default::default();
+        for id in ids {
+            validate_id(id)?;
+            let name = self
+                .index
+                .archive_for_id(id)?
+                .ok_or_else(|| anyhow::anyhow!("Article {} is not archived", id))?;
+            by_bundle.entry(name).or_default().push(id);
+        }
+
+        let mut restored = 0;
+        for (name, ids) in by_bundle {
+            restored += self.restore_from_bundle(&name, |file| ids.contains(&file.id.as_str()))?;
+        }
+        Ok(restored)
+    }
+
+    /// Moves every article of one archive bundle (`YYYY-MM` or `undated`) back
+    pub fn unarchive_bundle(&self, name: &str) -> Result<usize> {
+        if !archive::bundle_path(&self.archive_dir, name).exists() {
+            return Err(anyhow::anyhow!("No archive named {}", name));
+        }
+        self.restore_from_bundle(name, |_| true)
+    }
+
+    fn restore_from_bundle(
+        &self,
+        name: &str,
+        pick: impl Fn(&archive::ArchivedFile) -> bool,
+    ) -> Result<usize> {
+        let bundle = archive::bundle_path(&self.archive_dir, name);
+        let (restore, keep): (Vec<_>, Vec<_>) = archive::read_bundle(&bundle)?
+            .into_iter()
+            .partition(|f| pick(f));
+
+        let mut paths = Vec::new();
+        for file in &restore {
+            let path = self.articles_dir.join(&file.name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            self.ensure_in_articles_dir(&path)?;
+            fs::write(&path, &file.markdown)
+                .with_context(|| format!("Failed to restore article {}", file.id))?;
+            if let Some(ref raw) = file.raw {
+                fs::write(raw_entry_path(&path), raw)?;
+            }
+            if let Some(ref highlights) = file.highlights {
+                fs::write(highlights_path(&path), highlights)?;
+            }
+            paths.push(path);
+        }
+        archive::write_bundle(&bundle, &keep)?;
+        self.reindex_archive(name)?;
+        for path in &paths {
+            self.reindex_file(path)?;
+        }
+        Ok(restore.len())
+    }
+
+    /// Parses an article file, rejecting one without an ID as malformed
+    fn parse_valid_article(&self, path: &Path) -> Result<FeedItem> {
+        let item = self.parse_article_file(path)?;
+        if item.id.is_empty() {
+            return Err(anyhow::anyhow!("missing id in frontmatter"));
+        }
+        Ok(item)
+    }
+
+    /// Parses an article file, reusing the in-memory copy if the file is unchanged
+    pub(crate) fn parse_article_file(&self, path: &Path) -> Result<FeedItem> {
+        let metadata = fs::metadata(path)
+            .with_context(|| format!("Failed to read article file: {}", path.display()))?;
+        let stamp = (index::file_mtime(path)?, metadata.len());
+        if let Some((cached_stamp, item)) = self.parsed().get(path) {
+            if *cached_stamp == stamp {
+                return Ok(item.clone());
+            }
+        }
+
         let content = fs::read_to_string(path)
             .with_context(|| format!("Failed to read article file: {}", path.display()))?;
+        let item = Self::parse_article(&content, path)?;
+        self.parsed().put(path.to_path_buf(), (stamp, item.clone()));
+        Ok(item)
+    }
 
+    fn parsed(&self) -> MutexGuard<'_, LruCache<PathBuf, (FileStamp, FeedItem)>> {
+        self.parsed.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Parses an article's markdown; `path` is recorded as its file path
+    fn parse_article(content: &str, path: &Path) -> Result<FeedItem> {
         let parts: Vec<&str> = content.splitn(3, "---").collect();
         if parts.len() < 3 {
             return Err(anyhow::anyhow!(
@@ -213,8 +898,10 @@ starred: false
         let mut link = String::new();
         let mut author = None;
         let mut published = None;
+        let mut fetched = None;
         let mut read = false;
         let mut starred = false;
+        let mut tags = Vec::new();
 
         for line in frontmatter.lines() {
             if let Some((key, value)) = line.split_once(':') {
@@ -226,18 +913,20 @@ starred: false
                     "feed" => feed_url = value.to_string(),
                     "title" => title = value.to_string(),
                     "link" => link = value.to_string(),
-                    "author" => {
-                        if !value.is_empty() {
-                            author = Some(value.to_string());
-                        }
-                    }
+                    "author" if !value.is_empty() => author = Some(value.to_string()),
                     "date" => {
                         published = DateTime::parse_from_rfc3339(value)
                             .ok()
                             .map(|d| d.with_timezone(&Utc));
                     }
+                    "fetched" => {
+                        fetched = DateTime::parse_from_rfc3339(value)
+                            .ok()
+                            .map(|d| d.with_timezone(&Utc));
+                    }
                     "read" => read = value == "true",
                     "starred" => starred = value == "true",
+                    "tags" => tags = parse_tags(value),
                     _ => {}
                 }
             }
@@ -254,7 +943,10 @@ starred: false
             content: Some(body.to_string()),
             read,
             starred,
+            tags,
+            fetched,
             filepath: Some(path.to_string_lossy().to_string()),
+            raw: None,
         })
     }
 
@@ -265,6 +957,49 @@ starred: false
             .with_context(|| format!("Failed to mark article {} as read", item_id))
     }
 
+    /// Marks an article as unread again
+    /// Updates the YAML frontmatter in the article file
+    pub fn mark_as_unread(&self, item_id: &str) -> Result<()> {
+        self.update_article_state(item_id, "read", "false")
+            .with_context(|| format!("Failed to mark article {} as unread", item_id))
+    }
+
+    /// Marks the given articles read as one batch that `undo_read_batch` can
+    /// revert; if any update fails the ones already made are rolled back
+    pub fn mark_read_batch(&self, item_ids: &[String]) -> Result<ReadBatch> {
+        let mut batch = ReadBatch::default();
+        for id in item_ids {
+            let marked = self
+                .get_article_by_id(id)
+                .and_then(|article| match article {
+                    Some(article) if !article.read => self.mark_as_read(id).map(|_| true),
+                    _ => Ok(false),
+                });
+            match marked {
+                Ok(true) => batch.ids.push(id.clone()),
+                Ok(false) => {}
+                Err(e) => {
+                    self.undo_read_batch(&batch)?;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(batch)
+    }
+
+    /// Marks a batch's articles unread again
+    /// Returns how many were reverted; articles deleted since are skipped
+    pub fn undo_read_batch(&self, batch: &ReadBatch) -> Result<usize> {
+        let mut count = 0;
+        for id in &batch.ids {
+            if self.get_article_by_id(id)?.is_some() {
+                self.mark_as_unread(id)?;
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
     /// Toggles the starred status of an article
     /// Updates the YAML frontmatter in the article file
     pub fn toggle_star(&self, item_id: &str) -> Result<()> {
@@ -277,51 +1012,141 @@ starred: false
             .with_context(|| format!("Failed to toggle star for article {}", item_id))
     }
 
-    fn update_article_state(&self, item_id: &str, field: &str, value: &str) -> Result<()> {
-        // Find the article file by reading and matching the ID in frontmatter
-        let mut article_path = None;
-        for entry in fs::read_dir(&self.articles_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.extension().is_some_and(|ext| ext == "md") {
-                if let Ok(content) = fs::read_to_string(&path) {
-                    // Check if this file contains the matching ID in frontmatter
-                    if content
-                        .lines()
-                        .any(|line| line.trim() == format!("id: {}", item_id))
-                    {
-                        article_path = Some(path);
-                        break;
-                    }
-                }
+    /// Adds tags to an article, ignoring ones it already has (case-insensitive)
+    /// Returns the article's tags afterwards
+    pub fn add_tags(&self, item_id: &str, tags: &[String]) -> Result<Vec<String>> {
+        let mut current = self.article_tags(item_id)?;
+        for tag in tags {
+            if !current.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                current.push(tag.clone());
             }
         }
+        self.update_article_state(item_id, "tags", &format_tags(&current))?;
+        Ok(current)
+    }
+
+    /// Removes tags from an article (case-insensitive)
+    /// Returns the article's tags afterwards
+    pub fn remove_tags(&self, item_id: &str, tags: &[String]) -> Result<Vec<String>> {
+        let mut current = self.article_tags(item_id)?;
+        current.retain(|t| !tags.iter().any(|tag| t.eq_ignore_ascii_case(tag)));
+        self.update_article_state(item_id, "tags", &format_tags(&current))?;
+        Ok(current)
+    }
+
+    fn article_tags(&self, item_id: &str) -> Result<Vec<String>> {
+        Ok(self
+            .get_article_by_id(item_id)?
+            .ok_or_else(|| anyhow::anyhow!("Article not found: {}", item_id))?
+            .tags)
+    }
 
-        let article_path =
-            article_path.ok_or_else(|| anyhow::anyhow!("Article not found: {}", item_id))?;
+    /// Replaces the body of a stored article with freshly fetched content
+    /// Frontmatter (including read/starred state) is left untouched
+    pub fn update_article_content(
+        &self,
+        item_id: &str,
+        description: Option<&str>,
+        content: Option<&str>,
+    ) -> Result<()> {
+        let article_path = self.find_article_path(item_id)?;
+        let article = self.parse_article_file(&article_path)?;
 
-        let content = fs::read_to_string(&article_path)
+        let existing = fs::read_to_string(&article_path)
             .with_context(|| format!("Failed to read article {}", item_id))?;
-        let old_line = format!("{}: ", field);
-        let new_line = format!("{}: {}", field, value);
-
-        let updated = content
-            .lines()
-            .map(|line| {
-                if line.starts_with(&old_line) {
-                    new_line.clone()
-                } else {
-                    line.to_string()
-                }
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
+        let parts: Vec<&str> = existing.splitn(3, "---").collect();
+        if parts.len() < 3 {
+            return Err(anyhow::anyhow!(
+                "Invalid article format in {}: expected YAML frontmatter",
+                article_path.display()
+            ));
+        }
+
+        let updated = format!(
+            "---{}---{}",
+            parts[1],
+            self.seal_body(&format!(
+                "\n{}",
+                render_body(&article.title, description, content, &article.link)
+            ))?
+        );
 
         fs::write(&article_path, updated)
             .with_context(|| format!("Failed to update article {}", item_id))?;
+        self.reindex_file(&article_path)
+    }
+
+    /// Finds the file of an article by the ID in its frontmatter
+    fn find_article_path(&self, item_id: &str) -> Result<PathBuf> {
+        validate_id(item_id)?;
+
+        let Some(path) = self.indexed_path(item_id)? else {
+            if self.index.archive_for_id(item_id)?.is_some() {
+                return Err(anyhow::anyhow!(
+                    "Article {} is archived; run `zetrss unarchive {}` first",
+                    item_id,
+                    item_id
+                ));
+            }
+            return Err(anyhow::anyhow!("Article not found: {}", item_id));
+        };
+        self.ensure_in_articles_dir(&path)?;
+        Ok(path)
+    }
+
+    /// Rejects paths that resolve outside the articles directory
+    /// Guards against symlinks and traversal sequences smuggled in via IDs
+    fn ensure_in_articles_dir(&self, path: &Path) -> Result<()> {
+        let articles_dir = self.articles_dir.canonicalize()?;
+        // New files don't exist yet, so resolve their parent directory instead
+        let resolved = if path.exists() {
+            path.canonicalize()?
+        } else {
+            let parent = path
+                .parent()
+                .ok_or_else(|| anyhow::anyhow!("Invalid article path: {}", path.display()))?;
+            parent
+                .canonicalize()?
+                .join(path.file_name().unwrap_or_default())
+        };
+
+        if !resolved.starts_with(&articles_dir) {
+            return Err(anyhow::anyhow!(
+                "Refusing to access {}: outside the articles directory",
+                path.display()
+            ));
+        }
         Ok(())
     }
 
+    fn update_article_state(&self, item_id: &str, field: &str, value: &str) -> Result<()> {
+        let article_path = self.find_article_path(item_id)?;
+        set_frontmatter_field(&article_path, field, value)
+            .with_context(|| format!("Failed to update article {}", item_id))?;
+        self.reindex_file(&article_path)
+    }
+
+    /// Marks every unread article matching the filter as read in a single pass
+    /// Returns the number of articles updated
+    pub fn mark_read_where(&self, filter: &ArticleFilter) -> Result<usize> {
+        let unread = ArticleFilter {
+            read: Some(false),
+            ..filter.clone()
+        };
+        let mut count = 0;
+        for article in self.get_articles(&unread.into())? {
+            if let Some(ref filepath) = article.filepath {
+                let path = Path::new(filepath);
+                self.ensure_in_articles_dir(path)?;
+                set_frontmatter_field(path, "read", "true")
+                    .with_context(|| format!("Failed to mark article {} as read", article.id))?;
+                self.reindex_file(path)?;
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
     pub fn store_feed_list(&self, feeds: Vec<crate::scanner::FeedSource>) -> Result<()> {
         let feeds_file = self.base_dir.join("state").join("feeds.json");
         let json = serde_json::to_string_pretty(&feeds)?;
@@ -329,24 +1154,163 @@ starred: false
         Ok(())
     }
 
+    /// Whether the feed list has the feed, possibly spelled differently
+    pub fn has_feed(&self, url: &str) -> Result<bool> {
+        let key = crate::scanner::feed_key(url);
+        Ok(self
+            .get_feed_list()?
+            .iter()
+            .any(|f| crate::scanner::feed_key(&f.url) == key))
+    }
+
+    /// Adds a feed to the stored feed list
+    /// Returns false if the feed is already present, possibly spelled
+    /// differently (see `scanner::feed_key`)
+    pub fn add_feed(&self, feed: crate::scanner::FeedSource) -> Result<bool> {
+        if self.has_feed(&feed.url)? {
+            return Ok(false);
+        }
+        let mut feeds = self.get_feed_list()?;
+        feeds.push(feed);
+        self.store_feed_list(feeds)?;
+        Ok(true)
+    }
+
+    /// Removes a feed from the stored feed list along with its feed metadata
+    /// Returns the removed entry, or None if the URL wasn't in the list
+    pub fn remove_feed(&self, url: &str) -> Result<Option<crate::scanner::FeedSource>> {
+        let mut feeds = self.get_feed_list()?;
+        let Some(pos) = feeds.iter().position(|f| f.url == url) else {
+            return Ok(None);
+        };
+        let removed = feeds.remove(pos);
+        self.store_feed_list(feeds)?;
+        self.remove_feed_meta(url)?;
+        Ok(Some(removed))
+    }
+
+    /// Deletes a feed's metadata and fetch history, leaving its articles
+    pub fn remove_feed_meta(&self, url: &str) -> Result<()> {
+        for ext in ["json", HISTORY_EXT] {
+            let feed_meta_path = self.feed_meta_path(url, ext);
+            if feed_meta_path.exists() {
+                fs::remove_file(feed_meta_path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes a feed's cached articles except the starred ones
+    /// Returns the number of articles removed
+    pub fn delete_feed_articles(&self, feed_url: &str) -> Result<usize> {
+        let feed = ArticleFilter {
+            feed: Some(feed_url.to_string()),
+            ..Default::default()
+        };
+        let feed_dir = self.feed_dir(feed_url);
+        let (starred, articles): (Vec<FeedItem>, Vec<FeedItem>) = self
+            .index
+            .query(&feed, SortOrder::Newest)?
+            .into_iter()
+            .partition(|article| article.starred);
+        let mut count = 0;
+        for article in articles {
+            if let Some(ref filepath) = article.filepath {
+                let path = Path::new(filepath);
+                if starred.is_empty() && path.starts_with(&feed_dir) {
+                    // The whole directory goes below
+                    self.index.remove(path)?;
+                } else {
+                    self.delete_article_file(&article.id, path)?;
+                }
+                count += 1;
+            }
+        }
+        if starred.is_empty() && feed_dir.exists() {
+            fs::remove_dir_all(&feed_dir)
+                .with_context(|| format!("Failed to delete {}", feed_dir.display()))?;
+        }
+        Ok(count)
+    }
+
+    /// Deletes articles published before the policy's cutoff
+    /// Returns the affected articles; nothing is deleted on a dry run
+    pub fn prune(&self, policy: &PrunePolicy) -> Result<Vec<FeedItem>> {
+        let mut pruned = Vec::new();
+        for article in self
+            .index
+            .query(&ArticleFilter::default(), SortOrder::Newest)?
+        {
+            // Undated articles can't be aged, so they are always kept
+            let Some(published) = article.published else {
+                continue;
+            };
+            if published >= policy.cutoff
+                || (policy.keep_starred && article.starred)
+                || (policy.keep_unread && !article.read)
+            {
+                continue;
+            }
+
+            if !policy.dry_run {
+                if let Some(ref filepath) = article.filepath {
+                    self.delete_article_file(&article.id, Path::new(filepath))?;
+                }
+            }
+            pruned.push(article);
+        }
+        Ok(pruned)
+    }
+
+    /// Deletes a feed's articles beyond its retention limits, keeping starred ones
+    /// Articles are ranked newest first; undated ones count as oldest for
+    /// `max_items` but are never too old for `max_age`. Returns the deleted articles.
+    pub fn apply_retention(&self, feed_url: &str, retention: &Retention) -> Result<Vec<FeedItem>> {
+        let feed = ArticleFilter {
+            feed: Some(feed_url.to_string()),
+            ..Default::default()
+        };
+        // An age reaching past the earliest representable date keeps everything
+        let cutoff = retention
+            .max_age
+            .and_then(|age| Utc::now().checked_sub_signed(age));
+
+        let mut deleted = Vec::new();
+        for (rank, article) in self
+            .index
+            .query(&feed, SortOrder::Newest)?
+            .into_iter()
+            .enumerate()
+        {
+            let too_many = retention.max_items.is_some_and(|max| rank >= max);
+            let too_old = matches!((cutoff, article.published), (Some(c), Some(p)) if p < c);
+            if article.starred || !(too_many || too_old) {
+                continue;
+            }
+            if let Some(ref filepath) = article.filepath {
+                self.delete_article_file(&article.id, Path::new(filepath))?;
+                deleted.push(article);
+            }
+        }
+        Ok(deleted)
+    }
+
+    fn delete_article_file(&self, item_id: &str, path: &Path) -> Result<()> {
+        self.ensure_in_articles_dir(path)?;
+        fs::remove_file(path).with_context(|| format!("Failed to delete article {}", item_id))?;
+        self.parsed().pop(path);
+        for sidecar in [raw_entry_path(path), highlights_path(path)] {
+            if sidecar.exists() {
+                fs::remove_file(sidecar)?;
+            }
+        }
+        self.index.remove(path)
+    }
+
     pub fn get_feed_list(&self) -> Result<Vec<crate::scanner::FeedSource>> {
         let feeds_file = self.base_dir.join("state").join("feeds.json");
 
         if !feeds_file.exists() {
-            // Try legacy feeds.txt for backwards compatibility
-            let legacy_file = self.base_dir.join("state").join("feeds.txt");
-            if legacy_file.exists() {
-                let content = fs::read_to_string(legacy_file)?;
-                return Ok(content
-                    .lines()
-                    .filter(|line| !line.is_empty())
-                    .map(|url| crate::scanner::FeedSource {
-                        url: url.to_string(),
-                        source_file: String::new(),
-                        line_number: 0,
-                    })
-                    .collect());
-            }
             return Ok(Vec::new());
         }
 
@@ -354,6 +1318,402 @@ starred: false
         let feeds: Vec<crate::scanner::FeedSource> = serde_json::from_str(&content)?;
         Ok(feeds)
     }
+
+    /// What the last scan found per note, if a scan has been cached
+    pub fn get_scan_cache(&self) -> Result<Option<crate::scanner::ScanCache>> {
+        let path = self.state_path(crate::scanner::SCAN_CACHE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)?;
+        Ok(Some(serde_json::from_str(&content).with_context(|| {
+            format!("Invalid scan cache {}", path.display())
+        })?))
+    }
+
+    pub fn store_scan_cache(&self, cache: &crate::scanner::ScanCache) -> Result<()> {
+        let path = self.state_path(crate::scanner::SCAN_CACHE_FILE);
+        fs::write(path, serde_json::to_string(cache)?)?;
+        Ok(())
+    }
+
+    /// What `#feedsite` pages announced, from `state/discoveries.json`
+    pub fn get_discoveries(&self) -> Result<Vec<crate::discovery::Discovery>> {
+        let path = self.state_path(crate::discovery::DISCOVERIES_FILE);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&path)?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid discoveries {}", path.display()))
+    }
+
+    pub fn store_discoveries(&self, discoveries: &[crate::discovery::Discovery]) -> Result<()> {
+        let path = self.state_path(crate::discovery::DISCOVERIES_FILE);
+        fs::write(path, serde_json::to_string_pretty(discoveries)?)?;
+        Ok(())
+    }
+}
+
+/// Which cached articles `TextCache::prune` deletes
+#[derive(Debug, Clone)]
+pub struct PrunePolicy {
+    /// Articles published before this moment are pruned
+    pub cutoff: DateTime<Utc>,
+    pub keep_starred: bool,
+    pub keep_unread: bool,
+    /// Report what would be deleted without deleting anything
+    pub dry_run: bool,
+}
+
+/// Articles a bulk mark-read changed: the ones that were unread before
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadBatch {
+    pub ids: Vec<String>,
+}
+
+/// How many of a feed's articles survive each fetch
+/// Starred articles are always kept
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Retention {
+    /// Keep only the newest this many articles
+    pub max_items: Option<usize>,
+    /// Delete articles published longer ago than this
+    pub max_age: Option<chrono::Duration>,
+}
+
+/// Storage backend for feeds and articles
+/// `TextCache` is the production implementation; embedders and tests can
+/// provide their own (e.g. in-memory) store
+pub trait ArticleStore: Send + Sync {
+    /// Returns the number of articles that weren't stored before
+    fn store_feed(&self, feed: &Feed) -> Result<usize>;
+    /// One page of the articles matching the query, in its sort order
+    fn get_articles(&self, query: &ArticleQuery) -> Result<Vec<FeedItem>>;
+    fn get_article_by_id(&self, article_id: &str) -> Result<Option<FeedItem>>;
+    fn mark_as_read(&self, item_id: &str) -> Result<()>;
+    fn toggle_star(&self, item_id: &str) -> Result<()>;
+    fn store_feed_list(&self, feeds: Vec<crate::scanner::FeedSource>) -> Result<()>;
+    fn get_feed_list(&self) -> Result<Vec<crate::scanner::FeedSource>>;
+
+    /// Article counts per feed, ordered by feed URL
+    fn feed_counts(&self) -> Result<Vec<FeedCounts>> {
+        let mut counts: std::collections::BTreeMap<String, FeedCounts> = Default::default();
+        for article in self.get_articles(&ArticleQuery::default())? {
+            let feed = counts
+                .entry(article.feed_url.clone())
+                .or_insert_with(|| FeedCounts {
+                    feed_url: article.feed_url.clone(),
+                    ..Default::default()
+                });
+            feed.total += 1;
+            feed.unread += usize::from(!article.read);
+            feed.starred += usize::from(article.starred);
+        }
+        Ok(counts.into_values().collect())
+    }
+
+    /// Deletes a feed's articles beyond its retention limits
+    /// Returns the number deleted; the default keeps everything
+    fn apply_retention(&self, _feed_url: &str, _retention: &Retention) -> Result<usize> {
+        Ok(0)
+    }
+
+    /// Articles matching the filter, best match first
+    /// The default has no ranking and returns them newest first
+    fn search(&self, filter: &ArticleFilter, limit: Option<usize>) -> Result<Vec<FeedItem>> {
+        self.get_articles(&ArticleQuery {
+            filter: filter.clone(),
+            limit,
+            ..Default::default()
+        })
+    }
+
+    /// Records a fetch attempt; the default keeps no history
+    fn record_fetch(&self, _feed_url: &str, _record: &FetchRecord) -> Result<()> {
+        Ok(())
+    }
+
+    /// Number of malformed articles set aside; the default has none
+    fn quarantined_count(&self) -> Result<usize> {
+        Ok(0)
+    }
+
+    /// What the last scan found per note; the default keeps no cache, so
+    /// every scan reads every note
+    fn get_scan_cache(&self) -> Result<Option<crate::scanner::ScanCache>> {
+        Ok(None)
+    }
+
+    fn store_scan_cache(&self, _cache: &crate::scanner::ScanCache) -> Result<()> {
+        Ok(())
+    }
+
+    /// Feeds found on `#feedsite` pages; stores without them never discover any
+    fn get_discoveries(&self) -> Result<Vec<crate::discovery::Discovery>> {
+        Ok(Vec::new())
+    }
+
+    fn store_discoveries(&self, _discoveries: &[crate::discovery::Discovery]) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ArticleStore for TextCache {
+    fn store_feed(&self, feed: &Feed) -> Result<usize> {
+        TextCache::store_feed(self, feed)
+    }
+
+    fn get_articles(&self, query: &ArticleQuery) -> Result<Vec<FeedItem>> {
+        TextCache::get_articles(self, query)
+    }
+
+    fn get_article_by_id(&self, article_id: &str) -> Result<Option<FeedItem>> {
+        TextCache::get_article_by_id(self, article_id)
+    }
+
+    fn mark_as_read(&self, item_id: &str) -> Result<()> {
+        TextCache::mark_as_read(self, item_id)
+    }
+
+    fn toggle_star(&self, item_id: &str) -> Result<()> {
+        TextCache::toggle_star(self, item_id)
+    }
+
+    fn store_feed_list(&self, feeds: Vec<crate::scanner::FeedSource>) -> Result<()> {
+        TextCache::store_feed_list(self, feeds)
+    }
+
+    fn get_feed_list(&self) -> Result<Vec<crate::scanner::FeedSource>> {
+        TextCache::get_feed_list(self)
+    }
+
+    fn feed_counts(&self) -> Result<Vec<FeedCounts>> {
+        self.index.feed_counts()
+    }
+
+    fn search(&self, filter: &ArticleFilter, limit: Option<usize>) -> Result<Vec<FeedItem>> {
+        TextCache::search(self, filter, limit)
+    }
+
+    fn apply_retention(&self, feed_url: &str, retention: &Retention) -> Result<usize> {
+        Ok(TextCache::apply_retention(self, feed_url, retention)?.len())
+    }
+
+    fn record_fetch(&self, feed_url: &str, record: &FetchRecord) -> Result<()> {
+        TextCache::record_fetch(self, feed_url, record)
+    }
+
+    fn quarantined_count(&self) -> Result<usize> {
+        Ok(self.quarantined()?.len())
+    }
+
+    fn get_scan_cache(&self) -> Result<Option<crate::scanner::ScanCache>> {
+        TextCache::get_scan_cache(self)
+    }
+
+    fn store_scan_cache(&self, cache: &crate::scanner::ScanCache) -> Result<()> {
+        TextCache::store_scan_cache(self, cache)
+    }
+
+    fn get_discoveries(&self) -> Result<Vec<crate::discovery::Discovery>> {
+        TextCache::get_discoveries(self)
+    }
+
+    fn store_discoveries(&self, discoveries: &[crate::discovery::Discovery]) -> Result<()> {
+        TextCache::store_discoveries(self, discoveries)
+    }
+}
+
+/// Data directory used when none is given explicitly
+/// `$ZETRSS_DATA_DIR` (set by the Neovim plugin), else `data_dir` from the config file,
+/// else `$XDG_DATA_HOME/zetrss`, else `~/.local/share/zetrss`
+pub fn default_data_dir() -> PathBuf {
+    if let Ok(nvim_data) = std::env::var("ZETRSS_DATA_DIR") {
+        PathBuf::from(nvim_data)
+    } else if let Some(dir) = crate::config::get().data_dir() {
+        dir
+    } else {
+        xdg_data_dir()
+    }
+}
+
+/// `$XDG_DATA_HOME/zetrss`, else `~/.local/share/zetrss`
+pub fn xdg_data_dir() -> PathBuf {
+    if let Ok(xdg_data) = std::env::var("XDG_DATA_HOME") {
+        PathBuf::from(xdg_data).join("zetrss")
+    } else {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(home).join(".local/share/zetrss")
+    }
+}
+
+/// Strips characters from an article ID that could corrupt the frontmatter
+/// Applied to IDs from feeds before they are stored
+pub fn normalize_id(id: &str) -> String {
+    id.chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Validates an article ID supplied by a feed or a user
+/// IDs may contain slashes (many feeds use URLs), but never traversal segments,
+/// control characters or anything that would break the one-line frontmatter field
+pub fn validate_id(id: &str) -> Result<()> {
+    const MAX_ID_LEN: usize = 1024;
+
+    if id.trim().is_empty() {
+        return Err(anyhow::anyhow!("Article ID must not be empty"));
+    }
+    if id.len() > MAX_ID_LEN {
+        return Err(anyhow::anyhow!(
+            "Article ID is too long ({} bytes, max {})",
+            id.len(),
+            MAX_ID_LEN
+        ));
+    }
+    if id.chars().any(|c| c.is_control()) {
+        return Err(anyhow::anyhow!(
+            "Article ID contains control characters: {:?}",
+            id
+        ));
+    }
+    if id.split(['/', '\\']).any(|segment| segment == "..") {
+        return Err(anyhow::anyhow!(
+            "Article ID contains a path traversal segment: {}",
+            id
+        ));
+    }
+    Ok(())
+}
+
+/// Rewrites a `field: value` line in an article's frontmatter
+/// Lines in the body are never touched, even if they look like the field
+fn set_frontmatter_field(path: &Path, field: &str, value: &str) -> Result<()> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read article file: {}", path.display()))?;
+    let old_line = format!("{}: ", field);
+    let new_line = format!("{}: {}", field, value);
+
+    let mut delimiters = 0;
+    let mut found = false;
+    let mut lines = Vec::new();
+    for line in content.lines() {
+        if line.trim_end() == "---" {
+            delimiters += 1;
+            // Field missing (older files): add it at the end of the frontmatter
+            if delimiters == 2 && !found {
+                lines.push(new_line.clone());
+                found = true;
+            }
+        }
+        if delimiters == 1 && line.starts_with(&old_line) {
+            lines.push(new_line.clone());
+            found = true;
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+
+    fs::write(path, lines.join("\n"))?;
+    Ok(())
+}
+
+/// Formats tags as a YAML flow sequence for the frontmatter
+fn format_tags(tags: &[String]) -> String {
+    let tags: Vec<String> = tags
+        .iter()
+        .map(|t| t.replace([',', '[', ']', '\n'], " ").trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    format!("[{}]", tags.join(", "))
+}
+
+/// Parses a frontmatter tags value, accepting both `[a, b]` and `a, b`
+fn parse_tags(value: &str) -> Vec<String> {
+    value
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Renders the markdown body that follows an article's frontmatter
+fn render_body(
+    title: &str,
+    description: Option<&str>,
+    content: Option<&str>,
+    link: &str,
+) -> String {
+    format!(
+        "\n# {}\n\n{}\n\n{}\n\n[Read original]({})\n",
+        title,
+        description.unwrap_or(""),
+        content.unwrap_or(""),
+        link
+    )
+}
+
+/// Byte offset where an article's body starts, just after the closing `---`
+fn frontmatter_end(markdown: &str) -> Option<usize> {
+    let parts: Vec<&str> = markdown.splitn(3, "---").collect();
+    (parts.len() == 3).then(|| parts[0].len() + parts[1].len() + 6)
+}
+
+/// `<published>-<id>.md`, so files sort by date within a feed directory
+/// Undated items use the time they were first stored
+pub(crate) fn article_file_name(item: &FeedItem) -> String {
+    format!(
+        "{}-{}.md",
+        item.published
+            .unwrap_or_else(Utc::now)
+            .format(FILE_DATE_FORMAT),
+        sanitize_filename(&item.id)
+    )
+}
+
+/// True for names produced by `article_file_name`
+pub(crate) fn is_dated_file_name(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() > 16
+        && bytes[..8].iter().all(u8::is_ascii_digit)
+        && bytes[8] == b'-'
+        && bytes[9..15].iter().all(u8::is_ascii_digit)
+        && bytes[15] == b'-'
+}
+
+/// `<article>.json` next to `<article>.md`
+pub(crate) fn raw_entry_path(article_path: &Path) -> PathBuf {
+    article_path.with_extension("json")
+}
+
+/// `<article>.highlights.json` next to `<article>.md`
+pub(crate) fn highlights_path(article_path: &Path) -> PathBuf {
+    article_path.with_extension("highlights.json")
+}
+
+/// Directory name for a feed's articles: the sanitized URL plus a hash of the
+/// full URL, since sanitizing and truncating can make different feeds collide
+fn feed_dir_name(feed_url: &str) -> String {
+    let readable = feed_url
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+    format!(
+        "{}-{:08x}",
+        sanitize_filename(readable),
+        stable_hash(feed_url) as u32
+    )
+}
+
+/// FNV-1a: stable across Rust versions, unlike `DefaultHasher`
+pub(crate) fn stable_hash(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
 }
 
 fn sanitize_filename(s: &str) -> String {
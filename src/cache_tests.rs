@@ -15,6 +15,7 @@ fn create_test_feed() -> Feed {
         title: "Test Feed".to_string(),
         description: Some("Test Description".to_string()),
         last_fetched: Some(Utc::now()),
+        bytes: 0,
         items: vec![
             FeedItem {
                 id: "test-article-1".to_string(),
@@ -25,9 +26,7 @@ fn create_test_feed() -> Feed {
                 published: Some(Utc::now()),
                 author: Some("Test Author".to_string()),
                 content: Some("Article 1 content".to_string()),
-                read: false,
-                starred: false,
-                filepath: None,
+                ..Default::default()
             },
             FeedItem {
                 id: "test-article-2".to_string(),
@@ -38,9 +37,7 @@ fn create_test_feed() -> Feed {
                 published: Some(Utc::now()),
                 author: Some("Test Author".to_string()),
                 content: Some("Article 2 content".to_string()),
-                read: false,
-                starred: false,
-                filepath: None,
+                ..Default::default()
             },
         ],
     }
@@ -86,6 +83,68 @@ fn test_mark_as_read() {
     assert!(result.is_err());
 }
 
+#[test]
+fn test_mark_as_unread() {
+    let (cache, _temp_dir) = create_test_cache();
+    cache.store_feed(&create_test_feed()).unwrap();
+
+    cache.mark_as_read("test-article-1").unwrap();
+    cache.mark_as_unread("test-article-1").unwrap();
+    let article = cache.get_article_by_id("test-article-1").unwrap().unwrap();
+    assert!(!article.read);
+
+    assert!(cache.mark_as_unread("non-existent").is_err());
+}
+
+#[test]
+fn test_reading_position() {
+    let (cache, _temp_dir) = create_test_cache();
+    assert_eq!(cache.reading_position("a").unwrap(), None);
+
+    cache.set_reading_position("a", Some(42)).unwrap();
+    cache.set_reading_position("b", Some(7)).unwrap();
+    assert_eq!(cache.reading_position("a").unwrap(), Some(42));
+
+    cache.set_reading_position("a", None).unwrap();
+    assert_eq!(cache.reading_position("a").unwrap(), None);
+    assert_eq!(cache.reading_position("b").unwrap(), Some(7));
+}
+
+#[test]
+fn test_read_batch_undo() {
+    let (cache, _temp_dir) = create_test_cache();
+    cache.store_feed(&create_test_feed()).unwrap();
+    cache.mark_as_read("test-article-2").unwrap();
+
+    let ids = vec!["test-article-1".to_string(), "test-article-2".to_string()];
+    let batch = cache.mark_read_batch(&ids).unwrap();
+    // Only the article that was unread belongs to the batch
+    assert_eq!(batch.ids, vec!["test-article-1".to_string()]);
+    assert!(
+        cache
+            .get_article_by_id("test-article-1")
+            .unwrap()
+            .unwrap()
+            .read
+    );
+
+    assert_eq!(cache.undo_read_batch(&batch).unwrap(), 1);
+    assert!(
+        !cache
+            .get_article_by_id("test-article-1")
+            .unwrap()
+            .unwrap()
+            .read
+    );
+    assert!(
+        cache
+            .get_article_by_id("test-article-2")
+            .unwrap()
+            .unwrap()
+            .read
+    );
+}
+
 #[test]
 fn test_toggle_star() {
     let (cache, _temp_dir) = create_test_cache();
@@ -125,19 +184,836 @@ fn test_get_articles_limit() {
             published: Some(Utc::now()),
             author: Some("Test Author".to_string()),
             content: Some(format!("Article {} content", i)),
-            read: false,
-            starred: false,
-            filepath: None,
+            ..Default::default()
         });
     }
 
     cache.store_feed(&feed).unwrap();
 
     // Test with limit
-    let articles = cache.get_articles(Some(5)).unwrap();
+    let articles = cache
+        .get_articles(&ArticleQuery {
+            limit: Some(5),
+            ..Default::default()
+        })
+        .unwrap();
     assert_eq!(articles.len(), 5);
 
     // Test without limit
-    let articles = cache.get_articles(None).unwrap();
+    let articles = cache.get_articles(&ArticleQuery::default()).unwrap();
     assert_eq!(articles.len(), 9);
 }
+
+#[test]
+fn test_update_article_content() {
+    let (cache, _temp_dir) = create_test_cache();
+    let feed = create_test_feed();
+
+    cache.store_feed(&feed).unwrap();
+    cache.mark_as_read("test-article-1").unwrap();
+
+    cache
+        .update_article_content("test-article-1", None, Some("Fixed markup"))
+        .unwrap();
+
+    let article = cache.get_article_by_id("test-article-1").unwrap().unwrap();
+    let body = article.content.unwrap();
+    assert!(body.contains("Fixed markup"));
+    assert!(!body.contains("Article 1 content"));
+    // State in frontmatter survives the refresh
+    assert!(article.read);
+    assert_eq!(article.title, "Test Article 1");
+}
+
+#[test]
+fn test_search_articles() {
+    let (cache, _temp_dir) = create_test_cache();
+    let feed = create_test_feed();
+
+    cache.store_feed(&feed).unwrap();
+
+    // Full text matches body content, case-insensitively
+    let results = cache.search_articles("ARTICLE 2 CONTENT", false).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, "test-article-2");
+
+    // Title-only ignores body matches
+    let results = cache.search_articles("article 2 content", true).unwrap();
+    assert!(results.is_empty());
+
+    let results = cache.search_articles("test article", true).unwrap();
+    assert_eq!(results.len(), 2);
+}
+
+#[test]
+fn test_validate_id() {
+    assert!(validate_id("test-article-1").is_ok());
+    assert!(validate_id("https://example.com/2020/newsletter").is_ok());
+    assert!(validate_id("tag:example.com,2024:post-1").is_ok());
+
+    assert!(validate_id("").is_err());
+    assert!(validate_id("   ").is_err());
+    assert!(validate_id("../state/feeds").is_err());
+    assert!(validate_id("a/../../etc/passwd").is_err());
+    assert!(validate_id("..\\state").is_err());
+    assert!(validate_id("id\nread: true").is_err());
+    assert!(validate_id(&"x".repeat(2000)).is_err());
+}
+
+#[test]
+fn test_traversal_ids_rejected_by_cache() {
+    let (cache, _temp_dir) = create_test_cache();
+    let feed = create_test_feed();
+    cache.store_feed(&feed).unwrap();
+
+    assert!(cache.get_article_by_id("../state/feeds").is_err());
+    assert!(cache.mark_as_read("../state/feeds").is_err());
+    assert!(cache.toggle_star("../../etc/passwd").is_err());
+}
+
+#[test]
+fn test_store_feed_normalizes_ids() {
+    let (cache, _temp_dir) = create_test_cache();
+    let mut feed = create_test_feed();
+    feed.items[0].id = "injected\nread: true".to_string();
+    feed.items[1].id = "../escape".to_string();
+
+    cache.store_feed(&feed).unwrap();
+
+    // Control characters are neutralized instead of reaching the frontmatter
+    let article = cache
+        .get_article_by_id("injected read: true")
+        .unwrap()
+        .unwrap();
+    assert!(!article.read);
+
+    // Traversal IDs are skipped entirely
+    let articles = cache.get_articles(&ArticleQuery::default()).unwrap();
+    assert_eq!(articles.len(), 1);
+}
+
+#[test]
+fn test_add_and_remove_feed() {
+    let (cache, _temp_dir) = create_test_cache();
+    let source = crate::scanner::FeedSource {
+        url: "https://example.com/feed".to_string(),
+        source_file: String::new(),
+        line_number: 0,
+        root: None,
+        options: Default::default(),
+    };
+
+    assert!(cache.add_feed(source.clone()).unwrap());
+    // Duplicates are ignored, however they're spelled
+    let respelled = crate::scanner::FeedSource {
+        url: "http://www.example.com/feed/".to_string(),
+        ..source.clone()
+    };
+    assert!(!cache.add_feed(respelled).unwrap());
+    assert!(!cache.add_feed(source).unwrap());
+    assert_eq!(cache.get_feed_list().unwrap().len(), 1);
+
+    cache.store_feed(&create_test_feed()).unwrap();
+
+    let removed = cache.remove_feed("https://example.com/feed").unwrap();
+    assert!(removed.is_some());
+    assert!(cache.get_feed_list().unwrap().is_empty());
+    assert!(cache
+        .remove_feed("https://example.com/feed")
+        .unwrap()
+        .is_none());
+
+    // Articles stay until explicitly deleted
+    assert_eq!(
+        cache.get_articles(&ArticleQuery::default()).unwrap().len(),
+        2
+    );
+    let deleted = cache
+        .delete_feed_articles("https://example.com/feed")
+        .unwrap();
+    assert_eq!(deleted, 2);
+    assert!(cache
+        .get_articles(&ArticleQuery::default())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_prune() {
+    let (cache, _temp_dir) = create_test_cache();
+    let mut feed = create_test_feed();
+    let old = Utc::now() - chrono::Duration::days(60);
+    feed.items[0].published = Some(old);
+    feed.items[1].published = Some(old);
+    feed.items[1].id = "test-article-old-starred".to_string();
+    cache.store_feed(&feed).unwrap();
+    cache.toggle_star("test-article-old-starred").unwrap();
+
+    let mut policy = PrunePolicy {
+        cutoff: Utc::now() - chrono::Duration::days(30),
+        keep_starred: true,
+        keep_unread: false,
+        dry_run: true,
+    };
+
+    // Dry run reports but keeps files
+    let pruned = cache.prune(&policy).unwrap();
+    assert_eq!(pruned.len(), 1);
+    assert_eq!(pruned[0].id, "test-article-1");
+    assert_eq!(
+        cache.get_articles(&ArticleQuery::default()).unwrap().len(),
+        2
+    );
+
+    // Unread articles can be protected too
+    policy.keep_unread = true;
+    assert!(cache.prune(&policy).unwrap().is_empty());
+
+    policy.keep_unread = false;
+    policy.dry_run = false;
+    cache.prune(&policy).unwrap();
+    let remaining = cache.get_articles(&ArticleQuery::default()).unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].id, "test-article-old-starred");
+}
+
+#[test]
+fn test_mark_read_where() {
+    let (cache, _temp_dir) = create_test_cache();
+    let mut feed = create_test_feed();
+    feed.items[1].feed_url = "https://other.com/feed".to_string();
+    feed.items[1].content = Some("read: false".to_string());
+    cache.store_feed(&feed).unwrap();
+
+    let filter = crate::filter::ArticleFilter {
+        feed: Some("https://other.com/feed".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(cache.mark_read_where(&filter).unwrap(), 1);
+    // Already-read articles aren't counted again
+    assert_eq!(cache.mark_read_where(&filter).unwrap(), 0);
+
+    let article = cache.get_article_by_id("test-article-2").unwrap().unwrap();
+    assert!(article.read);
+    // Body lines that look like frontmatter are left alone
+    assert!(article.content.unwrap().contains("read: false"));
+    assert!(
+        !cache
+            .get_article_by_id("test-article-1")
+            .unwrap()
+            .unwrap()
+            .read
+    );
+
+    let all = crate::filter::ArticleFilter::default();
+    assert_eq!(cache.mark_read_where(&all).unwrap(), 1);
+}
+
+#[test]
+fn test_add_and_remove_tags() {
+    let (cache, _temp_dir) = create_test_cache();
+    cache.store_feed(&create_test_feed()).unwrap();
+
+    let tags = cache
+        .add_tags("test-article-1", &["rust".to_string(), "async".to_string()])
+        .unwrap();
+    assert_eq!(tags, vec!["rust", "async"]);
+    // Existing tags aren't duplicated
+    let tags = cache
+        .add_tags("test-article-1", &["Rust".to_string()])
+        .unwrap();
+    assert_eq!(tags.len(), 2);
+
+    let tags = cache
+        .remove_tags("test-article-1", &["RUST".to_string()])
+        .unwrap();
+    assert_eq!(tags, vec!["async"]);
+    let article = cache.get_article_by_id("test-article-1").unwrap().unwrap();
+    assert_eq!(article.tags, vec!["async"]);
+    assert!(cache.add_tags("missing", &["x".to_string()]).is_err());
+}
+
+#[test]
+fn test_tags_added_to_files_without_tags_field() {
+    let (cache, temp_dir) = create_test_cache();
+    let path = temp_dir.path().join("articles/legacy.md");
+    fs::write(
+        &path,
+        "---\nid: legacy\nfeed: https://example.com/feed\ntitle: Legacy\nlink: https://example.com/legacy\nread: false\nstarred: false\n---\n\nBody\n",
+    )
+    .unwrap();
+
+    cache.add_tags("legacy", &["old".to_string()]).unwrap();
+    let article = cache.get_article_by_id("legacy").unwrap().unwrap();
+    assert_eq!(article.tags, vec!["old"]);
+    assert_eq!(article.title, "Legacy");
+}
+
+#[test]
+fn test_index_follows_external_edits() {
+    let (cache, temp_dir) = create_test_cache();
+    cache.store_feed(&create_test_feed()).unwrap();
+    let unread = ArticleFilter {
+        read: Some(false),
+        ..Default::default()
+    };
+    assert_eq!(cache.get_articles(&unread.clone().into()).unwrap().len(), 2);
+
+    // Edit a file behind the cache's back (e.g. from the editor), then reopen
+    let path = cache
+        .get_article_by_id("test-article-1")
+        .unwrap()
+        .unwrap()
+        .filepath
+        .unwrap();
+    let content = fs::read_to_string(&path).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    fs::write(&path, content.replace("read: false", "read: true")).unwrap();
+    fs::remove_file(
+        cache
+            .get_article_by_id("test-article-2")
+            .unwrap()
+            .unwrap()
+            .filepath
+            .unwrap(),
+    )
+    .unwrap();
+
+    let reopened = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    assert!(reopened.get_articles(&unread.into()).unwrap().is_empty());
+    assert_eq!(
+        reopened
+            .get_articles(&ArticleQuery::default())
+            .unwrap()
+            .len(),
+        1
+    );
+    assert!(reopened
+        .get_article_by_id("test-article-2")
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_search_applies_metadata_filters() {
+    let (cache, _temp_dir) = create_test_cache();
+    cache.store_feed(&create_test_feed()).unwrap();
+    cache.mark_as_read("test-article-1").unwrap();
+
+    let filter = ArticleFilter {
+        text_terms: vec!["content".to_string()],
+        read: Some(false),
+        ..Default::default()
+    };
+    let results = cache.search(&filter, None).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, "test-article-2");
+
+    // Without search terms it lists like query_articles
+    let results = cache.search(&ArticleFilter::default(), Some(1)).unwrap();
+    assert_eq!(results.len(), 1);
+}
+
+#[test]
+fn test_raw_entries_stored_next_to_articles() {
+    let (cache, temp_dir) = create_test_cache();
+    let mut feed = create_test_feed();
+    let raw = crate::models::RawEntry {
+        id: "test-article-1".to_string(),
+        title: Some("Test Article 1".to_string()),
+        media: vec![crate::models::RawMedia {
+            content: vec![crate::models::RawMediaContent {
+                url: Some("https://example.com/episode.mp3".to_string()),
+                content_type: Some("audio/mpeg".to_string()),
+                size: Some(1234),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    feed.items[0].raw = Some(raw.clone());
+    cache.store_feed(&feed).unwrap();
+
+    assert_eq!(cache.get_raw_entry("test-article-1").unwrap(), Some(raw));
+    assert_eq!(cache.get_raw_entry("test-article-2").unwrap(), None);
+    // Raw entries don't show up as articles
+    assert_eq!(
+        cache.get_articles(&ArticleQuery::default()).unwrap().len(),
+        2
+    );
+
+    // A later fetch fills in entries for articles stored without one
+    let raw2 = crate::models::RawEntry {
+        id: "test-article-2".to_string(),
+        ..Default::default()
+    };
+    feed.items[1].raw = Some(raw2.clone());
+    cache.store_feed(&feed).unwrap();
+    assert_eq!(cache.get_raw_entry("test-article-2").unwrap(), Some(raw2));
+
+    cache
+        .delete_feed_articles("https://example.com/feed")
+        .unwrap();
+    let leftover = fs::read_dir(temp_dir.path().join("articles"))
+        .unwrap()
+        .count();
+    assert_eq!(leftover, 0);
+}
+
+#[test]
+fn test_archive_and_unarchive() {
+    let (cache, temp_dir) = create_test_cache();
+    let mut feed = create_test_feed();
+    feed.items[0].published = Some(Utc::now() - chrono::Duration::days(200));
+    feed.items[0].raw = Some(crate::models::RawEntry {
+        id: "test-article-1".to_string(),
+        ..Default::default()
+    });
+    cache.store_feed(&feed).unwrap();
+    let cutoff = Utc::now() - chrono::Duration::days(90);
+
+    let preview = cache.archive(cutoff, true).unwrap();
+    assert_eq!(preview.len(), 1);
+    assert_eq!(
+        cache.get_articles(&ArticleQuery::default()).unwrap().len(),
+        2
+    );
+
+    let archived = cache.archive(cutoff, false).unwrap();
+    assert_eq!(archived[0].id, "test-article-1");
+    let hot: Vec<_> = cache
+        .get_articles(&ArticleQuery::default())
+        .unwrap()
+        .into_iter()
+        .map(|a| a.id)
+        .collect();
+    assert_eq!(hot, vec!["test-article-2"]);
+
+    // Still searchable and readable, but not editable
+    let results = cache.search_articles("article 1 content", false).unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(results[0].filepath.is_none());
+    let article = cache.get_article_by_id("test-article-1").unwrap().unwrap();
+    assert_eq!(article.title, "Test Article 1");
+    let err = cache.mark_as_read("test-article-1").unwrap_err();
+    assert!(format!("{:#}", err).contains("archived"));
+
+    // Refetching doesn't bring it back
+    cache.store_feed(&feed).unwrap();
+    assert_eq!(
+        cache.get_articles(&ArticleQuery::default()).unwrap().len(),
+        1
+    );
+
+    // The index can be rebuilt from the bundles
+    drop(cache);
+    fs::remove_file(temp_dir.path().join("index.sqlite")).unwrap();
+    let cache = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    assert_eq!(cache.search_articles("article 1", false).unwrap().len(), 1);
+
+    assert!(cache.unarchive(&["test-article-2".to_string()]).is_err());
+    assert_eq!(cache.unarchive(&["test-article-1".to_string()]).unwrap(), 1);
+    assert_eq!(
+        cache.get_articles(&ArticleQuery::default()).unwrap().len(),
+        2
+    );
+    assert!(cache.get_raw_entry("test-article-1").unwrap().is_some());
+    cache.mark_as_read("test-article-1").unwrap();
+    let bundles = fs::read_dir(temp_dir.path().join("archive"))
+        .unwrap()
+        .count();
+    assert_eq!(bundles, 0);
+}
+
+#[test]
+fn test_articles_stored_per_feed() {
+    let (cache, temp_dir) = create_test_cache();
+    cache.store_feed(&create_test_feed()).unwrap();
+    let mut other = create_test_feed();
+    other.url = "https://other.example/rss".to_string();
+    for item in &mut other.items {
+        item.feed_url = other.url.clone();
+        item.id = format!("other-{}", item.id);
+    }
+    cache.store_feed(&other).unwrap();
+
+    let articles = temp_dir.path().join("articles");
+    let feed_dir = articles.join(feed_dir_name("https://example.com/feed"));
+    assert_eq!(fs::read_dir(&feed_dir).unwrap().count(), 2);
+    let article = cache.get_article_by_id("test-article-1").unwrap().unwrap();
+    assert!(Path::new(article.filepath.as_deref().unwrap()).starts_with(&feed_dir));
+
+    // Deleting a feed's articles removes its directory and leaves the others
+    assert_eq!(
+        cache
+            .delete_feed_articles("https://example.com/feed")
+            .unwrap(),
+        2
+    );
+    assert!(!feed_dir.exists());
+    assert!(cache.get_article_by_id("test-article-1").unwrap().is_none());
+    assert_eq!(
+        cache.get_articles(&ArticleQuery::default()).unwrap().len(),
+        2
+    );
+}
+
+#[test]
+fn test_feed_dir_names_distinct() {
+    let base = "https://example.com/a/very/long/path/that/goes/on/and/on/feed";
+    let a = feed_dir_name(&format!("{}/one", base));
+    let b = feed_dir_name(&format!("{}/two", base));
+    assert_ne!(a, b);
+    assert!(a.starts_with("example_com_a_very"));
+}
+
+#[test]
+fn test_parsed_articles_cached_until_file_changes() {
+    let (cache, _temp_dir) = create_test_cache();
+    cache.store_feed(&create_test_feed()).unwrap();
+    let article = cache.get_article_by_id("test-article-1").unwrap().unwrap();
+    let path = PathBuf::from(article.filepath.unwrap());
+
+    // Same size and mtime: the parsed copy is reused without reading the file
+    let original = fs::read_to_string(&path).unwrap();
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+    let edited = original.replace("Test Article 1", "Test Article X");
+    fs::write(&path, &edited).unwrap();
+    fs::File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    let article = cache.get_article_by_id("test-article-1").unwrap().unwrap();
+    assert_eq!(article.title, "Test Article 1");
+
+    // Any other change is picked up
+    fs::write(&path, edited.replace("Test Article X", "Retitled")).unwrap();
+    let article = cache.get_article_by_id("test-article-1").unwrap().unwrap();
+    assert_eq!(article.title, "Retitled");
+
+    // Our own writes invalidate the entry
+    cache.mark_as_read("test-article-1").unwrap();
+    assert!(
+        cache
+            .get_article_by_id("test-article-1")
+            .unwrap()
+            .unwrap()
+            .read
+    );
+}
+
+#[test]
+fn test_apply_retention() {
+    let (cache, _temp_dir) = create_test_cache();
+    let mut feed = create_test_feed();
+    let template = feed.items[0].clone();
+    feed.items = [1, 10, 20, 30]
+        .iter()
+        .enumerate()
+        .map(|(i, days)| FeedItem {
+            id: format!("aged-{}", i),
+            published: Some(Utc::now() - chrono::Duration::days(*days)),
+            ..template.clone()
+        })
+        .collect();
+    cache.store_feed(&feed).unwrap();
+    cache.toggle_star("aged-3").unwrap();
+
+    let ids = |cache: &TextCache| -> Vec<String> {
+        let mut ids: Vec<_> = cache
+            .get_articles(&ArticleQuery::default())
+            .unwrap()
+            .into_iter()
+            .map(|a| a.id)
+            .collect();
+        ids.sort();
+        ids
+    };
+
+    // Too old, but starred
+    let by_age = Retention {
+        max_items: None,
+        max_age: Some(chrono::Duration::days(15)),
+    };
+    let deleted = cache.apply_retention(&feed.url, &by_age).unwrap();
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0].id, "aged-2");
+    assert_eq!(ids(&cache), vec!["aged-0", "aged-1", "aged-3"]);
+
+    let by_count = Retention {
+        max_items: Some(1),
+        max_age: None,
+    };
+    assert_eq!(
+        cache.apply_retention(&feed.url, &by_count).unwrap().len(),
+        1
+    );
+    assert_eq!(ids(&cache), vec!["aged-0", "aged-3"]);
+
+    // Other feeds are untouched
+    assert!(cache
+        .apply_retention("https://other.example/feed", &by_count)
+        .unwrap()
+        .is_empty());
+
+    // An age too long to count back from now deletes nothing
+    let forever = Retention {
+        max_items: None,
+        max_age: Some(chrono::Duration::MAX),
+    };
+    assert!(cache
+        .apply_retention(&feed.url, &forever)
+        .unwrap()
+        .is_empty());
+    assert_eq!(ids(&cache), vec!["aged-0", "aged-3"]);
+}
+
+#[test]
+fn test_remove_feed_deletes_fetch_history() {
+    let (cache, _temp_dir) = create_test_cache();
+    let feed = create_test_feed();
+    cache
+        .add_feed(crate::scanner::FeedSource {
+            url: feed.url.clone(),
+            source_file: String::new(),
+            line_number: 0,
+            root: None,
+            options: Default::default(),
+        })
+        .unwrap();
+    assert_eq!(cache.store_feed(&feed).unwrap(), feed.items.len());
+    assert_eq!(cache.store_feed(&feed).unwrap(), 0);
+
+    let record = crate::history::FetchRecord {
+        time: Utc::now(),
+        status: crate::history::FetchStatus::Ok,
+        items: feed.items.len(),
+        added: feed.items.len(),
+        duration_ms: 5,
+        bytes: 100,
+        error: None,
+    };
+    cache.record_fetch(&feed.url, &record).unwrap();
+    assert_eq!(cache.fetch_history(&feed.url).unwrap(), vec![record]);
+
+    cache.remove_feed(&feed.url).unwrap();
+    assert!(cache.fetch_history(&feed.url).unwrap().is_empty());
+}
+
+#[test]
+fn test_get_articles_pages_through_the_index() {
+    let (cache, _temp_dir) = create_test_cache();
+    let mut feed = create_test_feed();
+    let template = feed.items[0].clone();
+    feed.items = (0..5)
+        .map(|i| FeedItem {
+            id: format!("paged-{}", i),
+            title: format!("Paged {}", i),
+            published: Some(Utc::now() - chrono::Duration::hours(i)),
+            ..template.clone()
+        })
+        .collect();
+    cache.store_feed(&feed).unwrap();
+    cache.mark_as_read("paged-1").unwrap();
+
+    let ids = |query: ArticleQuery| -> Vec<String> {
+        cache
+            .get_articles(&query)
+            .unwrap()
+            .into_iter()
+            .map(|a| a.id)
+            .collect()
+    };
+
+    let page = ArticleQuery {
+        offset: 1,
+        limit: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(ids(page.clone()), vec!["paged-1", "paged-2"]);
+
+    let oldest_unread = ArticleQuery {
+        filter: ArticleFilter {
+            read: Some(false),
+            ..Default::default()
+        },
+        sort: SortOrder::Oldest,
+        ..page.clone()
+    };
+    assert_eq!(ids(oldest_unread), vec!["paged-3", "paged-2"]);
+
+    // Text terms are checked against the files, after the index
+    let text = ArticleQuery {
+        filter: ArticleFilter {
+            text_terms: vec!["paged".to_string()],
+            ..Default::default()
+        },
+        ..page
+    };
+    assert_eq!(ids(text), vec!["paged-1", "paged-2"]);
+}
+
+#[test]
+fn test_rebuild_index_recovers_from_drift() {
+    let (cache, _temp_dir) = create_test_cache();
+    cache.store_feed(&create_test_feed()).unwrap();
+    let mut article = cache.get_article_by_id("test-article-1").unwrap().unwrap();
+    let path = PathBuf::from(article.filepath.clone().unwrap());
+    let unread: ArticleQuery = ArticleFilter {
+        read: Some(false),
+        ..Default::default()
+    }
+    .into();
+
+    // A stale row with the file's own mtime is invisible to a sync
+    article.read = true;
+    cache
+        .index
+        .upsert(&article, index::file_mtime(&path).unwrap())
+        .unwrap();
+    cache.sync_index().unwrap();
+    assert_eq!(cache.get_articles(&unread).unwrap().len(), 1);
+
+    assert_eq!(cache.rebuild_index().unwrap(), 2);
+    assert_eq!(cache.get_articles(&unread).unwrap().len(), 2);
+    assert_eq!(cache.search_articles("test", true).unwrap().len(), 2);
+}
+
+#[test]
+fn test_encrypted_cache_round_trip() {
+    let (cache, temp_dir) = create_test_cache();
+    let mut feed = create_test_feed();
+    feed.items[0].raw = Some(RawEntry {
+        id: "test-article-1".to_string(),
+        ..Default::default()
+    });
+    cache.store_feed(&feed).unwrap();
+    let path = cache
+        .get_article_by_id("test-article-1")
+        .unwrap()
+        .unwrap()
+        .filepath
+        .unwrap();
+    let plain = cache.get_article_by_id("test-article-1").unwrap().unwrap();
+
+    assert_eq!(cache.encrypt_all("secret").unwrap(), 2);
+    let on_disk = fs::read_to_string(&path).unwrap();
+    assert!(on_disk.contains("title: Test Article 1"));
+    assert!(!on_disk.contains("Article 1 content"));
+    assert!(!fs::read_to_string(raw_entry_path(Path::new(&path)))
+        .unwrap()
+        .contains("test-article-1"));
+    // Nor do the bodies linger in the index's free pages or its WAL
+    for name in ["index.sqlite", "index.sqlite-wal"] {
+        let bytes = fs::read(temp_dir.path().join(name)).unwrap_or_default();
+        assert!(
+            !String::from_utf8_lossy(&bytes).contains("Article 1 content"),
+            "{}",
+            name
+        );
+    }
+
+    // Bodies read back unchanged; only titles stay searchable
+    let article = cache.get_article_by_id("test-article-1").unwrap().unwrap();
+    assert_eq!(article.content, plain.content);
+    assert_eq!(
+        cache.get_raw_entry("test-article-1").unwrap().unwrap().id,
+        "test-article-1"
+    );
+    assert!(cache.search_articles("content", false).unwrap().is_empty());
+    assert_eq!(cache.search_articles("Article 1", true).unwrap().len(), 1);
+
+    // New articles are sealed as they are stored
+    let mut later = create_test_feed();
+    later.items.truncate(1);
+    later.items[0].id = "test-article-3".to_string();
+    later.items[0].content = Some("Third content".to_string());
+    cache.store_feed(&later).unwrap();
+
+    // Another process needs the passphrase
+    let reopened = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    assert!(reopened.is_encrypted());
+    assert!(reopened.unlock("wrong").is_err());
+    reopened.unlock("secret").unwrap();
+    let third = reopened
+        .get_article_by_id("test-article-3")
+        .unwrap()
+        .unwrap();
+    assert!(third.content.unwrap().contains("Third content"));
+
+    assert_eq!(reopened.decrypt_all().unwrap(), 3);
+    assert!(!reopened.is_encrypted());
+    assert!(fs::read_to_string(&path)
+        .unwrap()
+        .contains("Article 1 content"));
+    assert_eq!(reopened.search_articles("content", false).unwrap().len(), 3);
+}
+
+#[test]
+fn test_settled_malformed_files_quarantined() {
+    let (cache, temp_dir) = create_test_cache();
+    cache.store_feed(&create_test_feed()).unwrap();
+    let articles = temp_dir.path().join("articles");
+    fs::write(articles.join("fresh.md"), "no frontmatter").unwrap();
+    let stale = articles.join("stale.md");
+    fs::write(&stale, "---\ntitle: No ID\n---\nBody").unwrap();
+    fs::File::options()
+        .write(true)
+        .open(&stale)
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() - 2 * QUARANTINE_GRACE)
+        .unwrap();
+
+    cache.sync_index().unwrap();
+    // A file that just changed may still be being written
+    assert!(articles.join("fresh.md").exists());
+    assert!(!stale.exists());
+    assert!(temp_dir.path().join("quarantine/stale.md").exists());
+
+    let quarantined = cache.quarantined().unwrap();
+    assert_eq!(quarantined.len(), 1);
+    assert_eq!(quarantined[0].reason, "missing id in frontmatter");
+    assert_eq!(ArticleStore::quarantined_count(&cache).unwrap(), 1);
+    assert_eq!(
+        cache.get_articles(&ArticleQuery::default()).unwrap().len(),
+        2
+    );
+}
+
+#[test]
+fn test_highlights_stored_next_to_articles() {
+    let (cache, _temp_dir) = create_test_cache();
+    let mut feed = create_test_feed();
+    feed.items[0].published = Some(Utc::now() - chrono::Duration::days(200));
+    cache.store_feed(&feed).unwrap();
+
+    assert!(cache.get_highlights("test-article-1").unwrap().is_empty());
+    assert_eq!(cache.add_highlight("test-article-1", "First.").unwrap(), 1);
+    assert_eq!(
+        cache
+            .add_highlight("test-article-1", "Second\nline.")
+            .unwrap(),
+        2
+    );
+    let article = cache.get_article_by_id("test-article-1").unwrap().unwrap();
+    let path = Path::new(article.filepath.as_ref().unwrap()).with_extension("highlights.json");
+    assert!(path.exists());
+    let texts: Vec<String> = cache
+        .get_highlights("test-article-1")
+        .unwrap()
+        .into_iter()
+        .map(|h| h.text)
+        .collect();
+    assert_eq!(texts, vec!["First.", "Second\nline."]);
+
+    // Highlights travel with the article into the archive and back
+    cache
+        .archive(Utc::now() - chrono::Duration::days(90), false)
+        .unwrap();
+    assert!(!path.exists());
+    cache.unarchive(&["test-article-1".to_string()]).unwrap();
+    assert_eq!(cache.get_highlights("test-article-1").unwrap().len(), 2);
+}
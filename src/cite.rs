@@ -0,0 +1,183 @@
+//! Citations of articles, for zettelkastens that feed academic writing
+//!
+//! `zetrss cite` prints BibTeX (biblatex `@online`) or CSL-JSON entries with
+//! the author, title, outlet (the feed's title), URL, publication and access
+//! dates. Notes can carry the same citation key (`[notes] citekey`).
+
+use crate::models::FeedItem;
+use chrono::{DateTime, Datelike, Local, Utc};
+use serde_json::json;
+
+#[cfg(test)]
+#[path = "cite_tests.rs"]
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CiteFormat {
+    Bibtex,
+    CslJson,
+}
+
+/// What a citation of an article says
+#[derive(Debug, Clone, PartialEq)]
+pub struct Citation {
+    pub key: String,
+    pub author: Option<String>,
+    pub title: String,
+    /// The feed's title, else its host
+    pub outlet: String,
+    pub url: String,
+    pub published: Option<DateTime<Utc>>,
+    pub accessed: DateTime<Local>,
+}
+
+impl Citation {
+    pub fn new(article: &FeedItem, outlet: &str, accessed: DateTime<Local>) -> Self {
+        Self {
+            key: citation_key(article),
+            author: article.author.clone().filter(|a| !a.trim().is_empty()),
+            title: article.title.clone(),
+            outlet: outlet.to_string(),
+            url: article.link.clone(),
+            published: article.published,
+            accessed,
+        }
+    }
+}
+
+/// `<author's last name, else the link's host><year><first title word>`,
+/// lowercase ASCII, e.g. `doe2024async`
+pub fn citation_key(article: &FeedItem) -> String {
+    let author = article
+        .author
+        .as_deref()
+        .and_then(|author| {
+            // "Jane Doe", "Doe, Jane" and "jane@example.com (Jane Doe)"
+            let name = match author.split_once('(') {
+                Some((_, rest)) => rest.trim_end_matches(')'),
+                None => author,
+            };
+            match name.split_once(',') {
+                Some((last, _)) => Some(last),
+                None => name.split_whitespace().last(),
+            }
+        })
+        .filter(|name| !name.contains('@'))
+        .map(ascii_word)
+        .filter(|word| !word.is_empty())
+        .unwrap_or_else(|| {
+            let host = reqwest::Url::parse(&article.link)
+                .ok()
+                .and_then(|url| url.host_str().map(String::from))
+                .unwrap_or_default();
+            let host = host.trim_start_matches("www.");
+            ascii_word(host.split('.').next().unwrap_or(""))
+        });
+    let year = article
+        .published
+        .map(|d| d.year().to_string())
+        .unwrap_or_default();
+    let word = article
+        .title
+        .split_whitespace()
+        .map(ascii_word)
+        .find(|word| !word.is_empty() && !is_stop_word(word))
+        .unwrap_or_default();
+    let key = format!("{}{}{}", author, year, word);
+    if key.is_empty() {
+        "untitled".to_string()
+    } else {
+        key
+    }
+}
+
+fn ascii_word(text: &str) -> String {
+    text.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn is_stop_word(word: &str) -> bool {
+    matches!(word, "a" | "an" | "the" | "on" | "of" | "in" | "to" | "and")
+}
+
+/// Gives citations sharing a key the suffixes `a`, `b`, ... in order
+pub fn disambiguate(citations: &mut [Citation]) {
+    let keys: Vec<String> = citations.iter().map(|c| c.key.clone()).collect();
+    let mut seen: std::collections::HashMap<String, u8> = std::collections::HashMap::new();
+    for citation in citations.iter_mut() {
+        if keys.iter().filter(|k| **k == citation.key).count() < 2 {
+            continue;
+        }
+        let n = seen.entry(citation.key.clone()).or_default();
+        let suffix = (b'a' + *n % 26) as char;
+        *n += 1;
+        citation.key.push(suffix);
+    }
+}
+
+/// A biblatex `@online` entry
+pub fn bibtex(citation: &Citation) -> String {
+    let mut fields = Vec::new();
+    if let Some(author) = &citation.author {
+        fields.push(("author", bibtex_escape(author)));
+    }
+    fields.push(("title", bibtex_escape(&citation.title)));
+    fields.push(("organization", bibtex_escape(&citation.outlet)));
+    if let Some(published) = citation.published {
+        fields.push(("date", published.format("%Y-%m-%d").to_string()));
+    }
+    fields.push(("url", citation.url.clone()));
+    fields.push(("urldate", citation.accessed.format("%Y-%m-%d").to_string()));
+
+    let mut entry = format!("@online{{{},\n", citation.key);
+    for (name, value) in fields {
+        entry.push_str(&format!("  {} = {{{}}},\n", name, value));
+    }
+    entry.push_str("}\n");
+    entry
+}
+
+/// Escapes the characters LaTeX treats specially
+fn bibtex_escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A CSL-JSON item (`post-weblog`)
+pub fn csl_json(citation: &Citation) -> serde_json::Value {
+    let date_parts =
+        |year: i32, month: u32, day: u32| json!({ "date-parts": [[year, month, day]] });
+    let mut item = json!({
+        "id": citation.key,
+        "type": "post-weblog",
+        "title": citation.title,
+        "container-title": citation.outlet,
+        "URL": citation.url,
+        "accessed": date_parts(
+            citation.accessed.year(),
+            citation.accessed.month(),
+            citation.accessed.day()
+        ),
+    });
+    if let Some(author) = &citation.author {
+        item["author"] = json!([{ "literal": author }]);
+    }
+    if let Some(published) = citation.published {
+        item["issued"] = date_parts(published.year(), published.month(), published.day());
+    }
+    item
+}
@@ -0,0 +1,71 @@
+use super::*;
+use chrono::TimeZone;
+
+fn article() -> FeedItem {
+    FeedItem {
+        id: "post-1".to_string(),
+        feed_url: "https://example.com/feed".to_string(),
+        title: "The Rust & C++ 100% Guide".to_string(),
+        link: "https://www.example.com/guide".to_string(),
+        published: Some(Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap()),
+        author: Some("Jane Doe".to_string()),
+        ..Default::default()
+    }
+}
+
+fn citation() -> Citation {
+    let accessed = Local.with_ymd_and_hms(2024, 3, 5, 9, 30, 0).unwrap();
+    Citation::new(&article(), "Example Blog", accessed)
+}
+
+#[test]
+fn test_citation_key() {
+    assert_eq!(citation_key(&article()), "doe2024rust");
+
+    let mut article = article();
+    article.author = Some("Doe, Jane".to_string());
+    assert_eq!(citation_key(&article), "doe2024rust");
+    article.author = Some("jane@example.com (Jane Roe)".to_string());
+    assert_eq!(citation_key(&article), "roe2024rust");
+
+    // Without an author the link's host stands in
+    article.author = Some("jane@example.com".to_string());
+    article.published = None;
+    assert_eq!(citation_key(&article), "examplerust");
+}
+
+#[test]
+fn test_bibtex() {
+    assert_eq!(
+        bibtex(&citation()),
+        "@online{doe2024rust,
+  author = {Jane Doe},
+  title = {The Rust \\& C++ 100\\% Guide},
+  organization = {Example Blog},
+  date = {2024-01-15},
+  url = {https://www.example.com/guide},
+  urldate = {2024-03-05},
+}
+"
+    );
+}
+
+#[test]
+fn test_csl_json() {
+    let item = csl_json(&citation());
+    assert_eq!(item["id"], "doe2024rust");
+    assert_eq!(item["type"], "post-weblog");
+    assert_eq!(item["container-title"], "Example Blog");
+    assert_eq!(item["author"][0]["literal"], "Jane Doe");
+    assert_eq!(item["issued"]["date-parts"], json!([[2024, 1, 15]]));
+    assert_eq!(item["accessed"]["date-parts"], json!([[2024, 3, 5]]));
+}
+
+#[test]
+fn test_disambiguate() {
+    let mut citations = vec![citation(), citation(), citation()];
+    citations[1].key = "other".to_string();
+    disambiguate(&mut citations);
+    let keys: Vec<&str> = citations.iter().map(|c| c.key.as_str()).collect();
+    assert_eq!(keys, vec!["doe2024rusta", "other", "doe2024rustb"]);
+}
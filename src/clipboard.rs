@@ -0,0 +1,63 @@
+//! Copying text to the system clipboard from the TUI
+//!
+//! The native clipboard is used when there is one to talk to. Over SSH, or when
+//! it isn't reachable, the text goes to the terminal as an OSC 52 sequence,
+//! which most terminals forward to the clipboard of the machine they run on.
+
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use std::io::Write;
+use std::sync::Mutex;
+
+#[cfg(test)]
+#[path = "clipboard_tests.rs"]
+mod tests;
+
+/// Where copied text ended up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Copied {
+    Clipboard,
+    /// Handed to the terminal with OSC 52, which may ignore it
+    Terminal,
+}
+
+/// Copies `text`, preferring the native clipboard outside SSH sessions
+pub fn copy(text: &str, out: &mut impl Write) -> Result<Copied> {
+    if std::env::var_os("SSH_TTY").is_none() && copy_native(text).is_ok() {
+        return Ok(Copied::Clipboard);
+    }
+    out.write_all(osc52(text).as_bytes())?;
+    out.flush()?;
+    Ok(Copied::Terminal)
+}
+
+fn copy_native(text: &str) -> Result<()> {
+    // On X11 the copied text is served by its owner, so the handle is kept
+    // open for as long as the process runs
+    static CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+    let mut clipboard = CLIPBOARD
+        .lock()
+        .map_err(|_| anyhow!("clipboard lock poisoned"))?;
+    if clipboard.is_none() {
+        *clipboard = Some(arboard::Clipboard::new()?);
+    }
+    clipboard
+        .as_mut()
+        .expect("clipboard was just opened")
+        .set_text(text)?;
+    Ok(())
+}
+
+/// OSC 52 sequence setting the clipboard to `text`
+fn osc52(text: &str) -> String {
+    format!(
+        "\x1b]52;c;{}\x07",
+        base64::engine::general_purpose::STANDARD.encode(text)
+    )
+}
+
+/// `[title](link)` with the brackets in the title escaped
+pub fn markdown_link(title: &str, link: &str) -> String {
+    let title = title.replace('[', "\\[").replace(']', "\\]");
+    format!("[{}]({})", title, link)
+}
@@ -0,0 +1,17 @@
+use super::*;
+
+#[test]
+fn test_osc52_encodes_text() {
+    assert_eq!(
+        osc52("https://example.com/a"),
+        "\x1b]52;c;aHR0cHM6Ly9leGFtcGxlLmNvbS9h\x07"
+    );
+}
+
+#[test]
+fn test_markdown_link_escapes_brackets() {
+    assert_eq!(
+        markdown_link("Rust [1.80] released", "https://blog.rust-lang.org/"),
+        "[Rust \\[1.80\\] released](https://blog.rust-lang.org/)"
+    );
+}
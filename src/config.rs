@@ -0,0 +1,557 @@
+//! User configuration from `~/.config/zetrss/config.toml`
+//!
+//! ```toml
+//! zet_path = "~/notes/zet"
+//! data_dir = "~/.local/share/zetrss"
+//! profile = "personal"              # used when --profile isn't given
+//! zet_roots = ["~/work/zet"]        # more zets scanned for feeds after zet_path
+//!
+//! [scan]
+//! skip_dirs = ["archive", "templates", ".obsidian"]
+//! extensions = ["md", "org", "txt"]
+//! orphans = "keep"                  # or "remove" the metadata of feeds gone from the zet
+//!
+//! [fetch]
+//! concurrency = 8
+//! timeout_secs = 20
+//!
+//! [list]
+//! limit = 50
+//!
+//! [viewer]
+//! width = 100
+//! on_finish = "summary"
+//! images = "auto"                  # inline images: auto, kitty, sixel or off
+//! wpm = 250                         # reading speed for the header's reading time
+//! player = "mpv --no-video"         # plays audio/video enclosures (`m`)
+//!
+//! [notes]
+//! dir = "~/notes/zet/inbox"         # default: zet_path
+//! filename = "{{ feed }}/{{ date }}-{{ slug }}.md"  # default "{{ id }}-{{ slug }}.{{ ext }}"
+//! id_format = "%Y%m%d%H%M"          # strftime format of {{ id }}
+//! naming = "denote"                 # DATE--title__tags names instead of filename
+//! flavor = "obsidian"               # built-in note format: markdown, obsidian or org
+//! mode = "daily"                    # list articles in the daily note instead
+//! article = "embed"                 # keep the article's text in the note (or "link")
+//! citekey = true                    # add the article's citation key to notes
+//! related = 5                       # link new notes to this many related notes
+//! template = "~/.config/zetrss/note.md"  # Tera template for new notes
+//!
+//! [notes.fields]                   # extra frontmatter of new notes
+//! status = "inbox"
+//! project = "{{ feed_host }}"       # values are Tera templates
+//!
+//! [notes.daily]
+//! path = "daily/{{ date }}.md"      # default "{{ date }}.{{ ext }}"
+//! heading = "## Reading"
+//! entry = "- {{ source }}"          # Tera template of one entry
+//! template = "~/.config/zetrss/daily.md"  # for daily notes that don't exist yet
+//!
+//! [pocket]                          # `zetrss pocket` sends starred articles to Pocket
+//! consumer_key = "1234-abcd1234"    # access_token: $ZETRSS_POCKET_TOKEN or `access_token`
+//! on_star = true                    # send articles as they're starred in the TUI
+//! tags = ["zetrss"]
+//!
+//! [summarizer]                      # three-bullet summaries from a local LLM
+//! url = "http://localhost:11434/v1/chat/completions"  # OpenAI-compatible
+//! model = "llama3.2"
+//!
+//! [sync]                            # `zetrss sync` with FreshRSS and the like
+//! service = "greader"               # or "ttrss" (url = "https://example.com/tt-rss/api/")
+//! url = "https://rss.example.com/api/greader.php"
+//! username = "me"                   # password: $ZETRSS_SYNC_PASSWORD or `password`
+//!
+//! [theme]                           # see `theme` for presets and colors
+//! preset = "gruvbox"
+//!
+//! [feeds."https://news.ycombinator.com/rss"]
+//! interval = "10m"
+//! max_items = 200
+//! max_age = "14d"
+//!
+//! [profiles.personal]               # cache in <data_dir>/profiles/personal
+//!
+//! [profiles.work]
+//! zet_path = "~/work/zet"
+//! data_dir = "~/.local/share/zetrss-work"
+//! ```
+//!
+//! Every setting is optional; command-line flags and `ZETRSS_DATA_DIR` win over the file.
+
+use crate::cache::Retention;
+use crate::images::ImageMode;
+use crate::notes::{ArticleCopy, NoteFlavor, NoteMode, NoteNaming};
+use crate::orphans::OrphanAction;
+use crate::sync::SyncService;
+use crate::theme::ThemeConfig;
+use crate::viewer::FinishAction;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+#[cfg(test)]
+#[path = "config_tests.rs"]
+mod tests;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Zettelkasten directory scanned for `#feed` tags and where notes are created
+    pub zet_path: Option<String>,
+    /// More zet directories scanned for `#feed` tags after `zet_path`, e.g. a
+    /// work vault next to a personal one
+    pub zet_roots: Vec<String>,
+    pub scan: ScanConfig,
+    /// Where articles and state are stored
+    pub data_dir: Option<String>,
+    pub fetch: FetchConfig,
+    pub list: ListConfig,
+    pub viewer: ViewerConfig,
+    pub notes: NotesConfig,
+    pub pocket: PocketConfig,
+    pub summarizer: SummarizerConfig,
+    pub sync: SyncConfig,
+    pub theme: ThemeConfig,
+    /// Per-feed settings keyed by feed URL
+    pub feeds: HashMap<String, FeedConfig>,
+    /// Profile selected when `--profile` isn't given
+    pub profile: Option<String>,
+    /// Named profiles, each with its own feed list, cache and zet
+    pub profiles: HashMap<String, ProfileConfig>,
+    /// The profile applied by `select_profile`
+    #[serde(skip)]
+    pub active_profile: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileConfig {
+    /// Zet directory for this profile; the top-level `zet_path` if unset
+    pub zet_path: Option<String>,
+    /// Extra zet directories for this profile; the top-level ones if unset
+    pub zet_roots: Option<Vec<String>>,
+    /// Cache for this profile; `<data_dir>/profiles/<name>` if unset
+    pub data_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScanConfig {
+    /// Directories the scanner skips, as gitignore patterns (`archive` skips
+    /// every directory of that name, `notes/old` just that one)
+    pub skip_dirs: Vec<String>,
+    /// Extensions of the files scanned as notes
+    pub extensions: Vec<String>,
+    /// What a scan does with feeds no longer found in the zet
+    pub orphans: OrphanAction,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            skip_dirs: vec![
+                "archive".to_string(),
+                "templates".to_string(),
+                ".obsidian".to_string(),
+            ],
+            extensions: vec!["md".to_string(), "org".to_string(), "txt".to_string()],
+            orphans: OrphanAction::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FetchConfig {
+    /// Feeds fetched at the same time
+    pub concurrency: usize,
+    /// HTTP timeout per request
+    pub timeout_secs: u64,
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: crate::engine::MAX_CONCURRENT_FETCHES,
+            timeout_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ListConfig {
+    /// Default `--limit` for `list` and `search`
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ViewerConfig {
+    /// Column width articles are wrapped at
+    pub width: usize,
+    /// Default for `view --on-finish`
+    pub on_finish: FinishAction,
+    /// Inline images: auto, kitty, sixel or off
+    pub images: ImageMode,
+    /// Reading speed behind the header's reading time estimate
+    pub wpm: usize,
+    /// Shell command enclosures are played with; gets the URL as argument
+    pub player: String,
+}
+
+impl Default for ViewerConfig {
+    fn default() -> Self {
+        Self {
+            width: 80,
+            on_finish: FinishAction::default(),
+            images: ImageMode::default(),
+            wpm: 230,
+            player: "mpv".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotesConfig {
+    /// Directory notes are created in; `zet_path` if unset
+    pub dir: Option<String>,
+    /// Tera pattern for a new note's path under `dir`, e.g.
+    /// "{{ feed }}/{{ date }}-{{ slug }}.md"
+    pub filename: String,
+    /// strftime format of the `{{ id }}` Zettel ID in `filename`
+    pub id_format: String,
+    /// Whether new notes are named by `filename` or Denote's scheme
+    pub naming: NoteNaming,
+    /// Built-in note format used when `template` is unset
+    pub flavor: NoteFlavor,
+    /// Whether articles get a note of their own or an entry in the daily note
+    pub mode: NoteMode,
+    /// Whether new notes embed the cached article's text or link its file
+    pub article: ArticleCopy,
+    /// Whether new notes carry the article's citation key (see `zetrss cite`)
+    pub citekey: bool,
+    /// How many related zet notes new notes link to; 0 turns it off
+    pub related: usize,
+    pub daily: DailyNoteConfig,
+    /// Extra fields of every new note, as name = Tera template of the value
+    /// (e.g. `project = "{{ feed_host }}"`)
+    pub fields: BTreeMap<String, String>,
+    /// Tera template file notes are created from; the flavor's built-in one
+    /// if unset
+    pub template: Option<String>,
+}
+
+impl Default for NotesConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            filename: "{{ id }}-{{ slug }}.{{ ext }}".to_string(),
+            id_format: "%Y%m%d%H%M".to_string(),
+            naming: NoteNaming::default(),
+            flavor: NoteFlavor::default(),
+            mode: NoteMode::default(),
+            article: ArticleCopy::default(),
+            citekey: false,
+            related: 5,
+            daily: DailyNoteConfig::default(),
+            fields: BTreeMap::new(),
+            template: None,
+        }
+    }
+}
+
+/// A local LLM that summarizes articles for notes and the viewer
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SummarizerConfig {
+    /// OpenAI-compatible chat completions endpoint, e.g. ollama's
+    /// http://localhost:11434/v1/chat/completions; summaries are off if unset
+    pub url: Option<String>,
+    pub model: String,
+    /// Sent as a bearer token, for servers that want one
+    pub api_key: Option<String>,
+    /// Instructions sent ahead of the article's text
+    pub prompt: String,
+    pub timeout_secs: u64,
+}
+
+impl Default for SummarizerConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            model: "llama3.2".to_string(),
+            api_key: None,
+            prompt: "Summarize the article in exactly three short bullet points. \
+                     Reply with the bullets only, one per line, each starting with \"- \"."
+                .to_string(),
+            timeout_secs: 120,
+        }
+    }
+}
+
+/// The Pocket account starred articles are sent to
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PocketConfig {
+    /// Consumer key of a Pocket app (https://getpocket.com/developer/)
+    pub consumer_key: Option<String>,
+    /// From `zetrss pocket --login`; `$ZETRSS_POCKET_TOKEN` wins over this
+    pub access_token: Option<String>,
+    /// Send articles to Pocket as they're starred in the viewer and browser
+    pub on_star: bool,
+    /// Pocket tags of sent articles, besides the articles' own tags
+    pub tags: Vec<String>,
+    /// The API's root
+    pub url: String,
+}
+
+impl Default for PocketConfig {
+    fn default() -> Self {
+        Self {
+            consumer_key: None,
+            access_token: None,
+            on_star: false,
+            tags: Vec::new(),
+            url: "https://getpocket.com/v3".to_string(),
+        }
+    }
+}
+
+/// The feed reader account `zetrss sync` keeps the cache in step with
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyncConfig {
+    pub service: SyncService,
+    /// The API's root, e.g. FreshRSS's https://rss.example.com/api/greader.php
+    /// or TT-RSS's https://example.com/tt-rss/api/
+    pub url: Option<String>,
+    pub username: Option<String>,
+    /// `$ZETRSS_SYNC_PASSWORD` wins over this
+    pub password: Option<String>,
+    /// Days of articles pulled by the first sync
+    pub initial_days: u32,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            service: SyncService::default(),
+            url: None,
+            username: None,
+            password: None,
+            initial_days: 14,
+        }
+    }
+}
+
+/// Daily notes articles are listed in with `[notes] mode = "daily"`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DailyNoteConfig {
+    /// Tera pattern for the daily note's path under the notes directory
+    pub path: String,
+    /// Heading entries go under; "## Reading" (org: "* Reading") if unset
+    pub heading: Option<String>,
+    /// Tera template of one entry; a link and the summary's first sentence
+    /// if unset
+    pub entry: Option<String>,
+    /// Tera template file a missing daily note is created from
+    pub template: Option<String>,
+}
+
+impl Default for DailyNoteConfig {
+    fn default() -> Self {
+        Self {
+            path: "{{ date }}.{{ ext }}".to_string(),
+            heading: None,
+            entry: None,
+            template: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeedConfig {
+    /// Fetch interval for `zetrss daemon` (e.g. "10m"), overriding `--interval`
+    pub interval: Option<String>,
+    /// Keep only this many of the feed's newest articles after each fetch
+    pub max_items: Option<usize>,
+    /// Delete the feed's articles older than this (e.g. "14d") after each fetch
+    pub max_age: Option<String>,
+}
+
+impl FeedConfig {
+    /// The retention limits for this feed, or None if it keeps everything
+    pub fn retention(&self) -> Result<Option<Retention>> {
+        if self.max_items.is_none() && self.max_age.is_none() {
+            return Ok(None);
+        }
+        let max_age = match self.max_age.as_deref() {
+            Some(age) => {
+                // Counting back from now must work for the cutoff to exist
+                crate::filter::ago(age)?;
+                Some(crate::filter::parse_duration(age)?)
+            }
+            None => None,
+        };
+        Ok(Some(Retention {
+            max_items: self.max_items,
+            max_age,
+        }))
+    }
+}
+
+impl Config {
+    /// Loads the config from `path`, or from the default location if None
+    /// A missing file at the default location yields the default config
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => (config_path(), false),
+        };
+        if !required && !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// The zet directory with `~` expanded
+    /// Falls back to `~/git/$USER/zet` when not configured
+    pub fn zet_path(&self) -> String {
+        let path = self.zet_path.clone().unwrap_or_else(|| {
+            let username = std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .unwrap_or_else(|_| "user".to_string());
+            format!("~/git/{}/zet", username)
+        });
+        shellexpand::tilde(&path).to_string()
+    }
+
+    /// The directory new notes go to, with `~` expanded: `[notes] dir`, else
+    /// `zet_path`
+    pub fn notes_dir(&self) -> String {
+        match &self.notes.dir {
+            Some(dir) => shellexpand::tilde(dir).to_string(),
+            None => self.zet_path(),
+        }
+    }
+
+    /// Every zet directory scanned for feeds: `zet_path`, then `zet_roots`,
+    /// with `~` expanded and duplicates dropped
+    pub fn zet_roots(&self) -> Vec<String> {
+        let mut roots = vec![self.zet_path()];
+        for root in &self.zet_roots {
+            let root = shellexpand::tilde(root).to_string();
+            if !roots.contains(&root) {
+                roots.push(root);
+            }
+        }
+        roots
+    }
+
+    /// Applies the named profile (or the configured default) to `zet_path`
+    /// and `data_dir`. Profiles never share a data directory with each other
+    /// or with the top-level config.
+    pub fn select_profile(&mut self, name: Option<&str>) -> Result<()> {
+        let Some(name) = name.map(str::to_string).or_else(|| self.profile.clone()) else {
+            return Ok(());
+        };
+        let profile = self.profiles.get(&name).cloned().ok_or_else(|| {
+            let mut known: Vec<_> = self.profiles.keys().map(String::as_str).collect();
+            known.sort();
+            anyhow::anyhow!(
+                "Unknown profile '{}' (configured: {})",
+                name,
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            )
+        })?;
+
+        if profile.zet_path.is_some() {
+            self.zet_path = profile.zet_path;
+        }
+        if let Some(roots) = profile.zet_roots {
+            self.zet_roots = roots;
+        }
+        self.data_dir = Some(profile.data_dir.unwrap_or_else(|| {
+            let base = self.data_dir().unwrap_or_else(crate::cache::xdg_data_dir);
+            base.join("profiles")
+                .join(&name)
+                .to_string_lossy()
+                .to_string()
+        }));
+        self.active_profile = Some(name);
+        Ok(())
+    }
+
+    /// Retention limits by feed URL, for feeds that have any
+    pub fn retention(&self) -> Result<HashMap<String, Retention>> {
+        let mut retention = HashMap::new();
+        for (url, feed) in &self.feeds {
+            if let Some(limits) = feed
+                .retention()
+                .with_context(|| format!("Invalid max_age for feed {}", url))?
+            {
+                retention.insert(url.clone(), limits);
+            }
+        }
+        Ok(retention)
+    }
+
+    /// The configured data directory with `~` expanded, if any
+    pub fn data_dir(&self) -> Option<PathBuf> {
+        self.data_dir
+            .as_deref()
+            .map(|dir| PathBuf::from(shellexpand::tilde(dir).to_string()))
+    }
+}
+
+/// `$ZETRSS_CONFIG`, else `$XDG_CONFIG_HOME/zetrss/config.toml`, else `~/.config/zetrss/config.toml`
+pub fn config_path() -> PathBuf {
+    if let Ok(path) = std::env::var("ZETRSS_CONFIG") {
+        return PathBuf::from(path);
+    }
+    let config_home = std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+            PathBuf::from(home).join(".config")
+        });
+    config_home.join("zetrss").join("config.toml")
+}
+
+/// Installs the config used by `get()`; called once at startup
+/// Returns false if a config was already installed
+pub fn init(config: Config) -> bool {
+    CONFIG.set(config).is_ok()
+}
+
+/// The process-wide config
+/// Loads it from the default location on first use if `init` wasn't called;
+/// a broken file is reported and ignored so library users never fail here
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(|| {
+        Config::load(None).unwrap_or_else(|e| {
+            tracing::warn!("{:#}", e);
+            Config::default()
+        })
+    })
+}
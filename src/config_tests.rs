@@ -0,0 +1,162 @@
+use super::*;
+
+#[test]
+fn test_parse_full_config() {
+    let config = Config::parse(
+        r#"
+        zet_path = "/notes/zet"
+        data_dir = "/data/zetrss"
+
+        [fetch]
+        concurrency = 8
+
+        [list]
+        limit = 50
+
+        [viewer]
+        width = 100
+        on_finish = "summary"
+        images = "off"
+
+        [feeds."https://news.ycombinator.com/rss"]
+        interval = "10m"
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.zet_path(), "/notes/zet");
+    assert_eq!(config.data_dir(), Some(PathBuf::from("/data/zetrss")));
+    assert_eq!(config.fetch.concurrency, 8);
+    // Unset keys inside a section keep their defaults
+    assert_eq!(config.fetch.timeout_secs, 30);
+    assert_eq!(config.list.limit, Some(50));
+    assert_eq!(config.viewer.width, 100);
+    assert_eq!(config.viewer.on_finish, FinishAction::Summary);
+    assert_eq!(config.viewer.images, ImageMode::Off);
+    assert_eq!(
+        config.feeds["https://news.ycombinator.com/rss"]
+            .interval
+            .as_deref(),
+        Some("10m")
+    );
+}
+
+#[test]
+fn test_empty_config_uses_defaults() {
+    let config = Config::parse("").unwrap();
+    assert!(config.zet_path.is_none());
+    assert!(config.data_dir().is_none());
+    assert_eq!(
+        config.fetch.concurrency,
+        crate::engine::MAX_CONCURRENT_FETCHES
+    );
+    assert_eq!(config.viewer.width, 80);
+    assert!(config.zet_path().ends_with("/zet"));
+}
+
+#[test]
+fn test_unknown_keys_are_rejected() {
+    assert!(Config::parse("zet_pth = \"/typo\"").is_err());
+    assert!(Config::parse("[fetch]\nconcurency = 3").is_err());
+}
+
+#[test]
+fn test_load_missing_file() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let missing = temp_dir.path().join("config.toml");
+    // An explicitly requested file must exist
+    assert!(Config::load(Some(&missing)).is_err());
+
+    fs::write(&missing, "[list]\nlimit = 5").unwrap();
+    assert_eq!(Config::load(Some(&missing)).unwrap().list.limit, Some(5));
+}
+
+#[test]
+fn test_select_profile() {
+    let config = Config::parse(
+        r#"
+        zet_path = "/notes/zet"
+        data_dir = "/data/zetrss"
+        profile = "personal"
+
+        [profiles.work]
+        zet_path = "/work/zet"
+        data_dir = "/data/work"
+
+        [profiles.personal]
+        "#,
+    )
+    .unwrap();
+
+    let mut work = config.clone();
+    work.select_profile(Some("work")).unwrap();
+    assert_eq!(work.zet_path(), "/work/zet");
+    assert_eq!(work.data_dir(), Some(PathBuf::from("/data/work")));
+    assert_eq!(work.active_profile.as_deref(), Some("work"));
+
+    // The default profile inherits the zet but gets its own cache
+    let mut personal = config.clone();
+    personal.select_profile(None).unwrap();
+    assert_eq!(personal.zet_path(), "/notes/zet");
+    assert_eq!(
+        personal.data_dir(),
+        Some(PathBuf::from("/data/zetrss/profiles/personal"))
+    );
+
+    let err = config.clone().select_profile(Some("play")).unwrap_err();
+    assert!(err.to_string().contains("personal, work"));
+
+    // Without profiles nothing changes
+    let mut plain = Config::parse("data_dir = \"/data\"").unwrap();
+    plain.select_profile(None).unwrap();
+    assert_eq!(plain.data_dir(), Some(PathBuf::from("/data")));
+    assert!(plain.active_profile.is_none());
+}
+
+#[test]
+fn test_feed_retention() {
+    let config = Config::parse(
+        r#"
+        [feeds."https://news.ycombinator.com/rss"]
+        max_items = 200
+        max_age = "14d"
+
+        [feeds."https://blog.example/feed"]
+        interval = "1d"
+        "#,
+    )
+    .unwrap();
+
+    let retention = config.retention().unwrap();
+    assert_eq!(retention.len(), 1);
+    let hn = &retention["https://news.ycombinator.com/rss"];
+    assert_eq!(hn.max_items, Some(200));
+    assert_eq!(hn.max_age, Some(chrono::Duration::days(14)));
+
+    let bad = Config::parse("[feeds.\"https://x.example\"]\nmax_age = \"soon\"").unwrap();
+    assert!(bad.retention().is_err());
+    let too_old = Config::parse("[feeds.\"https://x.example\"]\nmax_age = \"999999999w\"").unwrap();
+    assert!(too_old.retention().is_err());
+}
+
+#[test]
+fn test_zet_roots() {
+    let config = Config::parse(
+        r#"
+        zet_path = "/notes/zet"
+        zet_roots = ["/work/zet", "/notes/zet", "/shared/zet"]
+
+        [profiles.solo]
+        zet_roots = []
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.zet_roots(),
+        vec!["/notes/zet", "/work/zet", "/shared/zet"]
+    );
+
+    let mut solo = config.clone();
+    solo.select_profile(Some("solo")).unwrap();
+    assert_eq!(solo.zet_roots(), vec!["/notes/zet"]);
+}
@@ -0,0 +1,222 @@
+//! Optional encryption of article bodies at rest
+//!
+//! A cache is encrypted once `state/encryption.json` exists. It holds the salt
+//! the key is derived from (Argon2id over a passphrase) and a check value that
+//! tells a wrong passphrase from damaged data. Article bodies and raw entries
+//! are sealed with XChaCha20-Poly1305; frontmatter (titles, feeds, read state)
+//! stays readable so listings and the Neovim plugin keep working.
+//!
+//! The passphrase comes from `$ZETRSS_PASSPHRASE`, else the OS keyring (see
+//! `zetrss unlock`), else a terminal prompt.
+
+use anyhow::{anyhow, Context, Result};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+#[path = "crypt_tests.rs"]
+mod tests;
+
+/// Marks sealed text; the rest is base64 of the nonce followed by the ciphertext
+pub const ENCRYPTED_PREFIX: &str = "zetrss:encrypted:v1:";
+
+/// Environment variable holding the passphrase, for scripts and the plugin
+pub const PASSPHRASE_ENV: &str = "ZETRSS_PASSPHRASE";
+
+const KEYRING_SERVICE: &str = "zetrss";
+const CHECK_TEXT: &str = "zetrss";
+const NONCE_LEN: usize = 24;
+
+/// Key derivation settings kept in `state/encryption.json`
+#[derive(Debug, Serialize, Deserialize)]
+struct KeyParams {
+    version: u32,
+    /// Base64 Argon2id salt
+    salt: String,
+    /// `CHECK_TEXT` sealed with the key, to verify passphrases
+    check: String,
+}
+
+/// Seals and opens text with the key of one cache
+pub struct Cipher {
+    aead: XChaCha20Poly1305,
+}
+
+impl Cipher {
+    fn derive(passphrase: &str, salt: &[u8]) -> Result<Self> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| anyhow!("Failed to derive encryption key: {}", e))?;
+        Ok(Self {
+            aead: XChaCha20Poly1305::new(&key.into()),
+        })
+    }
+
+    /// Encrypts `plaintext` with a fresh random nonce
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .aead
+            .encrypt(&nonce, plaintext.as_bytes())
+            .expect("XChaCha20-Poly1305 encryption is infallible for in-memory buffers");
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        format!("{}{}", ENCRYPTED_PREFIX, BASE64.encode(sealed))
+    }
+
+    /// Decrypts text produced by `encrypt`; surrounding whitespace is ignored
+    pub fn decrypt(&self, sealed: &str) -> Result<String> {
+        let encoded = sealed
+            .trim()
+            .strip_prefix(ENCRYPTED_PREFIX)
+            .ok_or_else(|| anyhow!("Not encrypted text"))?;
+        let bytes = BASE64.decode(encoded).context("Damaged encrypted text")?;
+        if bytes.len() < NONCE_LEN {
+            return Err(anyhow!("Damaged encrypted text"));
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plaintext = self
+            .aead
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("Failed to decrypt: wrong key or damaged data"))?;
+        Ok(String::from_utf8(plaintext)?)
+    }
+}
+
+/// True if `text` was sealed by a `Cipher`
+pub fn is_encrypted(text: &str) -> bool {
+    text.trim_start().starts_with(ENCRYPTED_PREFIX)
+}
+
+fn params_path(base_dir: &Path) -> PathBuf {
+    base_dir.join("state").join("encryption.json")
+}
+
+/// True if the cache in `base_dir` is encrypted
+pub fn is_enabled(base_dir: &Path) -> bool {
+    params_path(base_dir).exists()
+}
+
+/// Turns encryption on for the cache in `base_dir` and returns its cipher
+/// Existing files are left as they are; `TextCache::encrypt_all` converts them
+pub fn enable(base_dir: &Path, passphrase: &str) -> Result<Cipher> {
+    if is_enabled(base_dir) {
+        return Err(anyhow!(
+            "The cache in {} is already encrypted",
+            base_dir.display()
+        ));
+    }
+    let salt: [u8; 16] = rand_bytes();
+    let cipher = Cipher::derive(passphrase, &salt)?;
+    let params = KeyParams {
+        version: 1,
+        salt: BASE64.encode(salt),
+        check: cipher.encrypt(CHECK_TEXT),
+    };
+    let path = params_path(base_dir);
+    fs::create_dir_all(path.parent().unwrap_or(base_dir))?;
+    fs::write(&path, serde_json::to_string_pretty(&params)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(cipher)
+}
+
+/// Turns encryption off; call once every file has been decrypted
+pub fn disable(base_dir: &Path) -> Result<()> {
+    let path = params_path(base_dir);
+    if path.exists() {
+        fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// The cipher of an encrypted cache, after checking the passphrase
+pub fn open(base_dir: &Path, passphrase: &str) -> Result<Cipher> {
+    let path = params_path(base_dir);
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let params: KeyParams = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid encryption settings in {}", path.display()))?;
+    let salt = BASE64.decode(&params.salt)?;
+    let cipher = Cipher::derive(passphrase, &salt)?;
+    match cipher.decrypt(&params.check) {
+        Ok(check) if check == CHECK_TEXT => Ok(cipher),
+        _ => Err(anyhow!(
+            "Wrong passphrase for the cache in {}",
+            base_dir.display()
+        )),
+    }
+}
+
+/// The passphrase for the cache in `base_dir`: `$ZETRSS_PASSPHRASE`, the OS
+/// keyring, or a prompt when stdin is a terminal
+pub fn passphrase(base_dir: &Path) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    if let Ok(passphrase) = keyring_entry(base_dir).and_then(|e| Ok(e.get_password()?)) {
+        return Ok(passphrase);
+    }
+    if std::io::stdin().is_terminal() {
+        return prompt(false);
+    }
+    Err(anyhow!(
+        "The cache in {} is encrypted: set {} or run `zetrss unlock`",
+        base_dir.display(),
+        PASSPHRASE_ENV
+    ))
+}
+
+/// Asks for a passphrase on the terminal, twice if `confirm` is set
+pub fn prompt(confirm: bool) -> Result<String> {
+    let passphrase = rpassword::prompt_password("Cache passphrase: ")?;
+    if passphrase.is_empty() {
+        return Err(anyhow!("The passphrase can't be empty"));
+    }
+    if confirm && rpassword::prompt_password("Repeat passphrase: ")? != passphrase {
+        return Err(anyhow!("Passphrases don't match"));
+    }
+    Ok(passphrase)
+}
+
+/// Stores the passphrase in the OS keyring so commands stop prompting
+/// On Linux this is the session keyring, which is cleared on logout
+pub fn remember(base_dir: &Path, passphrase: &str) -> Result<()> {
+    keyring_entry(base_dir)?
+        .set_password(passphrase)
+        .context("Failed to store the passphrase in the keyring")
+}
+
+/// Removes a remembered passphrase; returns false if there was none
+pub fn forget(base_dir: &Path) -> Result<bool> {
+    match keyring_entry(base_dir)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(e).context("Failed to remove the passphrase from the keyring"),
+    }
+}
+
+/// Keyring entries are per data directory, so profiles can use different passphrases
+fn keyring_entry(base_dir: &Path) -> Result<keyring::Entry> {
+    let dir = base_dir
+        .canonicalize()
+        .unwrap_or_else(|_| base_dir.to_path_buf());
+    Ok(keyring::Entry::new(
+        KEYRING_SERVICE,
+        &dir.to_string_lossy(),
+    )?)
+}
+
+fn rand_bytes<const N: usize>() -> [u8; N] {
+    use chacha20poly1305::aead::rand_core::RngCore;
+    let mut bytes = [0u8; N];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
@@ -0,0 +1,45 @@
+use super::*;
+use tempfile::TempDir;
+
+#[test]
+fn test_enable_and_open() {
+    let dir = TempDir::new().unwrap();
+    assert!(!is_enabled(dir.path()));
+
+    let cipher = enable(dir.path(), "correct horse").unwrap();
+    assert!(is_enabled(dir.path()));
+    assert!(enable(dir.path(), "again").is_err());
+
+    let sealed = cipher.encrypt("Secret body");
+    assert!(is_encrypted(&sealed));
+    assert!(!sealed.contains("Secret"));
+    // Fresh nonce every time
+    assert_ne!(sealed, cipher.encrypt("Secret body"));
+
+    let reopened = open(dir.path(), "correct horse").unwrap();
+    assert_eq!(
+        reopened.decrypt(&format!("\n{}\n", sealed)).unwrap(),
+        "Secret body"
+    );
+    assert!(open(dir.path(), "wrong").is_err());
+
+    disable(dir.path()).unwrap();
+    assert!(!is_enabled(dir.path()));
+}
+
+#[test]
+fn test_decrypt_rejects_tampering() {
+    let dir = TempDir::new().unwrap();
+    let cipher = enable(dir.path(), "pass").unwrap();
+    let sealed = cipher.encrypt("Body");
+
+    let mut tampered = sealed.clone().into_bytes();
+    let last = tampered.len() - 2;
+    tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+    assert!(cipher
+        .decrypt(&String::from_utf8(tampered).unwrap())
+        .is_err());
+    assert!(cipher.decrypt("plain text").is_err());
+    assert!(cipher.decrypt(ENCRYPTED_PREFIX).is_err());
+    assert!(!is_encrypted("plain text"));
+}
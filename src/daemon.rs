@@ -0,0 +1,370 @@
+use crate::engine::{FetchEvent, ZetRss};
+use crate::rpc::{RpcError, RpcServer, CALL_FAILED};
+use crate::scanner::FeedSource;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+#[cfg(test)]
+#[path = "daemon_tests.rs"]
+mod tests;
+
+/// Name of the status file in the state directory
+pub const STATUS_FILE: &str = "daemon.json";
+
+/// How often a sleeping daemon looks for feeds added by a rescan or
+/// `scan --watch`
+const FEED_LIST_CHECK: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// How often the daemon fetches feeds
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub interval: Duration,
+    /// Per-feed intervals keyed by feed URL
+    pub overrides: HashMap<String, Duration>,
+}
+
+impl Schedule {
+    /// The feed's interval: a config or command-line override first, then the
+    /// `interval=` declared with the feed in the zet, then the base interval
+    pub fn interval_for(&self, feed: &FeedSource) -> Duration {
+        self.overrides
+            .get(&feed.url)
+            .copied()
+            .or_else(|| feed.options.interval())
+            .unwrap_or(self.interval)
+    }
+}
+
+/// Status written to `state/daemon.json` after every cycle
+/// The Neovim plugin reads this to show fresh counts without fetching itself
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub running: bool,
+    pub pid: u32,
+    pub started_at: Option<DateTime<Utc>>,
+    pub last_cycle: Option<DateTime<Utc>>,
+    pub next_cycle: Option<DateTime<Utc>>,
+    pub unread: usize,
+    pub feeds: Vec<FeedStatus>,
+    /// Socket the daemon answers requests on (see `ipc`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub socket: Option<String>,
+}
+
+/// Scheduling state of a single feed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedStatus {
+    pub url: String,
+    pub last_fetched: Option<DateTime<Utc>>,
+    pub next_due: DateTime<Utc>,
+    pub last_items: usize,
+    pub last_error: Option<String>,
+}
+
+impl DaemonStatus {
+    /// Returns the feeds due for fetching, adding newly discovered feeds as due now
+    /// Feeds no longer in the feed list are dropped from the status
+    pub fn due_feeds(&mut self, feeds: &[FeedSource], now: DateTime<Utc>) -> Vec<FeedSource> {
+        self.feeds
+            .retain(|status| feeds.iter().any(|f| f.url == status.url));
+        for feed in feeds {
+            if !self.feeds.iter().any(|status| status.url == feed.url) {
+                self.feeds.push(FeedStatus {
+                    url: feed.url.clone(),
+                    last_fetched: None,
+                    next_due: now,
+                    last_items: 0,
+                    last_error: None,
+                });
+            }
+        }
+
+        feeds
+            .iter()
+            .filter(|feed| {
+                self.feeds
+                    .iter()
+                    .any(|status| status.url == feed.url && status.next_due <= now)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Records a fetch and schedules the feed's next run
+    pub fn record_fetch(
+        &mut self,
+        feed: &FeedSource,
+        items: usize,
+        error: Option<String>,
+        now: DateTime<Utc>,
+        schedule: &Schedule,
+    ) {
+        if let Some(status) = self.feeds.iter_mut().find(|s| s.url == feed.url) {
+            status.last_fetched = Some(now);
+            status.next_due = later(now, schedule.interval_for(feed));
+            status.last_items = items;
+            status.last_error = error;
+        }
+    }
+
+    /// Whether the feed list has feeds this status doesn't schedule yet
+    pub fn has_new_feeds(&self, feeds: &[FeedSource]) -> bool {
+        feeds
+            .iter()
+            .any(|feed| !self.feeds.iter().any(|status| status.url == feed.url))
+    }
+
+    /// Makes every feed due at `now`, for a refresh asked for over the socket
+    pub fn make_all_due(&mut self, now: DateTime<Utc>) {
+        for status in &mut self.feeds {
+            status.next_due = now;
+        }
+    }
+
+    /// Earliest moment any feed becomes due
+    pub fn next_due(&self) -> Option<DateTime<Utc>> {
+        self.feeds.iter().map(|s| s.next_due).min()
+    }
+
+    /// Whether the daemon that wrote this status is still up: it says it's
+    /// running and its socket answers, or without a socket its process exists
+    pub fn is_alive(&self) -> bool {
+        if !self.running {
+            return false;
+        }
+        match self.socket {
+            Some(ref socket) => std::os::unix::net::UnixStream::connect(socket).is_ok(),
+            None => process_exists(self.pid),
+        }
+    }
+
+    /// Reads the status file written by a running (or stopped) daemon
+    pub fn load(reader: &ZetRss) -> Result<Option<Self>> {
+        Self::load_from(&reader.store().state_path(STATUS_FILE))
+    }
+
+    fn load_from(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    fn save(&self, reader: &ZetRss) -> Result<()> {
+        self.save_to(&reader.store().state_path(STATUS_FILE))
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Records that the daemon stopped
+    fn mark_stopped(&mut self) {
+        self.running = false;
+        self.next_cycle = None;
+        self.socket = None;
+    }
+}
+
+/// Whether a process with this pid exists
+fn process_exists(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: signal 0 only checks that the process exists
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    // It exists but belongs to someone else
+    std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Marks the status file stopped when dropped, so the daemon leaving by any
+/// path (a signal, an error, a panic) doesn't leave it claiming to run
+struct StoppedOnDrop {
+    path: PathBuf,
+}
+
+impl Drop for StoppedOnDrop {
+    fn drop(&mut self) {
+        let stopped = DaemonStatus::load_from(&self.path).and_then(|status| {
+            let Some(mut status) = status.filter(|s| s.pid == std::process::id()) else {
+                return Ok(());
+            };
+            status.mark_stopped();
+            status.save_to(&self.path)
+        });
+        if let Err(e) = stopped {
+            tracing::warn!("Failed to mark the daemon stopped: {:#}", e);
+        }
+    }
+}
+
+/// Requests reaching a running daemon from its socket
+#[derive(Default)]
+pub struct DaemonControl {
+    refresh: AtomicBool,
+    wake: Notify,
+}
+
+impl DaemonControl {
+    /// Asks for every feed to be fetched right away
+    pub fn request_refresh(&self) {
+        self.refresh.store(true, Ordering::SeqCst);
+        self.wake.notify_one();
+    }
+
+    fn take_refresh(&self) -> bool {
+        self.refresh.swap(false, Ordering::SeqCst)
+    }
+}
+
+/// The daemon's socket server: the `zetrss rpc` methods plus `refresh`
+/// (fetch every feed now) and `status` (the daemon's status)
+pub fn rpc_server(reader: ZetRss, control: Arc<DaemonControl>) -> RpcServer {
+    let status_path: PathBuf = reader.store().state_path(STATUS_FILE);
+    RpcServer::new(reader).with_methods(Box::new(move |method, _params| match method {
+        "refresh" => {
+            control.request_refresh();
+            Some(Ok(json!({ "refreshing": true })))
+        }
+        "status" => Some(
+            DaemonStatus::load_from(&status_path)
+                .map_err(RpcError::from)
+                .and_then(|status| {
+                    status.ok_or_else(|| RpcError::new(CALL_FAILED, "No status written yet"))
+                })
+                .map(|status| json!(status)),
+        ),
+        _ => None,
+    }))
+}
+
+/// Fetches feeds on their schedule until interrupted with Ctrl-C or SIGTERM
+/// The feed list is re-read every cycle so rescans are picked up; `control`
+/// wakes the daemon for refreshes asked for on its `socket`. A failing cycle
+/// is logged and retried on the next one rather than stopping the daemon.
+pub async fn run_daemon(
+    reader: &ZetRss,
+    schedule: &Schedule,
+    control: &DaemonControl,
+    socket: Option<&Path>,
+) -> Result<()> {
+    let mut status = DaemonStatus {
+        running: true,
+        pid: std::process::id(),
+        started_at: Some(Utc::now()),
+        socket: socket.map(|path| path.to_string_lossy().into_owned()),
+        ..Default::default()
+    };
+    let _stopped = StoppedOnDrop {
+        path: reader.store().state_path(STATUS_FILE),
+    };
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        .context("Failed to listen for SIGTERM")?;
+
+    loop {
+        let next = match run_cycle(reader, schedule, control, &mut status).await {
+            Ok(next) => next,
+            Err(e) => {
+                tracing::warn!("Daemon cycle failed: {:#}", e);
+                // Try again after a short pause rather than spinning on the error
+                Utc::now() + Duration::minutes(1)
+            }
+        };
+
+        let sleep_for = (next - Utc::now()).to_std().unwrap_or_default();
+        tokio::select! {
+            _ = wait_for_cycle(reader, &status, sleep_for) => {}
+            _ = control.wake.notified() => {}
+            _ = tokio::signal::ctrl_c() => break,
+            _ = terminate.recv() => break,
+        }
+    }
+
+    status.mark_stopped();
+    status.save(reader)
+}
+
+/// Fetches the due feeds and saves the status; returns when the next cycle
+/// should run
+async fn run_cycle(
+    reader: &ZetRss,
+    schedule: &Schedule,
+    control: &DaemonControl,
+    status: &mut DaemonStatus,
+) -> Result<DateTime<Utc>> {
+    let now = Utc::now();
+    let feeds = reader.store().get_feed_list()?;
+    if control.take_refresh() {
+        status.make_all_due(now);
+    }
+    let due = status.due_feeds(&feeds, now);
+
+    if !due.is_empty() {
+        tracing::info!("Fetching {} due feeds", due.len());
+        let results = reader
+            .fetch_feeds(&due, |event| {
+                if let FetchEvent::Finished(result) = event {
+                    if let Some(ref error) = result.error {
+                        tracing::warn!("{}: {}", result.url, error);
+                    }
+                }
+            })
+            .await;
+        let fetched_at = Utc::now();
+        for result in results {
+            if let Some(feed) = due.iter().find(|f| f.url == result.url) {
+                status.record_fetch(feed, result.items, result.error, fetched_at, schedule);
+            }
+        }
+    }
+
+    // Never spin faster than once a minute, never sleep past the base interval
+    let now = Utc::now();
+    let next = status
+        .next_due()
+        .unwrap_or(later(now, schedule.interval))
+        .clamp(now + Duration::minutes(1), later(now, schedule.interval));
+
+    status.last_cycle = Some(now);
+    status.next_cycle = Some(next);
+    status.unread = reader.stats()?.unread;
+    status.save(reader)?;
+    Ok(next)
+}
+
+/// `interval` after `now`, or the end of time for intervals reaching past it
+fn later(now: DateTime<Utc>, interval: Duration) -> DateTime<Utc> {
+    now.checked_add_signed(interval)
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// Sleeps until the next cycle, waking early when the feed list gains a feed
+/// so newly tagged feeds are fetched without waiting for the base interval
+async fn wait_for_cycle(reader: &ZetRss, status: &DaemonStatus, duration: std::time::Duration) {
+    let until = tokio::time::Instant::now() + duration;
+    loop {
+        let left = until.saturating_duration_since(tokio::time::Instant::now());
+        if left.is_zero() {
+            return;
+        }
+        tokio::time::sleep(left.min(FEED_LIST_CHECK)).await;
+        match reader.store().get_feed_list() {
+            Ok(feeds) if status.has_new_feeds(&feeds) => return,
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to read the feed list: {:#}", e),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use super::*;
+
+fn source(url: &str) -> FeedSource {
+    FeedSource {
+        url: url.to_string(),
+        source_file: String::new(),
+        line_number: 0,
+        root: None,
+        options: Default::default(),
+    }
+}
+
+fn schedule() -> Schedule {
+    Schedule {
+        interval: Duration::hours(1),
+        overrides: HashMap::from([("https://hn.example/rss".to_string(), Duration::minutes(15))]),
+    }
+}
+
+#[test]
+fn test_interval_declared_in_the_zet() {
+    let schedule = schedule();
+    let mut declared = source("https://blog.example/feed");
+    declared.options.interval = Some("6h".to_string());
+    assert_eq!(schedule.interval_for(&declared), Duration::hours(6));
+
+    // Config and command-line overrides win over the note
+    let mut overridden = source("https://hn.example/rss");
+    overridden.options.interval = Some("6h".to_string());
+    assert_eq!(schedule.interval_for(&overridden), Duration::minutes(15));
+
+    assert_eq!(
+        schedule.interval_for(&source("https://other.example/rss")),
+        Duration::hours(1)
+    );
+}
+
+#[test]
+fn test_has_new_feeds() {
+    let mut status = DaemonStatus::default();
+    let mut feeds = vec![source("https://blog.example/feed")];
+    assert!(status.has_new_feeds(&feeds));
+
+    status.due_feeds(&feeds, Utc::now());
+    assert!(!status.has_new_feeds(&feeds));
+    assert!(!status.has_new_feeds(&[]));
+
+    feeds.push(source("https://hn.example/rss"));
+    assert!(status.has_new_feeds(&feeds));
+}
+
+#[test]
+fn test_new_feeds_are_due_immediately() {
+    let mut status = DaemonStatus::default();
+    let now = Utc::now();
+    let feeds = vec![
+        source("https://blog.example/feed"),
+        source("https://hn.example/rss"),
+    ];
+
+    let due = status.due_feeds(&feeds, now);
+    assert_eq!(due.len(), 2);
+    assert_eq!(status.feeds.len(), 2);
+}
+
+#[test]
+fn test_per_feed_intervals() {
+    let mut status = DaemonStatus::default();
+    let schedule = schedule();
+    let now = Utc::now();
+    let feeds = vec![
+        source("https://blog.example/feed"),
+        source("https://hn.example/rss"),
+    ];
+
+    status.due_feeds(&feeds, now);
+    status.record_fetch(&feeds[0], 3, None, now, &schedule);
+    status.record_fetch(&feeds[1], 30, None, now, &schedule);
+
+    assert_eq!(status.next_due(), Some(now + Duration::minutes(15)));
+
+    // After 20 minutes only the overridden feed is due again
+    let due = status.due_feeds(&feeds, now + Duration::minutes(20));
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].url, "https://hn.example/rss");
+
+    // After the base interval both are due
+    let due = status.due_feeds(&feeds, now + Duration::hours(1));
+    assert_eq!(due.len(), 2);
+}
+
+#[test]
+fn test_removed_feeds_are_dropped() {
+    let mut status = DaemonStatus::default();
+    let now = Utc::now();
+    status.due_feeds(
+        &[
+            source("https://a.example/feed"),
+            source("https://b.example/feed"),
+        ],
+        now,
+    );
+
+    status.due_feeds(&[source("https://a.example/feed")], now);
+    assert_eq!(status.feeds.len(), 1);
+    assert_eq!(status.feeds[0].url, "https://a.example/feed");
+}
+
+#[test]
+fn test_refresh_over_the_socket() {
+    let mut status = DaemonStatus::default();
+    let now = Utc::now();
+    let schedule = Schedule {
+        interval: Duration::hours(1),
+        overrides: HashMap::new(),
+    };
+    let feed = source("https://a.example/feed");
+    status.due_feeds(std::slice::from_ref(&feed), now);
+    status.record_fetch(&feed, 1, None, now, &schedule);
+    assert!(status
+        .due_feeds(std::slice::from_ref(&feed), now)
+        .is_empty());
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let reader = ZetRss::open(temp_dir.path().to_path_buf()).unwrap();
+    let control = Arc::new(DaemonControl::default());
+    let server = rpc_server(reader, control.clone());
+    assert!(server.call("status", json!({})).is_err());
+    assert_eq!(
+        server.call("refresh", json!({})).unwrap(),
+        json!({ "refreshing": true })
+    );
+    assert!(control.take_refresh());
+    assert!(!control.take_refresh());
+
+    status.make_all_due(now);
+    assert_eq!(status.due_feeds(&[feed], now).len(), 1);
+}
+
+#[test]
+fn test_status_of_a_dead_daemon() {
+    let mut status = DaemonStatus {
+        running: true,
+        pid: std::process::id(),
+        ..Default::default()
+    };
+    assert!(status.is_alive());
+
+    // Its socket is gone
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    status.socket = Some(temp_dir.path().join("daemon.sock").display().to_string());
+    assert!(!status.is_alive());
+
+    status.mark_stopped();
+    assert!(!status.is_alive());
+}
+
+#[test]
+fn test_status_marked_stopped_on_drop() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join(STATUS_FILE);
+    DaemonStatus {
+        running: true,
+        pid: std::process::id(),
+        socket: Some("/tmp/daemon.sock".to_string()),
+        ..Default::default()
+    }
+    .save_to(&path)
+    .unwrap();
+
+    let result = std::panic::catch_unwind(|| {
+        let _stopped = StoppedOnDrop { path: path.clone() };
+        panic!("cycle blew up");
+    });
+    assert!(result.is_err());
+    let status = DaemonStatus::load_from(&path).unwrap().unwrap();
+    assert!(!status.running);
+    assert!(status.socket.is_none());
+}
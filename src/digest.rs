@@ -0,0 +1,139 @@
+use crate::engine::ZetRss;
+use crate::filter::ArticleFilter;
+use crate::models::FeedItem;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+#[path = "digest_tests.rs"]
+mod tests;
+
+/// Name of the file in the state directory remembering the last digest
+const STATE_FILE: &str = "digest.json";
+
+/// Longest summary kept per article, in characters
+const SUMMARY_LEN: usize = 300;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DigestState {
+    last_digest: Option<DateTime<Utc>>,
+}
+
+/// A digest written to the zet
+#[derive(Debug, Serialize)]
+pub struct Digest {
+    pub path: PathBuf,
+    pub articles: usize,
+    pub feeds: usize,
+}
+
+/// Writes a digest note of unread articles cached since the last digest
+/// With no previous digest the window is the last day; `since` overrides both
+/// Returns None (and leaves the state untouched) when there is nothing new
+pub fn write_digest(
+    reader: &ZetRss,
+    zet_path: &Path,
+    since: Option<DateTime<Utc>>,
+) -> Result<Option<Digest>> {
+    let state_path = reader.store().state_path(STATE_FILE);
+    let state = load_state(&state_path)?;
+    let now = Utc::now();
+
+    let filter = ArticleFilter {
+        read: Some(false),
+        // Windowed on when articles were cached, so one that arrives late
+        // with an old date still makes it into the next digest
+        fetched_since: Some(
+            since
+                .or(state.last_digest)
+                .unwrap_or(now - Duration::days(1)),
+        ),
+        ..Default::default()
+    };
+    let articles = reader.list(&filter.into())?;
+    if articles.is_empty() {
+        return Ok(None);
+    }
+
+    let local = Local::now();
+    let content = render_digest(&articles, &local.format("%Y-%m-%d").to_string());
+    fs::create_dir_all(zet_path)?;
+    let path = crate::notes::unused_path(
+        &zet_path.join(format!("{}-rss-digest.md", local.format("%Y%m%d%H%M"))),
+    );
+    fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let state = DigestState {
+        last_digest: Some(now),
+    };
+    fs::write(&state_path, serde_json::to_string_pretty(&state)?)?;
+
+    Ok(Some(Digest {
+        path,
+        articles: articles.len(),
+        feeds: count_feeds(&articles),
+    }))
+}
+
+/// Renders articles as a markdown note grouped by feed, newest first within each feed
+pub fn render_digest(articles: &[FeedItem], date: &str) -> String {
+    let mut by_feed: BTreeMap<&str, Vec<&FeedItem>> = BTreeMap::new();
+    for article in articles {
+        by_feed.entry(&article.feed_url).or_default().push(article);
+    }
+
+    let mut content = format!("# RSS Digest {}\n\n", date);
+    content.push_str(&format!(
+        "{} new articles from {} feeds\n",
+        articles.len(),
+        by_feed.len()
+    ));
+
+    for (feed_url, mut items) in by_feed {
+        items.sort_by_key(|a| std::cmp::Reverse(a.published));
+        content.push_str(&format!("\n## {}\n\n", feed_url));
+        for item in items {
+            content.push_str(&format!("- [{}]({})\n", item.title, item.link));
+            if let Some(summary) = summarize(item) {
+                content.push_str(&format!("  {}\n", summary));
+            }
+        }
+    }
+
+    content
+}
+
+/// First paragraph of the description (or content) as plain text on one line
+fn summarize(item: &FeedItem) -> Option<String> {
+    let html = item.description.as_deref().or(item.content.as_deref())?;
+    let text = html2text::from_read(html.as_bytes(), 10_000);
+    let first_para = text.split("\n\n").next().unwrap_or("");
+    let line = first_para.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.is_empty() {
+        return None;
+    }
+    if line.chars().count() > SUMMARY_LEN {
+        let truncated: String = line.chars().take(SUMMARY_LEN).collect();
+        return Some(format!("{}…", truncated.trim_end()));
+    }
+    Some(line)
+}
+
+fn count_feeds(articles: &[FeedItem]) -> usize {
+    let mut feeds: Vec<_> = articles.iter().map(|a| &a.feed_url).collect();
+    feeds.sort();
+    feeds.dedup();
+    feeds.len()
+}
+
+fn load_state(path: &Path) -> Result<DigestState> {
+    if !path.exists() {
+        return Ok(DigestState::default());
+    }
+    let content = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
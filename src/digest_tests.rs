@@ -0,0 +1,97 @@
+use super::*;
+use crate::cache::TextCache;
+use crate::fetcher::HttpFetcher;
+use crate::models::Feed;
+use tempfile::TempDir;
+
+fn article(id: &str, feed_url: &str, hours_ago: i64) -> FeedItem {
+    FeedItem {
+        id: id.to_string(),
+        feed_url: feed_url.to_string(),
+        title: format!("Title {}", id),
+        link: format!("https://example.com/{}", id),
+        description: Some(format!("<p>Summary of {}</p><p>Second paragraph</p>", id)),
+        published: Some(Utc::now() - Duration::hours(hours_ago)),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_render_digest_groups_by_feed() {
+    let articles = vec![
+        article("b1", "https://b.example/feed", 2),
+        article("a1", "https://a.example/feed", 5),
+        article("b2", "https://b.example/feed", 1),
+    ];
+    let digest = render_digest(&articles, "2024-03-15");
+
+    assert!(digest.starts_with("# RSS Digest 2024-03-15\n"));
+    assert!(digest.contains("3 new articles from 2 feeds"));
+    let a = digest.find("## https://a.example/feed").unwrap();
+    let b = digest.find("## https://b.example/feed").unwrap();
+    assert!(a < b);
+    // Newest first within a feed
+    assert!(digest.find("Title b2").unwrap() < digest.find("Title b1").unwrap());
+    assert!(digest.contains("- [Title a1](https://example.com/a1)\n  Summary of a1\n"));
+    assert!(!digest.contains("Second paragraph"));
+}
+
+#[test]
+fn test_write_digest_only_includes_newly_cached_articles() {
+    let data_dir = TempDir::new().unwrap();
+    let zet_dir = TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(data_dir.path().to_path_buf()).unwrap();
+    cache
+        .store_feed(&Feed {
+            url: "https://a.example/feed".to_string(),
+            title: "A".to_string(),
+            description: None,
+            last_fetched: Some(Utc::now()),
+            bytes: 0,
+            items: vec![
+                article("fresh", "https://a.example/feed", 1),
+                article("read", "https://a.example/feed", 1),
+            ],
+        })
+        .unwrap();
+    cache.mark_as_read("read").unwrap();
+    let reader = ZetRss::with_parts(cache, HttpFetcher);
+
+    let first = write_digest(&reader, zet_dir.path(), None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(first.articles, 1);
+    assert!(fs::read_to_string(&first.path)
+        .unwrap()
+        .contains("Title fresh"));
+
+    // Nothing new since the last digest
+    assert!(write_digest(&reader, zet_dir.path(), None)
+        .unwrap()
+        .is_none());
+
+    // An article cached late still makes the next digest despite its old
+    // date, which doesn't overwrite one written the same minute
+    reader
+        .store()
+        .store_feed(&Feed {
+            url: "https://a.example/feed".to_string(),
+            title: "A".to_string(),
+            description: None,
+            last_fetched: Some(Utc::now()),
+            bytes: 0,
+            items: vec![article("late", "https://a.example/feed", 48)],
+        })
+        .unwrap();
+    let second = write_digest(&reader, zet_dir.path(), None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(second.articles, 1);
+    assert_ne!(second.path, first.path);
+    assert!(fs::read_to_string(&second.path)
+        .unwrap()
+        .contains("Title late"));
+    assert!(fs::read_to_string(&first.path)
+        .unwrap()
+        .contains("Title fresh"));
+}
@@ -0,0 +1,222 @@
+//! Feeds discovered from the homepages of `#feedsite` sites
+//!
+//! A note can mark a plain homepage with `#feedsite <url>`. Scans fetch the
+//! page once and collect the feeds it announces with `<link rel="alternate">`.
+//! The findings go to `state/discoveries.json` as pending until the user picks
+//! one (`zetrss discoveries --accept`) or turns the site down (`--reject`).
+//! Only accepted feeds join the feed list.
+
+use crate::fetcher::FeedFetcher;
+use crate::scanner::{feed_key, FeedSource};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+#[cfg(test)]
+#[path = "discovery_tests.rs"]
+mod tests;
+
+/// Name of the discovery list in the state directory
+pub const DISCOVERIES_FILE: &str = "discoveries.json";
+
+/// Link types of RSS, Atom and JSON feeds
+const FEED_TYPES: &[&str] = &[
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/feed+json",
+];
+
+/// How long a site that announced no feeds (or couldn't be fetched) rests
+/// before scans look at it again
+fn recheck_after() -> Duration {
+    Duration::days(1)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscoveryStatus {
+    /// Waiting for the user to pick a feed
+    #[default]
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+/// What a `#feedsite` page announced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Discovery {
+    pub site: String,
+    /// Where the site is declared
+    pub source_file: String,
+    pub line_number: usize,
+    /// Feeds linked from the page, in page order
+    pub feeds: Vec<String>,
+    pub status: DiscoveryStatus,
+    /// The accepted feed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed: Option<String>,
+    /// Why the page couldn't be fetched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub checked: DateTime<Utc>,
+}
+
+impl Discovery {
+    /// Whether a scan at `now` should fetch the page again
+    fn needs_check(&self, now: DateTime<Utc>) -> bool {
+        self.status == DiscoveryStatus::Pending
+            && self.feeds.is_empty()
+            && now - self.checked >= recheck_after()
+    }
+}
+
+/// Feed URLs announced by `<link rel="alternate">` tags of an HTML page,
+/// resolved against `base_url`
+pub fn feed_links(html: &str, base_url: &str) -> Vec<String> {
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    static ATTR: OnceLock<Regex> = OnceLock::new();
+    let link = LINK.get_or_init(|| Regex::new(r"(?is)<link\b[^>]*>").unwrap());
+    let attr = ATTR.get_or_init(|| {
+        Regex::new(r#"(?is)\b(rel|type|href)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap()
+    });
+
+    let base = Url::parse(base_url).ok();
+    let mut feeds: Vec<String> = Vec::new();
+    for tag in link.find_iter(html) {
+        let (mut rel, mut kind, mut href) = (String::new(), String::new(), None);
+        for cap in attr.captures_iter(tag.as_str()) {
+            let value = cap
+                .get(2)
+                .or_else(|| cap.get(3))
+                .or_else(|| cap.get(4))
+                .map_or("", |m| m.as_str())
+                .trim();
+            match cap[1].to_ascii_lowercase().as_str() {
+                "rel" => rel = value.to_ascii_lowercase(),
+                "type" => kind = value.to_ascii_lowercase(),
+                _ => href = Some(value.replace("&amp;", "&")),
+            }
+        }
+        let Some(href) = href.filter(|h| !h.is_empty()) else {
+            continue;
+        };
+        if !rel.split_whitespace().any(|r| r == "alternate") || !FEED_TYPES.contains(&kind.as_str())
+        {
+            continue;
+        }
+        let url = match &base {
+            Some(base) => base.join(&href).map(String::from).unwrap_or(href),
+            None => href,
+        };
+        if !feeds.contains(&url) {
+            feeds.push(url);
+        }
+    }
+    feeds
+}
+
+/// Fetches a site's page and records the feeds it announces
+pub async fn discover(
+    fetcher: &impl FeedFetcher,
+    site: &FeedSource,
+    now: DateTime<Utc>,
+) -> Discovery {
+    let (feeds, error) = match fetcher.fetch_page(&site.url).await {
+        Ok(html) => (feed_links(&html, &site.url), None),
+        Err(e) => (Vec::new(), Some(e.to_string())),
+    };
+    Discovery {
+        site: site.url.clone(),
+        source_file: site.source_file.clone(),
+        line_number: site.line_number,
+        feeds,
+        status: DiscoveryStatus::Pending,
+        feed: None,
+        error,
+        checked: now,
+    }
+}
+
+/// The discoveries for `sites`: known sites keep their entry (with the
+/// declaration's current location), new ones and those due for another look
+/// are fetched, and sites no longer declared are forgotten
+pub async fn refresh(
+    fetcher: &impl FeedFetcher,
+    known: &[Discovery],
+    sites: &[FeedSource],
+    now: DateTime<Utc>,
+) -> Vec<Discovery> {
+    let mut discoveries = Vec::new();
+    for site in sites {
+        let key = feed_key(&site.url);
+        match known.iter().find(|d| feed_key(&d.site) == key) {
+            Some(found) if !found.needs_check(now) => discoveries.push(Discovery {
+                source_file: site.source_file.clone(),
+                line_number: site.line_number,
+                ..found.clone()
+            }),
+            _ => discoveries.push(discover(fetcher, site, now).await),
+        }
+    }
+    discoveries
+}
+
+/// The accepted feeds of sites still declared, each attributed to its
+/// `#feedsite` marker and carrying the marker's options
+pub fn accepted_feeds(discoveries: &[Discovery], sites: &[FeedSource]) -> Vec<FeedSource> {
+    discoveries
+        .iter()
+        .filter(|d| d.status == DiscoveryStatus::Accepted)
+        .filter_map(|d| {
+            let site = sites
+                .iter()
+                .find(|s| feed_key(&s.url) == feed_key(&d.site))?;
+            Some(FeedSource {
+                url: d.feed.clone()?,
+                ..site.clone()
+            })
+        })
+        .collect()
+}
+
+/// Marks the site's discovery accepted with `feed`, or its first announced
+/// feed; returns the accepted feed as a feed list entry
+pub fn accept(discoveries: &mut [Discovery], site: &str, feed: Option<&str>) -> Result<FeedSource> {
+    let discovery = find(discoveries, site)?;
+    let feed = match feed {
+        Some(feed) => feed.to_string(),
+        None => discovery
+            .feeds
+            .first()
+            .cloned()
+            .ok_or_else(|| anyhow!("{} announces no feeds; pass --feed", discovery.site))?,
+    };
+    discovery.status = DiscoveryStatus::Accepted;
+    discovery.feed = Some(feed.clone());
+    Ok(FeedSource {
+        url: feed,
+        source_file: discovery.source_file.clone(),
+        line_number: discovery.line_number,
+        root: None,
+        options: Default::default(),
+    })
+}
+
+/// Marks the site's discovery rejected, so its feeds stay out of the feed list
+pub fn reject(discoveries: &mut [Discovery], site: &str) -> Result<()> {
+    let discovery = find(discoveries, site)?;
+    discovery.status = DiscoveryStatus::Rejected;
+    discovery.feed = None;
+    Ok(())
+}
+
+fn find<'a>(discoveries: &'a mut [Discovery], site: &str) -> Result<&'a mut Discovery> {
+    let key = feed_key(site);
+    discoveries
+        .iter_mut()
+        .find(|d| feed_key(&d.site) == key)
+        .ok_or_else(|| anyhow!("No discovery for {}", site))
+}
@@ -0,0 +1,114 @@
+use super::*;
+use anyhow::anyhow;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+fn site(url: &str, line_number: usize) -> FeedSource {
+    FeedSource {
+        url: url.to_string(),
+        source_file: "blogs.md".to_string(),
+        line_number,
+        root: None,
+        options: Default::default(),
+    }
+}
+
+/// Serves a page announcing one feed for "up" sites and fails otherwise,
+/// counting the pages requested
+#[derive(Default)]
+struct FakeFetcher {
+    pages: AtomicUsize,
+}
+
+impl FeedFetcher for FakeFetcher {
+    async fn fetch_feed(&self, _url: &str) -> Result<crate::models::Feed> {
+        Err(anyhow!("not a feed"))
+    }
+
+    fn fetch_page(&self, url: &str) -> impl Future<Output = Result<String>> + Send {
+        self.pages.fetch_add(1, Ordering::SeqCst);
+        let up = url.contains("up");
+        async move {
+            if !up {
+                return Err(anyhow!("connection refused"));
+            }
+            Ok(r#"<link rel="alternate" type="application/atom+xml" href="atom.xml">"#.to_string())
+        }
+    }
+}
+
+#[test]
+fn test_feed_links() {
+    let html = r#"<html><head>
+        <link rel="stylesheet" href="/style.css">
+        <link rel="alternate" type="application/rss+xml" title="Posts" href="/feed.xml">
+        <LINK TYPE='application/atom+xml' REL='alternate' HREF='atom.xml?a=1&amp;b=2'>
+        <link rel="alternate" type="application/json+oembed" href="/oembed">
+        <link rel="alternate" hreflang="de" href="/de/">
+        <link rel=alternate type=application/feed+json href=https://cdn.example.org/feed.json>
+        <link rel="alternate" type="application/rss+xml" href="https://blog.example.com/feed.xml">
+    </head></html>"#;
+    assert_eq!(
+        feed_links(html, "https://blog.example.com/posts/"),
+        vec![
+            "https://blog.example.com/feed.xml",
+            "https://blog.example.com/posts/atom.xml?a=1&b=2",
+            "https://cdn.example.org/feed.json",
+        ]
+    );
+    assert!(feed_links("<p>No head</p>", "https://blog.example.com/").is_empty());
+}
+
+#[tokio::test]
+async fn test_refresh_fetches_new_and_failed_sites_only() {
+    let fetcher = FakeFetcher::default();
+    let now = Utc::now();
+    let sites = vec![
+        site("https://up.example.com/", 1),
+        site("https://down.example.com/", 2),
+    ];
+
+    let found = refresh(&fetcher, &[], &sites, now).await;
+    assert_eq!(fetcher.pages.load(Ordering::SeqCst), 2);
+    assert_eq!(found[0].feeds, vec!["https://up.example.com/atom.xml"]);
+    assert!(found[1].error.is_some());
+
+    // Moved markers keep their findings; failures wait a day to be retried
+    let sites = vec![site("https://up.example.com/", 5)];
+    let again = refresh(&fetcher, &found, &sites, now + Duration::hours(1)).await;
+    assert_eq!(fetcher.pages.load(Ordering::SeqCst), 2);
+    assert_eq!(again.len(), 1);
+    assert_eq!(again[0].line_number, 5);
+
+    let sites = vec![site("https://down.example.com/", 2)];
+    refresh(&fetcher, &found, &sites, now + Duration::days(2)).await;
+    assert_eq!(fetcher.pages.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_only_accepted_feeds_of_declared_sites_are_used() {
+    let fetcher = FakeFetcher::default();
+    let sites = vec![
+        site("https://up.example.com/", 1),
+        site("https://up2.example.com/", 2),
+    ];
+    let mut found = refresh(&fetcher, &[], &sites, Utc::now()).await;
+    assert!(accepted_feeds(&found, &sites).is_empty());
+
+    let accepted = accept(&mut found, "http://www.up.example.com", None).unwrap();
+    assert_eq!(accepted.url, "https://up.example.com/atom.xml");
+    accept(
+        &mut found,
+        "https://up2.example.com/",
+        Some("https://up2.example.com/rss"),
+    )
+    .unwrap();
+    reject(&mut found, "https://up2.example.com/").unwrap();
+    assert!(accept(&mut found, "https://elsewhere.example.com/", None).is_err());
+
+    let feeds = accepted_feeds(&found, &sites);
+    assert_eq!(feeds.len(), 1);
+    assert_eq!(feeds[0].url, "https://up.example.com/atom.xml");
+    assert_eq!(feeds[0].line_number, 1);
+    assert!(accepted_feeds(&found, &sites[1..]).is_empty());
+}
@@ -0,0 +1,397 @@
+use crate::cache::{ArticleStore, TextCache};
+use crate::config::{self, Config};
+use crate::fetcher::FeedFetcher;
+use crate::models::FeedItem;
+use crate::notes;
+use crate::quarantine;
+use crate::scanner::FeedSource;
+use chrono::{DateTime, Utc};
+use futures::future;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+#[cfg(test)]
+#[path = "doctor_tests.rs"]
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// Result of one diagnostic check, with a suggested fix when it didn't pass
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// State of the installation for `:checkhealth` (`zetrss healthcheck`):
+/// facts the plugin shows, plus the checks that need no network
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub version: &'static str,
+    pub data_dir: String,
+    pub feeds: usize,
+    pub index: IndexHealth,
+    /// Latest fetch attempt of any feed
+    pub last_fetch: Option<DateTime<Utc>>,
+    pub checks: Vec<Check>,
+}
+
+/// Whether the SQLite index opens, and the counts it answers with
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IndexHealth {
+    pub path: String,
+    pub ok: bool,
+    pub articles: usize,
+    pub unread: usize,
+    pub error: Option<String>,
+}
+
+/// Reports which environment variables decide the data directory
+pub fn check_environment() -> Check {
+    const NAME: &str = "environment";
+    if let Ok(dir) = std::env::var("ZETRSS_DATA_DIR") {
+        return Check::ok(NAME, format!("ZETRSS_DATA_DIR={}", dir));
+    }
+    if let Ok(dir) = std::env::var("XDG_DATA_HOME") {
+        return Check::ok(NAME, format!("XDG_DATA_HOME={}", dir));
+    }
+    if std::env::var("HOME").is_err() {
+        return Check::warn(
+            NAME,
+            "Neither ZETRSS_DATA_DIR, XDG_DATA_HOME nor HOME is set; data goes to ./.local/share/zetrss",
+            "Set ZETRSS_DATA_DIR to the directory ZetRss should use",
+        );
+    }
+    Check::ok(NAME, "Using the default ~/.local/share/zetrss")
+}
+
+/// Checks that the config file, if present, parses
+pub fn check_config(path: &Path) -> Check {
+    const NAME: &str = "config";
+    if !path.exists() {
+        return Check::ok(
+            NAME,
+            format!("No config file at {}; using defaults", path.display()),
+        );
+    }
+    match Config::load(Some(path)) {
+        Ok(_) => Check::ok(NAME, format!("Loaded {}", path.display())),
+        Err(e) => Check::fail(
+            NAME,
+            format!("{:#}", e),
+            "Fix the listed key, or move the file aside to use the defaults",
+        ),
+    }
+}
+
+/// Checks that the note template (`[notes] template`) can be read and
+/// renders an article
+pub fn check_note_template(path: Option<&str>) -> Check {
+    const NAME: &str = "note template";
+    let Some(path) = path else {
+        return Check::ok(NAME, "Using the built-in template");
+    };
+    let sample = FeedItem {
+        id: "doctor".to_string(),
+        feed_url: "https://example.com/feed.xml".to_string(),
+        title: "Example article".to_string(),
+        link: "https://example.com/article".to_string(),
+        published: Some(chrono::Utc::now()),
+        author: Some("Example author".to_string()),
+        content: Some("<p>Example summary.</p>".to_string()),
+        tags: vec!["example".to_string()],
+        ..Default::default()
+    };
+    match notes::load_template(Some(path), Default::default())
+        .and_then(|template| notes::render_note(&template, &sample, &["Quote".to_string()]))
+    {
+        Ok(_) => Check::ok(NAME, format!("{} renders", path)),
+        Err(e) => Check::fail(
+            NAME,
+            format!("{:#}", e),
+            "Fix the template, or remove `[notes] template` to use the built-in one",
+        ),
+    }
+}
+
+/// Checks that the data directory exists (or can be created) and is writable
+pub fn check_data_dir(dir: &Path) -> Check {
+    const NAME: &str = "data dir";
+    let fix = "Set ZETRSS_DATA_DIR to a writable directory, or fix the permissions";
+    if let Err(e) = fs::create_dir_all(dir) {
+        return Check::fail(NAME, format!("Can't create {}: {}", dir.display(), e), fix);
+    }
+
+    let probe = dir.join(format!(".doctor-{}", std::process::id()));
+    match fs::write(&probe, b"ok") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            Check::ok(NAME, format!("{} is writable", dir.display()))
+        }
+        Err(e) => Check::fail(
+            NAME,
+            format!("Can't write to {}: {}", dir.display(), e),
+            fix,
+        ),
+    }
+}
+
+/// Checks that the zet directory exists and contains notes
+pub fn check_zet_path(zet_path: &Path) -> Check {
+    const NAME: &str = "zet path";
+    if !zet_path.is_dir() {
+        return Check::fail(
+            NAME,
+            format!("{} is not a directory", zet_path.display()),
+            "Set `zet_path` in the config file (or pass --path), or create the directory",
+        );
+    }
+
+    let notes = WalkDir::new(zet_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| crate::scanner::is_note(e.path(), &crate::config::get().scan.extensions))
+        .count();
+    if notes == 0 {
+        return Check::warn(
+            NAME,
+            format!("No notes found in {}", zet_path.display()),
+            "Check that --path points at your zet",
+        );
+    }
+    Check::ok(NAME, format!("{} notes in {}", notes, zet_path.display()))
+}
+
+/// Checks that the feed list parses and isn't empty
+pub fn check_feed_list(cache: &TextCache) -> Check {
+    const NAME: &str = "feed list";
+    match cache.get_feed_list() {
+        Ok(feeds) if feeds.is_empty() => Check::warn(
+            NAME,
+            "No feeds in the feed list",
+            "Run `zetrss scan` or `zetrss add <url>`",
+        ),
+        Ok(feeds) => Check::ok(NAME, format!("{} feeds", feeds.len())),
+        Err(e) => Check::fail(
+            NAME,
+            format!("Can't read the feed list: {:#}", e),
+            "Run `zetrss scan` to rebuild state/feeds.json",
+        ),
+    }
+}
+
+/// Checks that every cached article file parses
+pub fn check_cache(cache: &TextCache) -> Check {
+    const NAME: &str = "cache integrity";
+    match cache.find_corrupt_articles() {
+        Ok(corrupt) if corrupt.is_empty() => Check::ok(NAME, "All article files parse"),
+        Ok(corrupt) => {
+            let files: Vec<String> = corrupt
+                .iter()
+                .map(|(path, reason)| format!("{} ({})", path.display(), reason))
+                .collect();
+            Check::warn(
+                NAME,
+                format!(
+                    "{} unreadable article files: {}",
+                    corrupt.len(),
+                    files.join(", ")
+                ),
+                "Run `zetrss reindex` to move them to quarantine/, or delete them; `fetch` re-creates them",
+            )
+        }
+        Err(e) => Check::fail(
+            NAME,
+            format!("Can't read the articles directory: {:#}", e),
+            "Check the permissions of the data directory",
+        ),
+    }
+}
+
+/// Reports malformed article files set aside in `quarantine/`
+pub fn check_quarantine(cache: &TextCache) -> Check {
+    const NAME: &str = "quarantine";
+    match cache.quarantined() {
+        Ok(entries) if entries.is_empty() => Check::ok(NAME, "No quarantined articles"),
+        Ok(entries) => {
+            let files: Vec<String> = entries
+                .iter()
+                .map(|entry| format!("{} ({})", entry.original, entry.reason))
+                .collect();
+            Check::warn(
+                NAME,
+                format!(
+                    "{} malformed article files quarantined: {}",
+                    entries.len(),
+                    files.join(", ")
+                ),
+                format!(
+                    "Fix them and move them back to articles/, or delete them from {}",
+                    quarantine::dir(cache.base_dir()).display()
+                ),
+            )
+        }
+        Err(e) => Check::fail(
+            NAME,
+            format!("Can't read the quarantine log: {:#}", e),
+            "Check the permissions of the data directory",
+        ),
+    }
+}
+
+/// Fetches up to `limit` feeds to check they are reachable and parse
+pub async fn check_feeds<F: FeedFetcher>(fetcher: &F, feeds: &[FeedSource], limit: usize) -> Check {
+    const NAME: &str = "feeds reachable";
+    let sample = &feeds[..feeds.len().min(limit)];
+    if sample.is_empty() {
+        return Check::ok(NAME, "No feeds to check");
+    }
+
+    let results = future::join_all(sample.iter().map(|feed| fetcher.fetch_feed(&feed.url))).await;
+    let failures: Vec<String> = sample
+        .iter()
+        .zip(results)
+        .filter_map(|(feed, result)| result.err().map(|e| format!("{} ({})", feed.url, e)))
+        .collect();
+
+    if failures.is_empty() {
+        Check::ok(
+            NAME,
+            format!("{} of {} feeds fetched", sample.len(), sample.len()),
+        )
+    } else {
+        Check::warn(
+            NAME,
+            format!(
+                "{} of {} feeds failed: {}",
+                failures.len(),
+                sample.len(),
+                failures.join(", ")
+            ),
+            "Check your network, then fix or `zetrss remove` the failing feeds",
+        )
+    }
+}
+
+/// Builds the health report of `data_dir` without touching the network or
+/// walking the zet, so `:checkhealth` stays fast
+pub fn health_report(data_dir: &Path) -> HealthReport {
+    let mut report = HealthReport {
+        version: env!("CARGO_PKG_VERSION"),
+        data_dir: data_dir.display().to_string(),
+        feeds: 0,
+        index: IndexHealth {
+            path: data_dir.join("index.sqlite").display().to_string(),
+            ..Default::default()
+        },
+        last_fetch: None,
+        checks: vec![
+            check_config(&config::config_path()),
+            check_environment(),
+            check_data_dir(data_dir),
+            check_note_template(config::get().notes.template.as_deref()),
+        ],
+    };
+
+    let cache = match TextCache::with_base_dir(data_dir.to_path_buf()) {
+        Ok(cache) => cache,
+        Err(e) => {
+            report.index.error = Some(format!("{:#}", e));
+            report.checks.push(Check::fail(
+                "cache",
+                format!("Can't open the cache: {:#}", e),
+                "Run `zetrss reindex`, or fix the data dir problem above",
+            ));
+            return report;
+        }
+    };
+    match cache.feed_counts() {
+        Ok(counts) => {
+            report.index.ok = true;
+            report.index.articles = counts.iter().map(|c| c.total).sum();
+            report.index.unread = counts.iter().map(|c| c.unread).sum();
+        }
+        Err(e) => report.index.error = Some(format!("{:#}", e)),
+    }
+    let feeds = cache.get_feed_list().unwrap_or_default();
+    report.feeds = feeds.len();
+    report.last_fetch = feeds
+        .iter()
+        .filter_map(|feed| cache.fetch_history(&feed.url).ok()?.last().map(|r| r.time))
+        .max();
+    report.checks.push(check_feed_list(&cache));
+    report.checks.push(check_quarantine(&cache));
+    report
+}
+
+/// Runs every check; feeds are only pinged when the cache could be opened
+pub async fn run_checks<F: FeedFetcher>(
+    fetcher: &F,
+    data_dir: &Path,
+    zet_roots: &[String],
+    ping: usize,
+) -> Vec<Check> {
+    let mut checks = vec![
+        check_config(&config::config_path()),
+        check_environment(),
+        check_data_dir(data_dir),
+    ];
+    checks.extend(zet_roots.iter().map(|root| check_zet_path(Path::new(root))));
+    checks.push(check_note_template(config::get().notes.template.as_deref()));
+
+    match TextCache::with_base_dir(data_dir.to_path_buf()) {
+        Ok(cache) => {
+            checks.push(check_feed_list(&cache));
+            checks.push(check_cache(&cache));
+            checks.push(check_quarantine(&cache));
+            let feeds = cache.get_feed_list().unwrap_or_default();
+            checks.push(check_feeds(fetcher, &feeds, ping).await);
+        }
+        Err(e) => checks.push(Check::fail(
+            "cache",
+            format!("Can't open the cache: {:#}", e),
+            "Fix the data dir problem above",
+        )),
+    }
+
+    checks
+}
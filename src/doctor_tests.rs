@@ -0,0 +1,188 @@
+use super::*;
+use crate::models::Feed;
+use anyhow::{anyhow, Result};
+use std::future::Future;
+use tempfile::TempDir;
+
+/// Fails for any URL containing "down"
+struct FakeFetcher;
+
+impl FeedFetcher for FakeFetcher {
+    fn fetch_feed(&self, url: &str) -> impl Future<Output = Result<Feed>> + Send {
+        let url = url.to_string();
+        async move {
+            if url.contains("down") {
+                return Err(anyhow!("connection refused"));
+            }
+            Ok(Feed {
+                url,
+                title: "Fake".to_string(),
+                description: None,
+                last_fetched: None,
+                bytes: 0,
+                items: Vec::new(),
+            })
+        }
+    }
+}
+
+fn source(url: &str) -> FeedSource {
+    FeedSource {
+        url: url.to_string(),
+        source_file: String::new(),
+        line_number: 0,
+        root: None,
+        options: Default::default(),
+    }
+}
+
+#[test]
+fn test_check_data_dir_and_zet_path() {
+    let temp_dir = TempDir::new().unwrap();
+    assert_eq!(check_data_dir(temp_dir.path()).status, CheckStatus::Ok);
+
+    let missing = temp_dir.path().join("missing-zet");
+    let check = check_zet_path(&missing);
+    assert_eq!(check.status, CheckStatus::Fail);
+    assert!(check.fix.is_some());
+
+    assert_eq!(check_zet_path(temp_dir.path()).status, CheckStatus::Warn);
+    fs::write(
+        temp_dir.path().join("note.md"),
+        "#feed https://example.com/rss",
+    )
+    .unwrap();
+    assert_eq!(check_zet_path(temp_dir.path()).status, CheckStatus::Ok);
+}
+
+#[test]
+fn test_check_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("config.toml");
+    assert_eq!(check_config(&path).status, CheckStatus::Ok);
+
+    fs::write(&path, "zet_path = \"~/zet\"").unwrap();
+    assert_eq!(check_config(&path).status, CheckStatus::Ok);
+
+    fs::write(&path, "zet_pth = \"~/zet\"").unwrap();
+    let check = check_config(&path);
+    assert_eq!(check.status, CheckStatus::Fail);
+    assert!(check.detail.contains("zet_pth"));
+}
+
+#[test]
+fn test_check_note_template() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("note.md");
+    let path_str = path.to_string_lossy().to_string();
+    assert_eq!(check_note_template(None).status, CheckStatus::Ok);
+    assert_eq!(
+        check_note_template(Some(&path_str)).status,
+        CheckStatus::Fail
+    );
+
+    fs::write(&path, "# {{ title }}\n{{ quotes | first | blockquote }}").unwrap();
+    assert_eq!(check_note_template(Some(&path_str)).status, CheckStatus::Ok);
+
+    fs::write(&path, "# {{ titel }}").unwrap();
+    let check = check_note_template(Some(&path_str));
+    assert_eq!(check.status, CheckStatus::Fail);
+    assert!(check.detail.contains("titel"), "{}", check.detail);
+}
+
+#[test]
+fn test_check_cache_reports_corrupt_articles() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    assert_eq!(check_cache(&cache).status, CheckStatus::Ok);
+    assert_eq!(check_feed_list(&cache).status, CheckStatus::Warn);
+
+    fs::write(temp_dir.path().join("articles/broken.md"), "no frontmatter").unwrap();
+    let check = check_cache(&cache);
+    assert_eq!(check.status, CheckStatus::Warn);
+    assert!(check.detail.contains("broken.md"));
+
+    fs::write(temp_dir.path().join("state/feeds.json"), "not json").unwrap();
+    assert_eq!(check_feed_list(&cache).status, CheckStatus::Fail);
+}
+
+#[test]
+fn test_check_quarantine_lists_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    assert_eq!(check_quarantine(&cache).status, CheckStatus::Ok);
+
+    let articles = temp_dir.path().join("articles");
+    fs::write(articles.join("broken.md"), "no frontmatter").unwrap();
+    quarantine::quarantine(
+        temp_dir.path(),
+        &articles,
+        &articles.join("broken.md"),
+        "expected YAML frontmatter",
+    )
+    .unwrap();
+    let check = check_quarantine(&cache);
+    assert_eq!(check.status, CheckStatus::Warn);
+    assert!(check.detail.starts_with("1 malformed"));
+    assert!(check
+        .detail
+        .contains("broken.md (expected YAML frontmatter)"));
+}
+
+#[tokio::test]
+async fn test_check_feeds_samples_and_reports_failures() {
+    let feeds = vec![
+        source("https://up.example/feed"),
+        source("https://down.example/feed"),
+        source("https://down.example/never-pinged"),
+    ];
+
+    let check = check_feeds(&FakeFetcher, &feeds, 2).await;
+    assert_eq!(check.status, CheckStatus::Warn);
+    assert!(check.detail.starts_with("1 of 2 feeds failed"));
+    assert!(!check.detail.contains("never-pinged"));
+
+    let check = check_feeds(&FakeFetcher, &feeds, 1).await;
+    assert_eq!(check.status, CheckStatus::Ok);
+}
+
+#[test]
+fn test_health_report() {
+    let dir = TempDir::new().unwrap();
+    let report = health_report(dir.path());
+    assert_eq!(report.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(report.feeds, 0);
+    assert!(report.index.ok);
+    assert_eq!(report.index.articles, 0);
+    assert!(report.last_fetch.is_none());
+    assert!(report
+        .checks
+        .iter()
+        .any(|c| c.name == "feed list" && c.status == CheckStatus::Warn));
+
+    let cache = TextCache::with_base_dir(dir.path().to_path_buf()).unwrap();
+    cache
+        .store_feed_list(vec![source("https://example.com/feed")])
+        .unwrap();
+    cache
+        .record_fetch(
+            "https://example.com/feed",
+            &crate::history::FetchRecord {
+                time: Utc::now(),
+                status: crate::history::FetchStatus::Ok,
+                items: 1,
+                added: 1,
+                duration_ms: 5,
+                bytes: 10,
+                error: None,
+            },
+        )
+        .unwrap();
+    let report = health_report(dir.path());
+    assert_eq!(report.feeds, 1);
+    assert!(report.last_fetch.is_some());
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["index"]["ok"], true);
+    assert!(json["checks"].is_array());
+}
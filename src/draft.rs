@@ -0,0 +1,168 @@
+//! Note buffer edited next to the article in the viewer's split-screen mode
+//!
+//! A plain line editor: typing inserts at the cursor, Enter splits the line,
+//! Backspace/Delete join lines at the edges, and the arrows, Home and End
+//! move the cursor.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use unicode_width::UnicodeWidthStr;
+
+#[cfg(test)]
+#[path = "draft_tests.rs"]
+mod tests;
+
+pub struct NoteDraft {
+    lines: Vec<String>,
+    row: usize,
+    /// Cursor position in chars within the current line
+    col: usize,
+    /// First line shown
+    scroll: usize,
+    /// The text the draft started from, to tell whether it was edited
+    original: String,
+}
+
+impl NoteDraft {
+    /// A draft of `text` with the cursor at its end
+    pub fn new(text: &str) -> Self {
+        let mut lines: Vec<String> = text.split('\n').map(String::from).collect();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        let row = lines.len() - 1;
+        let col = lines[row].chars().count();
+        Self {
+            lines,
+            row,
+            col,
+            scroll: 0,
+            original: text.to_string(),
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.text() != self.original
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    /// Cursor as (line, display column)
+    pub fn cursor(&self) -> (usize, usize) {
+        let line = &self.lines[self.row];
+        (self.row, line[..byte_index(line, self.col)].width())
+    }
+
+    /// Applies an editing or cursor key; false if the key means nothing here
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char(c) => self.insert(c),
+            KeyCode::Tab => {
+                for _ in 0..4 {
+                    self.insert(' ');
+                }
+            }
+            KeyCode::Enter => self.newline(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.left(),
+            KeyCode::Right => self.right(),
+            KeyCode::Up => self.vertical(-1),
+            KeyCode::Down => self.vertical(1),
+            KeyCode::Home => self.col = 0,
+            KeyCode::End => self.col = self.line_len(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Scrolls so the cursor line is within a view `height` lines tall
+    pub fn scroll_to_cursor(&mut self, height: usize) {
+        if self.row < self.scroll {
+            self.scroll = self.row;
+        } else if height > 0 && self.row >= self.scroll + height {
+            self.scroll = self.row + 1 - height;
+        }
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    fn insert(&mut self, c: char) {
+        let line = &mut self.lines[self.row];
+        line.insert(byte_index(line, self.col), c);
+        self.col += 1;
+    }
+
+    fn newline(&mut self) {
+        let line = &mut self.lines[self.row];
+        let rest = line.split_off(byte_index(line, self.col));
+        self.row += 1;
+        self.lines.insert(self.row, rest);
+        self.col = 0;
+    }
+
+    fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            let line = &mut self.lines[self.row];
+            line.remove(byte_index(line, self.col));
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.col < self.line_len() {
+            let line = &mut self.lines[self.row];
+            line.remove(byte_index(line, self.col));
+        } else if self.row + 1 < self.lines.len() {
+            let next = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&next);
+        }
+    }
+
+    fn left(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.line_len();
+        }
+    }
+
+    fn right(&mut self) {
+        if self.col < self.line_len() {
+            self.col += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    fn vertical(&mut self, delta: isize) {
+        self.row = self
+            .row
+            .saturating_add_signed(delta)
+            .min(self.lines.len() - 1);
+        self.col = self.col.min(self.line_len());
+    }
+}
+
+/// Byte offset of the `col`th char of `line`
+fn byte_index(line: &str, col: usize) -> usize {
+    line.char_indices().nth(col).map_or(line.len(), |(i, _)| i)
+}
@@ -0,0 +1,58 @@
+use super::*;
+use crossterm::event::KeyModifiers;
+
+fn press(draft: &mut NoteDraft, codes: &[KeyCode]) {
+    for code in codes {
+        draft.handle_key(KeyEvent::new(*code, KeyModifiers::NONE));
+    }
+}
+
+fn type_text(draft: &mut NoteDraft, text: &str) {
+    for c in text.chars() {
+        press(draft, &[KeyCode::Char(c)]);
+    }
+}
+
+#[test]
+fn test_typing_at_the_end() {
+    let mut draft = NoteDraft::new("# Title\n\n## Notes\n\n");
+    assert!(!draft.is_modified());
+    type_text(&mut draft, "Good point");
+    press(&mut draft, &[KeyCode::Enter]);
+    type_text(&mut draft, "über");
+    assert_eq!(draft.text(), "# Title\n\n## Notes\n\nGood point\nüber");
+    assert!(draft.is_modified());
+    assert_eq!(draft.cursor(), (5, 4));
+}
+
+#[test]
+fn test_editing_across_lines() {
+    let mut draft = NoteDraft::new("ab\ncd");
+    // Backspace at the start of a line joins it to the previous one
+    press(&mut draft, &[KeyCode::Home, KeyCode::Backspace]);
+    assert_eq!(draft.text(), "abcd");
+    assert_eq!(draft.cursor(), (0, 2));
+
+    press(&mut draft, &[KeyCode::Enter]);
+    assert_eq!(draft.text(), "ab\ncd");
+    press(&mut draft, &[KeyCode::Up, KeyCode::End, KeyCode::Delete]);
+    assert_eq!(draft.text(), "abcd");
+
+    press(&mut draft, &[KeyCode::Left]);
+    type_text(&mut draft, "é");
+    assert_eq!(draft.text(), "aébcd");
+}
+
+#[test]
+fn test_cursor_stays_in_view() {
+    let text = (0..20)
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut draft = NoteDraft::new(&text);
+    draft.scroll_to_cursor(5);
+    assert_eq!(draft.scroll(), 15);
+    press(&mut draft, &[KeyCode::Up; 10]);
+    draft.scroll_to_cursor(5);
+    assert_eq!(draft.scroll(), 9);
+}
@@ -0,0 +1,446 @@
+//! Async facade over scanning, fetching and the article cache
+//!
+//! Lets other Rust tools embed ZetRss without shelling out to the CLI:
+//!
+//! ```no_run
+//! # async fn demo() -> anyhow::Result<()> {
+//! use zetrss::engine::ZetRss;
+//! use zetrss::filter::{ArticleFilter, ArticleQuery};
+//!
+//! let reader = ZetRss::new()?;
+//! reader.scan("/home/me/zet").await?;
+//! reader.fetch(|_| {}).await?;
+//! let unread = reader.list(&ArticleQuery {
+//!     filter: ArticleFilter { read: Some(false), ..Default::default() },
+//!     limit: Some(10),
+//!     ..Default::default()
+//! })?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cache::{ArticleStore, Retention, TextCache};
+use crate::discovery;
+use crate::fetcher::{FeedFetcher, HttpFetcher};
+use crate::filter::{ArticleFilter, ArticleQuery};
+use crate::history::{FetchRecord, FetchStatus};
+use crate::images::{self, ArticleImage};
+use crate::models::{ArticleSummary, FeedItem};
+use crate::scanner::{FeedIndex, FeedSource};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::Instant;
+use unicode_width::UnicodeWidthStr;
+
+#[cfg(test)]
+#[path = "engine_tests.rs"]
+mod tests;
+
+/// Default number of feeds fetched at the same time
+pub const MAX_CONCURRENT_FETCHES: usize = 5;
+
+/// Progress notifications emitted while fetching
+#[derive(Debug, Clone)]
+pub enum FetchEvent {
+    Started { url: String },
+    Finished(FeedFetchResult),
+}
+
+/// Outcome of fetching and storing a single feed
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedFetchResult {
+    pub url: String,
+    /// Number of items in the fetched feed
+    pub items: usize,
+    /// Articles that weren't cached before
+    pub added: usize,
+    /// Articles deleted afterwards by the feed's retention limits
+    pub pruned: usize,
+    /// Set when the feed couldn't be fetched or stored
+    pub error: Option<String>,
+}
+
+/// Article counts across the whole cache
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LibraryStats {
+    pub total: usize,
+    pub unread: usize,
+    pub starred: usize,
+    /// Malformed article files moved to `quarantine/`
+    pub quarantined: usize,
+    pub feeds: Vec<FeedStats>,
+}
+
+/// One page of matching articles with what's needed to ask for the next
+#[derive(Debug, Clone, Serialize)]
+pub struct ArticlePage {
+    pub articles: Vec<ArticleSummary>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+    /// More articles match after this page
+    pub has_more: bool,
+}
+
+/// Article counts for a single feed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FeedStats {
+    pub url: String,
+    pub total: usize,
+    pub unread: usize,
+}
+
+/// An article prepared for display: metadata header plus body converted to text
+#[derive(Debug, Clone)]
+pub struct ArticleView {
+    pub article: FeedItem,
+    pub lines: Vec<String>,
+    /// Images in the body, shown as `[image: alt]` placeholder lines
+    pub images: Vec<ArticleImage>,
+    /// Words in the body, for the reading time estimate
+    pub words: usize,
+}
+
+impl ArticleView {
+    /// Renders an article's HTML body to plain text wrapped at `width` columns
+    pub fn from_article(article: FeedItem, width: usize) -> Self {
+        let mut urls = Vec::new();
+        let content = match article.content.as_ref().or(article.description.as_ref()) {
+            Some(html) => {
+                let (html, images) = images::placeholders(html, &article.link);
+                urls = images;
+                html2text::from_read(html.as_bytes(), width)
+            }
+            None => "No content available".to_string(),
+        };
+
+        let words = content.split_whitespace().count();
+
+        // Build full content with metadata
+        let mut full_content = String::new();
+        if let Some(ref author) = article.author {
+            full_content.push_str(&format!("Author: {}\n", author));
+        }
+        if let Some(ref published) = article.published {
+            full_content.push_str(&format!("Published: {}\n", published));
+        }
+        full_content.push_str(&format!("Link: {}\n", article.link));
+        full_content.push_str(&format!("\n{}\n\n", "─".repeat(width.clamp(1, 40))));
+        full_content.push_str(&content);
+
+        // html2text wraps the body by display width, but the metadata lines
+        // above it (long links, CJK titles) still need it
+        let lines: Vec<String> = full_content
+            .lines()
+            .flat_map(|line| wrap_line(line, width))
+            .collect();
+        let images = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| images::is_placeholder(line))
+            .zip(urls)
+            .map(|((line, _), url)| ArticleImage { url, line })
+            .collect();
+
+        Self {
+            article,
+            lines,
+            images,
+            words,
+        }
+    }
+}
+
+/// Splits `line` into pieces at most `width` terminal columns wide, at
+/// word boundaries where possible; a wide character never straddles the edge
+pub fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    if width == 0 || line.width() <= width {
+        return vec![line.to_string()];
+    }
+    textwrap::wrap(line, width)
+        .into_iter()
+        .map(|piece| piece.into_owned())
+        .collect()
+}
+
+/// The ZetRss engine: a store for articles plus a fetcher for feeds
+pub struct ZetRss<S = TextCache, F = HttpFetcher> {
+    store: S,
+    fetcher: F,
+    concurrency: usize,
+    /// Retention limits by feed URL, applied after each fetch
+    retention: HashMap<String, Retention>,
+}
+
+impl ZetRss {
+    /// Creates an engine backed by the default on-disk cache and HTTP fetcher
+    /// Fetch concurrency comes from the config file
+    pub fn new() -> Result<Self> {
+        Self::open(crate::cache::default_data_dir())
+    }
+
+    /// Creates an engine backed by the on-disk cache in `data_dir`
+    /// Fetch concurrency and per-feed retention come from the config file
+    pub fn open(data_dir: PathBuf) -> Result<Self> {
+        let config = crate::config::get();
+        Ok(
+            Self::with_parts(TextCache::with_base_dir(data_dir)?, HttpFetcher)
+                .with_concurrency(config.fetch.concurrency)
+                .with_retention(config.retention()?),
+        )
+    }
+}
+
+impl<S: ArticleStore, F: FeedFetcher> ZetRss<S, F> {
+    /// Creates an engine from a custom store and fetcher
+    pub fn with_parts(store: S, fetcher: F) -> Self {
+        Self {
+            store,
+            fetcher,
+            concurrency: MAX_CONCURRENT_FETCHES,
+            retention: HashMap::new(),
+        }
+    }
+
+    /// Sets per-feed retention limits, keyed by feed URL
+    pub fn with_retention(mut self, retention: HashMap<String, Retention>) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Sets how many feeds are fetched at the same time (at least one)
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Scans a zet directory for `#feed` tags and replaces the stored feed list
+    pub async fn scan(&self, zet_path: &str) -> Result<Vec<FeedSource>> {
+        self.scan_roots(&[zet_path.to_string()]).await
+    }
+
+    /// Scans several zet directories, merging their feeds into the stored
+    /// feed list; each feed records the root it came from
+    pub async fn scan_roots(&self, roots: &[String]) -> Result<Vec<FeedSource>> {
+        Ok(self.scan_index(roots).await?.feeds())
+    }
+
+    /// Like `scan_roots`, returning the per-note index (e.g. to watch it)
+    /// Notes unchanged since the last scan come from the store's scan cache
+    /// `#feedsite` pages not seen before are fetched for their feed links
+    pub async fn scan_index(&self, roots: &[String]) -> Result<FeedIndex> {
+        // A broken cache only costs reading every note again
+        let cache = self.store.get_scan_cache().unwrap_or_else(|e| {
+            tracing::warn!("{:#}", e);
+            None
+        });
+        let mut index = FeedIndex::build_cached(roots, &cache.unwrap_or_default())?;
+        let sites = index.sites();
+        let known = self.store.get_discoveries()?;
+        if !sites.is_empty() || !known.is_empty() {
+            let discoveries = discovery::refresh(&self.fetcher, &known, &sites, Utc::now()).await;
+            if discoveries != known {
+                self.store.store_discoveries(&discoveries)?;
+            }
+            index.set_discovered(discovery::accepted_feeds(&discoveries, &sites));
+        }
+        self.store.store_feed_list(index.feeds())?;
+        self.store.store_scan_cache(&index.cache())?;
+        tracing::debug!("Scan read {} changed notes", index.reads());
+        Ok(index)
+    }
+
+    /// Fetches every feed in the stored feed list
+    pub async fn fetch(&self, on_event: impl Fn(FetchEvent)) -> Result<Vec<FeedFetchResult>> {
+        let feeds = self.store.get_feed_list()?;
+        Ok(self.fetch_feeds(&feeds, on_event).await)
+    }
+
+    /// Fetches the given feeds concurrently and stores their items
+    /// Failures are reported per feed instead of aborting the whole run
+    pub async fn fetch_feeds(
+        &self,
+        feeds: &[FeedSource],
+        on_event: impl Fn(FetchEvent),
+    ) -> Vec<FeedFetchResult> {
+        // Owned sources keep the stream's futures `Send` for callers such as the HTTP server
+        stream::iter(feeds.to_vec())
+            .map(|feed| self.fetch_one(feed, &on_event))
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await
+    }
+
+    async fn fetch_one(&self, feed: FeedSource, on_event: &impl Fn(FetchEvent)) -> FeedFetchResult {
+        on_event(FetchEvent::Started {
+            url: feed.url.clone(),
+        });
+
+        let started = Instant::now();
+        let mut bytes = 0;
+        let result = match self.fetcher.fetch_feed(&feed.url).await {
+            Ok(mut feed_data) => {
+                // Tags declared with the feed in the zet go on its articles
+                for item in &mut feed_data.items {
+                    for tag in &feed.options.tags {
+                        if !item.tags.contains(tag) {
+                            item.tags.push(tag.clone());
+                        }
+                    }
+                }
+                if feed.options.full_content {
+                    self.fetch_pages(&mut feed_data.items).await;
+                }
+                let items = feed_data.items.len();
+                bytes = feed_data.bytes;
+                let stored = self
+                    .store
+                    .store_feed(&feed_data)
+                    .map_err(|e| format!("Failed to store: {}", e));
+                let pruned = match (&stored, self.retention.get(&feed.url)) {
+                    (Ok(_), Some(retention)) => self
+                        .store
+                        .apply_retention(&feed.url, retention)
+                        .map_err(|e| format!("Failed to apply retention: {}", e)),
+                    _ => Ok(0),
+                };
+                FeedFetchResult {
+                    url: feed.url,
+                    items,
+                    added: *stored.as_ref().unwrap_or(&0),
+                    pruned: *pruned.as_ref().unwrap_or(&0),
+                    error: stored.and(pruned).err(),
+                }
+            }
+            Err(e) => FeedFetchResult {
+                url: feed.url,
+                items: 0,
+                added: 0,
+                pruned: 0,
+                error: Some(format!("Failed to fetch: {}", e)),
+            },
+        };
+
+        let record = FetchRecord {
+            time: Utc::now(),
+            status: if result.error.is_some() {
+                FetchStatus::Error
+            } else {
+                FetchStatus::Ok
+            },
+            items: result.items,
+            added: result.added,
+            duration_ms: started.elapsed().as_millis() as u64,
+            bytes,
+            error: result.error.clone(),
+        };
+        // History is informational; a full disk shouldn't fail the fetch itself
+        if let Err(e) = self.store.record_fetch(&result.url, &record) {
+            tracing::warn!("Failed to record fetch of {}: {:#}", result.url, e);
+        }
+
+        on_event(FetchEvent::Finished(result.clone()));
+        result
+    }
+
+    /// Replaces the feed content of articles not cached yet with their web
+    /// page; an article whose page can't be fetched keeps the feed's content
+    async fn fetch_pages(&self, items: &mut [FeedItem]) {
+        for item in items {
+            if item.link.is_empty() || !matches!(self.store.get_article_by_id(&item.id), Ok(None)) {
+                continue;
+            }
+            match self.fetcher.fetch_page(&item.link).await {
+                Ok(html) => item.content = Some(html),
+                Err(e) => tracing::debug!("Keeping feed content of {}: {:#}", item.link, e),
+            }
+        }
+    }
+
+    /// Lists one page of the articles matching the query
+    pub fn list(&self, query: &ArticleQuery) -> Result<Vec<FeedItem>> {
+        self.store.get_articles(query)
+    }
+
+    /// Like `list`, as summaries telling whether more articles match
+    pub fn page(&self, query: &ArticleQuery) -> Result<ArticlePage> {
+        // One article past the page tells whether there are more
+        let mut articles = self.list(&ArticleQuery {
+            limit: query.limit.map(|limit| limit + 1),
+            ..query.clone()
+        })?;
+        let has_more = query.limit.is_some_and(|limit| articles.len() > limit);
+        articles.truncate(query.limit.unwrap_or(usize::MAX));
+        Ok(ArticlePage {
+            articles: articles.iter().map(ArticleSummary::from).collect(),
+            offset: query.offset,
+            limit: query.limit,
+            has_more,
+        })
+    }
+
+    /// Lists articles matching the filter, best full-text match first
+    pub fn search(&self, filter: &ArticleFilter, limit: Option<usize>) -> Result<Vec<FeedItem>> {
+        self.store.search(filter, limit)
+    }
+
+    /// Loads an article rendered for display at the given width
+    pub fn view_model(&self, article_id: &str, width: usize) -> Result<ArticleView> {
+        let article = self
+            .store
+            .get_article_by_id(article_id)?
+            .ok_or_else(|| anyhow!("Article not found: {}", article_id))?;
+        Ok(ArticleView::from_article(article, width))
+    }
+
+    /// Counts articles overall and per feed
+    /// Feeds in the feed list appear even if they have no cached articles yet
+    pub fn stats(&self) -> Result<LibraryStats> {
+        let mut stats = LibraryStats::default();
+        let mut per_feed: BTreeMap<String, FeedStats> = self
+            .store
+            .get_feed_list()?
+            .into_iter()
+            .map(|f| {
+                let feed_stats = FeedStats {
+                    url: f.url.clone(),
+                    ..Default::default()
+                };
+                (f.url, feed_stats)
+            })
+            .collect();
+
+        for counts in self.store.feed_counts()? {
+            stats.total += counts.total;
+            stats.unread += counts.unread;
+            stats.starred += counts.starred;
+            let feed = per_feed
+                .entry(counts.feed_url.clone())
+                .or_insert_with(|| FeedStats {
+                    url: counts.feed_url.clone(),
+                    ..Default::default()
+                });
+            feed.total = counts.total;
+            feed.unread = counts.unread;
+        }
+
+        stats.feeds = per_feed.into_values().collect();
+        stats.quarantined = self.store.quarantined_count()?;
+        Ok(stats)
+    }
+
+    pub fn mark_read(&self, article_id: &str) -> Result<()> {
+        self.store.mark_as_read(article_id)
+    }
+
+    pub fn toggle_star(&self, article_id: &str) -> Result<()> {
+        self.store.toggle_star(article_id)
+    }
+}
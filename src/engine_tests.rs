@@ -0,0 +1,414 @@
+use super::*;
+use crate::models::Feed;
+use chrono::Utc;
+use std::future::Future;
+use std::sync::Mutex;
+
+/// In-memory store so engine behavior can be tested without touching disk
+#[derive(Default)]
+struct MemoryStore {
+    articles: Mutex<Vec<FeedItem>>,
+    feeds: Mutex<Vec<FeedSource>>,
+}
+
+impl ArticleStore for MemoryStore {
+    fn store_feed(&self, feed: &Feed) -> Result<usize> {
+        self.articles.lock().unwrap().extend(feed.items.clone());
+        Ok(feed.items.len())
+    }
+
+    fn get_articles(&self, query: &ArticleQuery) -> Result<Vec<FeedItem>> {
+        Ok(query.apply(self.articles.lock().unwrap().clone()))
+    }
+
+    fn get_article_by_id(&self, article_id: &str) -> Result<Option<FeedItem>> {
+        Ok(self
+            .articles
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|a| a.id == article_id)
+            .cloned())
+    }
+
+    fn mark_as_read(&self, item_id: &str) -> Result<()> {
+        let mut articles = self.articles.lock().unwrap();
+        let article = articles
+            .iter_mut()
+            .find(|a| a.id == item_id)
+            .ok_or_else(|| anyhow!("Article not found: {}", item_id))?;
+        article.read = true;
+        Ok(())
+    }
+
+    fn toggle_star(&self, item_id: &str) -> Result<()> {
+        let mut articles = self.articles.lock().unwrap();
+        let article = articles
+            .iter_mut()
+            .find(|a| a.id == item_id)
+            .ok_or_else(|| anyhow!("Article not found: {}", item_id))?;
+        article.starred = !article.starred;
+        Ok(())
+    }
+
+    fn store_feed_list(&self, feeds: Vec<FeedSource>) -> Result<()> {
+        *self.feeds.lock().unwrap() = feeds;
+        Ok(())
+    }
+
+    fn get_feed_list(&self) -> Result<Vec<FeedSource>> {
+        Ok(self.feeds.lock().unwrap().clone())
+    }
+}
+
+/// Serves a two-item feed for any URL containing "good" and fails otherwise
+/// Pages of the feed's first item load; the rest fail, except for pages of
+/// "site" URLs, which link an RSS feed at `/feed.xml`
+struct FakeFetcher;
+
+impl FeedFetcher for FakeFetcher {
+    fn fetch_feed(&self, url: &str) -> impl Future<Output = Result<Feed>> + Send {
+        let url = url.to_string();
+        async move {
+            if !url.contains("good") {
+                return Err(anyhow!("connection refused"));
+            }
+            let items = (1..=2)
+                .map(|i| FeedItem {
+                    id: format!("{}#{}", url, i),
+                    feed_url: url.clone(),
+                    title: format!("Item {}", i),
+                    link: format!("{}/{}", url, i),
+                    published: Some(Utc::now() - chrono::Duration::hours(i)),
+                    content: Some(format!("<p>Body {}</p>", i)),
+                    ..Default::default()
+                })
+                .collect();
+            Ok(Feed {
+                url: url.clone(),
+                title: "Fake".to_string(),
+                description: None,
+                last_fetched: Some(Utc::now()),
+                bytes: 1024,
+                items,
+            })
+        }
+    }
+
+    fn fetch_page(&self, url: &str) -> impl Future<Output = Result<String>> + Send {
+        let url = url.to_string();
+        async move {
+            if url.contains("site") {
+                return Ok(
+                    r#"<link rel="alternate" type="application/rss+xml" href="/feed.xml">"#
+                        .to_string(),
+                );
+            }
+            if !url.ends_with("/1") {
+                return Err(anyhow!("not found"));
+            }
+            Ok(format!("<article>Page of {}</article>", url))
+        }
+    }
+}
+
+fn source(url: &str) -> FeedSource {
+    FeedSource {
+        url: url.to_string(),
+        source_file: String::new(),
+        line_number: 0,
+        root: None,
+        options: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_fetch_reports_per_feed_results() {
+    let reader = ZetRss::with_parts(MemoryStore::default(), FakeFetcher);
+    reader
+        .store()
+        .store_feed_list(vec![
+            source("https://good.example/feed"),
+            source("https://bad.example/feed"),
+        ])
+        .unwrap();
+
+    let events = Mutex::new(Vec::new());
+    let mut results = reader
+        .fetch(|event| events.lock().unwrap().push(event))
+        .await
+        .unwrap();
+    results.sort_by(|a, b| a.url.cmp(&b.url));
+
+    assert_eq!(results.len(), 2);
+    assert!(results[0]
+        .error
+        .as_deref()
+        .unwrap()
+        .contains("connection refused"));
+    assert_eq!(results[1].items, 2);
+    assert!(results[1].error.is_none());
+    // One started and one finished event per feed
+    assert_eq!(events.lock().unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn test_list_view_model_and_mark_read() {
+    let reader = ZetRss::with_parts(MemoryStore::default(), FakeFetcher);
+    reader
+        .fetch_feeds(&[source("https://good.example/feed")], |_| {})
+        .await;
+
+    let unread = ArticleQuery::from(ArticleFilter {
+        read: Some(false),
+        ..Default::default()
+    });
+    let articles = reader.list(&unread).unwrap();
+    assert_eq!(articles.len(), 2);
+    // Newest first
+    assert_eq!(articles[0].title, "Item 1");
+
+    let view = reader.view_model(&articles[0].id, 80).unwrap();
+    assert!(view.lines.iter().any(|l| l.contains("Body 1")));
+    assert!(!view.lines.iter().any(|l| l.contains("<p>")));
+
+    reader.mark_read(&articles[0].id).unwrap();
+    assert_eq!(reader.list(&unread).unwrap().len(), 1);
+    let empty_page = ArticleQuery {
+        limit: Some(0),
+        ..unread.clone()
+    };
+    assert_eq!(reader.list(&empty_page).unwrap().len(), 0);
+
+    let first = ArticleQuery {
+        filter: ArticleFilter::default(),
+        limit: Some(1),
+        ..Default::default()
+    };
+    let page = reader.page(&first).unwrap();
+    assert_eq!(page.articles.len(), 1);
+    assert!(page.has_more);
+    let last = reader
+        .page(&ArticleQuery {
+            offset: 1,
+            ..first.clone()
+        })
+        .unwrap();
+    assert_eq!(last.articles.len(), 1);
+    assert!(!last.has_more);
+    assert_ne!(page.articles[0].id, last.articles[0].id);
+
+    assert!(reader.view_model("missing", 80).is_err());
+
+    let stats = reader.stats().unwrap();
+    assert_eq!(stats.total, 2);
+    assert_eq!(stats.unread, 1);
+    assert_eq!(stats.feeds.len(), 1);
+    assert_eq!(stats.feeds[0].unread, 1);
+}
+
+#[test]
+fn test_open_uses_given_data_dir() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let reader = ZetRss::open(dir.path().to_path_buf()).unwrap();
+    reader
+        .store()
+        .add_feed(FeedSource {
+            url: "https://example.com/feed".to_string(),
+            source_file: "test.md".to_string(),
+            line_number: 1,
+            root: None,
+            options: Default::default(),
+        })
+        .unwrap();
+
+    assert!(dir.path().join("state/feeds.json").exists());
+    assert!(dir.path().join("index.sqlite").exists());
+}
+
+#[tokio::test]
+async fn test_fetch_applies_options_declared_in_the_zet() {
+    let reader = ZetRss::with_parts(MemoryStore::default(), FakeFetcher);
+    let mut feed = source("https://good.example/feed");
+    feed.options.tags = vec!["rust".to_string()];
+    feed.options.full_content = true;
+    reader.fetch_feeds(&[feed], |_| {}).await;
+
+    let articles = reader.store().articles.lock().unwrap().clone();
+    assert!(articles.iter().all(|a| a.tags == vec!["rust"]));
+    assert_eq!(
+        articles[0].content.as_deref(),
+        Some("<article>Page of https://good.example/feed/1</article>")
+    );
+    // A page that can't be fetched leaves the feed's content
+    assert_eq!(articles[1].content.as_deref(), Some("<p>Body 2</p>"));
+}
+
+#[tokio::test]
+async fn test_retention_applied_after_fetch() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let store = TextCache::with_base_dir(dir.path().to_path_buf()).unwrap();
+    let url = "https://good.example/feed";
+    let retention = HashMap::from([(
+        url.to_string(),
+        Retention {
+            max_items: Some(1),
+            max_age: None,
+        },
+    )]);
+    let reader = ZetRss::with_parts(store, FakeFetcher).with_retention(retention);
+
+    let results = reader.fetch_feeds(&[source(url)], |_| {}).await;
+    assert_eq!(results[0].items, 2);
+    assert_eq!(results[0].pruned, 1);
+    assert!(results[0].error.is_none());
+
+    let articles = reader.list(&ArticleQuery::default()).unwrap();
+    assert_eq!(articles.len(), 1);
+    assert_eq!(articles[0].title, "Item 1");
+}
+
+#[tokio::test]
+async fn test_fetch_attempts_are_recorded() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let store = TextCache::with_base_dir(dir.path().to_path_buf()).unwrap();
+    let reader = ZetRss::with_parts(store, FakeFetcher);
+    let good = source("https://good.example/feed");
+    let bad = source("https://bad.example/feed");
+
+    reader
+        .fetch_feeds(&[good.clone(), bad.clone()], |_| {})
+        .await;
+    let results = reader
+        .fetch_feeds(std::slice::from_ref(&good), |_| {})
+        .await;
+    assert_eq!(results[0].added, 0);
+
+    let history = reader.store().fetch_history(&good.url).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].status, FetchStatus::Ok);
+    assert_eq!((history[0].items, history[0].added), (2, 2));
+    assert_eq!((history[1].items, history[1].added), (2, 0));
+    assert_eq!(history[0].bytes, 1024);
+
+    let history = reader.store().fetch_history(&bad.url).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].status, FetchStatus::Error);
+    assert!(history[0]
+        .error
+        .as_deref()
+        .unwrap()
+        .starts_with("Failed to fetch"));
+}
+
+#[test]
+fn test_wrap_line_by_display_width() {
+    assert_eq!(wrap_line("short", 10), vec!["short"]);
+    assert_eq!(
+        wrap_line("the quick brown fox", 10),
+        vec!["the quick", "brown fox"]
+    );
+    // CJK characters are two columns wide and wrap between characters
+    assert_eq!(
+        wrap_line("日本語のテキスト", 7),
+        vec!["日本語", "のテキ", "スト"]
+    );
+    // A combining accent stays with its letter; emoji don't straddle the edge
+    assert_eq!(
+        wrap_line("cafe\u{301} 🎉🎉🎉", 6),
+        vec!["cafe\u{301}", "🎉🎉🎉"]
+    );
+    assert_eq!(wrap_line("a🎉🎉🎉", 4), vec!["a🎉", "🎉🎉"]);
+    // Long links break after their slashes
+    assert_eq!(
+        wrap_line("see https://example.com/x", 12),
+        vec!["see https://", "example.com/", "x"]
+    );
+}
+
+#[test]
+fn test_view_lines_fit_the_width() {
+    let article = FeedItem {
+        id: "a".to_string(),
+        title: "t".to_string(),
+        link: "https://example.com/記事/一二三四五六七八九十一二三四五六七八九十".to_string(),
+        author: Some("山田太郎".to_string()),
+        content: Some(
+            "<p>日本語のテキストはスペースなしで長く続きます。🎉🎉🎉🎉🎉🎉🎉</p>".to_string(),
+        ),
+        ..Default::default()
+    };
+    let view = ArticleView::from_article(article, 20);
+    assert!(view.lines.iter().all(|line| line.width() <= 20));
+    assert!(view.lines.contains(&"Link: https://".to_string()));
+}
+
+#[tokio::test]
+async fn test_feed_tags_from_the_zet_go_on_articles() {
+    let reader = ZetRss::with_parts(MemoryStore::default(), FakeFetcher);
+    let mut feed = source("https://good.example.com/feed");
+    feed.options.tags = vec!["rust".to_string()];
+    reader.fetch_feeds(&[feed], |_| {}).await;
+
+    let articles = reader.list(&ArticleQuery::default()).unwrap();
+    assert_eq!(articles.len(), 2);
+    assert!(articles.iter().all(|a| a.tags == vec!["rust"]));
+}
+
+#[tokio::test]
+async fn test_rescans_reuse_the_scan_cache() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let zet = tempfile::TempDir::new().unwrap();
+    std::fs::write(zet.path().join("a.md"), "#feed https://a.example.com/rss\n").unwrap();
+    let roots = vec![zet.path().to_string_lossy().to_string()];
+    let store = TextCache::with_base_dir(dir.path().to_path_buf()).unwrap();
+    let reader = ZetRss::with_parts(store, FakeFetcher);
+
+    assert_eq!(reader.scan_index(&roots).await.unwrap().reads(), 1);
+    let index = reader.scan_index(&roots).await.unwrap();
+    assert_eq!(index.reads(), 0);
+    assert_eq!(index.feeds().len(), 1);
+    assert_eq!(reader.store().get_feed_list().unwrap().len(), 1);
+
+    // A corrupt cache means a full scan, not a failed one
+    std::fs::write(dir.path().join("state/scan_cache.json"), "{").unwrap();
+    assert_eq!(reader.scan_index(&roots).await.unwrap().reads(), 1);
+}
+
+#[tokio::test]
+async fn test_scan_discovers_feedsite_feeds_pending_acceptance() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let zet = tempfile::TempDir::new().unwrap();
+    let note = zet.path().join("blogs.md");
+    std::fs::write(
+        &note,
+        "Nice blog #feedsite https://site.example.com/ interval=2h\n",
+    )
+    .unwrap();
+    let roots = vec![zet.path().to_string_lossy().to_string()];
+    let store = TextCache::with_base_dir(dir.path().to_path_buf()).unwrap();
+    let reader = ZetRss::with_parts(store, FakeFetcher);
+
+    // Found feeds wait for the user
+    assert!(reader.scan_roots(&roots).await.unwrap().is_empty());
+    let mut found = reader.store().get_discoveries().unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].feeds, vec!["https://site.example.com/feed.xml"]);
+    assert_eq!(found[0].line_number, 1);
+
+    discovery::accept(&mut found, "https://site.example.com", None).unwrap();
+    reader.store().store_discoveries(&found).unwrap();
+    let feeds = reader.scan_roots(&roots).await.unwrap();
+    assert_eq!(feeds.len(), 1);
+    assert_eq!(feeds[0].url, "https://site.example.com/feed.xml");
+    assert_eq!(feeds[0].source_file, note.to_string_lossy());
+    assert_eq!(
+        feeds[0].options.interval(),
+        Some(chrono::Duration::hours(2))
+    );
+
+    // Dropping the marker drops the site and its feed
+    std::fs::write(&note, "Nice blog, no feed\n").unwrap();
+    assert!(reader.scan_roots(&roots).await.unwrap().is_empty());
+    assert!(reader.store().get_discoveries().unwrap().is_empty());
+}
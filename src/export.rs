@@ -0,0 +1,183 @@
+use crate::models::{ArticleSummary, FeedItem};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+
+#[cfg(test)]
+#[path = "export_tests.rs"]
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+/// One exported article: the listing metadata plus, optionally, the body
+#[derive(Serialize)]
+struct ExportRecord {
+    #[serde(flatten)]
+    summary: ArticleSummary,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+}
+
+/// Flat row for CSV, which can't represent lists
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    id: &'a str,
+    feed_url: &'a str,
+    title: &'a str,
+    link: &'a str,
+    author: &'a str,
+    published: String,
+    read: bool,
+    starred: bool,
+    /// Tags joined with `;`
+    tags: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<&'a str>,
+}
+
+/// Writes articles as a JSON array or CSV with a header row
+pub fn write_export(
+    articles: &[FeedItem],
+    format: ExportFormat,
+    include_content: bool,
+    out: impl Write,
+) -> Result<()> {
+    match format {
+        ExportFormat::Json => {
+            let records: Vec<ExportRecord> = articles
+                .iter()
+                .map(|a| ExportRecord {
+                    summary: a.into(),
+                    content: include_content.then(|| a.content.clone().unwrap_or_default()),
+                })
+                .collect();
+            serde_json::to_writer_pretty(out, &records)?;
+        }
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            for a in articles {
+                writer.serialize(CsvRow {
+                    id: &a.id,
+                    feed_url: &a.feed_url,
+                    title: &a.title,
+                    link: &a.link,
+                    author: a.author.as_deref().unwrap_or(""),
+                    published: a.published.map(|d| d.to_rfc3339()).unwrap_or_default(),
+                    read: a.read,
+                    starred: a.starred,
+                    tags: a.tags.join(";"),
+                    content: include_content.then(|| a.content.as_deref().unwrap_or("")),
+                })?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Record formats of `list --format` for pickers (telescope, fzf) that
+/// spawn the binary and parse stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PickerFormat {
+    /// Tab-separated: id, title, feed title, date, flags
+    Lines,
+    /// One JSON object per line
+    Jsonl,
+}
+
+/// One article of a picker listing
+#[derive(Serialize)]
+struct PickerRecord<'a> {
+    id: &'a str,
+    title: String,
+    feed_url: &'a str,
+    /// The feed's title, else the feed URL's host
+    feed_title: String,
+    published: Option<String>,
+    read: bool,
+    starred: bool,
+    link: &'a str,
+}
+
+/// Writes one line per article; `feed_title` looks up feed titles, each
+/// feed once. Tabs and newlines in titles become spaces so every record stays
+/// on its line. Flags are `u` for unread and `s` for starred.
+pub fn write_picker_lines(
+    articles: &[FeedItem],
+    format: PickerFormat,
+    feed_title: impl Fn(&str) -> Option<String>,
+    mut out: impl Write,
+) -> Result<()> {
+    let mut titles: HashMap<&str, String> = HashMap::new();
+    for a in articles {
+        let feed = titles
+            .entry(a.feed_url.as_str())
+            .or_insert_with(|| feed_title(&a.feed_url).unwrap_or_else(|| host(&a.feed_url)))
+            .clone();
+        let record = PickerRecord {
+            id: &a.id,
+            title: one_line(&a.title),
+            feed_url: &a.feed_url,
+            feed_title: one_line(&feed),
+            published: a.published.map(|d| d.format("%Y-%m-%d %H:%M").to_string()),
+            read: a.read,
+            starred: a.starred,
+            link: &a.link,
+        };
+        match format {
+            PickerFormat::Lines => {
+                let flags = format!(
+                    "{}{}",
+                    if a.read { "" } else { "u" },
+                    if a.starred { "s" } else { "" }
+                );
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}\t{}",
+                    record.id,
+                    record.title,
+                    record.feed_title,
+                    record.published.as_deref().unwrap_or(""),
+                    flags
+                )?;
+            }
+            PickerFormat::Jsonl => {
+                serde_json::to_writer(&mut out, &record)?;
+                writeln!(out)?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn one_line(text: &str) -> String {
+    text.split(['\t', '\n', '\r'])
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The host of a URL, or the URL itself
+fn host(url: &str) -> String {
+    url.split("://")
+        .nth(1)
+        .and_then(|rest| rest.split('/').next())
+        .unwrap_or(url)
+        .to_string()
+}
+
+/// Writes `title<TAB>link` per article for completion sources (`zetrss
+/// complete`); articles without a link are left out
+pub fn write_completions(articles: &[FeedItem], mut out: impl Write) -> Result<()> {
+    for a in articles.iter().filter(|a| !a.link.is_empty()) {
+        writeln!(out, "{}\t{}", one_line(&a.title), a.link)?;
+    }
+    out.flush()?;
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use super::*;
+use chrono::{TimeZone, Utc};
+
+fn article() -> FeedItem {
+    FeedItem {
+        id: "post-1".to_string(),
+        feed_url: "https://example.com/feed".to_string(),
+        title: "Hello, \"world\"".to_string(),
+        link: "https://example.com/1".to_string(),
+        published: Some(Utc.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap()),
+        content: Some("line one\nline two".to_string()),
+        read: true,
+        tags: vec!["rust".to_string(), "async".to_string()],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_export_csv() {
+    let mut out = Vec::new();
+    write_export(&[article()], ExportFormat::Csv, false, &mut out).unwrap();
+    let csv = String::from_utf8(out).unwrap();
+    let mut lines = csv.lines();
+
+    assert_eq!(
+        lines.next().unwrap(),
+        "id,feed_url,title,link,author,published,read,starred,tags"
+    );
+    assert_eq!(
+        lines.next().unwrap(),
+        "post-1,https://example.com/feed,\"Hello, \"\"world\"\"\",https://example.com/1,,2024-03-15T12:00:00+00:00,true,false,rust;async"
+    );
+}
+
+#[test]
+fn test_export_json_with_content() {
+    let mut out = Vec::new();
+    write_export(&[article()], ExportFormat::Json, true, &mut out).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+
+    assert_eq!(json[0]["id"], "post-1");
+    assert_eq!(json[0]["tags"][1], "async");
+    assert_eq!(json[0]["content"], "line one\nline two");
+
+    let mut out = Vec::new();
+    write_export(&[article()], ExportFormat::Json, false, &mut out).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert!(json[0].get("content").is_none());
+}
+
+#[test]
+fn test_picker_lines() {
+    let unread = FeedItem {
+        id: "post-2".to_string(),
+        feed_url: "https://other.example/rss".to_string(),
+        title: "Tabs\tand\nnewlines".to_string(),
+        published: None,
+        read: false,
+        starred: true,
+        ..article()
+    };
+    let articles = [article(), unread];
+    let feed_title = |url: &str| (url == "https://example.com/feed").then(|| "Example".to_string());
+
+    let mut out = Vec::new();
+    write_picker_lines(&articles, PickerFormat::Lines, feed_title, &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "post-1\tHello, \"world\"\tExample\t2024-03-15 12:00\t",
+            "post-2\tTabs and newlines\tother.example\t\tus",
+        ]
+    );
+
+    let mut out = Vec::new();
+    write_picker_lines(&articles, PickerFormat::Jsonl, feed_title, &mut out).unwrap();
+    let records: Vec<serde_json::Value> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["feed_title"], "Example");
+    assert_eq!(records[0]["published"], "2024-03-15 12:00");
+    assert_eq!(records[1]["published"], serde_json::Value::Null);
+    assert_eq!(records[1]["starred"], true);
+    assert_eq!(records[1]["link"], "https://example.com/1");
+}
+
+#[test]
+fn test_completions() {
+    let unlinked = FeedItem {
+        link: String::new(),
+        ..article()
+    };
+    let multiline = FeedItem {
+        title: "Two\nlines".to_string(),
+        link: "https://example.com/2".to_string(),
+        ..article()
+    };
+    let mut out = Vec::new();
+    write_completions(&[article(), unlinked, multiline], &mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "Hello, \"world\"\thttps://example.com/1\nTwo lines\thttps://example.com/2\n"
+    );
+}
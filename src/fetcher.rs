@@ -1,16 +1,40 @@
-use crate::models::{Feed, FeedItem};
+use crate::models::{
+    Feed, FeedItem, RawCategory, RawContent, RawEntry, RawLink, RawMedia, RawMediaContent,
+    RawPerson,
+};
 use anyhow::{anyhow, Result};
 use chrono::Utc;
+use feed_rs::model::{Entry, Link, Person};
 use feed_rs::parser;
+use std::future::Future;
+
+/// Source of parsed feeds
+/// `HttpFetcher` is the production implementation; tests and embedders can
+/// substitute canned feeds
+pub trait FeedFetcher: Send + Sync {
+    fn fetch_feed(&self, url: &str) -> impl Future<Output = Result<Feed>> + Send;
+
+    /// Fetches an article's web page, for feeds declared with `full_content=true`
+    fn fetch_page(&self, url: &str) -> impl Future<Output = Result<String>> + Send {
+        fetch_page(url)
+    }
+}
+
+/// Fetches feeds over HTTP with `fetch_feed`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HttpFetcher;
+
+impl FeedFetcher for HttpFetcher {
+    fn fetch_feed(&self, url: &str) -> impl Future<Output = Result<Feed>> + Send {
+        fetch_feed(url)
+    }
+}
 
 /// Fetches an RSS/Atom feed from the given URL
 /// Parses the feed and converts it to our internal Feed model
 /// Returns an error if the fetch fails or the feed is invalid
 pub async fn fetch_feed(url: &str) -> Result<Feed> {
-    let client = reqwest::Client::builder()
-        .user_agent("ZetRss/0.1")
-        .timeout(std::time::Duration::from_secs(30))
-        .build()?;
+    let client = http_client()?;
 
     let response = client.get(url).send().await?;
 
@@ -24,6 +48,7 @@ pub async fn fetch_feed(url: &str) -> Result<Feed> {
     let mut items = Vec::new();
 
     for entry in feed.entries {
+        let raw = RawEntry::from(&entry);
         let id = entry.id.clone();
         let title = entry
             .title
@@ -45,6 +70,12 @@ pub async fn fetch_feed(url: &str) -> Result<Feed> {
 
         let author = entry.authors.first().map(|a| a.name.clone());
 
+        let tags = entry
+            .categories
+            .iter()
+            .map(|c| c.label.clone().unwrap_or_else(|| c.term.clone()))
+            .collect();
+
         let content = entry
             .content
             .and_then(|c| c.body)
@@ -61,7 +92,10 @@ pub async fn fetch_feed(url: &str) -> Result<Feed> {
             content,
             read: false,
             starred: false,
+            tags,
+            fetched: None,
             filepath: None,
+            raw: Some(raw),
         });
     }
 
@@ -78,5 +112,116 @@ pub async fn fetch_feed(url: &str) -> Result<Feed> {
         description: feed_description,
         last_fetched: Some(Utc::now()),
         items,
+        bytes: bytes.len(),
     })
 }
+
+impl From<&Entry> for RawEntry {
+    fn from(entry: &Entry) -> Self {
+        let link = |l: &Link| RawLink {
+            href: l.href.clone(),
+            rel: l.rel.clone(),
+            media_type: l.media_type.clone(),
+            title: l.title.clone(),
+            length: l.length,
+        };
+        let person = |p: &Person| RawPerson {
+            name: p.name.clone(),
+            uri: p.uri.clone(),
+            email: p.email.clone(),
+        };
+
+        RawEntry {
+            id: entry.id.clone(),
+            title: entry.title.as_ref().map(|t| t.content.clone()),
+            summary: entry.summary.as_ref().map(|t| t.content.clone()),
+            content: entry.content.as_ref().map(|c| RawContent {
+                body: c.body.clone(),
+                content_type: c.content_type.to_string(),
+                src: c.src.as_ref().map(|l| l.href.clone()),
+            }),
+            links: entry.links.iter().map(link).collect(),
+            authors: entry.authors.iter().map(person).collect(),
+            contributors: entry.contributors.iter().map(person).collect(),
+            categories: entry
+                .categories
+                .iter()
+                .map(|c| RawCategory {
+                    term: c.term.clone(),
+                    scheme: c.scheme.clone(),
+                    label: c.label.clone(),
+                })
+                .collect(),
+            media: entry
+                .media
+                .iter()
+                .map(|m| RawMedia {
+                    title: m.title.as_ref().map(|t| t.content.clone()),
+                    description: m.description.as_ref().map(|t| t.content.clone()),
+                    content: m
+                        .content
+                        .iter()
+                        .map(|c| RawMediaContent {
+                            url: c.url.as_ref().map(|u| u.to_string()),
+                            content_type: c.content_type.as_ref().map(|t| t.to_string()),
+                            size: c.size,
+                            width: c.width,
+                            height: c.height,
+                            duration_secs: c.duration.map(|d| d.as_secs()),
+                        })
+                        .collect(),
+                    thumbnails: m.thumbnails.iter().map(|t| t.image.uri.clone()).collect(),
+                })
+                .collect(),
+            published: entry.published,
+            updated: entry.updated,
+            source: entry.source.clone(),
+            rights: entry.rights.as_ref().map(|t| t.content.clone()),
+            language: entry.language.clone(),
+        }
+    }
+}
+
+/// Fetches the HTML of an article's web page
+/// Used to refresh articles whose feed entry only carries a summary
+pub async fn fetch_page(url: &str) -> Result<String> {
+    let client = http_client()?;
+
+    let response = client.get(url).send().await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Failed to fetch page: {}", response.status()));
+    }
+
+    Ok(response.text().await?)
+}
+
+/// Downloads a binary resource such as an article image
+/// Fails on responses larger than `max_bytes`
+pub async fn fetch_bytes(url: &str, max_bytes: usize) -> Result<Vec<u8>> {
+    let client = http_client()?;
+
+    let mut response = client.get(url).send().await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Failed to fetch {}: {}", url, response.status()));
+    }
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() > max_bytes {
+            return Err(anyhow!("{} is larger than {} bytes", url, max_bytes));
+        }
+    }
+    Ok(bytes)
+}
+
+fn http_client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .user_agent("ZetRss/0.1")
+        .timeout(std::time::Duration::from_secs(
+            crate::config::get().fetch.timeout_secs,
+        ))
+        .build()?)
+}
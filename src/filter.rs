@@ -0,0 +1,224 @@
+use crate::models::FeedItem;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use std::cmp::Reverse;
+
+#[cfg(test)]
+#[path = "filter_tests.rs"]
+mod tests;
+
+/// Criteria for selecting cached articles
+/// Unset fields match everything; all set criteria must hold
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ArticleFilter {
+    pub read: Option<bool>,
+    pub starred: Option<bool>,
+    /// Exact feed URL
+    pub feed: Option<String>,
+    /// Case-insensitive substring of the feed URL
+    pub feed_contains: Option<String>,
+    /// Tags the article must carry (case-insensitive)
+    pub tags: Vec<String>,
+    /// Case-insensitive substrings the title must contain
+    pub title_terms: Vec<String>,
+    /// Case-insensitive substrings of title, body or feed URL
+    pub text_terms: Vec<String>,
+    /// Inclusive lower bound on the published date
+    pub since: Option<DateTime<Utc>>,
+    /// Exclusive upper bound on the published date
+    pub until: Option<DateTime<Utc>>,
+    /// Inclusive lower bound on when the article was cached, falling back
+    /// to the published date for articles cached before that was recorded
+    pub fetched_since: Option<DateTime<Utc>>,
+}
+
+impl ArticleFilter {
+    /// Returns true if the article satisfies every criterion of the filter
+    pub fn matches(&self, item: &FeedItem) -> bool {
+        if self.read.is_some_and(|read| item.read != read) {
+            return false;
+        }
+        if self.starred.is_some_and(|starred| item.starred != starred) {
+            return false;
+        }
+        if let Some(ref feed) = self.feed {
+            if item.feed_url != *feed {
+                return false;
+            }
+        }
+        if let Some(ref needle) = self.feed_contains {
+            if !contains_ignore_case(&item.feed_url, needle) {
+                return false;
+            }
+        }
+        if !self
+            .tags
+            .iter()
+            .all(|tag| item.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+        {
+            return false;
+        }
+        if !self
+            .title_terms
+            .iter()
+            .all(|term| contains_ignore_case(&item.title, term))
+        {
+            return false;
+        }
+        if !self.text_terms.is_empty() {
+            let haystack = format!(
+                "{} {} {}",
+                item.title,
+                item.content.as_deref().unwrap_or(""),
+                item.feed_url
+            )
+            .to_lowercase();
+            if !self
+                .text_terms
+                .iter()
+                .all(|term| haystack.contains(&term.to_lowercase()))
+            {
+                return false;
+            }
+        }
+        if self.since.is_some() || self.until.is_some() {
+            // Articles without a date can't be placed in the window
+            let Some(published) = item.published else {
+                return false;
+            };
+            if self.since.is_some_and(|since| published < since) {
+                return false;
+            }
+            if self.until.is_some_and(|until| published >= until) {
+                return false;
+            }
+        }
+        if let Some(since) = self.fetched_since {
+            if item
+                .fetched
+                .or(item.published)
+                .is_none_or(|fetched| fetched < since)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Order articles are listed in
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Newest published first
+    #[default]
+    Newest,
+    /// Oldest published first
+    Oldest,
+    /// By title, ignoring case; newest first among equal titles
+    Title,
+    /// By feed URL; newest first within each feed
+    Feed,
+    /// Unread before read; newest first within each
+    Unread,
+}
+
+impl SortOrder {
+    /// Sorts articles in place; undated articles go last except by title
+    pub fn sort(self, articles: &mut [FeedItem]) {
+        match self {
+            SortOrder::Newest => articles.sort_by_key(|a| Reverse(a.published)),
+            SortOrder::Oldest => articles.sort_by_key(|a| (a.published.is_none(), a.published)),
+            SortOrder::Title => articles
+                .sort_by_cached_key(|a| (a.title.to_ascii_lowercase(), Reverse(a.published))),
+            SortOrder::Feed => {
+                articles.sort_by_cached_key(|a| (a.feed_url.clone(), Reverse(a.published)))
+            }
+            SortOrder::Unread => articles.sort_by_key(|a| (a.read, Reverse(a.published))),
+        }
+    }
+}
+
+/// One page of the articles matching a filter
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ArticleQuery {
+    pub filter: ArticleFilter,
+    pub sort: SortOrder,
+    /// Matching articles skipped before the page starts
+    pub offset: usize,
+    /// Page size; None for every remaining match
+    pub limit: Option<usize>,
+}
+
+impl ArticleQuery {
+    /// Selects, sorts and pages articles that are already in memory
+    pub fn apply(&self, articles: Vec<FeedItem>) -> Vec<FeedItem> {
+        let mut articles: Vec<_> = articles
+            .into_iter()
+            .filter(|a| self.filter.matches(a))
+            .collect();
+        self.sort.sort(&mut articles);
+        articles
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+impl From<ArticleFilter> for ArticleQuery {
+    fn from(filter: ArticleFilter) -> Self {
+        Self {
+            filter,
+            ..Default::default()
+        }
+    }
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+/// Parses a relative duration such as "30m", "12h", "3d" or "2w"
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| anyhow!("Missing unit in duration '{}' (use m, h, d or w)", s))?;
+    let (amount, unit) = s.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| anyhow!("Invalid duration '{}': expected a number like 3d", s))?;
+
+    let duration = match unit {
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => {
+            return Err(anyhow!(
+                "Unknown unit '{}' in duration '{}' (use m, h, d or w)",
+                unit,
+                s
+            ))
+        }
+    };
+    duration.ok_or_else(|| anyhow!("Duration '{}' is too long", s))
+}
+
+/// The moment the duration `s` (see `parse_duration`) before now
+pub fn ago(s: &str) -> Result<DateTime<Utc>> {
+    Utc::now()
+        .checked_sub_signed(parse_duration(s)?)
+        .ok_or_else(|| anyhow!("Duration '{}' reaches too far back", s))
+}
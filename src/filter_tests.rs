@@ -0,0 +1,172 @@
+use super::*;
+use chrono::Utc;
+
+fn article(read: bool, starred: bool, published: Option<DateTime<Utc>>) -> FeedItem {
+    FeedItem {
+        id: "article".to_string(),
+        feed_url: "https://example.com/feed".to_string(),
+        title: "Article".to_string(),
+        link: "https://example.com/article".to_string(),
+        published,
+        read,
+        starred,
+        tags: vec!["Rust".to_string()],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("30m").unwrap(), Duration::minutes(30));
+    assert_eq!(parse_duration("12h").unwrap(), Duration::hours(12));
+    assert_eq!(parse_duration("3d").unwrap(), Duration::days(3));
+    assert_eq!(parse_duration("2w").unwrap(), Duration::weeks(2));
+
+    assert!(parse_duration("3").is_err());
+    assert!(parse_duration("d").is_err());
+    assert!(parse_duration("3y").is_err());
+    // Too long for a Duration, or to count back from now
+    assert!(parse_duration("999999999999999999w").is_err());
+    assert!(ago("9999999999w").is_err());
+}
+
+#[test]
+fn test_filter_matches() {
+    let now = Utc::now();
+    let unread = article(false, false, Some(now));
+    let starred_read = article(true, true, Some(now - Duration::days(10)));
+    let undated = article(false, false, None);
+
+    let filter = ArticleFilter::default();
+    assert!(filter.matches(&unread));
+    assert!(filter.matches(&undated));
+
+    let filter = ArticleFilter {
+        read: Some(false),
+        ..Default::default()
+    };
+    assert!(filter.matches(&unread));
+    assert!(!filter.matches(&starred_read));
+
+    let filter = ArticleFilter {
+        starred: Some(true),
+        ..Default::default()
+    };
+    assert!(!filter.matches(&unread));
+    assert!(filter.matches(&starred_read));
+
+    let filter = ArticleFilter {
+        since: Some(now - Duration::days(3)),
+        ..Default::default()
+    };
+    assert!(filter.matches(&unread));
+    assert!(!filter.matches(&starred_read));
+    assert!(!filter.matches(&undated));
+
+    // Cached just now though published long ago; the date stands in when
+    // the cache time is unknown
+    let late = FeedItem {
+        fetched: Some(now),
+        ..starred_read.clone()
+    };
+    let filter = ArticleFilter {
+        fetched_since: Some(now - Duration::days(3)),
+        ..Default::default()
+    };
+    assert!(filter.matches(&late));
+    assert!(filter.matches(&unread));
+    assert!(!filter.matches(&starred_read));
+    assert!(!filter.matches(&undated));
+
+    let filter = ArticleFilter {
+        feed: Some("https://other.com/feed".to_string()),
+        ..Default::default()
+    };
+    assert!(!filter.matches(&unread));
+}
+
+#[test]
+fn test_filter_tags_and_terms() {
+    let item = article(false, false, Some(Utc::now()));
+
+    let filter = ArticleFilter {
+        tags: vec!["rust".to_string()],
+        title_terms: vec!["ARTICLE".to_string()],
+        feed_contains: Some("EXAMPLE".to_string()),
+        ..Default::default()
+    };
+    assert!(filter.matches(&item));
+
+    let filter = ArticleFilter {
+        tags: vec!["go".to_string()],
+        ..Default::default()
+    };
+    assert!(!filter.matches(&item));
+
+    let filter = ArticleFilter {
+        text_terms: vec!["article".to_string(), "missing".to_string()],
+        ..Default::default()
+    };
+    assert!(!filter.matches(&item));
+}
+
+#[test]
+fn test_article_query_sorts_and_pages() {
+    let dated = |title: &str, days: Option<i64>| FeedItem {
+        title: title.to_string(),
+        ..article(false, false, days.map(|d| Utc::now() - Duration::days(d)))
+    };
+    let articles = vec![
+        dated("beta", Some(3)),
+        dated("Alpha", Some(1)),
+        dated("gamma", None),
+        dated("delta", Some(2)),
+    ];
+    let titles = |query: &ArticleQuery| -> Vec<String> {
+        query
+            .apply(articles.clone())
+            .into_iter()
+            .map(|a| a.title)
+            .collect()
+    };
+
+    let newest = ArticleQuery::default();
+    assert_eq!(titles(&newest), vec!["Alpha", "delta", "beta", "gamma"]);
+    let oldest = ArticleQuery {
+        sort: SortOrder::Oldest,
+        ..Default::default()
+    };
+    assert_eq!(titles(&oldest), vec!["beta", "delta", "Alpha", "gamma"]);
+    let by_title = ArticleQuery {
+        sort: SortOrder::Title,
+        offset: 1,
+        limit: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(titles(&by_title), vec!["beta", "delta"]);
+
+    let mut read = articles.clone();
+    read[1].read = true;
+    read[0].feed_url = "https://a.example/rss".to_string();
+    let sorted = |sort: SortOrder| -> Vec<String> {
+        ArticleQuery {
+            sort,
+            ..Default::default()
+        }
+        .apply(read.clone())
+        .into_iter()
+        .map(|a| a.title)
+        .collect()
+    };
+    assert_eq!(
+        sorted(SortOrder::Unread),
+        vec!["delta", "beta", "gamma", "Alpha"]
+    );
+    assert_eq!(sorted(SortOrder::Feed)[0], "beta");
+
+    let past_end = ArticleQuery {
+        offset: 10,
+        ..Default::default()
+    };
+    assert!(titles(&past_end).is_empty());
+}
@@ -0,0 +1,403 @@
+//! Client for the Google Reader API
+//!
+//! FreshRSS (`https://<host>/api/greader.php`), BazQux, Inoreader and
+//! Miniflux serve the API Google Reader clients were written against:
+//! `ClientLogin` trades a username and password for an auth token, streams
+//! list articles newest first in pages, and `edit-tag` adds or removes the
+//! `read` and `starred` states. Folders are `user/-/label/<name>` categories.
+
+use crate::models::FeedItem;
+use crate::sync::{Changes, Pull, RemoteArticle, RemoteState, Subscription, SyncBackend};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::future::Future;
+use std::time::Duration;
+
+#[cfg(test)]
+#[path = "greader_tests.rs"]
+mod tests;
+
+/// State streams and categories
+pub const READING_LIST: &str = "user/-/state/com.google/reading-list";
+pub const READ: &str = "user/-/state/com.google/read";
+pub const STARRED: &str = "user/-/state/com.google/starred";
+const LABEL_PREFIX: &str = "user/-/label/";
+
+/// Articles requested per page of a stream
+const PAGE_SIZE: usize = 250;
+
+/// Pages read on a first sync, bounding the history pulled from a large
+/// account
+const MAX_PAGES: usize = 40;
+
+/// Pages read catching up since the last sync, or listing a state's IDs;
+/// beyond them the sync fails rather than skip articles or misread state
+const MAX_CATCH_UP_PAGES: usize = 1_000;
+
+/// Item IDs listed per `stream/items/ids` page
+const IDS_PAGE_SIZE: usize = 10_000;
+
+/// Items changed per `edit-tag` request
+const EDIT_BATCH: usize = 250;
+
+/// A signed-in session with a Google Reader API server
+pub struct GReader {
+    client: reqwest::Client,
+    base: String,
+    auth: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscriptionList {
+    subscriptions: Vec<RawSubscription>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawSubscription {
+    id: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    categories: Vec<RawCategory>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawCategory {
+    id: String,
+    #[serde(default)]
+    label: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamContents {
+    #[serde(default)]
+    items: Vec<RawItem>,
+    #[serde(default)]
+    continuation: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawItem {
+    id: String,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    published: Option<i64>,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    canonical: Vec<RawHref>,
+    #[serde(default)]
+    alternate: Vec<RawHref>,
+    #[serde(default)]
+    summary: Option<RawText>,
+    #[serde(default)]
+    content: Option<RawText>,
+    origin: RawOrigin,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawHref {
+    href: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawText {
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawOrigin {
+    #[serde(rename = "streamId")]
+    stream_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ItemRefs {
+    #[serde(default, rename = "itemRefs")]
+    item_refs: Vec<ItemRef>,
+    #[serde(default)]
+    continuation: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ItemRef {
+    id: String,
+}
+
+impl GReader {
+    /// Signs in at `url`, the API's root (e.g. `.../api/greader.php`)
+    pub async fn login(url: &str, username: &str, password: &str) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent("ZetRss/0.1")
+            .timeout(Duration::from_secs(crate::config::get().fetch.timeout_secs))
+            .build()?;
+        let base = url.trim_end_matches('/').to_string();
+        let response = client
+            .post(format!("{}/accounts/ClientLogin", base))
+            .form(&[("Email", username), ("Passwd", password)])
+            .send()
+            .await
+            .with_context(|| format!("Sync server at {} unreachable", base))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!("Sign-in to {} failed: {}", base, status));
+        }
+        let auth = auth_token(&response.text().await?)
+            .ok_or_else(|| anyhow!("Sign-in to {} returned no Auth token", base))?;
+        Ok(Self { client, base, auth })
+    }
+
+    fn get(&self, path: &str) -> reqwest::RequestBuilder {
+        self.client
+            .get(format!("{}/reader/api/0/{}", self.base, path))
+            .header("Authorization", format!("GoogleLogin auth={}", self.auth))
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T> {
+        let response = self
+            .get(path)
+            .query(&[("output", "json")])
+            .query(query)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!("{} answered {}", path, status));
+        }
+        response
+            .json()
+            .await
+            .with_context(|| format!("{} sent invalid JSON", path))
+    }
+
+    async fn subscription_list(&self) -> Result<Vec<Subscription>> {
+        let list: SubscriptionList = self.get_json("subscription/list", &[]).await?;
+        Ok(list
+            .subscriptions
+            .into_iter()
+            .filter_map(subscription)
+            .collect())
+    }
+
+    /// Articles crawled since `since` (Unix seconds), newest first. A first
+    /// sync stops after `MAX_PAGES`; catching up fails if there's more than
+    /// `MAX_CATCH_UP_PAGES` of them, since the cursor would skip the rest
+    async fn items_since(&self, since: i64, first: bool) -> Result<Vec<RemoteArticle>> {
+        let max_pages = if first { MAX_PAGES } else { MAX_CATCH_UP_PAGES };
+        let mut items = Vec::new();
+        let mut continuation = None;
+        for _ in 0..max_pages {
+            let mut query = vec![("n", PAGE_SIZE.to_string()), ("ot", since.to_string())];
+            if let Some(c) = continuation.take() {
+                query.push(("c", c));
+            }
+            let page: StreamContents = self
+                .get_json(&format!("stream/contents/{}", READING_LIST), &query)
+                .await?;
+            items.extend(page.items.into_iter().map(remote_article));
+            match page.continuation {
+                Some(c) if !c.is_empty() => continuation = Some(c),
+                _ => return Ok(items),
+            }
+        }
+        if first {
+            return Ok(items);
+        }
+        Err(anyhow!(
+            "Over {} new articles since the last sync; not syncing so none are skipped",
+            items.len()
+        ))
+    }
+
+    /// IDs of the articles in `stream`, leaving out those in `exclude`; fails
+    /// rather than return a partial list, which would misstate the others
+    async fn item_ids(&self, stream: &str, exclude: Option<&str>) -> Result<HashSet<String>> {
+        let mut ids = HashSet::new();
+        let mut continuation = None;
+        for _ in 0..MAX_CATCH_UP_PAGES {
+            let mut query = vec![("s", stream.to_string()), ("n", IDS_PAGE_SIZE.to_string())];
+            if let Some(exclude) = exclude {
+                query.push(("xt", exclude.to_string()));
+            }
+            if let Some(c) = continuation.take() {
+                query.push(("c", c));
+            }
+            let refs: ItemRefs = self.get_json("stream/items/ids", &query).await?;
+            ids.extend(refs.item_refs.iter().map(|r| long_item_id(&r.id)));
+            match refs.continuation {
+                Some(c) if !c.is_empty() => continuation = Some(c),
+                _ => return Ok(ids),
+            }
+        }
+        Err(anyhow!(
+            "Over {} articles in {}; not syncing their state",
+            ids.len(),
+            stream
+        ))
+    }
+
+    /// Adds (`add`) or removes the `tag` on the articles, in batches
+    async fn edit_tag(&self, ids: &[String], tag: &str, add: bool) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let token = self
+            .get("token")
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        for batch in ids.chunks(EDIT_BATCH) {
+            let mut form: Vec<(&str, &str)> = batch.iter().map(|id| ("i", id.as_str())).collect();
+            form.push((if add { "a" } else { "r" }, tag));
+            form.push(("T", token.trim()));
+            let response = self
+                .client
+                .post(format!("{}/reader/api/0/edit-tag", self.base))
+                .header("Authorization", format!("GoogleLogin auth={}", self.auth))
+                .form(&form)
+                .send()
+                .await?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(anyhow!("edit-tag answered {}", status));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SyncBackend for GReader {
+    fn subscriptions(&self) -> impl Future<Output = Result<Vec<Subscription>>> + Send {
+        self.subscription_list()
+    }
+
+    /// Pulls by crawl time: the cursor is when the last pull started
+    fn pull(
+        &self,
+        cursor: Option<&str>,
+        oldest: DateTime<Utc>,
+    ) -> impl Future<Output = Result<Pull>> + Send {
+        let started = Utc::now().timestamp();
+        let last = cursor.and_then(|cursor| cursor.parse().ok());
+        let since = last.unwrap_or(oldest.timestamp());
+        async move {
+            Ok(Pull {
+                articles: self.items_since(since, last.is_none()).await?,
+                cursor: Some(started.to_string()),
+            })
+        }
+    }
+
+    async fn remote_state(&self) -> Result<RemoteState> {
+        Ok(RemoteState {
+            unread: self.item_ids(READING_LIST, Some(READ)).await?,
+            starred: self.item_ids(STARRED, None).await?,
+        })
+    }
+
+    async fn push(&self, changes: &Changes) -> Result<()> {
+        self.edit_tag(&changes.read, READ, true).await?;
+        self.edit_tag(&changes.unread, READ, false).await?;
+        self.edit_tag(&changes.starred, STARRED, true).await?;
+        self.edit_tag(&changes.unstarred, STARRED, false).await
+    }
+}
+
+/// The `Auth=` line of a ClientLogin reply
+fn auth_token(reply: &str) -> Option<String> {
+    reply
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Auth="))
+        .map(str::to_string)
+}
+
+/// A subscription with its feed URL, which some servers only give in the ID
+fn subscription(raw: RawSubscription) -> Option<Subscription> {
+    let url = raw
+        .url
+        .filter(|url| !url.is_empty())
+        .or_else(|| raw.id.strip_prefix("feed/").map(str::to_string))
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))?;
+    let folders = raw
+        .categories
+        .into_iter()
+        .filter_map(|c| {
+            c.label
+                .or_else(|| c.id.strip_prefix(LABEL_PREFIX).map(str::to_string))
+        })
+        .collect();
+    Some(Subscription {
+        remote_id: raw.id,
+        url,
+        title: raw.title,
+        folders,
+    })
+}
+
+/// The long form (`tag:google.com,2005:reader/item/<hex>`) of an item ID;
+/// `stream/items/ids` lists them as signed decimal numbers
+pub fn long_item_id(id: &str) -> String {
+    match id.parse::<i64>() {
+        Ok(n) => format!("tag:google.com,2005:reader/item/{:016x}", n as u64),
+        Err(_) => id.to_string(),
+    }
+}
+
+/// The article of a stream item; its state comes from `item_ids`
+fn remote_article(item: RawItem) -> RemoteArticle {
+    let link = item
+        .canonical
+        .into_iter()
+        .chain(item.alternate)
+        .next()
+        .map(|l| l.href)
+        .unwrap_or_default();
+    let description = item.summary.map(|s| s.content);
+    // Servers that don't number their feeds name them by URL
+    let feed_url = item
+        .origin
+        .stream_id
+        .strip_prefix("feed/")
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+        .unwrap_or_default()
+        .to_string();
+    RemoteArticle {
+        remote_id: item.id.clone(),
+        feed_id: item.origin.stream_id,
+        article: FeedItem {
+            id: item.id,
+            feed_url,
+            title: item
+                .title
+                .filter(|t| !t.trim().is_empty())
+                .unwrap_or_else(|| "Untitled".to_string()),
+            link,
+            content: item
+                .content
+                .map(|c| c.content)
+                .or_else(|| description.clone()),
+            description,
+            published: item
+                .published
+                .and_then(|secs| Utc.timestamp_opt(secs, 0).single()),
+            author: item.author.filter(|a| !a.is_empty()),
+            read: false,
+            starred: false,
+            tags: Vec::new(),
+            fetched: None,
+            filepath: None,
+            raw: None,
+        },
+    }
+}
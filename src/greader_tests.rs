@@ -0,0 +1,77 @@
+use super::*;
+
+#[test]
+fn test_long_item_id() {
+    assert_eq!(
+        long_item_id("1"),
+        "tag:google.com,2005:reader/item/0000000000000001"
+    );
+    assert_eq!(
+        long_item_id("-1"),
+        "tag:google.com,2005:reader/item/ffffffffffffffff"
+    );
+    assert_eq!(
+        long_item_id("tag:google.com,2005:reader/item/00000000000000ff"),
+        "tag:google.com,2005:reader/item/00000000000000ff"
+    );
+}
+
+#[test]
+fn test_auth_token() {
+    assert_eq!(
+        auth_token("SID=abc\nLSID=null\nAuth=me/0123\n").as_deref(),
+        Some("me/0123")
+    );
+    assert_eq!(auth_token("Error=BadAuthentication\n"), None);
+}
+
+#[test]
+fn test_subscriptions() {
+    let list: SubscriptionList = serde_json::from_str(
+        r#"{"subscriptions":[
+            {"id":"feed/1","title":"Example","url":"https://example.com/feed",
+             "categories":[{"id":"user/-/label/Tech","label":"Tech"},{"id":"user/-/label/Rust"}]},
+            {"id":"feed/https://other.org/rss","title":"Other","categories":[]},
+            {"id":"feed/2","title":"No URL"}
+        ]}"#,
+    )
+    .unwrap();
+    let subscriptions: Vec<Subscription> = list
+        .subscriptions
+        .into_iter()
+        .filter_map(subscription)
+        .collect();
+    assert_eq!(subscriptions.len(), 2);
+    assert_eq!(subscriptions[0].remote_id, "feed/1");
+    assert_eq!(subscriptions[0].url, "https://example.com/feed");
+    assert_eq!(subscriptions[0].folders, vec!["Tech", "Rust"]);
+    assert_eq!(subscriptions[1].url, "https://other.org/rss");
+}
+
+#[test]
+fn test_remote_article() {
+    let item: RawItem = serde_json::from_str(
+        r#"{"id":"tag:google.com,2005:reader/item/0000000000000001",
+            "title":"Async Rust","published":1709285400,"author":"Ferris",
+            "alternate":[{"href":"https://example.com/async","type":"text/html"}],
+            "summary":{"content":"<p>Futures are lazy.</p>"},
+            "categories":["user/-/state/com.google/reading-list"],
+            "origin":{"streamId":"feed/1","title":"Example"}}"#,
+    )
+    .unwrap();
+    let remote = remote_article(item);
+    assert_eq!(remote.feed_id, "feed/1");
+    let article = remote.article;
+    assert_eq!(article.feed_url, "");
+    assert_eq!(
+        article.id,
+        "tag:google.com,2005:reader/item/0000000000000001"
+    );
+    assert_eq!(article.link, "https://example.com/async");
+    assert_eq!(article.author.as_deref(), Some("Ferris"));
+    assert_eq!(article.content.as_deref(), Some("<p>Futures are lazy.</p>"));
+    assert_eq!(
+        article.published.unwrap().to_rfc3339(),
+        "2024-03-01T09:30:00+00:00"
+    );
+}
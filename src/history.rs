@@ -0,0 +1,164 @@
+//! Per-feed fetch history: one JSON line per fetch attempt in
+//! `feeds/<feed>.log.jsonl`, shown by `zetrss log` and `zetrss broken`
+
+use crate::scanner::FeedSource;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+#[cfg(test)]
+#[path = "history_tests.rs"]
+mod tests;
+
+/// Attempts kept per feed; older ones are dropped once the log grows to twice this
+pub const MAX_RECORDS: usize = 500;
+
+/// Outcome of one fetch attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FetchStatus {
+    Ok,
+    Error,
+}
+
+/// One fetch attempt of a feed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FetchRecord {
+    pub time: DateTime<Utc>,
+    pub status: FetchStatus,
+    /// Items in the fetched feed
+    pub items: usize,
+    /// Articles that weren't cached before
+    pub added: usize,
+    pub duration_ms: u64,
+    /// Size of the downloaded document
+    pub bytes: usize,
+    pub error: Option<String>,
+}
+
+/// What a feed's history says about it, for the `log` overview
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeedHistory {
+    pub url: String,
+    pub attempts: usize,
+    pub failures: usize,
+    pub last_attempt: Option<FetchRecord>,
+    /// Last time a fetch added any articles
+    pub last_added: Option<DateTime<Utc>>,
+}
+
+impl FeedHistory {
+    pub fn from_records(url: &str, records: &[FetchRecord]) -> Self {
+        Self {
+            url: url.to_string(),
+            attempts: records.len(),
+            failures: records
+                .iter()
+                .filter(|r| r.status == FetchStatus::Error)
+                .count(),
+            last_attempt: records.last().cloned(),
+            last_added: records.iter().rev().find(|r| r.added > 0).map(|r| r.time),
+        }
+    }
+}
+
+/// A feed whose latest fetch failed, with the note line declaring it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BrokenFeed {
+    pub url: String,
+    /// Empty for feeds added without a note
+    pub source_file: String,
+    pub line_number: usize,
+    pub error: String,
+    /// Failed attempts in a row, up to the latest
+    pub failures: usize,
+    /// First attempt of the failing streak
+    pub failing_since: DateTime<Utc>,
+}
+
+impl BrokenFeed {
+    /// The feed's breakage, or None if its latest attempt succeeded (or it
+    /// was never fetched)
+    pub fn from_records(feed: &FeedSource, records: &[FetchRecord]) -> Option<Self> {
+        let latest = records.last().filter(|r| r.status == FetchStatus::Error)?;
+        let streak: Vec<&FetchRecord> = records
+            .iter()
+            .rev()
+            .take_while(|r| r.status == FetchStatus::Error)
+            .collect();
+        Some(Self {
+            url: feed.url.clone(),
+            source_file: feed.source_file.clone(),
+            line_number: feed.line_number,
+            error: latest.error.clone().unwrap_or_default(),
+            failures: streak.len(),
+            failing_since: streak.last().map_or(latest.time, |r| r.time),
+        })
+    }
+
+    /// A `file:line:col: message` line for Vim's quickfix list (the default
+    /// `errorformat` reads it); feeds without a note get just the message
+    pub fn quickfix_line(&self) -> String {
+        let message = format!(
+            "{}: {} (failed {}x since {} UTC)",
+            self.url,
+            self.error.replace('\n', " "),
+            self.failures,
+            self.failing_since.format("%Y-%m-%d %H:%M")
+        );
+        if self.source_file.is_empty() {
+            message
+        } else {
+            format!("{}:{}:1: {}", self.source_file, self.line_number, message)
+        }
+    }
+}
+
+/// Appends a record to the log at `path`, trimming it to the newest
+/// `MAX_RECORDS` once it has grown to twice that
+pub fn append(path: &Path, record: &FetchRecord) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open fetch log {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    drop(file);
+
+    // Counting lines is cheap; only parse the log when it's due for trimming
+    let lines = fs::read(path)?.iter().filter(|&&b| b == b'\n').count();
+    if lines >= MAX_RECORDS * 2 {
+        let records = read(path)?;
+        write(path, &records[records.len().saturating_sub(MAX_RECORDS)..])?;
+    }
+    Ok(())
+}
+
+/// Reads the log at `path`, oldest first; a missing log is empty
+/// Lines that don't parse (e.g. cut off by a crash) are skipped
+pub fn read(path: &Path) -> Result<Vec<FetchRecord>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read fetch log {}", path.display()))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+fn write(path: &Path, records: &[FetchRecord]) -> Result<()> {
+    let mut content = String::new();
+    for record in records {
+        content.push_str(&serde_json::to_string(record)?);
+        content.push('\n');
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
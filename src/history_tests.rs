@@ -0,0 +1,113 @@
+use super::*;
+use chrono::{Duration, TimeZone};
+use tempfile::TempDir;
+
+fn record(minutes: i64, added: usize, error: Option<&str>) -> FetchRecord {
+    FetchRecord {
+        time: Utc.with_ymd_and_hms(2024, 1, 5, 12, 0, 0).unwrap() + Duration::minutes(minutes),
+        status: if error.is_some() {
+            FetchStatus::Error
+        } else {
+            FetchStatus::Ok
+        },
+        items: if error.is_some() { 0 } else { 10 },
+        added,
+        duration_ms: 120,
+        bytes: 2048,
+        error: error.map(String::from),
+    }
+}
+
+#[test]
+fn test_append_and_read() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("feed.log.jsonl");
+    assert!(read(&path).unwrap().is_empty());
+
+    append(&path, &record(0, 3, None)).unwrap();
+    append(&path, &record(10, 0, Some("Failed to fetch: timeout"))).unwrap();
+    let records = read(&path).unwrap();
+    assert_eq!(
+        records,
+        vec![
+            record(0, 3, None),
+            record(10, 0, Some("Failed to fetch: timeout"))
+        ]
+    );
+
+    // A truncated last line doesn't hide the rest of the log
+    let mut content = fs::read_to_string(&path).unwrap();
+    content.push_str("{\"time\":");
+    fs::write(&path, content).unwrap();
+    assert_eq!(read(&path).unwrap().len(), 2);
+}
+
+#[test]
+fn test_append_trims_old_records() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("feed.log.jsonl");
+    for i in 0..MAX_RECORDS * 2 {
+        append(&path, &record(i as i64, 0, None)).unwrap();
+    }
+    let records = read(&path).unwrap();
+    assert_eq!(records.len(), MAX_RECORDS);
+    assert_eq!(
+        records.last().unwrap(),
+        &record((MAX_RECORDS * 2 - 1) as i64, 0, None)
+    );
+}
+
+#[test]
+fn test_feed_history_summary() {
+    let records = vec![
+        record(0, 3, None),
+        record(10, 0, None),
+        record(20, 0, Some("Failed to fetch: 500")),
+    ];
+    let history = FeedHistory::from_records("https://example.com/feed", &records);
+    assert_eq!(history.attempts, 3);
+    assert_eq!(history.failures, 1);
+    assert_eq!(history.last_attempt, Some(records[2].clone()));
+    assert_eq!(history.last_added, Some(records[0].time));
+
+    let empty = FeedHistory::from_records("https://example.com/feed", &[]);
+    assert_eq!(empty.last_attempt, None);
+    assert_eq!(empty.last_added, None);
+}
+
+#[test]
+fn test_broken_feed_from_failing_streak() {
+    let feed = FeedSource {
+        url: "https://example.com/rss".to_string(),
+        source_file: "/zet/reading.md".to_string(),
+        line_number: 12,
+        root: None,
+        options: Default::default(),
+    };
+    let records = vec![
+        record(0, 3, Some("Failed to fetch: timeout")),
+        record(10, 0, None),
+        record(20, 0, Some("Failed to fetch: 404 Not Found")),
+        record(30, 0, Some("Failed to fetch: 404\nNot Found")),
+    ];
+
+    let broken = BrokenFeed::from_records(&feed, &records).unwrap();
+    assert_eq!(broken.failures, 2);
+    assert_eq!(broken.failing_since, records[2].time);
+    assert_eq!(
+        broken.quickfix_line(),
+        "/zet/reading.md:12:1: https://example.com/rss: Failed to fetch: 404 Not Found (failed 2x since 2024-01-05 12:20 UTC)"
+    );
+
+    assert!(BrokenFeed::from_records(&feed, &records[..2]).is_none());
+    assert!(BrokenFeed::from_records(&feed, &[]).is_none());
+
+    let added = FeedSource {
+        source_file: String::new(),
+        ..feed
+    };
+    let line = BrokenFeed::from_records(&added, &records)
+        .unwrap()
+        .quickfix_line();
+    assert!(line.starts_with("https://example.com/rss: "));
+}
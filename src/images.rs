@@ -0,0 +1,299 @@
+//! Inline article images for the TUI viewer
+//!
+//! Article `<img>` tags become `[image: alt]` placeholder lines. On terminals
+//! speaking the kitty graphics protocol or sixel, the viewer downloads the
+//! images into `images/` under the data directory and draws them below their
+//! placeholders; everywhere else the placeholders stay as text.
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use regex::Regex;
+use serde::Deserialize;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+#[cfg(test)]
+#[path = "images_tests.rs"]
+mod tests;
+
+/// Largest image downloaded
+pub const MAX_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+/// Images downloaded per article
+const MAX_IMAGES: usize = 16;
+/// Tallest an image is drawn, in terminal rows
+const MAX_ROWS: u16 = 20;
+/// Base64 bytes per kitty graphics escape sequence
+const KITTY_CHUNK: usize = 4096;
+/// Removes every kitty image placement from the screen
+pub const KITTY_CLEAR: &str = "\x1b_Ga=d,q=2\x1b\\";
+
+/// `viewer.images` in the config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageMode {
+    /// Detect the protocol from the terminal's environment
+    #[default]
+    Auto,
+    Kitty,
+    Sixel,
+    /// Placeholders only
+    Off,
+}
+
+/// Terminal graphics protocols images can be drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Graphics {
+    Kitty,
+    Sixel,
+}
+
+impl ImageMode {
+    /// The protocol to draw images with, if any
+    pub fn graphics(self) -> Option<Graphics> {
+        match self {
+            ImageMode::Auto => detect(&|name| std::env::var(name).ok()),
+            ImageMode::Kitty => Some(Graphics::Kitty),
+            ImageMode::Sixel => Some(Graphics::Sixel),
+            ImageMode::Off => None,
+        }
+    }
+}
+
+/// Guesses the terminal's graphics support from its environment variables
+fn detect(var: &dyn Fn(&str) -> Option<String>) -> Option<Graphics> {
+    // Multiplexers don't pass either protocol through reliably
+    if var("TMUX").is_some() || var("STY").is_some() {
+        return None;
+    }
+    let term = var("TERM").unwrap_or_default();
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+    if var("KITTY_WINDOW_ID").is_some()
+        || matches!(term.as_str(), "xterm-kitty" | "xterm-ghostty")
+        || matches!(program.as_str(), "WezTerm" | "ghostty")
+    {
+        Some(Graphics::Kitty)
+    } else if term.contains("sixel")
+        || matches!(term.as_str(), "foot" | "foot-extra" | "mlterm" | "contour")
+        || matches!(program.as_str(), "iTerm.app" | "mlterm")
+    {
+        Some(Graphics::Sixel)
+    } else {
+        None
+    }
+}
+
+/// Replaces each `<img>` in article HTML with an `[image: alt]` paragraph
+/// Returns the rewritten HTML and the images' absolute URLs, in order;
+/// images without a usable URL are reduced to their alt text
+pub fn placeholders(html: &str, base_url: &str) -> (String, Vec<String>) {
+    static IMG: OnceLock<Regex> = OnceLock::new();
+    static ATTR: OnceLock<Regex> = OnceLock::new();
+    let img = IMG.get_or_init(|| Regex::new(r"(?is)<img\b[^>]*>").unwrap());
+    let attr = ATTR
+        .get_or_init(|| Regex::new(r#"(?is)\b(src|alt)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+
+    let mut urls = Vec::new();
+    let html = img.replace_all(html, |caps: &regex::Captures| {
+        let mut src = None;
+        let mut alt = String::new();
+        for attr in attr.captures_iter(&caps[0]) {
+            let value = attr.get(2).or(attr.get(3)).map_or("", |v| v.as_str());
+            if attr[1].eq_ignore_ascii_case("src") {
+                src = Some(value.trim().to_string());
+            } else {
+                alt = value.trim().to_string();
+            }
+        }
+        match src.and_then(|src| absolute_url(&src, base_url)) {
+            Some(url) => {
+                urls.push(url);
+                if alt.is_empty() {
+                    "<p>[image]</p>".to_string()
+                } else {
+                    format!("<p>[image: {}]</p>", alt)
+                }
+            }
+            None if alt.is_empty() => String::new(),
+            None => format!("[{}]", alt),
+        }
+    });
+    (html.into_owned(), urls)
+}
+
+/// An image in a rendered article
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArticleImage {
+    pub url: String,
+    /// Content line holding its placeholder
+    pub line: usize,
+}
+
+/// True for the placeholder lines written by `placeholders`
+pub fn is_placeholder(line: &str) -> bool {
+    line.starts_with("[image: ") || line == "[image]"
+}
+
+/// `src` resolved against the article link; only http(s) images are fetched
+fn absolute_url(src: &str, base_url: &str) -> Option<String> {
+    let url = match reqwest::Url::parse(base_url) {
+        Ok(base) => base.join(src).ok()?,
+        Err(_) => reqwest::Url::parse(src).ok()?,
+    };
+    matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+}
+
+/// Where an image is cached under the data directory
+pub fn cached_path(base_dir: &Path, url: &str) -> PathBuf {
+    base_dir
+        .join("images")
+        .join(format!("{:016x}", crate::cache::stable_hash(url)))
+}
+
+/// Downloads the images that aren't cached yet
+/// Failures are logged and leave the image as a placeholder
+pub fn download(base_dir: &Path, urls: &[String]) {
+    let missing: Vec<&String> = urls
+        .iter()
+        .take(MAX_IMAGES)
+        .filter(|url| !cached_path(base_dir, url).exists())
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+    if let Err(e) = fs::create_dir_all(base_dir.join("images")) {
+        tracing::debug!("Failed to create image cache: {}", e);
+        return;
+    }
+
+    // The viewer runs inside the CLI's runtime, which can't be blocked on,
+    // so the downloads get a runtime of their own on a scoped thread
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(e) => return tracing::debug!("Failed to start image downloads: {}", e),
+            };
+            runtime.block_on(futures::future::join_all(missing.into_iter().map(
+                |url| async move {
+                    if let Err(e) = download_one(base_dir, url).await {
+                        tracing::debug!("Failed to download image {}: {:#}", url, e);
+                    }
+                },
+            )));
+        });
+    });
+}
+
+async fn download_one(base_dir: &Path, url: &str) -> Result<()> {
+    let bytes = crate::fetcher::fetch_bytes(url, MAX_IMAGE_BYTES).await?;
+    // Written aside and renamed, so an interrupted download isn't cached
+    let path = cached_path(base_dir, url);
+    let partial = path.with_extension("part");
+    fs::write(&partial, bytes)?;
+    fs::rename(&partial, &path)?;
+    Ok(())
+}
+
+/// An image encoded for the terminal
+#[derive(Debug, Clone)]
+pub struct Encoded {
+    pub cols: u16,
+    pub rows: u16,
+    /// Escape sequences drawing the image at the cursor
+    pub data: String,
+}
+
+/// Decodes a cached image and encodes it at most `max_cols` wide
+/// `cell` is the pixel size of one terminal cell
+pub fn encode(path: &Path, graphics: Graphics, max_cols: u16, cell: (u16, u16)) -> Result<Encoded> {
+    let image = image::io::Reader::open(path)?
+        .with_guessed_format()?
+        .decode()
+        .with_context(|| format!("Failed to decode {}", path.display()))?;
+    let (cols, rows) = fit(image.width(), image.height(), max_cols, cell);
+    let data = match graphics {
+        Graphics::Kitty => {
+            let mut png = Vec::new();
+            image.write_to(&mut Cursor::new(&mut png), image::ImageOutputFormat::Png)?;
+            kitty_sequence(&png, cols, rows)
+        }
+        Graphics::Sixel => sixel_sequence(&image, cols, rows, cell)?,
+    };
+    Ok(Encoded { cols, rows, data })
+}
+
+/// Size in cells that keeps the image's aspect ratio within `max_cols` and
+/// `MAX_ROWS`, never scaling it past its own pixel size
+fn fit(width: u32, height: u32, max_cols: u16, (cell_w, cell_h): (u16, u16)) -> (u16, u16) {
+    let (width, height) = (f64::from(width.max(1)), f64::from(height.max(1)));
+    let (cell_w, cell_h) = (f64::from(cell_w.max(1)), f64::from(cell_h.max(1)));
+    let mut cols = (width / cell_w)
+        .ceil()
+        .clamp(1.0, f64::from(max_cols.max(1)));
+    let mut rows = (cols * cell_w * height / width / cell_h).ceil().max(1.0);
+    if rows > f64::from(MAX_ROWS) {
+        rows = f64::from(MAX_ROWS);
+        cols = (rows * cell_h * width / height / cell_w).ceil().max(1.0);
+    }
+    (cols as u16, rows as u16)
+}
+
+/// Kitty graphics protocol: PNG data scaled into `cols` x `rows` cells,
+/// split into chunks, leaving the cursor in place
+fn kitty_sequence(png: &[u8], cols: u16, rows: u16) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(png);
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(KITTY_CHUNK).collect();
+    let mut out = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).expect("base64 is ASCII");
+        if i == 0 {
+            out.push_str(&format!(
+                "\x1b_Ga=T,f=100,q=2,C=1,c={},r={},m={};{}\x1b\\",
+                cols, rows, more, chunk
+            ));
+        } else {
+            out.push_str(&format!("\x1b_Gm={};{}\x1b\\", more, chunk));
+        }
+    }
+    out
+}
+
+/// Sixel: the image resized to the pixels of `cols` x `rows` cells
+fn sixel_sequence(image: &DynamicImage, cols: u16, rows: u16, cell: (u16, u16)) -> Result<String> {
+    let rgb = image
+        .resize(
+            u32::from(cols) * u32::from(cell.0),
+            u32::from(rows) * u32::from(cell.1),
+            FilterType::Triangle,
+        )
+        .to_rgb8();
+    icy_sixel::sixel_string(
+        rgb.as_raw(),
+        rgb.width() as i32,
+        rgb.height() as i32,
+        icy_sixel::PixelFormat::RGB888,
+        icy_sixel::DiffusionMethod::Auto,
+        icy_sixel::MethodForLargest::Auto,
+        icy_sixel::MethodForRep::Auto,
+        icy_sixel::Quality::HIGH,
+    )
+    .map_err(|e| anyhow!("Failed to encode sixel: {}", e))
+}
+
+/// Pixel size of a terminal cell, guessing 8x16 when the terminal won't say
+pub fn cell_size() -> (u16, u16) {
+    match crossterm::terminal::window_size() {
+        Ok(size) if size.width > 0 && size.columns > 0 && size.rows > 0 => {
+            (size.width / size.columns, size.height / size.rows)
+        }
+        _ => (8, 16),
+    }
+}
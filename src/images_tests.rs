@@ -0,0 +1,90 @@
+use super::*;
+use tempfile::TempDir;
+
+fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+    move |name| {
+        vars.iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.to_string())
+    }
+}
+
+#[test]
+fn test_detect_graphics() {
+    assert_eq!(
+        detect(&env(&[("TERM", "xterm-kitty")])),
+        Some(Graphics::Kitty)
+    );
+    assert_eq!(
+        detect(&env(&[("TERM_PROGRAM", "WezTerm")])),
+        Some(Graphics::Kitty)
+    );
+    assert_eq!(detect(&env(&[("TERM", "foot")])), Some(Graphics::Sixel));
+    assert_eq!(detect(&env(&[("TERM", "xterm-256color")])), None);
+    // Not through a multiplexer, even in kitty
+    assert_eq!(
+        detect(&env(&[("TERM", "xterm-kitty"), ("TMUX", "/tmp/tmux")])),
+        None
+    );
+}
+
+#[test]
+fn test_placeholders_resolve_urls() {
+    let (html, urls) = placeholders(
+        r#"<p>Intro</p><img alt="A chart" src="/img/chart.png"><IMG SRC='https://cdn.example/b.jpg'><img src="data:image/png;base64,AAAA" alt="inline">"#,
+        "https://example.com/posts/1",
+    );
+    assert_eq!(
+        urls,
+        vec![
+            "https://example.com/img/chart.png",
+            "https://cdn.example/b.jpg"
+        ]
+    );
+    assert_eq!(
+        html,
+        "<p>Intro</p><p>[image: A chart]</p><p>[image]</p>[inline]"
+    );
+}
+
+#[test]
+fn test_fit_keeps_aspect_ratio() {
+    // 160x80 px with 8x16 cells: 20 columns, 5 rows
+    assert_eq!(fit(160, 80, 80, (8, 16)), (20, 5));
+    // Wider than the pane: scaled down to it
+    assert_eq!(fit(1600, 800, 40, (8, 16)), (40, 10));
+    // Tall images are capped at MAX_ROWS
+    assert_eq!(fit(100, 10_000, 80, (8, 16)), (1, MAX_ROWS));
+}
+
+#[test]
+fn test_kitty_sequence_is_chunked() {
+    let data = vec![0u8; KITTY_CHUNK];
+    let sequence = kitty_sequence(&data, 10, 4);
+    assert!(sequence.starts_with("\x1b_Ga=T,f=100,q=2,C=1,c=10,r=4,m=1;"));
+    assert_eq!(sequence.matches("\x1b_G").count(), 2);
+    let last = sequence.rsplit("\x1b_G").next().unwrap();
+    assert!(last.starts_with("m=0;"));
+    assert!(last.ends_with("\x1b\\"));
+}
+
+#[test]
+fn test_encode_cached_image() {
+    let temp_dir = TempDir::new().unwrap();
+    let url = "https://example.com/a.png";
+    let path = cached_path(temp_dir.path(), url);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    image::RgbImage::new(16, 32)
+        .save_with_format(&path, image::ImageFormat::Png)
+        .unwrap();
+
+    // Cached images aren't downloaded again
+    download(temp_dir.path(), &[url.to_string()]);
+
+    let kitty = encode(&path, Graphics::Kitty, 80, (8, 16)).unwrap();
+    assert_eq!((kitty.cols, kitty.rows), (2, 2));
+    assert!(kitty.data.starts_with("\x1b_G"));
+
+    let sixel = encode(&path, Graphics::Sixel, 80, (8, 16)).unwrap();
+    assert!(sixel.data.starts_with("\x1bP"));
+}
@@ -0,0 +1,508 @@
+use crate::crypt;
+use crate::filter::{ArticleFilter, SortOrder};
+use crate::models::FeedItem;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+#[cfg(test)]
+#[path = "index_tests.rs"]
+mod tests;
+
+/// SQLite index of article metadata, kept next to the markdown files
+///
+/// The markdown files stay the source of truth: `sync` brings the index up to
+/// date with whatever is on disk (comparing modification times), and the cache
+/// re-indexes each file it writes. Article bodies live only in the FTS5 table
+/// used for full-text search. Archived articles keep their rows, tagged with
+/// the bundle they live in, so they stay searchable.
+pub struct ArticleIndex {
+    conn: Mutex<Connection>,
+}
+
+/// Article counts for one feed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeedCounts {
+    pub feed_url: String,
+    pub total: usize,
+    pub unread: usize,
+    pub starred: usize,
+}
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS articles (
+    path      TEXT PRIMARY KEY,
+    id        TEXT NOT NULL,
+    feed_url  TEXT NOT NULL,
+    title     TEXT NOT NULL,
+    link      TEXT NOT NULL,
+    author    TEXT,
+    published INTEGER,
+    read      INTEGER NOT NULL,
+    starred   INTEGER NOT NULL,
+    tags      TEXT NOT NULL,
+    fetched   INTEGER, -- microseconds, so digests can window on it exactly
+    mtime     INTEGER NOT NULL,
+    archive   TEXT
+);
+CREATE TABLE IF NOT EXISTS archives (
+    name  TEXT PRIMARY KEY,
+    mtime INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS articles_id ON articles(id);
+CREATE INDEX IF NOT EXISTS articles_feed ON articles(feed_url);
+CREATE INDEX IF NOT EXISTS articles_published ON articles(published);
+CREATE INDEX IF NOT EXISTS articles_archive ON articles(archive);
+CREATE VIRTUAL TABLE IF NOT EXISTS articles_fts USING fts5(
+    path UNINDEXED,
+    title,
+    body,
+    tokenize = 'porter unicode61'
+);
+";
+
+/// Bumped when the schema changes in a way that needs every file re-read
+const SCHEMA_VERSION: i64 = 4;
+
+/// Relative weight of title matches over body matches in search ranking
+const TITLE_BOOST: f64 = 10.0;
+
+impl ArticleIndex {
+    /// Opens (creating if needed) the index database at `path`
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open article index {}", path.display()))?;
+        // Several zetrss processes (viewer, daemon, plugin calls) may share the index
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version < SCHEMA_VERSION {
+            // Everything here is derived, so the next sync simply rebuilds it
+            conn.execute_batch(
+                "DROP TABLE IF EXISTS articles;
+                 DROP TABLE IF EXISTS articles_fts;
+                 DROP TABLE IF EXISTS archives;",
+            )?;
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        }
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        // A panic while holding the lock can't leave SQLite in a bad state
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Inserts or replaces the row for an article file
+    /// `item.filepath` must be set
+    pub fn upsert(&self, item: &FeedItem, mtime: i64) -> Result<()> {
+        let path = item
+            .filepath
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Can't index article {} without a path", item.id))?;
+        insert_row(&self.conn(), path, item, mtime, None)
+    }
+
+    pub fn remove(&self, path: &Path) -> Result<()> {
+        delete_row(&self.conn(), &path.to_string_lossy())
+    }
+
+    /// Drops every row, so the next sync indexes everything from scratch
+    pub fn clear(&self) -> Result<()> {
+        self.conn().execute_batch(
+            "DELETE FROM articles;
+             DELETE FROM articles_fts;
+             DELETE FROM archives;",
+        )?;
+        Ok(())
+    }
+
+    /// Rewrites the database without free pages or stale full-text segments
+    /// and empties the WAL, so text dropped from the index leaves the disk
+    pub fn compact(&self) -> Result<()> {
+        let conn = self.conn();
+        conn.execute_batch(
+            "INSERT INTO articles_fts (articles_fts) VALUES ('optimize');
+             VACUUM;",
+        )?;
+        let busy: i64 = conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| row.get(0))?;
+        if busy != 0 {
+            return Err(anyhow::anyhow!(
+                "Article index is in use; close other zetrss processes and run `zetrss reindex`"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Path of the article file with this ID, if indexed and not archived
+    pub fn path_for_id(&self, id: &str) -> Result<Option<PathBuf>> {
+        let path: Option<String> = self
+            .conn()
+            .query_row(
+                "SELECT path FROM articles WHERE id = ?1 AND archive IS NULL
+                 ORDER BY mtime DESC LIMIT 1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(path.map(PathBuf::from))
+    }
+
+    /// Archive bundle holding the article with this ID, if it was archived
+    pub fn archive_for_id(&self, id: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn()
+            .query_row(
+                "SELECT archive FROM articles WHERE id = ?1 AND archive IS NOT NULL LIMIT 1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Metadata of articles matching the filter, in `sort` order
+    /// Rows carry no content, so `text_terms` are ignored here and must be
+    /// checked against the article files by the caller. Archived articles
+    /// are left out.
+    pub fn query(&self, filter: &ArticleFilter, sort: SortOrder) -> Result<Vec<FeedItem>> {
+        let mut clauses = vec!["archive IS NULL"];
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+        if let Some(read) = filter.read {
+            clauses.push("read = ?");
+            values.push(read.into());
+        }
+        if let Some(starred) = filter.starred {
+            clauses.push("starred = ?");
+            values.push(starred.into());
+        }
+        if let Some(ref feed) = filter.feed {
+            clauses.push("feed_url = ?");
+            values.push(feed.clone().into());
+        }
+        if let Some(since) = filter.since {
+            clauses.push("published >= ?");
+            values.push(since.timestamp().into());
+        }
+        if let Some(until) = filter.until {
+            // Rows hold whole seconds; the exact bound is re-checked below
+            clauses.push("published <= ?");
+            values.push(until.timestamp().into());
+        }
+        if let Some(since) = filter.fetched_since {
+            // Articles cached before fetch times were kept count from their date
+            clauses.push("coalesce(fetched, published * 1000000) >= ?");
+            values.push(since.timestamp_micros().into());
+        }
+
+        let sql = format!(
+            "SELECT path, id, feed_url, title, link, author, published, read, starred, tags,
+                    fetched
+             FROM articles WHERE {} ORDER BY {}",
+            clauses.join(" AND "),
+            match sort {
+                SortOrder::Newest => "published IS NULL, published DESC, mtime DESC",
+                SortOrder::Oldest => "published IS NULL, published ASC, mtime ASC",
+                SortOrder::Title => "lower(title), published DESC",
+                SortOrder::Feed => "feed_url, published IS NULL, published DESC, mtime DESC",
+                SortOrder::Unread => "read, published IS NULL, published DESC, mtime DESC",
+            }
+        );
+
+        let metadata_filter = ArticleFilter {
+            text_terms: Vec::new(),
+            since: None,
+            until: filter.until,
+            fetched_since: None,
+            ..filter.clone()
+        };
+
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values), |row| {
+            let tags: String = row.get(9)?;
+            Ok(FeedItem {
+                filepath: Some(row.get(0)?),
+                id: row.get(1)?,
+                feed_url: row.get(2)?,
+                title: row.get(3)?,
+                link: row.get(4)?,
+                author: row.get(5)?,
+                published: row
+                    .get::<_, Option<i64>>(6)?
+                    .and_then(|ts| DateTime::<Utc>::from_timestamp(ts, 0)),
+                read: row.get(7)?,
+                starred: row.get(8)?,
+                tags: serde_json::from_str(&tags).unwrap_or_default(),
+                fetched: row
+                    .get::<_, Option<i64>>(10)?
+                    .and_then(DateTime::<Utc>::from_timestamp_micros),
+                description: None,
+                content: None,
+                raw: None,
+            })
+        })?;
+
+        let mut items = Vec::new();
+        for item in rows {
+            let item = item?;
+            if metadata_filter.matches(&item) {
+                items.push(item);
+            }
+        }
+        Ok(items)
+    }
+
+    /// Articles matching an FTS5 query, best match first
+    /// Ranked with BM25, with title matches weighted above body matches.
+    /// Archived articles are included, with the bundle they live in.
+    pub fn search(&self, fts_query: &str, limit: Option<usize>) -> Result<Vec<SearchHit>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT f.path, a.archive FROM articles_fts f JOIN articles a ON a.path = f.path
+             WHERE articles_fts MATCH ?1
+             ORDER BY bm25(articles_fts, 0.0, ?2, 1.0) LIMIT ?3",
+        )?;
+        let limit = limit.map(|l| l as i64).unwrap_or(-1);
+        let hits = stmt
+            .query_map(params![fts_query, TITLE_BOOST, limit], |row| {
+                Ok(SearchHit {
+                    path: PathBuf::from(row.get::<_, String>(0)?),
+                    archive: row.get(1)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(hits)
+    }
+
+    /// Article counts per feed, ordered by feed URL
+    pub fn feed_counts(&self) -> Result<Vec<FeedCounts>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT feed_url, COUNT(*), SUM(read = 0), SUM(starred = 1)
+             FROM articles WHERE archive IS NULL GROUP BY feed_url ORDER BY feed_url",
+        )?;
+        let counts = stmt
+            .query_map([], |row| {
+                Ok(FeedCounts {
+                    feed_url: row.get(0)?,
+                    total: row.get::<_, i64>(1)? as usize,
+                    unread: row.get::<_, i64>(2)? as usize,
+                    starred: row.get::<_, i64>(3)? as usize,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(counts)
+    }
+
+    /// Brings the index up to date with the markdown files in `articles_dir`
+    /// Only files whose modification time changed are parsed again; rows for
+    /// deleted files are dropped. Returns the number of rows added or updated.
+    pub fn sync(
+        &self,
+        articles_dir: &Path,
+        parse: impl Fn(&Path) -> Result<FeedItem>,
+    ) -> Result<usize> {
+        let indexed: HashMap<String, i64> = self
+            .conn()
+            .prepare("SELECT path, mtime FROM articles WHERE archive IS NULL")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut seen = HashSet::new();
+        let mut changed = Vec::new();
+        for path in article_files(articles_dir) {
+            let key = path.to_string_lossy().to_string();
+            let mtime = file_mtime(&path).unwrap_or(0);
+            if indexed.get(&key) != Some(&mtime) {
+                changed.push((path, mtime));
+            }
+            seen.insert(key);
+        }
+
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        let mut updated = 0;
+        for (path, mtime) in changed {
+            // Unparseable files stay out of the index; the caller quarantines them
+            let Ok(item) = parse(&path) else {
+                delete_row(&tx, &path.to_string_lossy())?;
+                continue;
+            };
+            insert_row(&tx, &path.to_string_lossy(), &item, mtime, None)?;
+            updated += 1;
+        }
+
+        for path in indexed.keys().filter(|p| !seen.contains(*p)) {
+            delete_row(&tx, path)?;
+        }
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    /// Modification times of the archive bundles last indexed, by bundle name
+    pub fn archive_mtimes(&self) -> Result<HashMap<String, i64>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT name, mtime FROM archives")?;
+        let mtimes = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(mtimes)
+    }
+
+    /// Replaces the rows of an archive bundle with `items`
+    /// Each item is indexed under its original path in `articles/`
+    pub fn index_archive(
+        &self,
+        name: &str,
+        mtime: i64,
+        items: &[(String, FeedItem)],
+    ) -> Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        delete_archive_rows(&tx, name)?;
+        for (path, item) in items {
+            insert_row(&tx, path, item, mtime, Some(name))?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO archives (name, mtime) VALUES (?1, ?2)",
+            params![name, mtime],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Drops every row of an archive bundle
+    pub fn remove_archive(&self, name: &str) -> Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        delete_archive_rows(&tx, name)?;
+        tx.execute("DELETE FROM archives WHERE name = ?1", params![name])?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+/// A full-text search match
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub path: PathBuf,
+    /// Archive bundle holding the article, if it isn't in `articles/`
+    pub archive: Option<String>,
+}
+
+fn insert_row(
+    conn: &Connection,
+    path: &str,
+    item: &FeedItem,
+    mtime: i64,
+    archive: Option<&str>,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO articles
+         (path, id, feed_url, title, link, author, published, read, starred, tags, fetched,
+          mtime, archive)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        params![
+            path,
+            item.id,
+            item.feed_url,
+            item.title,
+            item.link,
+            item.author,
+            item.published.map(|d| d.timestamp()),
+            item.read,
+            item.starred,
+            serde_json::to_string(&item.tags)?,
+            item.fetched.map(|d| d.timestamp_micros()),
+            mtime,
+            archive,
+        ],
+    )?;
+    conn.execute("DELETE FROM articles_fts WHERE path = ?1", params![path])?;
+    conn.execute(
+        "INSERT INTO articles_fts (path, title, body) VALUES (?1, ?2, ?3)",
+        params![
+            path,
+            item.title,
+            // Sealed bodies stay out of the index; only titles are searchable
+            match item.content.as_deref() {
+                Some(body) if !crypt::is_encrypted(body) => plain_text(body),
+                _ => String::new(),
+            }
+        ],
+    )?;
+    Ok(())
+}
+
+fn delete_row(conn: &Connection, path: &str) -> Result<()> {
+    conn.execute("DELETE FROM articles WHERE path = ?1", params![path])?;
+    conn.execute("DELETE FROM articles_fts WHERE path = ?1", params![path])?;
+    Ok(())
+}
+
+fn delete_archive_rows(conn: &Connection, name: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM articles_fts WHERE path IN (SELECT path FROM articles WHERE archive = ?1)",
+        params![name],
+    )?;
+    conn.execute("DELETE FROM articles WHERE archive = ?1", params![name])?;
+    Ok(())
+}
+
+/// Article body with HTML tags removed, for the search index
+fn plain_text(body: &str) -> String {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    let tag = TAG.get_or_init(|| Regex::new(r"<[^>]+>").unwrap());
+    tag.replace_all(body, " ").into_owned()
+}
+
+/// Builds an FTS5 query from the filter's free-text and title terms
+/// Each term is matched as a phrase; returns `None` when there are no terms
+pub fn fts_query(filter: &ArticleFilter) -> Option<String> {
+    let phrase = |term: &str| format!("\"{}\"", term.replace('"', "\"\""));
+    let clauses: Vec<String> = filter
+        .text_terms
+        .iter()
+        .map(|t| phrase(t))
+        .chain(
+            filter
+                .title_terms
+                .iter()
+                .map(|t| format!("title : {}", phrase(t))),
+        )
+        .collect();
+    if clauses.is_empty() {
+        None
+    } else {
+        Some(clauses.join(" AND "))
+    }
+}
+
+/// Markdown files in `articles_dir` and its per-feed subdirectories
+pub fn article_files(articles_dir: &Path) -> Vec<PathBuf> {
+    WalkDir::new(articles_dir)
+        .min_depth(1)
+        .max_depth(2)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .collect()
+}
+
+/// Modification time in nanoseconds since the epoch
+pub fn file_mtime(path: &Path) -> Result<i64> {
+    let modified = fs::metadata(path)?.modified()?;
+    Ok(modified.duration_since(UNIX_EPOCH)?.as_nanos() as i64)
+}
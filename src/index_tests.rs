@@ -0,0 +1,181 @@
+use super::*;
+use chrono::Duration;
+use tempfile::TempDir;
+
+fn item(id: &str, feed_url: &str, read: bool, hours_ago: Option<i64>) -> FeedItem {
+    FeedItem {
+        id: id.to_string(),
+        feed_url: feed_url.to_string(),
+        title: format!("Title {}", id),
+        link: format!("https://example.com/{}", id),
+        published: hours_ago.map(|h| Utc::now() - Duration::hours(h)),
+        read,
+        tags: vec!["rust".to_string()],
+        filepath: Some(format!("/articles/{}.md", id)),
+        ..Default::default()
+    }
+}
+
+fn open_index() -> (ArticleIndex, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let index = ArticleIndex::open(&temp_dir.path().join("index.sqlite")).unwrap();
+    (index, temp_dir)
+}
+
+#[test]
+fn test_query_and_counts() {
+    let (index, _dir) = open_index();
+    index
+        .upsert(&item("a", "https://a.example/feed", false, Some(1)), 1)
+        .unwrap();
+    index
+        .upsert(&item("b", "https://a.example/feed", true, Some(5)), 2)
+        .unwrap();
+    index
+        .upsert(&item("c", "https://b.example/feed", false, None), 3)
+        .unwrap();
+
+    let all = index
+        .query(&ArticleFilter::default(), SortOrder::Newest)
+        .unwrap();
+    let ids: Vec<_> = all.iter().map(|a| a.id.as_str()).collect();
+    // Newest first, undated last
+    assert_eq!(ids, vec!["a", "b", "c"]);
+    assert_eq!(all[0].tags, vec!["rust"]);
+
+    let unread = ArticleFilter {
+        read: Some(false),
+        since: Some(Utc::now() - Duration::hours(2)),
+        ..Default::default()
+    };
+    let ids: Vec<_> = index
+        .query(&unread, SortOrder::Newest)
+        .unwrap()
+        .into_iter()
+        .map(|a| a.id)
+        .collect();
+    assert_eq!(ids, vec!["a"]);
+
+    let counts = index.feed_counts().unwrap();
+    assert_eq!(counts.len(), 2);
+    assert_eq!(counts[0].feed_url, "https://a.example/feed");
+    assert_eq!((counts[0].total, counts[0].unread), (2, 1));
+
+    assert_eq!(
+        index.path_for_id("b").unwrap(),
+        Some(PathBuf::from("/articles/b.md"))
+    );
+    index.remove(Path::new("/articles/b.md")).unwrap();
+    assert!(index.path_for_id("b").unwrap().is_none());
+}
+
+#[test]
+fn test_sync_tracks_changed_and_deleted_files() {
+    let (index, dir) = open_index();
+    let articles = dir.path().join("articles");
+    fs::create_dir_all(&articles).unwrap();
+    fs::write(articles.join("one.md"), "one").unwrap();
+    fs::write(articles.join("two.md"), "two").unwrap();
+
+    // Parse the ID from the file body to keep the test independent of the cache format
+    let parse = |path: &Path| -> Result<FeedItem> {
+        let id = fs::read_to_string(path)?;
+        let mut parsed = item(&id, "https://example.com/feed", false, Some(1));
+        parsed.filepath = Some(path.to_string_lossy().to_string());
+        Ok(parsed)
+    };
+
+    assert_eq!(index.sync(&articles, parse).unwrap(), 2);
+    // Nothing changed: nothing re-parsed
+    assert_eq!(index.sync(&articles, parse).unwrap(), 0);
+
+    fs::remove_file(articles.join("two.md")).unwrap();
+    assert_eq!(index.sync(&articles, parse).unwrap(), 0);
+    assert!(index.path_for_id("two").unwrap().is_none());
+    assert!(index.path_for_id("one").unwrap().is_some());
+}
+
+fn article(id: &str, title: &str, content: &str) -> FeedItem {
+    FeedItem {
+        title: title.to_string(),
+        content: Some(content.to_string()),
+        ..item(id, "https://a.example/feed", false, Some(1))
+    }
+}
+
+fn search_ids(index: &ArticleIndex, filter: &ArticleFilter) -> Vec<String> {
+    let query = fts_query(filter).unwrap();
+    index
+        .search(&query, None)
+        .unwrap()
+        .into_iter()
+        .map(|hit| hit.path.file_stem().unwrap().to_string_lossy().to_string())
+        .collect()
+}
+
+fn text(terms: &[&str]) -> ArticleFilter {
+    ArticleFilter {
+        text_terms: terms.iter().map(|t| t.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_search_ranks_title_matches_first() {
+    let (index, _dir) = open_index();
+    index
+        .upsert(
+            &article(
+                "body",
+                "Weekly links",
+                "<p>Notes on <b>tokio</b> internals</p>",
+            ),
+            1,
+        )
+        .unwrap();
+    index
+        .upsert(&article("title", "Tokio internals", "Scheduler notes"), 2)
+        .unwrap();
+    index
+        .upsert(&article("other", "Gardening", "Tomatoes"), 3)
+        .unwrap();
+
+    assert_eq!(search_ids(&index, &text(&["tokio"])), vec!["title", "body"]);
+    // Markup is stripped and words are stemmed
+    assert_eq!(search_ids(&index, &text(&["b"])), Vec::<String>::new());
+    assert_eq!(search_ids(&index, &text(&["tomato"])), vec!["other"]);
+
+    index.remove(Path::new("/articles/title.md")).unwrap();
+    assert_eq!(search_ids(&index, &text(&["tokio"])), vec!["body"]);
+}
+
+#[test]
+fn test_search_phrases_and_title_terms() {
+    let (index, _dir) = open_index();
+    index
+        .upsert(
+            &article("a", "Async Rust", "the borrow checker explained"),
+            1,
+        )
+        .unwrap();
+    index
+        .upsert(&article("b", "Checker games", "borrow a book"), 2)
+        .unwrap();
+
+    // Each term is a phrase; all terms must match
+    assert_eq!(search_ids(&index, &text(&["borrow checker"])), vec!["a"]);
+    assert_eq!(search_ids(&index, &text(&["borrow", "checker"])).len(), 2);
+    assert_eq!(search_ids(&index, &text(&["rust", "book"])).len(), 0);
+
+    let title = ArticleFilter {
+        title_terms: vec!["checker".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(search_ids(&index, &title), vec!["b"]);
+
+    // Quotes inside a term can't break the query syntax
+    assert!(index
+        .search(&fts_query(&text(&["say \"hi"])).unwrap(), None)
+        .is_ok());
+    assert!(fts_query(&ArticleFilter::default()).is_none());
+}
@@ -0,0 +1,180 @@
+//! Unix sockets of running instances
+//!
+//! A running daemon or viewer listens on a socket in a per-user directory
+//! (`$XDG_RUNTIME_DIR/zetrss`, else `zetrss-$USER` in the temp dir), speaking
+//! the line-delimited JSON-RPC of `zetrss rpc`. Clients such as the Neovim
+//! plugin reach the running process instead of spawning a binary per action.
+//! The daemon adds `refresh` and `status`, the viewer `jump` and `current`;
+//! `zetrss ipc` sends single requests.
+
+use crate::cache::ArticleStore;
+use crate::fetcher::FeedFetcher;
+use crate::rpc::RpcServer;
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(test)]
+#[path = "ipc_tests.rs"]
+mod tests;
+
+/// How long a client waits for an answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Directory of the sockets, created private to the user; one that already
+/// exists must be too, or someone else could plant or take over sockets
+pub fn socket_dir() -> Result<PathBuf> {
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime) => PathBuf::from(runtime).join("zetrss"),
+        None => {
+            let user = std::env::var("USER").unwrap_or_else(|_| "user".to_string());
+            std::env::temp_dir().join(format!("zetrss-{}", user))
+        }
+    };
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)
+        .with_context(|| format!("Failed to create socket directory {}", dir.display()))?;
+    check_private(&dir)?;
+    Ok(dir)
+}
+
+/// Fails unless `dir` is a directory (not a link to one) of the current user
+/// that nobody else may enter
+fn check_private(dir: &Path) -> Result<()> {
+    let meta = fs::symlink_metadata(dir)
+        .with_context(|| format!("Failed to check socket directory {}", dir.display()))?;
+    // SAFETY: geteuid has no preconditions and can't fail
+    let uid = unsafe { libc::geteuid() };
+    if !meta.is_dir() || meta.uid() != uid || meta.mode() & 0o777 != 0o700 {
+        bail!(
+            "Socket directory {} must be a directory owned by you with mode 700",
+            dir.display()
+        );
+    }
+    Ok(())
+}
+
+/// Socket of a `kind` of instance ("daemon", "viewer-<pid>") working on
+/// `data_dir`; instances of different data dirs (profiles) don't meet
+pub fn socket_path(kind: &str, data_dir: &Path) -> Result<PathBuf> {
+    let hash = crate::cache::stable_hash(&data_dir.to_string_lossy()) as u32;
+    Ok(socket_dir()?.join(format!("{}-{:08x}.sock", kind, hash)))
+}
+
+/// Sockets of the instances working on `data_dir`, daemon first
+pub fn instances(data_dir: &Path) -> Result<Vec<PathBuf>> {
+    let hash = format!(
+        "-{:08x}.sock",
+        crate::cache::stable_hash(&data_dir.to_string_lossy()) as u32
+    );
+    let mut sockets: Vec<PathBuf> = fs::read_dir(socket_dir()?)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().ends_with(&hash))
+        })
+        .collect();
+    sockets.sort_by_key(|path| !path.to_string_lossy().contains("/daemon-"));
+    Ok(sockets)
+}
+
+/// A socket being listened on; the socket file goes away with it
+pub struct Listener {
+    path: PathBuf,
+}
+
+impl Listener {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Answers requests on the socket at `path` from a background thread, one
+/// thread per connection. A socket nobody answers on is left over from a
+/// crashed instance and replaced; a live one is an error.
+pub fn listen<S, F>(path: &Path, server: RpcServer<S, F>) -> Result<Listener>
+where
+    S: ArticleStore + Send + Sync + 'static,
+    F: FeedFetcher + Send + Sync + 'static,
+{
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            bail!("Another instance listens on {}", path.display());
+        }
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Failed to listen on {}", path.display()))?;
+    let server = Arc::new(server);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let server = Arc::clone(&server);
+            std::thread::spawn(move || {
+                if let Err(e) = answer(&server, stream) {
+                    tracing::debug!("IPC connection failed: {:#}", e);
+                }
+            });
+        }
+    });
+    Ok(Listener {
+        path: path.to_path_buf(),
+    })
+}
+
+/// Answers each request line of a connection until the client hangs up
+fn answer<S: ArticleStore, F: FeedFetcher>(
+    server: &RpcServer<S, F>,
+    stream: UnixStream,
+) -> Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(answer) = server.handle_line(&line) {
+            writer.write_all(answer.as_bytes())?;
+            writer.write_all(b"\n")?;
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Sends one request to the instance at `path` and returns its result
+pub fn request(path: &Path, method: &str, params: Value) -> Result<Value> {
+    let mut stream = UnixStream::connect(path)
+        .with_context(|| format!("Nothing listens on {}", path.display()))?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+    stream.write_all(format!("{}\n", request).as_bytes())?;
+    let mut line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut line)
+        .with_context(|| format!("No answer from {}", path.display()))?;
+    let mut response: Value =
+        serde_json::from_str(&line).with_context(|| format!("Invalid answer: {}", line.trim()))?;
+    if let Some(error) = response.get("error") {
+        return Err(anyhow!(
+            "{}",
+            error["message"].as_str().unwrap_or("request failed")
+        ));
+    }
+    Ok(response["result"].take())
+}
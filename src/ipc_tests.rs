@@ -0,0 +1,69 @@
+use super::*;
+use crate::cache::TextCache;
+use crate::engine::ZetRss;
+use crate::fetcher::HttpFetcher;
+use tempfile::TempDir;
+
+fn server(dir: &Path) -> RpcServer {
+    let cache = TextCache::with_base_dir(dir.join("data")).unwrap();
+    RpcServer::new(ZetRss::with_parts(cache, HttpFetcher))
+}
+
+#[test]
+fn test_listen_and_request() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("test.sock");
+    let listener = listen(&path, server(temp_dir.path())).unwrap();
+
+    let unread = request(&path, "unread", json!({})).unwrap();
+    assert_eq!(unread, json!({ "unread": 0, "total": 0 }));
+    let error = request(&path, "get", json!({ "id": "nope" })).unwrap_err();
+    assert!(error.to_string().contains("Article not found: nope"));
+
+    // A live socket isn't taken over
+    assert!(listen(&path, server(temp_dir.path())).is_err());
+
+    drop(listener);
+    assert!(!path.exists());
+    assert!(request(&path, "unread", json!({})).is_err());
+}
+
+#[test]
+fn test_stale_socket_is_replaced() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("stale.sock");
+    // Bound but never accepting, as a crashed instance leaves it
+    drop(UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+
+    let _listener = listen(&path, server(temp_dir.path())).unwrap();
+    assert!(request(&path, "list", json!({})).unwrap().is_array());
+}
+
+#[test]
+fn test_socket_path_per_data_dir() {
+    let a = socket_path("daemon", Path::new("/data/a")).unwrap();
+    let b = socket_path("daemon", Path::new("/data/b")).unwrap();
+    assert_ne!(a, b);
+    assert_eq!(a.parent(), b.parent());
+    let name = a.file_name().unwrap().to_string_lossy().into_owned();
+    assert!(name.starts_with("daemon-") && name.ends_with(".sock"));
+}
+
+#[test]
+fn test_socket_dir_must_be_private() {
+    use std::os::unix::fs::PermissionsExt;
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path().join("sockets");
+    fs::create_dir(&dir).unwrap();
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o700)).unwrap();
+    assert!(check_private(&dir).is_ok());
+
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o777)).unwrap();
+    assert!(check_private(&dir).is_err());
+
+    let link = temp_dir.path().join("link");
+    std::os::unix::fs::symlink(&dir, &link).unwrap();
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o700)).unwrap();
+    assert!(check_private(&link).is_err());
+}
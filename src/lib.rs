@@ -1,5 +1,44 @@
+pub mod archive;
+pub mod browser;
 pub mod cache;
+pub mod cite;
+pub mod clipboard;
+pub mod config;
+pub mod crypt;
+pub mod daemon;
+pub mod digest;
+pub mod discovery;
+pub mod doctor;
+pub mod draft;
+pub mod engine;
+pub mod export;
 pub mod fetcher;
+pub mod filter;
+pub mod greader;
+pub mod history;
+pub mod images;
+pub mod index;
+pub mod ipc;
+pub mod migrate;
 pub mod models;
+pub mod notes;
+pub mod nvim;
+pub mod opml;
+pub mod orphans;
+pub mod pager;
+pub mod player;
+pub mod pocket;
+pub mod preview;
+pub mod progress;
+pub mod quarantine;
+pub mod query;
+pub mod related;
+pub mod rpc;
 pub mod scanner;
+pub mod server;
+pub mod summarize;
+pub mod sync;
+pub mod theme;
+pub mod ttrss;
 pub mod viewer;
+pub mod watch;
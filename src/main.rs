@@ -1,19 +1,33 @@
-mod cache;
-mod fetcher;
-mod models;
-mod scanner;
-mod viewer;
-
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use futures::stream::{self, StreamExt};
-use std::sync::Arc;
-use tokio::sync::Semaphore;
+use clap::{Args, Parser, Subcommand};
+use zetrss::{
+    browser, cache, cite, config, crypt, daemon, digest, discovery, doctor, engine, export,
+    fetcher, filter, history, ipc, models, notes, nvim, orphans, pocket, preview, progress, query,
+    rpc, scanner, server, sync, viewer, watch,
+};
 
 #[derive(Parser)]
 #[command(name = "zetrss")]
 #[command(about = "RSS reader integrated with your Zettelkasten", long_about = None)]
 struct Cli {
+    /// Emit structured JSON on stdout (errors as JSON on stderr)
+    /// Honored by scan, fetch, list, search, stats, log, digest, doctor,
+    /// healthcheck and complete
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Config file (default: $ZETRSS_CONFIG or ~/.config/zetrss/config.toml)
+    #[arg(long, global = true)]
+    config: Option<std::path::PathBuf>,
+
+    /// Data directory (default: $ZETRSS_DATA_DIR, `data_dir` from the config, else XDG)
+    #[arg(long, global = true)]
+    data_dir: Option<std::path::PathBuf>,
+
+    /// Profile from the config file, with its own feeds, cache and zet
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -23,143 +37,1697 @@ enum Commands {
     Scan {
         #[arg(short, long)]
         path: Option<String>,
+        /// Keep running and update the feed list as notes change
+        #[arg(short, long)]
+        watch: bool,
     },
     Fetch {
         #[arg(short, long)]
         update: bool,
+        /// No progress or summary; only failed feeds are reported (for cron)
+        #[arg(short, long)]
+        quiet: bool,
+        /// Stream progress as JSON lines to a file descriptor (e.g. 2), a
+        /// Unix socket or a file
+        #[arg(long, value_name = "DEST")]
+        events: Option<String>,
     },
+    /// Read articles in the TUI viewer; repeat --id to queue several, or
+    /// leave it out to browse the cached articles
     View {
+        #[arg(short, long)]
+        id: Vec<String>,
+        /// What to do after finishing the last queued article
+        /// (default: `viewer.on_finish` from the config, else list)
+        #[arg(long, value_enum)]
+        on_finish: Option<viewer::FinishAction>,
+        /// Articles J/K step through when a single --id is given
+        #[arg(long, value_enum, default_value = "date")]
+        nav: viewer::NavOrder,
+        /// Print the articles through $PAGER (else less -R, bat or more)
+        /// instead of the TUI viewer
+        #[arg(long, requires = "id")]
+        pager: bool,
+        /// Open every --id in a tab of its own (gt/gT switch tabs)
+        #[arg(long, requires = "id", conflicts_with = "pager")]
+        tabs: bool,
+    },
+    /// Print an article's body converted for reading, e.g. into a Neovim buffer
+    Preview {
         #[arg(short, long)]
         id: String,
+        /// Wrap width (default: `viewer.width` from the config)
+        #[arg(short, long)]
+        width: Option<usize>,
+        #[arg(long, value_enum, default_value = "text")]
+        format: preview::PreviewFormat,
     },
-    MarkRead {
+    /// Add, remove or list article tags (filter by tag with `list tag:<name>`)
+    Tag {
+        #[command(subcommand)]
+        action: TagAction,
+    },
+    /// Create a zet note from a cached article and print its path
+    Note {
+        #[arg(short, long)]
+        id: String,
+        /// Add the article to today's daily note, whatever `[notes] mode` says
+        #[arg(long)]
+        daily: bool,
+    },
+    /// Print citations of articles, as BibTeX or CSL-JSON
+    Cite {
+        #[arg(short, long, required = true)]
+        id: Vec<String>,
+        #[arg(long, value_enum, default_value = "bibtex")]
+        format: cite::CiteFormat,
+    },
+    /// List the zet notes most related to an article, best first
+    Related {
+        #[arg(short, long)]
         id: String,
+        #[arg(short, long, default_value = "5")]
+        limit: usize,
+    },
+    /// List the passages highlighted in an article, or write them into its note
+    Highlights {
+        #[arg(short, long)]
+        id: String,
+        /// Put every highlight under `## Highlights` in the article's note,
+        /// creating the note if needed, and print the note's path
+        #[arg(long)]
+        export: bool,
+    },
+    /// Mark one article, or every article matching the filters, as read
+    MarkRead {
+        #[arg(conflicts_with_all = ["feed", "older_than", "all"])]
+        id: Option<String>,
+        /// Mark all articles from this feed URL
+        #[arg(long)]
+        feed: Option<String>,
+        /// Mark articles published longer ago than this (e.g. 7d)
+        #[arg(long)]
+        older_than: Option<String>,
+        /// Mark every article
+        #[arg(long)]
+        all: bool,
     },
     MarkAllRead,
     /// List all scanned feeds as JSON
     ListFeeds,
+    /// Show article counts overall and per feed
+    Stats,
+    /// Show when each feed was last fetched and last produced new articles,
+    /// or the recorded fetch attempts of one feed
+    Log {
+        /// Feed URL to show the attempts of
+        url: Option<String>,
+        /// Maximum number of attempts to show, newest first
+        #[arg(short, long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// List feeds whose latest fetch failed, with the note line declaring
+    /// each so the `#feed` entry can be fixed
+    Broken {
+        /// Fetch every feed first instead of going by the last fetch
+        #[arg(long)]
+        fetch: bool,
+        /// Print `file:line:col: message` lines for Vim's quickfix list
+        #[arg(long)]
+        quickfix: bool,
+    },
+    /// Keep running and fetch feeds on a schedule
+    Daemon {
+        /// How often to fetch each feed (e.g. 30m, 2h)
+        #[arg(long, default_value = "30m")]
+        interval: String,
+        /// Per-feed interval as URL=DURATION; may be repeated
+        #[arg(long, value_name = "URL=DURATION")]
+        feed_interval: Vec<String>,
+        /// Print the status of a running daemon and exit
+        #[arg(long)]
+        status: bool,
+    },
+    /// Write a digest note of unread articles cached since the last digest
+    Digest {
+        /// Zet directory to write the note into
+        #[arg(short, long)]
+        path: Option<String>,
+        /// Include articles from this window instead (e.g. 1d, 1w)
+        #[arg(long)]
+        since: Option<String>,
+    },
+    /// Check the environment, cache and feeds, and suggest fixes
+    Doctor {
+        /// Zet directory to check
+        #[arg(short, long)]
+        path: Option<String>,
+        /// Number of feeds to fetch as a connectivity check (0 to skip)
+        #[arg(long, default_value_t = 3)]
+        ping: usize,
+    },
+    /// Report version, data dir, feed count, index and last fetch without
+    /// network access; with --json in the schema `:checkhealth zetrss` renders
+    Healthcheck,
+    /// Serve a local HTTP API for listing articles, marking them and fetching
+    Serve {
+        /// Port to listen on (always bound to localhost)
+        #[arg(short, long, default_value_t = 7878)]
+        port: u16,
+    },
+    /// Answer JSON-RPC requests on stdin, one per line, for the Neovim plugin
+    Rpc,
+    /// Attach to the Neovim at $NVIM over msgpack-RPC, defining a Lua function
+    /// per JSON-RPC method in the global `ZetRss` table
+    Nvim,
+    /// Send a request to a running daemon or viewer over its socket, e.g.
+    /// refresh, status, unread, jump or current
+    Ipc {
+        /// Method to call; with none, list the sockets of running instances
+        method: Option<String>,
+        /// The method's params as a JSON object, e.g. '{"id": "..."}'
+        #[arg(long)]
+        params: Option<String>,
+        /// Socket to send to; the daemon's, else any running instance's
+        #[arg(long)]
+        socket: Option<std::path::PathBuf>,
+    },
+    /// Add a feed to the feed list
+    Add {
+        url: String,
+        /// Also write a `#feed` line into this zet note
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// List feeds a scan no longer finds in the zet but whose data is
+    /// still cached, or clean them up
+    Orphans {
+        /// Delete the orphans' feed metadata and fetch history
+        #[arg(long)]
+        remove: bool,
+        /// Also delete their cached articles, except starred ones
+        #[arg(long, requires = "remove")]
+        delete_articles: bool,
+    },
+    /// List the feeds found on `#feedsite` pages, or confirm or turn down
+    /// a site's feed
+    Discoveries {
+        /// Add the site's feed to the feed list
+        #[arg(long, value_name = "SITE", conflicts_with = "reject")]
+        accept: Option<String>,
+        /// Feed to accept instead of the first one the page announces
+        #[arg(long, requires = "accept")]
+        feed: Option<String>,
+        /// Never add the site's feeds
+        #[arg(long, value_name = "SITE")]
+        reject: Option<String>,
+    },
+    /// Remove a feed from the feed list
+    Remove {
+        url: String,
+        /// Also delete the feed's cached articles, except starred ones
+        #[arg(long)]
+        delete_articles: bool,
+    },
+    /// Delete cached articles older than a retention window
+    Prune {
+        /// Delete articles published longer ago than this (e.g. 30d, 8w)
+        #[arg(long, default_value = "30d")]
+        older_than: String,
+        /// Keep starred articles regardless of age
+        #[arg(long)]
+        keep_starred: bool,
+        /// Keep unread articles regardless of age
+        #[arg(long)]
+        keep_unread: bool,
+        /// Show what would be deleted without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Move old articles into compressed per-month bundles under archive/
+    Archive {
+        /// Archive articles published longer ago than this (e.g. 90d, 26w)
+        #[arg(long, default_value = "90d")]
+        older_than: String,
+        /// Show what would be archived without moving anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Rebuild the article index from the files on disk, e.g. if listings look wrong
+    Reindex,
+    /// Encrypt article bodies at rest with a passphrase (from $ZETRSS_PASSPHRASE or a prompt)
+    Encrypt {
+        /// Also store the passphrase in the OS keyring (see `unlock`)
+        #[arg(long)]
+        remember: bool,
+    },
+    /// Decrypt every article and turn encryption off
+    Decrypt,
+    /// Store the passphrase of an encrypted cache in the OS keyring so commands stop asking
+    Unlock {
+        /// Remove the stored passphrase instead
+        #[arg(long)]
+        forget: bool,
+    },
+    /// Move archived articles back into the article cache
+    Unarchive {
+        /// IDs of archived articles to restore
+        #[arg(required_unless_present = "month")]
+        ids: Vec<String>,
+        /// Restore a whole month (YYYY-MM, or "undated")
+        #[arg(long, conflicts_with = "ids")]
+        month: Option<String>,
+    },
+    /// Full-text search of cached articles, best match first (accepts the `list` query syntax)
+    Search {
+        query: Vec<String>,
+        /// Only match against article titles
+        #[arg(long)]
+        title_only: bool,
+        /// Maximum number of results to show
+        #[arg(short, long)]
+        limit: Option<usize>,
+    },
+    /// Re-download an article and replace its stored content
+    Refresh {
+        id: String,
+        /// Fetch the full web page instead of the feed entry
+        #[arg(long)]
+        page: bool,
+    },
+    /// Sync subscriptions, articles and read/starred state with the `[sync]` account
+    Sync,
+    /// Send starred articles to Pocket (`[pocket]`), each article once
+    Pocket {
+        /// Grant zetrss access to a Pocket account and print its access token
+        #[arg(long)]
+        login: bool,
+    },
+    /// List cached articles, newest first
+    ///
+    /// An optional query narrows the list, e.g.
+    /// `feed:~substack tag:rust read:false published:>2024-01-01 "tokio"`
+    List {
+        #[command(flatten)]
+        filter: ListFilterArgs,
+        /// Maximum number of articles to show
+        #[arg(short, long)]
+        limit: Option<usize>,
+        /// Skip this many matching articles first (for paging with --limit)
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
+        #[arg(long, value_enum, default_value = "newest")]
+        sort: filter::SortOrder,
+        /// One record per line for pickers: tab-separated id, title, feed
+        /// title, date and flags (`u` unread, `s` starred), or JSON lines
+        #[arg(long, value_enum)]
+        format: Option<export::PickerFormat>,
+    },
+    /// Print one page of matching articles as JSON for scripts and the plugin:
+    /// {articles, offset, limit, has_more}, whatever `--json` says
+    Query {
+        /// Only articles from this feed URL
+        #[arg(long)]
+        feed: Option<String>,
+        /// Only read (true) or unread (false) articles
+        #[arg(long)]
+        read: Option<bool>,
+        /// Only starred (true) or unstarred (false) articles
+        #[arg(long)]
+        starred: Option<bool>,
+        /// Only articles carrying this tag (repeatable)
+        #[arg(long)]
+        tag: Vec<String>,
+        /// Only articles published within this window (e.g. 12h, 3d, 2w)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only articles whose title, body or feed URL contain this (repeatable)
+        #[arg(long)]
+        text: Vec<String>,
+        /// Page size
+        #[arg(short, long, default_value_t = 50)]
+        limit: usize,
+        /// Skip this many matching articles first
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
+        #[arg(long, value_enum, default_value = "newest")]
+        sort: filter::SortOrder,
+    },
+    /// Print recent article titles and links, `title<TAB>url` per line (or
+    /// JSON with --json), for "insert link to article" completion in notes
+    Complete {
+        /// Only articles whose title, body or feed URL contain these words
+        words: Vec<String>,
+        /// Number of articles, newest first
+        #[arg(short, long, default_value_t = 500)]
+        limit: usize,
+        /// Only articles published within this window (e.g. 12h, 3d, 2w)
+        #[arg(long)]
+        since: Option<String>,
+    },
+    /// Export article metadata for analysis or backup (same filters as `list`)
+    Export {
+        #[command(flatten)]
+        filter: ListFilterArgs,
+        #[arg(long, value_enum, default_value = "json")]
+        format: export::ExportFormat,
+        /// Include each article's body
+        #[arg(long)]
+        content: bool,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagAction {
+    /// Add tags to an article
+    Add {
+        #[arg(short, long)]
+        id: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Remove tags from an article
+    Remove {
+        #[arg(short, long)]
+        id: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// List an article's tags, or every tag with its article count
+    List {
+        #[arg(short, long)]
+        id: Option<String>,
+    },
+}
+
+/// Article selection shared by `list` and `export`
+#[derive(Args)]
+struct ListFilterArgs {
+    /// Query terms (see `list --help`); combined with the flags below
+    query: Vec<String>,
+    /// Only show unread articles
+    #[arg(long)]
+    unread: bool,
+    /// Only show starred articles
+    #[arg(long)]
+    starred: bool,
+    /// Only show articles from this feed URL
+    #[arg(long)]
+    feed: Option<String>,
+    /// Only show articles published within this window (e.g. 12h, 3d, 2w)
+    #[arg(long)]
+    since: Option<String>,
+}
+
+impl ListFilterArgs {
+    fn to_filter(&self) -> Result<filter::ArticleFilter> {
+        let mut article_filter = query::parse_query(&self.query.join(" "))?;
+        if self.unread {
+            article_filter.read = Some(false);
+        }
+        if self.starred {
+            article_filter.starred = Some(true);
+        }
+        if self.feed.is_some() {
+            article_filter.feed = self.feed.clone();
+        }
+        if let Some(ref since) = self.since {
+            article_filter.since = Some(filter::ago(since)?);
+        }
+        Ok(article_filter)
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
+    // Log to stderr so stdout stays machine-readable in --json mode
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
 
     let cli = Cli::parse();
+    let json = cli.json;
+
+    match run(cli).await {
+        Err(e) if json => {
+            eprintln!("{}", serde_json::json!({ "error": format!("{:#}", e) }));
+            std::process::exit(1);
+        }
+        result => result,
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let json = cli.json;
+    let mut config = config::Config::load(cli.config.as_deref())?;
+    config.select_profile(cli.profile.as_deref())?;
+    // An explicit --profile beats $ZETRSS_DATA_DIR; a default profile from the file doesn't
+    let profile_dir = cli.profile.as_ref().and_then(|_| config.data_dir());
+    config::init(config);
+    let data_dir = cli
+        .data_dir
+        .or(profile_dir)
+        .unwrap_or_else(cache::default_data_dir);
+    let open_cache = || cache::TextCache::with_base_dir(data_dir.clone());
+    let open_engine = || engine::ZetRss::open(data_dir.clone());
 
     match cli.command {
-        Commands::Scan { path } => {
-            let scan_path = match path {
-                Some(p) => p,
-                None => {
-                    // Get username dynamically
-                    let username = std::env::var("USER")
-                        .or_else(|_| std::env::var("USERNAME"))
-                        .unwrap_or_else(|_| "user".to_string());
-                    format!("~/git/{}/zet", username)
+        Commands::Scan { path, watch } => {
+            let partial = path.is_some();
+            let roots = zet_roots(path);
+            let reader = open_engine()?;
+            let (mut index, orphaned) = rescan(&reader, &roots, partial).await?;
+            let feeds = index.feeds();
+
+            if json {
+                print_json(&serde_json::json!({
+                    "count": feeds.len(),
+                    "feeds": feeds,
+                    "orphans": orphaned,
+                }))?;
+            } else {
+                println!("Found {} RSS feeds:", feeds.len());
+                for feed in &feeds {
+                    match &feed.root {
+                        Some(root) if roots.len() > 1 => println!("  - {}  ({})", feed.url, root),
+                        _ => println!("  - {}", feed.url),
+                    }
+                }
+                print_orphans_notice(&orphaned);
+                print_discoveries_notice(reader.store())?;
+            }
+
+            if watch {
+                if !json {
+                    println!("Watching for changes (Ctrl-C to stop)...");
                 }
+                watch::watch(&mut index, reader.store(), |changes| {
+                    let tracked = orphans::track(
+                        reader.store(),
+                        &changes.removed,
+                        &changes.added,
+                        chrono::Utc::now(),
+                    );
+                    if let Err(e) = tracked {
+                        tracing::warn!("Failed to record orphaned feeds: {:#}", e);
+                    }
+                    if json {
+                        if let Err(e) = print_json(changes) {
+                            tracing::warn!("{:#}", e);
+                        }
+                        return;
+                    }
+                    for feed in &changes.added {
+                        println!(
+                            "  + {}  ({}:{})",
+                            feed.url, feed.source_file, feed.line_number
+                        );
+                    }
+                    for feed in &changes.removed {
+                        println!("  - {}", feed.url);
+                    }
+                    for feed in &changes.updated {
+                        println!("  ~ {}", feed.url);
+                    }
+                    println!("{} feeds", changes.count);
+                })
+                .await?;
+            }
+        }
+        Commands::Fetch {
+            update,
+            quiet,
+            events,
+        } => {
+            let events = events
+                .map(|dest| progress::EventSink::open(&dest))
+                .transpose()?;
+            fetch_feeds(&open_engine()?, update, json, quiet, events.as_ref()).await?;
+        }
+        Commands::View {
+            id,
+            on_finish,
+            nav,
+            pager,
+            tabs,
+        } => {
+            let cache = open_cache()?;
+            if pager {
+                return viewer::run_pager(&cache, &id);
+            }
+            // Launch the TUI viewer, or the article list without an id
+            let outcome = match id.as_slice() {
+                [] => browser::run_browser(&cache)?,
+                _ if tabs => viewer::run_tab_viewer(&cache, &id)?,
+                [single] => {
+                    let (ids, start) = viewer::nav_list(&cache, single, nav)?;
+                    viewer::run_list_viewer(&cache, &ids, start)?
+                }
+                _ => viewer::run_viewer(&cache, &id)?,
             };
-            let expanded_path = shellexpand::tilde(&scan_path).to_string();
-            let feeds = scanner::scan_markdown_for_feeds(&expanded_path).await?;
-
-            let cache = cache::TextCache::new()?;
-            cache.store_feed_list(feeds.clone())?;
-
-            println!("Found {} RSS feeds:", feeds.len());
-            for feed in &feeds {
-                println!("  - {}", feed.url);
-            }
-        }
-        Commands::Fetch { update } => {
-            let cache = cache::TextCache::new()?;
-            let feeds = if update {
-                // Get username dynamically for update path
-                let username = std::env::var("USER")
-                    .or_else(|_| std::env::var("USERNAME"))
-                    .unwrap_or_else(|_| "user".to_string());
-                let zet_path = format!("~/git/{}/zet", username);
-                let expanded_path = shellexpand::tilde(&zet_path).to_string();
-                let new_feeds = scanner::scan_markdown_for_feeds(&expanded_path).await?;
-                cache.store_feed_list(new_feeds.clone())?;
-                new_feeds
-            } else {
-                cache.get_feed_list()?
+            let exit_code = if outcome.finished {
+                match on_finish.unwrap_or(config::get().viewer.on_finish) {
+                    viewer::FinishAction::List => 0,
+                    viewer::FinishAction::Exit => 4,
+                    viewer::FinishAction::Summary => {
+                        let summary = outcome.summary();
+                        println!("{}", summary);
+                        viewer::write_session_file("summary", &summary)?;
+                        5
+                    }
+                    viewer::FinishAction::Fetch => {
+                        fetch_feeds(&open_engine()?, false, false, false, None).await?;
+                        0
+                    }
+                }
+            } else {
+                outcome.exit_code
             };
-
-            // Concurrent fetching with rate limiting (max 5 concurrent fetches)
-            const MAX_CONCURRENT_FETCHES: usize = 5;
-            let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_FETCHES));
-            // Arc is necessary here to share the cache safely across async tasks
-            let cache = Arc::new(cache);
-
-            println!(
-                "Fetching {} feeds (up to {} concurrently)...",
-                feeds.len(),
-                MAX_CONCURRENT_FETCHES
-            );
-
-            let fetch_tasks = feeds.into_iter().map(|feed| {
-                let sem = semaphore.clone();
-                let cache = cache.clone();
-                let feed_url = feed.url.clone();
-                async move {
-                    let _permit = sem.acquire().await.unwrap();
-                    println!("  Fetching: {}", feed_url);
-                    match fetcher::fetch_feed(&feed_url).await {
-                        Ok(feed_data) => {
-                            let item_count = feed_data.items.len();
-                            match cache.store_feed(&feed_data) {
-                                Ok(_) => println!("    ✓ Stored {} items", item_count),
-                                Err(e) => eprintln!("    ✗ Failed to store: {}", e),
-                            }
+            std::process::exit(exit_code);
+        }
+        Commands::Preview { id, width, format } => {
+            let article = open_cache()?
+                .get_article_by_id(&id)?
+                .ok_or_else(|| anyhow::anyhow!("Article not found: {}", id))?;
+            let width = width.unwrap_or(config::get().viewer.width);
+            print!("{}", preview::render(article, width, format));
+        }
+        Commands::Tag { action } => {
+            let cache = open_cache()?;
+            let tags = match action {
+                TagAction::Add { id, tags } => cache.add_tags(&id, &tags)?,
+                TagAction::Remove { id, tags } => cache.remove_tags(&id, &tags)?,
+                TagAction::List { id: Some(id) } => {
+                    cache
+                        .get_article_by_id(&id)?
+                        .ok_or_else(|| anyhow::anyhow!("Article not found: {}", id))?
+                        .tags
+                }
+                TagAction::List { id: None } => {
+                    let mut counts = std::collections::BTreeMap::new();
+                    for article in cache.get_articles(&filter::ArticleQuery::default())? {
+                        for tag in article.tags {
+                            *counts.entry(tag.to_lowercase()).or_insert(0) += 1;
                         }
-                        Err(e) => {
-                            eprintln!("    ✗ Failed to fetch: {}", e);
+                    }
+                    if json {
+                        print_json(&counts)?;
+                    } else {
+                        for (tag, count) in counts {
+                            println!("{:>5}  {}", count, tag);
                         }
                     }
+                    return Ok(());
                 }
-            });
-
-            // Execute all fetches concurrently
-            stream::iter(fetch_tasks)
-                .buffer_unordered(MAX_CONCURRENT_FETCHES)
-                .collect::<Vec<_>>()
-                .await;
-
-            println!("\nFeed fetching complete!");
+            };
+            if json {
+                print_json(&tags)?;
+            } else {
+                println!("{}", tags.join(", "));
+            }
         }
-        Commands::View { id } => {
-            // Launch the TUI viewer
-            let exit_code = viewer::run_viewer(&id)?;
-            std::process::exit(exit_code);
+        Commands::Note { id, daily } => {
+            let article = open_cache()?
+                .get_article_by_id(&id)?
+                .ok_or_else(|| anyhow::anyhow!("Article not found: {}", id))?;
+            let path = if daily {
+                notes::add_to_daily_note(&article)?
+            } else {
+                notes::create_note_from_article(&article)?
+            };
+            if json {
+                print_json(&serde_json::json!({ "path": path }))?;
+            } else {
+                println!("{}", path);
+            }
+        }
+        Commands::Cite { id, format } => {
+            let cache = open_cache()?;
+            let now = chrono::Local::now();
+            let mut citations = Vec::new();
+            for id in &id {
+                let article = cache
+                    .get_article_by_id(id)?
+                    .ok_or_else(|| anyhow::anyhow!("Article not found: {}", id))?;
+                let outlet = cache
+                    .feed_title(&article.feed_url)
+                    .unwrap_or_else(|| notes::feed_host(&article.feed_url));
+                citations.push(cite::Citation::new(&article, &outlet, now));
+            }
+            cite::disambiguate(&mut citations);
+            match format {
+                cite::CiteFormat::Bibtex => {
+                    let entries: Vec<String> = citations.iter().map(cite::bibtex).collect();
+                    print!("{}", entries.join("\n"));
+                }
+                cite::CiteFormat::CslJson => {
+                    print_json(&citations.iter().map(cite::csl_json).collect::<Vec<_>>())?
+                }
+            }
         }
-        Commands::MarkRead { id } => {
-            // Mark article as read
-            let cache = cache::TextCache::new()?;
-            cache.mark_as_read(&id)?;
-            println!("Article marked as read");
+        Commands::Related { id, limit } => {
+            let article = open_cache()?
+                .get_article_by_id(&id)?
+                .ok_or_else(|| anyhow::anyhow!("Article not found: {}", id))?;
+            let related = notes::related_notes(&article, limit)?;
+            if json {
+                print_json(&related)?;
+            } else if related.is_empty() {
+                println!("No related notes");
+            } else {
+                for note in &related {
+                    println!("{:.2}  {}  {}", note.score, note.title, note.path);
+                }
+            }
+        }
+        Commands::Highlights { id, export } => {
+            let cache = open_cache()?;
+            let article = cache
+                .get_article_by_id(&id)?
+                .ok_or_else(|| anyhow::anyhow!("Article not found: {}", id))?;
+            let highlights = cache.get_highlights(&id)?;
+            if export {
+                if highlights.is_empty() {
+                    anyhow::bail!("No highlights in article {}", id);
+                }
+                let path = notes::export_highlights(&notes::notes_dir()?, &article, &highlights)?;
+                if json {
+                    print_json(
+                        &serde_json::json!({ "path": path, "highlights": highlights.len() }),
+                    )?;
+                } else {
+                    println!("{}", path);
+                }
+            } else if json {
+                print_json(&highlights)?;
+            } else if highlights.is_empty() {
+                println!("No highlights (select lines with V in the viewer and press h)");
+            } else {
+                for highlight in &highlights {
+                    println!("{}", highlight.created.format("%Y-%m-%d %H:%M"));
+                    for line in highlight.text.lines() {
+                        println!("  {}", line);
+                    }
+                    println!();
+                }
+            }
+        }
+        Commands::MarkRead {
+            id,
+            feed,
+            older_than,
+            all,
+        } => {
+            let cache = open_cache()?;
+            if let Some(id) = id {
+                // Mark article as read
+                cache.mark_as_read(&id)?;
+                println!("Article marked as read");
+            } else {
+                if feed.is_none() && older_than.is_none() && !all {
+                    anyhow::bail!("Specify an article id, or --feed, --older-than or --all");
+                }
+                let article_filter = filter::ArticleFilter {
+                    feed,
+                    until: older_than.map(|d| filter::ago(&d)).transpose()?,
+                    ..Default::default()
+                };
+                let count = cache.mark_read_where(&article_filter)?;
+                println!("Marked {} articles as read", count);
+            }
         }
         Commands::MarkAllRead => {
             // Mark all unread articles as read
-            let cache = cache::TextCache::new()?;
-            let articles = cache.get_articles(None)?;
-            let mut count = 0;
+            let cache = open_cache()?;
+            let count = cache.mark_read_where(&filter::ArticleFilter::default())?;
+            println!("Marked {} articles as read", count);
+        }
+        Commands::Stats => {
+            let stats = open_engine()?.stats()?;
+            if json {
+                print_json(&stats)?;
+            } else {
+                println!(
+                    "Articles: {} ({} unread, {} starred)",
+                    stats.total, stats.unread, stats.starred
+                );
+                println!("Feeds: {}", stats.feeds.len());
+                for feed in &stats.feeds {
+                    println!("  {:>4}/{:<4} {}", feed.unread, feed.total, feed.url);
+                }
+                if stats.quarantined > 0 {
+                    println!(
+                        "Quarantined: {} malformed article files (run `zetrss doctor` for details)",
+                        stats.quarantined
+                    );
+                }
+            }
+        }
+        Commands::Log { url, limit } => {
+            let cache = open_cache()?;
+            match url {
+                Some(url) => {
+                    let mut records = cache.fetch_history(&url)?;
+                    records.reverse();
+                    records.truncate(limit);
+                    if json {
+                        print_json(&records)?;
+                    } else if records.is_empty() {
+                        println!("No fetches recorded for {}", url);
+                    } else {
+                        records.iter().for_each(print_fetch_record);
+                    }
+                }
+                None => {
+                    let histories = cache
+                        .get_feed_list()?
+                        .into_iter()
+                        .map(|feed| {
+                            let records = cache.fetch_history(&feed.url)?;
+                            Ok(history::FeedHistory::from_records(&feed.url, &records))
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    if json {
+                        print_json(&histories)?;
+                    } else {
+                        histories.iter().for_each(print_feed_history);
+                    }
+                }
+            }
+        }
+        Commands::Broken { fetch, quickfix } => {
+            let reader = open_engine()?;
+            if fetch {
+                reader.fetch(|_| {}).await?;
+            }
+            let cache = reader.store();
+            let mut broken = Vec::new();
+            for feed in cache.get_feed_list()? {
+                let records = cache.fetch_history(&feed.url)?;
+                broken.extend(history::BrokenFeed::from_records(&feed, &records));
+            }
 
-            for article in articles {
-                if !article.read {
-                    cache.mark_as_read(&article.id)?;
-                    count += 1;
+            if json {
+                print_json(&broken)?;
+            } else if quickfix {
+                for feed in &broken {
+                    println!("{}", feed.quickfix_line());
+                }
+            } else if broken.is_empty() {
+                println!("No failing feeds");
+            } else {
+                for feed in &broken {
+                    println!("✗ {}", feed.url);
+                    println!("  {}", feed.error);
+                    if !feed.source_file.is_empty() {
+                        println!("  {}:{}", feed.source_file, feed.line_number);
+                    }
+                    println!(
+                        "  failed {}x since {}",
+                        feed.failures,
+                        feed.failing_since
+                            .with_timezone(&chrono::Local)
+                            .format("%Y-%m-%d %H:%M")
+                    );
                 }
             }
+        }
+        Commands::Daemon {
+            interval,
+            feed_interval,
+            status,
+        } => {
+            let reader = open_engine()?;
+            if status {
+                let Some(mut status) = daemon::DaemonStatus::load(&reader)? else {
+                    anyhow::bail!("No daemon status found; start one with `zetrss daemon`");
+                };
+                // A daemon that was killed never got to say it stopped
+                status.running = status.is_alive();
+                if json {
+                    print_json(&status)?;
+                } else {
+                    print_daemon_status(&status);
+                }
+                return Ok(());
+            }
 
-            println!("Marked {} articles as read", count);
+            // Intervals from the config file, then --feed-interval on top
+            let mut overrides = std::collections::HashMap::new();
+            for (url, feed_config) in &config::get().feeds {
+                if let Some(ref interval) = feed_config.interval {
+                    overrides.insert(url.clone(), filter::parse_duration(interval)?);
+                }
+            }
+            for entry in &feed_interval {
+                let (url, duration) = entry.rsplit_once('=').ok_or_else(|| {
+                    anyhow::anyhow!("Invalid --feed-interval '{}': expected URL=DURATION", entry)
+                })?;
+                overrides.insert(url.to_string(), filter::parse_duration(duration)?);
+            }
+            let schedule = daemon::Schedule {
+                interval: filter::parse_duration(&interval)?,
+                overrides,
+            };
+            if schedule.interval < chrono::Duration::minutes(1) {
+                anyhow::bail!("Daemon interval must be at least 1m");
+            }
+
+            // Clients reach the running daemon through its socket
+            let control = std::sync::Arc::new(daemon::DaemonControl::default());
+            let socket = ipc::socket_path("daemon", reader.store().base_dir())?;
+            let listener =
+                match ipc::listen(&socket, daemon::rpc_server(open_engine()?, control.clone())) {
+                    Ok(listener) => Some(listener),
+                    Err(e) => {
+                        tracing::warn!("Not listening for requests: {:#}", e);
+                        None
+                    }
+                };
+
+            eprintln!(
+                "Fetching feeds every {} (Ctrl-C or SIGTERM to stop)",
+                interval
+            );
+            daemon::run_daemon(
+                &reader,
+                &schedule,
+                &control,
+                listener.as_ref().map(|l| l.path()),
+            )
+            .await?;
+        }
+        Commands::Digest { path, since } => {
+            let zet_path = zet_path(path);
+            let since = since.map(|s| filter::ago(&s)).transpose()?;
+            let reader = open_engine()?;
+            let digest = digest::write_digest(&reader, std::path::Path::new(&zet_path), since)?;
+
+            if json {
+                print_json(&digest)?;
+            } else {
+                match digest {
+                    Some(digest) => println!(
+                        "Wrote digest of {} articles from {} feeds to {}",
+                        digest.articles,
+                        digest.feeds,
+                        digest.path.display()
+                    ),
+                    None => println!("No new unread articles since the last digest"),
+                }
+            }
+        }
+        Commands::Doctor { path, ping } => {
+            let checks =
+                doctor::run_checks(&fetcher::HttpFetcher, &data_dir, &zet_roots(path), ping).await;
+
+            if json {
+                print_json(&checks)?;
+            } else {
+                print_checks(&checks);
+            }
+
+            if checks.iter().any(|c| c.status == doctor::CheckStatus::Fail) {
+                std::process::exit(1);
+            }
+        }
+        Commands::Healthcheck => {
+            let report = doctor::health_report(&data_dir);
+            if json {
+                // Always exits 0 so the plugin can render failed checks
+                return print_json(&report);
+            }
+            println!("zetrss {}", report.version);
+            println!("Data dir: {}", report.data_dir);
+            println!("Feeds: {}", report.feeds);
+            match report.index.error {
+                Some(ref error) => println!("Index: {} ({})", report.index.path, error),
+                None => println!(
+                    "Index: {} articles, {} unread",
+                    report.index.articles, report.index.unread
+                ),
+            }
+            match report.last_fetch {
+                Some(time) => println!("Last fetch: {}", time.format("%Y-%m-%d %H:%M UTC")),
+                None => println!("Last fetch: never"),
+            }
+            print_checks(&report.checks);
+        }
+        Commands::Serve { port } => {
+            let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+            eprintln!("Serving on http://{} (Ctrl-C to stop)", addr);
+            server::serve(open_engine()?, addr).await?;
+        }
+        Commands::Rpc => rpc::serve(open_engine()?).await?,
+        Commands::Nvim => {
+            let address = std::env::var("NVIM")
+                .map_err(|_| anyhow::anyhow!("$NVIM is not set; start zetrss nvim from Neovim"))?;
+            nvim::attach(&address, rpc::RpcServer::new(open_engine()?))?;
+        }
+        Commands::Ipc {
+            method,
+            params,
+            socket,
+        } => {
+            let Some(method) = method else {
+                let sockets = ipc::instances(&data_dir)?;
+                if json {
+                    print_json(&sockets)?;
+                } else if sockets.is_empty() {
+                    println!("No running daemon or viewer");
+                } else {
+                    for socket in sockets {
+                        println!("{}", socket.display());
+                    }
+                }
+                return Ok(());
+            };
+            let params = match params {
+                Some(params) => serde_json::from_str(&params)
+                    .map_err(|e| anyhow::anyhow!("Invalid --params: {}", e))?,
+                None => serde_json::json!({}),
+            };
+            let socket = match socket {
+                Some(socket) => socket,
+                None => ipc::instances(&data_dir)?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("No running daemon or viewer"))?,
+            };
+            let result = ipc::request(&socket, &method, params)?;
+            println!("{}", serde_json::to_string_pretty(&result)?);
         }
         Commands::ListFeeds => {
-            let cache = cache::TextCache::new()?;
+            let cache = open_cache()?;
             let feeds = cache.get_feed_list()?;
             let json = serde_json::to_string(&feeds)?;
             println!("{}", json);
         }
+        Commands::Add { url, note } => {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                anyhow::bail!("Feed URL must start with http:// or https://: {}", url);
+            }
+
+            let cache = open_cache()?;
+            if cache.has_feed(&url)? {
+                println!("Feed already in list: {}", url);
+                return Ok(());
+            }
+
+            let source = match note {
+                Some(note) => {
+                    let note_path = shellexpand::tilde(&note).to_string();
+                    let source = scanner::append_feed_to_note(&note_path, &url)?;
+                    println!(
+                        "#feed line at {}:{}",
+                        source.source_file, source.line_number
+                    );
+                    source
+                }
+                None => scanner::FeedSource {
+                    url: url.clone(),
+                    source_file: String::new(),
+                    line_number: 0,
+                    root: None,
+                    options: Default::default(),
+                },
+            };
+
+            if cache.add_feed(source)? {
+                println!("Added feed: {}", url);
+            } else {
+                println!("Feed already in list: {}", url);
+            }
+        }
+        Commands::Orphans {
+            remove,
+            delete_articles,
+        } => {
+            let cache = open_cache()?;
+            if remove {
+                let cleanup = orphans::clean_up(&cache, delete_articles)?;
+                if json {
+                    print_json(&cleanup)?;
+                } else {
+                    println!("Removed {} orphaned feeds", cleanup.feeds.len());
+                    if delete_articles {
+                        println!("Deleted {} cached articles", cleanup.articles);
+                    }
+                }
+            } else if json {
+                print_json(&orphans::load(&cache)?)?;
+            } else {
+                let found = orphans::load(&cache)?;
+                if found.is_empty() {
+                    println!("No orphaned feeds");
+                }
+                for orphan in &found {
+                    println!(
+                        "{}  (was {}:{}, gone since {})",
+                        orphan.url,
+                        orphan.source_file,
+                        orphan.line_number,
+                        orphan.since.format("%Y-%m-%d")
+                    );
+                }
+            }
+        }
+        Commands::Discoveries {
+            accept,
+            feed,
+            reject,
+        } => {
+            let cache = open_cache()?;
+            let mut found = cache.get_discoveries()?;
+            if let Some(site) = accept {
+                let accepted = discovery::accept(&mut found, &site, feed.as_deref())?;
+                cache.store_discoveries(&found)?;
+                println!("Accepted {} for {}", accepted.url, site);
+                cache.add_feed(accepted)?;
+            } else if let Some(site) = reject {
+                discovery::reject(&mut found, &site)?;
+                cache.store_discoveries(&found)?;
+                println!("Rejected feeds of {}", site);
+            } else if json {
+                print_json(&found)?;
+            } else {
+                if found.is_empty() {
+                    println!("No #feedsite sites");
+                }
+                for site in &found {
+                    let state = match site.status {
+                        discovery::DiscoveryStatus::Pending => "pending",
+                        discovery::DiscoveryStatus::Accepted => "accepted",
+                        discovery::DiscoveryStatus::Rejected => "rejected",
+                    };
+                    println!(
+                        "{} [{}]  ({}:{})",
+                        site.site, state, site.source_file, site.line_number
+                    );
+                    if let Some(error) = &site.error {
+                        println!("    {}", error);
+                    } else if site.feeds.is_empty() {
+                        println!("    no feed links found");
+                    }
+                    for url in &site.feeds {
+                        let mark = if site.feed.as_ref() == Some(url) {
+                            "*"
+                        } else {
+                            "-"
+                        };
+                        println!("    {} {}", mark, url);
+                    }
+                }
+            }
+        }
+        Commands::Remove {
+            url,
+            delete_articles,
+        } => {
+            let cache = open_cache()?;
+            let removed = cache
+                .remove_feed(&url)?
+                .ok_or_else(|| anyhow::anyhow!("Feed not in list: {}", url))?;
+            println!("Removed feed: {}", url);
+
+            if delete_articles {
+                let count = cache.delete_feed_articles(&url)?;
+                println!("Deleted {} cached articles", count);
+            }
+
+            if !removed.source_file.is_empty() {
+                println!(
+                    "Note: still declared at {}:{}; remove the #feed line or the next scan will add it back",
+                    removed.source_file, removed.line_number
+                );
+            }
+        }
+        Commands::Prune {
+            older_than,
+            keep_starred,
+            keep_unread,
+            dry_run,
+        } => {
+            let policy = cache::PrunePolicy {
+                cutoff: filter::ago(&older_than)?,
+                keep_starred,
+                keep_unread,
+                dry_run,
+            };
+            let cache = open_cache()?;
+            let pruned = cache.prune(&policy)?;
+
+            if dry_run {
+                for article in &pruned {
+                    print_article_line(article);
+                }
+                println!("Would delete {} articles", pruned.len());
+            } else {
+                println!("Deleted {} articles", pruned.len());
+            }
+        }
+        Commands::Archive {
+            older_than,
+            dry_run,
+        } => {
+            let cutoff = filter::ago(&older_than)?;
+            let cache = open_cache()?;
+            let archived = cache.archive(cutoff, dry_run)?;
+
+            if dry_run {
+                for article in &archived {
+                    print_article_line(article);
+                }
+                println!("Would archive {} articles", archived.len());
+            } else {
+                println!("Archived {} articles", archived.len());
+            }
+        }
+        Commands::Reindex => {
+            let indexed = open_cache()?.rebuild_index()?;
+            println!("Indexed {} articles", indexed);
+        }
+        Commands::Encrypt { remember } => {
+            let passphrase = match std::env::var(crypt::PASSPHRASE_ENV) {
+                Ok(passphrase) => passphrase,
+                Err(_) => crypt::prompt(true)?,
+            };
+            let count = open_cache()?.encrypt_all(&passphrase)?;
+            if remember {
+                crypt::remember(&data_dir, &passphrase)?;
+            }
+            println!("Encrypted {} articles", count);
+        }
+        Commands::Decrypt => {
+            let count = open_cache()?.decrypt_all()?;
+            // The articles are already decrypted; a keyring hiccup isn't worth failing over
+            if let Err(e) = crypt::forget(&data_dir) {
+                eprintln!("Warning: {:#}", e);
+            }
+            println!("Decrypted {} articles", count);
+        }
+        Commands::Unlock { forget } => {
+            if forget {
+                if crypt::forget(&data_dir)? {
+                    println!("Removed the stored passphrase");
+                } else {
+                    println!("No passphrase was stored");
+                }
+            } else {
+                let cache = open_cache()?;
+                if !cache.is_encrypted() {
+                    anyhow::bail!("The cache in {} isn't encrypted", data_dir.display());
+                }
+                let passphrase = crypt::prompt(false)?;
+                cache.unlock(&passphrase)?;
+                crypt::remember(&data_dir, &passphrase)?;
+                println!("Passphrase stored in the keyring");
+            }
+        }
+        Commands::Unarchive { ids, month } => {
+            let cache = open_cache()?;
+            let restored = match month {
+                Some(month) => cache.unarchive_bundle(&month)?,
+                None => cache.unarchive(&ids)?,
+            };
+            println!("Restored {} articles", restored);
+        }
+        Commands::Search {
+            query,
+            title_only,
+            limit,
+        } => {
+            let mut article_filter = query::parse_query(&query.join(" "))?;
+            if title_only {
+                let terms = std::mem::take(&mut article_filter.text_terms);
+                article_filter.title_terms.extend(terms);
+            }
+
+            let limit = limit.or(config::get().list.limit);
+            let articles = open_engine()?.search(&article_filter, limit)?;
+            print_articles(&articles, json)?;
+        }
+        Commands::Sync => {
+            let cache = open_cache()?;
+            let report = sync::run(&cache, &config::get().sync).await?;
+            if json {
+                return print_json(&report);
+            }
+            println!(
+                "Synced {} subscriptions: {} articles added, {} linked, {} updated locally, {} changes sent",
+                report.subscriptions, report.added, report.linked, report.updated, report.pushed
+            );
+        }
+        Commands::Pocket { login } => {
+            let config = &config::get().pocket;
+            if login {
+                let code = pocket::request_token(config).await?;
+                println!("Allow zetrss access at {}", pocket::authorize_url(&code));
+                eprint!("Press Enter once access is allowed: ");
+                std::io::stdin().read_line(&mut String::new())?;
+                let token = pocket::access_token(config, &code).await?;
+                println!(
+                    "Set ${} to {} or add to [pocket]:\naccess_token = \"{}\"",
+                    pocket::TOKEN_ENV,
+                    token,
+                    token
+                );
+                return Ok(());
+            }
+            let cache = open_cache()?;
+            let report = pocket::export_starred(&cache, config).await?;
+            if json {
+                return print_json(&report);
+            }
+            println!(
+                "Sent {} starred articles to Pocket ({} sent before, {} rejected)",
+                report.sent, report.already_sent, report.failed
+            );
+        }
+        Commands::Refresh { id, page } => {
+            let cache = open_cache()?;
+            let article = cache
+                .get_article_by_id(&id)?
+                .ok_or_else(|| anyhow::anyhow!("Article not found: {}", id))?;
+
+            if page {
+                let html = fetcher::fetch_page(&article.link).await?;
+                cache.update_article_content(&article.id, None, Some(&html))?;
+                println!("Refreshed {} from {}", article.id, article.link);
+            } else {
+                let feed = fetcher::fetch_feed(&article.feed_url).await?;
+                let entry = feed
+                    .items
+                    .iter()
+                    .find(|item| item.id == article.id)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Article {} is no longer in {}. Try --page to fetch the web page instead.",
+                            article.id,
+                            article.feed_url
+                        )
+                    })?;
+                cache.update_article_content(
+                    &article.id,
+                    entry.description.as_deref(),
+                    entry.content.as_deref(),
+                )?;
+                println!("Refreshed {} from {}", article.id, article.feed_url);
+            }
+        }
+        Commands::List {
+            filter,
+            limit,
+            offset,
+            sort,
+            format,
+        } => {
+            let reader = open_engine()?;
+            let articles = reader.list(&filter::ArticleQuery {
+                filter: filter.to_filter()?,
+                sort,
+                offset,
+                limit: limit.or(config::get().list.limit),
+            })?;
+            match format {
+                Some(format) => export::write_picker_lines(
+                    &articles,
+                    format,
+                    |url| reader.store().feed_title(url),
+                    std::io::stdout().lock(),
+                )?,
+                None => print_articles(&articles, json)?,
+            }
+        }
+        Commands::Query {
+            feed,
+            read,
+            starred,
+            tag,
+            since,
+            text,
+            limit,
+            offset,
+            sort,
+        } => {
+            let since = match since {
+                Some(since) => Some(filter::ago(&since)?),
+                None => None,
+            };
+            let page = open_engine()?.page(&filter::ArticleQuery {
+                filter: filter::ArticleFilter {
+                    read,
+                    starred,
+                    feed,
+                    tags: tag,
+                    text_terms: text,
+                    since,
+                    ..Default::default()
+                },
+                sort,
+                offset,
+                limit: Some(limit),
+            })?;
+            print_json(&page)?;
+        }
+        Commands::Complete {
+            words,
+            limit,
+            since,
+        } => {
+            let since = match since {
+                Some(since) => Some(filter::ago(&since)?),
+                None => None,
+            };
+            let articles = open_engine()?.list(&filter::ArticleQuery {
+                filter: filter::ArticleFilter {
+                    text_terms: words,
+                    since,
+                    ..Default::default()
+                },
+                limit: Some(limit),
+                ..Default::default()
+            })?;
+            if json {
+                let links: Vec<_> = articles
+                    .iter()
+                    .filter(|a| !a.link.is_empty())
+                    .map(|a| serde_json::json!({ "title": a.title, "url": a.link, "id": a.id }))
+                    .collect();
+                print_json(&links)?;
+            } else {
+                export::write_completions(&articles, std::io::stdout().lock())?;
+            }
+        }
+        Commands::Export {
+            filter,
+            format,
+            content,
+            output,
+        } => {
+            let articles = open_engine()?.list(&filter.to_filter()?.into())?;
+            match output {
+                Some(path) => {
+                    let file = std::fs::File::create(&path)?;
+                    export::write_export(
+                        &articles,
+                        format,
+                        content,
+                        std::io::BufWriter::new(file),
+                    )?;
+                    eprintln!("Exported {} articles to {}", articles.len(), path);
+                }
+                None => export::write_export(&articles, format, content, std::io::stdout().lock())?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The zet directory from `--path`, else from the config file, with `~` expanded
+fn zet_path(path: Option<String>) -> String {
+    match path {
+        Some(path) => shellexpand::tilde(&path).to_string(),
+        None => config::get().zet_path(),
+    }
+}
+
+/// The zet directories to scan: `--path` alone if given, else `zet_path` and
+/// `zet_roots` from the config
+fn zet_roots(path: Option<String>) -> Vec<String> {
+    match path {
+        Some(path) => vec![shellexpand::tilde(&path).to_string()],
+        None => config::get().zet_roots(),
+    }
+}
+
+/// Rescans the zet roots and records the feeds no longer found as orphans,
+/// removing their metadata right away if `[scan] orphans` says so
+/// A `partial` scan (`--path` instead of the configured roots), or one that
+/// skipped a missing root, can't tell which feeds are gone and records none
+/// Returns the index and the orphans left
+async fn rescan(
+    reader: &engine::ZetRss,
+    roots: &[String],
+    partial: bool,
+) -> Result<(scanner::FeedIndex, Vec<orphans::Orphan>)> {
+    let previous = reader.store().get_feed_list()?;
+    let index = reader.scan_index(roots).await?;
+    if partial || !index.skipped().is_empty() {
+        return Ok((index, orphans::load(reader.store())?));
+    }
+    let feeds = index.feeds();
+    // Feeds added without a note were never declared in the zet
+    let gone: Vec<scanner::FeedSource> = previous
+        .into_iter()
+        .filter(|old| !old.source_file.is_empty() && !feeds.iter().any(|f| f.url == old.url))
+        .collect();
+    let orphaned = orphans::track(reader.store(), &gone, &feeds, chrono::Utc::now())?;
+
+    if orphaned.is_empty() || config::get().scan.orphans == orphans::OrphanAction::Keep {
+        return Ok((index, orphaned));
+    }
+    let cleanup = orphans::clean_up(reader.store(), false)?;
+    tracing::info!("Removed {} orphaned feeds", cleanup.feeds.len());
+    Ok((index, Vec::new()))
+}
+
+/// Tells the user about feeds gone from the zet whose data is still cached
+fn print_orphans_notice(orphaned: &[orphans::Orphan]) {
+    if orphaned.is_empty() {
+        return;
+    }
+    eprintln!(
+        "{} feeds are no longer in your notes; `zetrss orphans` lists them, `zetrss orphans --remove` cleans up their cached data",
+        orphaned.len()
+    );
+}
+
+fn print_discoveries_notice(cache: &cache::TextCache) -> Result<()> {
+    let pending = cache
+        .get_discoveries()?
+        .into_iter()
+        .filter(|d| d.status == discovery::DiscoveryStatus::Pending && !d.feeds.is_empty())
+        .count();
+    if pending > 0 {
+        eprintln!(
+            "{} #feedsite sites have feeds waiting for confirmation; see `zetrss discoveries`",
+            pending
+        );
+    }
+    Ok(())
+}
+
+/// Fetches all feeds in the feed list (rescanning the zet first if `update` is set)
+/// Shows a progress bar and a summary unless `quiet`; failed feeds are always listed
+async fn fetch_feeds(
+    reader: &engine::ZetRss,
+    update: bool,
+    json: bool,
+    quiet: bool,
+    events: Option<&progress::EventSink>,
+) -> Result<()> {
+    if update {
+        let (_, orphaned) = rescan(reader, &zet_roots(None), false).await?;
+        if !json && !quiet {
+            print_orphans_notice(&orphaned);
+        }
+    }
+    let feeds = reader.store().get_feed_list()?;
+    let emit = |event: &progress::ProgressEvent| {
+        if let Some(events) = events {
+            events.emit(event);
+        }
+    };
+    emit(&progress::ProgressEvent::Begin { feeds: feeds.len() });
+
+    if json {
+        let results = reader
+            .fetch_feeds(&feeds, |event| emit(&(&event).into()))
+            .await;
+        emit(&progress::ProgressEvent::done(&results));
+        let failed = results.iter().filter(|r| r.error.is_some()).count();
+        return print_json(&serde_json::json!({
+            "succeeded": results.len() - failed,
+            "failed": failed,
+            "feeds": results,
+        }));
+    }
+
+    let progress = if quiet {
+        indicatif::ProgressBar::hidden()
+    } else {
+        indicatif::ProgressBar::new(feeds.len() as u64)
+    };
+    progress.set_style(
+        indicatif::ProgressStyle::with_template(
+            "{bar:30.cyan/blue} {pos}/{len} done, {prefix} failed  {wide_msg}",
+        )?
+        .progress_chars("━━─"),
+    );
+    progress.set_prefix("0");
+    let failed = std::sync::atomic::AtomicUsize::new(0);
+
+    let results = reader
+        .fetch_feeds(&feeds, |event| {
+            emit(&(&event).into());
+            match event {
+                engine::FetchEvent::Started { url } => progress.set_message(url),
+                engine::FetchEvent::Finished(result) => {
+                    if result.error.is_some() {
+                        let count = failed.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+                        progress.set_prefix(count.to_string());
+                    }
+                    progress.inc(1);
+                }
+            }
+        })
+        .await;
+    progress.finish_and_clear();
+    emit(&progress::ProgressEvent::done(&results));
+
+    let failures: Vec<_> = results.iter().filter(|r| r.error.is_some()).collect();
+    if !quiet {
+        let items: usize = results.iter().map(|r| r.items).sum();
+        let added: usize = results.iter().map(|r| r.added).sum();
+        let pruned: usize = results.iter().map(|r| r.pruned).sum();
+        println!(
+            "Fetched {} feeds: {} succeeded, {} failed, {} items ({} new){}",
+            results.len(),
+            results.len() - failures.len(),
+            failures.len(),
+            items,
+            added,
+            if pruned > 0 {
+                format!(", {} old articles removed", pruned)
+            } else {
+                String::new()
+            }
+        );
+    }
+    if !failures.is_empty() {
+        let width = failures.iter().map(|r| r.url.len()).max().unwrap_or(0);
+        if !quiet {
+            eprintln!();
+        }
+        eprintln!("Failed feeds:");
+        for result in failures {
+            eprintln!(
+                "  {:<width$}  {}",
+                result.url,
+                result.error.as_deref().unwrap_or(""),
+                width = width
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_json<T: serde::Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string(value)?);
+    Ok(())
+}
+
+/// Prints diagnostic checks with an icon each and their suggested fixes
+fn print_checks(checks: &[doctor::Check]) {
+    for check in checks {
+        let icon = match check.status {
+            doctor::CheckStatus::Ok => "✓",
+            doctor::CheckStatus::Warn => "⚠",
+            doctor::CheckStatus::Fail => "✗",
+        };
+        println!("{} {}: {}", icon, check.name, check.detail);
+        if let Some(ref fix) = check.fix {
+            println!("    → {}", fix);
+        }
     }
+}
 
+/// Prints articles as one line each, or as a JSON array of summaries
+fn print_articles(articles: &[models::FeedItem], json: bool) -> Result<()> {
+    if json {
+        let summaries: Vec<models::ArticleSummary> = articles.iter().map(Into::into).collect();
+        return print_json(&summaries);
+    }
+    for article in articles {
+        print_article_line(article);
+    }
     Ok(())
 }
+
+/// Prints a one-line human-readable summary of an article
+fn print_article_line(article: &models::FeedItem) {
+    let read_marker = if article.read { "✓" } else { "●" };
+    let star_marker = if article.starred { "★" } else { " " };
+    let date = article
+        .published
+        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "----------------".to_string());
+    let domain = article
+        .feed_url
+        .split("://")
+        .nth(1)
+        .and_then(|rest| rest.split('/').next())
+        .unwrap_or(&article.feed_url);
+
+    println!(
+        "{} {} {}  {} - {}  [{}]",
+        read_marker, star_marker, date, article.title, domain, article.id
+    );
+}
+
+/// Prints one fetch attempt of a feed
+fn print_fetch_record(record: &history::FetchRecord) {
+    let time = record.time.format("%Y-%m-%d %H:%M:%S");
+    match record.error {
+        Some(ref error) => println!("✗ {}  {:>6}ms  {}", time, record.duration_ms, error),
+        None => println!(
+            "✓ {}  {:>6}ms  {:>8} bytes  {} items, {} new",
+            time, record.duration_ms, record.bytes, record.items, record.added
+        ),
+    }
+}
+
+/// Prints a feed's last fetch and when it last produced new articles
+fn print_feed_history(feed: &history::FeedHistory) {
+    let format = |time: Option<chrono::DateTime<chrono::Utc>>| {
+        time.map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "never".to_string())
+    };
+    let marker = match feed.last_attempt {
+        Some(ref last) if last.status == history::FetchStatus::Error => "✗",
+        Some(_) => "✓",
+        None => " ",
+    };
+    println!(
+        "{} fetched {:<16}  new {:<16}  {:>3}/{:<3} failed  {}",
+        marker,
+        format(feed.last_attempt.as_ref().map(|r| r.time)),
+        format(feed.last_added),
+        feed.failures,
+        feed.attempts,
+        feed.url
+    );
+}
+
+fn print_daemon_status(status: &daemon::DaemonStatus) {
+    let state = if status.running { "running" } else { "stopped" };
+    println!("Daemon {} (pid {})", state, status.pid);
+    if let Some(last) = status.last_cycle {
+        println!("Last cycle: {}", last.format("%Y-%m-%d %H:%M:%S"));
+    }
+    if let Some(next) = status.next_cycle {
+        println!("Next cycle: {}", next.format("%Y-%m-%d %H:%M:%S"));
+    }
+    println!("Unread: {}", status.unread);
+    for feed in &status.feeds {
+        match feed.last_error {
+            Some(ref error) => println!("  ✗ {} ({})", feed.url, error),
+            None => println!("  ✓ {} ({} items)", feed.url, feed.last_items),
+        }
+    }
+}
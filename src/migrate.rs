@@ -0,0 +1,200 @@
+//! Upgrades data directories written by older versions
+//!
+//! `state/format.json` records the layout version of a data directory. When
+//! `TextCache` opens an older one, each migration newer than the recorded
+//! version runs in order and the version is bumped after every step, so an
+//! interrupted upgrade resumes where it stopped. Directories without a marker
+//! are treated as version 0; every migration is a no-op on an empty cache.
+
+use crate::cache::{self, TextCache};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+#[path = "migrate_tests.rs"]
+mod tests;
+
+/// One layout change
+struct Migration {
+    /// Version the data directory is at once this has run
+    version: u32,
+    description: &'static str,
+    run: fn(&TextCache) -> Result<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "move articles into per-feed directories",
+        run: per_feed_directories,
+    },
+    Migration {
+        version: 2,
+        description: "name article files by date and ID",
+        run: dated_file_names,
+    },
+    Migration {
+        version: 3,
+        description: "convert state/feeds.txt to feeds.json",
+        run: feed_list_json,
+    },
+];
+
+/// Layout version written by this build
+pub const CURRENT_VERSION: u32 = 3;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FormatMarker {
+    version: u32,
+}
+
+fn marker_path(base_dir: &Path) -> PathBuf {
+    base_dir.join("state").join("format.json")
+}
+
+/// Layout version of the data directory; 0 if it predates the marker
+pub fn version(base_dir: &Path) -> Result<u32> {
+    let path = marker_path(base_dir);
+    if !path.exists() {
+        return Ok(0);
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let marker: FormatMarker = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid format marker {}", path.display()))?;
+    Ok(marker.version)
+}
+
+fn set_version(base_dir: &Path, version: u32) -> Result<()> {
+    let path = marker_path(base_dir);
+    fs::write(
+        &path,
+        serde_json::to_string_pretty(&FormatMarker { version })?,
+    )
+    .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Brings the cache's data directory up to `CURRENT_VERSION`
+/// Returns the descriptions of the migrations that ran
+pub(crate) fn run(cache: &TextCache) -> Result<Vec<&'static str>> {
+    let base_dir = cache.base_dir();
+    let current = version(base_dir)?;
+    if current > CURRENT_VERSION {
+        return Err(anyhow!(
+            "{} was written by a newer zetrss (format {}, this build knows {}); upgrade zetrss",
+            base_dir.display(),
+            current,
+            CURRENT_VERSION
+        ));
+    }
+
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        tracing::info!(
+            "Migrating {} to format {}: {}",
+            base_dir.display(),
+            migration.version,
+            migration.description
+        );
+        (migration.run)(cache).with_context(|| {
+            format!(
+                "Migration to format {} ({}) failed",
+                migration.version, migration.description
+            )
+        })?;
+        set_version(base_dir, migration.version)?;
+        applied.push(migration.description);
+    }
+    Ok(applied)
+}
+
+/// Moves an article file and its raw entry, unless the target is taken or
+/// another process moved it first
+fn move_article(from: &Path, to: &Path) -> Result<()> {
+    if to.exists() {
+        return Ok(());
+    }
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        result => result?,
+    }
+    let raw = cache::raw_entry_path(from);
+    if raw.exists() {
+        fs::rename(&raw, cache::raw_entry_path(to))?;
+    }
+    let highlights = cache::highlights_path(from);
+    if highlights.exists() {
+        fs::rename(&highlights, cache::highlights_path(to))?;
+    }
+    Ok(())
+}
+
+/// v1: articles used to live directly in `articles/`
+fn per_feed_directories(cache: &TextCache) -> Result<()> {
+    for entry in fs::read_dir(cache.articles_dir())?.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if !path.is_file() || path.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+        // Unparseable files stay put for `doctor` to report
+        let Ok(article) = cache.parse_article_file(&path) else {
+            continue;
+        };
+        let feed_dir = cache.feed_dir(&article.feed_url);
+        fs::create_dir_all(&feed_dir)?;
+        move_article(&path, &feed_dir.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// v2: early versions named files `<id>.md`; they are `<date>-<id>.md` now
+fn dated_file_names(cache: &TextCache) -> Result<()> {
+    for path in crate::index::article_files(cache.articles_dir()) {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if cache::is_dated_file_name(name) {
+            continue;
+        }
+        let Ok(article) = cache.parse_article_file(&path) else {
+            continue;
+        };
+        if article.id.is_empty() {
+            continue;
+        }
+        move_article(
+            &path,
+            &path.with_file_name(cache::article_file_name(&article)),
+        )?;
+    }
+    Ok(())
+}
+
+/// v3: the feed list used to be a plain list of URLs
+fn feed_list_json(cache: &TextCache) -> Result<()> {
+    let legacy = cache.state_path("feeds.txt");
+    if !legacy.exists() {
+        return Ok(());
+    }
+    if !cache.state_path("feeds.json").exists() {
+        let content = fs::read_to_string(&legacy)?;
+        let feeds = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|url| crate::scanner::FeedSource {
+                url: url.to_string(),
+                source_file: String::new(),
+                line_number: 0,
+                root: None,
+                options: Default::default(),
+            })
+            .collect();
+        cache.store_feed_list(feeds)?;
+    }
+    fs::remove_file(&legacy)?;
+    Ok(())
+}
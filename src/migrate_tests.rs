@@ -0,0 +1,86 @@
+use super::*;
+use crate::models::FeedItem;
+use chrono::{TimeZone, Utc};
+use tempfile::TempDir;
+
+const FEED: &str = "https://example.com/feed";
+
+#[test]
+fn test_old_layout_upgraded() {
+    let temp_dir = TempDir::new().unwrap();
+    let articles = temp_dir.path().join("articles");
+    let state = temp_dir.path().join("state");
+    fs::create_dir_all(&articles).unwrap();
+    fs::create_dir_all(&state).unwrap();
+    // Flat, ID-named files from the first releases
+    fs::write(
+        articles.join("old.md"),
+        format!(
+            "---\nid: old-article\nfeed: {}\ntitle: Old\ndate: 2024-01-15T12:05:30+00:00\nread: true\n---\nBody",
+            FEED
+        ),
+    )
+    .unwrap();
+    fs::write(articles.join("old.json"), "{}").unwrap();
+    fs::write(state.join("feeds.txt"), format!("{}\n\n", FEED)).unwrap();
+
+    let cache = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    assert_eq!(version(temp_dir.path()).unwrap(), CURRENT_VERSION);
+
+    let feed_dir = cache.feed_dir(FEED);
+    assert!(feed_dir.join("20240115-120530-old-article.md").exists());
+    assert!(feed_dir.join("20240115-120530-old-article.json").exists());
+    assert!(!articles.join("old.md").exists());
+    let article = cache.get_article_by_id("old-article").unwrap().unwrap();
+    assert!(article.read);
+
+    assert!(!state.join("feeds.txt").exists());
+    let feeds = cache.get_feed_list().unwrap();
+    assert_eq!(feeds.len(), 1);
+    assert_eq!(feeds[0].url, FEED);
+
+    // Nothing left to do on the next open
+    drop(cache);
+    let cache = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    assert!(run(&cache).unwrap().is_empty());
+}
+
+#[test]
+fn test_new_cache_starts_current() {
+    let temp_dir = TempDir::new().unwrap();
+    TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    assert_eq!(version(temp_dir.path()).unwrap(), CURRENT_VERSION);
+}
+
+#[test]
+fn test_newer_format_rejected() {
+    let temp_dir = TempDir::new().unwrap();
+    let state = temp_dir.path().join("state");
+    fs::create_dir_all(&state).unwrap();
+    fs::write(
+        state.join("format.json"),
+        format!("{{\"version\": {}}}", CURRENT_VERSION + 1),
+    )
+    .unwrap();
+
+    let err = TextCache::with_base_dir(temp_dir.path().to_path_buf())
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("upgrade zetrss"));
+}
+
+#[test]
+fn test_dated_file_names() {
+    let item = FeedItem {
+        id: "https://example.com/post?id=1".to_string(),
+        feed_url: FEED.to_string(),
+        title: "Post".to_string(),
+        published: Some(Utc.with_ymd_and_hms(2024, 1, 15, 12, 5, 30).unwrap()),
+        ..Default::default()
+    };
+    let name = cache::article_file_name(&item);
+    assert!(name.starts_with("20240115-120530-https___example"));
+    assert!(cache::is_dated_file_name(&name));
+    assert!(!cache::is_dated_file_name("old-article.md"));
+    assert!(!cache::is_dated_file_name("2024011-120530-x.md"));
+}
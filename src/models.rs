@@ -1,7 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FeedItem {
     pub id: String,
     pub feed_url: String,
@@ -13,8 +13,85 @@ pub struct FeedItem {
     pub content: Option<String>,
     pub read: bool,
     pub starred: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// When the article was first cached; unset for older articles
+    #[serde(default)]
+    pub fetched: Option<DateTime<Utc>>,
     #[serde(skip)]
     pub filepath: Option<String>,
+    /// The entry as parsed from the feed, set on freshly fetched items
+    #[serde(skip)]
+    pub raw: Option<RawEntry>,
+}
+
+/// A feed entry as parsed, kept next to the article so it can be re-rendered
+/// or reprocessed after the feed has dropped it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RawEntry {
+    pub id: String,
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    pub content: Option<RawContent>,
+    pub links: Vec<RawLink>,
+    pub authors: Vec<RawPerson>,
+    pub contributors: Vec<RawPerson>,
+    pub categories: Vec<RawCategory>,
+    /// Enclosures and Media RSS objects
+    pub media: Vec<RawMedia>,
+    pub published: Option<DateTime<Utc>>,
+    pub updated: Option<DateTime<Utc>>,
+    pub source: Option<String>,
+    pub rights: Option<String>,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RawContent {
+    pub body: Option<String>,
+    pub content_type: String,
+    pub src: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RawLink {
+    pub href: String,
+    pub rel: Option<String>,
+    pub media_type: Option<String>,
+    pub title: Option<String>,
+    pub length: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RawPerson {
+    pub name: String,
+    pub uri: Option<String>,
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RawCategory {
+    pub term: String,
+    pub scheme: Option<String>,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RawMedia {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub content: Vec<RawMediaContent>,
+    pub thumbnails: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RawMediaContent {
+    pub url: Option<String>,
+    pub content_type: Option<String>,
+    pub size: Option<u64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub duration_secs: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,4 +101,46 @@ pub struct Feed {
     pub description: Option<String>,
     pub last_fetched: Option<DateTime<Utc>>,
     pub items: Vec<FeedItem>,
+    /// Size of the downloaded document, 0 if it wasn't downloaded
+    #[serde(skip)]
+    pub bytes: usize,
+}
+
+/// Article metadata without the body, used for listings and JSON output
+#[derive(Debug, Clone, Serialize)]
+pub struct ArticleSummary {
+    pub id: String,
+    pub feed_url: String,
+    pub title: String,
+    pub link: String,
+    pub author: Option<String>,
+    pub published: Option<DateTime<Utc>>,
+    pub read: bool,
+    pub starred: bool,
+    pub tags: Vec<String>,
+    pub filepath: Option<String>,
+}
+
+impl From<&FeedItem> for ArticleSummary {
+    fn from(item: &FeedItem) -> Self {
+        Self {
+            id: item.id.clone(),
+            feed_url: item.feed_url.clone(),
+            title: item.title.clone(),
+            link: item.link.clone(),
+            author: item.author.clone(),
+            published: item.published,
+            read: item.read,
+            starred: item.starred,
+            tags: item.tags.clone(),
+            filepath: item.filepath.clone(),
+        }
+    }
+}
+
+/// A passage highlighted in the viewer, kept next to the article
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Highlight {
+    pub text: String,
+    pub created: DateTime<Utc>,
 }
@@ -0,0 +1,1120 @@
+use crate::models::{FeedItem, Highlight};
+use crate::scanner::{feed_key, FeedSource};
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use walkdir::WalkDir;
+
+#[cfg(test)]
+#[path = "notes_tests.rs"]
+mod tests;
+
+/// The built-in note format, chosen with `[notes] flavor`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteFlavor {
+    /// Plain markdown with `Source:` and `Feed:` lines
+    #[default]
+    Markdown,
+    /// YAML frontmatter (source, tags, aliases), a `[[wikilink]]` to the
+    /// feed and callouts for the summary and quotes
+    Obsidian,
+    /// An org heading with a PROPERTIES drawer and org timestamps
+    Org,
+}
+
+impl NoteFlavor {
+    /// The built-in template of this flavor
+    pub fn template(self) -> &'static str {
+        match self {
+            Self::Markdown => DEFAULT_TEMPLATE,
+            Self::Obsidian => OBSIDIAN_TEMPLATE,
+            Self::Org => ORG_TEMPLATE,
+        }
+    }
+
+    /// File extension of this flavor's notes, `{{ ext }}` in `[notes] filename`
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown | Self::Obsidian => "md",
+            Self::Org => "org",
+        }
+    }
+}
+
+/// Note template used unless `[notes] template` names another file
+pub const DEFAULT_TEMPLATE: &str = "# {{ title }}
+
+Source: {{ link }}
+Feed: {{ feed }}
+{% if topic_link %}Topic: {{ topic_link }}
+{% endif %}{% if citekey %}Citation: [@{{ citekey }}]
+{% endif %}{% if date %}Date: {{ date }}
+{% endif %}{% if tags %}Tags:{% for tag in tags %} #{{ tag | slug }}{% endfor %}
+{% endif %}{% for field in fields %}{{ field.name }}: {{ field.value }}
+{% endfor %}
+## Summary
+
+{{ summary }}
+
+{% if article_copy == \"embed\" and body %}<details>
+<summary>Article</summary>
+
+{{ body }}
+
+</details>
+
+{% elif article_copy == \"link\" and cached_link %}Cached copy: {{ cached_link }}
+
+{% endif %}{% if related %}## Related
+
+{% for note in related %}- {{ note.link }}
+{% endfor %}
+{% endif %}{% if highlights %}## Highlights
+
+{% for highlight in highlights %}{{ highlight.text | blockquote }}
+{% endfor %}{% endif %}## Notes
+
+{% for quote in quotes %}{{ quote | blockquote }}>
+> — {{ source }}
+
+{% endfor %}";
+
+/// Template of the `obsidian` flavor
+pub const OBSIDIAN_TEMPLATE: &str = "---
+title: {{ title | quoted }}
+source: {{ link | quoted }}
+feed: {{ feed | quoted }}
+{% if citekey %}citekey: {{ citekey | quoted }}
+{% endif %}{% if author %}author: {{ author | quoted }}
+{% endif %}{% if date %}published: {{ date | quoted }}
+{% endif %}created: {{ created | quoted }}
+{% for field in fields %}{{ field.name }}: {{ field.value | quoted }}
+{% endfor %}tags: [{% for tag in tags %}{{ tag | slug | quoted }}{% if not loop.last %}, {% endif %}{% endfor %}]
+aliases: [{{ title | quoted }}]
+---
+
+# {{ title }}
+
+Feed: [[{{ feed_host }}]]
+{% if topic_name %}Topic: [[{{ topic_name }}{% if topic_heading %}#{{ topic_heading }}{% endif %}]]
+{% endif %}
+> [!summary]
+{{ summary | blockquote }}
+{% if article_copy == \"embed\" and body %}> [!abstract]- Article
+{{ body | blockquote }}
+{% elif article_copy == \"link\" and cached_link %}Cached copy: {{ cached_link }}
+
+{% endif %}{% if related %}## Related
+
+{% for note in related %}- [[{{ note.name }}]]
+{% endfor %}
+{% endif %}{% if highlights %}## Highlights
+
+{% for highlight in highlights %}{{ highlight.text | blockquote }}
+{% endfor %}{% endif %}## Notes
+
+{% for quote in quotes %}> [!quote] {{ source }}
+{{ quote | blockquote }}
+{% endfor %}";
+
+/// What of the cached article new notes keep, chosen with `[notes] article`,
+/// so they stay useful after the original page is gone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArticleCopy {
+    /// Only the summary
+    #[default]
+    None,
+    /// The whole article as text, in a collapsed section
+    Embed,
+    /// A link to the article's file in the cache
+    Link,
+}
+
+/// How new notes are named, chosen with `[notes] naming`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteNaming {
+    /// The `[notes] filename` pattern
+    #[default]
+    Pattern,
+    /// Denote's `20240305T090700--title__tag1_tag2.md`, with the article's
+    /// tags as keywords
+    Denote,
+}
+
+/// Where notes of articles go, chosen with `[notes] mode`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteMode {
+    /// A new note per article
+    #[default]
+    Note,
+    /// An entry under `[notes.daily] heading` in today's daily note
+    Daily,
+}
+
+/// Template of the `org` flavor
+pub const ORG_TEMPLATE: &str = "#+TITLE: {{ title }}
+{% if tags %}#+FILETAGS: :{% for tag in tags %}{{ tag | slug | replace(from=\"-\", to=\"_\") }}:{% endfor %}
+{% endif %}
+* {{ title }}
+:PROPERTIES:
+:SOURCE: {{ link }}
+:FEED: {{ feed }}
+{% if citekey %}:CITEKEY: {{ citekey }}
+{% endif %}{% if topic_path %}:TOPIC: [[file:{{ topic_path }}{% if topic_heading %}::*{{ topic_heading }}{% endif %}][{{ topic_title }}]]
+{% endif %}{% if author %}:AUTHOR: {{ author }}
+{% endif %}{% if date_org %}:PUBLISHED: {{ date_org }}
+{% endif %}:CREATED: {{ created_org }}
+{% for field in fields %}:{{ field.name | upper }}: {{ field.value }}
+{% endfor %}:END:
+
+** Summary
+
+{{ summary }}
+
+{% if article_copy == \"embed\" and body %}** Article
+:PROPERTIES:
+:VISIBILITY: folded
+:END:
+
+{{ body | indent }}
+
+{% elif article_copy == \"link\" and cached %}Cached copy: [[file:{{ cached }}][{{ title }}]]
+
+{% endif %}{% if related %}** Related
+
+{% for note in related %}- [[file:{{ note.path }}][{{ note.title }}]]
+{% endfor %}
+{% endif %}{% if highlights %}** Highlights
+
+{% for highlight in highlights %}#+BEGIN_QUOTE
+{{ highlight.text }}
+#+END_QUOTE
+
+{% endfor %}{% endif %}** Notes
+
+{% for quote in quotes %}#+BEGIN_QUOTE
+{{ quote }}
+#+END_QUOTE
+— [[{{ link }}][{{ title }}]]
+
+{% endfor %}";
+
+/// Creates a zet note for an article in the notes directory (`[notes] dir`,
+/// else the zet), unless a note in the notes directory or a zet root already
+/// has the article as its source
+/// Returns the path of the new or existing note
+/// With `[notes] mode = "daily"` the article becomes an entry in the daily
+/// note instead (`add_to_daily_note`)
+pub fn create_note_from_article(article: &FeedItem) -> Result<String> {
+    match crate::config::get().notes.mode {
+        NoteMode::Note => {
+            let dir = notes_dir()?;
+            match existing_note(&dir, article) {
+                Some(path) => Ok(path.to_string_lossy().into_owned()),
+                None => create_note_in(&dir, article),
+            }
+        }
+        NoteMode::Daily => add_to_daily_note(article),
+    }
+}
+
+/// Lists the article in today's daily note per `[notes.daily]`
+/// Returns the path of the daily note
+pub fn add_to_daily_note(article: &FeedItem) -> Result<String> {
+    let notes = &crate::config::get().notes;
+    append_daily_entry(
+        &notes_dir()?,
+        article,
+        &notes.daily,
+        notes.flavor,
+        chrono::Local::now(),
+    )
+}
+
+/// Adds an entry for the article under the daily heading of the daily note
+/// for `now` in `dir`, creating the note from its template first if needed
+/// An article already linked from the note isn't added again
+pub fn append_daily_entry(
+    dir: &Path,
+    article: &FeedItem,
+    daily: &crate::config::DailyNoteConfig,
+    flavor: NoteFlavor,
+    now: chrono::DateTime<chrono::Local>,
+) -> Result<String> {
+    let org = flavor == NoteFlavor::Org;
+    let name = note_filename(&daily.path, "%Y-%m-%d", flavor.extension(), article, now)?;
+    let path = dir.join(name);
+
+    let existing = if path.exists() {
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?
+    } else {
+        let template = match &daily.template {
+            Some(template) => load_template(Some(template), flavor)?,
+            None if org => "#+TITLE: {{ date }}\n".to_string(),
+            None => "# {{ date }}\n".to_string(),
+        };
+        let mut context = tera::Context::new();
+        context.insert("date", &now.format("%Y-%m-%d").to_string());
+        context.insert("weekday", &now.format("%A").to_string());
+        render(&template, "daily note template", &context)?
+    };
+    if existing.contains(&article.link) {
+        return Ok(path.to_string_lossy().into_owned());
+    }
+
+    let default_entry = if org {
+        "- [[{{ link }}][{{ title }}]]{% if summary_line %} — {{ summary_line }}{% endif %}"
+    } else {
+        "- {{ source }}{% if summary_line %} — {{ summary_line }}{% endif %}"
+    };
+    let entry = render(
+        daily.entry.as_deref().unwrap_or(default_entry),
+        "daily note entry",
+        &note_context(article, &[], declaring_feed(&article.feed_url).as_ref()),
+    )?;
+    let heading = daily
+        .heading
+        .as_deref()
+        .unwrap_or(if org { "* Reading" } else { "## Reading" });
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(
+        &path,
+        insert_under_heading(&existing, heading, entry.trim_end()),
+    )
+    .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// `content` with `entry` added as the last item of the `heading` section,
+/// or in a new `heading` section at the end
+fn insert_under_heading(content: &str, heading: &str, entry: &str) -> String {
+    let mut lines: Vec<&str> = content.lines().collect();
+    let Some(start) = lines.iter().position(|l| l.trim_end() == heading) else {
+        let mut content = content.trim_end().to_string();
+        if !content.is_empty() {
+            content.push_str("\n\n");
+        }
+        return format!("{}{}\n\n{}\n", content, heading, entry);
+    };
+
+    let end = section_end(&lines, start, heading);
+    let mut at = end;
+    while at > start + 1 && lines[at - 1].trim().is_empty() {
+        at -= 1;
+    }
+    if at == start + 1 {
+        lines.insert(at, "");
+        at += 1;
+    }
+    lines.insert(at, entry);
+    if at + 1 < lines.len() && !lines[at + 1].trim().is_empty() {
+        lines.insert(at + 1, "");
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Index of the line ending the `heading` section that starts at `start`:
+/// the next heading of the same or a higher level, else the end
+fn section_end(lines: &[&str], start: usize, heading: &str) -> usize {
+    let marker = heading.chars().next().unwrap_or('#');
+    let level = heading.chars().take_while(|c| *c == marker).count();
+    let is_heading = |line: &str| {
+        let depth = line.chars().take_while(|c| *c == marker).count();
+        depth > 0 && depth <= level && line[depth..].starts_with(' ')
+    };
+    lines[start + 1..]
+        .iter()
+        .position(|l| is_heading(l))
+        .map_or(lines.len(), |i| start + 1 + i)
+}
+
+/// Writes the article's highlights into its note (creating the note first if
+/// there is none) under `## Highlights`, replacing what that section held
+/// Returns the path of the note
+pub fn export_highlights(
+    dir: &Path,
+    article: &FeedItem,
+    highlights: &[Highlight],
+) -> Result<String> {
+    let path = match existing_note(dir, article) {
+        Some(path) => path.to_string_lossy().into_owned(),
+        None => create_note_in(dir, article)?,
+    };
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read note {}", path))?;
+    let org = path.ends_with(".org");
+    let quotes: Vec<String> = highlights
+        .iter()
+        .map(|highlight| {
+            let lines: Vec<String> = highlight.text.lines().map(String::from).collect();
+            if org {
+                format!("#+BEGIN_QUOTE\n{}\n#+END_QUOTE\n", lines.join("\n"))
+            } else {
+                blockquote(&lines)
+            }
+        })
+        .collect();
+    let (heading, before) = if org {
+        ("** Highlights", "** Notes")
+    } else {
+        ("## Highlights", "## Notes")
+    };
+    fs::write(
+        &path,
+        replace_section(&content, heading, before, &quotes.join("\n")),
+    )
+    .with_context(|| format!("Failed to write note {}", path))?;
+    Ok(path)
+}
+
+/// `content` with the body of the `heading` section replaced by `body`; a
+/// missing section is added before the `before` heading, else at the end
+fn replace_section(content: &str, heading: &str, before: &str, body: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let section = format!("{}\n\n{}", heading, body.trim_end());
+    let (head, tail) = match lines.iter().position(|l| l.trim_end() == heading) {
+        Some(start) => {
+            let end = section_end(&lines, start, heading);
+            (&lines[..start], &lines[end..])
+        }
+        None => match lines.iter().position(|l| l.trim_end() == before) {
+            Some(at) => (&lines[..at], &lines[at..]),
+            None => (&lines[..], &[][..]),
+        },
+    };
+    let mut out = head.join("\n").trim_end().to_string();
+    if !out.is_empty() {
+        out.push_str("\n\n");
+    }
+    out.push_str(&section);
+    out.push('\n');
+    if !tail.is_empty() {
+        out.push('\n');
+        out.push_str(&tail.join("\n"));
+        out.push('\n');
+    }
+    out
+}
+
+/// The configured notes directory
+/// Fails when nothing is configured and the fallback zet doesn't exist, rather
+/// than creating `~/git/$USER/zet` out of the blue
+pub fn notes_dir() -> Result<PathBuf> {
+    let config = crate::config::get();
+    let dir = PathBuf::from(config.notes_dir());
+    if config.notes.dir.is_none() && config.zet_path.is_none() && !dir.is_dir() {
+        bail!(
+            "No notes directory: {} doesn't exist; set zet_path or [notes] dir in the config",
+            dir.display()
+        );
+    }
+    Ok(dir)
+}
+
+/// Creates a note for the article in `zet_path`, named by `[notes] filename`,
+/// or returns the note there whose source is already the article
+pub fn create_note_in(zet_path: &Path, article: &FeedItem) -> Result<String> {
+    if let Some(path) = find_note(zet_path, article) {
+        return Ok(path.to_string_lossy().into_owned());
+    }
+    write_note(zet_path, article, &note_template(article)?)
+}
+
+/// A note about the article in `dir` or any zet root, found by `find_note`
+pub fn existing_note(dir: &Path, article: &FeedItem) -> Option<PathBuf> {
+    std::iter::once(dir.to_path_buf())
+        .chain(
+            crate::config::get()
+                .zet_roots()
+                .into_iter()
+                .map(PathBuf::from),
+        )
+        .filter(|root| root.is_dir())
+        .find_map(|root| find_note(&root, article))
+}
+
+/// Starting text of an article's note, rendered from the `[notes] template`
+/// file or `DEFAULT_TEMPLATE`
+pub fn note_template(article: &FeedItem) -> Result<String> {
+    let mut context = article_context(article, &[]);
+    insert_fields(&mut context, &crate::config::get().notes.fields)?;
+    render(&template()?, "note template", &context)
+}
+
+/// The Tera template notes are created from
+fn template() -> Result<String> {
+    let notes = &crate::config::get().notes;
+    load_template(notes.template.as_deref(), notes.flavor)
+}
+
+/// The template file at `path` (`~` expanded), or the flavor's built-in one
+pub fn load_template(path: Option<&str>, flavor: NoteFlavor) -> Result<String> {
+    match path {
+        Some(path) => {
+            let path = shellexpand::tilde(path).to_string();
+            fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read note template {}", path))
+        }
+        None => Ok(flavor.template().to_string()),
+    }
+}
+
+/// Renders an article's note from a Tera `template`, with `quotes` (passages
+/// selected in the viewer) available to it
+///
+/// Variables: `title`, `link`, `feed` (URL), `feed_host`, `author`, `date`
+/// (published, may be empty), `created` (now, local time), `date_org` and
+/// `created_org` (the same as inactive org timestamps), `tags`, `summary`
+/// (the first paragraph), `quotes`, `highlights` (each with `text` and
+/// `created`; filled in for notes being created) and `source` (a markdown
+/// link to the article). Filters: `blockquote` turns text into `> ` lines, `quoted`
+/// makes a double-quoted YAML string and `slug` a file or tag name.
+///
+/// `topic_path`, `topic_name` (file stem), `topic_heading` (the heading above
+/// the declaration, may be empty), `topic_title` (the heading, else the file
+/// stem) and `topic_link` (a markdown link to the heading) point back to the
+/// zet note declaring the article's feed; all empty when the feed list
+/// doesn't know the feed.
+///
+/// `fields` lists the `[notes.fields]` as `name` and rendered `value`.
+pub fn render_note(template: &str, article: &FeedItem, quotes: &[String]) -> Result<String> {
+    let topic = declaring_feed(&article.feed_url);
+    let mut context = note_context(article, quotes, topic.as_ref());
+    insert_fields(&mut context, &crate::config::get().notes.fields)?;
+    render(template, "note template", &context)
+}
+
+/// A `[notes.fields]` entry as the `fields` template variable lists it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct NoteField {
+    name: String,
+    value: String,
+}
+
+/// Renders the `[notes.fields]` values (Tera templates seeing the note's
+/// variables) into `fields`; fields rendering empty are left out
+fn insert_fields(
+    context: &mut tera::Context,
+    fields: &std::collections::BTreeMap<String, String>,
+) -> Result<()> {
+    let mut rendered = Vec::new();
+    for (name, value) in fields {
+        let value = render(value, &format!("[notes.fields] {}", name), context)?;
+        if !value.trim().is_empty() {
+            rendered.push(NoteField {
+                name: name.clone(),
+                value: value.trim().to_string(),
+            });
+        }
+    }
+    context.insert("fields", &rendered);
+    Ok(())
+}
+
+/// The variables of a note being created: `render_note`'s, with the
+/// summarizer's bullets as `summary` when `[summarizer]` is configured, the
+/// `[notes] related` notes closest to the article as `related` and the
+/// article's highlights as `highlights`
+fn article_context(article: &FeedItem, quotes: &[String]) -> tera::Context {
+    let topic = declaring_feed(&article.feed_url);
+    let mut context = note_context(article, quotes, topic.as_ref());
+    context.insert("highlights", &stored_highlights(article));
+    match crate::summarize::summary(&crate::cache::default_data_dir(), article) {
+        Ok(Some(summary)) => context.insert("summary", &summary),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to summarize {}: {:#}", article.id, e),
+    }
+    let limit = crate::config::get().notes.related;
+    if limit > 0 {
+        match related_notes(article, limit) {
+            Ok(related) => {
+                let related: Vec<RelatedLink> = related.into_iter().map(Into::into).collect();
+                context.insert("related", &related);
+            }
+            Err(e) => tracing::warn!("Failed to find notes related to {}: {:#}", article.id, e),
+        }
+    }
+    context
+}
+
+/// The article's highlights; the cache is only opened when its file has some
+fn stored_highlights(article: &FeedItem) -> Vec<Highlight> {
+    let has_highlights = article
+        .filepath
+        .as_deref()
+        .is_some_and(|path| crate::cache::highlights_path(Path::new(path)).exists());
+    if !has_highlights {
+        return Vec::new();
+    }
+    crate::cache::TextCache::new()
+        .and_then(|cache| cache.get_highlights(&article.id))
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to read highlights of {}: {:#}", article.id, e);
+            Vec::new()
+        })
+}
+
+/// The stored feed list's entry for the feed, telling where it's declared
+fn declaring_feed(feed_url: &str) -> Option<FeedSource> {
+    let path = crate::cache::default_data_dir()
+        .join("state")
+        .join("feeds.json");
+    let feeds: Vec<FeedSource> = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    let key = feed_key(feed_url);
+    feeds.into_iter().find(|feed| feed_key(&feed.url) == key)
+}
+
+/// The variables of `render_note`, plus `summary_line` (the summary's first
+/// sentence or line, for one-line entries)
+fn note_context(
+    article: &FeedItem,
+    quotes: &[String],
+    topic: Option<&FeedSource>,
+) -> tera::Context {
+    let summary = article
+        .content
+        .as_ref()
+        .map(|content| {
+            let text = html2text::from_read(content.as_bytes(), 80);
+            text.split("\n\n").next().unwrap_or("").to_string()
+        })
+        .unwrap_or_default();
+
+    let mut context = tera::Context::new();
+    context.insert("title", &article.title);
+    context.insert("link", &article.link);
+    context.insert("feed", &article.feed_url);
+    context.insert("feed_host", &feed_host(&article.feed_url));
+    context.insert("author", &article.author.clone().unwrap_or_default());
+    context.insert(
+        "date",
+        &article.published.map(|d| d.to_string()).unwrap_or_default(),
+    );
+    context.insert(
+        "created",
+        &chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+    );
+    context.insert(
+        "date_org",
+        &article
+            .published
+            .map(|d| org_timestamp(d.with_timezone(&chrono::Local)))
+            .unwrap_or_default(),
+    );
+    context.insert("created_org", &org_timestamp(chrono::Local::now()));
+    context.insert("tags", &note_tags(&article.tags));
+    context.insert("summary_line", &summary_line(&summary));
+    context.insert("summary", &summary);
+    context.insert("quotes", quotes);
+    context.insert(
+        "source",
+        &crate::clipboard::markdown_link(&article.title, &article.link),
+    );
+    let notes = &crate::config::get().notes;
+    context.insert("article_copy", &notes.article);
+    context.insert(
+        "citekey",
+        &if notes.citekey {
+            crate::cite::citation_key(article)
+        } else {
+            String::new()
+        },
+    );
+    context.insert(
+        "body",
+        &article
+            .content
+            .as_ref()
+            .or(article.description.as_ref())
+            .map(|html| {
+                html2text::from_read(html.as_bytes(), 80)
+                    .trim_end()
+                    .to_string()
+            })
+            .unwrap_or_default(),
+    );
+    let cached = article.filepath.clone().unwrap_or_default();
+    let cached_link = match cached.as_str() {
+        "" => String::new(),
+        path if path.contains(' ') => format!("[cached article](<{}>)", path),
+        path => format!("[cached article]({})", path),
+    };
+    context.insert("cached", &cached);
+    context.insert("cached_link", &cached_link);
+    context.insert("related", &Vec::<RelatedLink>::new());
+    context.insert("fields", &Vec::<NoteField>::new());
+    context.insert("highlights", &Vec::<Highlight>::new());
+    insert_topic(&mut context, topic);
+    context
+}
+
+/// The article's tags (feed categories, the feed's `tags=` and the user's)
+/// without those differing only in case or punctuation, first one kept
+fn note_tags(tags: &[String]) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    tags.iter()
+        .filter(|tag| !tag.trim().is_empty() && seen.insert(slug(tag)))
+        .cloned()
+        .collect()
+}
+
+/// A related zet note as the `related` template variable lists it
+#[derive(Serialize)]
+struct RelatedLink {
+    path: String,
+    /// File stem, for wikilinks
+    name: String,
+    title: String,
+    /// Markdown link to the note
+    link: String,
+}
+
+impl From<crate::related::RelatedNote> for RelatedLink {
+    fn from(note: crate::related::RelatedNote) -> Self {
+        let name = Path::new(&note.path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let target = if note.path.contains(' ') {
+            format!("<{}>", note.path)
+        } else {
+            note.path.clone()
+        };
+        Self {
+            link: crate::clipboard::markdown_link(&note.title, &target),
+            name,
+            title: note.title,
+            path: note.path,
+        }
+    }
+}
+
+/// The `limit` zet notes most related to the article (see `related`)
+pub fn related_notes(article: &FeedItem, limit: usize) -> Result<Vec<crate::related::RelatedNote>> {
+    let config = crate::config::get();
+    let mut roots = config.zet_roots();
+    roots.push(config.notes_dir());
+    let index = crate::related::TermIndex::build(&roots)?;
+    Ok(index.related(&crate::related::article_text(article), limit))
+}
+
+/// Adds the `topic_*` variables for the note declaring a feed
+fn insert_topic(context: &mut tera::Context, topic: Option<&FeedSource>) {
+    let (path, name, heading) = match topic {
+        Some(source) => {
+            let path = Path::new(&source.source_file);
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let heading = fs::read_to_string(path)
+                .ok()
+                .and_then(|content| {
+                    let org = path.extension().is_some_and(|ext| ext == "org");
+                    heading_above(&content, source.line_number, org)
+                })
+                .unwrap_or_default();
+            (source.source_file.clone(), name, heading)
+        }
+        None => Default::default(),
+    };
+    let title = if heading.is_empty() { &name } else { &heading };
+    let link = if path.is_empty() {
+        String::new()
+    } else {
+        let mut target = path.clone();
+        if !heading.is_empty() {
+            target = format!("{}#{}", target, heading_anchor(&heading));
+        }
+        if target.contains(' ') {
+            target = format!("<{}>", target);
+        }
+        crate::clipboard::markdown_link(title, &target)
+    };
+    context.insert("topic_title", title);
+    context.insert("topic_path", &path);
+    context.insert("topic_name", &name);
+    context.insert("topic_heading", &heading);
+    context.insert("topic_link", &link);
+}
+
+/// Text of the last markdown (or `org`) heading before the 1-indexed `line`
+fn heading_above(content: &str, line: usize, org: bool) -> Option<String> {
+    static MARKDOWN: OnceLock<Regex> = OnceLock::new();
+    static ORG: OnceLock<Regex> = OnceLock::new();
+    let heading = if org {
+        ORG.get_or_init(|| Regex::new(r"^\*+\s+(.+?)\s*$").unwrap())
+    } else {
+        MARKDOWN.get_or_init(|| Regex::new(r"^#{1,6}\s+(.+?)\s*$").unwrap())
+    };
+    content
+        .lines()
+        .take(line.saturating_sub(1))
+        .filter_map(|l| heading.captures(l).map(|cap| cap[1].to_string()))
+        .last()
+}
+
+/// The anchor markdown renderers give a heading: lowercase, spaces turned
+/// into dashes and other punctuation dropped
+fn heading_anchor(heading: &str) -> String {
+    heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Renders a Tera `template` (named `what` in errors) with the note filters
+fn render(template: &str, what: &str, context: &tera::Context) -> Result<String> {
+    let mut tera = tera::Tera::default();
+    tera.register_filter("blockquote", blockquote_filter);
+    tera.register_filter("indent", indent_filter);
+    tera.register_filter("quoted", quoted_filter);
+    tera.register_filter("slug", slug_filter);
+    tera.add_raw_template("note", template)
+        .map_err(|e| anyhow::anyhow!("Invalid {}: {}", what, tera_error(&e)))?;
+    tera.render("note", context)
+        .map_err(|e| anyhow::anyhow!("Failed to render {}: {}", what, tera_error(&e)))
+}
+
+/// The first sentence (or line) of `summary`, at most 160 characters
+fn summary_line(summary: &str) -> String {
+    let line = summary.split_whitespace().collect::<Vec<_>>().join(" ");
+    let sentence = match line.find(". ") {
+        Some(end) => &line[..=end],
+        None => &line,
+    };
+    if sentence.chars().count() <= 160 {
+        return sentence.to_string();
+    }
+    let cut: String = sentence.chars().take(159).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// Tera errors keep the useful part (e.g. the parse error) in their sources
+fn tera_error(error: &tera::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+/// An inactive org timestamp, e.g. `[2024-03-05 Tue 09:07]`
+fn org_timestamp(time: chrono::DateTime<chrono::Local>) -> String {
+    time.format("[%Y-%m-%d %a %H:%M]").to_string()
+}
+
+/// The feed URL's host without `www.`, or the URL if it has none
+pub fn feed_host(feed_url: &str) -> String {
+    reqwest::Url::parse(feed_url)
+        .ok()
+        .and_then(|url| {
+            url.host_str()
+                .map(|h| h.trim_start_matches("www.").to_string())
+        })
+        .unwrap_or_else(|| feed_url.to_string())
+}
+
+fn quoted_filter(
+    value: &tera::Value,
+    _args: &HashMap<String, tera::Value>,
+) -> tera::Result<tera::Value> {
+    let text = tera::from_value::<String>(value.clone())?;
+    // A JSON string is a valid YAML double-quoted scalar
+    Ok(tera::Value::String(tera::to_value(text)?.to_string()))
+}
+
+fn slug_filter(
+    value: &tera::Value,
+    _args: &HashMap<String, tera::Value>,
+) -> tera::Result<tera::Value> {
+    let text = tera::from_value::<String>(value.clone())?;
+    Ok(tera::Value::String(slug(&text)))
+}
+
+fn blockquote_filter(
+    value: &tera::Value,
+    _args: &HashMap<String, tera::Value>,
+) -> tera::Result<tera::Value> {
+    let text = tera::from_value::<String>(value.clone())?;
+    let lines: Vec<String> = text.lines().map(String::from).collect();
+    Ok(tera::Value::String(blockquote(&lines)))
+}
+
+/// Tera filter indenting every non-blank line by two spaces, which keeps
+/// text in an org note from starting headings
+fn indent_filter(
+    value: &tera::Value,
+    _args: &HashMap<String, tera::Value>,
+) -> tera::Result<tera::Value> {
+    let text = tera::from_value::<String>(value.clone())?;
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| match line.trim() {
+            "" => String::new(),
+            _ => format!("  {}", line),
+        })
+        .collect();
+    Ok(tera::Value::String(lines.join("\n")))
+}
+
+/// Writes `content` as a new note for the article in `zet_path`, named by
+/// `[notes] filename`; an existing file is never overwritten
+/// Returns the path of the note
+pub fn write_note(zet_path: &Path, article: &FeedItem, content: &str) -> Result<String> {
+    let notes = &crate::config::get().notes;
+    let now = chrono::Local::now();
+    let name = match notes.naming {
+        NoteNaming::Pattern => note_filename(
+            &notes.filename,
+            &notes.id_format,
+            notes.flavor.extension(),
+            article,
+            now,
+        )?,
+        NoteNaming::Denote => denote_filename(notes.flavor.extension(), article, now),
+    };
+    let path = unused_path(&zet_path.join(name));
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// A note's path relative to the notes directory, rendered from the Tera
+/// `pattern` with `id` (`now` as `id_format`), `date`, `year`, `month`,
+/// `slug` (the title), `feed` (the feed's host) and `ext`
+pub fn note_filename(
+    pattern: &str,
+    id_format: &str,
+    ext: &str,
+    article: &FeedItem,
+    now: chrono::DateTime<chrono::Local>,
+) -> Result<PathBuf> {
+    let mut context = tera::Context::new();
+    context.insert("id", &now.format(id_format).to_string());
+    context.insert("date", &now.format("%Y-%m-%d").to_string());
+    context.insert("year", &now.format("%Y").to_string());
+    context.insert("month", &now.format("%m").to_string());
+    context.insert("slug", &slug(&article.title));
+    context.insert("feed", &slug(&feed_host(&article.feed_url)));
+    context.insert("ext", ext);
+    let name = tera::Tera::one_off(pattern, &context, false)
+        .map_err(|e| anyhow::anyhow!("Invalid note filename pattern: {}", tera_error(&e)))?;
+
+    let path = PathBuf::from(name.trim());
+    let escapes = path
+        .components()
+        .any(|c| !matches!(c, std::path::Component::Normal(_)));
+    if path.as_os_str().is_empty() || escapes {
+        bail!(
+            "Note filename pattern gives '{}'; it must be a relative path inside the notes directory",
+            path.display()
+        );
+    }
+    Ok(path)
+}
+
+/// A Denote file name: `now` as identifier, the title's slug and the
+/// article's tags as sorted keywords, e.g.
+/// `20240305T090700--async-rust-a-tour__async_rustlang.md`
+pub fn denote_filename(
+    ext: &str,
+    article: &FeedItem,
+    now: chrono::DateTime<chrono::Local>,
+) -> PathBuf {
+    let mut keywords: Vec<String> = article
+        .tags
+        .iter()
+        .map(|tag| denote_keyword(tag))
+        .filter(|keyword| !keyword.is_empty())
+        .collect();
+    keywords.sort();
+    keywords.dedup();
+    let keywords = if keywords.is_empty() {
+        String::new()
+    } else {
+        format!("__{}", keywords.join("_"))
+    };
+    PathBuf::from(format!(
+        "{}--{}{}.{}",
+        now.format("%Y%m%dT%H%M%S"),
+        slug(&article.title),
+        keywords,
+        ext
+    ))
+}
+
+/// A tag as one Denote keyword: lowercase letters and digits only, since `-`
+/// and `_` separate the parts of the name
+fn denote_keyword(tag: &str) -> String {
+    tag.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Lowercase words of `text` joined by `-`, at most 50 characters
+pub fn slug(text: &str) -> String {
+    let mut slug = String::new();
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_lowercase());
+    }
+    let slug: String = slug.chars().take(50).collect();
+    match slug.trim_end_matches('-') {
+        "" => "untitled".to_string(),
+        slug => slug.to_string(),
+    }
+}
+
+/// `path`, or the first of `name-2.ext`, `name-3.ext`, ... that doesn't exist
+pub(crate) fn unused_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{}-{}{}", stem, n, ext)))
+        .find(|candidate| !candidate.exists())
+        .expect("some numbered name is free")
+}
+
+/// The note in `zet_path` whose `Source:` line is the article's link
+/// The newest such note wins if there are several
+pub fn find_note(zet_path: &Path, article: &FeedItem) -> Option<PathBuf> {
+    WalkDir::new(zet_path)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.path()
+                .extension()
+                .is_some_and(|ext| ext == "md" || ext == "org")
+        })
+        .filter(|e| {
+            fs::read_to_string(e.path())
+                .is_ok_and(|note| note.lines().any(|l| is_source_line(l, &article.link)))
+        })
+        .max_by_key(|e| e.file_name().to_owned())
+        .map(|e| e.into_path())
+}
+
+/// Whether the line names `link` as the note's source: `Source: <link>`, a
+/// frontmatter `source: <link>` (quoted or not) or an org `:SOURCE: <link>`
+fn is_source_line(line: &str, link: &str) -> bool {
+    let line = line.trim_start().strip_prefix(':').unwrap_or(line);
+    let Some((key, value)) = line.split_once(':') else {
+        return false;
+    };
+    let value = value.trim();
+    key.eq_ignore_ascii_case("source")
+        && (value == link
+            || value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) == Some(link))
+}
+
+/// Appends `lines` as a blockquote crediting the article to its note, or
+/// creates the note from the template with `lines` as its one quote
+/// Returns the path of the note
+pub fn append_quote(zet_path: &Path, article: &FeedItem, lines: &[String]) -> Result<String> {
+    let path = match find_note(zet_path, article) {
+        Some(path) => path.to_string_lossy().into_owned(),
+        None => {
+            let content = render(
+                &template()?,
+                "note template",
+                &article_context(article, &[lines.join("\n")]),
+            )?;
+            return write_note(zet_path, article, &content);
+        }
+    };
+    let mut note = OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open note {}", path))?;
+    let existing = fs::read_to_string(&path)?;
+    let separator = if existing.is_empty() || existing.ends_with("\n\n") {
+        ""
+    } else if existing.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    let quote = if path.ends_with(".org") {
+        render_org_quote(article, lines)
+    } else {
+        render_quote(article, lines)
+    };
+    write!(note, "{}{}", separator, quote)
+        .with_context(|| format!("Failed to append to note {}", path))?;
+    Ok(path)
+}
+
+/// Lines as a markdown blockquote followed by a link to the article
+fn render_quote(article: &FeedItem, lines: &[String]) -> String {
+    let mut quote = blockquote(lines);
+    quote.push_str(&format!(
+        ">\n> — {}\n\n",
+        crate::clipboard::markdown_link(&article.title, &article.link)
+    ));
+    quote
+}
+
+/// Lines as an org quote block followed by a link to the article
+fn render_org_quote(article: &FeedItem, lines: &[String]) -> String {
+    format!(
+        "#+BEGIN_QUOTE\n{}\n#+END_QUOTE\n— [[{}][{}]]\n\n",
+        lines.join("\n").trim_end(),
+        article.link,
+        article.title
+    )
+}
+
+/// Lines as `> ` markdown blockquote lines
+fn blockquote(lines: &[String]) -> String {
+    let mut quote = String::new();
+    for line in lines {
+        let line = line.trim_end();
+        if line.is_empty() {
+            quote.push_str(">\n");
+        } else {
+            quote.push_str(&format!("> {}\n", line));
+        }
+    }
+    quote
+}
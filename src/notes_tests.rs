@@ -0,0 +1,547 @@
+use super::*;
+use chrono::{TimeZone, Utc};
+use tempfile::TempDir;
+
+fn article() -> FeedItem {
+    FeedItem {
+        id: "post-1".to_string(),
+        feed_url: "https://example.com/feed".to_string(),
+        title: "Async Rust: A Tour!".to_string(),
+        link: "https://example.com/async".to_string(),
+        published: Some(Utc::now()),
+        content: Some("<p>First paragraph.</p><p>Second paragraph.</p>".to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_create_note_in() {
+    let temp_dir = TempDir::new().unwrap();
+    let zet = temp_dir.path().join("zet");
+
+    let path = create_note_in(&zet, &article()).unwrap();
+    assert!(path.ends_with("-async-rust-a-tour.md"));
+
+    let note = std::fs::read_to_string(&path).unwrap();
+    assert!(note.starts_with("# Async Rust: A Tour!\n\n"));
+    assert!(note.contains("Source: https://example.com/async\n"));
+    assert!(note.contains("First paragraph."));
+    assert!(!note.contains("Second paragraph."));
+    assert!(note.ends_with("## Notes\n\n"));
+}
+
+#[test]
+fn test_append_quote_creates_then_reuses_note() {
+    let temp_dir = TempDir::new().unwrap();
+    let zet = temp_dir.path().join("zet");
+    let article = article();
+
+    let lines = vec![
+        "Futures are lazy.".to_string(),
+        "".to_string(),
+        "Poll them.".to_string(),
+    ];
+    let path = append_quote(&zet, &article, &lines).unwrap();
+    assert_eq!(find_note(&zet, &article).unwrap().to_string_lossy(), path);
+    let note = std::fs::read_to_string(&path).unwrap();
+    assert!(note.contains("## Notes\n\n> Futures are lazy.\n>\n> Poll them.\n>\n> — [Async Rust: A Tour!](https://example.com/async)\n"));
+
+    // A second quote lands in the same note
+    let again = append_quote(&zet, &article, &["Executors run them.".to_string()]).unwrap();
+    assert_eq!(again, path);
+    let note = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(note.matches("> — [Async Rust").count(), 2);
+    assert!(note.contains("(https://example.com/async)\n\n> Executors run them.\n"));
+}
+
+#[test]
+fn test_render_note_from_custom_template() {
+    let mut article = article();
+    article.tags = vec!["rust".to_string(), "async".to_string()];
+    article.published = None;
+    let template = "---\ntags: [{{ tags | join(sep=\", \") }}]\n---\n\
+                    # {{ title | upper }}\n{{ date }}\n\
+                    {% for quote in quotes %}{{ quote | blockquote }}{% endfor %}{{ summary }}";
+    let note = render_note(template, &article, &["Quoted.\n\nMore.".to_string()]).unwrap();
+    assert_eq!(
+        note,
+        "---\ntags: [rust, async]\n---\n# ASYNC RUST: A TOUR!\n\n> Quoted.\n>\n> More.\nFirst paragraph."
+    );
+
+    let err = render_note("{% if %}", &article, &[]).unwrap_err();
+    assert!(err.to_string().starts_with("Invalid note template"));
+    assert!(render_note("{{ missing }}", &article, &[]).is_err());
+}
+
+#[test]
+fn test_note_filename_patterns() {
+    let now = chrono::Local.with_ymd_and_hms(2024, 3, 5, 9, 7, 0).unwrap();
+    let article = article();
+    let name = |pattern: &str, id_format: &str| {
+        note_filename(pattern, id_format, "md", &article, now)
+            .map(|p| p.to_string_lossy().into_owned())
+    };
+
+    assert_eq!(
+        name("{{ id }}-{{ slug }}.md", "%Y%m%d%H%M").unwrap(),
+        "202403050907-async-rust-a-tour.md"
+    );
+    assert_eq!(
+        name("{{ feed }}/{{year}}/{{date}}-{{slug}}.md", "").unwrap(),
+        "example-com/2024/2024-03-05-async-rust-a-tour.md"
+    );
+    assert_eq!(name("{{ id }}.md", "%y%j").unwrap(), "24065.md");
+
+    assert!(name("../{{ slug }}.md", "").is_err());
+    assert!(name("/tmp/{{ slug }}.md", "").is_err());
+    assert!(name("{{ nope }}.md", "").is_err());
+
+    let mut tagged = article.clone();
+    tagged.tags = vec![
+        "Rust Lang".to_string(),
+        "async".to_string(),
+        "rust_lang".to_string(),
+        "!!".to_string(),
+    ];
+    assert_eq!(
+        denote_filename("md", &tagged, now),
+        PathBuf::from("20240305T090700--async-rust-a-tour__async_rustlang.md")
+    );
+    assert_eq!(
+        denote_filename("org", &article, now),
+        PathBuf::from("20240305T090700--async-rust-a-tour.org")
+    );
+
+    assert_eq!(slug("  C++ & Rust: 100% «safe»?! "), "c-rust-100-safe");
+    assert_eq!(slug("?!"), "untitled");
+    assert_eq!(slug(&"word ".repeat(20)).len(), 49);
+}
+
+#[test]
+fn test_write_note_never_overwrites() {
+    let temp_dir = TempDir::new().unwrap();
+    let first = create_note_in(temp_dir.path(), &article()).unwrap();
+    let second = write_note(temp_dir.path(), &article(), "second").unwrap();
+    assert_ne!(first, second);
+    assert!(std::fs::read_to_string(&first)
+        .unwrap()
+        .starts_with("# Async"));
+    assert_eq!(std::fs::read_to_string(&second).unwrap(), "second");
+    let path = temp_dir.path().join("taken.md");
+    std::fs::write(&path, "").unwrap();
+    assert_eq!(unused_path(&path), temp_dir.path().join("taken-2.md"));
+}
+
+#[test]
+fn test_obsidian_flavor() {
+    let mut article = article();
+    article.title = "Say \"hi\"".to_string();
+    article.tags = vec!["Rust Lang".to_string()];
+    let note = render_note(
+        NoteFlavor::Obsidian.template(),
+        &article,
+        &["Lazy.".to_string()],
+    )
+    .unwrap();
+    assert!(
+        note.starts_with("---\ntitle: \"Say \\\"hi\\\"\"\nsource: \"https://example.com/async\"\n")
+    );
+    assert!(note.contains("tags: [\"rust-lang\"]\naliases: [\"Say \\\"hi\\\"\"]\n---\n"));
+    assert!(note.contains("Feed: [[example.com]]\n"));
+    assert!(note.contains("> [!summary]\n> First paragraph.\n"));
+    assert!(note.contains("> [!quote] [Say \"hi\"](https://example.com/async)\n> Lazy.\n"));
+
+    // Quotes find notes by their frontmatter source too
+    let temp_dir = TempDir::new().unwrap();
+    let path = write_note(temp_dir.path(), &article, &note).unwrap();
+    assert_eq!(
+        find_note(temp_dir.path(), &article)
+            .unwrap()
+            .to_string_lossy(),
+        path
+    );
+    assert!(is_source_line(
+        "Source: https://example.com/async",
+        "https://example.com/async"
+    ));
+    assert!(!is_source_line(
+        "source: https://example.com/asyncx",
+        "https://example.com/async"
+    ));
+}
+
+#[test]
+fn test_org_flavor() {
+    let mut article = article();
+    article.tags = vec!["Rust Lang".to_string(), "async".to_string()];
+    let note = render_note(NoteFlavor::Org.template(), &article, &["Lazy.".to_string()]).unwrap();
+    assert!(note.starts_with("#+TITLE: Async Rust: A Tour!\n#+FILETAGS: :rust_lang:async:\n\n* Async Rust: A Tour!\n:PROPERTIES:\n:SOURCE: https://example.com/async\n"));
+    let published = org_timestamp(article.published.unwrap().with_timezone(&chrono::Local));
+    assert!(note.contains(&format!(":PUBLISHED: {}\n", published)));
+    assert!(note.contains(":END:\n\n** Summary\n\nFirst paragraph.\n\n** Notes\n\n#+BEGIN_QUOTE\nLazy.\n#+END_QUOTE\n— [[https://example.com/async][Async Rust: A Tour!]]\n"));
+    assert_eq!(published.len(), "[2024-03-05 Tue 09:07]".len());
+
+    let now = chrono::Local.with_ymd_and_hms(2024, 3, 5, 9, 7, 0).unwrap();
+    let name = note_filename(
+        "{{ id }}-{{ slug }}.{{ ext }}",
+        "%Y%m%d%H%M",
+        NoteFlavor::Org.extension(),
+        &article,
+        now,
+    )
+    .unwrap();
+    assert_eq!(name, PathBuf::from("202403050907-async-rust-a-tour.org"));
+
+    // Quotes into an existing org note use a quote block
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("note.org");
+    std::fs::write(&path, &note).unwrap();
+    let quoted = append_quote(temp_dir.path(), &article, &["Poll.".to_string()]).unwrap();
+    assert_eq!(quoted, path.to_string_lossy());
+    assert!(std::fs::read_to_string(&path).unwrap().ends_with("#+BEGIN_QUOTE\nPoll.\n#+END_QUOTE\n— [[https://example.com/async][Async Rust: A Tour!]]\n\n"));
+}
+
+#[test]
+fn test_append_daily_entry() {
+    let temp_dir = TempDir::new().unwrap();
+    let now = chrono::Local
+        .with_ymd_and_hms(2024, 3, 5, 9, 30, 0)
+        .unwrap();
+    let daily = crate::config::DailyNoteConfig::default();
+
+    let path = append_daily_entry(
+        temp_dir.path(),
+        &article(),
+        &daily,
+        NoteFlavor::Markdown,
+        now,
+    )
+    .unwrap();
+    assert!(path.ends_with("2024-03-05.md"));
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "# 2024-03-05\n\n## Reading\n\n- [Async Rust: A Tour!](https://example.com/async) — First paragraph.\n"
+    );
+
+    // Later entries follow the earlier ones; linked articles aren't repeated
+    let mut other = article();
+    other.link = "https://example.com/pin".to_string();
+    other.title = "Pinning".to_string();
+    other.content = None;
+    append_daily_entry(temp_dir.path(), &other, &daily, NoteFlavor::Markdown, now).unwrap();
+    append_daily_entry(temp_dir.path(), &other, &daily, NoteFlavor::Markdown, now).unwrap();
+    let note = std::fs::read_to_string(&path).unwrap();
+    assert!(note.ends_with("— First paragraph.\n- [Pinning](https://example.com/pin)\n"));
+    assert_eq!(note.matches("example.com/pin").count(), 1);
+}
+
+#[test]
+fn test_append_daily_entry_org() {
+    let temp_dir = TempDir::new().unwrap();
+    let now = chrono::Local
+        .with_ymd_and_hms(2024, 3, 5, 9, 30, 0)
+        .unwrap();
+    let daily = crate::config::DailyNoteConfig {
+        path: "journal/{{ year }}/{{ date }}.{{ ext }}".to_string(),
+        ..Default::default()
+    };
+
+    let path =
+        append_daily_entry(temp_dir.path(), &article(), &daily, NoteFlavor::Org, now).unwrap();
+    assert!(path.ends_with("journal/2024/2024-03-05.org"));
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "#+TITLE: 2024-03-05\n\n* Reading\n\n- [[https://example.com/async][Async Rust: A Tour!]] — First paragraph.\n"
+    );
+}
+
+#[test]
+fn test_insert_under_heading() {
+    let note = "# Today\n\n## Reading\n\n- one\n\n## Todo\n\n- laundry\n";
+    assert_eq!(
+        insert_under_heading(note, "## Reading", "- two"),
+        "# Today\n\n## Reading\n\n- one\n- two\n\n## Todo\n\n- laundry\n"
+    );
+
+    // Deeper headings belong to the section
+    let note = "## Reading\n### Papers\n- a\n## Todo\n";
+    assert_eq!(
+        insert_under_heading(note, "## Reading", "- b"),
+        "## Reading\n### Papers\n- a\n- b\n\n## Todo\n"
+    );
+
+    // An empty section, and a missing one
+    assert_eq!(
+        insert_under_heading("* Reading\n* Todo\n", "* Reading", "- x"),
+        "* Reading\n\n- x\n\n* Todo\n"
+    );
+    assert_eq!(
+        insert_under_heading("# Today\n\nNotes.\n", "## Reading", "- x"),
+        "# Today\n\nNotes.\n\n## Reading\n\n- x\n"
+    );
+}
+
+#[test]
+fn test_backlink_to_declaring_note() {
+    let temp_dir = TempDir::new().unwrap();
+    let topic = temp_dir.path().join("rust.md");
+    std::fs::write(
+        &topic,
+        "# Rust\n\n## Async & Await\n\n* reading list\n#feed https://example.com/feed\n",
+    )
+    .unwrap();
+    let source = FeedSource {
+        url: "https://example.com/feed".to_string(),
+        source_file: topic.to_string_lossy().into_owned(),
+        line_number: 6,
+        root: None,
+        options: Default::default(),
+    };
+    let topic = topic.to_string_lossy();
+
+    let note = render(
+        DEFAULT_TEMPLATE,
+        "",
+        &note_context(&article(), &[], Some(&source)),
+    )
+    .unwrap();
+    assert!(note.contains(&format!("Topic: [Async & Await]({}#async--await)\n", topic)));
+
+    let note = render(
+        OBSIDIAN_TEMPLATE,
+        "",
+        &note_context(&article(), &[], Some(&source)),
+    )
+    .unwrap();
+    assert!(note.contains("Topic: [[rust#Async & Await]]\n"));
+
+    let note = render(
+        ORG_TEMPLATE,
+        "",
+        &note_context(&article(), &[], Some(&source)),
+    )
+    .unwrap();
+    assert!(note.contains(&format!(
+        ":TOPIC: [[file:{}::*Async & Await][Async & Await]]\n",
+        topic
+    )));
+
+    // Feeds the feed list doesn't know get no backlink
+    let note = render(DEFAULT_TEMPLATE, "", &note_context(&article(), &[], None)).unwrap();
+    assert!(!note.contains("Topic:"));
+}
+
+#[test]
+fn test_heading_above() {
+    let org = "#+TITLE: Rust\n* Reading\n** Blogs\n#feed https://a.example/feed\n";
+    assert_eq!(heading_above(org, 4, true).as_deref(), Some("Blogs"));
+    assert_eq!(heading_above(org, 2, true), None);
+    assert_eq!(heading_above("* item\n#feed x\n", 2, false), None);
+}
+
+#[test]
+fn test_create_note_in_reuses_existing_note() {
+    let temp_dir = TempDir::new().unwrap();
+    let existing = temp_dir.path().join("topics").join("async.md");
+    std::fs::create_dir_all(existing.parent().unwrap()).unwrap();
+    std::fs::write(&existing, "# Async\n\nSource: https://example.com/async\n").unwrap();
+
+    let path = create_note_in(temp_dir.path(), &article()).unwrap();
+    assert_eq!(path, existing.to_string_lossy());
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+
+    // Another article still gets a note of its own
+    let mut other = article();
+    other.link = "https://example.com/pin".to_string();
+    assert_ne!(create_note_in(temp_dir.path(), &other).unwrap(), path);
+}
+
+#[test]
+fn test_article_copy_in_notes() {
+    let mut article = article();
+    article.content = Some("<p>First paragraph.</p><ul><li>A point.</li></ul>".to_string());
+    article.filepath = Some("/data/articles/example/post-1.md".to_string());
+    let context = |copy: &str| {
+        let mut context = note_context(&article, &[], None);
+        context.insert("article_copy", copy);
+        context
+    };
+
+    let note = render(DEFAULT_TEMPLATE, "", &context("none")).unwrap();
+    assert!(!note.contains("<details>") && !note.contains("Cached copy"));
+
+    let note = render(DEFAULT_TEMPLATE, "", &context("embed")).unwrap();
+    assert!(note.contains("<details>\n<summary>Article</summary>\n\nFirst paragraph.\n\n* A point.\n\n</details>\n\n## Notes"));
+
+    let note = render(DEFAULT_TEMPLATE, "", &context("link")).unwrap();
+    assert!(note
+        .contains("Cached copy: [cached article](/data/articles/example/post-1.md)\n\n## Notes"));
+
+    let note = render(OBSIDIAN_TEMPLATE, "", &context("embed")).unwrap();
+    assert!(note.contains("> [!abstract]- Article\n> First paragraph.\n>\n> * A point.\n"));
+
+    // Org bodies are indented so list items don't turn into headings
+    let note = render(ORG_TEMPLATE, "", &context("embed")).unwrap();
+    assert!(note
+        .contains(":VISIBILITY: folded\n:END:\n\n  First paragraph.\n\n  * A point.\n\n** Notes"));
+    let note = render(ORG_TEMPLATE, "", &context("link")).unwrap();
+    assert!(note
+        .contains("Cached copy: [[file:/data/articles/example/post-1.md][Async Rust: A Tour!]]\n"));
+}
+
+#[test]
+fn test_export_highlights() {
+    let temp_dir = TempDir::new().unwrap();
+    let highlight = |text: &str| Highlight {
+        text: text.to_string(),
+        created: Utc::now(),
+    };
+    let path = export_highlights(
+        temp_dir.path(),
+        &article(),
+        &[highlight("Futures are lazy."), highlight("Poll\nthem.")],
+    )
+    .unwrap();
+    let note = std::fs::read_to_string(&path).unwrap();
+    assert!(note.contains("## Highlights\n\n> Futures are lazy.\n\n> Poll\n> them.\n\n## Notes\n"));
+
+    // Exporting again replaces the section instead of repeating it
+    export_highlights(temp_dir.path(), &article(), &[highlight("Only this.")]).unwrap();
+    let note = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(note.matches("## Highlights").count(), 1);
+    assert!(note.contains("## Highlights\n\n> Only this.\n\n## Notes\n"));
+    assert!(!note.contains("lazy"));
+}
+
+#[test]
+fn test_replace_section() {
+    assert_eq!(
+        replace_section(
+            "# T\n\n## Highlights\n\n> old\n\n### Sub\n> older\n## Notes\n\nmine\n",
+            "## Highlights",
+            "## Notes",
+            "> new\n"
+        ),
+        "# T\n\n## Highlights\n\n> new\n\n## Notes\n\nmine\n"
+    );
+    assert_eq!(
+        replace_section("# T\n\nText.\n", "## Highlights", "## Notes", "> new\n"),
+        "# T\n\nText.\n\n## Highlights\n\n> new\n"
+    );
+}
+
+#[test]
+fn test_citekey_in_notes() {
+    let mut context = note_context(&article(), &[], None);
+    assert!(!render(DEFAULT_TEMPLATE, "", &context)
+        .unwrap()
+        .contains("Citation:"));
+    context.insert("citekey", "example2024async");
+    assert!(render(DEFAULT_TEMPLATE, "", &context)
+        .unwrap()
+        .contains("Citation: [@example2024async]\n"));
+    assert!(render(OBSIDIAN_TEMPLATE, "", &context)
+        .unwrap()
+        .contains("citekey: \"example2024async\"\n"));
+    assert!(render(ORG_TEMPLATE, "", &context)
+        .unwrap()
+        .contains(":CITEKEY: example2024async\n"));
+}
+
+#[test]
+fn test_related_notes_in_notes() {
+    let mut context = note_context(&article(), &[], None);
+    let related: Vec<RelatedLink> = vec![crate::related::RelatedNote {
+        path: "/zet/async rust.md".to_string(),
+        title: "Async Rust".to_string(),
+        score: 0.5,
+    }
+    .into()];
+    context.insert("related", &related);
+    assert!(render(DEFAULT_TEMPLATE, "", &context)
+        .unwrap()
+        .contains("## Related\n\n- [Async Rust](</zet/async rust.md>)\n\n## Notes"));
+    assert!(render(OBSIDIAN_TEMPLATE, "", &context)
+        .unwrap()
+        .contains("## Related\n\n- [[async rust]]\n\n## Notes"));
+    assert!(render(ORG_TEMPLATE, "", &context)
+        .unwrap()
+        .contains("** Related\n\n- [[file:/zet/async rust.md][Async Rust]]\n\n** Notes"));
+}
+
+#[test]
+fn test_note_fields() {
+    let fields: std::collections::BTreeMap<String, String> = [
+        ("status", "inbox"),
+        ("project", "{{ feed_host }}"),
+        ("author", "{{ author }}"),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value.to_string()))
+    .collect();
+    let note = |template: &str| {
+        let mut context = note_context(&article(), &[], None);
+        insert_fields(&mut context, &fields).unwrap();
+        render(template, "", &context).unwrap()
+    };
+
+    // Fields come in name order, and those rendering empty are left out
+    assert!(note(DEFAULT_TEMPLATE).contains("project: example.com\nstatus: inbox\n\n## Summary"));
+    assert!(
+        note(OBSIDIAN_TEMPLATE).contains("project: \"example.com\"\nstatus: \"inbox\"\ntags: []")
+    );
+    assert!(note(ORG_TEMPLATE).contains(":PROJECT: example.com\n:STATUS: inbox\n:END:"));
+
+    let mut context = note_context(&article(), &[], None);
+    let broken = [("status".to_string(), "{{ nope".to_string())].into();
+    assert!(insert_fields(&mut context, &broken).is_err());
+}
+
+#[test]
+fn test_note_tags() {
+    let mut article = article();
+    article.tags = vec![
+        "Rust Lang".to_string(),
+        "async".to_string(),
+        "rust-lang".to_string(),
+        " ".to_string(),
+    ];
+    let note = render_note(DEFAULT_TEMPLATE, &article, &[]).unwrap();
+    assert!(note.contains("Tags: #rust-lang #async\n"));
+    let note = render_note(OBSIDIAN_TEMPLATE, &article, &[]).unwrap();
+    assert!(note.contains("tags: [\"rust-lang\", \"async\"]\n"));
+    let note = render_note(ORG_TEMPLATE, &article, &[]).unwrap();
+    assert!(note.contains("#+FILETAGS: :rust_lang:async:\n"));
+
+    let note = render_note(DEFAULT_TEMPLATE, &self::article(), &[]).unwrap();
+    assert!(!note.contains("Tags:"));
+}
+
+#[test]
+fn test_highlights_in_notes() {
+    let mut context = note_context(&article(), &["Picked.".to_string()], None);
+    let highlights = vec![Highlight {
+        text: "First line.\nSecond line.".to_string(),
+        created: Utc::now(),
+    }];
+    context.insert("highlights", &highlights);
+    let note = render(DEFAULT_TEMPLATE, "", &context).unwrap();
+    assert!(
+        note.contains("## Highlights\n\n> First line.\n> Second line.\n\n## Notes\n\n> Picked.\n")
+    );
+    // A later export replaces the section instead of adding another
+    let exported = replace_section(&note, "## Highlights", "## Notes", "> Other.\n");
+    assert_eq!(exported.matches("## Highlights").count(), 1);
+    assert!(exported.contains("## Highlights\n\n> Other.\n\n## Notes"));
+
+    assert!(render(OBSIDIAN_TEMPLATE, "", &context)
+        .unwrap()
+        .contains("## Highlights\n\n> First line.\n> Second line.\n\n## Notes"));
+    assert!(render(ORG_TEMPLATE, "", &context).unwrap().contains(
+        "** Highlights\n\n#+BEGIN_QUOTE\nFirst line.\nSecond line.\n#+END_QUOTE\n\n** Notes"
+    ));
+
+    // Without highlights there's no section
+    let note = render(DEFAULT_TEMPLATE, "", &note_context(&article(), &[], None)).unwrap();
+    assert!(!note.contains("## Highlights"));
+}
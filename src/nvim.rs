@@ -0,0 +1,219 @@
+//! Neovim remote plugin over msgpack-RPC
+//!
+//! `zetrss nvim` connects to the Neovim at `$NVIM` (set for its jobs and
+//! terminals), registers one Lua function per `rpc` method in the global
+//! `ZetRss` table, e.g. `ZetRss.mark_read({ id = "..." })`, and answers the
+//! `rpcrequest`s those functions send. Results arrive as Lua tables, without
+//! a JSON round trip or a terminal buffer. The process exits when Neovim
+//! closes the channel.
+
+use crate::cache::ArticleStore;
+use crate::fetcher::FeedFetcher;
+use crate::rpc::{self, RpcServer};
+use anyhow::{anyhow, bail, Context, Result};
+use rmpv::Value;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::os::unix::net::UnixStream;
+
+#[cfg(test)]
+#[path = "nvim_tests.rs"]
+mod tests;
+
+const REQUEST: u64 = 0;
+const RESPONSE: u64 = 1;
+const NOTIFICATION: u64 = 2;
+
+/// Defines the `ZetRss` functions; run with the channel id and method names
+const REGISTER: &str = r#"
+local channel, methods = ...
+local remote = { channel = channel }
+for _, method in ipairs(methods) do
+  remote[method:gsub("-", "_")] = function(params)
+    return vim.rpcrequest(channel, method, params or vim.empty_dict())
+  end
+end
+_G.ZetRss = remote
+vim.api.nvim_exec_autocmds("User", { pattern = "ZetRssAttached", modeline = false })
+"#;
+
+/// A msgpack-RPC channel to Neovim
+struct Session<R, W, S, F> {
+    reader: R,
+    writer: W,
+    server: RpcServer<S, F>,
+    next_id: u64,
+}
+
+impl<R: Read, W: Write, S: ArticleStore, F: FeedFetcher> Session<R, W, S, F> {
+    fn send(&mut self, message: Value) -> Result<()> {
+        rmpv::encode::write_value(&mut self.writer, &message)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// The next message, or None once Neovim hangs up
+    fn receive(&mut self) -> Result<Option<Vec<Value>>> {
+        match rmpv::decode::read_value(&mut self.reader) {
+            Ok(Value::Array(message)) => Ok(Some(message)),
+            Ok(other) => bail!("Unexpected message from Neovim: {}", other),
+            Err(rmpv::decode::Error::InvalidMarkerRead(e))
+                if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e).context("Failed to read from Neovim"),
+        }
+    }
+
+    /// Calls a Neovim API function and returns its result, answering
+    /// Neovim's requests that arrive meanwhile
+    fn request(&mut self, method: &str, args: Vec<Value>) -> Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(Value::Array(vec![
+            REQUEST.into(),
+            id.into(),
+            method.into(),
+            Value::Array(args),
+        ]))?;
+        loop {
+            let message = self
+                .receive()?
+                .ok_or_else(|| anyhow!("Neovim closed the channel during {}", method))?;
+            if message.first().and_then(Value::as_u64) == Some(RESPONSE)
+                && message.get(1).and_then(Value::as_u64) == Some(id)
+            {
+                let error = message.get(2).cloned().unwrap_or(Value::Nil);
+                if !error.is_nil() {
+                    bail!("{} failed: {}", method, error_message(&error));
+                }
+                return Ok(message.get(3).cloned().unwrap_or(Value::Nil));
+            }
+            self.handle(message)?;
+        }
+    }
+
+    /// Answers a request from Neovim; notifications run without an answer
+    fn handle(&mut self, message: Vec<Value>) -> Result<()> {
+        let kind = message.first().and_then(Value::as_u64);
+        let (id, method, args) = match (kind, message.as_slice()) {
+            (Some(REQUEST), [_, id, method, args]) => (Some(id.clone()), method, args),
+            (Some(NOTIFICATION), [_, method, args]) => (None, method, args),
+            // Responses to requests we gave up on
+            _ => return Ok(()),
+        };
+        let method = method.as_str().unwrap_or_default().to_string();
+        let params = args
+            .as_array()
+            .and_then(|args| args.first())
+            .map(to_json)
+            .unwrap_or(serde_json::Value::Null);
+        let result = self.server.call(&method, params);
+        let Some(id) = id else { return Ok(()) };
+        let (error, result) = match result {
+            Ok(result) => (Value::Nil, from_json(&result)),
+            Err(error) => (Value::from(error.message), Value::Nil),
+        };
+        self.send(Value::Array(vec![RESPONSE.into(), id, error, result]))
+    }
+}
+
+/// Connects to the Neovim listening at `address`, registers the `ZetRss`
+/// functions and answers their calls until Neovim closes the channel
+pub fn attach<S: ArticleStore, F: FeedFetcher>(
+    address: &str,
+    server: RpcServer<S, F>,
+) -> Result<()> {
+    let stream = UnixStream::connect(address)
+        .with_context(|| format!("Failed to connect to Neovim at {}", address))?;
+    let mut session = Session {
+        reader: BufReader::new(stream.try_clone()?),
+        writer: BufWriter::new(stream),
+        server,
+        next_id: 0,
+    };
+
+    let info = session.request("nvim_get_api_info", Vec::new())?;
+    let channel = info
+        .as_array()
+        .and_then(|info| info.first())
+        .and_then(Value::as_u64)
+        .ok_or_else(|| anyhow!("Neovim didn't tell the channel id"))?;
+    let methods = rpc::METHODS.iter().map(|&method| method.into()).collect();
+    session.request(
+        "nvim_exec_lua",
+        vec![
+            REGISTER.into(),
+            Value::Array(vec![channel.into(), Value::Array(methods)]),
+        ],
+    )?;
+
+    while let Some(message) = session.receive()? {
+        session.handle(message)?;
+    }
+    Ok(())
+}
+
+/// The message of an error Neovim sent: a string or `[type, message]`
+fn error_message(error: &Value) -> String {
+    match error {
+        Value::Array(parts) if parts.len() == 2 => parts[1].to_string(),
+        other => other.to_string(),
+    }
+    .trim_matches('"')
+    .to_string()
+}
+
+/// A msgpack value as JSON; binary strings are read as UTF-8 and
+/// extension types (Neovim's buffer and window handles) become null
+fn to_json(value: &Value) -> serde_json::Value {
+    use serde_json::Value as Json;
+    match value {
+        Value::Nil | Value::Ext(..) => Json::Null,
+        Value::Boolean(b) => Json::Bool(*b),
+        Value::Integer(i) => match (i.as_i64(), i.as_u64()) {
+            (Some(i), _) => Json::from(i),
+            (None, Some(u)) => Json::from(u),
+            (None, None) => Json::Null,
+        },
+        Value::F32(f) => Json::from(*f as f64),
+        Value::F64(f) => Json::from(*f),
+        Value::String(s) => Json::String(s.as_str().unwrap_or_default().to_string()),
+        Value::Binary(bytes) => Json::String(String::from_utf8_lossy(bytes).into_owned()),
+        Value::Array(items) => Json::Array(items.iter().map(to_json).collect()),
+        Value::Map(entries) => Json::Object(
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key.as_str() {
+                        Some(key) => key.to_string(),
+                        None => key.to_string(),
+                    };
+                    (key, to_json(value))
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// A JSON value as msgpack
+fn from_json(value: &serde_json::Value) -> Value {
+    use serde_json::Value as Json;
+    match value {
+        Json::Null => Value::Nil,
+        Json::Bool(b) => Value::Boolean(*b),
+        Json::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => Value::from(i),
+            (None, Some(u)) => Value::from(u),
+            (None, None) => Value::F64(n.as_f64().unwrap_or_default()),
+        },
+        Json::String(s) => Value::from(s.as_str()),
+        Json::Array(items) => Value::Array(items.iter().map(from_json).collect()),
+        Json::Object(entries) => Value::Map(
+            entries
+                .iter()
+                .map(|(key, value)| (Value::from(key.as_str()), from_json(value)))
+                .collect(),
+        ),
+    }
+}
@@ -0,0 +1,110 @@
+use super::*;
+use crate::cache::TextCache;
+use crate::engine::ZetRss;
+use crate::fetcher::HttpFetcher;
+use std::os::unix::net::UnixListener;
+use tempfile::TempDir;
+
+fn read(stream: &mut UnixStream) -> Vec<Value> {
+    match rmpv::decode::read_value(stream).unwrap() {
+        Value::Array(message) => message,
+        other => panic!("not a message: {}", other),
+    }
+}
+
+fn write(stream: &mut UnixStream, message: Vec<Value>) {
+    rmpv::encode::write_value(stream, &Value::Array(message)).unwrap();
+}
+
+#[test]
+fn test_attach_registers_and_answers() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("nvim.sock");
+    let listener = UnixListener::bind(&path).unwrap();
+    let cache = TextCache::with_base_dir(temp_dir.path().join("data")).unwrap();
+    let server = RpcServer::new(ZetRss::with_parts(cache, HttpFetcher));
+    let address = path.to_string_lossy().into_owned();
+    let plugin = std::thread::spawn(move || attach(&address, server));
+
+    // Plays Neovim: tells the channel id, runs the registration, then calls
+    let (mut nvim, _) = listener.accept().unwrap();
+    let info = read(&mut nvim);
+    assert_eq!(info[2].as_str(), Some("nvim_get_api_info"));
+    write(
+        &mut nvim,
+        vec![
+            RESPONSE.into(),
+            info[1].clone(),
+            Value::Nil,
+            Value::Array(vec![7.into(), Value::Map(Vec::new())]),
+        ],
+    );
+
+    let register = read(&mut nvim);
+    assert_eq!(register[2].as_str(), Some("nvim_exec_lua"));
+    let args = register[3].as_array().unwrap()[1].as_array().unwrap();
+    assert_eq!(args[0].as_u64(), Some(7));
+    let methods: Vec<&str> = args[1]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(Value::as_str)
+        .collect();
+    assert_eq!(methods, rpc::METHODS);
+    write(
+        &mut nvim,
+        vec![RESPONSE.into(), register[1].clone(), Value::Nil, Value::Nil],
+    );
+
+    write(
+        &mut nvim,
+        vec![
+            REQUEST.into(),
+            1.into(),
+            "unread".into(),
+            Value::Array(vec![Value::Map(Vec::new())]),
+        ],
+    );
+    let answer = read(&mut nvim);
+    assert_eq!(answer[1].as_u64(), Some(1));
+    assert!(answer[2].is_nil());
+    assert_eq!(
+        to_json(&answer[3]),
+        serde_json::json!({ "unread": 0, "total": 0 })
+    );
+
+    // Failures come back as the error member
+    let params = Value::Map(vec![("id".into(), "nope".into())]);
+    write(
+        &mut nvim,
+        vec![
+            REQUEST.into(),
+            2.into(),
+            "get".into(),
+            Value::Array(vec![params]),
+        ],
+    );
+    let answer = read(&mut nvim);
+    assert!(answer[2]
+        .as_str()
+        .unwrap()
+        .contains("Article not found: nope"));
+    assert!(answer[3].is_nil());
+
+    // Closing the channel ends the plugin
+    drop(nvim);
+    plugin.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_value_conversion() {
+    let json = serde_json::json!({
+        "id": "a",
+        "count": 3,
+        "score": 0.5,
+        "tags": ["x", "y"],
+        "read": false,
+        "author": null,
+    });
+    assert_eq!(to_json(&from_json(&json)), json);
+}
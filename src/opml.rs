@@ -0,0 +1,77 @@
+//! OPML subscription lists
+//!
+//! Feed readers export their subscriptions as OPML: nested `<outline>`
+//! elements, where outlines with an `xmlUrl` are feeds and the others are
+//! folders. `.opml` files inside the zet are scanned as feed sources.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+#[cfg(test)]
+#[path = "opml_tests.rs"]
+mod tests;
+
+/// A feed listed in an OPML file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpmlFeed {
+    pub url: String,
+    /// The outline's `title`, else its `text`
+    pub title: Option<String>,
+    /// Names of the folders the feed is nested in, outermost first
+    pub folders: Vec<String>,
+    /// 1-indexed line of the feed's outline
+    pub line_number: usize,
+}
+
+/// Whether the file is an OPML subscription list
+pub fn is_opml(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("opml"))
+}
+
+/// The feeds of an OPML document, in document order
+pub fn parse(content: &str) -> Result<Vec<OpmlFeed>> {
+    let doc = roxmltree::Document::parse(content).context("Invalid OPML")?;
+    let body = doc
+        .root_element()
+        .children()
+        .find(|n| n.has_tag_name("body"))
+        .context("OPML has no <body>")?;
+    let mut feeds = Vec::new();
+    collect(&doc, body, &mut Vec::new(), &mut feeds);
+    Ok(feeds)
+}
+
+fn collect(
+    doc: &roxmltree::Document,
+    parent: roxmltree::Node,
+    folders: &mut Vec<String>,
+    feeds: &mut Vec<OpmlFeed>,
+) {
+    for outline in parent.children().filter(|n| n.has_tag_name("outline")) {
+        let name = ["title", "text"]
+            .iter()
+            .filter_map(|attr| outline.attribute(*attr))
+            .map(str::trim)
+            .find(|name| !name.is_empty());
+        match outline.attribute("xmlUrl").map(str::trim) {
+            Some(url) if !url.is_empty() => feeds.push(OpmlFeed {
+                url: url.to_string(),
+                title: name.map(String::from),
+                folders: folders.clone(),
+                line_number: doc.text_pos_at(outline.range().start).row as usize,
+            }),
+            Some(_) => {}
+            None => {
+                // Unnamed folders group feeds without tagging them
+                let named = name.is_some();
+                folders.extend(name.map(String::from));
+                collect(doc, outline, folders, feeds);
+                if named {
+                    folders.pop();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use super::*;
+
+const EXPORT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+  <head><title>Subscriptions</title></head>
+  <body>
+    <outline text="Rust Blog" type="rss" xmlUrl="https://blog.rust-lang.org/feed.xml"/>
+    <outline text="Tech">
+      <outline title="LWN" text="lwn" xmlUrl=" https://lwn.net/headlines/rss "/>
+      <outline text="AI &amp; ML">
+        <outline text="" xmlUrl="https://example.com/ai.rss"/>
+      </outline>
+      <outline text="Broken" xmlUrl=""/>
+    </outline>
+    <outline>
+      <outline text="Loose" xmlUrl="https://loose.example.com/rss"/>
+    </outline>
+  </body>
+</opml>
+"#;
+
+#[test]
+fn test_parse_nests_feeds_in_folders() {
+    let feeds = parse(EXPORT).unwrap();
+    let found: Vec<(&str, Option<&str>, Vec<&str>, usize)> = feeds
+        .iter()
+        .map(|f| {
+            (
+                f.url.as_str(),
+                f.title.as_deref(),
+                f.folders.iter().map(String::as_str).collect(),
+                f.line_number,
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (
+                "https://blog.rust-lang.org/feed.xml",
+                Some("Rust Blog"),
+                vec![],
+                5
+            ),
+            (
+                "https://lwn.net/headlines/rss",
+                Some("LWN"),
+                vec!["Tech"],
+                7
+            ),
+            (
+                "https://example.com/ai.rss",
+                None,
+                vec!["Tech", "AI & ML"],
+                9
+            ),
+            ("https://loose.example.com/rss", Some("Loose"), vec![], 14),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_rejects_non_opml() {
+    assert!(parse("<opml><head/></opml>").is_err());
+    assert!(parse("not xml").is_err());
+    assert!(is_opml(Path::new("subs.OPML")));
+    assert!(!is_opml(Path::new("subs.xml")));
+}
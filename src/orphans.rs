@@ -0,0 +1,116 @@
+//! Feeds that disappeared from the zet
+//!
+//! A rescan that no longer finds a feed drops it from the feed list, but its
+//! metadata, fetch history and articles stay in the cache. Such feeds are
+//! remembered as orphans in `state/orphans.json` until they're cleaned up
+//! (`zetrss orphans --remove`, or after every complete scan with
+//! `[scan] orphans`) or turn up in the zet again. Feeds added without a note
+//! are never orphaned, and articles are only deleted on request, starred
+//! ones never.
+
+use crate::cache::TextCache;
+use crate::scanner::FeedSource;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+#[cfg(test)]
+#[path = "orphans_tests.rs"]
+mod tests;
+
+/// Name of the orphan list in the state directory
+pub const ORPHANS_FILE: &str = "orphans.json";
+
+/// A feed no longer declared in the zet whose data is still cached
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Orphan {
+    pub url: String,
+    /// Where the feed was last declared
+    pub source_file: String,
+    pub line_number: usize,
+    /// When a scan first missed it
+    pub since: DateTime<Utc>,
+}
+
+/// What happens to orphaned feeds after a scan (`[scan] orphans`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrphanAction {
+    /// Remember them and say so; `zetrss orphans --remove` cleans up
+    #[default]
+    Keep,
+    /// Delete their feed metadata and fetch history; "delete" is read the
+    /// same, since only `zetrss orphans --remove --delete-articles` deletes
+    /// articles
+    #[serde(alias = "delete")]
+    Remove,
+}
+
+/// What `clean_up` deleted
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Cleanup {
+    pub feeds: Vec<String>,
+    pub articles: usize,
+}
+
+/// The orphans recorded so far
+pub fn load(cache: &TextCache) -> Result<Vec<Orphan>> {
+    let path = cache.state_path(ORPHANS_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn save(cache: &TextCache, orphans: &[Orphan]) -> Result<()> {
+    let path = cache.state_path(ORPHANS_FILE);
+    fs::write(&path, serde_json::to_string_pretty(orphans)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Records the `gone` feeds as orphans and forgets orphans that are `found`
+/// again; returns every orphan
+pub fn track(
+    cache: &TextCache,
+    gone: &[FeedSource],
+    found: &[FeedSource],
+    now: DateTime<Utc>,
+) -> Result<Vec<Orphan>> {
+    let mut orphans = load(cache)?;
+    let before = orphans.len();
+    orphans.retain(|orphan| !found.iter().any(|f| f.url == orphan.url));
+    let mut changed = orphans.len() != before;
+    for feed in gone {
+        if !orphans.iter().any(|o| o.url == feed.url) {
+            orphans.push(Orphan {
+                url: feed.url.clone(),
+                source_file: feed.source_file.clone(),
+                line_number: feed.line_number,
+                since: now,
+            });
+            changed = true;
+        }
+    }
+    if changed {
+        save(cache, &orphans)?;
+    }
+    Ok(orphans)
+}
+
+/// Deletes every orphan's feed metadata, plus its unstarred cached articles
+/// with `delete_articles`, and forgets the orphans
+pub fn clean_up(cache: &TextCache, delete_articles: bool) -> Result<Cleanup> {
+    let mut cleanup = Cleanup::default();
+    for orphan in load(cache)? {
+        cache.remove_feed_meta(&orphan.url)?;
+        if delete_articles {
+            cleanup.articles += cache.delete_feed_articles(&orphan.url)?;
+        }
+        cleanup.feeds.push(orphan.url);
+    }
+    save(cache, &[])?;
+    Ok(cleanup)
+}
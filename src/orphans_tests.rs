@@ -0,0 +1,120 @@
+use super::*;
+use crate::models::{Feed, FeedItem};
+use tempfile::TempDir;
+
+fn source(url: &str) -> FeedSource {
+    FeedSource {
+        url: url.to_string(),
+        source_file: "reading.md".to_string(),
+        line_number: 3,
+        root: None,
+        options: Default::default(),
+    }
+}
+
+fn feed(url: &str) -> Feed {
+    Feed {
+        url: url.to_string(),
+        title: "Feed".to_string(),
+        description: None,
+        last_fetched: Some(Utc::now()),
+        bytes: 0,
+        items: vec![FeedItem {
+            id: format!("{}#1", url),
+            feed_url: url.to_string(),
+            title: "Item".to_string(),
+            link: format!("{}/1", url),
+            published: Some(Utc::now()),
+            content: Some("Body".to_string()),
+            ..Default::default()
+        }],
+    }
+}
+
+#[test]
+fn test_track_records_and_forgets_orphans() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    let now = Utc::now();
+    let a = source("https://a.example.com/rss");
+    let b = source("https://b.example.com/rss");
+
+    let orphans = track(&cache, &[a.clone(), b.clone()], &[], now).unwrap();
+    assert_eq!(orphans.len(), 2);
+    assert_eq!(orphans[0].source_file, "reading.md");
+
+    // Missing again later keeps the first date
+    let later = now + chrono::Duration::days(1);
+    let orphans = track(&cache, std::slice::from_ref(&a), &[], later).unwrap();
+    assert_eq!(orphans.len(), 2);
+    assert_eq!(orphans[0].since, now);
+
+    // A feed back in the zet stops being an orphan
+    let orphans = track(&cache, &[], &[a], now).unwrap();
+    let urls: Vec<&str> = orphans.iter().map(|o| o.url.as_str()).collect();
+    assert_eq!(urls, vec!["https://b.example.com/rss"]);
+    assert_eq!(load(&cache).unwrap().len(), 1);
+}
+
+#[test]
+fn test_clean_up_removes_metadata_and_optionally_articles() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    let url = "https://gone.example.com/rss";
+    cache.store_feed(&feed(url)).unwrap();
+    assert_eq!(cache.feed_title(url).as_deref(), Some("Feed"));
+
+    track(&cache, &[source(url)], &[], Utc::now()).unwrap();
+    let cleanup = clean_up(&cache, false).unwrap();
+    assert_eq!(cleanup.feeds, vec![url]);
+    assert_eq!(cleanup.articles, 0);
+    assert_eq!(cache.feed_title(url), None);
+    assert!(load(&cache).unwrap().is_empty());
+    assert!(cache
+        .get_article_by_id(&format!("{}#1", url))
+        .unwrap()
+        .is_some());
+
+    track(&cache, &[source(url)], &[], Utc::now()).unwrap();
+    assert_eq!(clean_up(&cache, true).unwrap().articles, 1);
+    assert!(cache
+        .get_article_by_id(&format!("{}#1", url))
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_clean_up_keeps_starred_articles() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    let url = "https://gone.example.com/rss";
+    let mut feed = feed(url);
+    let mut second = feed.items[0].clone();
+    second.id = format!("{}#2", url);
+    second.link = format!("{}/2", url);
+    feed.items.push(second);
+    cache.store_feed(&feed).unwrap();
+    cache.toggle_star(&format!("{}#2", url)).unwrap();
+
+    track(&cache, &[source(url)], &[], Utc::now()).unwrap();
+    assert_eq!(clean_up(&cache, true).unwrap().articles, 1);
+    assert!(cache
+        .get_article_by_id(&format!("{}#1", url))
+        .unwrap()
+        .is_none());
+    let kept = cache
+        .get_article_by_id(&format!("{}#2", url))
+        .unwrap()
+        .unwrap();
+    assert!(kept.starred);
+}
+
+#[test]
+fn test_delete_action_only_removes_metadata() {
+    #[derive(Deserialize)]
+    struct Scan {
+        orphans: OrphanAction,
+    }
+    let scan: Scan = toml::from_str("orphans = \"delete\"").unwrap();
+    assert_eq!(scan.orphans, OrphanAction::Remove);
+}
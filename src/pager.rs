@@ -0,0 +1,49 @@
+//! Handing rendered articles to an external pager
+//!
+//! `$PAGER` is run through the shell, so it may carry options
+//! (`PAGER="less -RS"`). Without it `less -R`, `bat` and `more` are tried in
+//! that order.
+
+use anyhow::{bail, Context, Result};
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+#[cfg(test)]
+#[path = "pager_tests.rs"]
+mod tests;
+
+/// Pagers tried when `$PAGER` isn't set
+const FALLBACKS: &[&[&str]] = &[
+    &["less", "-R"],
+    &["bat", "--paging=always", "--style=plain"],
+    &["more"],
+];
+
+/// Pipes `text` to the pager and waits for it to exit
+pub fn page(text: &str) -> Result<()> {
+    if let Some(pager) = std::env::var("PAGER").ok().filter(|p| !p.trim().is_empty()) {
+        return feed(Command::new("sh").arg("-c").arg(&pager), text)
+            .with_context(|| format!("Failed to run pager '{}'", pager));
+    }
+    for args in FALLBACKS {
+        match feed(Command::new(args[0]).args(&args[1..]), text) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            res => return res.with_context(|| format!("Failed to run pager '{}'", args[0])),
+        }
+    }
+    bail!("No pager found; set $PAGER")
+}
+
+/// Starts `command` with `text` on its stdin and waits for it
+fn feed(command: &mut Command, text: &str) -> io::Result<()> {
+    let mut child = command.stdin(Stdio::piped()).spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Quitting the pager before the end closes the pipe; that's fine
+        match stdin.write_all(text.as_bytes()) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(e),
+            _ => {}
+        }
+    }
+    child.wait()?;
+    Ok(())
+}
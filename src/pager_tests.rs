@@ -0,0 +1,28 @@
+use super::*;
+use tempfile::TempDir;
+
+#[test]
+fn test_feed_pipes_text_to_command() {
+    let temp_dir = TempDir::new().unwrap();
+    let out = temp_dir.path().join("paged.txt");
+    feed(
+        Command::new("sh")
+            .arg("-c")
+            .arg(format!("cat > '{}'", out.display())),
+        "Title\n\nBody\n",
+    )
+    .unwrap();
+    assert_eq!(std::fs::read_to_string(out).unwrap(), "Title\n\nBody\n");
+}
+
+#[test]
+fn test_pager_quitting_early_is_not_an_error() {
+    let text = "line\n".repeat(100_000);
+    feed(Command::new("sh").arg("-c").arg("exit 0"), &text).unwrap();
+}
+
+#[test]
+fn test_missing_command_is_not_found() {
+    let err = feed(&mut Command::new("zetrss-no-such-pager"), "x").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+}
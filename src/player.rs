@@ -0,0 +1,116 @@
+//! Audio and video enclosures, played with an external player
+//!
+//! RSS `<enclosure>`s and Media RSS objects arrive as the raw entry's `media`,
+//! Atom `rel="enclosure"` links as its `links`. The player (`viewer.player`,
+//! mpv by default) gets the enclosure URL and streams it.
+
+use crate::models::RawEntry;
+use anyhow::{bail, Context, Result};
+use std::process::Command;
+
+#[cfg(test)]
+#[path = "player_tests.rs"]
+mod tests;
+
+/// File extensions of audio and video enclosures whose type isn't given
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "mp3", "m4a", "aac", "ogg", "oga", "opus", "flac", "wav", "mp4", "m4v", "mov", "webm", "mkv",
+];
+
+/// A playable attachment of an article
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enclosure {
+    pub url: String,
+    pub media_type: Option<String>,
+    /// Size in bytes, as announced by the feed
+    pub length: Option<u64>,
+    pub duration_secs: Option<u64>,
+}
+
+impl Enclosure {
+    pub fn is_video(&self) -> bool {
+        match &self.media_type {
+            Some(media_type) => media_type.starts_with("video/"),
+            None => matches!(
+                extension(&self.url),
+                Some("mp4" | "m4v" | "mov" | "webm" | "mkv")
+            ),
+        }
+    }
+
+    /// Header text, e.g. "Audio · 42:10 · 38.2 MB"
+    pub fn describe(&self) -> String {
+        let mut parts = vec![if self.is_video() { "Video" } else { "Audio" }.to_string()];
+        if let Some(secs) = self.duration_secs.filter(|s| *s > 0) {
+            parts.push(if secs >= 3600 {
+                format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+            } else {
+                format!("{}:{:02}", secs / 60, secs % 60)
+            });
+        }
+        if let Some(bytes) = self.length.filter(|b| *b > 0) {
+            parts.push(format!("{:.1} MB", bytes as f64 / 1_000_000.0));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// The audio and video enclosures of a feed entry, without duplicates
+pub fn enclosures(raw: &RawEntry) -> Vec<Enclosure> {
+    let media = raw.media.iter().flat_map(|m| &m.content).filter_map(|c| {
+        Some(Enclosure {
+            url: c.url.clone()?,
+            media_type: c.content_type.clone(),
+            length: c.size,
+            duration_secs: c.duration_secs,
+        })
+    });
+    let links = raw
+        .links
+        .iter()
+        .filter(|l| l.rel.as_deref() == Some("enclosure"))
+        .map(|l| Enclosure {
+            url: l.href.clone(),
+            media_type: l.media_type.clone(),
+            length: l.length,
+            duration_secs: None,
+        });
+
+    let mut found: Vec<Enclosure> = Vec::new();
+    for enclosure in media.chain(links) {
+        if is_playable(&enclosure) && !found.iter().any(|e| e.url == enclosure.url) {
+            found.push(enclosure);
+        }
+    }
+    found
+}
+
+fn is_playable(enclosure: &Enclosure) -> bool {
+    match &enclosure.media_type {
+        Some(media_type) => media_type.starts_with("audio/") || media_type.starts_with("video/"),
+        None => extension(&enclosure.url).is_some_and(|ext| MEDIA_EXTENSIONS.contains(&ext)),
+    }
+}
+
+/// Lowercase extension of the URL's path, ignoring query and fragment
+fn extension(url: &str) -> Option<&'static str> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let ext = path.rsplit_once('.')?.1.to_ascii_lowercase();
+    MEDIA_EXTENSIONS.iter().copied().find(|known| *known == ext)
+}
+
+/// Runs `player` (a shell command, e.g. "mpv --no-video") on `url` and waits
+/// for it to exit
+pub fn play(player: &str, url: &str) -> Result<()> {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", player))
+        .arg("sh")
+        .arg(url)
+        .status()
+        .with_context(|| format!("Failed to run player '{}'", player))?;
+    if !status.success() {
+        bail!("Player '{}' exited with {}", player, status);
+    }
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use super::*;
+use crate::models::{RawLink, RawMedia, RawMediaContent};
+use tempfile::TempDir;
+
+fn media(url: &str, content_type: Option<&str>) -> RawMediaContent {
+    RawMediaContent {
+        url: Some(url.to_string()),
+        content_type: content_type.map(String::from),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_enclosures_from_media_and_links() {
+    let raw = RawEntry {
+        media: vec![RawMedia {
+            content: vec![
+                RawMediaContent {
+                    size: Some(38_200_000),
+                    duration_secs: Some(2530),
+                    ..media("https://example.com/ep1.mp3", Some("audio/mpeg"))
+                },
+                media("https://example.com/cover.jpg", Some("image/jpeg")),
+                media("https://example.com/clip.webm?dl=1", None),
+            ],
+            ..Default::default()
+        }],
+        links: vec![
+            RawLink {
+                href: "https://example.com/post".to_string(),
+                rel: Some("alternate".to_string()),
+                ..Default::default()
+            },
+            // Atom feeds announce the same file as a link too
+            RawLink {
+                href: "https://example.com/ep1.mp3".to_string(),
+                rel: Some("enclosure".to_string()),
+                media_type: Some("audio/mpeg".to_string()),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    let found = enclosures(&raw);
+    let urls: Vec<&str> = found.iter().map(|e| e.url.as_str()).collect();
+    assert_eq!(
+        urls,
+        vec![
+            "https://example.com/ep1.mp3",
+            "https://example.com/clip.webm?dl=1"
+        ]
+    );
+    assert_eq!(found[0].describe(), "Audio · 42:10 · 38.2 MB");
+    assert!(found[1].is_video());
+    assert_eq!(found[1].describe(), "Video");
+}
+
+#[test]
+fn test_play_passes_url_as_one_argument() {
+    let temp_dir = TempDir::new().unwrap();
+    let out = temp_dir.path().join("played.txt");
+    let player = format!("printf '%s' > '{}'", out.display());
+    play(&player, "https://example.com/a b.mp3?x=1&y=2").unwrap();
+    assert_eq!(
+        std::fs::read_to_string(out).unwrap(),
+        "https://example.com/a b.mp3?x=1&y=2"
+    );
+
+    assert!(play("exit 3", "https://example.com/ep.mp3").is_err());
+}
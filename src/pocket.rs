@@ -0,0 +1,286 @@
+//! Sends starred articles to Pocket
+//!
+//! Pocket's v3 API saves pages to a read-it-later list given a Pocket app's
+//! consumer key and an access token the user grants that app, which
+//! `zetrss pocket --login` asks for. `zetrss pocket` sends the starred
+//! articles not sent before, and with `[pocket] on_star = true` the viewer
+//! and browser send an article as it's starred. IDs of sent articles are
+//! kept in `state/pocket.json`, so starring an article again doesn't add it
+//! twice; unstarring leaves it in Pocket.
+
+use crate::cache::TextCache;
+use crate::config::PocketConfig;
+use crate::filter::{ArticleFilter, ArticleQuery};
+use crate::models::FeedItem;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[cfg(test)]
+#[path = "pocket_tests.rs"]
+mod tests;
+
+/// Name of the sent article list in the state directory
+pub const SENT_FILE: &str = "pocket.json";
+
+/// Environment variable holding the access token
+pub const TOKEN_ENV: &str = "ZETRSS_POCKET_TOKEN";
+
+/// Page the user grants the app access on
+const AUTHORIZE_URL: &str = "https://getpocket.com/auth/authorize";
+
+/// Where Pocket sends the browser after access is granted; the CLI doesn't
+/// listen for it, so any page will do
+const REDIRECT_URI: &str = "https://getpocket.com/connected_applications";
+
+/// Articles added per `send` request
+const SEND_BATCH: usize = 100;
+
+/// Outcome of `zetrss pocket`
+#[derive(Debug, Default, Serialize)]
+pub struct ExportReport {
+    pub sent: usize,
+    /// Starred articles Pocket rejected
+    pub failed: usize,
+    /// Starred articles sent before
+    pub already_sent: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct SendReply {
+    #[serde(default)]
+    action_results: Vec<Value>,
+}
+
+/// Whether `[pocket] consumer_key` and an access token are set
+pub fn enabled() -> bool {
+    credentials(&crate::config::get().pocket).is_ok()
+}
+
+fn consumer_key(config: &PocketConfig) -> Result<&str> {
+    config
+        .consumer_key
+        .as_deref()
+        .ok_or_else(|| anyhow!("No [pocket] consumer_key configured"))
+}
+
+/// The consumer key and access token
+fn credentials(config: &PocketConfig) -> Result<(&str, String)> {
+    let consumer_key = consumer_key(config)?;
+    let access_token = std::env::var(TOKEN_ENV)
+        .ok()
+        .or_else(|| config.access_token.clone())
+        .ok_or_else(|| {
+            anyhow!(
+                "No Pocket access token: run `zetrss pocket --login`, then set ${} or [pocket] access_token",
+                TOKEN_ENV
+            )
+        })?;
+    Ok((consumer_key, access_token))
+}
+
+/// POSTs `body` to the API method at `path` and returns the JSON reply
+async fn post(config: &PocketConfig, path: &str, body: Value) -> Result<Value> {
+    let client = reqwest::Client::builder()
+        .user_agent("ZetRss/0.1")
+        .timeout(Duration::from_secs(crate::config::get().fetch.timeout_secs))
+        .build()?;
+    let url = format!("{}/{}", config.url.trim_end_matches('/'), path);
+    let response = client
+        .post(&url)
+        .header("X-Accept", "application/json")
+        .json(&body)
+        .send()
+        .await
+        .with_context(|| format!("Pocket at {} unreachable", url))?;
+    let status = response.status();
+    if !status.is_success() {
+        // Pocket explains errors in a header rather than the body
+        let reason = response
+            .headers()
+            .get("X-Error")
+            .and_then(|e| e.to_str().ok())
+            .unwrap_or("no reason given")
+            .to_string();
+        return Err(anyhow!("Pocket {} answered {}: {}", path, status, reason));
+    }
+    response
+        .json()
+        .await
+        .with_context(|| format!("Pocket {} sent invalid JSON", path))
+}
+
+/// Starts granting access: returns the request token to approve
+pub async fn request_token(config: &PocketConfig) -> Result<String> {
+    let consumer_key = consumer_key(config)?;
+    let reply = post(
+        config,
+        "oauth/request",
+        json!({ "consumer_key": consumer_key, "redirect_uri": REDIRECT_URI }),
+    )
+    .await?;
+    reply["code"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("Pocket sent no request token"))
+}
+
+/// The page on which the user approves the request token
+pub fn authorize_url(code: &str) -> String {
+    reqwest::Url::parse_with_params(
+        AUTHORIZE_URL,
+        &[("request_token", code), ("redirect_uri", REDIRECT_URI)],
+    )
+    .map(String::from)
+    .unwrap_or_else(|_| AUTHORIZE_URL.to_string())
+}
+
+/// Trades an approved request token for an access token
+pub async fn access_token(config: &PocketConfig, code: &str) -> Result<String> {
+    let consumer_key = consumer_key(config)?;
+    let reply = post(
+        config,
+        "oauth/authorize",
+        json!({ "consumer_key": consumer_key, "code": code }),
+    )
+    .await?;
+    reply["access_token"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("Pocket sent no access token; was access granted?"))
+}
+
+/// The `add` action saving an article, tagged with the configured tags and
+/// its own
+fn add_action(config: &PocketConfig, article: &FeedItem) -> Value {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in config.tags.iter().chain(&article.tags) {
+        // Tags are sent comma-separated
+        let tag = tag.replace(',', " ").trim().to_string();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    let mut action = json!({
+        "action": "add",
+        "url": article.link,
+        "title": article.title,
+        "time": chrono::Utc::now().timestamp(),
+    });
+    if !tags.is_empty() {
+        action["tags"] = json!(tags.join(","));
+    }
+    action
+}
+
+/// Adds the articles to Pocket; returns whether each was added
+pub async fn send(config: &PocketConfig, articles: &[FeedItem]) -> Result<Vec<bool>> {
+    let (consumer_key, access_token) = credentials(config)?;
+    let mut added = Vec::with_capacity(articles.len());
+    for batch in articles.chunks(SEND_BATCH) {
+        let actions: Vec<Value> = batch.iter().map(|a| add_action(config, a)).collect();
+        let reply: SendReply = serde_json::from_value(
+            post(
+                config,
+                "send",
+                json!({
+                    "consumer_key": consumer_key,
+                    "access_token": access_token,
+                    "actions": actions,
+                }),
+            )
+            .await?,
+        )
+        .context("Pocket send sent unexpected JSON")?;
+        added.extend((0..batch.len()).map(|i| {
+            // Each result is the saved item, or false
+            reply
+                .action_results
+                .get(i)
+                .is_some_and(|r| !r.is_null() && *r != Value::Bool(false))
+        }));
+    }
+    Ok(added)
+}
+
+/// Sends the starred articles not sent before
+/// Sent IDs are saved after every batch, so a failing batch doesn't cause
+/// the ones Pocket already took to be sent again
+pub async fn export_starred(cache: &TextCache, config: &PocketConfig) -> Result<ExportReport> {
+    let base_dir = cache.base_dir();
+    let mut sent = load_sent(base_dir)?;
+    let starred = cache.get_articles(&ArticleQuery {
+        filter: ArticleFilter {
+            starred: Some(true),
+            ..Default::default()
+        },
+        ..Default::default()
+    })?;
+    let (done, todo): (Vec<FeedItem>, Vec<FeedItem>) = starred
+        .into_iter()
+        .filter(|a| a.link.starts_with("http://") || a.link.starts_with("https://"))
+        .partition(|a| sent.contains(&a.id));
+
+    let mut report = ExportReport {
+        already_sent: done.len(),
+        ..Default::default()
+    };
+    for batch in todo.chunks(SEND_BATCH) {
+        for (article, added) in batch.iter().zip(send(config, batch).await?) {
+            if added {
+                sent.insert(article.id.clone());
+                report.sent += 1;
+            } else {
+                report.failed += 1;
+            }
+        }
+        save_sent(base_dir, &sent)?;
+    }
+    Ok(report)
+}
+
+/// Sends an article that was just starred when `[pocket] on_star` is set
+/// (blocking until Pocket answers); returns whether it was sent
+pub fn on_star(base_dir: &Path, article: &FeedItem) -> Result<bool> {
+    let config = &crate::config::get().pocket;
+    if !config.on_star || !enabled() {
+        return Ok(false);
+    }
+    let mut sent = load_sent(base_dir)?;
+    if sent.contains(&article.id) {
+        return Ok(false);
+    }
+    let added = crate::summarize::blocking(send(config, std::slice::from_ref(article)))?;
+    if added.first() != Some(&true) {
+        return Err(anyhow!("Pocket didn't add {}", article.link));
+    }
+    sent.insert(article.id.clone());
+    save_sent(base_dir, &sent)?;
+    Ok(true)
+}
+
+fn sent_path(base_dir: &Path) -> PathBuf {
+    base_dir.join("state").join(SENT_FILE)
+}
+
+/// IDs of the articles sent to Pocket
+pub fn load_sent(base_dir: &Path) -> Result<BTreeSet<String>> {
+    let path = sent_path(base_dir);
+    if !path.exists() {
+        return Ok(BTreeSet::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+}
+
+fn save_sent(base_dir: &Path, sent: &BTreeSet<String>) -> Result<()> {
+    let path = sent_path(base_dir);
+    fs::create_dir_all(base_dir.join("state"))?;
+    fs::write(&path, serde_json::to_string_pretty(sent)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
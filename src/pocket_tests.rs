@@ -0,0 +1,205 @@
+use super::*;
+use crate::models::Feed;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+fn article(n: usize) -> FeedItem {
+    FeedItem {
+        id: format!("https://example.com/{}", n),
+        feed_url: "https://example.com/feed".to_string(),
+        title: format!("Post {}", n),
+        link: format!("https://example.com/{}", n),
+        tags: vec!["rust".to_string(), "async, io".to_string()],
+        ..Default::default()
+    }
+}
+
+/// A Pocket API that rejects Post 3, fails whole requests holding Post 999,
+/// and records every added URL
+async fn serve(added: Arc<Mutex<Vec<String>>>) -> String {
+    let router = Router::new()
+        .route(
+            "/v3/oauth/request",
+            post(|Json(body): Json<Value>| async move {
+                assert_eq!(body["consumer_key"], "key");
+                Json(json!({ "code": "req-1" }))
+            }),
+        )
+        .route(
+            "/v3/oauth/authorize",
+            post(|Json(body): Json<Value>| async move {
+                assert_eq!(body["code"], "req-1");
+                Json(json!({ "access_token": "token-1", "username": "me" }))
+            }),
+        )
+        .route(
+            "/v3/send",
+            post(
+                move |headers: HeaderMap, Json(body): Json<Value>| async move {
+                    assert_eq!(headers["x-accept"], "application/json");
+                    if body["access_token"] != "token-1" {
+                        let mut headers = HeaderMap::new();
+                        headers.insert("X-Error", "Invalid access token".parse().unwrap());
+                        return Err((StatusCode::UNAUTHORIZED, headers));
+                    }
+                    let actions = body["actions"].as_array().unwrap();
+                    if actions
+                        .iter()
+                        .any(|a| a["url"] == "https://example.com/999")
+                    {
+                        return Err((StatusCode::SERVICE_UNAVAILABLE, HeaderMap::new()));
+                    }
+                    let results: Vec<Value> = body["actions"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|action| {
+                            let url = action["url"].as_str().unwrap().to_string();
+                            if url.ends_with("/3") {
+                                return json!(false);
+                            }
+                            added.lock().unwrap().push(url);
+                            json!({ "item_id": "1" })
+                        })
+                        .collect();
+                    Ok(Json(json!({ "status": 1, "action_results": results })))
+                },
+            ),
+        );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/v3", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    url
+}
+
+fn config(url: String) -> PocketConfig {
+    PocketConfig {
+        consumer_key: Some("key".to_string()),
+        access_token: Some("token-1".to_string()),
+        tags: vec!["zetrss".to_string(), "rust".to_string()],
+        url,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_add_action() {
+    let action = add_action(&config(String::new()), &article(1));
+    assert_eq!(action["action"], "add");
+    assert_eq!(action["url"], "https://example.com/1");
+    assert_eq!(action["title"], "Post 1");
+    assert_eq!(action["tags"], "zetrss,rust,async  io");
+
+    let untagged = add_action(
+        &PocketConfig::default(),
+        &FeedItem {
+            tags: Vec::new(),
+            ..article(1)
+        },
+    );
+    assert!(untagged.get("tags").is_none());
+}
+
+#[test]
+fn test_authorize_url() {
+    assert_eq!(
+        authorize_url("a b"),
+        "https://getpocket.com/auth/authorize?request_token=a+b&redirect_uri=https%3A%2F%2Fgetpocket.com%2Fconnected_applications"
+    );
+}
+
+#[tokio::test]
+async fn test_login() {
+    let config = config(serve(Arc::default()).await);
+    let code = request_token(&config).await.unwrap();
+    assert_eq!(code, "req-1");
+    assert_eq!(access_token(&config, &code).await.unwrap(), "token-1");
+}
+
+#[tokio::test]
+async fn test_export_starred_sends_each_article_once() {
+    let dir = TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(dir.path().to_path_buf()).unwrap();
+    cache
+        .store_feed(&Feed {
+            url: "https://example.com/feed".to_string(),
+            title: "Example".to_string(),
+            description: None,
+            last_fetched: None,
+            items: (1..=4).map(article).collect(),
+            bytes: 0,
+        })
+        .unwrap();
+    for n in [1, 2, 3] {
+        cache.toggle_star(&article(n).id).unwrap();
+    }
+    let added = Arc::new(Mutex::new(Vec::new()));
+    let config = config(serve(added.clone()).await);
+
+    let report = export_starred(&cache, &config).await.unwrap();
+    assert_eq!((report.sent, report.failed, report.already_sent), (2, 1, 0));
+    let mut urls = added.lock().unwrap().clone();
+    urls.sort();
+    assert_eq!(urls, vec!["https://example.com/1", "https://example.com/2"]);
+    assert_eq!(
+        load_sent(dir.path()).unwrap(),
+        BTreeSet::from([article(1).id, article(2).id])
+    );
+
+    // Sent articles aren't sent again; the rejected one is retried
+    cache.toggle_star(&article(4).id).unwrap();
+    let report = export_starred(&cache, &config).await.unwrap();
+    assert_eq!((report.sent, report.failed, report.already_sent), (1, 1, 2));
+    assert_eq!(added.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_send_reports_pocket_errors() {
+    let config = PocketConfig {
+        access_token: Some("expired".to_string()),
+        ..config(serve(Arc::default()).await)
+    };
+    let err = send(&config, &[article(1)]).await.unwrap_err();
+    assert!(err.to_string().contains("Invalid access token"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_failed_batch_keeps_earlier_batches_sent() {
+    let dir = TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(dir.path().to_path_buf()).unwrap();
+    // Newest first, so Post 999 lands in the second batch
+    let now = chrono::Utc::now();
+    let mut items: Vec<FeedItem> = (1..=SEND_BATCH)
+        .map(|n| FeedItem {
+            published: Some(now - chrono::Duration::minutes(n as i64)),
+            ..article(n + 100)
+        })
+        .collect();
+    items.push(FeedItem {
+        published: Some(now - chrono::Duration::days(1)),
+        ..article(999)
+    });
+    cache
+        .store_feed(&Feed {
+            url: "https://example.com/feed".to_string(),
+            title: "Example".to_string(),
+            description: None,
+            last_fetched: None,
+            items: items.clone(),
+            bytes: 0,
+        })
+        .unwrap();
+    for item in &items {
+        cache.toggle_star(&item.id).unwrap();
+    }
+    let added = Arc::new(Mutex::new(Vec::new()));
+    let config = config(serve(added.clone()).await);
+
+    assert!(export_starred(&cache, &config).await.is_err());
+    let added: BTreeSet<String> = added.lock().unwrap().iter().cloned().collect();
+    assert_eq!(added.len(), SEND_BATCH);
+    assert_eq!(load_sent(dir.path()).unwrap(), added);
+}
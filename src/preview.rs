@@ -0,0 +1,58 @@
+//! Articles rendered for display outside the TUI
+//!
+//! `zetrss preview` prints an article as wrapped plain text (what the viewer
+//! and pager show) or as Markdown with a heading and metadata list, so Neovim
+//! can show it in a normal buffer with the user's colorscheme and folding.
+
+use crate::engine::ArticleView;
+use crate::models::FeedItem;
+
+#[cfg(test)]
+#[path = "preview_tests.rs"]
+mod tests;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum PreviewFormat {
+    #[default]
+    Text,
+    Markdown,
+}
+
+/// The article in `format`, wrapped at `width` columns
+pub fn render(article: FeedItem, width: usize, format: PreviewFormat) -> String {
+    match format {
+        PreviewFormat::Text => {
+            let view = ArticleView::from_article(article, width);
+            crate::viewer::pager_text(&view.article, &view.lines)
+        }
+        PreviewFormat::Markdown => markdown(&article, width),
+    }
+}
+
+/// Title as a heading, metadata as a list, then the body converted from HTML
+fn markdown(article: &FeedItem, width: usize) -> String {
+    let mut text = format!("# {}\n\n", article.title.trim());
+    text.push_str(&format!("- Feed: {}\n", article.feed_url));
+    if let Some(ref author) = article.author {
+        text.push_str(&format!("- Author: {}\n", author));
+    }
+    if let Some(ref published) = article.published {
+        text.push_str(&format!(
+            "- Published: {}\n",
+            published.format("%Y-%m-%d %H:%M")
+        ));
+    }
+    if !article.tags.is_empty() {
+        text.push_str(&format!("- Tags: {}\n", article.tags.join(", ")));
+    }
+    text.push_str(&format!("- Link: <{}>\n\n", article.link));
+    match article
+        .content
+        .as_deref()
+        .or(article.description.as_deref())
+    {
+        Some(html) => text.push_str(&html2text::from_read(html.as_bytes(), width)),
+        None => text.push_str("No content available\n"),
+    }
+    text
+}
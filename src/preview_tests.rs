@@ -0,0 +1,48 @@
+use super::*;
+use chrono::{TimeZone, Utc};
+
+fn article() -> FeedItem {
+    FeedItem {
+        id: "post-1".to_string(),
+        feed_url: "https://example.com/feed".to_string(),
+        title: "Async Rust".to_string(),
+        link: "https://example.com/async".to_string(),
+        published: Some(Utc.with_ymd_and_hms(2024, 3, 1, 9, 30, 0).unwrap()),
+        author: Some("Ferris".to_string()),
+        content: Some(
+            "<p>Futures are <b>lazy</b>. They do nothing until polled, which the runtime \
+             does for you.</p><ul><li>one</li><li>two</li></ul>"
+                .to_string(),
+        ),
+        tags: vec!["rust".to_string()],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_text_preview() {
+    let text = render(article(), 40, PreviewFormat::Text);
+    assert!(text.starts_with("Async Rust\nFeed: https://example.com/feed\n"));
+    assert!(text.contains("Author: Ferris"));
+    assert!(text.contains("lazy"));
+    assert!(!text.contains("<p>"));
+    assert!(text.lines().all(|line| line.chars().count() <= 40));
+}
+
+#[test]
+fn test_markdown_preview() {
+    let markdown = render(article(), 80, PreviewFormat::Markdown);
+    assert!(markdown.starts_with("# Async Rust\n\n- Feed: https://example.com/feed\n"));
+    assert!(markdown.contains("- Author: Ferris\n"));
+    assert!(markdown.contains("- Published: 2024-03-01 09:30\n"));
+    assert!(markdown.contains("- Tags: rust\n"));
+    assert!(markdown.contains("- Link: <https://example.com/async>\n\n"));
+    assert!(markdown.contains("Futures are lazy."));
+    assert!(markdown.contains("* one"));
+
+    let empty = FeedItem {
+        content: None,
+        ..article()
+    };
+    assert!(render(empty, 80, PreviewFormat::Markdown).ends_with("No content available\n"));
+}
@@ -0,0 +1,131 @@
+//! Fetch progress as newline-delimited JSON events
+//!
+//! `fetch --events <dest>` writes one JSON object per line while it runs, so
+//! the Neovim plugin (or any script) can show live progress instead of
+//! waiting for the process to exit:
+//!
+//! ```text
+//! {"event":"begin","feeds":12}
+//! {"event":"started","url":"https://example.com/feed"}
+//! {"event":"finished","url":"https://example.com/feed","items":20,"added":3,"pruned":0,"error":null}
+//! {"event":"done","feeds":12,"succeeded":11,"failed":1,"added":17}
+//! ```
+//!
+//! `<dest>` is a file descriptor number (e.g. 2 for stderr), a Unix socket
+//! to connect to, or a file or FIFO to write to.
+
+use crate::engine::{FeedFetchResult, FetchEvent};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::sync::Mutex;
+
+#[cfg(test)]
+#[path = "progress_tests.rs"]
+mod tests;
+
+/// One line of the event stream
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent<'a> {
+    /// Fetching begins with this many feeds
+    Begin {
+        feeds: usize,
+    },
+    Started {
+        url: &'a str,
+    },
+    /// A feed's items were stored (`added` new ones), or it failed (`error`)
+    Finished(&'a FeedFetchResult),
+    Done {
+        feeds: usize,
+        succeeded: usize,
+        failed: usize,
+        added: usize,
+    },
+}
+
+impl<'a> From<&'a FetchEvent> for ProgressEvent<'a> {
+    fn from(event: &'a FetchEvent) -> Self {
+        match event {
+            FetchEvent::Started { url } => Self::Started { url },
+            FetchEvent::Finished(result) => Self::Finished(result),
+        }
+    }
+}
+
+impl ProgressEvent<'_> {
+    /// The closing event summarizing `results`
+    pub fn done(results: &[FeedFetchResult]) -> ProgressEvent<'static> {
+        let failed = results.iter().filter(|r| r.error.is_some()).count();
+        ProgressEvent::Done {
+            feeds: results.len(),
+            succeeded: results.len() - failed,
+            failed,
+            added: results.iter().map(|r| r.added).sum(),
+        }
+    }
+}
+
+/// Where events are written, one line each
+pub struct EventSink {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl EventSink {
+    pub fn new(writer: Box<dyn Write + Send>) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /// Opens `dest`: a file descriptor number, a Unix socket or a file
+    pub fn open(dest: &str) -> Result<Self> {
+        let writer: Box<dyn Write + Send> = if let Ok(fd) = dest.parse::<u32>() {
+            let path = format!("/dev/fd/{}", fd);
+            Box::new(
+                OpenOptions::new()
+                    .write(true)
+                    .open(&path)
+                    .with_context(|| format!("File descriptor {} isn't open", fd))?,
+            )
+        } else if is_socket(Path::new(dest)) {
+            Box::new(
+                UnixStream::connect(dest)
+                    .with_context(|| format!("Failed to connect to {}", dest))?,
+            )
+        } else {
+            Box::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(dest)
+                    .with_context(|| format!("Failed to open {}", dest))?,
+            )
+        };
+        Ok(Self::new(writer))
+    }
+
+    /// Writes an event; a reader that went away doesn't stop the fetch
+    pub fn emit(&self, event: &ProgressEvent) {
+        let Ok(mut line) = serde_json::to_string(event) else {
+            return;
+        };
+        line.push('\n');
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writer
+            .write_all(line.as_bytes())
+            .and_then(|_| writer.flush())
+        {
+            tracing::debug!("Failed to write progress event: {}", e);
+        }
+    }
+}
+
+fn is_socket(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).is_ok_and(|meta| meta.file_type().is_socket())
+}
@@ -0,0 +1,74 @@
+use super::*;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader};
+use std::os::unix::net::UnixListener;
+use tempfile::TempDir;
+
+fn result(url: &str, added: usize, error: Option<&str>) -> FeedFetchResult {
+    FeedFetchResult {
+        url: url.to_string(),
+        items: 5,
+        added,
+        pruned: 0,
+        error: error.map(str::to_string),
+    }
+}
+
+fn lines(text: &str) -> Vec<Value> {
+    text.lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_events_to_a_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("events.jsonl");
+    let sink = EventSink::open(&path.to_string_lossy()).unwrap();
+
+    let results = vec![
+        result("https://a.example/feed", 2, None),
+        result("https://b.example/feed", 0, Some("timed out")),
+    ];
+    sink.emit(&ProgressEvent::Begin { feeds: 2 });
+    sink.emit(&ProgressEvent::from(&FetchEvent::Started {
+        url: results[0].url.clone(),
+    }));
+    for r in &results {
+        sink.emit(&ProgressEvent::Finished(r));
+    }
+    sink.emit(&ProgressEvent::done(&results));
+
+    let events = lines(&std::fs::read_to_string(&path).unwrap());
+    assert_eq!(events.len(), 5);
+    assert_eq!(events[0], json!({ "event": "begin", "feeds": 2 }));
+    assert_eq!(
+        events[1],
+        json!({ "event": "started", "url": "https://a.example/feed" })
+    );
+    assert_eq!(events[2]["event"], "finished");
+    assert_eq!(events[2]["added"], 2);
+    assert_eq!(events[3]["error"], "timed out");
+    assert_eq!(
+        events[4],
+        json!({ "event": "done", "feeds": 2, "succeeded": 1, "failed": 1, "added": 2 })
+    );
+}
+
+#[test]
+fn test_events_to_a_socket() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("progress.sock");
+    let listener = UnixListener::bind(&path).unwrap();
+
+    let sink = EventSink::open(&path.to_string_lossy()).unwrap();
+    sink.emit(&ProgressEvent::Begin { feeds: 1 });
+    drop(sink);
+
+    let (stream, _) = listener.accept().unwrap();
+    let line = BufReader::new(stream).lines().next().unwrap().unwrap();
+    assert_eq!(lines(&line), vec![json!({ "event": "begin", "feeds": 1 })]);
+
+    // A closed descriptor is an error up front
+    assert!(EventSink::open("987").is_err());
+}
@@ -0,0 +1,95 @@
+//! Malformed article files moved out of the way
+//!
+//! Files in `articles/` that don't parse would otherwise just vanish from
+//! listings. `TextCache::sync_index` moves them to `quarantine/`, keeping
+//! their path below `articles/`, and logs why in `quarantine/reasons.jsonl`
+//! so `stats` and `doctor` can tell the user. Fixed files can be moved back.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+#[path = "quarantine_tests.rs"]
+mod tests;
+
+const REASONS_FILE: &str = "reasons.jsonl";
+
+/// One quarantined article file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub time: DateTime<Utc>,
+    /// Where the file was, relative to `articles/`
+    pub original: String,
+    /// Where it is now, relative to `quarantine/`
+    pub file: String,
+    pub reason: String,
+}
+
+/// `quarantine/` in a data directory
+pub fn dir(base_dir: &Path) -> PathBuf {
+    base_dir.join("quarantine")
+}
+
+/// Moves `path` (and its raw `.json` entry) from `articles_dir` into the
+/// quarantine and records `reason`
+/// A file already quarantined under the same name gets a numbered suffix
+pub fn quarantine(
+    base_dir: &Path,
+    articles_dir: &Path,
+    path: &Path,
+    reason: &str,
+) -> Result<QuarantineEntry> {
+    let relative = path.strip_prefix(articles_dir).unwrap_or(path);
+    let dir = dir(base_dir);
+    let mut target = dir.join(relative);
+    let mut n = 1;
+    while target.exists() {
+        target = dir.join(relative).with_extension(format!("{}.md", n));
+        n += 1;
+    }
+    fs::create_dir_all(target.parent().unwrap_or(&dir))?;
+    fs::rename(path, &target)
+        .with_context(|| format!("Failed to quarantine {}", path.display()))?;
+    let raw = path.with_extension("json");
+    if raw.exists() {
+        fs::rename(&raw, target.with_extension("json"))?;
+    }
+
+    let entry = QuarantineEntry {
+        time: Utc::now(),
+        original: relative.to_string_lossy().to_string(),
+        file: target
+            .strip_prefix(&dir)
+            .unwrap_or(&target)
+            .to_string_lossy()
+            .to_string(),
+        reason: reason.to_string(),
+    };
+    let mut log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(REASONS_FILE))?;
+    writeln!(log, "{}", serde_json::to_string(&entry)?)?;
+    Ok(entry)
+}
+
+/// Files still in the quarantine, oldest first
+/// Entries whose file has since been deleted or moved back are left out
+pub fn entries(base_dir: &Path) -> Result<Vec<QuarantineEntry>> {
+    let dir = dir(base_dir);
+    let path = dir.join(REASONS_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<QuarantineEntry>(line).ok())
+        .filter(|entry| dir.join(&entry.file).exists())
+        .collect())
+}
@@ -0,0 +1,46 @@
+use super::*;
+use tempfile::TempDir;
+
+#[test]
+fn test_quarantine_moves_file_and_logs_reason() {
+    let temp_dir = TempDir::new().unwrap();
+    let articles = temp_dir.path().join("articles");
+    let feed_dir = articles.join("example_com-1234");
+    fs::create_dir_all(&feed_dir).unwrap();
+
+    for _ in 0..2 {
+        fs::write(feed_dir.join("bad.md"), "no frontmatter").unwrap();
+        fs::write(feed_dir.join("bad.json"), "{}").unwrap();
+        quarantine(
+            temp_dir.path(),
+            &articles,
+            &feed_dir.join("bad.md"),
+            "expected YAML frontmatter",
+        )
+        .unwrap();
+        assert!(!feed_dir.join("bad.md").exists());
+        assert!(!feed_dir.join("bad.json").exists());
+    }
+
+    let quarantined = dir(temp_dir.path()).join("example_com-1234");
+    assert!(quarantined.join("bad.md").exists());
+    assert!(quarantined.join("bad.json").exists());
+    assert!(quarantined.join("bad.1.md").exists());
+    assert!(quarantined.join("bad.1.json").exists());
+
+    let logged = entries(temp_dir.path()).unwrap();
+    assert_eq!(logged.len(), 2);
+    assert_eq!(logged[0].original, "example_com-1234/bad.md");
+    assert_eq!(logged[1].file, "example_com-1234/bad.1.md");
+    assert_eq!(logged[0].reason, "expected YAML frontmatter");
+
+    // Deleted files no longer count
+    fs::remove_file(quarantined.join("bad.md")).unwrap();
+    assert_eq!(entries(temp_dir.path()).unwrap().len(), 1);
+}
+
+#[test]
+fn test_entries_empty_without_log() {
+    let temp_dir = TempDir::new().unwrap();
+    assert!(entries(temp_dir.path()).unwrap().is_empty());
+}
@@ -0,0 +1,164 @@
+use crate::filter::{ago, ArticleFilter};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+#[cfg(test)]
+#[path = "query_tests.rs"]
+mod tests;
+
+/// Parses a query string into an article filter
+///
+/// Terms are separated by whitespace and must all match:
+///   feed:<url>          exact feed URL (`feed:~text` matches a substring)
+///   title:<text>        title contains text
+///   tag:<name>          article carries the tag
+///   read:true|false     read state
+///   starred:true|false  starred state
+///   published:<date>    published on a day; prefix with >, >=, < or <= for ranges
+///   since:<duration>    published within a window such as 3d or 12h
+///   <word> / "phrase"   title, body or feed URL contains the text
+pub fn parse_query(query: &str) -> Result<ArticleFilter> {
+    let mut filter = ArticleFilter::default();
+
+    for token in tokenize(query)? {
+        let (key, value) = match token {
+            Token::Text(text) => {
+                filter.text_terms.push(text);
+                continue;
+            }
+            Token::Field(key, value) => (key, value),
+        };
+
+        match key.as_str() {
+            "feed" => match value.strip_prefix('~') {
+                Some(needle) => filter.feed_contains = Some(needle.to_string()),
+                None => filter.feed = Some(value),
+            },
+            "title" => filter.title_terms.push(value),
+            "tag" => filter.tags.push(value),
+            "read" => filter.read = Some(parse_bool(&key, &value)?),
+            "starred" => filter.starred = Some(parse_bool(&key, &value)?),
+            "published" => apply_date_bound(&mut filter, &value)?,
+            "since" => filter.since = Some(ago(&value)?),
+            _ => {
+                return Err(anyhow!(
+                    "Unknown query field '{}' (expected feed, title, tag, read, starred, published or since)",
+                    key
+                ))
+            }
+        }
+    }
+
+    Ok(filter)
+}
+
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    Field(String, String),
+}
+
+/// Splits a query into bare/quoted text terms and `key:value` fields
+/// Field values may be quoted too: `title:"hello world"`
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        // A token that opens with a quote is text, even if it contains a colon
+        let starts_quoted = c == '"';
+        let mut word = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            chars.next();
+            if c == '"' {
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '"' {
+                        closed = true;
+                        break;
+                    }
+                    word.push(c);
+                }
+                if !closed {
+                    return Err(anyhow!("Unterminated quote in query: {}", query));
+                }
+            } else {
+                word.push(c);
+            }
+        }
+
+        // Bare URLs (`https://...`) are text, not a field named "https"
+        let field = if starts_quoted {
+            None
+        } else {
+            word.split_once(':').filter(|(key, value)| {
+                !key.is_empty()
+                    && key.chars().all(|c| c.is_ascii_alphabetic())
+                    && !value.starts_with("//")
+            })
+        };
+
+        match field {
+            Some((key, value)) => {
+                if value.is_empty() {
+                    return Err(anyhow!("Missing value for query field '{}'", key));
+                }
+                tokens.push(Token::Field(key.to_lowercase(), value.to_string()));
+            }
+            None => tokens.push(Token::Text(word)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => Err(anyhow!(
+            "Invalid value '{}' for {}: expected true or false",
+            value,
+            key
+        )),
+    }
+}
+
+/// Applies a `published:` constraint such as `>2024-01-01` or `2024-03-15`
+fn apply_date_bound(filter: &mut ArticleFilter, value: &str) -> Result<()> {
+    let (op, date) = ["<=", ">=", "<", ">"]
+        .iter()
+        .find_map(|op| value.strip_prefix(op).map(|rest| (*op, rest)))
+        .unwrap_or(("=", value));
+
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| anyhow!("Invalid date '{}': expected YYYY-MM-DD", date))?;
+    let start = start_of_day(day);
+    let next = start + Duration::days(1);
+
+    match op {
+        ">" => filter.since = Some(next),
+        ">=" => filter.since = Some(start),
+        "<" => filter.until = Some(start),
+        "<=" => filter.until = Some(next),
+        _ => {
+            filter.since = Some(start);
+            filter.until = Some(next);
+        }
+    }
+    Ok(())
+}
+
+fn start_of_day(day: NaiveDate) -> DateTime<Utc> {
+    day.and_hms_opt(0, 0, 0)
+        .expect("midnight is a valid time")
+        .and_utc()
+}
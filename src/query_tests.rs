@@ -0,0 +1,56 @@
+use super::*;
+
+#[test]
+fn test_parse_full_query() {
+    let filter =
+        parse_query(r#"feed:~substack tag:rust read:false published:>2024-01-01 "tokio runtime""#)
+            .unwrap();
+
+    assert_eq!(filter.feed_contains.as_deref(), Some("substack"));
+    assert_eq!(filter.tags, vec!["rust"]);
+    assert_eq!(filter.read, Some(false));
+    assert_eq!(
+        filter.since,
+        Some(start_of_day(NaiveDate::from_ymd_opt(2024, 1, 2).unwrap()))
+    );
+    assert_eq!(filter.until, None);
+    assert_eq!(filter.text_terms, vec!["tokio runtime"]);
+}
+
+#[test]
+fn test_parse_fields() {
+    let filter =
+        parse_query("feed:https://example.com/feed starred:yes title:\"hello world\"").unwrap();
+    assert_eq!(filter.feed.as_deref(), Some("https://example.com/feed"));
+    assert_eq!(filter.starred, Some(true));
+    assert_eq!(filter.title_terms, vec!["hello world"]);
+
+    let filter = parse_query("published:2024-03-15").unwrap();
+    let day = start_of_day(NaiveDate::from_ymd_opt(2024, 3, 15).unwrap());
+    assert_eq!(filter.since, Some(day));
+    assert_eq!(filter.until, Some(day + Duration::days(1)));
+
+    let filter = parse_query("published:<=2024-03-15").unwrap();
+    assert_eq!(filter.until, Some(day + Duration::days(1)));
+}
+
+#[test]
+fn test_parse_text_terms() {
+    let filter = parse_query(r#"rust https://example.com "read:later""#).unwrap();
+    assert_eq!(
+        filter.text_terms,
+        vec!["rust", "https://example.com", "read:later"]
+    );
+    assert_eq!(filter.read, None);
+
+    assert_eq!(parse_query("").unwrap(), ArticleFilter::default());
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(parse_query("color:blue").is_err());
+    assert!(parse_query("read:maybe").is_err());
+    assert!(parse_query("published:yesterday").is_err());
+    assert!(parse_query("tag:").is_err());
+    assert!(parse_query("\"unterminated").is_err());
+}
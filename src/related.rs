@@ -0,0 +1,193 @@
+//! Existing zet notes related to an article
+//!
+//! A term index of the zet (word counts per note, without stop words) is
+//! built on demand, and notes are ranked by the cosine similarity of their
+//! TF-IDF weighted terms to the article's. New article notes list the best
+//! matches (`[notes] related`) so they join the graph right away; the viewer
+//! shows them with `R` and `zetrss related` prints them.
+
+use crate::models::FeedItem;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+#[path = "related_tests.rs"]
+mod tests;
+
+/// Scores below this are coincidental overlap, not related notes
+const MIN_SCORE: f64 = 0.05;
+
+/// Words too common to tell notes apart
+const STOP_WORDS: &[&str] = &[
+    "about", "after", "again", "all", "also", "and", "any", "are", "because", "been", "before",
+    "being", "but", "can", "com", "could", "did", "does", "doing", "down", "each", "feed", "few",
+    "for", "from", "had", "has", "have", "her", "here", "him", "his", "how", "http", "https",
+    "into", "its", "just", "more", "most", "not", "now", "off", "once", "only", "other", "our",
+    "out", "over", "own", "same", "she", "should", "some", "such", "than", "that", "the", "their",
+    "them", "then", "there", "these", "they", "this", "those", "through", "too", "under", "until",
+    "very", "was", "were", "what", "when", "where", "which", "while", "who", "why", "will", "with",
+    "would", "www", "you", "your",
+];
+
+/// A zet note suggested for an article
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RelatedNote {
+    pub path: String,
+    pub title: String,
+    pub score: f64,
+}
+
+struct IndexedNote {
+    path: PathBuf,
+    title: String,
+    terms: HashMap<String, usize>,
+}
+
+/// Term counts of every note in the zet
+pub struct TermIndex {
+    notes: Vec<IndexedNote>,
+    /// Number of notes each term appears in
+    document_frequency: HashMap<String, usize>,
+}
+
+impl TermIndex {
+    /// Indexes the notes of `roots`; missing roots and unreadable notes are
+    /// skipped
+    pub fn build(roots: &[String]) -> Result<Self> {
+        let mut notes = Vec::new();
+        let mut seen = HashSet::new();
+        for root in roots.iter().filter(|root| Path::new(root).is_dir()) {
+            for path in crate::scanner::note_paths(root)? {
+                // Nested roots list their notes twice
+                if !seen.insert(path.clone()) {
+                    continue;
+                }
+                if let Ok(content) = fs::read_to_string(&path) {
+                    notes.push((path, content));
+                }
+            }
+        }
+        Ok(Self::from_notes(notes))
+    }
+
+    /// Indexes notes given as (path, content)
+    pub fn from_notes(notes: Vec<(PathBuf, String)>) -> Self {
+        let mut document_frequency: HashMap<String, usize> = HashMap::new();
+        let notes: Vec<IndexedNote> = notes
+            .into_iter()
+            .map(|(path, content)| {
+                let terms = terms(&content);
+                for term in terms.keys() {
+                    *document_frequency.entry(term.clone()).or_default() += 1;
+                }
+                IndexedNote {
+                    title: note_title(&content, &path),
+                    path,
+                    terms,
+                }
+            })
+            .collect();
+        Self {
+            notes,
+            document_frequency,
+        }
+    }
+
+    /// The `limit` notes closest to `text`, best first
+    pub fn related(&self, text: &str, limit: usize) -> Vec<RelatedNote> {
+        let query = self.weights(&terms(text));
+        let query_norm = norm(&query);
+        if query_norm == 0.0 {
+            return Vec::new();
+        }
+        let mut related: Vec<RelatedNote> = self
+            .notes
+            .iter()
+            .filter_map(|note| {
+                let weights = self.weights(&note.terms);
+                let dot: f64 = query
+                    .iter()
+                    .filter_map(|(term, weight)| weights.get(term).map(|w| w * weight))
+                    .sum();
+                let score = dot / (query_norm * norm(&weights));
+                (score >= MIN_SCORE).then(|| RelatedNote {
+                    path: note.path.to_string_lossy().into_owned(),
+                    title: note.title.clone(),
+                    score,
+                })
+            })
+            .collect();
+        related.sort_by(|a, b| b.score.total_cmp(&a.score));
+        related.truncate(limit);
+        related
+    }
+
+    /// TF-IDF weights of term counts; terms no note has are left out
+    fn weights(&self, terms: &HashMap<String, usize>) -> HashMap<String, f64> {
+        let total = self.notes.len() as f64;
+        terms
+            .iter()
+            .filter_map(|(term, &count)| {
+                let df = *self.document_frequency.get(term)? as f64;
+                let idf = ((total + 1.0) / (df + 1.0)).ln() + 1.0;
+                Some((term.clone(), (1.0 + (count as f64).ln()) * idf))
+            })
+            .collect()
+    }
+}
+
+fn norm(weights: &HashMap<String, f64>) -> f64 {
+    weights.values().map(|w| w * w).sum::<f64>().sqrt()
+}
+
+/// The article's title (counted twice) and text, for `TermIndex::related`
+pub fn article_text(article: &FeedItem) -> String {
+    let html = article
+        .content
+        .as_deref()
+        .or(article.description.as_deref())
+        .unwrap_or("");
+    format!(
+        "{0}\n{0}\n{1}",
+        article.title,
+        html2text::from_read(html.as_bytes(), 10_000)
+    )
+}
+
+/// Lowercase words of three or more letters and their counts, without stop
+/// words and numbers
+fn terms(text: &str) -> HashMap<String, usize> {
+    let mut terms = HashMap::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let word = word.to_lowercase();
+        if word.chars().count() < 3
+            || word.chars().all(|c| c.is_ascii_digit())
+            || STOP_WORDS.binary_search(&word.as_str()).is_ok()
+        {
+            continue;
+        }
+        *terms.entry(word).or_default() += 1;
+    }
+    terms
+}
+
+/// A note's first heading or `#+TITLE:`, else its file name
+fn note_title(content: &str, path: &Path) -> String {
+    content
+        .lines()
+        .find_map(|line| {
+            line.strip_prefix("# ")
+                .or_else(|| line.strip_prefix("#+TITLE: "))
+                .or_else(|| line.strip_prefix("#+title: "))
+                .map(|title| title.trim().to_string())
+        })
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        })
+}
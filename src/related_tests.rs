@@ -0,0 +1,58 @@
+use super::*;
+
+fn note(path: &str, content: &str) -> (PathBuf, String) {
+    (PathBuf::from(path), content.to_string())
+}
+
+fn index() -> TermIndex {
+    TermIndex::from_notes(vec![
+        note(
+            "zet/async.md",
+            "# Async Rust\n\nFutures, executors and the tokio runtime. Pinning futures.",
+        ),
+        note(
+            "zet/gardening.org",
+            "#+TITLE: Gardening\n\nTomatoes need sun. Compost the beds in autumn.",
+        ),
+        note(
+            "zet/rust-notes.md",
+            "Ownership and borrowing in Rust; the borrow checker.",
+        ),
+    ])
+}
+
+#[test]
+fn test_related_ranks_by_shared_terms() {
+    let related = index().related("Tokio futures: how executors poll futures in Rust", 5);
+    let titles: Vec<&str> = related.iter().map(|n| n.title.as_str()).collect();
+    assert_eq!(titles, vec!["Async Rust", "rust-notes"]);
+    assert!(related[0].score > related[1].score);
+    assert_eq!(related[0].path, "zet/async.md");
+
+    assert_eq!(index().related("Tokio futures", 1).len(), 1);
+    assert!(index().related("Nothing in common here", 5).is_empty());
+}
+
+#[test]
+fn test_terms_skip_stop_words_and_numbers() {
+    let terms = terms("The Rust book, 2024: the RUST way to write code");
+    assert_eq!(terms.get("rust"), Some(&2));
+    assert!(!terms.contains_key("the"));
+    assert!(!terms.contains_key("2024"));
+    assert!(!terms.contains_key("to"));
+}
+
+#[test]
+fn test_stop_words_sorted() {
+    assert!(STOP_WORDS.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn test_note_title() {
+    assert_eq!(note_title("#+TITLE: Org\n", Path::new("a.org")), "Org");
+    assert_eq!(
+        note_title("text\n# Heading\n", Path::new("a.md")),
+        "Heading"
+    );
+    assert_eq!(note_title("#feed x\n", Path::new("dir/topic.md")), "topic");
+}
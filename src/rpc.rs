@@ -0,0 +1,321 @@
+//! JSON-RPC over stdio for the Neovim plugin
+//!
+//! `zetrss rpc` reads one JSON-RPC 2.0 request per line on stdin and answers
+//! each with one line on stdout, so the plugin keeps a single process around
+//! for any number of actions instead of spawning the binary (or handing data
+//! over through temp files) for each. Methods and their params:
+//!
+//!   list         {query?, limit?, offset?, sort?}  article summaries matching
+//!                                                  the `list` query syntax
+//!   get          {id}                              an article including its content
+//!   mark-read    {id}                              the article's {id, read, starred}
+//!   star         {id, starred?}                    toggles the star, or sets it
+//!   create-note  {id, daily?}                      {path} of the article's note
+//!   open-url     {id}                              opens the article's link in the
+//!                                                  browser, returns {url}
+//!
+//!   unread       {}                                {unread, total} article counts
+//!
+//! Requests without an `id` are notifications and get no answer. The process
+//! exits when stdin closes. Running daemons and viewers answer the same
+//! requests, and a few of their own, on a Unix socket (see `ipc`).
+
+use crate::cache::{ArticleStore, TextCache};
+use crate::engine::ZetRss;
+use crate::fetcher::{FeedFetcher, HttpFetcher};
+use crate::filter::{ArticleQuery, SortOrder};
+use crate::models::{ArticleSummary, FeedItem};
+use crate::query;
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::process::{Command, Stdio};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+#[cfg(test)]
+#[path = "rpc_tests.rs"]
+mod tests;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// Failures of a valid call, e.g. an unknown article
+pub const CALL_FAILED: i64 = -32000;
+
+/// Methods every server answers
+pub const METHODS: &[&str] = &[
+    "list",
+    "get",
+    "mark-read",
+    "star",
+    "create-note",
+    "open-url",
+    "unread",
+];
+
+/// An error answered as the response's `error` member
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(CALL_FAILED, format!("{:#}", e))
+    }
+}
+
+pub type RpcResult = std::result::Result<Value, RpcError>;
+
+/// Methods a server answers beyond the common ones: None for methods it
+/// doesn't know
+pub type ExtraMethods = Box<dyn Fn(&str, &Value) -> Option<RpcResult> + Send + Sync>;
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Value,
+    /// Absent for notifications
+    id: Option<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ListParams {
+    query: Option<String>,
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default)]
+    sort: SortOrder,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IdParams {
+    id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StarParams {
+    id: String,
+    starred: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NoteParams {
+    id: String,
+    #[serde(default)]
+    daily: bool,
+}
+
+/// Answers JSON-RPC requests against an engine
+pub struct RpcServer<S = TextCache, F = HttpFetcher> {
+    reader: ZetRss<S, F>,
+    /// Opens a URL for `open-url`; the browser outside tests
+    open_url: fn(&str) -> Result<()>,
+    extra: Option<ExtraMethods>,
+}
+
+impl<S: ArticleStore, F: FeedFetcher> RpcServer<S, F> {
+    pub fn new(reader: ZetRss<S, F>) -> Self {
+        Self {
+            reader,
+            open_url: open_in_browser,
+            extra: None,
+        }
+    }
+
+    /// Answers `methods` too, ahead of the common ones
+    pub fn with_methods(mut self, methods: ExtraMethods) -> Self {
+        self.extra = Some(methods);
+        self
+    }
+
+    pub fn reader(&self) -> &ZetRss<S, F> {
+        &self.reader
+    }
+
+    /// The response line to a request line, or None for notifications
+    pub fn handle_line(&self, line: &str) -> Option<String> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => {
+                return Some(response(
+                    Value::Null,
+                    Err(RpcError::new(PARSE_ERROR, e.to_string())),
+                ))
+            }
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let request = match serde_json::from_value::<Request>(request) {
+            Ok(request) if request.jsonrpc == "2.0" => request,
+            Ok(_) => {
+                let error = RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\"");
+                return Some(response(id, Err(error)));
+            }
+            Err(e) => {
+                return Some(response(
+                    id,
+                    Err(RpcError::new(INVALID_REQUEST, e.to_string())),
+                ))
+            }
+        };
+        let result = self.call(&request.method, request.params);
+        request.id.map(|id| response(id, result))
+    }
+
+    /// Runs a method
+    pub fn call(&self, method: &str, params: Value) -> RpcResult {
+        if let Some(result) = self.extra.as_ref().and_then(|extra| extra(method, &params)) {
+            return result;
+        }
+        match method {
+            "list" => {
+                let params: ListParams = params_of(params)?;
+                let filter = query::parse_query(params.query.as_deref().unwrap_or(""))
+                    .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+                let articles = self.reader.list(&ArticleQuery {
+                    filter,
+                    sort: params.sort,
+                    offset: params.offset,
+                    limit: params.limit,
+                })?;
+                let summaries: Vec<ArticleSummary> =
+                    articles.iter().map(ArticleSummary::from).collect();
+                to_value(&summaries)
+            }
+            "get" => {
+                let params: IdParams = params_of(params)?;
+                to_value(&self.article(&params.id)?)
+            }
+            "mark-read" => {
+                let params: IdParams = params_of(params)?;
+                self.article(&params.id)?;
+                self.reader.mark_read(&params.id)?;
+                self.state(&params.id)
+            }
+            "star" => {
+                let params: StarParams = params_of(params)?;
+                let article = self.article(&params.id)?;
+                if params.starred != Some(article.starred) {
+                    self.reader.toggle_star(&params.id)?;
+                }
+                self.state(&params.id)
+            }
+            "create-note" => {
+                let params: NoteParams = params_of(params)?;
+                let article = self.article(&params.id)?;
+                let path = if params.daily {
+                    crate::notes::add_to_daily_note(&article)?
+                } else {
+                    crate::notes::create_note_from_article(&article)?
+                };
+                Ok(json!({ "path": path }))
+            }
+            "open-url" => {
+                let params: IdParams = params_of(params)?;
+                let article = self.article(&params.id)?;
+                (self.open_url)(&article.link)?;
+                Ok(json!({ "url": article.link }))
+            }
+            "unread" => {
+                let stats = self.reader.stats()?;
+                Ok(json!({ "unread": stats.unread, "total": stats.total }))
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method: {}", method),
+            )),
+        }
+    }
+
+    fn article(&self, id: &str) -> std::result::Result<FeedItem, RpcError> {
+        self.reader
+            .store()
+            .get_article_by_id(id)?
+            .ok_or_else(|| RpcError::new(CALL_FAILED, format!("Article not found: {}", id)))
+    }
+
+    fn state(&self, id: &str) -> RpcResult {
+        let article = self.article(id)?;
+        Ok(json!({ "id": article.id, "read": article.read, "starred": article.starred }))
+    }
+}
+
+/// A method's params as `T`, missing params counting as `{}`
+pub fn params_of<T: DeserializeOwned>(params: Value) -> std::result::Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn to_value(value: &impl Serialize) -> RpcResult {
+    serde_json::to_value(value).map_err(|e| RpcError::from(anyhow::Error::from(e)))
+}
+
+/// A response object as one line of JSON
+fn response(id: Value, result: RpcResult) -> String {
+    let response = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+    };
+    response.to_string()
+}
+
+/// Opens `url` with `$BROWSER`, else the desktop's opener, without waiting
+fn open_in_browser(url: &str) -> Result<()> {
+    let opener = std::env::var("BROWSER").unwrap_or_else(|_| {
+        if cfg!(target_os = "macos") {
+            "open".to_string()
+        } else {
+            "xdg-open".to_string()
+        }
+    });
+    // stdout carries the protocol, so the opener must not write to it
+    Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", opener))
+        .arg("sh")
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run '{}'", opener))?;
+    Ok(())
+}
+
+/// Answers requests from stdin on stdout until stdin closes
+pub async fn serve(reader: ZetRss) -> Result<()> {
+    let server = RpcServer::new(reader);
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(mut answer) = server.handle_line(&line) {
+            answer.push('\n');
+            stdout.write_all(answer.as_bytes()).await?;
+            stdout.flush().await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use super::*;
+use crate::models::Feed;
+use chrono::Utc;
+use tempfile::TempDir;
+
+fn article(id: &str, title: &str) -> FeedItem {
+    FeedItem {
+        id: id.to_string(),
+        feed_url: "https://example.com/feed".to_string(),
+        title: title.to_string(),
+        link: format!("https://example.com/{}", title),
+        published: Some(Utc::now()),
+        content: Some(format!("<p>{} body</p>", title)),
+        ..Default::default()
+    }
+}
+
+/// A server over a temporary cache holding two articles
+fn server() -> (RpcServer, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    cache
+        .store_feed(&Feed {
+            url: "https://example.com/feed".to_string(),
+            title: "Example".to_string(),
+            description: None,
+            last_fetched: Some(Utc::now()),
+            bytes: 0,
+            items: vec![article("post-1", "rust"), article("post-2", "golang")],
+        })
+        .unwrap();
+    let mut server = RpcServer::new(ZetRss::with_parts(cache, HttpFetcher));
+    server.open_url = |_| Ok(());
+    (server, temp_dir)
+}
+
+fn call(server: &RpcServer, request: Value) -> Value {
+    let line = server.handle_line(&request.to_string()).unwrap();
+    assert!(!line.contains('\n'));
+    serde_json::from_str(&line).unwrap()
+}
+
+#[test]
+fn test_list_and_get() {
+    let (server, _dir) = server();
+    let all = call(
+        &server,
+        json!({"jsonrpc": "2.0", "id": 1, "method": "list"}),
+    );
+    assert_eq!(all["id"], 1);
+    assert_eq!(all["result"].as_array().unwrap().len(), 2);
+
+    let rust = call(
+        &server,
+        json!({"jsonrpc": "2.0", "id": 2, "method": "list", "params": {"query": "rust"}}),
+    );
+    let rust = rust["result"].as_array().unwrap();
+    assert_eq!(rust.len(), 1);
+    assert_eq!(rust[0]["id"], "post-1");
+    assert!(rust[0].get("content").is_none());
+
+    let article = call(
+        &server,
+        json!({"jsonrpc": "2.0", "id": "a", "method": "get", "params": {"id": "post-2"}}),
+    );
+    assert_eq!(article["id"], "a");
+    assert_eq!(article["result"]["title"], "golang");
+    assert!(article["result"]["content"]
+        .as_str()
+        .unwrap()
+        .contains("golang body"));
+}
+
+#[test]
+fn test_mark_read_and_star() {
+    let (server, _dir) = server();
+    let read = call(
+        &server,
+        json!({"jsonrpc": "2.0", "id": 1, "method": "mark-read", "params": {"id": "post-1"}}),
+    );
+    assert_eq!(
+        read["result"],
+        json!({"id": "post-1", "read": true, "starred": false})
+    );
+
+    let star = |params: Value| {
+        call(
+            &server,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "star", "params": params}),
+        )["result"]["starred"]
+            .clone()
+    };
+    assert_eq!(star(json!({"id": "post-1"})), true);
+    assert_eq!(star(json!({"id": "post-1", "starred": true})), true);
+    assert_eq!(star(json!({"id": "post-1"})), false);
+    assert_eq!(star(json!({"id": "post-1", "starred": false})), false);
+
+    let opened = call(
+        &server,
+        json!({"jsonrpc": "2.0", "id": 1, "method": "open-url", "params": {"id": "post-2"}}),
+    );
+    assert_eq!(opened["result"]["url"], "https://example.com/golang");
+}
+
+#[test]
+fn test_errors_and_notifications() {
+    let (server, _dir) = server();
+    let code = |line: &str| {
+        let answer: Value = serde_json::from_str(&server.handle_line(line).unwrap()).unwrap();
+        answer["error"]["code"].as_i64().unwrap()
+    };
+    assert_eq!(code("{not json"), PARSE_ERROR);
+    assert_eq!(code(r#"{"id": 1, "method": "list"}"#), INVALID_REQUEST);
+    assert_eq!(
+        code(r#"[{"jsonrpc": "2.0", "id": 1, "method": "list"}]"#),
+        INVALID_REQUEST
+    );
+    assert_eq!(
+        code(r#"{"jsonrpc": "2.0", "id": 1, "method": "nope"}"#),
+        METHOD_NOT_FOUND
+    );
+    assert_eq!(
+        code(r#"{"jsonrpc": "2.0", "id": 1, "method": "get", "params": {}}"#),
+        INVALID_PARAMS
+    );
+    assert_eq!(
+        code(r#"{"jsonrpc": "2.0", "id": 1, "method": "get", "params": {"id": "x"}}"#),
+        CALL_FAILED
+    );
+
+    // Notifications are carried out without an answer
+    assert!(server
+        .handle_line(r#"{"jsonrpc": "2.0", "method": "mark-read", "params": {"id": "post-2"}}"#)
+        .is_none());
+    let article = call(
+        &server,
+        json!({"jsonrpc": "2.0", "id": 1, "method": "get", "params": {"id": "post-2"}}),
+    );
+    assert_eq!(article["result"]["read"], true);
+}
@@ -1,59 +1,723 @@
-use anyhow::Result;
+use crate::opml;
+use anyhow::{bail, Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
 use walkdir::WalkDir;
 
+#[cfg(test)]
+#[path = "scanner_tests.rs"]
+mod tests;
+
+/// Ignore files read from the root of a zet, in gitignore syntax
+/// `.navireaderignore` is the name from before the rename
+pub const IGNORE_FILES: &[&str] = &[".zetrssignore", ".navireaderignore"];
+
+/// Name of the scan cache in the state directory
+pub const SCAN_CACHE_FILE: &str = "scan_cache.json";
+
+/// Bumped when the scanner finds feeds differently, so cached notes are read
+/// again after an upgrade
+const SCAN_CACHE_VERSION: u32 = 4;
+
 /// Information about a discovered feed
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FeedSource {
     pub url: String,
     pub source_file: String,
     pub line_number: usize,
+    /// The zet directory the feed was found in, when scanned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    /// Settings declared with the feed in the note
+    #[serde(default, skip_serializing_if = "FeedOptions::is_empty")]
+    pub options: FeedOptions,
+}
+
+/// Per-feed settings declared in the zet next to the feed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedOptions {
+    /// Name shown for the feed instead of the one it publishes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Tags given to the feed's new articles
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// How often the daemon fetches the feed, e.g. "6h"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<String>,
+    /// Store each new article's web page instead of the feed's summary
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub full_content: bool,
+}
+
+impl FeedOptions {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Sets one option from its `key` and text `value`, as written in a note
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "title" => self.title = Some(value.to_string()),
+            "tags" => self.tags = flow_list(value),
+            "interval" => {
+                let interval = crate::filter::parse_duration(value)?;
+                if interval <= chrono::Duration::zero() {
+                    bail!("Interval must be positive: {}", value);
+                }
+                self.interval = Some(value.to_string());
+            }
+            "full_content" => {
+                self.full_content = match value {
+                    "true" | "yes" => true,
+                    "false" | "no" => false,
+                    _ => bail!("Expected true or false for full_content, got '{}'", value),
+                }
+            }
+            _ => bail!("Unknown feed option '{}'", key),
+        }
+        Ok(())
+    }
+
+    /// The declared fetch interval, if it parses
+    pub fn interval(&self) -> Option<chrono::Duration> {
+        crate::filter::parse_duration(self.interval.as_deref()?).ok()
+    }
 }
 
-/// Scans a directory recursively for markdown files containing RSS feed URLs
-/// Looks for URLs marked with '#feed' tag
+/// Scans a directory recursively for notes containing RSS feed URLs
+/// Notes are markdown, org-mode and plain text files (`scan.extensions`)
+/// Looks for URLs marked with '#feed' tag, in any format, and for a `feeds:`
+/// list in markdown frontmatter
+/// Skips the configured `scan.skip_dirs` and paths matched by the zet's
+/// ignore file, so template notes with example tags stay out of the list
 /// Returns a deduplicated list of feed URLs with their source locations
 pub async fn scan_markdown_for_feeds(zet_path: &str) -> Result<Vec<FeedSource>> {
-    // Use HashMap to deduplicate by URL, keeping first occurrence
-    let mut feeds: HashMap<String, FeedSource> = HashMap::new();
+    Ok(FeedIndex::build(&[zet_path.to_string()])?.feeds())
+}
+
+/// What the last scan found in each note, so notes that haven't changed
+/// since aren't read again
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanCache {
+    pub version: u32,
+    pub notes: BTreeMap<PathBuf, CachedNote>,
+}
+
+/// A scanned note, identified by its modification time and size
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedNote {
+    pub modified: SystemTime,
+    pub size: u64,
+    pub feeds: Vec<FeedSource>,
+    /// Sites marked with `#feedsite`, whose feeds are discovered from their pages
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sites: Vec<FeedSource>,
+}
+
+/// The feeds of every note in a set of zet roots, kept per note so a single
+/// changed note can be rescanned without walking the whole zet again
+#[derive(Debug)]
+pub struct FeedIndex {
+    roots: Vec<RootIndex>,
+    /// Roots that didn't exist, so their feeds are missing from the index
+    skipped: Vec<String>,
+    /// Notes read (rather than taken from the scan cache) since the index was built
+    reads: usize,
+    /// Feeds found on `#feedsite` pages and accepted by the user
+    discovered: Vec<FeedSource>,
+}
+
+#[derive(Debug)]
+struct RootIndex {
+    path: String,
+    ignored: Gitignore,
+    /// Notes by path, in path order so the first occurrence is stable
+    notes: BTreeMap<PathBuf, CachedNote>,
+}
+
+impl FeedIndex {
+    /// Scans every note in `roots`; missing roots are skipped with a warning
+    pub fn build(roots: &[String]) -> Result<Self> {
+        Self::build_cached(roots, &ScanCache::default())
+    }
+
+    /// Scans every note in `roots`, reading only the notes that changed since
+    /// `cache` was taken; a cache from another scanner version is ignored
+    pub fn build_cached(roots: &[String], cache: &ScanCache) -> Result<Self> {
+        let previous = if cache.version == SCAN_CACHE_VERSION {
+            &cache.notes
+        } else {
+            &BTreeMap::new()
+        };
+        let mut index = Self {
+            roots: Vec::new(),
+            skipped: Vec::new(),
+            reads: 0,
+            discovered: Vec::new(),
+        };
+        for root in roots {
+            if !Path::new(root).is_dir() {
+                tracing::warn!("Zet directory {} does not exist; skipping it", root);
+                index.skipped.push(root.clone());
+                continue;
+            }
+            let mut root_index = RootIndex {
+                path: root.clone(),
+                ignored: ignore_rules(root, &crate::config::get().scan.skip_dirs)?,
+                notes: BTreeMap::new(),
+            };
+            index.reads += root_index.scan(Path::new(root), previous)?;
+            index.roots.push(root_index);
+        }
+        Ok(index)
+    }
+
+    /// The notes and their feeds, for the next `build_cached`
+    pub fn cache(&self) -> ScanCache {
+        ScanCache {
+            version: SCAN_CACHE_VERSION,
+            notes: self
+                .roots
+                .iter()
+                .flat_map(|root| root.notes.clone())
+                .collect(),
+        }
+    }
+
+    /// Number of notes read so far; the rest came from the scan cache
+    pub fn reads(&self) -> usize {
+        self.reads
+    }
+
+    /// The directories being scanned
+    pub fn roots(&self) -> impl Iterator<Item = &str> {
+        self.roots.iter().map(|root| root.path.as_str())
+    }
 
-    // Match URLs explicitly marked with #feed tag
-    let feed_tag_regex = Regex::new(r"#feed\s+(https?://[^\s\)>\]]+)")?;
+    /// The roots left out because they don't exist
+    pub fn skipped(&self) -> &[String] {
+        &self.skipped
+    }
+
+    /// Merged feed list: each root's feeds sorted by URL, a URL found in
+    /// several roots keeping the source from the root listed first
+    /// URLs naming the same feed (see `feed_key`) count as one
+    pub fn feeds(&self) -> Vec<FeedSource> {
+        let mut feeds: Vec<FeedSource> = Vec::new();
+        let mut seen = HashSet::new();
+        for root in &self.roots {
+            let mut found: Vec<&FeedSource> = Vec::new();
+            let mut in_root = HashSet::new();
+            for feed in root.notes.values().flat_map(|note| &note.feeds) {
+                if in_root.insert(feed_key(&feed.url)) {
+                    found.push(feed);
+                }
+            }
+            found.sort_by(|a, b| a.url.cmp(&b.url));
+            for feed in found {
+                if seen.insert(feed_key(&feed.url)) {
+                    feeds.push(feed.clone());
+                }
+            }
+        }
+        for feed in &self.discovered {
+            if seen.insert(feed_key(&feed.url)) {
+                feeds.push(feed.clone());
+            }
+        }
+        feeds
+    }
 
-    for entry in WalkDir::new(zet_path)
+    /// Sites marked with `#feedsite`, in the order of `feeds`
+    pub fn sites(&self) -> Vec<FeedSource> {
+        let mut sites: Vec<FeedSource> = Vec::new();
+        let mut seen = HashSet::new();
+        for root in &self.roots {
+            for site in root.notes.values().flat_map(|note| &note.sites) {
+                if seen.insert(feed_key(&site.url)) {
+                    sites.push(site.clone());
+                }
+            }
+        }
+        sites
+    }
+
+    /// Adds feeds discovered from `#feedsite` pages after the declared ones
+    pub fn set_discovered(&mut self, feeds: Vec<FeedSource>) {
+        self.discovered = feeds;
+    }
+
+    /// Rescans a changed path: a note, a directory of notes or an ignore
+    /// file; removed paths drop their notes. Paths outside the roots are
+    /// ignored
+    pub fn update(&mut self, changed: &Path) -> Result<()> {
+        for root in &mut self.roots {
+            let Some(path) = root.resolve(changed) else {
+                continue;
+            };
+            if path.parent() == Some(Path::new(&root.path))
+                && path
+                    .file_name()
+                    .is_some_and(|name| IGNORE_FILES.iter().any(|f| name == *f))
+            {
+                root.ignored = ignore_rules(&root.path, &crate::config::get().scan.skip_dirs)?;
+                let previous = std::mem::take(&mut root.notes);
+                self.reads += root.scan(Path::new(&root.path.clone()), &previous)?;
+                continue;
+            }
+            let (previous, kept) = std::mem::take(&mut root.notes)
+                .into_iter()
+                .partition(|(note, _)| note.starts_with(&path));
+            root.notes = kept;
+            let ignored = path.exists()
+                && root
+                    .ignored
+                    .matched_path_or_any_parents(&path, path.is_dir())
+                    .is_ignore();
+            if path.exists() && !ignored {
+                self.reads += root.scan(&path, &previous)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RootIndex {
+    /// The path as found under this root, e.g. with the root's `~/zet`
+    /// spelling instead of the absolute path a watcher reports
+    fn resolve(&self, path: &Path) -> Option<PathBuf> {
+        if let Ok(relative) = path.strip_prefix(&self.path) {
+            return Some(Path::new(&self.path).join(relative));
+        }
+        let canonical_root = fs::canonicalize(&self.path).ok()?;
+        let relative = path.strip_prefix(canonical_root).ok()?;
+        Some(Path::new(&self.path).join(relative))
+    }
+
+    /// Scans the notes at or under `start` into the index, taking unchanged
+    /// ones from `previous`; returns the number of notes read
+    fn scan(&mut self, start: &Path, previous: &BTreeMap<PathBuf, CachedNote>) -> Result<usize> {
+        let root = Path::new(&self.path);
+        let extensions = &crate::config::get().scan.extensions;
+        let mut reads = 0;
+        for entry in WalkDir::new(start)
+            .follow_links(true)
+            .into_iter()
+            // The root itself is never ignored, whatever its name
+            .filter_entry(|e| {
+                e.path() == root
+                    || !self
+                        .ignored
+                        .matched(e.path(), e.file_type().is_dir())
+                        .is_ignore()
+            })
+            .filter_map(|e| e.ok())
+            .filter(|e| is_note(e.path(), extensions) || opml::is_opml(e.path()))
+        {
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+            let (modified, size) = (metadata.modified()?, metadata.len());
+            let note = match previous.get(path) {
+                Some(note) if note.modified == modified && note.size == size => note.clone(),
+                _ => {
+                    reads += 1;
+                    let content = fs::read_to_string(path)?;
+                    let (feeds, sites) = if opml::is_opml(path) {
+                        (opml_feeds(&content, path, &self.path), Vec::new())
+                    } else {
+                        note_feeds(&content, path, &self.path)
+                    };
+                    CachedNote {
+                        modified,
+                        size,
+                        feeds,
+                        sites,
+                    }
+                }
+            };
+            self.notes.insert(path.to_path_buf(), note);
+        }
+        Ok(reads)
+    }
+}
+
+/// The notes under `root` a scan reads, skipping `scan.skip_dirs` and
+/// ignored paths
+pub fn note_paths(root: &str) -> Result<Vec<PathBuf>> {
+    let config = crate::config::get();
+    let ignored = ignore_rules(root, &config.scan.skip_dirs)?;
+    Ok(WalkDir::new(root)
         .follow_links(true)
         .into_iter()
+        .filter_entry(|e| {
+            e.path() == Path::new(root)
+                || !ignored
+                    .matched(e.path(), e.file_type().is_dir())
+                    .is_ignore()
+        })
         .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().is_some_and(|ext| ext == "md"))
-    {
-        let path = entry.path();
-        let content = fs::read_to_string(path)?;
-
-        for (line_num, line) in content.lines().enumerate() {
-            for cap in feed_tag_regex.captures_iter(line) {
-                if let Some(url) = cap.get(1) {
-                    let url_str = url.as_str().trim();
-                    // Clean up the URL - remove trailing punctuation that might not be part of URL
-                    let url_str = url_str.trim_end_matches(['.', ',', ')', ']', '>']);
-
-                    // Only insert if we haven't seen this URL before
-                    if !feeds.contains_key(url_str) {
-                        feeds.insert(
-                            url_str.to_string(),
-                            FeedSource {
-                                url: url_str.to_string(),
-                                source_file: path.to_string_lossy().to_string(),
-                                line_number: line_num + 1, // 1-indexed for editors
-                            },
-                        );
+        .filter(|e| is_note(e.path(), &config.scan.extensions))
+        .map(|e| e.into_path())
+        .collect())
+}
+
+/// Whether the file has one of the note `extensions` (`scan.extensions`)
+pub fn is_note(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+}
+
+/// The feeds of an OPML file, tagged with the names of their folders
+/// A file that doesn't parse is logged and contributes no feeds
+fn opml_feeds(content: &str, path: &Path, root: &str) -> Vec<FeedSource> {
+    let listed = match opml::parse(content) {
+        Ok(listed) => listed,
+        Err(e) => {
+            tracing::warn!("{}: {:#}", path.display(), e);
+            return Vec::new();
+        }
+    };
+    let mut seen = HashSet::new();
+    listed
+        .into_iter()
+        .filter(|feed| seen.insert(feed_key(&feed.url)))
+        .map(|feed| FeedSource {
+            url: feed.url,
+            source_file: path.to_string_lossy().to_string(),
+            line_number: feed.line_number,
+            root: Some(root.to_string()),
+            options: FeedOptions {
+                title: feed.title,
+                tags: feed.folders,
+                ..Default::default()
+            },
+        })
+        .collect()
+}
+
+/// The feeds declared in one note: its frontmatter `feeds:` first, then
+/// `#feed` tags; a URL declared twice keeps its first declaration
+/// Also returns the note's `#feedsite` sites, which take the same options
+/// Every feed and site gets the note's own tags (`note_tags`) after its own
+fn note_feeds(content: &str, path: &Path, root: &str) -> (Vec<FeedSource>, Vec<FeedSource>) {
+    // Match URLs explicitly marked with #feed (or #feedsite) tag
+    static FEED_TAG: OnceLock<Regex> = OnceLock::new();
+    // `key=value` options following the URL, e.g. `interval=6h title="My Feed"`
+    static OPTION: OnceLock<Regex> = OnceLock::new();
+    let feed_tag =
+        FEED_TAG.get_or_init(|| Regex::new(r"#feed(site)?\s+(https?://[^\s\)>\]]+)").unwrap());
+    let option =
+        OPTION.get_or_init(|| Regex::new(r#"^\s+([a-z_]+)=("[^"]*"|'[^']*'|\S+)"#).unwrap());
+
+    let source = |url: String, line_number: usize, options: FeedOptions| FeedSource {
+        url,
+        source_file: path.to_string_lossy().to_string(),
+        line_number,
+        root: Some(root.to_string()),
+        options,
+    };
+    let mut feeds: Vec<FeedSource> = Vec::new();
+    let mut sites: Vec<FeedSource> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut seen_sites = HashSet::new();
+    for (line_number, url, options) in frontmatter_feeds(content, path) {
+        if seen.insert(feed_key(&url)) {
+            feeds.push(source(url, line_number, options));
+        }
+    }
+
+    for (line_num, line) in content.lines().enumerate() {
+        for cap in feed_tag.captures_iter(line) {
+            if let Some(url) = cap.get(2) {
+                let url_str = url.as_str().trim();
+                // Clean up the URL - remove trailing punctuation that might not be part of URL
+                let url_str = url_str.trim_end_matches(['.', ',', ')', ']', '>']);
+                let is_site = cap.get(1).is_some();
+
+                // Only insert if we haven't seen this URL before
+                let seen = if is_site { &mut seen_sites } else { &mut seen };
+                if seen.insert(feed_key(url_str)) {
+                    let mut options = FeedOptions::default();
+                    let mut rest = &line[url.end()..];
+                    while let Some(found) = option.captures(rest) {
+                        if let Err(e) = options.set(&found[1], unquote(&found[2])) {
+                            tracing::warn!("{}:{}: {}", path.display(), line_num + 1, e);
+                        }
+                        rest = &rest[found[0].len()..];
+                    }
+                    // 1-indexed for editors
+                    let found = source(url_str.to_string(), line_num + 1, options);
+                    if is_site {
+                        sites.push(found);
+                    } else {
+                        feeds.push(found);
                     }
                 }
             }
         }
     }
 
-    Ok(feeds.into_values().collect())
+    let inherited = note_tags(content);
+    for feed in feeds.iter_mut().chain(sites.iter_mut()) {
+        for tag in &inherited {
+            if !feed.options.tags.contains(tag) {
+                feed.options.tags.push(tag.clone());
+            }
+        }
+    }
+    (feeds, sites)
+}
+
+/// Paths the scan of `zet_path` leaves out: the `skip_dirs` directories,
+/// then the root's ignore files, which can re-include them with `!dir/`
+fn ignore_rules(zet_path: &str, skip_dirs: &[String]) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(zet_path);
+    for dir in skip_dirs {
+        builder.add_line(None, &format!("{}/", dir.trim_end_matches('/')))?;
+    }
+    for name in IGNORE_FILES {
+        let path = Path::new(zet_path).join(name);
+        if path.is_file() {
+            if let Some(e) = builder.add(&path) {
+                tracing::warn!("{}: {}", path.display(), e);
+            }
+        }
+    }
+    Ok(builder.build()?)
+}
+
+/// What two spellings of the same feed URL have in common: the scheme, a
+/// `www.` prefix, default ports, the host's case and a trailing slash don't
+/// matter, so `http://www.x.com/feed` and `https://x.com/feed/` are one feed
+pub fn feed_key(url: &str) -> String {
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return url.trim_end_matches('/').to_lowercase();
+    };
+    let host = parsed.host_str().unwrap_or_default();
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let port = match parsed.port() {
+        Some(port) if port != 80 && port != 443 => format!(":{}", port),
+        _ => String::new(),
+    };
+    let query = parsed
+        .query()
+        .map(|q| format!("?{}", q))
+        .unwrap_or_default();
+    format!(
+        "{}{}{}{}",
+        host,
+        port,
+        parsed.path().trim_end_matches('/'),
+        query
+    )
+}
+
+/// Feeds declared in a note's YAML frontmatter under `feeds:`, as a flow
+/// list (`feeds: [url1, url2]`) or a block list whose items are URLs or maps
+/// with `url`, `title` and `tags`:
+///
+/// ```yaml
+/// feeds:
+///   - https://example.com/rss
+///   - url: https://blog.rust-lang.org/feed.xml
+///     title: Rust Blog
+///     tags: [rust, lang]
+///     interval: 6h
+/// ```
+///
+/// Returns each feed with the (1-based) line its URL is on
+fn frontmatter_feeds(content: &str, path: &Path) -> Vec<(usize, String, FeedOptions)> {
+    let mut lines = content.lines().enumerate();
+    if lines.next().map(|(_, line)| line.trim_end()) != Some("---") {
+        return Vec::new();
+    }
+
+    let mut feeds = Vec::new();
+    let mut in_feeds = false;
+    for (index, line) in lines {
+        if line.trim_end() == "---" {
+            break;
+        }
+        let line_number = index + 1;
+        // A top-level key starts or ends the `feeds:` list
+        if !line.starts_with([' ', '\t', '-']) {
+            in_feeds = false;
+            if let Some(("feeds", value)) = line.split_once(':').map(|(k, v)| (k.trim(), v.trim()))
+            {
+                in_feeds = value.is_empty();
+                for url in flow_list(value) {
+                    feeds.push((line_number, url, FeedOptions::default()));
+                }
+            }
+            continue;
+        }
+        if !in_feeds {
+            continue;
+        }
+
+        let trimmed = line.trim();
+        let (item_start, entry) = match trimmed.strip_prefix('-') {
+            Some(item) => (true, item.trim()),
+            None => (false, trimmed),
+        };
+        // A URL's scheme colon isn't a key
+        let key_value = entry
+            .split_once(':')
+            .filter(|(_, value)| !value.starts_with("//"))
+            .map(|(key, value)| (key.trim(), unquote(value)));
+        if item_start {
+            let url = if key_value.is_some() {
+                ""
+            } else {
+                unquote(entry)
+            };
+            feeds.push((line_number, url.to_string(), FeedOptions::default()));
+        }
+        let (Some((key, value)), Some((url_line, url, options))) = (key_value, feeds.last_mut())
+        else {
+            continue;
+        };
+        if key == "url" {
+            *url_line = line_number;
+            *url = value.to_string();
+        } else if let Err(e) = options.set(key, value) {
+            tracing::warn!("{}:{}: {}", path.display(), line_number, e);
+        }
+    }
+    feeds.retain(|(_, url, _)| url.starts_with("http://") || url.starts_with("https://"));
+    feeds
+}
+
+/// The tags of a note: its frontmatter `tags:` (a flow or block list), an
+/// org-mode `#+filetags:` line and `#hashtags` outside code blocks, in that
+/// order and without duplicates; `#feed` and `#feedsite` markers aren't tags
+fn note_tags(content: &str) -> Vec<String> {
+    static HASHTAG: OnceLock<Regex> = OnceLock::new();
+    let hashtag = HASHTAG
+        .get_or_init(|| Regex::new(r"(?:^|[\s(\[,;])#([\p{L}\p{N}_][\p{L}\p{N}_/-]*)").unwrap());
+
+    let mut tags: Vec<String> = Vec::new();
+    let mut add = |tag: &str| {
+        let tag = tag.trim();
+        if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    };
+
+    let mut lines = content.lines().peekable();
+    if lines.peek().map(|line| line.trim_end()) == Some("---") {
+        lines.next();
+        let mut in_tags = false;
+        for line in lines.by_ref() {
+            if line.trim_end() == "---" {
+                break;
+            }
+            if !line.starts_with([' ', '\t', '-']) {
+                in_tags = false;
+                if let Some(("tags", value)) =
+                    line.split_once(':').map(|(k, v)| (k.trim(), v.trim()))
+                {
+                    in_tags = value.is_empty();
+                    flow_list(value).iter().for_each(|tag| add(tag));
+                }
+            } else if let Some(item) = line.trim().strip_prefix('-').filter(|_| in_tags) {
+                add(unquote(item));
+            }
+        }
+    }
+
+    let mut in_code = false;
+    for line in lines {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        if let Some(value) = trimmed
+            .get(..11)
+            .filter(|key| key.eq_ignore_ascii_case("#+filetags:"))
+            .map(|_| &trimmed[11..])
+        {
+            value.split(':').for_each(&mut add);
+            continue;
+        }
+        for cap in hashtag.captures_iter(line) {
+            let tag = &cap[1];
+            let is_marker = tag == "feed" || tag == "feedsite";
+            // `#1` is an issue or list number, not a tag
+            if !is_marker && !tag.chars().all(|c| c.is_ascii_digit()) {
+                add(tag);
+            }
+        }
+    }
+    tags
+}
+
+/// Items of a YAML flow list (`[a, "b"]`) or a bare comma-separated list
+fn flow_list(value: &str) -> Vec<String> {
+    value
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|item| unquote(item).to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim().trim_matches(|c| c == '"' || c == '\'')
+}
+
+/// Scans several zet directories (e.g. a work and a personal vault) and
+/// merges their feeds; a URL tagged in more than one keeps the source from
+/// the root listed first
+pub async fn scan_roots(roots: &[String]) -> Result<Vec<FeedSource>> {
+    Ok(FeedIndex::build(roots)?.feeds())
+}
+
+/// Appends a `#feed <url>` line to a zet note, creating the note if needed
+/// A note that can't be read is left alone, and one already declaring the
+/// feed isn't written to
+/// Returns the feed source pointing at the feed's line so scans stay consistent
+pub fn append_feed_to_note(note_path: &str, url: &str) -> Result<FeedSource> {
+    let mut content = match fs::read_to_string(note_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", note_path)),
+    };
+    let source = |line_number| FeedSource {
+        url: url.to_string(),
+        source_file: note_path.to_string(),
+        line_number,
+        root: None,
+        options: FeedOptions::default(),
+    };
+
+    let key = feed_key(url);
+    let declared = content.lines().position(|line| {
+        let mut words = line.split_whitespace();
+        words.any(|w| w == "#feed") && words.next().is_some_and(|u| feed_key(u) == key)
+    });
+    if let Some(index) = declared {
+        return Ok(source(index + 1));
+    }
+
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&format!("#feed {}\n", url));
+    fs::write(note_path, &content)?;
+    Ok(source(content.lines().count()))
 }
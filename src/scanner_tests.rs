@@ -0,0 +1,450 @@
+use super::*;
+use tempfile::TempDir;
+
+fn root_with(notes: &[(&str, &str)]) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    for (name, content) in notes {
+        let path = dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    dir
+}
+
+fn path(dir: &TempDir) -> String {
+    dir.path().to_string_lossy().to_string()
+}
+
+#[tokio::test]
+async fn test_scan_finds_tagged_urls() {
+    let zet = root_with(&[
+        (
+            "rust.md",
+            "# Rust\n\n#feed https://blog.rust-lang.org/feed.xml.\nhttps://untagged.example.com/rss\n",
+        ),
+        ("sub/ai.md", "See (#feed https://example.com/ai.rss)\n"),
+        ("page.html", "#feed https://ignored.example.com/rss\n"),
+    ]);
+    let mut feeds = scan_markdown_for_feeds(&path(&zet)).await.unwrap();
+    feeds.sort_by(|a, b| a.url.cmp(&b.url));
+
+    let urls: Vec<&str> = feeds.iter().map(|f| f.url.as_str()).collect();
+    assert_eq!(
+        urls,
+        vec![
+            "https://blog.rust-lang.org/feed.xml",
+            "https://example.com/ai.rss"
+        ]
+    );
+    assert_eq!(feeds[0].line_number, 3);
+    assert_eq!(feeds[0].root.as_deref(), Some(path(&zet).as_str()));
+}
+
+#[test]
+fn test_feedsite_markers_are_sites_not_feeds() {
+    let zet = root_with(&[
+        (
+            "blogs.md",
+            "#feedsite https://blog.example.com/ title=\"Blog\"\n#feed https://blog.example.com/rss\n",
+        ),
+        ("more.md", "Again: #feedsite https://www.blog.example.com\n"),
+    ]);
+    let index = FeedIndex::build(&[path(&zet)]).unwrap();
+    let urls: Vec<String> = index.feeds().into_iter().map(|f| f.url).collect();
+    assert_eq!(urls, vec!["https://blog.example.com/rss"]);
+
+    let sites = index.sites();
+    assert_eq!(sites.len(), 1);
+    assert_eq!(sites[0].url, "https://blog.example.com/");
+    assert_eq!(sites[0].line_number, 1);
+    assert_eq!(sites[0].options.title.as_deref(), Some("Blog"));
+}
+
+#[test]
+fn test_missing_roots_are_skipped_and_reported() {
+    let zet = root_with(&[("feeds.md", "#feed https://example.com/rss\n")]);
+    let missing = format!("{}/gone", path(&zet));
+    let index = FeedIndex::build(&[path(&zet), missing.clone()]).unwrap();
+    assert_eq!(index.feeds().len(), 1);
+    assert_eq!(index.skipped(), [missing]);
+}
+
+#[test]
+fn test_opml_files_are_feed_sources() {
+    let zet = root_with(&[
+        (
+            "feeds.opml",
+            "<opml version=\"2.0\"><body>\n<outline text=\"Tech\">\n<outline text=\"LWN\" xmlUrl=\"https://lwn.net/headlines/rss\"/>\n</outline>\n</body></opml>\n",
+        ),
+        ("broken.opml", "<opml><body>"),
+        ("reading.md", "#feed https://lwn.net/headlines/rss/ tags=kernel\n"),
+    ]);
+    let index = FeedIndex::build(&[path(&zet)]).unwrap();
+    let feeds = index.feeds();
+    assert_eq!(feeds.len(), 1);
+    // Notes are read in path order, so the OPML entry wins here
+    assert!(feeds[0].source_file.ends_with("feeds.opml"));
+    assert_eq!(feeds[0].line_number, 3);
+    assert_eq!(feeds[0].options.title.as_deref(), Some("LWN"));
+    assert_eq!(feeds[0].options.tags, vec!["Tech"]);
+}
+
+#[test]
+fn test_feeds_inherit_the_tags_of_their_note() {
+    let note = "---\ntitle: Reading\ntags:\n  - rust\n  - 'lang'\n---\n\
+                # Heading #rust #systems/low-level (see #42)\n\
+                #feed https://a.example.com/rss tags=lang,news\n\
+                https://b.example.com/page#section\n\
+                ```\n#not-a-tag\n```\n";
+    let (feeds, _) = note_feeds(note, Path::new("n.md"), "zet");
+    assert_eq!(
+        feeds[0].options.tags,
+        vec!["lang", "news", "rust", "systems/low-level"]
+    );
+
+    let org = "#+TITLE: Reading\n#+FILETAGS: :emacs:lisp:\n#feedsite https://c.example.com/\n";
+    let (feeds, sites) = note_feeds(org, Path::new("n.org"), "zet");
+    assert!(feeds.is_empty());
+    assert_eq!(sites[0].options.tags, vec!["emacs", "lisp"]);
+}
+
+#[tokio::test]
+async fn test_scan_roots_merges_and_records_roots() {
+    let work = root_with(&[(
+        "team.md",
+        "#feed https://shared.example.com/rss\n#feed https://work.example.com/rss\n",
+    )]);
+    let personal = root_with(&[(
+        "reading.md",
+        "#feed https://shared.example.com/rss\n#feed https://home.example.com/rss\n",
+    )]);
+    let missing = work.path().join("missing").to_string_lossy().to_string();
+
+    let feeds = scan_roots(&[path(&work), missing, path(&personal)])
+        .await
+        .unwrap();
+    let found: Vec<(&str, String)> = feeds
+        .iter()
+        .map(|f| (f.url.as_str(), f.root.clone().unwrap()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("https://shared.example.com/rss", path(&work)),
+            ("https://work.example.com/rss", path(&work)),
+            ("https://home.example.com/rss", path(&personal)),
+        ]
+    );
+}
+
+#[test]
+fn test_frontmatter_feeds() {
+    let note = r#"---
+title: Reading list
+feeds:
+  - https://example.com/rss
+  - url: "https://blog.rust-lang.org/feed.xml"
+    title: Rust Blog
+    tags: [rust, lang]
+    interval: 6h
+  - title: Titled first
+    url: https://example.org/atom
+  - not a url
+aliases: [reading]
+---
+#feed https://example.net/rss
+"#;
+    let feeds = frontmatter_feeds(note, Path::new("reading.md"));
+    let urls: Vec<(usize, &str)> = feeds
+        .iter()
+        .map(|(line, url, _)| (*line, url.as_str()))
+        .collect();
+    assert_eq!(
+        urls,
+        vec![
+            (4, "https://example.com/rss"),
+            (5, "https://blog.rust-lang.org/feed.xml"),
+            (10, "https://example.org/atom"),
+        ]
+    );
+    assert!(feeds[0].2.is_empty());
+    assert_eq!(feeds[1].2.title.as_deref(), Some("Rust Blog"));
+    assert_eq!(feeds[1].2.tags, vec!["rust", "lang"]);
+    assert_eq!(feeds[1].2.interval.as_deref(), Some("6h"));
+    assert_eq!(feeds[2].2.title.as_deref(), Some("Titled first"));
+
+    let flow = frontmatter_feeds(
+        "---\nfeeds: [https://a.example.com/rss, 'https://b.example.com/rss']\n---\n",
+        Path::new("reading.md"),
+    );
+    assert_eq!(flow.len(), 2);
+    assert_eq!(flow[1].1, "https://b.example.com/rss");
+
+    // Only frontmatter counts
+    assert!(frontmatter_feeds(
+        "# Note\nfeeds: [https://a.example.com/rss]\n",
+        Path::new("reading.md")
+    )
+    .is_empty());
+}
+
+#[tokio::test]
+async fn test_scan_merges_frontmatter_and_tags() {
+    let zet = root_with(&[(
+        "reading.md",
+        "---\nfeeds:\n  - url: https://example.com/rss\n    title: Example\n---\n#feed https://example.com/rss\n#feed https://example.net/rss\n",
+    )]);
+    let mut feeds = scan_markdown_for_feeds(&path(&zet)).await.unwrap();
+    feeds.sort_by(|a, b| a.url.cmp(&b.url));
+    assert_eq!(feeds.len(), 2);
+    // The frontmatter entry, with its title, wins over the tag line
+    assert_eq!(feeds[0].line_number, 3);
+    assert_eq!(feeds[0].options.title.as_deref(), Some("Example"));
+    assert_eq!(feeds[1].url, "https://example.net/rss");
+}
+
+#[tokio::test]
+async fn test_scan_reads_options_on_the_feed_line() {
+    let zet = root_with(&[(
+        "rust.md",
+        concat!(
+            "#feed https://a.example.com/rss interval=6h tags=rust,ai full_content=true\n",
+            "#feed https://b.example.com/rss title=\"Rust Weekly\" see the archive tags=x\n",
+            "#feed https://c.example.com/rss interval=soon colour=red tags=news\n",
+        ),
+    )]);
+    let mut feeds = scan_markdown_for_feeds(&path(&zet)).await.unwrap();
+    feeds.sort_by(|a, b| a.url.cmp(&b.url));
+
+    let a = &feeds[0].options;
+    assert_eq!(a.interval.as_deref(), Some("6h"));
+    assert_eq!(a.interval(), Some(chrono::Duration::hours(6)));
+    assert_eq!(a.tags, vec!["rust", "ai"]);
+    assert!(a.full_content);
+
+    // Options stop at the first word that isn't key=value
+    let b = &feeds[1].options;
+    assert_eq!(b.title.as_deref(), Some("Rust Weekly"));
+    assert!(b.tags.is_empty());
+
+    // Bad options are skipped, good ones still apply
+    let c = &feeds[2].options;
+    assert_eq!(c.interval, None);
+    assert_eq!(c.tags, vec!["news"]);
+}
+
+#[test]
+fn test_feed_options_reject_bad_values() {
+    let mut options = FeedOptions::default();
+    assert!(options.set("interval", "0m").is_err());
+    assert!(options.set("full_content", "maybe").is_err());
+    assert!(options.set("colour", "red").is_err());
+    assert!(options.is_empty());
+
+    options.set("full_content", "yes").unwrap();
+    options.set("interval", "30m").unwrap();
+    assert_eq!(options.interval(), Some(chrono::Duration::minutes(30)));
+}
+
+#[tokio::test]
+async fn test_scan_skips_ignored_paths() {
+    let zet = root_with(&[
+        ("rust.md", "#feed https://kept.example.com/rss\n"),
+        ("templates/feed.md", "#feed https://example.com/your-feed\n"),
+        (
+            "notes/archive/old.md",
+            "#feed https://archived.example.com/rss\n",
+        ),
+        (
+            ".obsidian/snippet.md",
+            "#feed https://obsidian.example.com/rss\n",
+        ),
+        ("drafts/idea.md", "#feed https://draft.example.com/rss\n"),
+        ("scratch.md", "#feed https://scratch.example.com/rss\n"),
+        (".zetrssignore", "drafts/\n/scratch.md\n"),
+    ]);
+    let feeds = scan_markdown_for_feeds(&path(&zet)).await.unwrap();
+    let urls: Vec<&str> = feeds.iter().map(|f| f.url.as_str()).collect();
+    assert_eq!(urls, vec!["https://kept.example.com/rss"]);
+}
+
+#[test]
+fn test_ignore_file_can_reinclude_skipped_dirs() {
+    let zet = root_with(&[(".navireaderignore", "!archive/\n*.tmp.md\n")]);
+    let skip_dirs = vec!["archive".to_string(), "notes/old/".to_string()];
+    let rules = ignore_rules(&path(&zet), &skip_dirs).unwrap();
+
+    let ignored = |rel: &str, is_dir: bool| rules.matched(zet.path().join(rel), is_dir).is_ignore();
+    assert!(!ignored("archive", true));
+    assert!(ignored("notes/old", true));
+    assert!(!ignored("old", true));
+    assert!(ignored("draft.tmp.md", false));
+}
+
+#[test]
+fn test_index_updates_single_notes() {
+    let zet = root_with(&[
+        ("a.md", "#feed https://a.example.com/rss\n"),
+        ("sub/b.md", "#feed https://b.example.com/rss\n"),
+    ]);
+    let mut index = FeedIndex::build(&[path(&zet)]).unwrap();
+    let urls = |index: &FeedIndex| index.feeds().into_iter().map(|f| f.url).collect::<Vec<_>>();
+    assert_eq!(
+        urls(&index),
+        vec!["https://a.example.com/rss", "https://b.example.com/rss"]
+    );
+
+    // Watchers report absolute paths; the index keeps the root's spelling
+    let a = zet.path().canonicalize().unwrap().join("a.md");
+    fs::write(
+        &a,
+        "#feed https://a.example.com/rss\n#feed https://c.example.com/rss\n",
+    )
+    .unwrap();
+    index.update(&a).unwrap();
+    let c = index
+        .feeds()
+        .into_iter()
+        .find(|f| f.url == "https://c.example.com/rss")
+        .unwrap();
+    assert_eq!(c.source_file, zet.path().join("a.md").to_string_lossy());
+    assert_eq!(c.line_number, 2);
+
+    fs::remove_dir_all(zet.path().join("sub")).unwrap();
+    index.update(&zet.path().join("sub")).unwrap();
+    fs::create_dir_all(zet.path().join("templates")).unwrap();
+    fs::write(
+        zet.path().join("templates/feed.md"),
+        "#feed https://example.com/your-feed\n",
+    )
+    .unwrap();
+    index.update(&zet.path().join("templates/feed.md")).unwrap();
+    assert_eq!(
+        urls(&index),
+        vec!["https://a.example.com/rss", "https://c.example.com/rss"]
+    );
+
+    // Editing the ignore file rescans the whole root
+    fs::write(zet.path().join(".zetrssignore"), "!templates/\na.md\n").unwrap();
+    index.update(&zet.path().join(".zetrssignore")).unwrap();
+    assert_eq!(urls(&index), vec!["https://example.com/your-feed"]);
+}
+
+#[test]
+fn test_build_cached_reads_only_changed_notes() {
+    let zet = root_with(&[
+        ("a.md", "#feed https://a.example.com/rss\n"),
+        ("b.md", "#feed https://b.example.com/rss\n"),
+    ]);
+    let index = FeedIndex::build(&[path(&zet)]).unwrap();
+    assert_eq!(index.reads(), 2);
+    let mut cache = index.cache();
+
+    // Cached feeds are trusted while the note's mtime and size match
+    let a = zet.path().join("a.md");
+    cache.notes.get_mut(&a).unwrap().feeds[0].url = "https://cached.example.com/rss".into();
+    // A different size, as mtimes can be too coarse to tell quick writes apart
+    fs::write(
+        zet.path().join("b.md"),
+        "#feed https://changed.example.com/rss\n",
+    )
+    .unwrap();
+    let index = FeedIndex::build_cached(&[path(&zet)], &cache).unwrap();
+    assert_eq!(index.reads(), 1);
+    let urls: Vec<String> = index.feeds().into_iter().map(|f| f.url).collect();
+    assert_eq!(
+        urls,
+        vec![
+            "https://cached.example.com/rss",
+            "https://changed.example.com/rss"
+        ]
+    );
+
+    // A cache from another scanner version is ignored
+    cache.version = 0;
+    let index = FeedIndex::build_cached(&[path(&zet)], &cache).unwrap();
+    assert_eq!(index.reads(), 2);
+}
+
+#[test]
+fn test_feed_key_ignores_spelling_differences() {
+    let key = feed_key("https://example.com/feed");
+    for url in [
+        "http://example.com/feed",
+        "https://example.com/feed/",
+        "https://www.Example.COM/feed",
+        "http://example.com:80/feed",
+        "https://example.com:443/feed#latest",
+    ] {
+        assert_eq!(feed_key(url), key, "{}", url);
+    }
+    // Paths, queries and other ports still tell feeds apart
+    assert_ne!(feed_key("https://example.com/Feed"), key);
+    assert_ne!(feed_key("https://example.com/feed?tag=rust"), key);
+    assert_ne!(feed_key("https://example.com:8080/feed"), key);
+    assert_ne!(feed_key("https://blog.example.com/feed"), key);
+}
+
+#[tokio::test]
+async fn test_scan_dedups_spellings_of_one_feed() {
+    let zet = root_with(&[
+        (
+            "a.md",
+            "#feed https://x.example.com/feed\n#feed http://x.example.com/feed/\n",
+        ),
+        ("b.md", "#feed https://www.x.example.com/feed\n"),
+    ]);
+    let feeds = scan_markdown_for_feeds(&path(&zet)).await.unwrap();
+    assert_eq!(feeds.len(), 1);
+    assert_eq!(feeds[0].url, "https://x.example.com/feed");
+    assert_eq!(feeds[0].line_number, 1);
+}
+
+#[tokio::test]
+async fn test_scan_reads_org_and_text_notes() {
+    let zet = root_with(&[
+        (
+            "reading.org",
+            "* Reading\n  - Rust :: #feed https://org.example.com/rss interval=1d\n",
+        ),
+        ("links.TXT", "#feed https://text.example.com/rss\n"),
+    ]);
+    let mut feeds = scan_markdown_for_feeds(&path(&zet)).await.unwrap();
+    feeds.sort_by(|a, b| a.url.cmp(&b.url));
+    let urls: Vec<&str> = feeds.iter().map(|f| f.url.as_str()).collect();
+    assert_eq!(
+        urls,
+        vec![
+            "https://org.example.com/rss",
+            "https://text.example.com/rss"
+        ]
+    );
+    assert_eq!(feeds[0].line_number, 2);
+    assert_eq!(feeds[0].options.interval.as_deref(), Some("1d"));
+
+    assert!(is_note(Path::new("a.md"), &["md".to_string()]));
+    assert!(!is_note(Path::new("a.org"), &["md".to_string()]));
+    assert!(!is_note(Path::new("md"), &["md".to_string()]));
+}
+
+#[test]
+fn test_append_feed_to_note() {
+    let zet = root_with(&[("reading.md", "# Reading\n#feed https://a.example.com/rss")]);
+    let note = format!("{}/reading.md", path(&zet));
+
+    let source = append_feed_to_note(&note, "https://b.example.com/rss").unwrap();
+    assert_eq!(source.line_number, 3);
+    // Already declared, spelled differently: the note stays as it is
+    let source = append_feed_to_note(&note, "https://www.b.example.com/rss/").unwrap();
+    assert_eq!(source.line_number, 3);
+    assert_eq!(
+        fs::read_to_string(&note).unwrap(),
+        "# Reading\n#feed https://a.example.com/rss\n#feed https://b.example.com/rss\n"
+    );
+
+    // A note that can't be read isn't overwritten
+    let binary = format!("{}/binary.md", path(&zet));
+    fs::write(&binary, [0xff, 0xfe, 0x00]).unwrap();
+    assert!(append_feed_to_note(&binary, "https://c.example.com/rss").is_err());
+    assert_eq!(fs::read(&binary).unwrap(), [0xff, 0xfe, 0x00]);
+}
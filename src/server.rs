@@ -0,0 +1,221 @@
+//! Local HTTP API for integrations beyond Neovim (browser extensions, shortcuts)
+//!
+//! Endpoints (all JSON):
+//!   GET  /articles?q=<query>&limit=<n>   article summaries matching the `list` query syntax
+//!        &offset=<n>&sort=<order>        paged, sorted newest (default), oldest or title
+//!   GET  /articles/{id}                  a single article including its content
+//!   POST /articles/{id}/read             mark an article as read
+//!   POST /articles/{id}/star             toggle an article's starred flag
+//!   POST /fetch                          fetch every feed and report per-feed results
+//!   GET  /stats                          article counts overall and per feed
+//!
+//! Article IDs often contain slashes, so clients must percent-encode them.
+//! Requests must name a loopback `Host` and, from a browser, come from a
+//! loopback page or an extension, so other web pages can't use the API
+//! through cross-site requests or DNS rebinding.
+
+use crate::cache::ArticleStore;
+use crate::engine::{FeedFetchResult, LibraryStats, ZetRss};
+use crate::fetcher::FeedFetcher;
+use crate::filter::{ArticleQuery, SortOrder};
+use crate::models::{ArticleSummary, FeedItem};
+use crate::query;
+use anyhow::Result;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+#[cfg(test)]
+#[path = "server_tests.rs"]
+mod tests;
+
+/// Builds the API router around a shared engine
+pub fn router<S, F>(reader: Arc<ZetRss<S, F>>) -> Router
+where
+    S: ArticleStore + 'static,
+    F: FeedFetcher + 'static,
+{
+    Router::new()
+        .route("/articles", get(list_articles::<S, F>))
+        .route("/articles/:id", get(get_article::<S, F>))
+        .route("/articles/:id/read", post(mark_read::<S, F>))
+        .route("/articles/:id/star", post(toggle_star::<S, F>))
+        .route("/fetch", post(fetch::<S, F>))
+        .route("/stats", get(stats::<S, F>))
+        .layer(middleware::from_fn(same_host_only))
+        .with_state(reader)
+}
+
+/// Turns away requests addressed to another host name or sent by a web page
+/// that isn't served from this machine
+async fn same_host_only(request: Request, next: Next) -> Response {
+    if !allowed_request(request.headers()) {
+        return ApiError(
+            StatusCode::FORBIDDEN,
+            "Only local clients may use this API".to_string(),
+        )
+        .into_response();
+    }
+    next.run(request).await
+}
+
+fn allowed_request(headers: &HeaderMap) -> bool {
+    let host = headers.get(header::HOST).and_then(|h| h.to_str().ok());
+    if !host.is_some_and(is_loopback_host) {
+        return false;
+    }
+    match headers.get(header::ORIGIN).map(|o| o.to_str()) {
+        None => true,
+        Some(Ok(origin)) => {
+            if origin.starts_with("chrome-extension://") || origin.starts_with("moz-extension://") {
+                return true;
+            }
+            origin
+                .strip_prefix("http://")
+                .or_else(|| origin.strip_prefix("https://"))
+                .is_some_and(is_loopback_host)
+        }
+        Some(Err(_)) => false,
+    }
+}
+
+/// Whether `host` (with an optional port) names this machine
+fn is_loopback_host(host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or(""),
+        None => host.split(':').next().unwrap_or(""),
+    };
+    matches!(name, "localhost" | "127.0.0.1" | "::1")
+}
+
+/// Serves the API on `addr` until interrupted with Ctrl-C
+pub async fn serve(reader: ZetRss, addr: SocketAddr) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router(Arc::new(reader)))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
+
+/// An error returned to the client as `{"error": "..."}`
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn not_found(id: &str) -> Self {
+        Self(StatusCode::NOT_FOUND, format!("Article not found: {}", id))
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<Json<T>, ApiError>;
+
+#[derive(Deserialize)]
+struct ListParams {
+    q: Option<String>,
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default)]
+    sort: SortOrder,
+}
+
+#[derive(Serialize)]
+struct ArticleState {
+    id: String,
+    read: bool,
+    starred: bool,
+}
+
+async fn list_articles<S: ArticleStore, F: FeedFetcher>(
+    State(reader): State<Arc<ZetRss<S, F>>>,
+    Query(params): Query<ListParams>,
+) -> ApiResult<Vec<ArticleSummary>> {
+    let filter = query::parse_query(params.q.as_deref().unwrap_or(""))
+        .map_err(|e| ApiError(StatusCode::BAD_REQUEST, e.to_string()))?;
+    let articles = reader.list(&ArticleQuery {
+        filter,
+        sort: params.sort,
+        offset: params.offset,
+        limit: params.limit,
+    })?;
+    Ok(Json(articles.iter().map(ArticleSummary::from).collect()))
+}
+
+async fn get_article<S: ArticleStore, F: FeedFetcher>(
+    State(reader): State<Arc<ZetRss<S, F>>>,
+    Path(id): Path<String>,
+) -> ApiResult<FeedItem> {
+    find_article(&reader, &id).map(Json)
+}
+
+async fn mark_read<S: ArticleStore, F: FeedFetcher>(
+    State(reader): State<Arc<ZetRss<S, F>>>,
+    Path(id): Path<String>,
+) -> ApiResult<ArticleState> {
+    find_article(&reader, &id)?;
+    reader.mark_read(&id)?;
+    article_state(&reader, &id)
+}
+
+async fn toggle_star<S: ArticleStore, F: FeedFetcher>(
+    State(reader): State<Arc<ZetRss<S, F>>>,
+    Path(id): Path<String>,
+) -> ApiResult<ArticleState> {
+    find_article(&reader, &id)?;
+    reader.toggle_star(&id)?;
+    article_state(&reader, &id)
+}
+
+async fn fetch<S: ArticleStore, F: FeedFetcher>(
+    State(reader): State<Arc<ZetRss<S, F>>>,
+) -> ApiResult<Vec<FeedFetchResult>> {
+    Ok(Json(reader.fetch(|_| {}).await?))
+}
+
+async fn stats<S: ArticleStore, F: FeedFetcher>(
+    State(reader): State<Arc<ZetRss<S, F>>>,
+) -> ApiResult<LibraryStats> {
+    Ok(Json(reader.stats()?))
+}
+
+/// Looks up an article, mapping invalid or unknown IDs to 404
+fn find_article<S: ArticleStore, F: FeedFetcher>(
+    reader: &ZetRss<S, F>,
+    id: &str,
+) -> std::result::Result<FeedItem, ApiError> {
+    match reader.store().get_article_by_id(id) {
+        Ok(Some(article)) => Ok(article),
+        Ok(None) | Err(_) => Err(ApiError::not_found(id)),
+    }
+}
+
+fn article_state<S: ArticleStore, F: FeedFetcher>(
+    reader: &ZetRss<S, F>,
+    id: &str,
+) -> ApiResult<ArticleState> {
+    let article = find_article(reader, id)?;
+    Ok(Json(ArticleState {
+        id: article.id,
+        read: article.read,
+        starred: article.starred,
+    }))
+}
@@ -0,0 +1,191 @@
+use super::*;
+use crate::cache::TextCache;
+use crate::fetcher::HttpFetcher;
+use crate::models::Feed;
+use chrono::Utc;
+use tempfile::TempDir;
+
+fn article(id: &str, title: &str) -> FeedItem {
+    FeedItem {
+        id: id.to_string(),
+        feed_url: "https://example.com/feed".to_string(),
+        title: title.to_string(),
+        link: format!("https://example.com/{}", title),
+        published: Some(Utc::now()),
+        content: Some(format!("<p>{} body</p>", title)),
+        ..Default::default()
+    }
+}
+
+/// Starts the API on an ephemeral port over a temporary cache
+async fn spawn_server() -> (String, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    cache
+        .store_feed(&Feed {
+            url: "https://example.com/feed".to_string(),
+            title: "Example".to_string(),
+            description: None,
+            last_fetched: Some(Utc::now()),
+            bytes: 0,
+            items: vec![
+                article("https://example.com/posts/1", "rust"),
+                article("post-2", "golang"),
+            ],
+        })
+        .unwrap();
+
+    let app = router(Arc::new(ZetRss::with_parts(cache, HttpFetcher)));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{}", addr), temp_dir)
+}
+
+fn client() -> reqwest::Client {
+    reqwest::Client::builder().no_proxy().build().unwrap()
+}
+
+#[tokio::test]
+async fn test_list_and_get_articles() {
+    let (base, _dir) = spawn_server().await;
+
+    let all: Vec<serde_json::Value> = client()
+        .get(format!("{}/articles", base))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(all.len(), 2);
+
+    let filtered: Vec<serde_json::Value> = client()
+        .get(format!("{}/articles?q=title:rust", base))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0]["id"], "https://example.com/posts/1");
+
+    let page: Vec<serde_json::Value> = client()
+        .get(format!("{}/articles?sort=title&offset=1&limit=1", base))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0]["title"], "rust");
+
+    // IDs with slashes must be percent-encoded
+    let article: serde_json::Value = client()
+        .get(format!(
+            "{}/articles/https%3A%2F%2Fexample.com%2Fposts%2F1",
+            base
+        ))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(article["title"], "rust");
+
+    let response = client()
+        .get(format!("{}/articles?q=bogus:field", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_mark_read_and_star() {
+    let (base, _dir) = spawn_server().await;
+
+    let state: serde_json::Value = client()
+        .post(format!("{}/articles/post-2/read", base))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(state["read"], true);
+
+    let state: serde_json::Value = client()
+        .post(format!("{}/articles/post-2/star", base))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(state["starred"], true);
+
+    let stats: serde_json::Value = client()
+        .get(format!("{}/stats", base))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(stats["unread"], 1);
+    assert_eq!(stats["starred"], 1);
+
+    let response = client()
+        .post(format!("{}/articles/missing/read", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_rejects_foreign_hosts_and_origins() {
+    let (base, _dir) = spawn_server().await;
+    let star = format!("{}/articles/post-2/star", base);
+
+    // DNS rebinding: the browser sends the attacker's host name
+    let response = client()
+        .post(&star)
+        .header("Host", "evil.example.com")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    // Cross-site request from a web page
+    let response = client()
+        .post(&star)
+        .header("Origin", "https://evil.example.com")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    for origin in ["http://localhost:3000", "moz-extension://abc"] {
+        let response = client()
+            .get(format!("{}/stats", base))
+            .header("Origin", origin)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK, "{}", origin);
+    }
+}
+
+#[test]
+fn test_is_loopback_host() {
+    assert!(is_loopback_host("127.0.0.1:7878"));
+    assert!(is_loopback_host("localhost"));
+    assert!(is_loopback_host("[::1]:7878"));
+    assert!(!is_loopback_host("localhost.evil.example.com"));
+    assert!(!is_loopback_host("127.0.0.1.nip.io:7878"));
+}
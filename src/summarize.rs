@@ -0,0 +1,194 @@
+//! Article summaries from a local LLM
+//!
+//! With `[summarizer] url` set to an OpenAI-compatible chat completions
+//! endpoint (ollama serves one at `http://localhost:11434/v1/chat/completions`),
+//! new notes get a three-bullet summary instead of the article's first
+//! paragraph, and the viewer shows it above the article (`S` asks for one).
+//! Summaries are kept in `state/summaries.json`, so each article is sent
+//! once; encrypted caches don't keep them.
+
+use crate::config::SummarizerConfig;
+use crate::models::FeedItem;
+use anyhow::{anyhow, Context, Result};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[cfg(test)]
+#[path = "summarize_tests.rs"]
+mod tests;
+
+/// Name of the summary store in the state directory
+pub const SUMMARIES_FILE: &str = "summaries.json";
+
+/// Characters of article text sent along, enough for most posts while
+/// staying inside small local models' context windows
+const MAX_INPUT_CHARS: usize = 12_000;
+
+/// Bullets kept from a reply
+const BULLETS: usize = 3;
+
+/// Whether `[summarizer] url` is set
+pub fn enabled() -> bool {
+    crate::config::get().summarizer.url.is_some()
+}
+
+/// The article's summary: the stored one, else a new one from the configured
+/// summarizer (blocking until it answers), else None when summaries are off
+pub fn summary(base_dir: &Path, article: &FeedItem) -> Result<Option<String>> {
+    let config = &crate::config::get().summarizer;
+    if config.url.is_none() {
+        return Ok(None);
+    }
+    if let Some(summary) = stored(base_dir, &article.id) {
+        return Ok(Some(summary));
+    }
+    let summary = blocking(request(config, article))?;
+    if !crate::crypt::is_enabled(base_dir) {
+        store(base_dir, &article.id, &summary)?;
+    }
+    Ok(Some(summary))
+}
+
+/// The stored summary of an article, if it was summarized before
+pub fn stored(base_dir: &Path, article_id: &str) -> Option<String> {
+    summaries(base_dir).ok()?.remove(article_id)
+}
+
+fn summaries_path(base_dir: &Path) -> PathBuf {
+    base_dir.join("state").join(SUMMARIES_FILE)
+}
+
+fn summaries(base_dir: &Path) -> Result<BTreeMap<String, String>> {
+    let path = summaries_path(base_dir);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+}
+
+fn store(base_dir: &Path, article_id: &str, summary: &str) -> Result<()> {
+    let mut all = summaries(base_dir)?;
+    all.insert(article_id.to_string(), summary.to_string());
+    let path = summaries_path(base_dir);
+    fs::create_dir_all(base_dir.join("state"))?;
+    fs::write(&path, serde_json::to_string_pretty(&all)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Runs `future` to completion from synchronous code. The viewer and note
+/// creation run inside the CLI's runtime, which can't be blocked on, so the
+/// request gets a runtime of its own on a scoped thread.
+pub(crate) fn blocking<T: Send>(
+    future: impl std::future::Future<Output = Result<T>> + Send,
+) -> Result<T> {
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()?
+                    .block_on(future)
+            })
+            .join()
+            .map_err(|_| anyhow!("Request thread panicked"))?
+    })
+}
+
+/// The article as plain text, cut to `MAX_INPUT_CHARS`
+fn article_text(article: &FeedItem) -> String {
+    let html = article
+        .content
+        .as_deref()
+        .or(article.description.as_deref())
+        .unwrap_or("");
+    let text = html2text::from_read(html.as_bytes(), 10_000);
+    let text: String = text.chars().take(MAX_INPUT_CHARS).collect();
+    format!("{}\n\n{}", article.title, text.trim())
+}
+
+/// The chat completions request for summarizing an article
+fn request_body(config: &SummarizerConfig, article: &FeedItem) -> serde_json::Value {
+    json!({
+        "model": config.model,
+        "stream": false,
+        "temperature": 0.2,
+        "messages": [
+            { "role": "system", "content": config.prompt },
+            { "role": "user", "content": article_text(article) },
+        ],
+    })
+}
+
+/// Asks the summarizer for the article's summary
+pub async fn request(config: &SummarizerConfig, article: &FeedItem) -> Result<String> {
+    let url = config
+        .url
+        .as_deref()
+        .ok_or_else(|| anyhow!("No [summarizer] url configured"))?;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.timeout_secs))
+        .build()?;
+    let mut request = client.post(url).json(&request_body(config, article));
+    if let Some(key) = &config.api_key {
+        request = request.bearer_auth(key);
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("Summarizer at {} unreachable", url))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow!(
+            "Summarizer answered {}: {}",
+            status,
+            body.chars().take(200).collect::<String>()
+        ));
+    }
+    let reply: serde_json::Value = response
+        .json()
+        .await
+        .context("Summarizer sent invalid JSON")?;
+    let content = reply["choices"][0]["message"]["content"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Summarizer reply has no choices[0].message.content"))?;
+    bullets(content)
+}
+
+/// The first three bullets (or lines) of a reply, as `- ` lines
+pub fn bullets(reply: &str) -> Result<String> {
+    let lines: Vec<String> = reply
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            let line = line
+                .strip_prefix("- ")
+                .or_else(|| line.strip_prefix("* "))
+                .or_else(|| line.strip_prefix("• "))
+                .unwrap_or_else(|| {
+                    // "1. " and "1) "
+                    let digits = line.chars().take_while(char::is_ascii_digit).count();
+                    match line[digits..]
+                        .strip_prefix(". ")
+                        .or(line[digits..].strip_prefix(") "))
+                    {
+                        Some(rest) if digits > 0 => rest,
+                        _ => line,
+                    }
+                });
+            line.trim().to_string()
+        })
+        .filter(|line| !line.is_empty() && !line.ends_with(':'))
+        .take(BULLETS)
+        .map(|line| format!("- {}", line))
+        .collect();
+    if lines.is_empty() {
+        return Err(anyhow!("Summarizer sent an empty summary"));
+    }
+    Ok(lines.join("\n"))
+}
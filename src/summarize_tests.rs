@@ -0,0 +1,84 @@
+use super::*;
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+fn article() -> FeedItem {
+    FeedItem {
+        id: "post-1".to_string(),
+        feed_url: "https://example.com/feed".to_string(),
+        title: "Async Rust".to_string(),
+        link: "https://example.com/async".to_string(),
+        content: Some("<p>Futures are lazy.</p>".to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_bullets() {
+    assert_eq!(
+        bullets("Here is the summary:\n\n* One.\n2. Two.\n• Three.\n- Four.").unwrap(),
+        "- One.\n- Two.\n- Three."
+    );
+    assert_eq!(bullets("Just a sentence.").unwrap(), "- Just a sentence.");
+    assert!(bullets("\n  \n").is_err());
+}
+
+#[test]
+fn test_request_body() {
+    let config = SummarizerConfig::default();
+    let body = request_body(&config, &article());
+    assert_eq!(body["model"], "llama3.2");
+    assert_eq!(body["messages"][0]["content"], config.prompt);
+    assert_eq!(
+        body["messages"][1]["content"],
+        "Async Rust\n\nFutures are lazy."
+    );
+}
+
+#[test]
+fn test_summaries_stored_per_article() {
+    let temp_dir = TempDir::new().unwrap();
+    assert_eq!(stored(temp_dir.path(), "post-1"), None);
+    store(temp_dir.path(), "post-1", "- One.").unwrap();
+    store(temp_dir.path(), "post-2", "- Two.").unwrap();
+    assert_eq!(stored(temp_dir.path(), "post-1").as_deref(), Some("- One."));
+}
+
+#[tokio::test]
+async fn test_request_reads_chat_completion() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!(
+        "http://{}/v1/chat/completions",
+        listener.local_addr().unwrap()
+    );
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = vec![0; 65536];
+        let n = socket.read(&mut request).await.unwrap();
+        let request = String::from_utf8_lossy(&request[..n]).to_string();
+        let body = r#"{"choices":[{"message":{"role":"assistant","content":"- Futures are lazy.\n- Executors poll them.\n- Nothing runs until awaited.\n- Extra."}}]}"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        socket.write_all(response.as_bytes()).await.unwrap();
+        request
+    });
+
+    let config = SummarizerConfig {
+        url: Some(url),
+        api_key: Some("secret".to_string()),
+        ..Default::default()
+    };
+    let summary = request(&config, &article()).await.unwrap();
+    assert_eq!(
+        summary,
+        "- Futures are lazy.\n- Executors poll them.\n- Nothing runs until awaited."
+    );
+    let request = server.await.unwrap();
+    assert!(request.starts_with("POST /v1/chat/completions"));
+    assert!(request
+        .to_lowercase()
+        .contains("authorization: bearer secret"));
+}
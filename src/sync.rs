@@ -0,0 +1,428 @@
+//! Two-way sync with a feed reader service
+//!
+//! `zetrss sync` keeps the cache in step with an account on a feed reader
+//! server (`[sync]`). Each service is a `SyncBackend`: the Google Reader API
+//! of FreshRSS and others (`greader`) or Tiny Tiny RSS (`ttrss`). `sync`
+//! does the rest for any of them:
+//!
+//! 1. The account's subscriptions are read; the folders (TT-RSS: the
+//!    category) a feed is filed in become the tags of its articles.
+//! 2. Articles the server got since the last sync are stored. One already
+//!    cached from fetching its feed directly (same feed and link) is linked
+//!    to its remote copy instead of being stored twice.
+//! 3. Read and starred state is reconciled for every linked article: the side
+//!    that changed a flag since the last sync wins, the local side when both
+//!    did, and the outcome is applied locally and sent to the server.
+//!
+//! Where the next pull starts and each linked article's remote ID and
+//! agreed state are kept in `state/sync.json`.
+
+use crate::cache::TextCache;
+use crate::config::SyncConfig;
+use crate::filter::{ArticleFilter, ArticleQuery};
+use crate::greader::GReader;
+use crate::models::{Feed, FeedItem};
+use crate::ttrss::TtRss;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+#[path = "sync_tests.rs"]
+mod tests;
+
+/// Name of the sync state in the state directory
+pub const SYNC_FILE: &str = "sync.json";
+
+/// Environment variable holding the sync password, instead of `[sync] password`
+pub const PASSWORD_ENV: &str = "ZETRSS_SYNC_PASSWORD";
+
+/// The API the sync server speaks (`[sync] service`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncService {
+    /// Google Reader API: FreshRSS, BazQux, Inoreader, Miniflux
+    #[default]
+    Greader,
+    /// Tiny Tiny RSS
+    Ttrss,
+}
+
+/// A feed the account is subscribed to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subscription {
+    /// The server's ID for the feed, which articles name as their origin
+    pub remote_id: String,
+    pub url: String,
+    pub title: String,
+    /// Folders the feed is filed in
+    pub folders: Vec<String>,
+}
+
+/// An article pulled from the server
+#[derive(Debug, Clone)]
+pub struct RemoteArticle {
+    pub remote_id: String,
+    /// `Subscription::remote_id` of the article's feed
+    pub feed_id: String,
+    /// The article; its `feed_url` is empty unless the server gave one
+    pub article: FeedItem,
+}
+
+/// A feed reader service the cache is synced with
+/// Each implementation (`GReader`, `TtRss`) only speaks its service's API;
+/// `sync` stores, links and reconciles the same way for all of them
+pub trait SyncBackend: Send + Sync {
+    /// The account's feeds, with the folders they're filed in
+    fn subscriptions(&self) -> impl Future<Output = Result<Vec<Subscription>>> + Send;
+
+    /// Articles the server got since `cursor`, or on a first sync (no
+    /// `cursor`) those from after `oldest`; the cursor's meaning is up to the
+    /// backend
+    fn pull(
+        &self,
+        cursor: Option<&str>,
+        oldest: DateTime<Utc>,
+    ) -> impl Future<Output = Result<Pull>> + Send;
+
+    /// Which articles are unread and starred on the server
+    fn remote_state(&self) -> impl Future<Output = Result<RemoteState>> + Send;
+
+    /// Sends state changes made in the cache
+    fn push(&self, changes: &Changes) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// What `SyncBackend::pull` got
+#[derive(Debug, Clone, Default)]
+pub struct Pull {
+    pub articles: Vec<RemoteArticle>,
+    /// Where the next pull starts, stored once the sync succeeds
+    pub cursor: Option<String>,
+}
+
+/// Remote IDs of the unread and of the starred articles on the server
+#[derive(Debug, Clone, Default)]
+pub struct RemoteState {
+    pub unread: HashSet<String>,
+    pub starred: HashSet<String>,
+}
+
+impl RemoteState {
+    pub fn flags(&self, remote_id: &str) -> Flags {
+        Flags {
+            read: !self.unread.contains(remote_id),
+            starred: self.starred.contains(remote_id),
+        }
+    }
+}
+
+/// An article's read and starred state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Flags {
+    pub read: bool,
+    pub starred: bool,
+}
+
+/// A cached article linked to its copy on the server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncedItem {
+    pub remote_id: String,
+    /// The state both sides agreed on at the last sync
+    #[serde(flatten)]
+    pub flags: Flags,
+}
+
+/// What `state/sync.json` remembers between syncs
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    /// Where the next pull starts: when the last one started (Unix time) for
+    /// Google Reader servers, the newest article ID seen for TT-RSS
+    pub cursor: Option<String>,
+    /// Linked articles by local ID
+    pub items: BTreeMap<String, SyncedItem>,
+}
+
+/// What a sync did
+#[derive(Debug, Default, Serialize)]
+pub struct SyncReport {
+    pub subscriptions: usize,
+    /// Articles stored from the server
+    pub added: usize,
+    /// Articles newly linked to their remote copy, stored or already cached
+    pub linked: usize,
+    /// Local articles whose state changed to match the server
+    pub updated: usize,
+    /// State changes sent to the server
+    pub pushed: usize,
+}
+
+/// State changes to send, as remote article IDs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changes {
+    pub read: Vec<String>,
+    pub unread: Vec<String>,
+    pub starred: Vec<String>,
+    pub unstarred: Vec<String>,
+}
+
+impl Changes {
+    pub fn len(&self) -> usize {
+        self.read.len() + self.unread.len() + self.starred.len() + self.unstarred.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The state both sides should settle on. A flag changed on one side since
+/// the `last` sync takes that side's value, the local one when both changed;
+/// an article never synced before is read or starred if either side says so.
+pub fn merge(last: Option<Flags>, local: Flags, remote: Flags) -> Flags {
+    let flag = |last: Option<bool>, local: bool, remote: bool| match last {
+        None => local || remote,
+        Some(last) if local != last => local,
+        Some(_) => remote,
+    };
+    Flags {
+        read: flag(last.map(|f| f.read), local.read, remote.read),
+        starred: flag(last.map(|f| f.starred), local.starred, remote.starred),
+    }
+}
+
+fn state_path(base_dir: &Path) -> PathBuf {
+    base_dir.join("state").join(SYNC_FILE)
+}
+
+/// The sync state; empty before the first sync
+pub fn load_state(base_dir: &Path) -> Result<SyncState> {
+    let path = state_path(base_dir);
+    if !path.exists() {
+        return Ok(SyncState::default());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+}
+
+fn save_state(base_dir: &Path, state: &SyncState) -> Result<()> {
+    let path = state_path(base_dir);
+    fs::create_dir_all(base_dir.join("state"))?;
+    fs::write(&path, serde_json::to_string_pretty(state)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Syncs the cache with the server configured in `[sync]`
+pub async fn run(cache: &TextCache, config: &SyncConfig) -> Result<SyncReport> {
+    let url = config
+        .url
+        .as_deref()
+        .ok_or_else(|| anyhow!("No [sync] url configured"))?;
+    let username = config
+        .username
+        .as_deref()
+        .ok_or_else(|| anyhow!("No [sync] username configured"))?;
+    let password = std::env::var(PASSWORD_ENV)
+        .ok()
+        .or_else(|| config.password.clone())
+        .ok_or_else(|| anyhow!("No sync password: set ${} or [sync] password", PASSWORD_ENV))?;
+
+    let oldest = chrono::Duration::try_days(i64::from(config.initial_days))
+        .and_then(|days| Utc::now().checked_sub_signed(days))
+        .ok_or_else(|| anyhow!("[sync] initial_days reaches too far back"))?;
+    match config.service {
+        SyncService::Greader => {
+            let backend = GReader::login(url, username, &password).await?;
+            sync(cache, &backend, oldest).await
+        }
+        SyncService::Ttrss => {
+            let backend = TtRss::login(url, username, &password).await?;
+            sync(cache, &backend, oldest).await
+        }
+    }
+}
+
+/// Syncs the cache with `backend`; a first sync pulls articles from after `oldest`
+pub async fn sync(
+    cache: &TextCache,
+    backend: &impl SyncBackend,
+    oldest: DateTime<Utc>,
+) -> Result<SyncReport> {
+    let mut state = load_state(cache.base_dir())?;
+    let mut report = SyncReport::default();
+
+    let subscriptions = backend.subscriptions().await?;
+    report.subscriptions = subscriptions.len();
+    let pull = backend.pull(state.cursor.as_deref(), oldest).await?;
+    let (links, added) = store_items(cache, &state, &subscriptions, pull.articles)?;
+    report.added = added;
+    report.linked = links.len();
+
+    let remote = backend.remote_state().await?;
+    let (changes, updated) = reconcile(cache, &mut state, links, |remote_id| {
+        remote.flags(remote_id)
+    })?;
+    report.updated = updated;
+    backend.push(&changes).await?;
+    report.pushed = changes.len();
+
+    // Only a sync that got through moves the cursor, so a failed one is redone
+    state.cursor = pull.cursor;
+    save_state(cache.base_dir(), &state)?;
+    Ok(report)
+}
+
+/// Stores the pulled articles that aren't linked yet, grouped by feed
+/// Returns the new links (local ID, remote ID) and how many articles were stored
+fn store_items(
+    cache: &TextCache,
+    state: &SyncState,
+    subscriptions: &[Subscription],
+    items: Vec<RemoteArticle>,
+) -> Result<(Vec<(String, String)>, usize)> {
+    let linked: HashSet<&str> = state
+        .items
+        .values()
+        .map(|item| item.remote_id.as_str())
+        .collect();
+    // Cached article IDs by link, per feed
+    let mut cached: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut feeds: BTreeMap<String, Feed> = BTreeMap::new();
+    let mut links = Vec::new();
+
+    for item in items {
+        if linked.contains(item.remote_id.as_str()) {
+            continue;
+        }
+        let subscription = subscriptions.iter().find(|s| s.remote_id == item.feed_id);
+        let mut article = item.article;
+        if let Some(subscription) = subscription {
+            article.feed_url = subscription.url.clone();
+            article.tags = subscription.folders.clone();
+        }
+        if article.feed_url.is_empty() {
+            tracing::warn!("Skipping {}: unknown feed {}", item.remote_id, item.feed_id);
+            continue;
+        }
+        let feed_url = article.feed_url.clone();
+
+        if !cached.contains_key(&feed_url) {
+            let query = ArticleQuery {
+                filter: ArticleFilter {
+                    feed: Some(feed_url.clone()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let by_link = cache
+                .get_articles(&query)?
+                .into_iter()
+                .filter(|a| !a.link.is_empty())
+                .map(|a| (a.link, a.id))
+                .collect();
+            cached.insert(feed_url.clone(), by_link);
+        }
+        if let Some(local_id) = cached[&feed_url].get(&article.link) {
+            links.push((local_id.clone(), item.remote_id));
+            continue;
+        }
+
+        links.push((article.id.clone(), item.remote_id));
+        feeds
+            .entry(feed_url.clone())
+            .or_insert_with(|| Feed {
+                title: subscription
+                    .map(|s| s.title.clone())
+                    .filter(|t| !t.is_empty())
+                    .unwrap_or_else(|| feed_url.clone()),
+                url: feed_url,
+                description: None,
+                last_fetched: Some(Utc::now()),
+                items: Vec::new(),
+                bytes: 0,
+            })
+            .items
+            .push(article);
+    }
+
+    let mut added = 0;
+    for feed in feeds.values() {
+        added += cache.store_feed(feed)?;
+    }
+    Ok((links, added))
+}
+
+/// Settles every linked article's state (see `merge`), applying the outcome
+/// to the cache; `remote` gives an article's state on the server by remote ID
+/// Returns the changes to send and how many cached articles changed
+fn reconcile(
+    cache: &TextCache,
+    state: &mut SyncState,
+    links: Vec<(String, String)>,
+    remote: impl Fn(&str) -> Flags,
+) -> Result<(Changes, usize)> {
+    let local: HashMap<String, Flags> = cache
+        .get_articles(&ArticleQuery::default())?
+        .into_iter()
+        .map(|a| {
+            let flags = Flags {
+                read: a.read,
+                starred: a.starred,
+            };
+            (a.id, flags)
+        })
+        .collect();
+    let new: HashSet<String> = links.iter().map(|(id, _)| id.clone()).collect();
+    for (id, remote_id) in links {
+        state.items.insert(
+            id,
+            SyncedItem {
+                remote_id,
+                flags: Flags::default(),
+            },
+        );
+    }
+
+    let mut changes = Changes::default();
+    let mut updated = 0;
+    // Articles deleted, archived or never stored are no longer synced
+    state.items.retain(|id, _| local.contains_key(id));
+    for (id, synced) in state.items.iter_mut() {
+        let local = local[id];
+        let remote = remote(&synced.remote_id);
+        let last = (!new.contains(id)).then_some(synced.flags);
+        let agreed = merge(last, local, remote);
+
+        if agreed.read != local.read {
+            if agreed.read {
+                cache.mark_as_read(id)?;
+            } else {
+                cache.mark_as_unread(id)?;
+            }
+        }
+        if agreed.starred != local.starred {
+            cache.toggle_star(id)?;
+        }
+        if agreed != local {
+            updated += 1;
+        }
+
+        let remote_id = synced.remote_id.clone();
+        match (agreed.read, remote.read) {
+            (true, false) => changes.read.push(remote_id.clone()),
+            (false, true) => changes.unread.push(remote_id.clone()),
+            _ => {}
+        }
+        match (agreed.starred, remote.starred) {
+            (true, false) => changes.starred.push(remote_id),
+            (false, true) => changes.unstarred.push(remote_id),
+            _ => {}
+        }
+        synced.flags = agreed;
+    }
+    Ok((changes, updated))
+}
@@ -0,0 +1,524 @@
+use super::*;
+use crate::greader;
+use crate::models::FeedItem;
+use axum::extract::{Query, State};
+use axum::routing::{get, post};
+use axum::{Form, Json, Router};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+fn flags(read: bool, starred: bool) -> Flags {
+    Flags { read, starred }
+}
+
+#[test]
+fn test_merge() {
+    // Never synced: either side's read or star sticks
+    assert_eq!(
+        merge(None, flags(true, false), flags(false, true)),
+        flags(true, true)
+    );
+    // Only the server changed
+    assert_eq!(
+        merge(
+            Some(flags(false, false)),
+            flags(false, false),
+            flags(true, true)
+        ),
+        flags(true, true)
+    );
+    // Only the cache changed
+    assert_eq!(
+        merge(
+            Some(flags(true, true)),
+            flags(false, true),
+            flags(true, true)
+        ),
+        flags(false, true)
+    );
+    // Both changed: the cache wins
+    assert_eq!(
+        merge(
+            Some(flags(false, false)),
+            flags(false, true),
+            flags(true, false)
+        ),
+        flags(true, true)
+    );
+}
+
+/// A backend holding one article, recording the cursors and changes it gets
+#[derive(Default)]
+struct FakeBackend {
+    state: Mutex<RemoteState>,
+    cursors: Mutex<Vec<Option<String>>>,
+    pushed: Mutex<Vec<Changes>>,
+}
+
+impl SyncBackend for FakeBackend {
+    async fn subscriptions(&self) -> Result<Vec<Subscription>> {
+        Ok(vec![Subscription {
+            remote_id: "f1".to_string(),
+            url: "https://example.com/feed".to_string(),
+            title: "Example".to_string(),
+            folders: Vec::new(),
+        }])
+    }
+
+    async fn pull(&self, cursor: Option<&str>, _oldest: DateTime<Utc>) -> Result<Pull> {
+        let mut cursors = self.cursors.lock().unwrap();
+        cursors.push(cursor.map(str::to_string));
+        let article = FeedItem {
+            id: "r1".to_string(),
+            feed_url: String::new(),
+            link: "https://example.com/r1".to_string(),
+            ..cached_article()
+        };
+        Ok(Pull {
+            articles: vec![RemoteArticle {
+                remote_id: "r1".to_string(),
+                feed_id: "f1".to_string(),
+                article,
+            }],
+            cursor: Some(format!("after-pull-{}", cursors.len())),
+        })
+    }
+
+    async fn remote_state(&self) -> Result<RemoteState> {
+        Ok(self.state.lock().unwrap().clone())
+    }
+
+    async fn push(&self, changes: &Changes) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        for id in &changes.read {
+            state.unread.remove(id);
+        }
+        state.unread.extend(changes.unread.iter().cloned());
+        state.starred.extend(changes.starred.iter().cloned());
+        for id in &changes.unstarred {
+            state.starred.remove(id);
+        }
+        self.pushed.lock().unwrap().push(changes.clone());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_sync_merges_changes_and_passes_the_cursor() {
+    let dir = TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(dir.path().to_path_buf()).unwrap();
+    let backend = FakeBackend::default();
+    backend
+        .state
+        .lock()
+        .unwrap()
+        .unread
+        .insert("r1".to_string());
+
+    let report = sync(&cache, &backend, Utc::now()).await.unwrap();
+    assert_eq!((report.added, report.linked, report.pushed), (1, 1, 0));
+
+    // Read here and starred there since: both changes stick
+    cache.mark_as_read("r1").unwrap();
+    backend
+        .state
+        .lock()
+        .unwrap()
+        .starred
+        .insert("r1".to_string());
+    sync(&cache, &backend, Utc::now()).await.unwrap();
+    let article = cache.get_article_by_id("r1").unwrap().unwrap();
+    assert!(article.read && article.starred);
+    assert_eq!(backend.pushed.lock().unwrap()[1].read, vec!["r1"]);
+
+    // Unstarred on both sides, and unread again here: only that is sent
+    cache.toggle_star("r1").unwrap();
+    cache.mark_as_unread("r1").unwrap();
+    backend.state.lock().unwrap().starred.clear();
+    let report = sync(&cache, &backend, Utc::now()).await.unwrap();
+    assert_eq!(report.pushed, 1);
+    assert_eq!(backend.pushed.lock().unwrap()[2].unread, vec!["r1"]);
+    let article = cache.get_article_by_id("r1").unwrap().unwrap();
+    assert!(!article.read && !article.starred);
+
+    assert_eq!(
+        *backend.cursors.lock().unwrap(),
+        vec![
+            None,
+            Some("after-pull-1".to_string()),
+            Some("after-pull-2".to_string())
+        ]
+    );
+}
+
+/// A Google Reader API server holding two articles of one feed
+#[derive(Default)]
+struct FakeServer {
+    unread: BTreeSet<u64>,
+    starred: BTreeSet<u64>,
+    edits: Vec<Vec<(String, String)>>,
+}
+
+fn item_id(n: u64) -> String {
+    format!("tag:google.com,2005:reader/item/{:016x}", n)
+}
+
+fn item_number(id: &str) -> u64 {
+    u64::from_str_radix(id.rsplit('/').next().unwrap(), 16).unwrap()
+}
+
+async fn serve(server: Arc<Mutex<FakeServer>>) -> String {
+    let router = Router::new()
+        .route(
+            "/api/accounts/ClientLogin",
+            post(|| async { "SID=none\nAuth=secret\n" }),
+        )
+        .route("/api/reader/api/0/token", get(|| async { "tok\n" }))
+        .route(
+            "/api/reader/api/0/subscription/list",
+            get(|| async {
+                Json(json!({"subscriptions": [{
+                    "id": "feed/1",
+                    "title": "Example",
+                    "url": "https://example.com/feed",
+                    "categories": [{"id": "user/-/label/Tech", "label": "Tech"}],
+                }]}))
+            }),
+        )
+        .route(
+            "/api/reader/api/0/stream/contents/*stream",
+            get(|| async {
+                let item = |n: u64, slug: &str| {
+                    json!({
+                        "id": item_id(n),
+                        "title": format!("Post {}", n),
+                        "published": 1709285400,
+                        "alternate": [{"href": format!("https://example.com/{}", slug)}],
+                        "summary": {"content": "<p>Hello</p>"},
+                        "origin": {"streamId": "feed/1"},
+                    })
+                };
+                Json(json!({"items": [item(2, "b"), item(1, "a")]}))
+            }),
+        )
+        .route(
+            "/api/reader/api/0/stream/items/ids",
+            get(
+                |State(server): State<Arc<Mutex<FakeServer>>>,
+                 Query(query): Query<HashMap<String, String>>| async move {
+                    let server = server.lock().unwrap();
+                    let ids = if query["s"] == greader::STARRED {
+                        &server.starred
+                    } else {
+                        &server.unread
+                    };
+                    // One ID per page, so clients must follow `continuation`
+                    let start: usize = query.get("c").map_or(0, |c| c.parse().unwrap());
+                    let refs: Vec<_> = ids
+                        .iter()
+                        .skip(start)
+                        .take(1)
+                        .map(|n| json!({"id": (*n as i64).to_string()}))
+                        .collect();
+                    if start + 1 < ids.len() {
+                        Json(json!({ "itemRefs": refs, "continuation": (start + 1).to_string() }))
+                    } else {
+                        Json(json!({ "itemRefs": refs }))
+                    }
+                },
+            ),
+        )
+        .route(
+            "/api/reader/api/0/edit-tag",
+            post(
+                |State(server): State<Arc<Mutex<FakeServer>>>,
+                 Form(form): Form<Vec<(String, String)>>| async move {
+                    let mut server = server.lock().unwrap();
+                    for (key, value) in &form {
+                        let tag = form.iter().find(|(k, _)| k == "a" || k == "r").unwrap();
+                        if key != "i" {
+                            continue;
+                        }
+                        let set = if tag.1 == greader::READ {
+                            &mut server.unread
+                        } else {
+                            &mut server.starred
+                        };
+                        // Adding `read` takes the article out of the unread set
+                        let insert = (tag.0 == "a") != (tag.1 == greader::READ);
+                        if insert {
+                            set.insert(item_number(value));
+                        } else {
+                            set.remove(&item_number(value));
+                        }
+                    }
+                    server.edits.push(form);
+                    "OK"
+                },
+            ),
+        )
+        .with_state(server);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/api", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    url
+}
+
+fn cached_article() -> FeedItem {
+    FeedItem {
+        id: "https://example.com/a".to_string(),
+        feed_url: "https://example.com/feed".to_string(),
+        title: "Post 1".to_string(),
+        link: "https://example.com/a".to_string(),
+        content: Some("<p>Hello</p>".to_string()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_sync_with_google_reader_api() {
+    let dir = TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(dir.path().to_path_buf()).unwrap();
+    // Post 1 was fetched from the feed directly and read here
+    cache
+        .store_feed(&Feed {
+            url: "https://example.com/feed".to_string(),
+            title: "Example".to_string(),
+            description: None,
+            last_fetched: None,
+            items: vec![cached_article()],
+            bytes: 0,
+        })
+        .unwrap();
+    cache.mark_as_read("https://example.com/a").unwrap();
+
+    let server = Arc::new(Mutex::new(FakeServer {
+        unread: BTreeSet::from([1, 2]),
+        starred: BTreeSet::from([2]),
+        ..Default::default()
+    }));
+    let config = SyncConfig {
+        url: Some(serve(server.clone()).await),
+        username: Some("me".to_string()),
+        password: Some("pw".to_string()),
+        ..Default::default()
+    };
+
+    let report = run(&cache, &config).await.unwrap();
+    assert_eq!(report.subscriptions, 1);
+    assert_eq!(report.added, 1);
+    assert_eq!(report.linked, 2);
+    assert_eq!(report.updated, 1);
+    assert_eq!(report.pushed, 1);
+
+    // Post 1 isn't stored twice, and its read state went to the server
+    assert_eq!(
+        cache.get_articles(&ArticleQuery::default()).unwrap().len(),
+        2
+    );
+    let post2 = cache.get_article_by_id(&item_id(2)).unwrap().unwrap();
+    assert!(post2.starred && !post2.read);
+    assert_eq!(post2.tags, vec!["Tech"]);
+    assert_eq!(
+        server.lock().unwrap().edits,
+        vec![vec![
+            ("i".to_string(), item_id(1)),
+            ("a".to_string(), greader::READ.to_string()),
+            ("T".to_string(), "tok".to_string()),
+        ]]
+    );
+    assert_eq!(server.lock().unwrap().unread, BTreeSet::from([2]));
+
+    // Post 2 is read on the server and unstarred here
+    server.lock().unwrap().unread.clear();
+    cache.toggle_star(&item_id(2)).unwrap();
+    let report = run(&cache, &config).await.unwrap();
+    assert_eq!((report.added, report.linked), (0, 0));
+    assert_eq!((report.updated, report.pushed), (1, 1));
+    let post2 = cache.get_article_by_id(&item_id(2)).unwrap().unwrap();
+    assert!(post2.read && !post2.starred);
+    assert!(server.lock().unwrap().starred.is_empty());
+
+    let state = load_state(dir.path()).unwrap();
+    assert_eq!(state.items.len(), 2);
+    assert_eq!(state.items["https://example.com/a"].remote_id, item_id(1));
+    assert!(state.cursor.is_some());
+}
+
+/// A TT-RSS server with the same two articles, numbered 1 and 2
+#[derive(Default)]
+struct FakeTtRss {
+    unread: BTreeSet<u64>,
+    starred: BTreeSet<u64>,
+    /// `updateArticle` calls: article IDs, mode, field
+    updates: Vec<(String, u64, u64)>,
+}
+
+async fn serve_ttrss(server: Arc<Mutex<FakeTtRss>>) -> String {
+    let router = Router::new()
+        .route(
+            "/tt-rss/api/",
+            post(
+                |State(server): State<Arc<Mutex<FakeTtRss>>>, Json(body): Json<Value>| async move {
+                    let op = body["op"].as_str().unwrap();
+                    if op == "login" {
+                        return Json(json!({"status": 0, "content": {"session_id": "s1"}}));
+                    }
+                    if body["sid"] != "s1" {
+                        return Json(json!({"status": 1, "content": {"error": "NOT_LOGGED_IN"}}));
+                    }
+                    let mut server = server.lock().unwrap();
+                    let content = match op {
+                        "getCategories" => json!([{"id": "1", "title": "Tech"}]),
+                        "getFeeds" => json!([{
+                            "id": 7,
+                            "title": "Example",
+                            "feed_url": "https://example.com/feed",
+                            "cat_id": 1,
+                        }]),
+                        "getHeadlines" if body["skip"] != 0 => json!([]),
+                        "getHeadlines" if body["view_mode"] == "unread" => {
+                            json!(server
+                                .unread
+                                .iter()
+                                .map(|id| json!({"id": id}))
+                                .collect::<Vec<_>>())
+                        }
+                        "getHeadlines" if body["feed_id"] == -1 => {
+                            json!(server
+                                .starred
+                                .iter()
+                                .map(|id| json!({"id": id}))
+                                .collect::<Vec<_>>())
+                        }
+                        "getHeadlines" => {
+                            let since = body["since_id"].as_u64().unwrap();
+                            let headlines: Vec<_> = [(2, "b"), (1, "a")]
+                                .into_iter()
+                                .filter(|(id, _)| *id > since)
+                                .map(|(id, slug)| {
+                                    json!({
+                                        "id": id,
+                                        "title": format!("Post {}", id),
+                                        "link": format!("https://example.com/{}", slug),
+                                        "updated": Utc::now().timestamp(),
+                                        "content": "<p>Hello</p>",
+                                        "feed_id": "7",
+                                    })
+                                })
+                                .collect();
+                            json!(headlines)
+                        }
+                        "updateArticle" => {
+                            let ids = body["article_ids"].as_str().unwrap().to_string();
+                            let mode = body["mode"].as_u64().unwrap();
+                            let field = body["field"].as_u64().unwrap();
+                            for id in ids.split(',') {
+                                let id: u64 = id.parse().unwrap();
+                                let set = if field == 2 {
+                                    &mut server.unread
+                                } else {
+                                    &mut server.starred
+                                };
+                                if mode == 1 {
+                                    set.insert(id);
+                                } else {
+                                    set.remove(&id);
+                                }
+                            }
+                            server.updates.push((ids, mode, field));
+                            json!({"status": "OK"})
+                        }
+                        _ => json!({"error": "UNKNOWN_METHOD"}),
+                    };
+                    Json(json!({"status": 0, "content": content}))
+                },
+            ),
+        )
+        .with_state(server);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/tt-rss/api/", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    url
+}
+
+#[tokio::test]
+async fn test_sync_with_tiny_tiny_rss() {
+    let dir = TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(dir.path().to_path_buf()).unwrap();
+    cache
+        .store_feed(&Feed {
+            url: "https://example.com/feed".to_string(),
+            title: "Example".to_string(),
+            description: None,
+            last_fetched: None,
+            items: vec![cached_article()],
+            bytes: 0,
+        })
+        .unwrap();
+    cache.mark_as_read("https://example.com/a").unwrap();
+
+    let server = Arc::new(Mutex::new(FakeTtRss {
+        unread: BTreeSet::from([1, 2]),
+        starred: BTreeSet::from([2]),
+        ..Default::default()
+    }));
+    let config = SyncConfig {
+        service: SyncService::Ttrss,
+        url: Some(serve_ttrss(server.clone()).await),
+        username: Some("me".to_string()),
+        password: Some("pw".to_string()),
+        ..Default::default()
+    };
+
+    let report = run(&cache, &config).await.unwrap();
+    assert_eq!(
+        (report.subscriptions, report.added, report.linked),
+        (1, 1, 2)
+    );
+    assert_eq!((report.updated, report.pushed), (1, 1));
+    let post2 = cache
+        .get_article_by_id("https://example.com/b")
+        .unwrap()
+        .unwrap();
+    assert!(post2.starred && !post2.read);
+    assert_eq!(post2.tags, vec!["Tech"]);
+    // Marking read sets the unread field (2) to false (0)
+    assert_eq!(
+        server.lock().unwrap().updates,
+        vec![("1".to_string(), 0, 2)]
+    );
+    assert_eq!(load_state(dir.path()).unwrap().cursor.as_deref(), Some("2"));
+
+    server.lock().unwrap().unread.clear();
+    cache.toggle_star("https://example.com/b").unwrap();
+    let report = run(&cache, &config).await.unwrap();
+    assert_eq!((report.added, report.linked), (0, 0));
+    assert_eq!((report.updated, report.pushed), (1, 1));
+    let post2 = cache
+        .get_article_by_id("https://example.com/b")
+        .unwrap()
+        .unwrap();
+    assert!(post2.read && !post2.starred);
+    assert_eq!(
+        server.lock().unwrap().updates.last(),
+        Some(&("2".to_string(), 0, 0))
+    );
+}
+
+#[tokio::test]
+async fn test_initial_days_too_far_back() {
+    let dir = TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(dir.path().to_path_buf()).unwrap();
+    let config = SyncConfig {
+        url: Some("http://127.0.0.1:9".to_string()),
+        username: Some("me".to_string()),
+        password: Some("pw".to_string()),
+        initial_days: u32::MAX,
+        ..Default::default()
+    };
+    let err = run(&cache, &config).await.unwrap_err();
+    assert!(err.to_string().contains("initial_days"), "{}", err);
+}
@@ -0,0 +1,308 @@
+//! Colors and borders of the TUI viewer and article browser
+//!
+//! ```toml
+//! [theme]
+//! preset = "gruvbox"        # default, light, gruvbox or mono
+//! background = "auto"       # auto, dark or light
+//! borders = "rounded"       # plain, rounded, double or thick
+//!
+//! [theme.colors]            # overrides of single preset colors
+//! title = "#88c0d0"
+//! key_bg = "blue"
+//! ```
+//!
+//! Colors are names (`red`, `lightblue`, `darkgray`, ...), `#rrggbb` or
+//! 256-color indexes.
+//!
+//! On a light background the default preset becomes `light` and gruvbox its
+//! light variant. `auto` goes by `COLORFGBG`, which many terminals set, and
+//! assumes dark without it.
+
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::BorderType;
+use serde::{Deserialize, Deserializer};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+#[cfg(test)]
+#[path = "theme_tests.rs"]
+mod tests;
+
+/// The `[theme]` config section
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    pub preset: Preset,
+    pub background: Background,
+    /// Border style of every frame; the preset's if unset
+    pub borders: Option<Borders>,
+    pub colors: ThemeColors,
+}
+
+/// Built-in palettes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    /// Terminal palette colors, as the viewer always used
+    #[default]
+    Default,
+    /// Terminal palette colors readable on a light background
+    Light,
+    /// Warm truecolor palette
+    Gruvbox,
+    /// No colors; bold and reverse video only
+    Mono,
+}
+
+/// Terminal background the palette has to suit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Background {
+    /// Detected from `COLORFGBG`, dark if unknown
+    #[default]
+    Auto,
+    Dark,
+    Light,
+}
+
+impl Background {
+    /// Whether the background is light
+    pub fn is_light(self) -> bool {
+        match self {
+            Background::Auto => detect_light(&|name| std::env::var(name).ok()),
+            Background::Dark => false,
+            Background::Light => true,
+        }
+    }
+}
+
+/// Reads the background from `COLORFGBG` ("fg;bg" or "fg;default;bg"), whose
+/// last field is an ANSI color index: 7 and 9-15 are light
+fn detect_light(var: &dyn Fn(&str) -> Option<String>) -> bool {
+    var("COLORFGBG")
+        .and_then(|value| value.rsplit(';').next()?.trim().parse::<u8>().ok())
+        .is_some_and(|bg| bg == 7 || (9..=15).contains(&bg))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Borders {
+    Plain,
+    Rounded,
+    Double,
+    Thick,
+}
+
+impl From<Borders> for BorderType {
+    fn from(borders: Borders) -> Self {
+        match borders {
+            Borders::Plain => BorderType::Plain,
+            Borders::Rounded => BorderType::Rounded,
+            Borders::Double => BorderType::Double,
+            Borders::Thick => BorderType::Thick,
+        }
+    }
+}
+
+/// Per-element color overrides in `[theme.colors]`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeColors {
+    /// Frame borders
+    pub border: Option<ThemeColor>,
+    /// Border of the focused browser pane
+    pub focused_border: Option<ThemeColor>,
+    /// Article title in the header
+    pub title: Option<ThemeColor>,
+    /// Feed URLs and names
+    pub feed: Option<ThemeColor>,
+    /// Dates, scroll positions and other secondary text
+    pub muted: Option<ThemeColor>,
+    /// Unread markers and prompts
+    pub accent: Option<ThemeColor>,
+    pub star: Option<ThemeColor>,
+    /// Key chips in the footer
+    pub key_fg: Option<ThemeColor>,
+    pub key_bg: Option<ThemeColor>,
+    /// Selected row in the browser lists
+    pub selection_bg: Option<ThemeColor>,
+    /// Current search match
+    pub match_fg: Option<ThemeColor>,
+    pub match_bg: Option<ThemeColor>,
+    pub error: Option<ThemeColor>,
+}
+
+/// A color from the config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThemeColor(pub Color);
+
+impl<'de> Deserialize<'de> for ThemeColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Color::from_str(&name)
+            .map(ThemeColor)
+            .map_err(|_| serde::de::Error::custom(format!("unknown color '{}'", name)))
+    }
+}
+
+/// Resolved styles used by the TUI
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub border_type: BorderType,
+    pub border: Style,
+    pub focused_border: Style,
+    pub title: Style,
+    pub feed: Style,
+    pub muted: Style,
+    pub accent: Style,
+    pub star: Style,
+    pub key: Style,
+    pub selection: Style,
+    pub current_match: Style,
+    /// Search matches other than the current one
+    pub other_match: Style,
+    pub error: Style,
+}
+
+impl Theme {
+    pub fn preset(preset: Preset) -> Self {
+        match preset {
+            Preset::Default => Self {
+                border_type: BorderType::Plain,
+                border: Style::default().fg(Color::Blue),
+                focused_border: Style::default().fg(Color::Cyan),
+                title: Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+                feed: Style::default().fg(Color::Yellow),
+                muted: Style::default().fg(Color::Gray),
+                accent: Style::default().fg(Color::Cyan),
+                star: Style::default().fg(Color::Yellow),
+                key: Style::default().bg(Color::DarkGray).fg(Color::White),
+                selection: Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+                current_match: Style::default().bg(Color::Yellow).fg(Color::Black),
+                other_match: Style::default().bg(Color::DarkGray).fg(Color::White),
+                error: Style::default().fg(Color::Red),
+            },
+            Preset::Light => Self {
+                border_type: BorderType::Plain,
+                border: Style::default().fg(Color::Blue),
+                focused_border: Style::default().fg(Color::Magenta),
+                title: Style::default()
+                    .fg(Color::Blue)
+                    .add_modifier(Modifier::BOLD),
+                feed: Style::default().fg(Color::Magenta),
+                muted: Style::default().fg(Color::DarkGray),
+                accent: Style::default().fg(Color::Blue),
+                star: Style::default().fg(Color::Rgb(0xaf, 0x87, 0x00)),
+                key: Style::default().bg(Color::Gray).fg(Color::Black),
+                selection: Style::default()
+                    .bg(Color::Gray)
+                    .add_modifier(Modifier::BOLD),
+                current_match: Style::default().bg(Color::Yellow).fg(Color::Black),
+                other_match: Style::default().bg(Color::Gray).fg(Color::Black),
+                error: Style::default().fg(Color::Red),
+            },
+            Preset::Gruvbox => Self::gruvbox(false),
+            Preset::Mono => Self {
+                border_type: BorderType::Plain,
+                border: Style::default(),
+                focused_border: Style::default().add_modifier(Modifier::BOLD),
+                title: Style::default().add_modifier(Modifier::BOLD),
+                feed: Style::default().add_modifier(Modifier::ITALIC),
+                muted: Style::default().add_modifier(Modifier::DIM),
+                accent: Style::default().add_modifier(Modifier::BOLD),
+                star: Style::default(),
+                key: Style::default().add_modifier(Modifier::REVERSED),
+                selection: Style::default().add_modifier(Modifier::REVERSED),
+                current_match: Style::default().add_modifier(Modifier::REVERSED),
+                other_match: Style::default().add_modifier(Modifier::UNDERLINED),
+                error: Style::default().add_modifier(Modifier::BOLD),
+            },
+        }
+    }
+
+    /// Gruvbox's dark or light palette
+    fn gruvbox(light: bool) -> Self {
+        let rgb = |dark: u32, light_color: u32| {
+            let c = if light { light_color } else { dark };
+            Color::Rgb((c >> 16) as u8, (c >> 8) as u8, c as u8)
+        };
+        let bg1 = rgb(0x3c3836, 0xebdbb2);
+        let fg = rgb(0xebdbb2, 0x3c3836);
+        let yellow = rgb(0xfabd2f, 0xb57614);
+        let aqua = rgb(0x8ec07c, 0x427b58);
+        Self {
+            border_type: BorderType::Rounded,
+            border: Style::default().fg(rgb(0x665c54, 0xa89984)),
+            focused_border: Style::default().fg(aqua),
+            title: Style::default().fg(aqua).add_modifier(Modifier::BOLD),
+            feed: Style::default().fg(yellow),
+            muted: Style::default().fg(rgb(0xa89984, 0x7c6f64)),
+            accent: Style::default().fg(rgb(0x83a598, 0x076678)),
+            star: Style::default().fg(yellow),
+            key: Style::default().bg(bg1).fg(fg),
+            selection: Style::default().bg(bg1).add_modifier(Modifier::BOLD),
+            current_match: Style::default().bg(yellow).fg(rgb(0x282828, 0xfbf1c7)),
+            other_match: Style::default().bg(bg1).fg(fg),
+            error: Style::default().fg(rgb(0xfb4934, 0x9d0006)),
+        }
+    }
+
+    /// The preset's palette for a light or dark background: the default
+    /// preset turns into `light`, gruvbox into its light variant
+    pub fn preset_for(preset: Preset, light: bool) -> Self {
+        match (preset, light) {
+            (Preset::Default, true) => Self::preset(Preset::Light),
+            (Preset::Gruvbox, true) => Self::gruvbox(true),
+            _ => Self::preset(preset),
+        }
+    }
+
+    /// The preset, adapted to a `light` background or not, with the config's
+    /// overrides applied
+    pub fn from_config(config: &ThemeConfig, light: bool) -> Self {
+        let mut theme = Self::preset_for(config.preset, light);
+        if let Some(borders) = config.borders {
+            theme.border_type = borders.into();
+        }
+
+        let colors = &config.colors;
+        let fg = |style: &mut Style, color: Option<ThemeColor>| {
+            if let Some(ThemeColor(color)) = color {
+                *style = style.fg(color);
+            }
+        };
+        fg(&mut theme.border, colors.border);
+        fg(&mut theme.focused_border, colors.focused_border);
+        fg(&mut theme.title, colors.title);
+        fg(&mut theme.feed, colors.feed);
+        fg(&mut theme.muted, colors.muted);
+        fg(&mut theme.accent, colors.accent);
+        fg(&mut theme.star, colors.star);
+        fg(&mut theme.key, colors.key_fg);
+        fg(&mut theme.current_match, colors.match_fg);
+        fg(&mut theme.error, colors.error);
+
+        let bg = |style: &mut Style, color: Option<ThemeColor>| {
+            if let Some(ThemeColor(color)) = color {
+                *style = style.bg(color);
+            }
+        };
+        bg(&mut theme.key, colors.key_bg);
+        bg(&mut theme.selection, colors.selection_bg);
+        bg(&mut theme.current_match, colors.match_bg);
+        theme
+    }
+}
+
+/// The theme from the process-wide config
+pub fn get() -> &'static Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    THEME.get_or_init(|| {
+        let config = &crate::config::get().theme;
+        Theme::from_config(config, config.background.is_light())
+    })
+}
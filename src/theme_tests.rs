@@ -0,0 +1,73 @@
+use super::*;
+use crate::config::Config;
+
+#[test]
+fn test_default_theme_keeps_classic_colors() {
+    let theme = Theme::from_config(&ThemeConfig::default(), false);
+    assert_eq!(theme, Theme::preset(Preset::Default));
+    assert_eq!(theme.border, Style::default().fg(Color::Blue));
+    assert_eq!(theme.border_type, BorderType::Plain);
+}
+
+#[test]
+fn test_overrides_apply_on_top_of_preset() {
+    let config = Config::parse(
+        r##"
+        [theme]
+        preset = "gruvbox"
+        borders = "double"
+
+        [theme.colors]
+        title = "#88c0d0"
+        key_bg = "blue"
+        selection_bg = "238"
+        "##,
+    )
+    .unwrap();
+    let theme = Theme::from_config(&config.theme, false);
+    let gruvbox = Theme::preset(Preset::Gruvbox);
+
+    assert_eq!(theme.border_type, BorderType::Double);
+    assert_eq!(theme.title.fg, Some(Color::Rgb(0x88, 0xc0, 0xd0)));
+    // Modifiers of the preset survive a color override
+    assert!(theme.title.add_modifier.contains(Modifier::BOLD));
+    assert_eq!(theme.key.bg, Some(Color::Blue));
+    assert_eq!(theme.key.fg, gruvbox.key.fg);
+    assert_eq!(theme.selection.bg, Some(Color::Indexed(238)));
+    assert_eq!(theme.feed, gruvbox.feed);
+}
+
+#[test]
+fn test_light_background_switches_palette() {
+    let env =
+        |value: &'static str| move |name: &str| (name == "COLORFGBG").then(|| value.to_string());
+    assert!(detect_light(&env("0;15")));
+    assert!(detect_light(&env("0;default;7")));
+    assert!(!detect_light(&env("15;0")));
+    assert!(!detect_light(&env("15;default")));
+    assert!(!detect_light(&|_: &str| None));
+
+    let config = Config::parse("[theme]\nbackground = \"light\"").unwrap();
+    assert!(config.theme.background.is_light());
+    let theme = Theme::from_config(&config.theme, true);
+    assert_eq!(theme, Theme::preset(Preset::Light));
+    // No DarkGray-on-White chips on a light background
+    assert_eq!(theme.key, Style::default().bg(Color::Gray).fg(Color::Black));
+    assert_ne!(
+        Theme::preset_for(Preset::Gruvbox, true),
+        Theme::preset(Preset::Gruvbox)
+    );
+    assert_eq!(
+        Theme::preset_for(Preset::Mono, true),
+        Theme::preset(Preset::Mono)
+    );
+}
+
+#[test]
+fn test_bad_theme_values_are_rejected() {
+    let err = Config::parse("[theme.colors]\ntitle = \"blurple\"").unwrap_err();
+    assert!(format!("{:#}", err).contains("unknown color 'blurple'"));
+    assert!(Config::parse("[theme]\npreset = \"neon\"").is_err());
+    assert!(Config::parse("[theme]\nbackground = \"grey\"").is_err());
+    assert!(Config::parse("[theme.colors]\nbackground = \"red\"").is_err());
+}
@@ -0,0 +1,371 @@
+//! Client for the Tiny Tiny RSS API
+//!
+//! TT-RSS answers JSON requests POSTed to `<tt-rss>/api/` (enabled under
+//! Preferences → "Enable API"): `login` returns a session ID sent along with
+//! every other call, feeds are filed in categories, `getHeadlines` pages
+//! through articles at most 200 at a time, and `updateArticle` sets the
+//! unread or starred ("marked") field of many articles in one call. Article
+//! IDs only grow, so the newest one seen is where the next pull starts.
+
+use crate::models::FeedItem;
+use crate::sync::{Changes, Pull, RemoteArticle, RemoteState, Subscription, SyncBackend};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Deserializer};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::time::Duration;
+
+#[cfg(test)]
+#[path = "ttrss_tests.rs"]
+mod tests;
+
+/// The most headlines `getHeadlines` returns per call
+const PAGE_SIZE: usize = 200;
+
+/// Pages read on a first pull, bounding the history pulled from a large
+/// account
+const MAX_PAGES: usize = 50;
+
+/// Pages read catching up since the last pull, or listing a state's IDs;
+/// beyond them the sync fails rather than skip articles or misread state
+const MAX_CATCH_UP_PAGES: usize = 1_000;
+
+/// Articles changed per `updateArticle` call
+const UPDATE_BATCH: usize = 500;
+
+/// Virtual feeds: every article, and the starred ones
+const ALL_ARTICLES: i64 = -4;
+const STARRED: i64 = -1;
+
+/// Feeds of every category, for `getFeeds`
+const ALL_CATEGORIES: i64 = -3;
+
+/// Article fields `updateArticle` sets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Starred = 0,
+    Unread = 2,
+}
+
+/// A signed-in session with a TT-RSS server
+pub struct TtRss {
+    client: reqwest::Client,
+    url: String,
+    session: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Reply {
+    status: i64,
+    #[serde(default)]
+    content: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct Category {
+    #[serde(deserialize_with = "number")]
+    id: i64,
+    title: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawFeed {
+    #[serde(deserialize_with = "number")]
+    id: i64,
+    #[serde(default)]
+    title: String,
+    feed_url: String,
+    #[serde(default, deserialize_with = "number")]
+    cat_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Headline {
+    #[serde(deserialize_with = "number")]
+    id: i64,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    link: String,
+    #[serde(default)]
+    updated: i64,
+    #[serde(default)]
+    author: String,
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default, deserialize_with = "number")]
+    feed_id: i64,
+}
+
+/// Older servers send some IDs as strings
+fn number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Number(n) => n
+            .as_i64()
+            .ok_or_else(|| serde::de::Error::custom("ID out of range")),
+        Value::String(s) => s.parse().map_err(serde::de::Error::custom),
+        other => Err(serde::de::Error::custom(format!(
+            "expected an ID, got {}",
+            other
+        ))),
+    }
+}
+
+impl TtRss {
+    /// Signs in at `url`, the API endpoint (e.g. `https://example.com/tt-rss/api/`)
+    pub async fn login(url: &str, username: &str, password: &str) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent("ZetRss/0.1")
+            .timeout(Duration::from_secs(crate::config::get().fetch.timeout_secs))
+            .build()?;
+        let mut session = Self {
+            client,
+            url: url.to_string(),
+            session: String::new(),
+        };
+        let content = session
+            .call("login", json!({ "user": username, "password": password }))
+            .await?;
+        session.session = content["session_id"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Sign-in to {} returned no session_id", url))?
+            .to_string();
+        Ok(session)
+    }
+
+    /// Calls the API method `op` and returns the reply's content
+    async fn call(&self, op: &str, params: Value) -> Result<Value> {
+        let mut body = params;
+        body["op"] = json!(op);
+        if !self.session.is_empty() {
+            body["sid"] = json!(self.session);
+        }
+        let response = self
+            .client
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await
+            .with_context(|| format!("Sync server at {} unreachable", self.url))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!("{} answered {}", op, status));
+        }
+        let reply: Reply = response
+            .json()
+            .await
+            .with_context(|| format!("{} sent invalid JSON", op))?;
+        if reply.status != 0 {
+            return Err(anyhow!(
+                "{} failed: {}",
+                op,
+                reply.content["error"].as_str().unwrap_or("unknown error")
+            ));
+        }
+        Ok(reply.content)
+    }
+
+    async fn feeds(&self) -> Result<Vec<Subscription>> {
+        let categories: Vec<Category> = serde_json::from_value(
+            self.call("getCategories", json!({ "include_empty": true }))
+                .await?,
+        )
+        .context("getCategories sent unexpected JSON")?;
+        let feeds: Vec<RawFeed> = serde_json::from_value(
+            self.call("getFeeds", json!({ "cat_id": ALL_CATEGORIES }))
+                .await?,
+        )
+        .context("getFeeds sent unexpected JSON")?;
+        Ok(subscriptions(categories, feeds))
+    }
+
+    async fn headlines(&self, params: Value) -> Result<Vec<Headline>> {
+        serde_json::from_value(self.call("getHeadlines", params).await?)
+            .context("getHeadlines sent unexpected JSON")
+    }
+
+    /// Articles newer than article `since_id`, or on a first pull
+    /// (`since_id` None) those updated after `oldest`; newest first
+    /// Returns them with the newest article ID seen. A first pull stops
+    /// after `MAX_PAGES`; catching up fails past `MAX_CATCH_UP_PAGES`, since
+    /// the next pull would start beyond the articles left out
+    async fn items_since(
+        &self,
+        since_id: Option<i64>,
+        oldest: DateTime<Utc>,
+    ) -> Result<(Vec<RemoteArticle>, Option<i64>)> {
+        let max_pages = if since_id.is_some() {
+            MAX_CATCH_UP_PAGES
+        } else {
+            MAX_PAGES
+        };
+        let mut articles = Vec::new();
+        let mut newest = since_id;
+        for page in 0..max_pages {
+            let headlines = self
+                .headlines(json!({
+                    "feed_id": ALL_ARTICLES,
+                    "view_mode": "all_articles",
+                    "show_content": true,
+                    "limit": PAGE_SIZE,
+                    "skip": page * PAGE_SIZE,
+                    "since_id": since_id.unwrap_or(0),
+                }))
+                .await?;
+            let full = headlines.len() == PAGE_SIZE;
+            let mut reached_oldest = false;
+            for headline in headlines {
+                if since_id.is_none() && headline.updated < oldest.timestamp() {
+                    reached_oldest = true;
+                    continue;
+                }
+                newest = newest.max(Some(headline.id));
+                articles.push(remote_article(headline));
+            }
+            if !full || reached_oldest || since_id.is_none() && page + 1 == max_pages {
+                return Ok((articles, newest));
+            }
+        }
+        Err(anyhow!(
+            "Over {} new articles since the last sync; not syncing so none are skipped",
+            articles.len()
+        ))
+    }
+
+    /// IDs of the unread (else the starred) articles; fails rather than
+    /// return a partial list, which would misstate the others
+    async fn item_ids(&self, unread: bool) -> Result<HashSet<String>> {
+        let (feed_id, view_mode) = if unread {
+            (ALL_ARTICLES, "unread")
+        } else {
+            (STARRED, "all_articles")
+        };
+        let mut ids = HashSet::new();
+        for page in 0..MAX_CATCH_UP_PAGES {
+            let headlines = self
+                .headlines(json!({
+                    "feed_id": feed_id,
+                    "view_mode": view_mode,
+                    "show_content": false,
+                    "limit": PAGE_SIZE,
+                    "skip": page * PAGE_SIZE,
+                }))
+                .await?;
+            let full = headlines.len() == PAGE_SIZE;
+            ids.extend(headlines.iter().map(|h| h.id.to_string()));
+            if !full {
+                return Ok(ids);
+            }
+        }
+        Err(anyhow!(
+            "Over {} {} articles; not syncing their state",
+            ids.len(),
+            if unread { "unread" } else { "starred" }
+        ))
+    }
+
+    /// Sets `field` of the articles to `value`, many articles per call
+    async fn update(&self, ids: &[String], field: Field, value: bool) -> Result<()> {
+        for batch in ids.chunks(UPDATE_BATCH) {
+            self.call(
+                "updateArticle",
+                json!({
+                    "article_ids": batch.join(","),
+                    "mode": u8::from(value),
+                    "field": field as u8,
+                }),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+impl SyncBackend for TtRss {
+    fn subscriptions(&self) -> impl Future<Output = Result<Vec<Subscription>>> + Send {
+        self.feeds()
+    }
+
+    /// Pulls by article ID: the cursor is the newest one seen
+    fn pull(
+        &self,
+        cursor: Option<&str>,
+        oldest: DateTime<Utc>,
+    ) -> impl Future<Output = Result<Pull>> + Send {
+        let since_id = cursor.and_then(|cursor| cursor.parse().ok());
+        async move {
+            let (articles, newest) = self.items_since(since_id, oldest).await?;
+            Ok(Pull {
+                articles,
+                cursor: newest.map(|id| id.to_string()),
+            })
+        }
+    }
+
+    async fn remote_state(&self) -> Result<RemoteState> {
+        Ok(RemoteState {
+            unread: self.item_ids(true).await?,
+            starred: self.item_ids(false).await?,
+        })
+    }
+
+    async fn push(&self, changes: &Changes) -> Result<()> {
+        self.update(&changes.read, Field::Unread, false).await?;
+        self.update(&changes.unread, Field::Unread, true).await?;
+        self.update(&changes.starred, Field::Starred, true).await?;
+        self.update(&changes.unstarred, Field::Starred, false).await
+    }
+}
+
+/// Feeds with their category's title as their folder
+fn subscriptions(categories: Vec<Category>, feeds: Vec<RawFeed>) -> Vec<Subscription> {
+    let titles: HashMap<i64, String> = categories
+        .into_iter()
+        // 0 is "Uncategorized", negative IDs are special categories
+        .filter(|c| c.id > 0)
+        .map(|c| (c.id, c.title))
+        .collect();
+    feeds
+        .into_iter()
+        .map(|feed| Subscription {
+            remote_id: feed.id.to_string(),
+            url: feed.feed_url,
+            title: feed.title,
+            folders: titles.get(&feed.cat_id).cloned().into_iter().collect(),
+        })
+        .collect()
+}
+
+/// The article of a headline, stored under its link when it has one since
+/// TT-RSS's numeric IDs only mean something to the server
+fn remote_article(headline: Headline) -> RemoteArticle {
+    let content = headline.content.filter(|c| !c.is_empty());
+    RemoteArticle {
+        remote_id: headline.id.to_string(),
+        feed_id: headline.feed_id.to_string(),
+        article: FeedItem {
+            id: if headline.link.is_empty() {
+                format!("ttrss:{}", headline.id)
+            } else {
+                headline.link.clone()
+            },
+            feed_url: String::new(),
+            title: Some(headline.title)
+                .filter(|t| !t.trim().is_empty())
+                .unwrap_or_else(|| "Untitled".to_string()),
+            link: headline.link,
+            description: content.clone(),
+            content,
+            published: Utc.timestamp_opt(headline.updated, 0).single(),
+            author: Some(headline.author).filter(|a| !a.is_empty()),
+            read: false,
+            starred: false,
+            tags: Vec::new(),
+            fetched: None,
+            filepath: None,
+            raw: None,
+        },
+    }
+}
@@ -0,0 +1,94 @@
+use super::*;
+
+#[test]
+fn test_subscriptions_take_their_category() {
+    let categories: Vec<Category> = serde_json::from_value(json!([
+        {"id": "1", "title": "Tech", "unread": 3},
+        {"id": 0, "title": "Uncategorized"},
+        {"id": -1, "title": "Special"},
+    ]))
+    .unwrap();
+    let feeds: Vec<RawFeed> = serde_json::from_value(json!([
+        {"id": 7, "title": "Example", "feed_url": "https://example.com/feed", "cat_id": 1},
+        {"id": "8", "title": "Other", "feed_url": "https://other.org/rss", "cat_id": 0},
+    ]))
+    .unwrap();
+    let subscriptions = subscriptions(categories, feeds);
+    assert_eq!(subscriptions.len(), 2);
+    assert_eq!(subscriptions[0].remote_id, "7");
+    assert_eq!(subscriptions[0].folders, vec!["Tech"]);
+    assert_eq!(subscriptions[1].remote_id, "8");
+    assert!(subscriptions[1].folders.is_empty());
+}
+
+#[test]
+fn test_remote_article() {
+    let headline: Headline = serde_json::from_value(json!({
+        "id": 42,
+        "title": "Async Rust",
+        "link": "https://example.com/async",
+        "updated": 1709285400,
+        "author": "",
+        "content": "<p>Futures are lazy.</p>",
+        "feed_id": "7",
+        "unread": true,
+        "marked": false,
+    }))
+    .unwrap();
+    let remote = remote_article(headline);
+    assert_eq!(remote.remote_id, "42");
+    assert_eq!(remote.feed_id, "7");
+    assert_eq!(remote.article.id, "https://example.com/async");
+    assert_eq!(remote.article.author, None);
+    assert_eq!(
+        remote.article.published.unwrap().to_rfc3339(),
+        "2024-03-01T09:30:00+00:00"
+    );
+
+    let linkless: Headline = serde_json::from_value(json!({"id": 43})).unwrap();
+    assert_eq!(remote_article(linkless).article.id, "ttrss:43");
+}
+
+/// A server whose every `getHeadlines` page is full, counting the calls
+async fn endless_server(calls: std::sync::Arc<std::sync::atomic::AtomicUsize>) -> TtRss {
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use std::sync::atomic::Ordering;
+
+    let router = Router::new().route(
+        "/api/",
+        post(move |Json(body): Json<Value>| async move {
+            if body["op"] == "login" {
+                return Json(json!({"status": 0, "content": {"session_id": "s"}}));
+            }
+            let skip = body["skip"].as_u64().unwrap() as i64;
+            calls.fetch_add(1, Ordering::SeqCst);
+            let page: Vec<Value> = (0..PAGE_SIZE as i64)
+                .map(|i| json!({"id": 1_000_000 - skip - i, "updated": Utc::now().timestamp()}))
+                .collect();
+            Json(json!({"status": 0, "content": page}))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/api/", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    TtRss::login(&url, "me", "pw").await.unwrap()
+}
+
+#[tokio::test]
+async fn test_truncated_listings_fail_the_sync() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    let calls = std::sync::Arc::new(AtomicUsize::new(0));
+    let server = endless_server(calls.clone()).await;
+    let oldest = Utc::now() - chrono::Duration::days(1);
+
+    // A first pull stops after MAX_PAGES and starts the next one after them
+    let (articles, newest) = server.items_since(None, oldest).await.unwrap();
+    assert_eq!(articles.len(), MAX_PAGES * PAGE_SIZE);
+    assert_eq!(newest, Some(1_000_000));
+    assert_eq!(calls.load(Ordering::SeqCst), MAX_PAGES);
+
+    // Catching up or listing state can't leave anything out
+    assert!(server.items_since(Some(1), oldest).await.is_err());
+    assert!(server.item_ids(true).await.is_err());
+}
@@ -1,40 +1,434 @@
-use crate::cache::TextCache;
+use crate::cache::{ArticleStore, TextCache};
+use crate::clipboard::{self, Copied};
+use crate::draft::NoteDraft;
+use crate::engine::{wrap_line, ArticleView};
+use crate::images::{self, Graphics};
+use crate::player::Enclosure;
+use crate::theme::{self, Theme};
 use anyhow::{Context, Result};
 use crossterm::{
-    event::{self, Event, KeyCode},
+    event::{self, Event, KeyCode, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState,
+    },
     Frame, Terminal,
 };
 use std::fs::OpenOptions;
 use std::io::{self, Write};
 use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use unicode_width::UnicodeWidthStr;
 use uuid::Uuid;
 
-/// Runs the TUI article viewer for the specified article
-/// Returns an exit code: 0=normal, 1=open browser, 2=create note
-pub fn run_viewer(article_id: &str) -> Result<i32> {
-    // Get session ID from environment or generate new one
-    let session_id =
-        std::env::var("ZETRSS_SESSION_ID").unwrap_or_else(|_| Uuid::new_v4().to_string());
+#[cfg(test)]
+#[path = "viewer_tests.rs"]
+mod tests;
 
-    // Setup terminal
+/// What the viewer does once the last article in its queue is finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FinishAction {
+    /// Return to the article list (exit code 0)
+    #[default]
+    List,
+    /// Exit without returning to the list (exit code 4)
+    Exit,
+    /// Exit and report articles read and time spent (exit code 5)
+    Summary,
+    /// Fetch new articles, then return to the list (exit code 0)
+    Fetch,
+}
+
+/// Result of a viewer session
+pub struct ViewerOutcome {
+    /// Exit code for the action that ended the session: 0=normal, 1=open browser,
+    /// 2=create note, 3=open in vim
+    pub exit_code: i32,
+    /// True when the reader quit through the last article of the queue
+    /// (as opposed to leaving early with Esc or an action key)
+    pub finished: bool,
+    pub articles_read: usize,
+    pub elapsed: Duration,
+}
+
+impl ViewerOutcome {
+    /// Human-readable summary of the session, e.g. "Read 3 articles in 4m 05s"
+    pub fn summary(&self) -> String {
+        let secs = self.elapsed.as_secs();
+        format!(
+            "Read {} article{} in {}m {:02}s",
+            self.articles_read,
+            if self.articles_read == 1 { "" } else { "s" },
+            secs / 60,
+            secs % 60
+        )
+    }
+}
+
+/// Terminal the viewer and the article list draw on
+pub(crate) type Tui = Terminal<CrosstermBackend<io::Stdout>>;
+
+/// Switches the terminal to raw mode on the alternate screen
+pub(crate) fn enter_tui() -> Result<Tui> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    Ok(Terminal::new(CrosstermBackend::new(stdout))?)
+}
+
+/// Restores the terminal left by `enter_tui`
+pub(crate) fn leave_tui(terminal: &mut Tui) -> Result<()> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    Ok(())
+}
+
+/// Hands the terminal back to the shell for a child process, such as a pager
+fn suspend_tui(terminal: &mut Tui) -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()
+}
+
+/// Takes the terminal back after `suspend_tui`, redrawing from scratch
+fn resume_tui(terminal: &mut Tui) -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    terminal.clear()
+}
+
+/// Pages each article through the external pager in turn, marking it read
+pub fn run_pager(cache: &TextCache, article_ids: &[String]) -> Result<()> {
+    let width = crate::config::get().viewer.width;
+    for id in article_ids {
+        let article = cache
+            .get_article_by_id(id)?
+            .ok_or_else(|| anyhow::anyhow!("Article not found: {}", id))?;
+        cache.mark_as_read(id)?;
+        let view = ArticleView::from_article(article, width);
+        crate::pager::page(&pager_text(&view.article, &view.lines))?;
+    }
+    Ok(())
+}
+
+/// An article as plain text for the pager: title and feed above the content
+pub(crate) fn pager_text(article: &crate::models::FeedItem, lines: &[String]) -> String {
+    let mut text = format!("{}\nFeed: {}\n\n", article.title, article.feed_url);
+    for line in lines {
+        text.push_str(line);
+        text.push('\n');
+    }
+    text
+}
+
+/// How reading a queue of articles ended
+pub(crate) struct QueueEnd {
+    pub articles_read: usize,
+    /// True when the reader quit through the last article of the queue
+    pub finished: bool,
+    /// Action key that ended the queue; `Reading` for `q` or Esc
+    pub mode: ViewerMode,
+    /// The article shown last
+    pub article: crate::models::FeedItem,
+}
+
+/// Runs the TUI article viewer over a queue of articles
+/// `q` finishes the current article and opens the next one, Esc leaves early
+pub fn run_viewer(cache: &TextCache, article_ids: &[String]) -> Result<ViewerOutcome> {
+    run(cache, article_ids, 0, true, false)
+}
+
+/// Runs the TUI article viewer with every article open in its own tab;
+/// gt/gT switch between them and `q` closes one
+pub fn run_tab_viewer(cache: &TextCache, article_ids: &[String]) -> Result<ViewerOutcome> {
+    run(cache, article_ids, 0, false, true)
+}
+
+/// Runs the TUI article viewer on `article_ids[start]`; J/K step through the
+/// rest of the list and `q` ends the session
+pub fn run_list_viewer(
+    cache: &TextCache,
+    article_ids: &[String],
+    start: usize,
+) -> Result<ViewerOutcome> {
+    run(cache, article_ids, start, false, false)
+}
+
+fn run(
+    cache: &TextCache,
+    article_ids: &[String],
+    start: usize,
+    queue: bool,
+    tabs: bool,
+) -> Result<ViewerOutcome> {
+    let started = Instant::now();
+
+    // Load the first article before touching the terminal so a bad id
+    // doesn't leave the terminal in raw mode
+    let first_id = article_ids
+        .get(start)
+        .ok_or_else(|| anyhow::anyhow!("No article to view"))?;
+    let first = load_app(cache, first_id)?;
+
+    let mut terminal = enter_tui()?;
+    let res = read_queue(
+        &mut terminal,
+        cache,
+        article_ids,
+        start,
+        queue,
+        tabs,
+        Some(first),
+    );
+    leave_tui(&mut terminal)?;
+    let end = res?;
+
+    Ok(ViewerOutcome {
+        exit_code: action_exit_code(&end.mode, &end.article)?,
+        finished: end.finished,
+        articles_read: end.articles_read,
+        elapsed: started.elapsed(),
+    })
+}
+
+/// Order of the articles J/K step through when `view` gets a single id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum NavOrder {
+    /// Every cached article, newest first
+    #[default]
+    Date,
+    /// Unread articles, newest first
+    Unread,
+}
+
+/// The article list around `article_id` for J/K navigation, and the
+/// article's position in it
+pub fn nav_list(
+    cache: &TextCache,
+    article_id: &str,
+    order: NavOrder,
+) -> Result<(Vec<String>, usize)> {
+    let filter = crate::filter::ArticleFilter {
+        read: (order == NavOrder::Unread).then_some(false),
+        ..Default::default()
+    };
+    let mut ids: Vec<String> = cache
+        .get_articles(&crate::filter::ArticleQuery::from(filter))?
+        .into_iter()
+        .map(|a| a.id)
+        .collect();
+    let start = match ids.iter().position(|id| id == article_id) {
+        Some(start) => start,
+        None => {
+            // e.g. an already read article in unread order
+            ids.insert(0, article_id.to_string());
+            0
+        }
+    };
+    Ok((ids, start))
+}
+
+/// Shows articles from `article_ids`, starting at `start`, on an already set
+/// up terminal. J/K move to the next/previous article; in a `queue` `q` does
+/// too, otherwise it closes the tab and the session ends with the last tab.
+/// With `tabs` every article opens in a tab of its own, `start` being the
+/// current one; `T` opens the next article in a new tab either way.
+/// `first` is the preloaded first article, if the caller loaded it already
+pub(crate) fn read_queue(
+    terminal: &mut Tui,
+    cache: &TextCache,
+    article_ids: &[String],
+    start: usize,
+    queue: bool,
+    tabs: bool,
+    mut first: Option<ViewerApp>,
+) -> Result<QueueEnd> {
+    let mut opened = std::collections::HashSet::new();
+    let mut watch = NewArticles::new(cache);
+    let remote = Remote::listen(cache);
+    let mut open = |index: usize, preloaded: Option<ViewerApp>| -> Result<ViewerApp> {
+        let mut app = match preloaded {
+            Some(app) => app,
+            None => load_app(cache, &article_ids[index])?,
+        };
+        app.index = index;
+        app.remaining = article_ids.len() - index - 1;
+        app.queue = queue;
+        opened.insert(index);
+        Ok(app)
+    };
+
+    let mut open_tabs = Vec::new();
+    let mut current = 0;
+    if tabs {
+        for index in 0..article_ids.len() {
+            let preloaded = if index == start { first.take() } else { None };
+            open_tabs.push(open(index, preloaded)?);
+        }
+        current = start;
+    } else {
+        open_tabs.push(open(start, first.take())?);
+    }
+
+    loop {
+        let titles: Vec<String> = open_tabs.iter().map(|t| t.article.title.clone()).collect();
+        let app = &mut open_tabs[current];
+        app.tab_titles = titles;
+        app.tab = current;
+        if let Some(remote) = &remote {
+            remote.show(&app.article.id);
+        }
+
+        run_app(terminal, app, cache, &mut watch, remote.as_ref())?;
+        clear_images(terminal, app)?;
+        save_position(terminal, cache, app)?;
+
+        let index = app.index;
+        match app.nav.take() {
+            Some(Nav::Next) => open_tabs[current] = open(index + 1, None)?,
+            Some(Nav::Prev) => open_tabs[current] = open(index - 1, None)?,
+            Some(Nav::NewTab) => {
+                current += 1;
+                open_tabs.insert(current, open(index + 1, None)?);
+            }
+            Some(Nav::NextTab) => current = (current + 1) % open_tabs.len(),
+            Some(Nav::PrevTab) => current = (current + open_tabs.len() - 1) % open_tabs.len(),
+            Some(Nav::Jump(id)) => {
+                // Articles outside the list open in place of the current one
+                let jumped = match article_ids.iter().position(|a| *a == id) {
+                    Some(target) => open(target, None),
+                    None => load_app(cache, &id).and_then(|app| open(index, Some(app))),
+                };
+                match jumped {
+                    Ok(app) => open_tabs[current] = app,
+                    Err(e) => open_tabs[current].status = Some(format!("Can't jump: {:#}", e)),
+                }
+            }
+            None if queue
+                && app.mode == ViewerMode::Reading
+                && !app.left_early
+                && app.remaining > 0 =>
+            {
+                open_tabs[current] = open(index + 1, None)?;
+            }
+            // `q` closes the tab while others are open
+            None if app.mode == ViewerMode::Reading && !app.left_early && open_tabs.len() > 1 => {
+                open_tabs.remove(current);
+                current = current.min(open_tabs.len() - 1);
+            }
+            None => {
+                let app = open_tabs.swap_remove(current);
+                return Ok(QueueEnd {
+                    articles_read: opened.len(),
+                    finished: app.mode == ViewerMode::Reading
+                        && !app.left_early
+                        && (!queue || app.remaining == 0),
+                    mode: app.mode,
+                    article: app.article,
+                });
+            }
+        }
+    }
+}
+
+/// Carries out the action that ended a viewer session and returns its exit code:
+/// 0=normal, 1=open browser, 2=create note, 3=open in vim
+pub(crate) fn action_exit_code(
+    mode: &ViewerMode,
+    article: &crate::models::FeedItem,
+) -> Result<i32> {
+    Ok(match mode {
+        ViewerMode::Reading => 0,
+        ViewerMode::OpenBrowser => {
+            write_session_file("open_url", &article.link)?;
+            1
+        }
+        ViewerMode::CreateNote => {
+            // Create the note and write path to temp file
+            if let Ok(note_path) = crate::notes::create_note_from_article(article) {
+                write_session_file("note_path", &note_path)?;
+            }
+            2
+        }
+        ViewerMode::OpenInVim => {
+            // Write article filepath to temp file for Lua to open in vim buffer
+            if let Some(ref filepath) = article.filepath {
+                write_session_file("vim_path", filepath)?;
+            }
+            3
+        }
+    })
+}
+
+/// Where data of `kind` for the Lua side goes: `<kind>.txt` in the session
+/// directory, else `zetrss_<kind>_<session>.txt` in the temp dir, the session
+/// being the given ID or a random one nobody else can guess. Per-session
+/// names keep concurrent viewers from reading each other's files.
+pub(crate) fn session_file(
+    kind: &str,
+    dir: Option<PathBuf>,
+    session_id: Option<String>,
+) -> PathBuf {
+    match dir {
+        Some(dir) => dir.join(format!("{}.txt", kind)),
+        None => {
+            let session_id = session_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+            std::env::temp_dir().join(format!("zetrss_{}_{}.txt", kind, session_id))
+        }
+    }
+}
 
-    // Load article
-    let cache = TextCache::new().context("Failed to initialize article cache")?;
-    let article = cache
+/// Writes data for the Lua side to the session file of `kind` (see
+/// `session_file`), from `$ZETRSS_SESSION_DIR` or `$ZETRSS_SESSION_ID`, readable
+/// only by the current user. Whoever started the viewer reads and removes it;
+/// without either variable nobody knows the name, so it's printed.
+pub fn write_session_file(kind: &str, contents: &str) -> Result<()> {
+    let dir = std::env::var_os("ZETRSS_SESSION_DIR").map(PathBuf::from);
+    let session_id = std::env::var("ZETRSS_SESSION_ID").ok();
+    let announce = dir.is_none() && session_id.is_none();
+    let path = session_file(kind, dir, session_id);
+    write_private(&path, contents).with_context(|| format!("Failed to write {} file", kind))?;
+    if announce {
+        eprintln!("Wrote {} to {}", kind, path.display());
+    }
+    Ok(())
+}
+
+/// Writes `contents` to a new file only the current user can read. A file
+/// left at `path` is replaced rather than opened, so a link or file someone
+/// else planted in a shared directory fails the write instead of taking it.
+pub(crate) fn write_private(path: &Path, contents: &str) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            return Err(e).with_context(|| format!("Failed to replace {}", path.display()))
+        }
+        _ => {}
+    }
+    let mut file = OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .mode(0o600) // Only owner can read/write
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Loads an article, marks it read and prepares its display lines
+pub(crate) fn load_app(cache: &TextCache, article_id: &str) -> Result<ViewerApp> {
+    let mut article = cache
         .get_article_by_id(article_id)
         .with_context(|| format!("Failed to load article {}", article_id))?
         .ok_or_else(|| {
@@ -48,153 +442,617 @@ pub fn run_viewer(article_id: &str) -> Result<i32> {
     cache
         .mark_as_read(&article.id)
         .with_context(|| format!("Failed to mark article {} as read", article_id))?;
+    article.read = true;
+    let scroll = cache.reading_position(&article.id)?.unwrap_or(0);
 
-    // Prepare content for display
-    let content = if let Some(ref content) = article.content {
-        html2text::from_read(content.as_bytes(), 80)
-    } else if let Some(ref desc) = article.description {
-        html2text::from_read(desc.as_bytes(), 80)
-    } else {
-        "No content available".to_string()
+    let config = &crate::config::get().viewer;
+    let mut view = ArticleView::from_article(article, wrap_width());
+    let minutes = reading_minutes(view.words, config.wpm);
+    let graphics = config.images.graphics();
+    let images = match graphics {
+        Some(graphics) => place_images(cache, &mut view, graphics),
+        None => Vec::new(),
     };
 
-    // Build full content with metadata
-    let mut full_content = String::new();
-    if let Some(ref author) = article.author {
-        full_content.push_str(&format!("Author: {}\n", author));
-    }
-    if let Some(ref published) = article.published {
-        full_content.push_str(&format!("Published: {}\n", published));
-    }
-    full_content.push_str(&format!("Link: {}\n", article.link));
-    full_content.push_str("\n────────────────────────────────────────\n\n");
-    full_content.push_str(&content);
+    // A missing or unreadable raw entry just means no enclosure
+    let enclosure = cache
+        .get_raw_entry(&view.article.id)
+        .ok()
+        .flatten()
+        .and_then(|raw| crate::player::enclosures(&raw).into_iter().next());
 
-    // Split into lines for scrolling
-    let content_lines: Vec<String> = full_content.lines().map(String::from).collect();
+    let status = (scroll > 0).then(|| {
+        format!(
+            "Resumed at line {} of {} (g goes to the top)",
+            usize::from(scroll) + 1,
+            view.lines.len()
+        )
+    });
 
-    // Create app state
     let mut app = ViewerApp {
-        article: article.clone(),
-        scroll: 0,
+        article: view.article,
+        scroll,
         mode: ViewerMode::Reading,
-        content_lines,
+        content_lines: view.lines,
+        index: 0,
+        remaining: 0,
+        queue: true,
+        nav: None,
+        left_early: false,
+        search_input: None,
+        search: None,
+        graphics,
+        images,
+        status,
+        minutes,
+        draft: None,
+        selection: None,
+        tab_titles: Vec::new(),
+        tab: 0,
+        enclosure,
+        new_articles: 0,
+        unread: Unread::default(),
+        summarized: false,
     };
+    if let Some(summary) = crate::summarize::stored(cache.base_dir(), &app.article.id) {
+        app.show_summary(&summary);
+    }
+    app.refresh_unread(cache);
+    Ok(app)
+}
 
-    // Run app
-    let res = run_app(&mut terminal, &mut app);
+/// Column width articles are wrapped at: the configured width, narrowed to
+/// the terminal's so that every content line takes exactly one row
+fn wrap_width() -> usize {
+    let columns = crossterm::terminal::size()
+        .map(|(cols, _)| usize::from(cols.saturating_sub(2)))
+        .unwrap_or(usize::MAX);
+    crate::config::get().viewer.width.min(columns).max(1)
+}
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    terminal.show_cursor()?;
+/// Estimated minutes to read `words` words, at least one
+fn reading_minutes(words: usize, wpm: usize) -> usize {
+    words.div_ceil(wpm.max(1)).max(1)
+}
 
-    if let Err(err) = res {
-        return Err(err.into());
+/// How far through the article a scroll offset is, in percent
+fn percent_read(scroll: usize, lines: usize, viewport_height: usize) -> usize {
+    let max_scroll = lines.saturating_sub(viewport_height);
+    (scroll.min(max_scroll) * 100)
+        .checked_div(max_scroll)
+        .unwrap_or(100)
+}
+
+/// Downloads and encodes the article's images, making room for each one
+/// below its placeholder line; images that fail keep just the placeholder
+fn place_images(cache: &TextCache, view: &mut ArticleView, graphics: Graphics) -> Vec<InlineImage> {
+    if view.images.is_empty() {
+        return Vec::new();
     }
+    let urls: Vec<String> = view.images.iter().map(|image| image.url.clone()).collect();
+    images::download(cache.base_dir(), &urls);
 
-    // Return different exit codes based on action
-    let exit_code = match app.mode {
-        ViewerMode::Reading => 0,
-        ViewerMode::OpenBrowser => {
-            // Write URL to a temp file for Lua to read
-            // Use session-specific temp file to avoid race conditions
-            let temp_dir = std::env::temp_dir();
-            let temp_file = temp_dir.join(format!("zetrss_open_url_{}.txt", session_id));
-
-            // Write with restrictive permissions
-            let mut file = OpenOptions::new()
-                .create(true)
-                .write(true)
-                .truncate(true)
-                .mode(0o600) // Only owner can read/write
-                .open(&temp_file)
-                .context("Failed to create temp file for URL")?;
-
-            file.write_all(article.link.as_bytes())
-                .context("Failed to write URL to temp file")?;
-            file.sync_all().context("Failed to sync URL file to disk")?;
-            1
+    let cell = images::cell_size();
+    let width = wrap_width().try_into().unwrap_or(u16::MAX);
+    let mut placed = Vec::new();
+    let mut inserted = 0;
+    for image in &view.images {
+        let path = images::cached_path(cache.base_dir(), &image.url);
+        if !path.exists() {
+            continue;
         }
-        ViewerMode::CreateNote => {
-            // Create the note and write path to temp file
-            if let Ok(note_path) = create_note_from_article(&article) {
-                // Use session-specific temp file to avoid race conditions
-                let temp_dir = std::env::temp_dir();
-                let temp_file = temp_dir.join(format!("zetrss_note_path_{}.txt", session_id));
-
-                // Write with restrictive permissions
-                let mut file = OpenOptions::new()
-                    .create(true)
-                    .write(true)
-                    .truncate(true)
-                    .mode(0o600) // Only owner can read/write
-                    .open(&temp_file)
-                    .context("Failed to create temp file for note path")?;
-
-                file.write_all(note_path.as_bytes())
-                    .context("Failed to write note path to temp file")?;
-                file.sync_all()
-                    .context("Failed to sync note path file to disk")?;
+        let encoded = match images::encode(&path, graphics, width, cell) {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                tracing::debug!("Failed to encode image {}: {:#}", image.url, e);
+                continue;
             }
-            2
+        };
+        let line = image.line + inserted + 1;
+        let rows = usize::from(encoded.rows);
+        view.lines
+            .splice(line..line, std::iter::repeat_n(String::new(), rows));
+        inserted += rows;
+        placed.push(InlineImage { line, encoded });
+    }
+    placed
+}
+
+/// How often the viewer looks for articles fetched while it's open
+const NEW_ARTICLE_CHECK: Duration = Duration::from_secs(30);
+
+/// Articles that arrived in the cache since the viewer opened, e.g. fetched
+/// by `zetrss daemon`
+struct NewArticles {
+    /// Articles in the cache when the viewer opened
+    baseline: usize,
+    checked: Instant,
+    count: usize,
+}
+
+impl NewArticles {
+    fn new(cache: &TextCache) -> Self {
+        Self {
+            baseline: article_total(cache).unwrap_or(0),
+            checked: Instant::now(),
+            count: 0,
         }
-        ViewerMode::OpenInVim => {
-            // Write article filepath to temp file for Lua to open in vim buffer
-            if let Some(ref filepath) = article.filepath {
-                let temp_dir = std::env::temp_dir();
-                let temp_file = temp_dir.join(format!("zetrss_vim_path_{}.txt", session_id));
-
-                let mut file = OpenOptions::new()
-                    .create(true)
-                    .write(true)
-                    .truncate(true)
-                    .mode(0o600)
-                    .open(&temp_file)
-                    .context("Failed to create temp file for vim path")?;
-
-                file.write_all(filepath.as_bytes())
-                    .context("Failed to write vim path to temp file")?;
-                file.sync_all()
-                    .context("Failed to sync vim path file to disk")?;
-            }
-            3
+    }
+
+    /// Time left until the next check is due
+    fn until_check(&self) -> Duration {
+        NEW_ARTICLE_CHECK.saturating_sub(self.checked.elapsed())
+    }
+
+    /// Picks up article files written by other processes and re-counts
+    fn check(&mut self, cache: &TextCache) {
+        self.checked = Instant::now();
+        match cache.sync_index().and_then(|_| article_total(cache)) {
+            Ok(total) => self.count = total.saturating_sub(self.baseline),
+            Err(e) => tracing::debug!("Failed to look for new articles: {:#}", e),
         }
-    };
+    }
+}
+
+/// How often the viewer looks for requests from its socket while idle
+const REMOTE_POLL: Duration = Duration::from_millis(200);
+
+#[derive(serde::Deserialize)]
+struct JumpParams {
+    id: String,
+}
+
+/// The viewer's socket (`$ZETRSS_SOCKET`, else its own in `ipc::socket_dir`):
+/// the `zetrss rpc` methods plus `jump` (show an article) and `current` (the
+/// article shown)
+struct Remote {
+    jumps: std::sync::mpsc::Receiver<String>,
+    shown: std::sync::Arc<std::sync::Mutex<String>>,
+    _listener: crate::ipc::Listener,
+}
+
+impl Remote {
+    /// None when the socket can't be set up; the viewer works without it
+    fn listen(cache: &TextCache) -> Option<Self> {
+        Self::try_listen(cache)
+            .map_err(|e| tracing::debug!("Viewer not listening for requests: {:#}", e))
+            .ok()
+    }
+
+    fn try_listen(cache: &TextCache) -> Result<Self> {
+        use crate::rpc::{params_of, RpcServer};
+        let path = match std::env::var_os("ZETRSS_SOCKET") {
+            Some(path) => PathBuf::from(path),
+            None => crate::ipc::socket_path(
+                &format!("viewer-{}", std::process::id()),
+                cache.base_dir(),
+            )?,
+        };
+        let (sender, jumps) = std::sync::mpsc::channel();
+        let shown = std::sync::Arc::new(std::sync::Mutex::new(String::new()));
+        let current = shown.clone();
+        let reader = crate::engine::ZetRss::open(cache.base_dir().to_path_buf())?;
+        let server =
+            RpcServer::new(reader).with_methods(Box::new(move |method, params| match method {
+                "jump" => Some(params_of::<JumpParams>(params.clone()).and_then(|params| {
+                    sender
+                        .send(params.id.clone())
+                        .map_err(|_| anyhow::anyhow!("The viewer is closing"))?;
+                    Ok(serde_json::json!({ "id": params.id }))
+                })),
+                "current" => Some(Ok(serde_json::json!({
+                    "id": current.lock().map(|id| id.clone()).unwrap_or_default()
+                }))),
+                _ => None,
+            }));
+        Ok(Self {
+            jumps,
+            shown,
+            _listener: crate::ipc::listen(&path, server)?,
+        })
+    }
+
+    fn show(&self, article_id: &str) {
+        if let Ok(mut shown) = self.shown.lock() {
+            *shown = article_id.to_string();
+        }
+    }
+
+    /// The latest article asked for since the last look
+    fn next_jump(&self) -> Option<String> {
+        self.jumps.try_iter().last()
+    }
+}
+
+/// Unread articles in one feed and in the whole cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Unread {
+    feed: usize,
+    total: usize,
+}
+
+/// Unread counts for `feed_url`, from the index
+fn unread_counts(cache: &TextCache, feed_url: &str) -> Result<Unread> {
+    let mut unread = Unread::default();
+    for counts in cache.feed_counts()? {
+        if counts.feed_url == feed_url {
+            unread.feed = counts.unread;
+        }
+        unread.total += counts.unread;
+    }
+    Ok(unread)
+}
 
-    Ok(exit_code)
+/// Articles in the cache, archived ones aside
+fn article_total(cache: &TextCache) -> Result<usize> {
+    Ok(cache.feed_counts()?.iter().map(|c| c.total).sum())
+}
+
+/// Step through the article list, or between tabs
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Nav {
+    Next,
+    Prev,
+    /// Open the next article in a tab after this one
+    NewTab,
+    NextTab,
+    PrevTab,
+    /// Show this article instead, as asked for on the viewer's socket
+    Jump(String),
 }
 
 #[derive(PartialEq)]
-enum ViewerMode {
+pub(crate) enum ViewerMode {
     Reading,
     OpenBrowser,
     CreateNote,
     OpenInVim,
 }
 
-struct ViewerApp {
+pub(crate) struct ViewerApp {
     article: crate::models::FeedItem,
     scroll: u16,
     mode: ViewerMode,
     content_lines: Vec<String>,
+    /// Position in the list of articles being read
+    index: usize,
+    /// Articles listed after this one
+    remaining: usize,
+    /// Whether `q` moves on to the next article
+    queue: bool,
+    /// Set when J/K asked for another article
+    nav: Option<Nav>,
+    /// Set when the reader leaves the queue with Esc
+    left_early: bool,
+    /// Search being typed after `/`
+    search_input: Option<String>,
+    /// Last search and its matches, cleared with Esc
+    search: Option<Search>,
+    /// Protocol `images` are drawn with
+    graphics: Option<Graphics>,
+    images: Vec<InlineImage>,
+    /// Footer message shown until the next key, e.g. after copying the link
+    status: Option<String>,
+    /// Estimated reading time
+    minutes: usize,
+    /// Note being written next to the article, opened with `e`
+    draft: Option<NoteDraft>,
+    /// Lines being selected for a quote, after `V`
+    selection: Option<Selection>,
+    /// Titles of the open tabs, empty until the viewer runs
+    tab_titles: Vec<String>,
+    /// This article's tab
+    tab: usize,
+    /// Audio or video attached to the article, played with `m`
+    enclosure: Option<Enclosure>,
+    /// Articles fetched since the viewer opened, shown in the header
+    new_articles: usize,
+    /// Unread counts shown in the header, refreshed as they change
+    unread: Unread,
+    /// Whether the summarizer's summary is shown above the article
+    summarized: bool,
+}
+
+/// Content lines picked in visual mode: from `anchor` to `cursor`, inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Selection {
+    anchor: usize,
+    cursor: usize,
+}
+
+impl Selection {
+    fn range(self) -> std::ops::RangeInclusive<usize> {
+        self.anchor.min(self.cursor)..=self.anchor.max(self.cursor)
+    }
+}
+
+/// An image drawn over the blank content lines reserved for it
+struct InlineImage {
+    /// First reserved content line
+    line: usize,
+    encoded: images::Encoded,
+}
+
+/// Matches of an in-article search
+struct Search {
+    query: String,
+    matches: Vec<Match>,
+    /// Index into `matches` of the match jumped to last
+    current: usize,
+}
+
+/// A search hit: byte range `start..end` of content line `line`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Match {
+    line: usize,
+    start: usize,
+    end: usize,
+}
+
+/// Case-insensitive, non-overlapping occurrences of `query` in `lines`
+fn find_matches(lines: &[String], query: &str) -> Vec<Match> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let mut matches = Vec::new();
+    for (line_no, line) in lines.iter().enumerate() {
+        let mut start = 0;
+        while start < line.len() {
+            match match_len(&line[start..], query) {
+                Some(len) => {
+                    matches.push(Match {
+                        line: line_no,
+                        start,
+                        end: start + len,
+                    });
+                    start += len;
+                }
+                None => {
+                    start += line[start..].chars().next().map_or(1, char::len_utf8);
+                }
+            }
+        }
+    }
+    matches
+}
+
+/// Length in bytes of `text`'s prefix that equals `query` ignoring case
+fn match_len(text: &str, query: &str) -> Option<usize> {
+    let mut len = 0;
+    let mut chars = text.chars();
+    for q in query.chars() {
+        let c = chars.next()?;
+        if !c.to_lowercase().eq(q.to_lowercase()) {
+            return None;
+        }
+        len += c.len_utf8();
+    }
+    Some(len)
+}
+
+impl ViewerApp {
+    /// Puts the summarizer's bullets above the article
+    fn show_summary(&mut self, summary: &str) {
+        self.show_above("Summary", summary);
+        self.summarized = true;
+    }
+
+    /// Puts a titled block of lines above the article, wrapped like it
+    fn show_above(&mut self, title: &str, text: &str) {
+        let width = wrap_width();
+        let mut lines = vec![title.to_string()];
+        for line in text.lines() {
+            lines.extend(wrap_line(line, width));
+        }
+        lines.push(String::new());
+        let added = lines.len();
+        self.content_lines.splice(0..0, lines);
+        for image in &mut self.images {
+            image.line += added;
+        }
+        // Line numbers below the new block moved down
+        if let Some(selection) = self.selection.as_mut() {
+            selection.anchor += added;
+            selection.cursor += added;
+        }
+        if let Some(search) = self.search.as_mut() {
+            search.matches = find_matches(&self.content_lines, &search.query);
+            let above = search.matches.iter().filter(|m| m.line < added).count();
+            search.current = (search.current + above).min(search.matches.len().saturating_sub(1));
+        }
+    }
+
+    /// Re-reads the header's unread counts; they stay as they were on error
+    fn refresh_unread(&mut self, cache: &TextCache) {
+        match unread_counts(cache, &self.article.feed_url) {
+            Ok(unread) => self.unread = unread,
+            Err(e) => tracing::debug!("Failed to count unread articles: {:#}", e),
+        }
+    }
+
+    /// The furthest the article scrolls in a viewport this high; the
+    /// content grows when a summary or related notes are shown
+    fn max_scroll(&self, viewport_height: u16) -> u16 {
+        (self.content_lines.len() as u16).saturating_sub(viewport_height)
+    }
+
+    /// Runs a search and jumps to the first match at or below the top of the
+    /// viewport
+    fn search(&mut self, query: String, viewport_height: u16) {
+        let matches = find_matches(&self.content_lines, &query);
+        let current = matches
+            .iter()
+            .position(|m| m.line >= self.scroll as usize)
+            .unwrap_or(0);
+        self.search = Some(Search {
+            query,
+            matches,
+            current,
+        });
+        self.scroll_to_match(viewport_height);
+    }
+
+    /// Moves to the next (or previous) match, wrapping around
+    fn jump_match(&mut self, forward: bool, viewport_height: u16) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+        let count = search.matches.len();
+        if count == 0 {
+            return;
+        }
+        search.current = if forward {
+            (search.current + 1) % count
+        } else {
+            (search.current + count - 1) % count
+        };
+        self.scroll_to_match(viewport_height);
+    }
+
+    /// Scrolls so the current match is visible, a third of the way down
+    fn scroll_to_match(&mut self, viewport_height: u16) {
+        let Some(hit) = self.search.as_ref().and_then(|s| s.matches.get(s.current)) else {
+            return;
+        };
+        let line = hit.line as u16;
+        if line < self.scroll || line >= self.scroll + viewport_height {
+            let max_scroll = (self.content_lines.len() as u16).saturating_sub(viewport_height);
+            self.scroll = line.saturating_sub(viewport_height / 3).min(max_scroll);
+        }
+    }
 }
 
 fn run_app(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    terminal: &mut Tui,
     app: &mut ViewerApp,
+    cache: &TextCache,
+    watch: &mut NewArticles,
+    remote: Option<&Remote>,
 ) -> io::Result<()> {
-    // Calculate max scroll based on content
-    let content_height = app.content_lines.len() as u16;
+    // A saved position may be past the end once the article renders shorter
+    let viewport_height = terminal.size()?.height.saturating_sub(7);
+    app.scroll = app.scroll.min(app.max_scroll(viewport_height));
+    // Scroll offset the images were last drawn at
+    let mut drawn_scroll = None;
+    // Scroll offset before `g`, while t/T may still follow it
+    let mut pending_g = None;
 
     loop {
+        if drawn_scroll != Some(app.scroll) {
+            clear_images(terminal, app)?;
+        }
         terminal.draw(|f| ui(f, app))?;
+        if drawn_scroll != Some(app.scroll) {
+            draw_images(terminal, app)?;
+            drawn_scroll = Some(app.scroll);
+        }
+
+        // Wait for input, counting newly fetched articles now and then and
+        // looking out for jumps asked for on the socket
+        app.new_articles = watch.count;
+        let wait = match remote {
+            Some(_) => watch.until_check().min(REMOTE_POLL),
+            None => watch.until_check(),
+        };
+        if !event::poll(wait)? {
+            if let Some(id) = remote.and_then(Remote::next_jump) {
+                app.nav = Some(Nav::Jump(id));
+                return Ok(());
+            }
+            if watch.until_check().is_zero() {
+                watch.check(cache);
+                app.refresh_unread(cache);
+            }
+            continue;
+        }
+        let event = event::read()?;
+        if let Event::Resize(..) = event {
+            drawn_scroll = None;
+        }
+        if let Event::Key(key) = event {
+            app.status = None;
+            let after_g = pending_g.take();
+            let viewport_height = terminal.size()?.height.saturating_sub(7); // Account for header/footer
+            if let Some(draft) = app.draft.as_mut() {
+                match key.code {
+                    KeyCode::Esc => {
+                        save_draft(app);
+                        drawn_scroll = None;
+                    }
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.draft = None;
+                        app.status = Some("Note draft discarded".to_string());
+                        drawn_scroll = None;
+                    }
+                    KeyCode::PageDown => {
+                        let max_scroll = app.max_scroll(viewport_height);
+                        app.scroll = (app.scroll + viewport_height).min(max_scroll);
+                    }
+                    KeyCode::PageUp => app.scroll = app.scroll.saturating_sub(viewport_height),
+                    _ => {
+                        draft.handle_key(key);
+                        draft.scroll_to_cursor(viewport_height.saturating_sub(2) as usize);
+                    }
+                }
+                continue;
+            }
+            if let Some(selection) = app.selection.as_mut() {
+                let last = app.content_lines.len().saturating_sub(1);
+                match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        selection.cursor = (selection.cursor + 1).min(last);
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        selection.cursor = selection.cursor.saturating_sub(1);
+                    }
+                    KeyCode::Enter | KeyCode::Char('y') => capture_quote(app),
+                    KeyCode::Char('h') => record_highlight(cache, app),
+                    KeyCode::Esc | KeyCode::Char('V') => app.selection = None,
+                    _ => {}
+                }
+                if let Some(selection) = app.selection {
+                    // Keep the moving end on screen
+                    let cursor = selection.cursor as u16;
+                    if cursor < app.scroll {
+                        app.scroll = cursor;
+                    } else if cursor >= app.scroll + viewport_height {
+                        app.scroll = cursor + 1 - viewport_height;
+                    }
+                }
+                continue;
+            }
+            if let Some(input) = app.search_input.as_mut() {
+                match key.code {
+                    KeyCode::Enter => {
+                        let query = app.search_input.take().unwrap_or_default();
+                        app.search(query, viewport_height);
+                    }
+                    KeyCode::Esc => app.search_input = None,
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Char(c) => input.push(c),
+                    _ => {}
+                }
+                continue;
+            }
 
-        // Read events (blocking)
-        if let Event::Key(key) = event::read()? {
             // Handle all key events
             match key.code {
-                KeyCode::Char('q') | KeyCode::Esc => {
+                KeyCode::Char('q') => {
+                    return Ok(());
+                }
+                KeyCode::Char('/') => app.search_input = Some(String::new()),
+                // While a search is active n/N move between matches and Esc ends it
+                KeyCode::Char('n') if app.search.is_some() => {
+                    app.jump_match(true, viewport_height);
+                }
+                KeyCode::Char('N') if app.search.is_some() => {
+                    app.jump_match(false, viewport_height);
+                }
+                KeyCode::Esc if app.search.is_some() => app.search = None,
+                KeyCode::Esc => {
+                    app.left_early = true;
                     return Ok(());
                 }
                 KeyCode::Char('o') => {
@@ -205,13 +1063,127 @@ fn run_app(
                     app.mode = ViewerMode::CreateNote;
                     return Ok(());
                 }
-                KeyCode::Char('s') => {
-                    let cache = TextCache::new().ok();
-                    if let Some(cache) = cache {
-                        if cache.toggle_star(&app.article.id).is_ok() {
-                            app.article.starred = !app.article.starred;
+                KeyCode::Char('V') if !app.content_lines.is_empty() => {
+                    let top = app.scroll as usize;
+                    app.selection = Some(Selection {
+                        anchor: top,
+                        cursor: top,
+                    });
+                }
+                KeyCode::Char('e') => match crate::notes::note_template(&app.article) {
+                    Ok(template) => {
+                        let mut draft = NoteDraft::new(&template);
+                        draft.scroll_to_cursor(viewport_height.saturating_sub(2) as usize);
+                        app.draft = Some(draft);
+                        drawn_scroll = None;
+                    }
+                    Err(e) => app.status = Some(format!("{:#}", e)),
+                },
+                KeyCode::Char('s') if cache.toggle_star(&app.article.id).is_ok() => {
+                    app.article.starred = !app.article.starred;
+                    if app.article.starred {
+                        match crate::pocket::on_star(cache.base_dir(), &app.article) {
+                            Ok(true) => app.status = Some("Sent to Pocket".to_string()),
+                            Ok(false) => {}
+                            Err(e) => app.status = Some(format!("{:#}", e)),
+                        }
+                    }
+                }
+                KeyCode::Char('u') => {
+                    let res = if app.article.read {
+                        cache.mark_as_unread(&app.article.id)
+                    } else {
+                        cache.mark_as_read(&app.article.id)
+                    };
+                    if res.is_ok() {
+                        app.article.read = !app.article.read;
+                        app.refresh_unread(cache);
+                    }
+                }
+                KeyCode::Char('p') => {
+                    suspend_tui(terminal)?;
+                    let paged = crate::pager::page(&pager_text(&app.article, &app.content_lines));
+                    resume_tui(terminal)?;
+                    drawn_scroll = None;
+                    if let Err(e) = paged {
+                        app.status = Some(format!("{:#}", e));
+                    }
+                }
+                KeyCode::Char('S') if app.summarized => {
+                    app.status = Some("Already summarized".to_string());
+                }
+                KeyCode::Char('S') if !crate::summarize::enabled() => {
+                    app.status =
+                        Some("No summarizer; set [summarizer] url in the config".to_string());
+                }
+                KeyCode::Char('S') => {
+                    app.status = Some("Summarizing...".to_string());
+                    terminal.draw(|f| ui(f, app))?;
+                    match crate::summarize::summary(cache.base_dir(), &app.article) {
+                        Ok(Some(summary)) => {
+                            app.show_summary(&summary);
+                            app.scroll = 0;
+                            app.status = None;
+                        }
+                        Ok(None) => {}
+                        Err(e) => app.status = Some(format!("Failed to summarize: {:#}", e)),
+                    }
+                    drawn_scroll = None;
+                }
+                KeyCode::Char('R') => {
+                    app.status = Some("Looking for related notes...".to_string());
+                    terminal.draw(|f| ui(f, app))?;
+                    let limit = match crate::config::get().notes.related {
+                        0 => 5,
+                        limit => limit,
+                    };
+                    match crate::notes::related_notes(&app.article, limit) {
+                        Ok(related) if related.is_empty() => {
+                            app.status = Some("No related notes in the zet".to_string());
+                        }
+                        Ok(related) => {
+                            let lines: Vec<String> = related
+                                .iter()
+                                .map(|note| format!("- {} ({})", note.title, note.path))
+                                .collect();
+                            app.show_above("Related notes", &lines.join("\n"));
+                            app.scroll = 0;
+                            app.status = None;
+                        }
+                        Err(e) => {
+                            app.status = Some(format!("Failed to find related notes: {:#}", e))
+                        }
+                    }
+                    drawn_scroll = None;
+                }
+                KeyCode::Char('m') => match app.enclosure.clone() {
+                    Some(enclosure) => {
+                        let player = &crate::config::get().viewer.player;
+                        suspend_tui(terminal)?;
+                        let played = crate::player::play(player, &enclosure.url);
+                        resume_tui(terminal)?;
+                        drawn_scroll = None;
+                        if let Err(e) = played {
+                            app.status = Some(format!("{:#}", e));
                         }
                     }
+                    None => app.status = Some("No audio or video in this article".to_string()),
+                },
+                KeyCode::Char('y') => {
+                    let link = app.article.link.clone();
+                    copy_to_clipboard(terminal, app, &link, "link");
+                }
+                KeyCode::Char('Y') => {
+                    let link = clipboard::markdown_link(&app.article.title, &app.article.link);
+                    copy_to_clipboard(terminal, app, &link, "markdown link");
+                }
+                KeyCode::Char('J') | KeyCode::Char(']') if app.remaining > 0 => {
+                    app.nav = Some(Nav::Next);
+                    return Ok(());
+                }
+                KeyCode::Char('K') | KeyCode::Char('[') if app.index > 0 => {
+                    app.nav = Some(Nav::Prev);
+                    return Ok(());
                 }
                 KeyCode::Char('v') => {
                     app.mode = ViewerMode::OpenInVim;
@@ -219,7 +1191,7 @@ fn run_app(
                 }
                 KeyCode::Char('j') | KeyCode::Down => {
                     let viewport_height = terminal.size()?.height.saturating_sub(7); // Account for header/footer
-                    let max_scroll = content_height.saturating_sub(viewport_height);
+                    let max_scroll = app.max_scroll(viewport_height);
                     if app.scroll < max_scroll {
                         app.scroll = app.scroll.saturating_add(1);
                     }
@@ -229,19 +1201,40 @@ fn run_app(
                 }
                 KeyCode::PageDown | KeyCode::Char(' ') => {
                     let viewport_height = terminal.size()?.height.saturating_sub(7);
-                    let max_scroll = content_height.saturating_sub(viewport_height);
+                    let max_scroll = app.max_scroll(viewport_height);
                     app.scroll = (app.scroll + viewport_height).min(max_scroll);
                 }
                 KeyCode::PageUp => {
                     let viewport_height = terminal.size()?.height.saturating_sub(7);
                     app.scroll = app.scroll.saturating_sub(viewport_height);
                 }
-                KeyCode::Char('g') | KeyCode::Home => {
+                // gt/gT switch tabs; `g` has moved to the top by then, so
+                // the tab keeps where it was
+                KeyCode::Char('t') | KeyCode::Char('T') if after_g.is_some() => {
+                    app.scroll = after_g.unwrap_or(0);
+                    if app.tab_titles.len() > 1 {
+                        app.nav = Some(if key.code == KeyCode::Char('t') {
+                            Nav::NextTab
+                        } else {
+                            Nav::PrevTab
+                        });
+                        return Ok(());
+                    }
+                }
+                KeyCode::Char('T') if app.remaining > 0 => {
+                    app.nav = Some(Nav::NewTab);
+                    return Ok(());
+                }
+                KeyCode::Char('g') => {
+                    pending_g = Some(app.scroll);
+                    app.scroll = 0;
+                }
+                KeyCode::Home => {
                     app.scroll = 0;
                 }
                 KeyCode::Char('G') | KeyCode::End => {
                     let viewport_height = terminal.size()?.height.saturating_sub(7);
-                    let max_scroll = content_height.saturating_sub(viewport_height);
+                    let max_scroll = app.max_scroll(viewport_height);
                     app.scroll = max_scroll;
                 }
                 _ => {}
@@ -250,34 +1243,211 @@ fn run_app(
     }
 }
 
-fn ui(f: &mut Frame, app: &ViewerApp) {
-    let chunks = Layout::default()
+/// Remembers the scroll offset of an article left mid-read, and forgets it
+/// once the article was read to the end
+fn save_position(terminal: &Tui, cache: &TextCache, app: &ViewerApp) -> Result<()> {
+    let viewport_height = terminal.size()?.height.saturating_sub(7);
+    let max_scroll = (app.content_lines.len() as u16).saturating_sub(viewport_height);
+    let position = (app.scroll > 0 && app.scroll < max_scroll).then_some(app.scroll);
+    cache.set_reading_position(&app.article.id, position)
+}
+
+/// Appends the selected lines as a quote to the article's note
+fn capture_quote(app: &mut ViewerApp) {
+    let Some(selection) = app.selection.take() else {
+        return;
+    };
+    let lines = &app.content_lines[selection.range()];
+    app.status = Some(
+        match crate::notes::notes_dir()
+            .and_then(|dir| crate::notes::append_quote(&dir, &app.article, lines))
+        {
+            Ok(path) => format!(
+                "Quoted {} line{} into {}",
+                lines.len(),
+                if lines.len() == 1 { "" } else { "s" },
+                path
+            ),
+            Err(e) => format!("Failed to capture quote: {:#}", e),
+        },
+    );
+}
+
+/// Records the selected lines as a highlight of the article
+fn record_highlight(cache: &TextCache, app: &mut ViewerApp) {
+    let Some(selection) = app.selection.take() else {
+        return;
+    };
+    let text = app.content_lines[selection.range()].join("\n");
+    app.status = Some(match cache.add_highlight(&app.article.id, &text) {
+        Ok(count) => format!(
+            "Highlighted; {} highlight{} in this article (zetrss highlights --export writes them to the note)",
+            count,
+            if count == 1 { "" } else { "s" }
+        ),
+        Err(e) => format!("Failed to record highlight: {:#}", e),
+    });
+}
+
+/// Closes the note draft, writing it into the zet if it was edited
+fn save_draft(app: &mut ViewerApp) {
+    let Some(draft) = app.draft.take() else {
+        return;
+    };
+    if !draft.is_modified() {
+        app.status = Some("Note draft unchanged; nothing saved".to_string());
+        return;
+    }
+    app.status = Some(
+        match crate::notes::notes_dir()
+            .and_then(|dir| crate::notes::write_note(&dir, &app.article, &draft.text()))
+        {
+            Ok(path) => format!("Saved note {}", path),
+            Err(e) => format!("Failed to save note: {:#}", e),
+        },
+    );
+}
+
+/// Copies `text` and reports the outcome in the footer
+fn copy_to_clipboard(terminal: &mut Tui, app: &mut ViewerApp, text: &str, what: &str) {
+    app.status = Some(match clipboard::copy(text, terminal.backend_mut()) {
+        Ok(Copied::Clipboard) => format!("Copied {}", what),
+        Ok(Copied::Terminal) => format!("Copied {} through the terminal (OSC 52)", what),
+        Err(e) => format!("Failed to copy {}: {}", what, e),
+    });
+}
+
+/// Removes drawn images; sixel pixels only go away when every cell is redrawn
+fn clear_images(terminal: &mut Tui, app: &ViewerApp) -> io::Result<()> {
+    if app.images.is_empty() {
+        return Ok(());
+    }
+    if app.graphics == Some(Graphics::Kitty) {
+        terminal
+            .backend_mut()
+            .write_all(images::KITTY_CLEAR.as_bytes())?;
+    }
+    terminal.clear()
+}
+
+/// Draws the images whose reserved lines are all on screen
+fn draw_images(terminal: &mut Tui, app: &ViewerApp) -> io::Result<()> {
+    // The article pane is too narrow for them next to a note draft
+    if app.images.is_empty() || app.draft.is_some() {
+        return Ok(());
+    }
+    let area = content_area(terminal.size()?);
+    let top = app.scroll as usize;
+    let bottom = top + area.height as usize;
+    let backend = terminal.backend_mut();
+    for image in &app.images {
+        if image.line < top
+            || image.line + usize::from(image.encoded.rows) > bottom
+            || image.encoded.cols > area.width
+        {
+            continue;
+        }
+        crossterm::queue!(
+            backend,
+            crossterm::cursor::MoveTo(area.x, area.y + (image.line - top) as u16)
+        )?;
+        backend.write_all(image.encoded.data.as_bytes())?;
+    }
+    backend.flush()
+}
+
+/// Header, content and footer areas of the viewer
+fn layout(area: Rect) -> std::rc::Rc<[Rect]> {
+    Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(4), // Header
             Constraint::Min(10),   // Content
             Constraint::Length(3), // Footer
         ])
-        .split(f.size());
+        .split(area)
+}
+
+/// Where content lines are drawn: the content area inside its borders
+fn content_area(area: Rect) -> Rect {
+    let content = layout(area)[1];
+    Rect {
+        x: content.x + 1,
+        y: content.y,
+        width: content.width.saturating_sub(2),
+        height: content.height.saturating_sub(1),
+    }
+}
+
+fn ui(f: &mut Frame, app: &ViewerApp) {
+    let theme = theme::get();
+    let chunks = layout(f.size());
 
-    render_header(f, chunks[0], app);
-    render_content(f, chunks[1], app);
-    render_footer(f, chunks[2]);
+    render_header(f, chunks[0], app, theme);
+    match &app.draft {
+        Some(draft) => {
+            let panes = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+                .split(chunks[1]);
+            render_content(f, panes[0], app, theme);
+            render_draft(f, panes[1], draft, theme);
+        }
+        None => render_content(f, chunks[1], app, theme),
+    }
+    render_footer(f, chunks[2], app, theme);
 }
 
-fn render_header(f: &mut Frame, area: Rect, app: &ViewerApp) {
+/// The note draft, with the terminal cursor at the editing position
+fn render_draft(f: &mut Frame, area: Rect, draft: &NoteDraft, theme: &Theme) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(theme.focused_border)
+        .title(" Note draft ")
+        .title_alignment(Alignment::Center);
+    let inner = block.inner(area);
+    let lines: Vec<Line> = draft
+        .lines()
+        .iter()
+        .skip(draft.scroll())
+        .take(inner.height as usize)
+        .map(|line| Line::raw(line.clone()))
+        .collect();
+    f.render_widget(Paragraph::new(lines).block(block), area);
+
+    let (row, col) = draft.cursor();
+    let y = row.saturating_sub(draft.scroll());
+    if y < inner.height as usize && col < inner.width as usize {
+        f.set_cursor(inner.x + col as u16, inner.y + y as u16);
+    }
+}
+
+fn render_header(f: &mut Frame, area: Rect, app: &ViewerApp, theme: &Theme) {
     let header_text = vec![
-        Line::from(vec![Span::styled(
-            app.article.title.clone(),
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        )]),
+        Line::from(vec![Span::styled(app.article.title.clone(), theme.title)]),
         Line::from(vec![
             Span::raw("Feed: "),
+            Span::styled(app.article.feed_url.clone(), theme.feed),
+            Span::styled(format!("  {} min read", app.minutes), theme.muted),
+            Span::styled(
+                if app.article.starred {
+                    "  ★ Starred"
+                } else {
+                    ""
+                },
+                theme.star,
+            ),
             Span::styled(
-                app.article.feed_url.clone(),
-                Style::default().fg(Color::Yellow),
+                if app.article.read { "" } else { "  ● Unread" },
+                theme.accent,
+            ),
+            Span::styled(
+                app.enclosure
+                    .as_ref()
+                    .map(|e| format!("  ▶ {} (m plays)", e.describe()))
+                    .unwrap_or_default(),
+                theme.accent,
             ),
         ]),
     ];
@@ -286,128 +1456,290 @@ fn render_header(f: &mut Frame, area: Rect, app: &ViewerApp) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Blue))
-                .title(" RSS Article ")
-                .title_alignment(Alignment::Center),
+                .border_type(theme.border_type)
+                .border_style(theme.border)
+                .title(tab_bar(&app.tab_titles, app.tab, theme))
+                .title_alignment(Alignment::Center)
+                .title(new_articles_title(app.new_articles, theme))
+                .title(
+                    Title::from(Span::styled(
+                        format!(
+                            " {} unread in feed · {} unread in all ",
+                            app.unread.feed, app.unread.total
+                        ),
+                        theme.muted,
+                    ))
+                    .position(Position::Bottom)
+                    .alignment(Alignment::Right),
+                ),
         )
         .alignment(Alignment::Left);
 
     f.render_widget(header, area);
 }
 
-fn render_content(f: &mut Frame, area: Rect, app: &ViewerApp) {
+/// Header title on the right announcing articles fetched while reading
+fn new_articles_title(count: usize, theme: &Theme) -> Title<'static> {
+    let text = match count {
+        0 => String::new(),
+        1 => " 1 new article ".to_string(),
+        n => format!(" {} new articles ", n),
+    };
+    Title::from(Span::styled(text, theme.accent)).alignment(Alignment::Right)
+}
+
+/// Longest tab title shown in the header
+const TAB_TITLE_WIDTH: usize = 24;
+
+/// Header title: the open tabs, numbered, with the current one highlighted,
+/// or just " RSS Article " with a single tab
+fn tab_bar(titles: &[String], current: usize, theme: &Theme) -> Line<'static> {
+    if titles.len() < 2 {
+        return Line::raw(" RSS Article ");
+    }
+    let spans = titles.iter().enumerate().map(|(i, title)| {
+        let mut label = title.clone();
+        if title.width() > TAB_TITLE_WIDTH {
+            label = wrap_line(title, TAB_TITLE_WIDTH - 1).swap_remove(0);
+            label.push('…');
+        }
+        let style = if i == current {
+            theme.title
+        } else {
+            theme.muted
+        };
+        Span::styled(format!(" {}:{} ", i + 1, label), style)
+    });
+    Line::from(spans.collect::<Vec<_>>())
+}
+
+fn render_content(f: &mut Frame, area: Rect, app: &ViewerApp, theme: &Theme) {
     // Calculate visible range
     let viewport_height = area.height as usize;
     let start = app.scroll as usize;
     let end = (start + viewport_height).min(app.content_lines.len());
 
-    // Get visible lines
-    let visible_lines: Vec<String> = if start < app.content_lines.len() {
-        app.content_lines[start..end].to_vec()
-    } else {
-        vec![]
-    };
-
-    // Join lines for display
-    let content = visible_lines.join("\n");
+    // Visible lines, with search matches highlighted
+    let visible_lines: Vec<Line> = app
+        .content_lines
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(end.saturating_sub(start))
+        .map(|(line_no, line)| {
+            let line = highlight_line(line_no, line, app.search.as_ref(), theme);
+            match app.selection {
+                Some(selection) if selection.range().contains(&line_no) => {
+                    line.patch_style(theme.selection)
+                }
+                _ => line,
+            }
+        })
+        .collect();
 
-    // Add scroll indicator
+    // Add progress indicator
     let scroll_indicator = if app.content_lines.len() > viewport_height {
-        let current = app.scroll as usize + 1;
-        let total = app.content_lines.len();
-        format!(" [{}/{}] ", current, total)
+        let percent = percent_read(
+            app.scroll as usize,
+            app.content_lines.len(),
+            viewport_height,
+        );
+        format!(" {}% ", percent)
     } else {
         String::new()
     };
 
-    let paragraph = Paragraph::new(content)
-        .block(
-            Block::default()
-                .borders(Borders::LEFT | Borders::RIGHT | Borders::BOTTOM)
-                .border_style(Style::default().fg(Color::Gray))
-                .title(scroll_indicator)
-                .title_alignment(Alignment::Right),
-        )
-        .wrap(Wrap { trim: false }); // Don't wrap since we pre-wrapped
+    let paragraph = Paragraph::new(visible_lines).block(
+        Block::default()
+            .borders(Borders::LEFT | Borders::RIGHT | Borders::BOTTOM)
+            .border_type(theme.border_type)
+            .border_style(theme.muted)
+            .title(scroll_indicator)
+            .title_alignment(Alignment::Right),
+    );
+    // No `Wrap`: lines are pre-wrapped by display width, and wrapping here
+    // again would put more rows on screen than the scroll offsets count
 
     f.render_widget(paragraph, area);
+
+    // Scrollbar over the right border, above the bottom corner
+    let max_scroll = app.content_lines.len().saturating_sub(viewport_height);
+    if max_scroll > 0 {
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .track_style(theme.muted)
+            .thumb_style(theme.accent);
+        // Thumb sized by the share of the article on screen
+        let mut state = ScrollbarState::new(max_scroll + 1)
+            .viewport_content_length(viewport_height)
+            .position(app.scroll as usize);
+        let track = Rect {
+            height: area.height.saturating_sub(1),
+            ..area
+        };
+        f.render_stateful_widget(scrollbar, track, &mut state);
+    }
+}
+
+/// A content line as spans, with the search matches on it highlighted
+fn highlight_line(
+    line_no: usize,
+    line: &str,
+    search: Option<&Search>,
+    theme: &Theme,
+) -> Line<'static> {
+    let Some(search) = search else {
+        return Line::raw(line.to_string());
+    };
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for (index, hit) in search
+        .matches
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| m.line == line_no)
+    {
+        spans.push(Span::raw(line[pos..hit.start].to_string()));
+        let style = if index == search.current {
+            theme.current_match
+        } else {
+            theme.other_match
+        };
+        spans.push(Span::styled(line[hit.start..hit.end].to_string(), style));
+        pos = hit.end;
+    }
+    spans.push(Span::raw(line[pos..].to_string()));
+    Line::from(spans)
 }
 
-fn render_footer(f: &mut Frame, area: Rect) {
+fn render_footer(f: &mut Frame, area: Rect, app: &ViewerApp, theme: &Theme) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(theme.border);
+    let key = |k: &'static str| Span::styled(k, theme.key);
+    if let Some(input) = &app.search_input {
+        let prompt = Paragraph::new(Line::from(vec![
+            Span::styled("/", theme.accent),
+            Span::raw(input.clone()),
+        ]))
+        .block(block.title(" Search "));
+        f.render_widget(prompt, area);
+        return;
+    }
+    if let Some(selection) = app.selection {
+        let footer = Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!("{} lines selected  ", selection.range().count()),
+                theme.accent,
+            ),
+            key(" j/k "),
+            Span::raw(" Extend  "),
+            key(" Enter/y "),
+            Span::raw(" Quote into note  "),
+            key(" h "),
+            Span::raw(" Highlight  "),
+            key(" Esc "),
+            Span::raw(" Cancel  "),
+        ]))
+        .block(block)
+        .alignment(Alignment::Center);
+        f.render_widget(footer, area);
+        return;
+    }
+    if app.draft.is_some() {
+        let footer = Paragraph::new(Line::from(vec![
+            key(" Esc "),
+            Span::raw(" Save note  "),
+            key(" Ctrl-C "),
+            Span::raw(" Discard  "),
+            key(" PgUp/PgDn "),
+            Span::raw(" Scroll article  "),
+        ]))
+        .block(block)
+        .alignment(Alignment::Center);
+        f.render_widget(footer, area);
+        return;
+    }
+    if let Some(status) = &app.status {
+        let footer = Paragraph::new(Span::styled(status.clone(), theme.accent))
+            .block(block)
+            .alignment(Alignment::Center);
+        f.render_widget(footer, area);
+        return;
+    }
+    if let Some(search) = &app.search {
+        let status = if search.matches.is_empty() {
+            format!("No matches for \"{}\"", search.query)
+        } else {
+            format!(
+                "\"{}\" {}/{}",
+                search.query,
+                search.current + 1,
+                search.matches.len()
+            )
+        };
+        let footer = Paragraph::new(Line::from(vec![
+            Span::raw(format!("{}  ", status)),
+            key(" n/N "),
+            Span::raw(" Next/prev  "),
+            key(" Esc "),
+            Span::raw(" End search  "),
+        ]))
+        .block(block)
+        .alignment(Alignment::Center);
+        f.render_widget(footer, area);
+        return;
+    }
+
+    let quit_label = if app.queue && app.remaining > 0 {
+        format!(" Next ({} left)  ", app.remaining)
+    } else if app.tab_titles.len() > 1 {
+        " Close tab  ".to_string()
+    } else {
+        " Quit  ".to_string()
+    };
     let footer_text = Line::from(vec![
-        Span::styled(" q ", Style::default().bg(Color::DarkGray).fg(Color::White)),
-        Span::raw(" Quit  "),
-        Span::styled(" v ", Style::default().bg(Color::DarkGray).fg(Color::White)),
+        key(" q "),
+        Span::raw(quit_label),
+        key(" v "),
         Span::raw(" Vim  "),
-        Span::styled(" o ", Style::default().bg(Color::DarkGray).fg(Color::White)),
+        key(" o "),
         Span::raw(" Browser  "),
-        Span::styled(" n ", Style::default().bg(Color::DarkGray).fg(Color::White)),
+        key(" n "),
         Span::raw(" Note  "),
-        Span::styled(" s ", Style::default().bg(Color::DarkGray).fg(Color::White)),
+        key(" s "),
         Span::raw(" Star  "),
-        Span::styled(
-            " j/k ",
-            Style::default().bg(Color::DarkGray).fg(Color::White),
-        ),
+        key(" y/Y "),
+        Span::raw(" Copy link  "),
+        key(" p "),
+        Span::raw(" Pager  "),
+        key(" e "),
+        Span::raw(" Draft note  "),
+        key(" V "),
+        Span::raw(" Quote  "),
+        key(" u "),
+        Span::raw(" Unread  "),
+        key(" j/k "),
         Span::raw(" Scroll  "),
+        key(" J/K "),
+        Span::raw(format!(
+            " {}/{}  ",
+            app.index + 1,
+            app.index + app.remaining + 1
+        )),
+        key(" T "),
+        Span::raw(" New tab  "),
+        key(" gt/gT "),
+        Span::raw(" Tabs  "),
+        key(" / "),
+        Span::raw(" Search  "),
     ]);
 
     let footer = Paragraph::new(footer_text)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Blue)),
-        )
+        .block(block)
         .alignment(Alignment::Center);
 
     f.render_widget(footer, area);
 }
-
-fn create_note_from_article(article: &crate::models::FeedItem) -> Result<String> {
-    let username = std::env::var("USER")
-        .or_else(|_| std::env::var("USERNAME"))
-        .unwrap_or_else(|_| "user".to_string());
-
-    let home = std::env::var("HOME")
-        .or_else(|_| std::env::var("USERPROFILE"))
-        .unwrap_or_else(|_| format!("/Users/{}", username));
-
-    let zet_path = format!("{}/git/{}/zet", home, username);
-    let date = chrono::Local::now().format("%Y%m%d%H%M").to_string();
-    let safe_title = article
-        .title
-        .chars()
-        .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
-        .collect::<String>()
-        .to_lowercase();
-    let safe_title = if safe_title.len() > 50 {
-        safe_title.chars().take(50).collect()
-    } else {
-        safe_title
-    };
-
-    let filename = format!("{}/{}-{}.md", zet_path, date, safe_title);
-
-    let mut content = String::new();
-    content.push_str(&format!("# {}\n\n", article.title));
-    content.push_str(&format!("Source: {}\n", article.link));
-    content.push_str(&format!("Feed: {}\n", article.feed_url));
-    if let Some(ref published) = article.published {
-        content.push_str(&format!("Date: {}\n", published));
-    }
-    content.push_str("\n## Summary\n\n");
-
-    if let Some(ref article_content) = article.content {
-        let summary = html2text::from_read(article_content.as_bytes(), 80);
-        let first_para = summary.split("\n\n").next().unwrap_or("");
-        content.push_str(first_para);
-    }
-
-    content.push_str("\n\n## Notes\n\n");
-
-    // Create directory if it doesn't exist
-    std::fs::create_dir_all(&zet_path)?;
-    std::fs::write(&filename, content)?;
-
-    Ok(filename)
-}
@@ -0,0 +1,340 @@
+use super::*;
+use std::os::unix::fs::PermissionsExt;
+
+fn lines(text: &[&str]) -> Vec<String> {
+    text.iter().map(|l| l.to_string()).collect()
+}
+
+fn app_with(content: Vec<String>) -> ViewerApp {
+    ViewerApp {
+        article: crate::models::FeedItem {
+            id: "a".to_string(),
+            ..Default::default()
+        },
+        scroll: 0,
+        mode: ViewerMode::Reading,
+        content_lines: content,
+        index: 0,
+        remaining: 0,
+        queue: true,
+        nav: None,
+        left_early: false,
+        search_input: None,
+        search: None,
+        graphics: None,
+        images: Vec::new(),
+        status: None,
+        minutes: 1,
+        draft: None,
+        selection: None,
+        tab_titles: Vec::new(),
+        tab: 0,
+        enclosure: None,
+        new_articles: 0,
+        unread: Unread::default(),
+        summarized: false,
+    }
+}
+
+/// Stores articles with the given ids in one feed, newest first
+fn store_articles(cache: &TextCache, ids: &[&str]) {
+    let items = ids
+        .iter()
+        .zip(1..)
+        .map(|(id, hours)| {
+            let mut item = app_with(Vec::new()).article;
+            item.id = id.to_string();
+            item.feed_url = "https://example.com/feed".to_string();
+            item.published = Some(chrono::Utc::now() - chrono::Duration::hours(hours));
+            item
+        })
+        .collect();
+    cache
+        .store_feed(&crate::models::Feed {
+            url: "https://example.com/feed".to_string(),
+            title: "Example".to_string(),
+            description: None,
+            last_fetched: None,
+            bytes: 0,
+            items,
+        })
+        .unwrap();
+}
+
+#[test]
+fn test_find_matches_ignores_case() {
+    let content = lines(&["Rust and rust", "nothing", "Straße RUST"]);
+    let matches = find_matches(&content, "rust");
+    let found: Vec<(usize, &str)> = matches
+        .iter()
+        .map(|m| (m.line, &content[m.line][m.start..m.end]))
+        .collect();
+    assert_eq!(found, vec![(0, "Rust"), (0, "rust"), (2, "RUST")]);
+
+    // Byte offsets stay on char boundaries after multi-byte text
+    assert_eq!(find_matches(&content, "ße")[0].start, 4);
+    assert!(find_matches(&content, "").is_empty());
+    assert!(find_matches(&content, "rusty").is_empty());
+}
+
+#[test]
+fn test_search_scrolls_to_matches() {
+    let mut content = vec![String::new(); 100];
+    content[10] = "first hit".to_string();
+    content[80] = "second hit".to_string();
+    let mut app = app_with(content);
+
+    app.search("HIT".to_string(), 20);
+    let search = app.search.as_ref().unwrap();
+    assert_eq!(search.matches.len(), 2);
+    // Line 10 is already visible
+    assert_eq!(app.scroll, 0);
+
+    app.jump_match(true, 20);
+    assert_eq!(app.search.as_ref().unwrap().current, 1);
+    assert_eq!(app.scroll, 80 - 20 / 3);
+
+    // Wraps around
+    app.jump_match(true, 20);
+    assert_eq!(app.search.as_ref().unwrap().current, 0);
+    assert_eq!(app.scroll, 10 - 20 / 3);
+    app.jump_match(false, 20);
+    assert_eq!(app.search.as_ref().unwrap().current, 1);
+}
+
+#[test]
+fn test_nav_list_orders() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    store_articles(&cache, &["a1", "a2", "a3"]);
+    cache.mark_as_read("a2").unwrap();
+
+    let (ids, start) = nav_list(&cache, "a2", NavOrder::Date).unwrap();
+    assert_eq!(ids, vec!["a1", "a2", "a3"]);
+    assert_eq!(start, 1);
+
+    // A read article leads the unread ones
+    let (ids, start) = nav_list(&cache, "a2", NavOrder::Unread).unwrap();
+    assert_eq!(ids, vec!["a2", "a1", "a3"]);
+    assert_eq!(start, 0);
+}
+
+#[test]
+fn test_images_get_rows_below_placeholder() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    let url = "https://example.com/chart.png";
+    let path = images::cached_path(cache.base_dir(), url);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    image::RgbImage::new(16, 32)
+        .save_with_format(&path, image::ImageFormat::Png)
+        .unwrap();
+
+    let mut article = app_with(Vec::new()).article;
+    article.content = Some(format!(
+        "<p>Before</p><img src=\"{}\" alt=\"Chart\"><p>After</p>",
+        url
+    ));
+    let mut view = ArticleView::from_article(article, 80);
+    let placeholder = view.images[0].line;
+    assert_eq!(view.lines[placeholder], "[image: Chart]");
+
+    let placed = place_images(&cache, &mut view, Graphics::Kitty);
+    assert_eq!(placed.len(), 1);
+    assert_eq!(placed[0].line, placeholder + 1);
+    let rows = usize::from(placed[0].encoded.rows);
+    assert!(view.lines[placeholder + 1..=placeholder + rows]
+        .iter()
+        .all(String::is_empty));
+    assert!(view.lines[placeholder + rows + 1..]
+        .iter()
+        .any(|line| line == "After"));
+}
+
+#[test]
+fn test_reading_time_and_progress() {
+    assert_eq!(reading_minutes(0, 230), 1);
+    assert_eq!(reading_minutes(460, 230), 2);
+    assert_eq!(reading_minutes(461, 230), 3);
+
+    // 110 lines in a 10 line viewport: 100 scroll positions
+    assert_eq!(percent_read(0, 110, 10), 0);
+    assert_eq!(percent_read(50, 110, 10), 50);
+    assert_eq!(percent_read(100, 110, 10), 100);
+    // Everything fits on screen
+    assert_eq!(percent_read(0, 5, 10), 100);
+}
+
+#[test]
+fn test_scrollbar_follows_scroll() {
+    let content: Vec<String> = (0..100).map(|i| format!("line {}", i)).collect();
+    let mut app = app_with(content);
+    let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(40, 27)).unwrap();
+    // Content rows are 4..23 on a 27 row screen; the scrollbar is the last column
+    let column = |terminal: &Terminal<ratatui::backend::TestBackend>| -> String {
+        let buffer = terminal.backend().buffer();
+        (4..23).map(|y| buffer.get(39, y).symbol()).collect()
+    };
+
+    terminal.draw(|f| ui(f, &app)).unwrap();
+    assert!(column(&terminal).starts_with('█'));
+    assert!(!column(&terminal).ends_with('█'));
+
+    app.scroll = 80;
+    terminal.draw(|f| ui(f, &app)).unwrap();
+    assert!(!column(&terminal).starts_with('█'));
+    assert!(column(&terminal).ends_with('█'));
+}
+
+#[test]
+fn test_tab_bar_lists_open_tabs() {
+    let theme = theme::get();
+    assert_eq!(
+        tab_bar(&["Only".to_string()], 0, theme).to_string(),
+        " RSS Article "
+    );
+
+    let titles = vec![
+        "Short".to_string(),
+        "A title far too long to fit in a tab".to_string(),
+    ];
+    let bar = tab_bar(&titles, 1, theme);
+    assert_eq!(bar.to_string(), " 1:Short  2:A title far too long to… ");
+    assert_eq!(bar.spans[0].style, theme.muted);
+    assert_eq!(bar.spans[1].style, theme.title);
+
+    // Wide titles are cut by display width
+    let bar = tab_bar(
+        &["中文标题非常非常长的一篇文章".to_string(), "b".to_string()],
+        0,
+        theme,
+    );
+    assert_eq!(bar.spans[0].content, " 1:中文标题非常非常长的一… ");
+}
+
+#[test]
+fn test_new_articles_are_counted_and_shown() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    store_articles(&cache, &["a1", "a2"]);
+    let mut watch = NewArticles::new(&cache);
+    assert!(watch.until_check() > Duration::ZERO);
+
+    // Written by another process, e.g. the daemon
+    let other = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    store_articles(&other, &["a1", "a2", "a3", "a4", "a5"]);
+    watch.check(&cache);
+    assert_eq!(watch.count, 3);
+
+    let mut app = app_with(vec!["text".to_string()]);
+    app.new_articles = watch.count;
+    let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(60, 20)).unwrap();
+    terminal.draw(|f| ui(f, &app)).unwrap();
+    let top: String = (0..60)
+        .map(|x| terminal.backend().buffer().get(x, 0).symbol().to_string())
+        .collect();
+    assert!(top.contains(" 3 new articles "));
+}
+
+#[test]
+fn test_header_shows_unread_counts() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let cache = TextCache::with_base_dir(temp_dir.path().to_path_buf()).unwrap();
+    store_articles(&cache, &["a1", "a2", "a3"]);
+    let mut other = app_with(Vec::new()).article;
+    other.id = "b1".to_string();
+    other.feed_url = "https://example.org/feed".to_string();
+    cache
+        .store_feed(&crate::models::Feed {
+            url: other.feed_url.clone(),
+            title: "Other".to_string(),
+            description: None,
+            last_fetched: None,
+            bytes: 0,
+            items: vec![other],
+        })
+        .unwrap();
+
+    // Opening an article marks it read, which the counts reflect
+    let app = load_app(&cache, "a1").unwrap();
+    assert_eq!(app.unread, Unread { feed: 2, total: 3 });
+
+    let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(80, 20)).unwrap();
+    terminal.draw(|f| ui(f, &app)).unwrap();
+    let row: String = (0..80)
+        .map(|x| terminal.backend().buffer().get(x, 3).symbol().to_string())
+        .collect();
+    assert!(row.contains(" 2 unread in feed · 3 unread in all "));
+}
+
+#[test]
+fn test_summary_shown_above_article() {
+    let mut app = app_with(lines(&["Link: x", "", "Body"]));
+    app.show_summary("- One.\n- Two.");
+    assert_eq!(
+        app.content_lines,
+        lines(&["Summary", "- One.", "- Two.", "", "Link: x", "", "Body"])
+    );
+    assert!(app.summarized);
+}
+
+#[test]
+fn test_lines_above_keep_search_and_selection_in_place() {
+    let mut app = app_with(lines(&["Body text", "more text here"]));
+    app.search("text".to_string(), 20);
+    app.jump_match(true, 20);
+    app.selection = Some(Selection {
+        anchor: 0,
+        cursor: 1,
+    });
+    app.show_above("Related", "a text note");
+
+    let search = app.search.as_ref().unwrap();
+    assert_eq!(search.matches.len(), 3);
+    let current = &search.matches[search.current];
+    assert_eq!(current.line, 4);
+    assert!(current.end <= app.content_lines[current.line].len());
+    let selection = app.selection.unwrap();
+    assert_eq!((selection.anchor, selection.cursor), (3, 4));
+}
+
+#[test]
+fn test_summary_extends_scrolling() {
+    let mut app = app_with(vec![String::new(); 30]);
+    assert_eq!(app.max_scroll(20), 10);
+    app.show_summary("- One.\n- Two.");
+    assert_eq!(app.max_scroll(20), 14);
+}
+
+#[test]
+fn test_session_files() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let in_dir = session_file("note_path", Some(temp_dir.path().to_path_buf()), None);
+    assert_eq!(in_dir, temp_dir.path().join("note_path.txt"));
+
+    // Without a directory the session, else a random ID, names the file
+    let named = session_file("open_url", None, Some("abc".to_string()));
+    assert!(named.ends_with("zetrss_open_url_abc.txt"));
+    let random = session_file("open_url", None, None);
+    assert_ne!(random, session_file("open_url", None, None));
+    assert!(!random
+        .to_string_lossy()
+        .contains(&std::process::id().to_string()));
+
+    write_private(&in_dir, "/notes/a.md").unwrap();
+    assert_eq!(std::fs::read_to_string(&in_dir).unwrap(), "/notes/a.md");
+    let mode = std::fs::metadata(&in_dir).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    // A link planted at the path is replaced, not written through
+    let target = temp_dir.path().join("target.txt");
+    std::fs::write(&target, "keep").unwrap();
+    std::fs::remove_file(&in_dir).unwrap();
+    std::os::unix::fs::symlink(&target, &in_dir).unwrap();
+    write_private(&in_dir, "/notes/b.md").unwrap();
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "keep");
+    assert!(!std::fs::symlink_metadata(&in_dir)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+}
@@ -0,0 +1,120 @@
+//! `zetrss scan --watch`: keeps the stored feed list in step with the zet
+//!
+//! Changed notes are rescanned one at a time through a `FeedIndex`, and the
+//! merged feed list is stored whenever it changes, so a running daemon picks
+//! up newly tagged feeds without a manual rescan.
+
+use crate::cache::ArticleStore;
+use crate::scanner::{FeedIndex, FeedSource};
+use anyhow::{Context, Result};
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+#[cfg(test)]
+#[path = "watch_tests.rs"]
+mod tests;
+
+/// Quiet time after a change before notes are rescanned, so the several
+/// events of one editor save are handled together
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// How a batch of note changes altered the feed list
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FeedChanges {
+    pub added: Vec<FeedSource>,
+    pub removed: Vec<FeedSource>,
+    /// Feeds whose declared options changed
+    pub updated: Vec<FeedSource>,
+    /// Feeds in the list afterwards
+    pub count: usize,
+}
+
+impl FeedChanges {
+    pub fn between(old: &[FeedSource], new: &[FeedSource]) -> Self {
+        let find = |feeds: &[FeedSource], url: &str| feeds.iter().position(|f| f.url == url);
+        Self {
+            added: new
+                .iter()
+                .filter(|f| find(old, &f.url).is_none())
+                .cloned()
+                .collect(),
+            removed: old
+                .iter()
+                .filter(|f| find(new, &f.url).is_none())
+                .cloned()
+                .collect(),
+            updated: new
+                .iter()
+                .filter(|f| find(old, &f.url).is_some_and(|i| old[i].options != f.options))
+                .cloned()
+                .collect(),
+            count: new.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
+/// Watches the index's roots until Ctrl-C, storing the feed list whenever
+/// notes change it; `on_change` hears about added, removed and updated feeds
+pub async fn watch(
+    index: &mut FeedIndex,
+    store: &impl ArticleStore,
+    on_change: impl Fn(&FeedChanges),
+) -> Result<()> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        // Only fails once the loop below has stopped listening
+        let _ = tx.send(event);
+    })?;
+    for root in index.roots() {
+        watcher
+            .watch(Path::new(root), RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch {}", root))?;
+    }
+
+    let mut feeds = index.feeds();
+    loop {
+        let mut changed = BTreeSet::new();
+        tokio::select! {
+            event = rx.recv() => match event {
+                Some(event) => collect(event, &mut changed),
+                None => return Ok(()),
+            },
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        while let Ok(Some(event)) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {
+            collect(event, &mut changed);
+        }
+
+        for path in &changed {
+            // A note that can't be read now may be fine after the next save
+            if let Err(e) = index.update(path) {
+                tracing::warn!("Failed to rescan {}: {:#}", path.display(), e);
+            }
+        }
+        let updated = index.feeds();
+        if updated != feeds {
+            store.store_feed_list(updated.clone())?;
+            let changes = FeedChanges::between(&feeds, &updated);
+            if !changes.is_empty() {
+                on_change(&changes);
+            }
+            feeds = updated;
+        }
+    }
+}
+
+fn collect(event: notify::Result<notify::Event>, changed: &mut BTreeSet<PathBuf>) {
+    match event {
+        Ok(event) if !event.kind.is_access() => changed.extend(event.paths),
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Watch error: {}", e),
+    }
+}
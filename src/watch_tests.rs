@@ -0,0 +1,37 @@
+use super::*;
+
+fn source(url: &str, line_number: usize) -> FeedSource {
+    FeedSource {
+        url: url.to_string(),
+        source_file: "reading.md".to_string(),
+        line_number,
+        root: None,
+        options: Default::default(),
+    }
+}
+
+#[test]
+fn test_changes_between_feed_lists() {
+    let old = vec![
+        source("https://kept.example.com/rss", 1),
+        source("https://moved.example.com/rss", 2),
+        source("https://gone.example.com/rss", 3),
+    ];
+    let mut retagged = source("https://kept.example.com/rss", 1);
+    retagged.options.tags = vec!["rust".to_string()];
+    let new = vec![
+        retagged,
+        // Lines shift as notes are edited; that alone isn't worth reporting
+        source("https://moved.example.com/rss", 5),
+        source("https://new.example.com/rss", 6),
+    ];
+
+    let changes = FeedChanges::between(&old, &new);
+    let urls = |feeds: &[FeedSource]| feeds.iter().map(|f| f.url.clone()).collect::<Vec<_>>();
+    assert_eq!(urls(&changes.added), vec!["https://new.example.com/rss"]);
+    assert_eq!(urls(&changes.removed), vec!["https://gone.example.com/rss"]);
+    assert_eq!(urls(&changes.updated), vec!["https://kept.example.com/rss"]);
+    assert_eq!(changes.count, 3);
+
+    assert!(FeedChanges::between(&old, &old).is_empty());
+}
@@ -16,6 +16,7 @@ fn test_multiple_viewer_instances() {
         title: "Test Feed".to_string(),
         description: Some("Test".to_string()),
         last_fetched: Some(chrono::Utc::now()),
+        bytes: 0,
         items: vec![
             zetrss::models::FeedItem {
                 id: "test-1".to_string(),
@@ -24,11 +25,8 @@ fn test_multiple_viewer_instances() {
                 link: "https://test.com/1".to_string(),
                 description: Some("Test 1".to_string()),
                 published: Some(chrono::Utc::now()),
-                author: None,
                 content: Some("Content 1".to_string()),
-                read: false,
-                starred: false,
-                filepath: None,
+                ..Default::default()
             },
             zetrss::models::FeedItem {
                 id: "test-2".to_string(),
@@ -37,11 +35,8 @@ fn test_multiple_viewer_instances() {
                 link: "https://test.com/2".to_string(),
                 description: Some("Test 2".to_string()),
                 published: Some(chrono::Utc::now()),
-                author: None,
                 content: Some("Content 2".to_string()),
-                read: false,
-                starred: false,
-                filepath: None,
+                ..Default::default()
             },
         ],
     };